@@ -0,0 +1,135 @@
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::alpha_discard,
+}
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::{
+    prepass_io::{VertexOutput, FragmentOutput},
+    pbr_deferred_functions::deferred_output,
+}
+#else
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+#endif
+
+struct TileSettings {
+    filter: u32,
+    hue_shift: f32,
+    border_width: f32,
+    border_grid: vec2<f32>,
+    border_color: vec4<f32>,
+}
+
+@group(1) @binding(100)
+var<uniform> settings: TileSettings;
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// Rotates the color around the gray axis
+fn shift_hue(color: vec3<f32>, angle: f32) -> vec3<f32> {
+    let axis = vec3<f32>(0.57735);
+    let cos_angle = cos(angle);
+    return color * cos_angle + cross(axis, color) * sin(angle) + axis * dot(axis, color) * (1.0 - cos_angle);
+}
+
+// Cells of the dissolve noise along a side of the whole picture
+const NOISE_SCALE: f32 = 48.0;
+// Width of the glowing rim left by the dissolve
+const DISSOLVE_EDGE: f32 = 0.06;
+const DISSOLVE_EDGE_COLOR: vec3<f32> = vec3<f32>(4.0, 1.6, 0.3);
+
+fn hash(cell: vec2<f32>) -> f32 {
+    return fract(sin(dot(cell, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+// Smoothed value noise between 0 and 1
+fn value_noise(position: vec2<f32>) -> f32 {
+    let cell = floor(position);
+    let t = smoothstep(vec2<f32>(0.0), vec2<f32>(1.0), fract(position));
+    let bottom = mix(hash(cell), hash(cell + vec2<f32>(1.0, 0.0)), t.x);
+    let top = mix(hash(cell + vec2<f32>(0.0, 1.0)), hash(cell + vec2<f32>(1.0, 1.0)), t.x);
+    return mix(bottom, top, t.y);
+}
+
+// Cases in the order of the `ColorFilter` variants
+fn apply_filter(color: vec3<f32>) -> vec3<f32> {
+    switch settings.filter {
+        case 1u: { // grayscale
+            return vec3<f32>(luminance(color));
+        }
+        case 2u: { // sepia
+            return clamp(vec3<f32>(
+                dot(color, vec3<f32>(0.393, 0.769, 0.189)),
+                dot(color, vec3<f32>(0.349, 0.686, 0.168)),
+                dot(color, vec3<f32>(0.272, 0.534, 0.131)),
+            ), vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+        case 3u: { // hue shift
+            return clamp(shift_hue(color, settings.hue_shift), vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+        case 4u: { // invert
+            return vec3<f32>(1.0) - color;
+        }
+        default: {
+            return color;
+        }
+    }
+}
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+#ifdef VERTEX_COLORS
+    // The vertex colors carry the shading of the tile, the glow then the dissolve, not a tint of the picture
+    let glow = in.color.rgb;
+    let dissolve = in.color.a;
+    var material_in = in;
+    material_in.color = vec4<f32>(1.0);
+    var pbr_input = pbr_input_from_standard_material(material_in, is_front);
+    pbr_input.material.emissive = vec4<f32>(glow, 1.0);
+#else
+    let dissolve = 0.0;
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+#endif
+    pbr_input.material.base_color = vec4<f32>(apply_filter(pbr_input.material.base_color.rgb), pbr_input.material.base_color.a);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+#ifdef VERTEX_UVS
+    if settings.border_width > 0.0 {
+        // Position within the tile, the UVs of a tile being a window on the picture
+        let local = fract(in.uv * settings.border_grid);
+        let edge = min(local, vec2<f32>(1.0) - local);
+        if min(edge.x, edge.y) < settings.border_width {
+            pbr_input.material.base_color = settings.border_color;
+        }
+    }
+    if dissolve > 0.0 {
+        // Scaled up a little so the last fragments are gone before the dissolve reaches 1
+        let threshold = dissolve * (1.0 + DISSOLVE_EDGE);
+        let noise = value_noise(in.uv * NOISE_SCALE);
+        if noise < threshold - DISSOLVE_EDGE {
+            discard;
+        }
+        if noise < threshold {
+            pbr_input.material.emissive = vec4<f32>(DISSOLVE_EDGE_COLOR, 1.0);
+        }
+    }
+#endif
+
+#ifdef PREPASS_PIPELINE
+    let out = deferred_output(in, pbr_input);
+#else
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+#endif
+
+    return out;
+}
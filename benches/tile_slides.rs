@@ -0,0 +1,173 @@
+//! Every tile of a 10x10 board sliding at once, as in a fast random walk shuffle or auto-solve, along both paths of
+//! the game: the animation clips of the tiles and the lerp of the mega board tiles, which have no player.
+//! Sliding must not allocate: the benchmark panics before measuring anything if a frame of slides does. The first
+//! frame of a clip is left out of the budget, the player caches there the path to the tile its clip animates
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use bevy::{animation::animation_player, ecs::schedule::ExecutorKind, prelude::*};
+use bevy_taquin_3d::slide::{place_sliding_tiles, start_slides, stop_removed_slides, tile_name, Easing, TileAnimationFinished, TileAnimations, TileLerp, TileSlide};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+const SIZE: usize = 10;
+const FRAME: f32 = 1. / 60.;
+/// Speed of the slides of the random walk
+const SPEED: f32 = 6.;
+const EASINGS: [Easing; 3] = [Easing::Overshoot, Easing::EaseOut, Easing::Bounce];
+
+/// Counts the allocations, to check the budget of none per frame
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Cell of every tile but the empty one
+fn cell(index: usize) -> Vec3 {
+    Vec3::new((index % SIZE) as f32, (index / SIZE) as f32, 0.)
+}
+
+/// A slide for every tile but the empty one, at the speed of the random walk
+fn slides() -> Vec<(Vec3, TileLerp)> {
+    (0..SIZE * SIZE - 1).map(|index| {
+        let translation = cell(index);
+        let easing = [Easing::Approach, Easing::EaseOut, Easing::Bounce][index % 3];
+        (translation, TileLerp::new(translation + Vec3::X).with_speed(SPEED).with_easing(easing))
+    }).collect()
+}
+
+/// Slides still running after the frame
+fn advance_frame(slides: &mut [(Vec3, TileLerp)]) -> usize {
+    let mut running = 0;
+    for (translation, lerp) in slides.iter_mut() {
+        if !lerp.advance(translation, FRAME) {
+            running += 1;
+        }
+    }
+    running
+}
+
+/// The tiles of a board with their slide clips, and the systems of the game playing them in `PostUpdate`
+struct ClipBoard {
+    world: World,
+    schedule: Schedule,
+    tiles: Vec<Entity>,
+    sliding: QueryState<(), With<TileSlide>>,
+}
+
+impl ClipBoard {
+    fn new() -> Self {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.init_resource::<Events<TileAnimationFinished>>();
+        let mut clips = Assets::<AnimationClip>::default();
+        let animations = TileAnimations::new(&mut clips, Vec3::X, -Vec3::Y);
+        world.insert_resource(clips);
+        let tiles = (0..SIZE * SIZE - 1)
+            .map(|index| world.spawn((TransformBundle::from_transform(Transform::from_translation(cell(index))), tile_name(), animations.clone(), AnimationPlayer::default())).id())
+            .collect::<Vec<Entity>>();
+        let mut schedule = Schedule::default();
+        // One thread, the count of allocations is the one of the slides alone
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        schedule.add_systems((stop_removed_slides, start_slides, animation_player, place_sliding_tiles).chain());
+        let sliding = world.query_filtered::<(), With<TileSlide>>();
+        Self { world, schedule, tiles, sliding }
+    }
+
+    /// Every tile slides back to its cell from the next one
+    fn start_slides(&mut self) {
+        for (index, tile) in self.tiles.iter().enumerate() {
+            let slide = TileSlide::new(cell(index)).with_speed(SPEED).with_easing(EASINGS[index % EASINGS.len()]);
+            let mut entity = self.world.entity_mut(*tile);
+            entity.get_mut::<Transform>().unwrap().translation = cell(index) + Vec3::X;
+            entity.insert(slide);
+        }
+    }
+
+    /// Slides still running after the frame
+    fn frame(&mut self) -> usize {
+        self.world.resource_mut::<Time>().advance_by(Duration::from_secs_f32(FRAME));
+        self.schedule.run(&mut self.world);
+        self.world.resource_mut::<Events<TileAnimationFinished>>().update();
+        self.world.clear_trackers();
+        self.sliding.iter(&self.world).count()
+    }
+
+    /// Frames until every tile landed
+    fn slide_all(&mut self) -> usize {
+        self.start_slides();
+        let mut frames = 1;
+        while self.frame() > 0 {
+            frames += 1;
+        }
+        frames
+    }
+}
+
+fn check_allocation_budget() {
+    let mut slides = slides();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    while advance_frame(&mut slides) > 0 {}
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    assert_eq!(allocations, 0, "lerping the tiles allocated {allocations} times");
+
+    let mut board = ClipBoard::new();
+    // The storages and the event buffers grow to the size of a frame of slides the first time
+    board.slide_all();
+    board.start_slides();
+    board.frame();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    while board.frame() > 0 {}
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    assert_eq!(allocations, 0, "playing the slide clips allocated {allocations} times");
+}
+
+fn bench_tile_slides(c: &mut Criterion) {
+    check_allocation_budget();
+    c.bench_function("advance 99 slides by a frame", |b| {
+        b.iter_batched_ref(slides, |slides| black_box(advance_frame(slides)), BatchSize::SmallInput);
+    });
+
+    // A move adds a slide to a tile and its landing removes it
+    let mut world = World::new();
+    let tiles = (0..SIZE * SIZE - 1).map(|_| world.spawn(Transform::default()).id()).collect::<Vec<Entity>>();
+    c.bench_function("start and land 99 slides", |b| {
+        b.iter(|| {
+            for tile in tiles.iter() {
+                world.entity_mut(*tile).insert(TileLerp::new(Vec3::X));
+            }
+            for tile in tiles.iter() {
+                world.entity_mut(*tile).remove::<TileLerp>();
+            }
+        });
+    });
+
+    let mut board = ClipBoard::new();
+    c.bench_function("start the clips of 99 slides and play a frame", |b| {
+        b.iter(|| {
+            board.start_slides();
+            black_box(board.frame())
+        });
+    });
+    c.bench_function("play the clips of 99 slides until they land", |b| {
+        b.iter(|| black_box(board.slide_all()));
+    });
+}
+
+criterion_group!(benches, bench_tile_slides);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+//! Solves a board into a goal other than the numbers in order, here the numbers counting down to the empty slot.
+//! The solver only knows the ordered goal, so the tiles are renumbered by their place in the goal beforehand
+use bevy_taquin_3d::prelude::*;
+
+const SIZE: usize = 3;
+const GOAL: [i16; SIZE * SIZE] = [8, 7, 6, 5, 4, 3, 2, 1, 9];
+const MAX_NODES: u64 = 5_000_000;
+
+/// Values of `tiles` replaced by their index in `goal`, counted from 1, so that `goal` becomes the ordered board
+fn renumbered(tiles: &[i16], goal: &[i16]) -> Vec<u8> {
+    let mut place = vec![0; goal.len() + 1];
+    for (index, value) in goal.iter().enumerate() {
+        place[*value as usize] = index as u8 + 1;
+    }
+    tiles.iter().map(|value| place[*value as usize]).collect()
+}
+
+fn main() {
+    let tiles = seeded_tiles(SIZE as i8, SIZE as i8, 7).iter().map(|value| value.0).collect::<Vec<i16>>();
+    println!("start {tiles:?}, goal {GOAL:?}");
+    // Half of the goals are out of reach of a board, as half of the positions are out of reach of the ordered goal
+    let board = Board::new(SIZE, renumbered(&tiles, &GOAL));
+    if !board.is_solvable() {
+        println!("this board cannot reach the goal");
+        return;
+    }
+    let mut taquin = Taquin::new(SIZE as i8);
+    taquin.set_tiles(tiles.chunks(SIZE).map(|row| row.iter().copied().map(TileValue).collect()).collect());
+
+    let Some(moves) = solve(&board, MAX_NODES) else {
+        println!("no solution within {MAX_NODES} nodes");
+        return;
+    };
+
+    // Moves are cells, they apply to the board with its own numbers as well
+    for tile in &moves {
+        assert!(taquin.slide(*tile));
+    }
+    let reached = taquin.tiles.iter().flatten().map(|value| value.0).collect::<Vec<i16>>();
+    assert_eq!(reached, GOAL);
+    println!("goal reached in {} moves", moves.len());
+}
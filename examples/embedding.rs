@@ -0,0 +1,90 @@
+//! The board as a resource of another Bevy app: tiles are entities sliding with `TileLerp`,
+//! driven by the moves of a solution until the board is solved. Headless, it prints the moves as they land
+use std::time::Duration;
+
+use bevy::{app::{AppExit, ScheduleRunnerPlugin}, prelude::*};
+use bevy_taquin_3d::prelude::*;
+
+const SIZE: i8 = 3;
+/// World units between the centers of two cells
+const CELL: f32 = 1.;
+
+/// Tiles left to slide, the first one next
+#[derive(Resource)]
+struct Moves(Vec<TileCoordinates>);
+
+fn main() {
+    App::new()
+        .add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1. / 60.))))
+        .add_systems(Startup, setup)
+        .add_systems(Update, (play_next_move, slide_tiles, exit_when_solved).chain())
+        .run();
+}
+
+fn translation(coordinates: TileCoordinates) -> Vec3 {
+    Vec3::new(coordinates.i as f32 * CELL, -coordinates.j as f32 * CELL, 0.)
+}
+
+fn setup(
+    mut commands: Commands,
+) {
+    let mut taquin = Taquin::new(SIZE);
+    taquin.set_tiles(seeded_tiles(SIZE, SIZE, 1).chunks(SIZE as usize).map(|row| row.to_vec()).collect());
+    let moves = solve(&taquin.board(), 5_000_000).unwrap_or_default();
+    info!(moves = moves.len(), "solving");
+
+    for (j, row) in taquin.tiles.iter().enumerate() {
+        for (i, value) in row.iter().enumerate().filter(|(_, value)| !value.is_empty(taquin.tiles_nb)) {
+            let coordinates = TileCoordinates::new(i as i8, j as i8);
+            commands.spawn((*value, coordinates, TransformBundle::from_transform(Transform::from_translation(translation(coordinates)))));
+        }
+    }
+    commands.insert_resource(taquin);
+    commands.insert_resource(Moves(moves));
+}
+
+/// The next move starts once the previous tile has landed
+fn play_next_move(
+    mut commands: Commands,
+    mut taquin: ResMut<Taquin>,
+    mut moves: ResMut<Moves>,
+    mut tiles_query: Query<(Entity, &TileValue, &mut TileCoordinates)>,
+    sliding_query: Query<(), With<TileLerp>>,
+) {
+    if !sliding_query.is_empty() || moves.0.is_empty() {
+        return;
+    }
+    let tile = moves.0.remove(0);
+    let empty = taquin.get_empty_tile_coordinates();
+    if !taquin.slide(tile) {
+        return;
+    }
+    for (entity, value, mut coordinates) in tiles_query.iter_mut().filter(|(_, _, coordinates)| **coordinates == tile) {
+        println!("tile {} slides to ({}, {})", value.0, empty.i, empty.j);
+        *coordinates = empty;
+        commands.entity(entity).insert(TileLerp::new(translation(empty)).with_easing(Easing::EaseOut).with_speed(4.));
+    }
+}
+
+fn slide_tiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tiles_query: Query<(Entity, &mut Transform, &mut TileLerp)>,
+) {
+    for (entity, mut transform, mut lerp) in tiles_query.iter_mut() {
+        if lerp.advance(&mut transform.translation, time.delta_seconds()) {
+            commands.entity(entity).remove::<TileLerp>();
+        }
+    }
+}
+
+fn exit_when_solved(
+    taquin: Res<Taquin>,
+    sliding_query: Query<(), With<TileLerp>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    if taquin.is_solved() && sliding_query.is_empty() {
+        println!("solved");
+        exit_events.send(AppExit);
+    }
+}
@@ -0,0 +1,31 @@
+//! Solves seeded boards without a window, with the shortest and the row by row strategies.
+//! Run with `cargo run --release --no-default-features --example headless_solve -- <seed>`, without building bevy
+use bevy_taquin_3d::prelude::*;
+
+const SIZE: i8 = 3;
+/// Positions explored before a search gives up
+const MAX_NODES: u64 = 5_000_000;
+
+fn main() {
+    let seed = std::env::args().nth(1).and_then(|seed| seed.parse().ok()).unwrap_or(42);
+    let tiles = seeded_tiles(SIZE, SIZE, seed);
+    let board = Board::new(SIZE as usize, tiles.iter().map(|value| value.0 as u8).collect());
+    println!("seed {seed}: {:?}", board.tiles());
+
+    for strategy in [Strategy::Optimal, Strategy::HumanLike] {
+        let Some(solution) = strategy.solve(&board, SearchLimits::new(MAX_NODES)) else {
+            println!("{strategy:?}: no solution within {MAX_NODES} nodes");
+            continue;
+        };
+        // The moves are the tiles to slide into the empty slot, replayed on the board they solve it
+        let mut replay = board.clone();
+        for tile in &solution.moves {
+            assert!(replay.slide_tile(*tile));
+        }
+        assert!(replay.is_solved());
+        println!("{strategy:?}: {} moves, {} nodes", solution.moves.len(), solution.stats.nodes);
+        for (index, caption) in &solution.captions {
+            println!("  from move {index}: {caption}");
+        }
+    }
+}
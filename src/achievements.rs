@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    stats::{GameHistory, GameRecord, GameRecorded},
+    taquin::TileMoved,
+    AppState,
+};
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Achievements>()
+            .add_event::<AchievementUnlocked>()
+            .add_systems(Update, (
+                count_slides.run_if(on_event::<TileMoved>()),
+                unlock_achievements.run_if(on_event::<TileMoved>().or_else(on_event::<GameRecorded>())),
+                save_achievements.run_if(on_event::<GameRecorded>().or_else(on_event::<AchievementUnlocked>())),
+            ).chain().run_if(in_state(AppState::Running)))
+            .add_systems(OnExit(AppState::Running), save_achievements);
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Achievement {
+    FirstSolve,
+    NoHints,
+    FourByFourUnder200,
+    ThreeByThreeUnderMinute,
+    TenSolves,
+    HundredSolves,
+    ThousandSlides,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 7] = [
+        Achievement::FirstSolve,
+        Achievement::NoHints,
+        Achievement::FourByFourUnder200,
+        Achievement::ThreeByThreeUnderMinute,
+        Achievement::TenSolves,
+        Achievement::HundredSolves,
+        Achievement::ThousandSlides,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Achievement::FirstSolve => "Solve a puzzle",
+            Achievement::NoHints => "Solve a 4x4 or larger without hints",
+            Achievement::FourByFourUnder200 => "Solve a 4x4 under 200 moves",
+            Achievement::ThreeByThreeUnderMinute => "Solve a 3x3 under a minute",
+            Achievement::TenSolves => "Solve 10 puzzles",
+            Achievement::HundredSolves => "Solve 100 puzzles",
+            Achievement::ThousandSlides => "Slide 1000 tiles",
+        }
+    }
+
+    /// Whether the game just solved, the `solves` so far and the `slides` ever played earn it
+    fn is_earned(self, game: Option<&GameRecord>, solves: usize, slides: usize) -> bool {
+        match self {
+            Achievement::FirstSolve => solves >= 1,
+            Achievement::NoHints => game.is_some_and(|game| game.size >= 4 && game.hints == 0),
+            Achievement::FourByFourUnder200 => game.is_some_and(|game| game.size == 4 && game.score() < 200),
+            Achievement::ThreeByThreeUnderMinute => game.is_some_and(|game| game.size == 3 && game.time < 60.),
+            Achievement::TenSolves => solves >= 10,
+            Achievement::HundredSolves => solves >= 100,
+            Achievement::ThousandSlides => slides >= 1000,
+        }
+    }
+}
+
+/// Sent once for each achievement, when it is earned
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AchievementUnlocked(pub Achievement);
+
+/// Achievements earned, with when, persisted between runs
+#[derive(Resource, Serialize, Deserialize, Debug)]
+pub struct Achievements {
+    #[serde(default)]
+    pub version: u32,
+    /// Seconds since the unix epoch when each one was earned
+    pub unlocked: BTreeMap<Achievement, u64>,
+    /// Tiles slid in every game, solved or not
+    pub slides: usize,
+}
+
+impl Versioned for Achievements {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl FromWorld for Achievements {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().achievements();
+        let empty = Achievements { version: Self::VERSION, unlocked: BTreeMap::new(), slides: 0 };
+        if !persistence::exists(&path) {
+            return empty;
+        }
+        persistence::load_versioned(path, Format::Ron).unwrap_or_else(|error| {
+            error!("cannot load the achievements: {error}");
+            empty
+        })
+    }
+}
+
+impl Achievements {
+    /// Achievements not unlocked yet that are earned now, unlocked at `date`
+    fn unlock(&mut self, game: Option<&GameRecord>, solves: usize, date: u64) -> Vec<Achievement> {
+        let earned = Achievement::ALL.into_iter()
+            .filter(|achievement| !self.unlocked.contains_key(achievement) && achievement.is_earned(game, solves, self.slides))
+            .collect::<Vec<Achievement>>();
+        for achievement in earned.iter() {
+            self.unlocked.insert(*achievement, date);
+        }
+        earned
+    }
+}
+
+fn count_slides(
+    mut tile_moved_events: EventReader<TileMoved>,
+    mut achievements: ResMut<Achievements>,
+) {
+    achievements.slides += tile_moved_events.read().count();
+}
+
+/// Only solves that made it to the history count, the auto-solver ones stay out
+fn unlock_achievements(
+    mut recorded_events: EventReader<GameRecorded>,
+    mut achievements: ResMut<Achievements>,
+    history: Res<GameHistory>,
+    mut unlocked_events: EventWriter<AchievementUnlocked>,
+) {
+    let now = persistence::timestamp();
+    let mut earned = achievements.unlock(None, history.games.len(), now);
+    for GameRecorded(game) in recorded_events.read() {
+        earned.extend(achievements.unlock(Some(game), history.games.len(), now));
+    }
+    for achievement in earned {
+        info!(?achievement, "achievement unlocked");
+        unlocked_events.send(AchievementUnlocked(achievement));
+    }
+}
+
+fn save_achievements(
+    achievements: Res<Achievements>,
+    paths: Res<DataPaths>,
+) {
+    if let Err(error) = persistence::save_versioned(paths.achievements(), achievements.as_ref(), Format::Ron) {
+        error!("cannot save the achievements: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{achievements::{Achievement, Achievements}, stats::GameRecord};
+
+    #[test]
+    fn test_unlock() {
+        let mut achievements = Achievements { version: 1, unlocked: BTreeMap::new(), slides: 999 };
+        let game = GameRecord { date: 0, size: 4, seed: None, moves: 150, time: 90., optimal_moves: None, position: None, hints: 1, penalty: 10 };
+        assert_eq!(achievements.unlock(Some(&game), 1, 7), [Achievement::FirstSolve, Achievement::FourByFourUnder200]);
+        assert_eq!(achievements.unlock(Some(&game), 1, 8), []);
+        achievements.slides += 1;
+        assert_eq!(achievements.unlock(None, 10, 9), [Achievement::TenSolves, Achievement::ThousandSlides]);
+        assert_eq!(achievements.unlocked[&Achievement::FirstSolve], 7);
+    }
+}
@@ -0,0 +1,180 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::graphics::{MainCamera, MainLight};
+
+const DUST_COUNT: usize = 80;
+/// Radius of the dust cone where it meets the board
+const DUST_CONE_RADIUS: f32 = 6.;
+const DUST_SPEED: f32 = 0.3;
+/// Radians per second of the environment light around the board
+const ENVIRONMENT_LIGHT_SPEED: f32 = 0.05;
+const SWAY_ANGLE: f32 = PI / 180.;
+const SWAY_PERIOD: f32 = 8.;
+/// Point the camera sways around
+const BOARD_CENTER: Vec3 = Vec3::new(0., 1., 0.);
+
+pub struct AmbiencePlugin;
+
+impl Plugin for AmbiencePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Ambience>()
+            .add_systems(Update, (
+                toggle_ambience,
+                (despawn_ambience, spawn_ambience).chain().run_if(resource_changed::<Ambience>()),
+                (drift_dust, rotate_environment_light).run_if(|ambience: Res<Ambience>| ambience.enabled),
+                sway_frame,
+            ).chain());
+    }
+}
+
+/// Subtle motion around the board
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ambience {
+    /// Dust in the light and a slowly turning environment light
+    pub enabled: bool,
+    /// The board gently rocks, the camera moves around it so the tiles stay in their frame
+    pub frame_sway: bool,
+}
+
+impl Default for Ambience {
+    fn default() -> Self {
+        Self { enabled: true, frame_sway: false }
+    }
+}
+
+/// Spawned while the ambience is enabled
+#[derive(Component)]
+struct AmbienceEntity;
+
+#[derive(Component)]
+struct Dust {
+    velocity: Vec3,
+}
+
+#[derive(Component)]
+struct EnvironmentLight;
+
+/// Somewhere in the cone going from the light down to the board
+fn dust_position(light: Vec3, rng: &mut impl Rng) -> Vec3 {
+    let along = rng.gen_range(0.15..1.);
+    let angle = rng.gen_range(0. ..2. * PI);
+    let radius = DUST_CONE_RADIUS * along * rng.gen::<f32>().sqrt();
+    light.lerp(BOARD_CENTER, along) + Vec3::new(angle.cos() * radius, 0., angle.sin() * radius)
+}
+
+fn dust_velocity(rng: &mut impl Rng) -> Vec3 {
+    Vec3::new(rng.gen_range(-1. ..1.), rng.gen_range(-0.5..0.5), rng.gen_range(-1. ..1.)).normalize_or_zero() * DUST_SPEED
+}
+
+fn toggle_ambience(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut ambience: ResMut<Ambience>,
+) {
+    if keyboard_input.just_released(KeyCode::A) {
+        ambience.enabled = !ambience.enabled;
+    }
+}
+
+fn despawn_ambience(
+    mut commands: Commands,
+    ambience_query: Query<Entity, With<AmbienceEntity>>,
+) {
+    for entity in ambience_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn spawn_ambience(
+    mut commands: Commands,
+    ambience: Res<Ambience>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    light_query: Query<&Transform, With<MainLight>>,
+) {
+    if !ambience.enabled {
+        return;
+    }
+    commands.spawn((DirectionalLightBundle {
+        directional_light: DirectionalLight {
+            color: Color::rgb(1., 0.9, 0.75),
+            illuminance: 1500.,
+            ..default()
+        },
+        transform: Transform::from_rotation(Quat::from_euler(EulerRot::YXZ, 0., -PI / 3., 0.)),
+        ..default()
+    }, EnvironmentLight, AmbienceEntity));
+
+    let light = light_query.iter().next().map_or(Vec3::new(8., 16., 8.), |transform| transform.translation);
+    let mesh = meshes.add(shape::UVSphere { radius: 0.04, sectors: 6, stacks: 4 }.into());
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1., 0.95, 0.8, 0.35),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    let mut rng = rand::thread_rng();
+    for _ in 0..DUST_COUNT {
+        commands.spawn((PbrBundle {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            transform: Transform::from_translation(dust_position(light, &mut rng)),
+            ..default()
+        }, Dust { velocity: dust_velocity(&mut rng) }, AmbienceEntity));
+    }
+}
+
+/// Dust floats around and comes back elsewhere in the light once out of it
+fn drift_dust(
+    time: Res<Time>,
+    light_query: Query<&Transform, (With<MainLight>, Without<Dust>)>,
+    mut dust_query: Query<(&mut Transform, &mut Dust)>,
+) {
+    let Some(light) = light_query.iter().next().map(|transform| transform.translation) else {
+        return;
+    };
+    let mut rng = rand::thread_rng();
+    for (mut transform, mut dust) in dust_query.iter_mut() {
+        transform.translation += dust.velocity * time.delta_seconds();
+        let along = (transform.translation.y - light.y) / (BOARD_CENTER.y - light.y);
+        let axis = light.lerp(BOARD_CENTER, along);
+        let offset = Vec2::new(transform.translation.x - axis.x, transform.translation.z - axis.z).length();
+        if !(0.1..=1.).contains(&along) || offset > DUST_CONE_RADIUS * along {
+            transform.translation = dust_position(light, &mut rng);
+            dust.velocity = dust_velocity(&mut rng);
+        }
+    }
+}
+
+fn rotate_environment_light(
+    time: Res<Time>,
+    mut light_query: Query<&mut Transform, With<EnvironmentLight>>,
+) {
+    for mut transform in light_query.iter_mut() {
+        transform.rotate_y(ENVIRONMENT_LIGHT_SPEED * time.delta_seconds());
+    }
+}
+
+/// Rocks the camera around the board, back to rest when the sway is turned off
+fn sway_frame(
+    time: Res<Time>,
+    ambience: Res<Ambience>,
+    mut applied_angle: Local<f32>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    let angle = if ambience.frame_sway {
+        SWAY_ANGLE * (2. * PI * time.elapsed_seconds() / SWAY_PERIOD).sin()
+    } else {
+        0.
+    };
+    if angle == *applied_angle {
+        return;
+    }
+    for mut transform in camera_query.iter_mut() {
+        transform.rotate_around(BOARD_CENTER, Quat::from_rotation_y(angle - *applied_angle));
+    }
+    *applied_angle = angle;
+}
@@ -0,0 +1,299 @@
+use std::collections::{BTreeMap, HashSet};
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    solver::{self, Board},
+    solver_cache::SolverCache,
+    taquin::{is_solver_board, Taquin, TaquinShuffled},
+};
+
+/// Positions explored per sampled board before it is skipped
+const SAMPLE_MAX_NODES: u64 = 20_000_000;
+
+pub struct AnalysisPlugin;
+
+impl Plugin for AnalysisPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<DifficultyBuckets>()
+            .add_systems(Update, rate_shuffle.run_if(on_event::<TaquinShuffled>().and_then(is_solver_board)));
+    }
+}
+
+/// Number of positions at each optimal distance from the solved board
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DistanceDistribution {
+    pub size: i8,
+    /// Whether every position was visited, otherwise `counts` comes from random samples
+    pub exhaustive: bool,
+    pub counts: BTreeMap<u32, u64>,
+}
+
+impl DistanceDistribution {
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    pub fn mean(&self) -> f32 {
+        self.counts.iter().map(|(distance, count)| *distance as f32 * *count as f32).sum::<f32>() / self.total().max(1) as f32
+    }
+
+    /// Smallest distance reached by at least `ratio` of the positions
+    pub fn percentile(&self, ratio: f32) -> u32 {
+        let threshold = (self.total() as f32 * ratio).ceil() as u64;
+        let mut seen = 0;
+        for (distance, count) in &self.counts {
+            seen += count;
+            if seen >= threshold {
+                return *distance;
+            }
+        }
+        self.counts.keys().last().copied().unwrap_or(0)
+    }
+}
+
+/// Cached results of the analysis mode
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DistanceTables {
+    #[serde(default)]
+    pub version: u32,
+    pub distributions: Vec<DistanceDistribution>,
+}
+
+impl Versioned for DistanceTables {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+/// Breadth first search of every position reachable from the solved board
+pub fn explore(size: usize) -> DistanceDistribution {
+    let mut counts = BTreeMap::new();
+    let solved = Board::solved(size);
+    let mut seen = HashSet::from([solved.key()]);
+    let mut frontier = vec![solved];
+    let mut distance = 0;
+    while !frontier.is_empty() {
+        counts.insert(distance, frontier.len() as u64);
+        frontier = frontier.iter()
+            .flat_map(|board| board.successors().collect::<Vec<Board>>())
+            .filter(|board| seen.insert(board.key()))
+            .collect();
+        distance += 1;
+    }
+    DistanceDistribution { size: size as i8, exhaustive: true, counts }
+}
+
+/// Optimal distance of `board` from the cache or the solver, `None` if too hard for the node budget
+fn optimal_distance(board: &Board, cache: &mut SolverCache) -> Option<u32> {
+    cache.get(board).map(|cached| cached.length).or_else(|| {
+        let solution = solver::solve(board, SAMPLE_MAX_NODES)?;
+        cache.insert_solution(board, &solution);
+        Some(solution.len() as u32)
+    })
+}
+
+/// Optimal distances of `samples` random boards, the ones too hard for the node budget are skipped
+pub fn sample(size: usize, samples: usize, cache: &mut SolverCache) -> DistanceDistribution {
+    let mut rng = rand::thread_rng();
+    let mut counts = BTreeMap::new();
+    for index in 0..samples {
+        let board = Board::random(size, &mut rng);
+        match optimal_distance(&board, cache) {
+            Some(distance) => *counts.entry(distance).or_insert(0) += 1,
+            None => eprintln!("sample {index} skipped, too hard for the node budget"),
+        }
+    }
+    DistanceDistribution { size: size as i8, exhaustive: false, counts }
+}
+
+/// Analysis mode: explores 3x3, samples 4x4, prints the distributions and caches them
+pub fn run(paths: &DataPaths, samples: usize) -> Result<(), PersistenceError> {
+    let mut cache = SolverCache::load(paths);
+    let distributions = vec![explore(3), sample(4, samples, &mut cache)];
+    persistence::save_versioned(paths.solver_cache(), &cache, Format::Ron)?;
+    for distribution in &distributions {
+        let kind = if distribution.exhaustive { "exhaustive" } else { "sampled" };
+        println!("{0}x{0} ({kind}, {1} positions, mean {2:.2})", distribution.size, distribution.total(), distribution.mean());
+        for (distance, count) in &distribution.counts {
+            println!("{distance:>4} {count:>8} {:>6.2}%", *count as f32 * 100. / distribution.total() as f32);
+        }
+    }
+    let tables = DistanceTables { version: DistanceTables::VERSION, distributions };
+    persistence::save_versioned(paths.distances(), &tables, Format::Ron)?;
+    println!("saved to {}", paths.distances().display());
+    Ok(())
+}
+
+/// Board of `size` shuffled from `seed`, the same on every run
+pub fn seeded_scramble(size: usize, seed: u64) -> Board {
+    Board::random(size, &mut StdRng::seed_from_u64(seed))
+}
+
+/// Scramble report mode: solves `scrambles` seeded scrambles of each size, prints the histogram of their
+/// distances with the difficulty of each, exports every scramble as csv and ships the quartiles as the
+/// difficulty thresholds of the game
+pub fn scramble_report(paths: &DataPaths, sizes: &[usize], scrambles: usize, first_seed: u64) -> Result<(), PersistenceError> {
+    let mut cache = SolverCache::load(paths);
+    let mut tables = load_tables(paths);
+    let mut csv = String::from("size,seed,distance,difficulty\n");
+    for size in sizes {
+        let mut counts = BTreeMap::new();
+        let mut results = Vec::new();
+        for seed in first_seed..first_seed + scrambles as u64 {
+            let distance = optimal_distance(&seeded_scramble(*size, seed), &mut cache);
+            if let Some(distance) = distance {
+                *counts.entry(distance).or_insert(0) += 1;
+            }
+            results.push((seed, distance));
+            if results.len() % 100 == 0 {
+                eprintln!("{size}x{size}: {} of {scrambles} scrambles solved", results.len());
+            }
+        }
+        let distribution = DistanceDistribution { size: *size as i8, exhaustive: false, counts };
+        let buckets = DifficultyBuckets::from_tables(&DistanceTables { version: DistanceTables::VERSION, distributions: vec![distribution.clone()] });
+        let rate = |distance| buckets.rate(*size as i8, distance).map_or("", Difficulty::name);
+
+        let skipped = results.iter().filter(|(_, distance)| distance.is_none()).count();
+        let [q1, q2, q3] = [0.25, 0.5, 0.75].map(|ratio| distribution.percentile(ratio));
+        println!("{size}x{size}: {scrambles} scrambles, {skipped} skipped, mean {:.2}, quartiles {q1}/{q2}/{q3}", distribution.mean());
+        let largest = distribution.counts.values().max().copied().unwrap_or(1);
+        for (distance, count) in &distribution.counts {
+            println!("{distance:>4} {count:>6} {:<50} {}", "#".repeat((count * 50).div_ceil(largest) as usize), rate(*distance));
+        }
+        for (seed, distance) in results {
+            let distance_column = distance.map_or_else(String::new, |distance| distance.to_string());
+            csv.push_str(&format!("{size},{seed},{distance_column},{}\n", distance.map_or("", rate)));
+        }
+
+        tables.distributions.retain(|existing| existing.size != distribution.size);
+        tables.distributions.push(distribution);
+    }
+
+    persistence::save_versioned(paths.solver_cache(), &cache, Format::Ron)?;
+    persistence::save_versioned(paths.distances(), &tables, Format::Ron)?;
+    let path = paths.exports().join(format!("scrambles-{}.csv", persistence::timestamp()));
+    persistence::write(&path, csv)?;
+    println!("thresholds saved to {}, scrambles exported to {}", paths.distances().display(), path.display());
+    Ok(())
+}
+
+fn load_tables(paths: &DataPaths) -> DistanceTables {
+    let path = paths.distances();
+    let empty = || DistanceTables { version: DistanceTables::VERSION, distributions: Vec::new() };
+    if !persistence::exists(&path) {
+        return empty();
+    }
+    persistence::load_versioned(&path, Format::Ron).unwrap_or_else(|error| {
+        eprintln!("cannot load distance tables, starting from scratch: {error}");
+        empty()
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+}
+
+/// Quartiles of the cached distance distribution of each size, empty until the analysis mode ran
+#[derive(Resource, Debug)]
+pub struct DifficultyBuckets(BTreeMap<i8, [u32; 3]>);
+
+impl DifficultyBuckets {
+    pub fn from_tables(tables: &DistanceTables) -> Self {
+        Self(tables.distributions.iter()
+            .map(|distribution| (distribution.size, [0.25, 0.5, 0.75].map(|ratio| distribution.percentile(ratio))))
+            .collect())
+    }
+
+    pub fn rate(&self, size: i8, distance: u32) -> Option<Difficulty> {
+        let bounds = self.0.get(&size)?;
+        Some(match bounds.iter().position(|bound| distance <= *bound) {
+            Some(0) => Difficulty::Easy,
+            Some(1) => Difficulty::Medium,
+            Some(_) => Difficulty::Hard,
+            None => Difficulty::Expert,
+        })
+    }
+}
+
+impl FromWorld for DifficultyBuckets {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().distances();
+        if !persistence::exists(&path) {
+            return Self(BTreeMap::new());
+        }
+        persistence::load_versioned(&path, Format::Ron).map_or_else(|error| {
+            warn!("cannot load distance tables: {error}");
+            Self(BTreeMap::new())
+        }, |tables| Self::from_tables(&tables))
+    }
+}
+
+/// Only 3x3 boards are solved here, bigger ones would stall the frame unless already cached
+fn rate_shuffle(
+    taquin: Res<Taquin>,
+    buckets: Res<DifficultyBuckets>,
+    mut cache: ResMut<SolverCache>,
+) {
+    let board = taquin.board();
+    let distance = match cache.get(&board) {
+        Some(cached) => cached.length,
+        None if taquin.width == 3 => {
+            let Some(solution) = solver::solve(&board, SAMPLE_MAX_NODES) else {
+                return;
+            };
+            cache.insert_solution(&board, &solution);
+            solution.len() as u32
+        },
+        None => return,
+    };
+    match buckets.rate(taquin.width, distance) {
+        Some(difficulty) => info!(distance, ?difficulty, "shuffle rated"),
+        None => info!(distance, "shuffle not rated, run the analysis mode to calibrate difficulty"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::{explore, seeded_scramble, DifficultyBuckets, Difficulty, DistanceTables};
+
+    #[test]
+    fn test_explore_2x2() {
+        let distribution = explore(2);
+        assert_eq!(distribution.total(), 12);
+        assert_eq!(distribution.counts.keys().last(), Some(&6));
+
+        let buckets = DifficultyBuckets::from_tables(&DistanceTables { version: 1, distributions: vec![distribution] });
+        assert_eq!(buckets.rate(2, 0), Some(Difficulty::Easy));
+        assert_eq!(buckets.rate(2, 6), Some(Difficulty::Expert));
+        assert_eq!(buckets.rate(3, 6), None);
+    }
+
+    #[test]
+    fn test_seeded_scramble() {
+        assert_eq!(seeded_scramble(4, 42), seeded_scramble(4, 42));
+        assert_ne!(seeded_scramble(4, 42), seeded_scramble(4, 43));
+        assert!(seeded_scramble(4, 42).is_solvable());
+    }
+}
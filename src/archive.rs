@@ -0,0 +1,207 @@
+use std::io::Read;
+
+use flate2::read::DeflateDecoder;
+
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+/// 1980-01-01, the earliest date of the format
+const DOS_DATE: u16 = 0x21;
+/// The files of an archive unpack to this at most, a shared folder of pictures needs far less
+const MAX_UNPACKED_BYTES: u64 = 200 * 1024 * 1024;
+
+/// A file of a zip archive, its name using `/` between folders
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveFile {
+    pub name: String,
+    pub contents: Vec<u8>,
+}
+
+/// Zip archive of `files`, stored without compression since the pictures are compressed already
+pub fn write_zip(files: &[ArchiveFile]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut central_directory = Vec::new();
+    for file in files {
+        let offset = archive.len() as u32;
+        let crc = crc32fast::hash(&file.contents);
+        let size = file.contents.len() as u32;
+
+        put_u32(&mut archive, LOCAL_HEADER);
+        put_common_header(&mut archive, crc, size, file.name.len() as u16);
+        archive.extend_from_slice(file.name.as_bytes());
+        archive.extend_from_slice(&file.contents);
+
+        put_u32(&mut central_directory, CENTRAL_HEADER);
+        // Made by version 2.0
+        put_u16(&mut central_directory, 20);
+        put_common_header(&mut central_directory, crc, size, file.name.len() as u16);
+        // Comment length, disk, internal and external attributes
+        central_directory.extend_from_slice(&[0; 10]);
+        put_u32(&mut central_directory, offset);
+        central_directory.extend_from_slice(file.name.as_bytes());
+    }
+    let central_directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&central_directory);
+
+    put_u32(&mut archive, END_OF_CENTRAL_DIRECTORY);
+    // This disk and the disk of the central directory
+    put_u32(&mut archive, 0);
+    put_u16(&mut archive, files.len() as u16);
+    put_u16(&mut archive, files.len() as u16);
+    put_u32(&mut archive, central_directory.len() as u32);
+    put_u32(&mut archive, central_directory_offset);
+    // Comment length
+    put_u16(&mut archive, 0);
+    archive
+}
+
+/// Files of a zip archive, stored or deflated, folders left out. The sizes in the headers are not trusted,
+/// a file unpacks to no more than its size and the archive to no more than `MAX_UNPACKED_BYTES`
+pub fn read_zip(archive: &[u8]) -> Result<Vec<ArchiveFile>, String> {
+    let end = (0..=archive.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_SIZE)).rev()
+        .find(|offset| get_u32(archive, *offset) == Some(END_OF_CENTRAL_DIRECTORY))
+        .ok_or("not a zip archive")?;
+    let truncated = || "truncated zip archive".to_string();
+    let count = get_u16(archive, end + 10).ok_or_else(truncated)? as usize;
+    let mut offset = get_u32(archive, end + 16).ok_or_else(truncated)? as usize;
+
+    let mut files = Vec::new();
+    let mut unpacked = 0;
+    for _ in 0..count {
+        if get_u32(archive, offset) != Some(CENTRAL_HEADER) {
+            return Err("corrupted zip central directory".to_string());
+        }
+        let method = get_u16(archive, offset + 10).ok_or_else(truncated)?;
+        let crc = get_u32(archive, offset + 16).ok_or_else(truncated)?;
+        let compressed_size = get_u32(archive, offset + 20).ok_or_else(truncated)? as usize;
+        let size = get_u32(archive, offset + 24).ok_or_else(truncated)? as usize;
+        let name_length = get_u16(archive, offset + 28).ok_or_else(truncated)? as usize;
+        let extra_length = get_u16(archive, offset + 30).ok_or_else(truncated)? as usize;
+        let comment_length = get_u16(archive, offset + 32).ok_or_else(truncated)? as usize;
+        let local_offset = get_u32(archive, offset + 42).ok_or_else(truncated)? as usize;
+        let name = archive.get(offset + 46..offset + 46 + name_length).ok_or_else(truncated)?;
+        let name = String::from_utf8(name.to_vec()).map_err(|_| "file name is not UTF-8".to_string())?;
+        offset += 46 + name_length + extra_length + comment_length;
+        if name.ends_with('/') {
+            continue;
+        }
+
+        if get_u32(archive, local_offset) != Some(LOCAL_HEADER) {
+            return Err(format!("{name}: corrupted zip entry"));
+        }
+        let local_name_length = get_u16(archive, local_offset + 26).ok_or_else(truncated)? as usize;
+        let local_extra_length = get_u16(archive, local_offset + 28).ok_or_else(truncated)? as usize;
+        let start = local_offset + 30 + local_name_length + local_extra_length;
+        let data = archive.get(start..start + compressed_size).ok_or_else(truncated)?;
+        unpacked += size as u64;
+        if unpacked > MAX_UNPACKED_BYTES {
+            return Err(format!("the archive unpacks to more than {} MB", MAX_UNPACKED_BYTES / 1024 / 1024));
+        }
+        let contents = match method {
+            STORED => data.to_vec(),
+            DEFLATED => {
+                // One byte past the size tells a file bigger than its header says
+                let mut contents = Vec::new();
+                DeflateDecoder::new(data).take(size as u64 + 1).read_to_end(&mut contents).map_err(|error| format!("{name}: {error}"))?;
+                contents
+            },
+            _ => return Err(format!("{name}: unsupported compression method {method}")),
+        };
+        if contents.len() != size || crc32fast::hash(&contents) != crc {
+            return Err(format!("{name}: corrupted contents"));
+        }
+        files.push(ArchiveFile { name, contents });
+    }
+    Ok(files)
+}
+
+/// Version needed, flags, method, time and date, CRC, sizes, name and extra field lengths
+fn put_common_header(buffer: &mut Vec<u8>, crc: u32, size: u32, name_length: u16) {
+    put_u16(buffer, 20);
+    put_u16(buffer, 0);
+    put_u16(buffer, STORED);
+    put_u16(buffer, 0);
+    put_u16(buffer, DOS_DATE);
+    put_u32(buffer, crc);
+    put_u32(buffer, size);
+    put_u32(buffer, size);
+    put_u16(buffer, name_length);
+    put_u16(buffer, 0);
+}
+
+fn put_u16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn get_u16(buffer: &[u8], offset: usize) -> Option<u16> {
+    buffer.get(offset..offset + 2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn get_u32(buffer: &[u8], offset: usize) -> Option<u32> {
+    buffer.get(offset..offset + 4).map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::DeflateEncoder, Compression};
+
+    use crate::archive::{put_u16, put_u32, read_zip, CENTRAL_HEADER, DEFLATED, END_OF_CENTRAL_DIRECTORY, LOCAL_HEADER, MAX_UNPACKED_BYTES};
+
+    /// Archive of a single deflated file whose headers give `size` as its unpacked size
+    fn deflated_zip(contents: &[u8], size: u32) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents).unwrap();
+        let data = encoder.finish().unwrap();
+        let header = |buffer: &mut Vec<u8>| {
+            put_u16(buffer, 20);
+            put_u16(buffer, 0);
+            put_u16(buffer, DEFLATED);
+            put_u32(buffer, 0);
+            put_u32(buffer, crc32fast::hash(contents));
+            put_u32(buffer, data.len() as u32);
+            put_u32(buffer, size);
+            put_u16(buffer, 1);
+            put_u16(buffer, 0);
+        };
+        let mut archive = Vec::new();
+        put_u32(&mut archive, LOCAL_HEADER);
+        header(&mut archive);
+        archive.push(b'a');
+        archive.extend_from_slice(&data);
+        let central_directory_offset = archive.len() as u32;
+        put_u32(&mut archive, CENTRAL_HEADER);
+        put_u16(&mut archive, 20);
+        header(&mut archive);
+        archive.extend_from_slice(&[0; 10]);
+        put_u32(&mut archive, 0);
+        archive.push(b'a');
+        put_u32(&mut archive, END_OF_CENTRAL_DIRECTORY);
+        put_u32(&mut archive, 0);
+        put_u16(&mut archive, 1);
+        put_u16(&mut archive, 1);
+        let length = archive.len() as u32;
+        put_u32(&mut archive, length - central_directory_offset - 12);
+        put_u32(&mut archive, central_directory_offset);
+        put_u16(&mut archive, 0);
+        archive
+    }
+
+    #[test]
+    fn test_read_deflated_zip() {
+        let contents = vec![7; 100_000];
+        let files = read_zip(&deflated_zip(&contents, contents.len() as u32)).unwrap();
+        assert_eq!(files[0].contents, contents);
+        // A file bigger than its header says is not unpacked past it
+        assert!(read_zip(&deflated_zip(&contents, 10)).is_err());
+        assert!(read_zip(&deflated_zip(&contents, MAX_UNPACKED_BYTES as u32 + 1)).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+
+use crate::{
+    marker::Markers,
+    rotation::RotatingTiles,
+    taquin::Taquin,
+    tile::{TileCoordinates, TileSlide, TileSelected},
+    AppState,
+};
+
+const ASSIST_KEY: KeyCode = KeyCode::Q;
+/// Ghost size relative to a tile, leaving the cell borders visible
+const GHOST_SCALE: f32 = 0.9;
+/// Distance in front of the board, so the ghost is not hidden by the cell
+const GHOST_OFFSET: f32 = 0.05;
+
+pub struct AssistPlugin;
+
+impl Plugin for AssistPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<AssistMode>()
+            .add_systems(Startup, setup_assist_ghost)
+            // Q turns the tiles when they rotate, see `rotation`
+            .add_systems(Update, (toggle_assist_mode.run_if(resource_equals(RotatingTiles(false))), update_assist_ghost).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// Shows where the selected tile would go when Space is pressed
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct AssistMode(pub bool);
+
+#[derive(Component)]
+struct AssistGhost;
+
+fn setup_assist_ghost(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Quad::new(Vec2::ONE))),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgba(1., 1., 1., 0.25),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        visibility: Visibility::Hidden,
+        ..default()
+    }, AssistGhost));
+}
+
+fn toggle_assist_mode(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut assist: ResMut<AssistMode>,
+) {
+    if keyboard_input.just_released(ASSIST_KEY) {
+        assist.0 = !assist.0;
+        info!(assist = assist.0, "assist mode");
+    }
+}
+
+/// The ghost covers the empty cell while the selected tile is next to it, hidden during slides
+fn update_assist_ghost(
+    assist: Res<AssistMode>,
+    taquin: Res<Taquin>,
+    markers: Res<Markers>,
+    selected_query: Query<&TileCoordinates, With<TileSelected>>,
+    slide_query: Query<(), With<TileSlide>>,
+    mut ghost_query: Query<(&mut Transform, &mut Visibility), With<AssistGhost>>,
+) {
+    let Ok((mut transform, mut visibility)) = ghost_query.get_single_mut() else {
+        return;
+    };
+    let empty = selected_query.get_single().ok().and_then(|selected| taquin.get_empty_next_to(selected));
+    let shown = assist.0 && slide_query.is_empty() && empty.is_some();
+    let target = if shown { Visibility::Inherited } else { Visibility::Hidden };
+    if *visibility != target {
+        *visibility = target;
+    }
+    let Some(empty) = empty.filter(|_| shown) else {
+        return;
+    };
+    let translation = markers.tile_translation(taquin.width, taquin.height, empty) + Vec3::Z * GHOST_OFFSET;
+    let scale = (markers.tile_size(taquin.width, taquin.height) * GHOST_SCALE).extend(1.);
+    if transform.translation != translation || transform.scale != scale {
+        transform.translation = translation;
+        transform.scale = scale;
+    }
+}
@@ -0,0 +1,131 @@
+use bevy::{input::mouse::MouseMotion, prelude::*};
+
+use crate::{
+    auto_solve::{AutoSolveOptions, AutoSolvePlayback, AutoSolveRequested, AutoSolveStopRequested},
+    replay::ReplayPlayback,
+    scramble::RandomWalk,
+    solver::Strategy,
+    taquin::{ShuffleRequested, Taquin, TaquinShuffled},
+    tile::TileSlide,
+    AppState,
+};
+
+/// Seconds without any input on the main menu or a board not being played before the demo starts
+const ATTRACT_DELAY: f32 = 60.;
+/// Board of a demo started from the main menu
+const ATTRACT_SIZE: i8 = 4;
+/// Seconds the solved board stays on screen before the next shuffle
+const LOOP_PAUSE: f32 = 3.;
+/// Slow enough to follow each move
+const ATTRACT_SOLVE: AutoSolveOptions = AutoSolveOptions { strategy: Strategy::HumanLike, moves_per_second: 1.5, start_delay: 1., extra_moves: 0 };
+
+pub struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, enter_attract_mode.run_if(not(resource_exists::<AttractMode>())
+                .and_then(in_state(AppState::MainMenu).or_else(in_state(AppState::Running)))))
+            .add_systems(Update, (
+                leave_attract_mode,
+                shuffle_attract_board.run_if(resource_exists::<AttractMode>()
+                    .and_then(not(resource_exists::<AutoSolvePlayback>()))
+                    .and_then(not(resource_exists::<ShuffleRequested>()))
+                    .and_then(not(resource_exists::<RandomWalk>()))
+                    .and_then(not(any_with_component::<TileSlide>()))),
+                solve_attract_board.run_if(resource_exists::<AttractMode>().and_then(on_event::<TaquinShuffled>())),
+            ).chain().run_if(in_state(AppState::Running).and_then(resource_exists::<AttractMode>())));
+    }
+}
+
+/// Demo playing by itself: the board is shuffled then solved slowly, over and over until any input
+#[derive(Resource)]
+struct AttractMode {
+    next_shuffle: Timer,
+}
+
+/// Whether any key, button, touch or mouse motion came this frame
+fn input_received(
+    keyboard_input: &Input<KeyCode>,
+    mouse_input: &Input<MouseButton>,
+    gamepad_input: &Input<GamepadButton>,
+    touches: &Touches,
+    motion_events: &mut EventReader<MouseMotion>,
+) -> bool {
+    keyboard_input.get_just_pressed().count() + mouse_input.get_just_pressed().count()
+        + gamepad_input.get_just_pressed().count() + touches.iter_just_pressed().count() + motion_events.read().count() > 0
+}
+
+/// A game being played is left alone, only an unshuffled square board or the main menu turn into the demo
+#[allow(clippy::too_many_arguments)]
+fn enter_attract_mode(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    mut motion_events: EventReader<MouseMotion>,
+    state: Res<State<AppState>>,
+    time: Res<Time<Real>>,
+    mut taquin: ResMut<Taquin>,
+    replay: Option<Res<ReplayPlayback>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut idle: Local<f32>,
+) {
+    let playing = *state.get() == AppState::Running && (taquin.is_shuffled || !taquin.is_square() || replay.is_some());
+    if playing || input_received(&keyboard_input, &mouse_input, &gamepad_input, &touches, &mut motion_events) {
+        *idle = 0.;
+        return;
+    }
+    *idle += time.delta_seconds();
+    if *idle < ATTRACT_DELAY {
+        return;
+    }
+    *idle = 0.;
+    info!("attract mode started");
+    if *state.get() == AppState::MainMenu {
+        *taquin = Taquin::rect(ATTRACT_SIZE, ATTRACT_SIZE);
+        next_state.set(AppState::SetupTiles);
+    }
+    // The first shuffle comes right away
+    let mut next_shuffle = Timer::from_seconds(LOOP_PAUSE, TimerMode::Once);
+    next_shuffle.tick(next_shuffle.duration());
+    commands.insert_resource(AttractMode { next_shuffle });
+}
+
+/// The board is left as the demo had it, to be played from there
+fn leave_attract_mode(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    mut motion_events: EventReader<MouseMotion>,
+    mut stop_events: EventWriter<AutoSolveStopRequested>,
+) {
+    if !input_received(&keyboard_input, &mouse_input, &gamepad_input, &touches, &mut motion_events) {
+        return;
+    }
+    info!("attract mode stopped");
+    commands.remove_resource::<AttractMode>();
+    stop_events.send_default();
+}
+
+fn shuffle_attract_board(
+    mut commands: Commands,
+    mut attract: ResMut<AttractMode>,
+    taquin: Res<Taquin>,
+    time: Res<Time<Real>>,
+) {
+    if taquin.is_shuffled || !attract.next_shuffle.tick(time.delta()).finished() {
+        return;
+    }
+    attract.next_shuffle.reset();
+    commands.init_resource::<ShuffleRequested>();
+}
+
+fn solve_attract_board(
+    mut requested_events: EventWriter<AutoSolveRequested>,
+) {
+    requested_events.send(AutoSolveRequested(ATTRACT_SOLVE));
+}
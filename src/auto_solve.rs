@@ -0,0 +1,323 @@
+use bevy::{input::mouse::MouseMotion, prelude::*, tasks::{block_on, AsyncComputeTaskPool, Task}};
+
+use crate::{
+    graphics::BatterySaver,
+    marker::Markers,
+    playback::{self, PlaybackController},
+    replay::{Replay, ReplayMove, ReplayPlayback},
+    solver::{self, Board, SearchLimits, SearchStats, Solution, Strategy},
+    solver_cache::SolverCache,
+    taquin::{is_solver_board, SlideSelectedTile, Taquin, TaquinShuffled},
+    tile::{TileCoordinates, TileIndex, TileSlide, TileSelected},
+    AppState,
+};
+
+/// Positions explored before giving up, enough for any 3x3 and most 4x4 boards
+const MAX_NODES: u64 = 50_000_000;
+/// Seconds of an auto-solve without any input before the graphics drop to the low-power preset
+const UNATTENDED_DELAY: f32 = 20.;
+
+pub struct AutoSolvePlugin;
+
+impl Plugin for AutoSolvePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<AutoSolveOptions>()
+            .init_resource::<SolverTable>()
+            .add_event::<AutoSolveStarted>()
+            .add_event::<AutoSolveRequested>()
+            .add_event::<AutoSolveStopRequested>()
+            .add_systems(Update, (
+                stop_auto_solve.run_if(resource_exists::<AutoSolveTask>().or_else(resource_exists::<AutoSolvePlayback>())),
+                start_auto_solve.run_if(not(resource_exists::<AutoSolveTask>()).and_then(not(resource_exists::<AutoSolvePlayback>())).and_then(is_solver_board)),
+                receive_auto_solve.run_if(resource_exists::<AutoSolveTask>()),
+                apply_auto_solve_seek.run_if(resource_exists::<AutoSolvePlayback>()),
+                play_auto_solve.run_if(resource_exists::<AutoSolvePlayback>().and_then(not(any_with_component::<TileSlide>()))),
+                cancel_auto_solve.run_if(on_event::<TaquinShuffled>()),
+            ).chain().run_if(in_state(AppState::Running).and_then(not(resource_exists::<ReplayPlayback>()))))
+            .add_systems(OnEnter(AppState::MainMenu), cancel_auto_solve)
+            .add_systems(Update, watch_unattended_auto_solve);
+    }
+}
+
+/// Handicap of the auto-solver, from easy to brutal
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct AutoSolveOptions {
+    pub strategy: Strategy,
+    pub moves_per_second: f32,
+    /// Seconds to wait before the first move
+    pub start_delay: f32,
+    /// Moves wasted on detours on top of the solution of `strategy`
+    pub extra_moves: usize,
+}
+
+impl AutoSolveOptions {
+    pub fn preset(name: &str) -> Option<Self> {
+        let (strategy, moves_per_second, start_delay, extra_moves) = match name {
+            "easy" => (Strategy::HumanLike, 1., 3., 10),
+            "medium" => (Strategy::HumanLike, 2., 2., 0),
+            "hard" => (Strategy::Optimal, 4., 1., 2),
+            "brutal" => (Strategy::Optimal, 8., 0., 0),
+            _ => return None,
+        };
+        Some(Self { strategy, moves_per_second, start_delay, extra_moves })
+    }
+}
+
+impl Default for AutoSolveOptions {
+    fn default() -> Self {
+        Self { strategy: Strategy::Optimal, moves_per_second: 4., start_delay: 0., extra_moves: 0 }
+    }
+}
+
+/// Transposition table size of the optimal solver and the counters of its last search
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct SolverTable {
+    pub size: usize,
+    pub last_stats: Option<SearchStats>,
+}
+
+impl SolverTable {
+    pub fn new(size: usize) -> Self {
+        Self { size, last_stats: None }
+    }
+}
+
+impl Default for SolverTable {
+    fn default() -> Self {
+        Self::new(solver::DEFAULT_TABLE_SIZE)
+    }
+}
+
+/// Sent when the auto-solver takes over the current game
+#[derive(Event, Default)]
+pub struct AutoSolveStarted;
+
+/// Asks the auto-solver to take over with these options rather than `AutoSolveOptions`, as `S` does
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AutoSolveRequested(pub AutoSolveOptions);
+
+/// Stops the search or the moves left, as `S` does again
+#[derive(Event, Default)]
+pub struct AutoSolveStopRequested;
+
+#[derive(Resource)]
+struct AutoSolveTask {
+    task: Option<Task<Option<Solution>>>,
+    board: Board,
+    /// Board the solution starts from, with no move yet
+    start: Replay,
+    options: AutoSolveOptions,
+    /// Whether the solution is optimal and worth caching
+    cacheable: bool,
+}
+
+/// Solution being played back, as a replay with one move every `1 / moves_per_second` seconds after the start
+/// delay. Its play state is the `PlaybackController` inserted with it
+#[derive(Resource)]
+pub struct AutoSolvePlayback {
+    solution: Replay,
+    captions: Vec<(usize, String)>,
+}
+
+impl AutoSolvePlayback {
+    fn new(mut start: Replay, solution: Solution, options: &AutoSolveOptions) -> Self {
+        let interval = 1. / options.moves_per_second.max(0.01);
+        start.moves = solution.moves.into_iter()
+            .enumerate()
+            .map(|(index, tile)| ReplayMove { tile, time: options.start_delay.max(0.) + index as f32 * interval, into: None })
+            .collect();
+        Self { solution: start, captions: solution.captions }
+    }
+
+    /// Inserts the playback with its controller
+    fn start(self, commands: &mut Commands) {
+        commands.insert_resource(PlaybackController::new("Auto-solve", self.solution.moves.len()));
+        commands.insert_resource(self);
+    }
+
+    /// What the moves being played from `played` on are meant to achieve, when the strategy explains itself
+    pub fn caption(&self, played: usize) -> Option<&str> {
+        self.captions.iter().rev().find(|(start, _)| *start <= played).map(|(_, caption)| caption.as_str())
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_auto_solve(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut requested_events: EventReader<AutoSolveRequested>,
+    taquin: Res<Taquin>,
+    options: Res<AutoSolveOptions>,
+    table: Res<SolverTable>,
+    mut cache: ResMut<SolverCache>,
+    mut started_events: EventWriter<AutoSolveStarted>,
+) {
+    let control = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let pressed = (keyboard_input.just_released(KeyCode::S) && !control).then_some(*options);
+    let Some(options) = requested_events.read().last().map(|AutoSolveRequested(options)| *options).or(pressed) else {
+        return;
+    };
+    if taquin.is_solved() {
+        return;
+    }
+
+    let board = taquin.board();
+    let AutoSolveOptions { strategy, extra_moves, .. } = options;
+    info!(size = taquin.width, ?strategy, extra_moves, "auto-solve requested");
+    let cacheable = strategy == Strategy::Optimal && extra_moves == 0;
+    if let Some(moves) = cacheable.then(|| cache.solution(&board)).flatten() {
+        info!(moves = moves.len(), "auto-solve started from the solver cache");
+        AutoSolvePlayback::new(Replay::new(&taquin), Solution { moves, captions: Vec::new(), stats: SearchStats::default() }, &options).start(&mut commands);
+        started_events.send_default();
+        return;
+    }
+
+    let task_board = board.clone();
+    let limits = SearchLimits { max_nodes: MAX_NODES, table_size: table.size };
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        solver::solve_with_extra_moves(&task_board, strategy, extra_moves, limits, &mut rand::thread_rng())
+    });
+    commands.insert_resource(AutoSolveTask { task: Some(task), board, start: Replay::new(&taquin), options, cacheable });
+}
+
+/// `S` again stops the search or the moves left, the tile sliding still lands
+fn stop_auto_solve(
+    mut commands: Commands,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut stop_events: EventReader<AutoSolveStopRequested>,
+) {
+    let requested = stop_events.read().count() > 0;
+    if !keyboard_input.clear_just_released(KeyCode::S) && !requested {
+        return;
+    }
+    info!("auto-solve stopped");
+    commands.remove_resource::<AutoSolveTask>();
+    commands.remove_resource::<AutoSolvePlayback>();
+    commands.remove_resource::<PlaybackController>();
+}
+
+fn receive_auto_solve(
+    mut commands: Commands,
+    mut task: ResMut<AutoSolveTask>,
+    mut table: ResMut<SolverTable>,
+    mut cache: ResMut<SolverCache>,
+    mut started_events: EventWriter<AutoSolveStarted>,
+) {
+    if !task.task.as_ref().is_some_and(|task| task.is_finished()) {
+        return;
+    }
+    commands.remove_resource::<AutoSolveTask>();
+    let Some(solution) = task.task.take().and_then(block_on) else {
+        warn!("auto-solve gave up, the board is too hard for the solver");
+        return;
+    };
+    if task.cacheable {
+        cache.insert_solution(&task.board, &solution.moves);
+    }
+    if task.options.strategy == Strategy::Optimal {
+        table.last_stats = Some(solution.stats);
+    }
+
+    let stats = solution.stats;
+    info!(moves = solution.moves.len(), nodes = stats.nodes, hit_rate = stats.hit_rate(), "auto-solve started");
+    AutoSolvePlayback::new(task.start.clone(), solution, &task.options).start(&mut commands);
+    started_events.send_default();
+}
+
+/// Marks the battery saver unattended once an auto-solve has played alone for a while, any input or the end
+/// of the moves brings the full quality back
+#[allow(clippy::too_many_arguments)]
+fn watch_unattended_auto_solve(
+    playback: Option<Res<AutoSolvePlayback>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    time: Res<Time<Real>>,
+    mut motion_events: EventReader<MouseMotion>,
+    mut battery_saver: ResMut<BatterySaver>,
+    mut idle: Local<f32>,
+) {
+    let input = keyboard_input.get_just_pressed().count() + mouse_input.get_just_pressed().count()
+        + gamepad_input.get_just_pressed().count() + touches.iter_just_pressed().count() + motion_events.read().count() > 0;
+    if input || playback.is_none() {
+        *idle = 0.;
+    } else {
+        *idle += time.delta_seconds();
+    }
+    let unattended = *idle >= UNATTENDED_DELAY;
+    if battery_saver.unattended != unattended {
+        info!(unattended, "auto-solve attendance");
+        battery_saver.unattended = unattended;
+    }
+}
+
+/// Seeking puts the tiles where the solution had them, the moves played in between are not counted
+fn apply_auto_solve_seek(
+    mut commands: Commands,
+    playback: Res<AutoSolvePlayback>,
+    mut controller: ResMut<PlaybackController>,
+    mut taquin: ResMut<Taquin>,
+    markers: Res<Markers>,
+    index: Res<TileIndex>,
+    mut tiles_query: Query<(&mut TileCoordinates, &mut Transform)>,
+) {
+    let Some(move_count) = controller.take_seek() else {
+        return;
+    };
+    let board = match playback.solution.board_at(move_count) {
+        Ok(board) => board,
+        Err(error) => {
+            error!("{error}");
+            return;
+        }
+    };
+    taquin.set_tiles(board.tiles);
+    playback::snap_tiles(&mut commands, &taquin, &markers, &index, &mut tiles_query);
+    controller.seeked(move_count, move_count.checked_sub(1).map_or(0., |index| playback.solution.moves[index].time));
+}
+
+fn play_auto_solve(
+    mut commands: Commands,
+    time: Res<Time>,
+    playback: Res<AutoSolvePlayback>,
+    mut controller: ResMut<PlaybackController>,
+    selected_tile_query: Query<(Entity, &TileCoordinates), With<TileSelected>>,
+    index: Res<TileIndex>,
+    mut slide_events: EventWriter<SlideSelectedTile>,
+) {
+    let Some(next_move) = playback.solution.moves.get(controller.played()).copied() else {
+        commands.remove_resource::<AutoSolvePlayback>();
+        commands.remove_resource::<PlaybackController>();
+        return;
+    };
+    if !controller.tick(time.delta_seconds(), next_move.time) {
+        return;
+    }
+
+    // The selection is moved onto the tile first, it slides on the next frame
+    let Ok((selected_entity, selected_coordinates)) = selected_tile_query.get_single() else {
+        return;
+    };
+    if *selected_coordinates != next_move.tile {
+        if let Some(entity) = index.at(next_move.tile) {
+            commands.entity(selected_entity).remove::<TileSelected>();
+            commands.entity(entity).insert(TileSelected);
+        }
+        return;
+    }
+    slide_events.send_default();
+    controller.advance(next_move.time);
+}
+
+fn cancel_auto_solve(
+    mut commands: Commands,
+    playback: Option<Res<AutoSolvePlayback>>,
+) {
+    commands.remove_resource::<AutoSolveTask>();
+    if playback.is_some() {
+        commands.remove_resource::<AutoSolvePlayback>();
+        commands.remove_resource::<PlaybackController>();
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::{
+    input::{keyboard::KeyboardInput, ButtonState},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    auto_solve::AutoSolveOptions,
+    exit::Shutdown,
+    keybindings::{Binding, KeyBindings},
+    persistence::{self, DataPaths},
+    taquin::{TaquinSolved, TileMoved},
+    AppState,
+};
+
+/// Auto-solver moves played before the report
+const BENCHMARK_MOVES: usize = 200;
+/// Frames between two texture toggles
+const TOGGLE_FRAMES: usize = 90;
+/// Frames left to the shuffle to settle before the auto-solver is started
+const SHUFFLE_FRAMES: usize = 10;
+
+/// Plays a scripted game with the keys a player would press, then reports the frame times and exits
+pub struct BenchmarkPlugin;
+
+impl Plugin for BenchmarkPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(AutoSolveOptions { moves_per_second: 20., start_delay: 0., ..default() })
+            .init_resource::<Benchmark>()
+            .add_systems(Update, (
+                run_benchmark.run_if(|benchmark: Res<Benchmark>| benchmark.moves < BENCHMARK_MOVES),
+                report_benchmark.run_if((|benchmark: Res<Benchmark>| benchmark.moves >= BENCHMARK_MOVES).and_then(run_once())),
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Step {
+    #[default]
+    Shuffle,
+    /// Waiting for the shuffle to settle
+    Shuffled(usize),
+    Solving,
+}
+
+#[derive(Resource, Default)]
+struct Benchmark {
+    step: Step,
+    moves: usize,
+    /// Seconds, real time
+    frame_times: Vec<f32>,
+    /// Pressed on the previous frame, released on this one
+    pressed: Vec<KeyCode>,
+}
+
+/// Frame time statistics in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FrameTimeReport {
+    frames: usize,
+    average: f32,
+    p50: f32,
+    p95: f32,
+    p99: f32,
+    max: f32,
+}
+
+impl FrameTimeReport {
+    fn new(frame_times: &[f32]) -> Option<Self> {
+        let mut sorted = frame_times.iter().map(|time| time * 1000.).collect::<Vec<f32>>();
+        sorted.sort_by(f32::total_cmp);
+        Some(Self {
+            frames: sorted.len(),
+            average: sorted.iter().sum::<f32>() / sorted.len() as f32,
+            p50: percentile(&sorted, 50.)?,
+            p95: percentile(&sorted, 95.)?,
+            p99: percentile(&sorted, 99.)?,
+            max: *sorted.last()?,
+        })
+    }
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f32], percent: f32) -> Option<f32> {
+    let rank = (percent / 100. * sorted.len() as f32).ceil() as usize;
+    sorted.get(rank.clamp(1, sorted.len().max(1)) - 1).copied()
+}
+
+fn key_event(key_code: KeyCode, state: ButtonState, window: Entity) -> KeyboardInput {
+    KeyboardInput { scan_code: 0, key_code: Some(key_code), state, window }
+}
+
+fn run_benchmark(
+    mut benchmark: ResMut<Benchmark>,
+    time: Res<Time<Real>>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+    mut moved_events: EventReader<TileMoved>,
+    mut solved_events: EventReader<TaquinSolved>,
+    mut key_events: EventWriter<KeyboardInput>,
+    bindings: Res<KeyBindings>,
+) {
+    let window = window_query.get_single().unwrap_or(Entity::PLACEHOLDER);
+    let released = std::mem::take(&mut benchmark.pressed);
+    key_events.send_batch(released.into_iter().map(|key_code| key_event(key_code, ButtonState::Released, window)));
+
+    benchmark.frame_times.push(time.delta_seconds());
+    // Shuffles do not send moves, all of them are played by the auto-solver
+    benchmark.moves += moved_events.read().count();
+    let solved = solved_events.read().count() > 0;
+
+    let mut pressed = Vec::new();
+    if benchmark.frame_times.len().is_multiple_of(TOGGLE_FRAMES) {
+        pressed.push(bindings.key(Binding::Texture));
+    }
+    benchmark.step = match benchmark.step {
+        Step::Shuffle => {
+            pressed.push(bindings.key(Binding::Shuffle));
+            Step::Shuffled(0)
+        },
+        Step::Shuffled(frames) if frames < SHUFFLE_FRAMES => Step::Shuffled(frames + 1),
+        Step::Shuffled(_) => {
+            pressed.push(KeyCode::S);
+            Step::Solving
+        },
+        Step::Solving if solved => Step::Shuffle,
+        Step::Solving => Step::Solving,
+    };
+    key_events.send_batch(pressed.iter().map(|key_code| key_event(*key_code, ButtonState::Pressed, window)));
+    benchmark.pressed = pressed;
+}
+
+fn report_benchmark(
+    benchmark: Res<Benchmark>,
+    paths: Res<DataPaths>,
+    mut shutdown_events: EventWriter<Shutdown>,
+) {
+    match FrameTimeReport::new(&benchmark.frame_times) {
+        Some(report) => println!(
+            "{} frames, average {:.2} ms, p50 {:.2} ms, p95 {:.2} ms, p99 {:.2} ms, max {:.2} ms",
+            report.frames, report.average, report.p50, report.p95, report.p99, report.max,
+        ),
+        None => eprintln!("no frame measured"),
+    }
+    let csv = benchmark.frame_times.iter().enumerate().map(|(frame, time)| format!("{frame},{:.3}\n", time * 1000.)).collect::<String>();
+    let path = paths.exports().join(format!("benchmark-{}.csv", persistence::timestamp()));
+    match persistence::write(&path, format!("frame,milliseconds\n{csv}")) {
+        Ok(()) => println!("frame times exported to {}", path.display()),
+        Err(error) => eprintln!("cannot export the frame times: {error}"),
+    }
+    shutdown_events.send_default();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::benchmark::{percentile, FrameTimeReport};
+
+    #[test]
+    fn test_frame_time_report() {
+        let sorted = (1..=100).map(|value| value as f32).collect::<Vec<f32>>();
+        assert_eq!((percentile(&sorted, 50.), percentile(&sorted, 99.), percentile(&sorted, 100.)), (Some(50.), Some(99.), Some(100.)));
+        assert_eq!(percentile(&[], 50.), None);
+
+        let report = FrameTimeReport::new(&[0.016, 0.017, 0.015, 0.032]).unwrap();
+        assert_eq!((report.frames, report.p50, report.max), (4, 16., 32.));
+        assert!((report.average - 20.).abs() < 1e-4);
+        assert!(FrameTimeReport::new(&[]).is_none());
+    }
+}
@@ -0,0 +1,328 @@
+use bevy::prelude::*;
+
+use crate::{
+    cli::{MAX_EMPTY_SLOTS, MAX_SIZE},
+    custom_image::is_image_url,
+    marker::Markers,
+    persistence::PersistenceError,
+    platform,
+    replay::{push_code_number, read_code_numbers},
+    rotation::{ArrangedRotations, RotatingTiles},
+    taquin::{place_tiles, EmptySlots, ShuffleSeed, Taquin, TaquinShuffled},
+    tile::{TileCoordinates, TileValue, Topology},
+    AppState,
+};
+
+/// 2 added the empty slots, the topology and the rotations, a code of 1 is a flat board with one empty slot
+const CODE_VERSION: u32 = 2;
+/// The seed is cut in parts small enough for a number of the code
+const SEED_PART_BITS: u32 = 16;
+
+pub struct BoardCodePlugin;
+
+impl Plugin for BoardCodePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, paste_board_code.run_if(in_state(AppState::MainMenu).or_else(in_state(AppState::Running))))
+            .add_systems(Update, (copy_board_code, place_pasted_board.run_if(resource_exists::<PastedBoard>())).run_if(in_state(AppState::Running)));
+    }
+}
+
+/// A position as shared in a bug report or a chat: the size and kind of the board, the seed it was shuffled from
+/// and its tiles
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardCode {
+    pub width: i8,
+    pub height: i8,
+    /// Empty slots, the highest values stand for them
+    pub empty_count: usize,
+    pub topology: Topology,
+    /// Seed the board was shuffled from, moves may have been played since
+    pub seed: Option<u64>,
+    /// Row after row, the empty slots as the highest values
+    pub tiles: Vec<TileValue>,
+    /// Quarter turns of each tile by value, on a board of `--rotating-tiles` only
+    pub rotations: Option<Vec<u8>>,
+}
+
+impl BoardCode {
+    pub fn new(taquin: &Taquin, seed: Option<u64>, rotating: bool) -> Self {
+        Self {
+            width: taquin.width,
+            height: taquin.height,
+            empty_count: taquin.empty_count(),
+            topology: taquin.topology,
+            seed,
+            tiles: taquin.tiles.iter().flatten().copied().collect(),
+            rotations: rotating.then(|| (1..=taquin.tiles_nb).map(|value| taquin.rotation(TileValue(value as i16))).collect()),
+        }
+    }
+
+    /// The version, the board size, the empty slots, whether it wraps, whether a seed follows then its parts,
+    /// the tiles, then whether rotations follow and the rotation of each value
+    pub fn to_code(&self) -> String {
+        let mut code = String::new();
+        let wraps = (self.topology == Topology::Torus) as u32;
+        for number in [CODE_VERSION, self.width as u32, self.height as u32, self.empty_count as u32, wraps] {
+            push_code_number(&mut code, number);
+        }
+        match self.seed {
+            Some(seed) => {
+                push_code_number(&mut code, 1);
+                for part in 0..u64::BITS / SEED_PART_BITS {
+                    push_code_number(&mut code, (seed >> (part * SEED_PART_BITS)) as u32 & ((1 << SEED_PART_BITS) - 1));
+                }
+            },
+            None => push_code_number(&mut code, 0),
+        }
+        for value in self.tiles.iter() {
+            push_code_number(&mut code, value.0 as u32);
+        }
+        match &self.rotations {
+            Some(rotations) => {
+                push_code_number(&mut code, 1);
+                for rotation in rotations.iter() {
+                    push_code_number(&mut code, *rotation as u32);
+                }
+            },
+            None => push_code_number(&mut code, 0),
+        }
+        code
+    }
+
+    pub fn from_code(code: &str) -> Result<Self, PersistenceError> {
+        let mut numbers = read_code_numbers(code.trim())?.into_iter();
+        let mut next = || numbers.next().ok_or_else(|| PersistenceError::Invalid("board code is cut".to_string()));
+        let version = next()?;
+        if version != 1 && version != CODE_VERSION {
+            return Err(PersistenceError::unsupported_version(version));
+        }
+        let (width, height) = (next()?, next()?);
+        if !(2..=MAX_SIZE as u32).contains(&width) || !(2..=MAX_SIZE as u32).contains(&height) {
+            return Err(PersistenceError::Invalid(format!("{width}x{height} board is not supported")));
+        }
+        let (empty_count, topology) = match version {
+            1 => (1, Topology::Flat),
+            _ => (next()? as usize, match next()? {
+                0 => Topology::Flat,
+                1 => Topology::Torus,
+                flag => return Err(PersistenceError::Invalid(format!("unexpected wrap flag {flag}"))),
+            }),
+        };
+        if !(1..=MAX_EMPTY_SLOTS).contains(&empty_count) {
+            return Err(PersistenceError::Invalid(format!("{empty_count} empty slots, not between 1 and {MAX_EMPTY_SLOTS}")));
+        }
+        let seed = match next()? {
+            0 => None,
+            1 => Some((0..u64::BITS / SEED_PART_BITS).try_fold(0u64, |seed, part| Ok::<_, PersistenceError>(seed | (next()? as u64) << (part * SEED_PART_BITS)))?),
+            flag => return Err(PersistenceError::Invalid(format!("unexpected seed flag {flag}"))),
+        };
+        let tiles = (0..width * height).map(|_| Ok(TileValue(next()? as i16))).collect::<Result<Vec<TileValue>, PersistenceError>>()?;
+        let rotations = match version {
+            1 => None,
+            _ => match next()? {
+                0 => None,
+                1 => Some((0..width * height).map(|_| Ok(next()? as u8)).collect::<Result<Vec<u8>, PersistenceError>>()?),
+                flag => return Err(PersistenceError::Invalid(format!("unexpected rotations flag {flag}"))),
+            },
+        };
+        if numbers.next().is_some() {
+            return Err(PersistenceError::Invalid(format!("more than the {} tiles of a {width}x{height} board", width * height)));
+        }
+        let board = Self { width: width as i8, height: height as i8, empty_count, topology, seed, tiles, rotations };
+        board.validate()?;
+        Ok(board)
+    }
+
+    /// Every tile once, fewer empty slots than cells, quarter turns only, in a position the empty slots can solve
+    /// on its topology
+    fn validate(&self) -> Result<(), PersistenceError> {
+        let mut values = self.tiles.iter().map(|value| value.0).collect::<Vec<i16>>();
+        values.sort();
+        if values != (1..=self.width as i16 * self.height as i16).collect::<Vec<i16>>() {
+            return Err(PersistenceError::Invalid("board is not a permutation of the tiles".to_string()));
+        }
+        if self.empty_count >= self.tiles.len() {
+            return Err(PersistenceError::Invalid(format!("{} empty slots leave no tile on a {}x{} board", self.empty_count, self.width, self.height)));
+        }
+        if self.rotations.iter().flatten().any(|rotation| *rotation > 3) {
+            return Err(PersistenceError::Invalid("a tile is turned more than three quarters".to_string()));
+        }
+        if !self.taquin().is_solvable() {
+            return Err(PersistenceError::Invalid("board cannot be solved".to_string()));
+        }
+        Ok(())
+    }
+
+    fn taquin(&self) -> Taquin {
+        let mut taquin = Taquin::rect(self.width, self.height);
+        taquin.topology = self.topology;
+        taquin.set_empty_count(self.empty_count);
+        taquin.set_tiles(self.tiles.chunks(self.width as usize).map(|row| row.to_vec()).collect());
+        taquin
+    }
+
+    /// Whether the board can be placed in a game of this size and kind without starting a new one
+    fn fits(&self, taquin: &Taquin, topology: Topology, rotating: RotatingTiles) -> bool {
+        (self.width, self.height, self.empty_count, self.topology, self.rotations.is_some())
+            == (taquin.width, taquin.height, taquin.empty_count(), topology, rotating.0)
+    }
+}
+
+/// Board to put on screen once the game runs, from `--state` or the clipboard
+#[derive(Resource)]
+pub struct PastedBoard(pub BoardCode);
+
+fn is_control_pressed(keyboard_input: &Input<KeyCode>) -> bool {
+    keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+/// Ctrl+C, the code also goes in the page url so the link opens the board, see `--state`
+fn copy_board_code(
+    keyboard_input: Res<Input<KeyCode>>,
+    taquin: Res<Taquin>,
+    rotating: Res<RotatingTiles>,
+    seed: Res<ShuffleSeed>,
+) {
+    if !keyboard_input.just_released(KeyCode::C) || !is_control_pressed(&keyboard_input) {
+        return;
+    }
+    let code = BoardCode::new(&taquin, seed.current, rotating.0).to_code();
+    platform::set_url_fragment(&format!("state={code}"));
+    match platform::copy_text(&code) {
+        Ok(()) => info!(code, "board code copied"),
+        Err(error) => warn!(code, "cannot copy the board code: {error}"),
+    }
+}
+
+/// Ctrl+V, a board of another size or kind is only pasted in the main menu, where the board is picked
+#[allow(clippy::too_many_arguments)]
+fn paste_board_code(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<State<AppState>>,
+    mut taquin: ResMut<Taquin>,
+    mut topology: ResMut<Topology>,
+    mut empty_slots: ResMut<EmptySlots>,
+    mut rotating: ResMut<RotatingTiles>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !keyboard_input.just_released(KeyCode::V) || !is_control_pressed(&keyboard_input) {
+        return;
+    }
+    let text = match platform::paste_text() {
+        // A picture address is downloaded by `custom_image`
+        Ok(text) if is_image_url(text.trim()) => return,
+        text => text,
+    };
+    let board = match text.map_err(|error| error.to_string()).and_then(|text| BoardCode::from_code(&text).map_err(|error| error.to_string())) {
+        Ok(board) => board,
+        Err(error) => {
+            warn!("cannot paste a board code: {error}");
+            return;
+        },
+    };
+    if *state.get() == AppState::MainMenu {
+        info!(width = board.width, height = board.height, empty_count = board.empty_count, topology = ?board.topology, rotating = board.rotations.is_some(), "board code pasted in the main menu");
+        *taquin = Taquin::rect(board.width, board.height);
+        *topology = board.topology;
+        empty_slots.0 = board.empty_count;
+        *rotating = RotatingTiles(board.rotations.is_some());
+        next_state.set(AppState::SetupTiles);
+    } else if !board.fits(&taquin, *topology, *rotating) {
+        warn!(width = board.width, height = board.height, empty_count = board.empty_count, topology = ?board.topology, "board code made for another kind of board, paste it in the main menu");
+        return;
+    }
+    commands.insert_resource(PastedBoard(board));
+}
+
+/// As a shuffle would, the game starts over from the pasted board. Its rotations replace the ones the shuffle draws
+#[allow(clippy::too_many_arguments)]
+fn place_pasted_board(
+    mut commands: Commands,
+    pasted: Res<PastedBoard>,
+    markers: Res<Markers>,
+    mut taquin: ResMut<Taquin>,
+    topology: Res<Topology>,
+    rotating: Res<RotatingTiles>,
+    mut tiles_query: Query<(&TileValue, &mut TileCoordinates, &mut Transform)>,
+    mut seed: ResMut<ShuffleSeed>,
+    mut shuffle_events: EventWriter<TaquinShuffled>,
+) {
+    let PastedBoard(board) = &*pasted;
+    commands.remove_resource::<PastedBoard>();
+    if !board.fits(&taquin, *topology, *rotating) {
+        warn!(width = board.width, height = board.height, empty_count = board.empty_count, topology = ?board.topology, "board code made for another kind of board");
+        return;
+    }
+    place_tiles(&mut taquin, &markers, &mut tiles_query, &board.tiles);
+    if let Some(rotations) = &board.rotations {
+        commands.insert_resource(ArrangedRotations(rotations.clone()));
+    }
+    seed.current = board.seed;
+    info!(seed = ?board.seed, "board placed from its code");
+    shuffle_events.send_default();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{board_code::BoardCode, replay::push_code_number, tile::{TileValue, Topology}};
+
+    fn board(values: &[i16]) -> BoardCode {
+        BoardCode {
+            width: 3,
+            height: 2,
+            empty_count: 1,
+            topology: Topology::Flat,
+            seed: Some(u64::MAX - 41),
+            tiles: values.iter().map(|value| TileValue(*value)).collect(),
+            rotations: None,
+        }
+    }
+
+    #[test]
+    fn test_board_code() {
+        let board = board(&[1, 2, 3, 4, 6, 5]);
+        let code = board.to_code();
+        assert_eq!(BoardCode::from_code(&code).unwrap(), board);
+        assert_eq!(BoardCode::from_code(&format!(" {code}\n")).unwrap(), board);
+        let unseeded = BoardCode { seed: None, ..board.clone() };
+        assert_eq!(BoardCode::from_code(&unseeded.to_code()).unwrap(), unseeded);
+        assert!(BoardCode::from_code(&code[..code.len() - 1]).is_err());
+        assert!(BoardCode::from_code(&format!("{code}B")).is_err());
+    }
+
+    #[test]
+    fn test_board_kinds() {
+        // Two tiles swapped cannot be solved with one empty slot on a flat board, they can with two or on a torus
+        let swapped = board(&[2, 1, 3, 4, 5, 6]);
+        let two_empty = BoardCode { empty_count: 2, ..swapped.clone() };
+        assert_eq!(BoardCode::from_code(&two_empty.to_code()).unwrap(), two_empty);
+        let torus = BoardCode { topology: Topology::Torus, ..swapped.clone() };
+        assert_eq!(BoardCode::from_code(&torus.to_code()).unwrap(), torus);
+        let turned = BoardCode { rotations: Some(vec![1, 0, 3, 2, 0, 0]), ..board(&[1, 2, 3, 4, 6, 5]) };
+        assert_eq!(BoardCode::from_code(&turned.to_code()).unwrap(), turned);
+
+        assert!(BoardCode::from_code(&BoardCode { rotations: Some(vec![4, 0, 0, 0, 0, 0]), ..turned.clone() }.to_code()).is_err());
+        assert!(BoardCode::from_code(&BoardCode { empty_count: 6, ..swapped.clone() }.to_code()).is_err());
+        assert!(BoardCode::from_code(&BoardCode { empty_count: 0, ..swapped }.to_code()).is_err());
+    }
+
+    #[test]
+    fn test_first_version_code() {
+        let mut code = String::new();
+        for number in [1, 3, 2, 0, 1, 2, 3, 4, 6, 5] {
+            push_code_number(&mut code, number);
+        }
+        let board = BoardCode { seed: None, ..board(&[1, 2, 3, 4, 6, 5]) };
+        assert_eq!(BoardCode::from_code(&code).unwrap(), board);
+    }
+
+    #[test]
+    fn test_invalid_board_code() {
+        // Two tiles swapped cannot be solved
+        assert!(BoardCode::from_code(&board(&[2, 1, 3, 4, 5, 6]).to_code()).is_err());
+        assert!(BoardCode::from_code(&board(&[1, 1, 3, 4, 5, 6]).to_code()).is_err());
+        assert!(BoardCode::from_code(&BoardCode { width: 2, ..board(&[1, 2, 3, 4, 5, 6]) }.to_code()).is_err());
+    }
+}
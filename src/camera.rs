@@ -0,0 +1,159 @@
+use bevy::{input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel}, prelude::*};
+
+use crate::{frame::Frame, graphics::MainCamera, marker::Markers, AppState};
+
+/// Puts the camera back where it was before it was moved, with Shift as C alone cycles the color filter
+const RESET_KEY: KeyCode = KeyCode::C;
+/// Radians per pixel dragged
+const ROTATE_SPEED: f32 = 0.005;
+/// Share of the distance to the board per pixel dragged
+const PAN_SPEED: f32 = 0.0015;
+/// Share of the distance to the board per line scrolled
+const ZOOM_SPEED: f32 = 0.1;
+/// Pixels of a touchpad scroll counted as one line
+const PIXELS_PER_LINE: f32 = 100.;
+/// Radians the camera can turn left or right of the front of the board
+const MAX_YAW: f32 = 1.2;
+/// Radians above the board the camera stays within, never under the table nor right over the frame
+const MIN_PITCH: f32 = 0.05;
+const MAX_PITCH: f32 = 1.3;
+const MIN_DISTANCE: f32 = 12.;
+const MAX_DISTANCE: f32 = 90.;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CameraHome>()
+            .add_systems(Update, (reset_camera, orbit_camera).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// Where the camera was before the player moved it, and where it was left.
+/// Other modules place the camera too, such as the versus mode, a camera found elsewhere than it was left is the new home
+#[derive(Resource, Default)]
+struct CameraHome {
+    home: Option<Transform>,
+    placed: Option<Transform>,
+}
+
+/// Mouse motion of a frame, in pixels and in lines scrolled
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct OrbitInput {
+    rotate: Vec2,
+    pan: Vec2,
+    zoom: f32,
+}
+
+impl OrbitInput {
+    fn is_idle(&self) -> bool {
+        *self == OrbitInput::default()
+    }
+}
+
+/// Camera turned around `pivot` by `input.rotate`, brought closer by `input.zoom`, with the pivot moved by `input.pan`
+/// and kept inside `bounds`. The camera stays within the limits and looks at the pivot
+fn orbit(transform: &Transform, pivot: Vec3, input: OrbitInput, bounds: Rect) -> Transform {
+    let offset = transform.translation - pivot;
+    let distance = offset.length().max(MIN_DISTANCE);
+    let yaw = (offset.x.atan2(offset.z) - input.rotate.x * ROTATE_SPEED).clamp(-MAX_YAW, MAX_YAW);
+    let pitch = ((offset.y / distance).clamp(-1., 1.).asin() + input.rotate.y * ROTATE_SPEED).clamp(MIN_PITCH, MAX_PITCH);
+    let distance = (distance * (1. - input.zoom * ZOOM_SPEED)).clamp(MIN_DISTANCE, MAX_DISTANCE);
+
+    // Dragging moves the board with the mouse, the pivot the other way
+    let pan = (transform.up() * input.pan.y - transform.right() * input.pan.x) * distance * PAN_SPEED;
+    let moved = pivot + pan;
+    let pivot = moved.truncate().clamp(bounds.min, bounds.max).extend(moved.z);
+    let direction = Vec3::new(pitch.cos() * yaw.sin(), pitch.sin(), pitch.cos() * yaw.cos());
+    Transform::from_translation(pivot + direction * distance).looking_at(pivot, Vec3::Y)
+}
+
+fn reset_camera(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut home: ResMut<CameraHome>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    if !keyboard_input.just_released(RESET_KEY) || !keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        return;
+    }
+    let Some(transform) = home.home else {
+        return;
+    };
+    for mut camera in camera_query.iter_mut() {
+        *camera = transform;
+    }
+    home.placed = Some(transform);
+    info!("camera reset");
+}
+
+/// Right drag turns around the board, middle drag pans and the wheel zooms
+fn orbit_camera(
+    mouse_input: Res<Input<MouseButton>>,
+    mut motion_events: EventReader<MouseMotion>,
+    mut wheel_events: EventReader<MouseWheel>,
+    markers: Res<Markers>,
+    frame_query: Query<&GlobalTransform, With<Frame>>,
+    mut home: ResMut<CameraHome>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    let motion = motion_events.read().map(|motion| motion.delta).sum::<Vec2>();
+    let input = OrbitInput {
+        rotate: if mouse_input.pressed(MouseButton::Right) { motion } else { Vec2::ZERO },
+        pan: if mouse_input.pressed(MouseButton::Middle) { motion } else { Vec2::ZERO },
+        zoom: wheel_events.read().map(|wheel| match wheel.unit {
+            MouseScrollUnit::Line => wheel.y,
+            MouseScrollUnit::Pixel => wheel.y / PIXELS_PER_LINE,
+        }).sum(),
+    };
+    if input.is_idle() {
+        return;
+    }
+
+    // The pivot stays over the frames, the versus one included
+    let size = Vec2::new(markers.inner_width(), markers.inner_height());
+    let bounds = frame_query.iter()
+        .map(|frame| Rect::from_center_size(frame.translation().truncate(), size))
+        .reduce(|bounds, frame| bounds.union(frame))
+        .unwrap_or(Rect::from_center_size(markers.center().truncate(), size));
+    for mut transform in camera_query.iter_mut() {
+        if home.placed != Some(*transform) {
+            home.home = Some(*transform);
+        }
+        // Turns around the point it looks at, at the depth of the board
+        let forward = transform.forward();
+        let pivot = transform.translation + forward * (markers.center() - transform.translation).dot(forward);
+        *transform = orbit(&transform, pivot, input, bounds);
+        home.placed = Some(*transform);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::camera::{orbit, OrbitInput, MAX_DISTANCE, MAX_PITCH, MIN_DISTANCE};
+
+    #[test]
+    fn test_orbit_limits() {
+        let pivot = Vec3::new(0., 10., 0.);
+        let bounds = Rect::new(-8., 2., 8., 18.);
+        let camera = Transform::from_xyz(0., 30., 40.).looking_at(pivot, Vec3::Y);
+
+        // Dragged far up, the camera stops short of looking straight down and still looks at the board
+        let above = orbit(&camera, pivot, OrbitInput { rotate: Vec2::new(0., 10_000.), ..default() }, bounds);
+        let offset = above.translation - pivot;
+        assert!(((offset.y / offset.length()).asin() - MAX_PITCH).abs() < 1e-4);
+        assert!(above.forward().dot((pivot - above.translation).normalize()) > 0.9999);
+
+        let near = orbit(&camera, pivot, OrbitInput { zoom: 100., ..default() }, bounds);
+        assert!((near.translation.distance(pivot) - MIN_DISTANCE).abs() < 1e-3);
+        let far = orbit(&camera, pivot, OrbitInput { zoom: -100., ..default() }, bounds);
+        assert!((far.translation.distance(pivot) - MAX_DISTANCE).abs() < 1e-3);
+
+        // Panned away, the pivot stops at the edge of the frame
+        let panned = orbit(&camera, pivot, OrbitInput { pan: Vec2::new(-1e6, 0.), ..default() }, bounds);
+        let looked_at = panned.translation + panned.forward() * panned.translation.distance(Vec3::new(8., 10., 0.));
+        assert!(looked_at.distance(Vec3::new(8., 10., 0.)) < 1e-2);
+    }
+}
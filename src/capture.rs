@@ -0,0 +1,226 @@
+use std::path::PathBuf;
+
+use bevy::{
+    prelude::*,
+    render::view::screenshot::ScreenshotManager,
+    tasks::{block_on, AsyncComputeTaskPool, Task},
+    window::PrimaryWindow,
+};
+
+use crate::{
+    gui::TimerTick,
+    locale::Locale,
+    persistence::{self, DataPaths, PersistenceError},
+    platform, png,
+    replay::ReplayPlayback,
+    solve_gif::{GifBoard, Picture, BACKGROUND},
+    taquin::{MoveCount, ShuffleSeed, Taquin, TaquinSolved},
+    tile::TileValue,
+    tile_label::digit_glyph,
+    AppState, TaquinSprites,
+};
+
+const CAPTURE_KEY: KeyCode = KeyCode::F12;
+const CARD_WIDTH: u32 = 320;
+const CARD_MARGIN: u32 = 10;
+/// Side in pixels of a dot of the 3x5 font
+const GLYPH_SCALE: u32 = 3;
+const LINE_HEIGHT: u32 = 7 * GLYPH_SCALE;
+const TEXT_COLOR: [u8; 3] = [230, 230, 230];
+
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, capture_screen)
+            .add_systems(Update, (
+                start_result_card.run_if(not(resource_exists::<ReplayPlayback>())),
+                receive_result_card.run_if(resource_exists::<CardTask>()),
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// Result card composed and encoded off the frame
+#[derive(Resource)]
+struct CardTask(Task<Result<PathBuf, PersistenceError>>);
+
+/// What the card tells under the board
+struct CardLines {
+    moves: usize,
+    time: String,
+    seed: Option<u64>,
+}
+
+impl CardLines {
+    fn lines(&self) -> [String; 3] {
+        [
+            format!("MOVES {}", self.moves),
+            format!("TIME {}", self.time),
+            self.seed.map_or_else(|| "NO SEED".to_string(), |seed| format!("SEED {seed}")),
+        ]
+    }
+}
+
+/// Rows of a 3x5 character, most significant bit on the left, the digits being those of the tile labels
+fn glyph(character: char) -> [u8; 5] {
+    if let Some(digit) = character.to_digit(10) {
+        return digit_glyph(digit);
+    }
+    match character {
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        'S' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        _ => [0; 5],
+    }
+}
+
+/// The solved board over the moves, the time and the seed, as `width`, `height` and RGB pixels
+fn render_card(tiles: &[Vec<TileValue>], empty_count: usize, picture: &Picture, lines: &CardLines) -> (u32, u32, Vec<u8>) {
+    let (columns, rows) = (tiles.first().map_or(1, Vec::len) as u32, tiles.len() as u32);
+    let board = GifBoard { picture, columns, rows, cell: (CARD_WIDTH - 2 * CARD_MARGIN) / columns.max(rows), empty_count };
+    let (board_width, board_height) = (columns * board.cell, rows * board.cell);
+    let text_top = CARD_MARGIN + board_height + CARD_MARGIN;
+    let height = text_top + 3 * LINE_HEIGHT + CARD_MARGIN;
+
+    let mut pixels = [BACKGROUND].repeat((CARD_WIDTH * height) as usize);
+    let board_left = (CARD_WIDTH - board_width) / 2;
+    for y in 0..board_height {
+        for x in 0..board_width {
+            let value = tiles[(y / board.cell) as usize][(x / board.cell) as usize];
+            pixels[((CARD_MARGIN + y) * CARD_WIDTH + board_left + x) as usize] = board.cell_color(value, x % board.cell, y % board.cell);
+        }
+    }
+    for (line, text) in lines.lines().iter().enumerate() {
+        let top = text_top + line as u32 * LINE_HEIGHT;
+        for (index, character) in text.chars().enumerate() {
+            let left = CARD_MARGIN + index as u32 * 4 * GLYPH_SCALE;
+            for (row, bits) in glyph(character).into_iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) == 0 {
+                        continue;
+                    }
+                    for (dx, dy) in (0..GLYPH_SCALE).flat_map(|dx| (0..GLYPH_SCALE).map(move |dy| (dx, dy))) {
+                        let x = left + column * GLYPH_SCALE + dx;
+                        if x < CARD_WIDTH {
+                            pixels[((top + row as u32 * GLYPH_SCALE + dy) * CARD_WIDTH + x) as usize] = TEXT_COLOR;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    (CARD_WIDTH, height, pixels.concat())
+}
+
+/// F12, saved by the renderer once the frame is drawn
+fn capture_screen(
+    keyboard_input: Res<Input<KeyCode>>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+    paths: Res<DataPaths>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+) {
+    if !keyboard_input.just_released(CAPTURE_KEY) {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    if let Err(error) = platform::create_dir_all(&paths.captures()) {
+        error!("cannot create the captures folder: {error}");
+        return;
+    }
+    let path = paths.captures().join(format!("capture-{}.png", persistence::timestamp()));
+    match screenshot_manager.save_screenshot_to_disk(window, &path) {
+        Ok(()) => info!("capturing the screen to {}", path.display()),
+        Err(_) => warn!("a capture of the screen is already pending"),
+    }
+}
+
+/// The solve time is the last one the timer showed
+#[allow(clippy::too_many_arguments)]
+fn start_result_card(
+    mut commands: Commands,
+    mut tick_events: EventReader<TimerTick>,
+    mut solved_events: EventReader<TaquinSolved>,
+    mut elapsed: Local<f32>,
+    taquin: Res<Taquin>,
+    move_count: Res<MoveCount>,
+    seed: Res<ShuffleSeed>,
+    locale: Res<Locale>,
+    sprites: Res<TaquinSprites>,
+    images: Res<Assets<Image>>,
+    paths: Res<DataPaths>,
+) {
+    if let Some(TimerTick(seconds)) = tick_events.read().last() {
+        *elapsed = *seconds;
+    }
+    if solved_events.read().count() == 0 || !taquin.is_shuffled {
+        return;
+    }
+    let Some(picture) = sprites.images.get(sprites.selected).and_then(|handle| images.get(handle)).and_then(Picture::from_image) else {
+        warn!("cannot read the picture of the tiles for the result card");
+        return;
+    };
+    let lines = CardLines { moves: move_count.0, time: locale.format_duration(*elapsed), seed: seed.current };
+    let (tiles, empty_count) = (taquin.tiles.clone(), taquin.empty_count());
+    let path = paths.captures().join(format!("result-{}.png", persistence::timestamp()));
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let (width, height, pixels) = render_card(&tiles, empty_count, &picture, &lines);
+        let contents = png::encode(width, height, &pixels)?;
+        persistence::write(&path, contents).map(|()| path)
+    });
+    commands.insert_resource(CardTask(task));
+}
+
+fn receive_result_card(
+    mut commands: Commands,
+    mut task: ResMut<CardTask>,
+) {
+    if !task.0.is_finished() {
+        return;
+    }
+    commands.remove_resource::<CardTask>();
+    match block_on(&mut task.0) {
+        Ok(path) => info!("result card saved to {}", path.display()),
+        Err(error) => error!("cannot save the result card: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        capture::{glyph, render_card, CardLines, CARD_MARGIN, CARD_WIDTH, LINE_HEIGHT, TEXT_COLOR},
+        solve_gif::Picture,
+        tile::TileValue,
+    };
+
+    #[test]
+    fn test_render_card() {
+        let picture = Picture { width: 1, height: 1, rgba: vec![255, 0, 0, 255] };
+        let tiles = vec![vec![TileValue(1), TileValue(2)], vec![TileValue(3), TileValue(4)]];
+        let lines = CardLines { moves: 12, time: "0:05.3".to_string(), seed: Some(u64::MAX) };
+        let (width, height, pixels) = render_card(&tiles, 1, &picture, &lines);
+        let board = CARD_WIDTH - 2 * CARD_MARGIN;
+        assert_eq!((width, height), (CARD_WIDTH, board + 3 * CARD_MARGIN + 3 * LINE_HEIGHT));
+        assert_eq!(pixels.len(), (width * height * 3) as usize);
+        // Inside the first tile, off its edge
+        let index = (((CARD_MARGIN + 5) * width + CARD_MARGIN + 5) * 3) as usize;
+        assert_eq!(pixels[index..index + 3], [255, 0, 0]);
+        // The top left dot of the M of MOVES
+        let index = (((board + 2 * CARD_MARGIN) * width + CARD_MARGIN) * 3) as usize;
+        assert_eq!(pixels[index..index + 3], TEXT_COLOR);
+        // The longest seed still fits
+        assert!((lines.lines()[2].len() as u32 * 4 - 1) * 3 <= width - CARD_MARGIN);
+        assert_eq!(glyph('?'), [0; 5]);
+    }
+}
@@ -0,0 +1,265 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    graphics::{MainCamera, MainLight},
+    marker::Markers,
+    taquin::TaquinSolved,
+    tile::{GlowOverride, TileValue},
+    tile_material::{TileMaterial, TileShading},
+};
+
+/// Seconds from the solve to the end of the celebration, everything is cleaned up by then
+const CELEBRATION_SECONDS: f32 = 4.;
+const CONFETTI_COUNT: usize = 120;
+/// Upward speed range of the confetti bursting out of the top of the frame
+const CONFETTI_SPEED: f32 = 9.;
+const GRAVITY: f32 = 12.;
+/// Delay between a tile and the next one in the emissive sweep
+const SWEEP_STEP_SECONDS: f32 = 0.05;
+const SWEEP_PULSE_SECONDS: f32 = 0.4;
+const SWEEP_COLOR: Color = Color::rgb(1., 0.85, 0.4);
+/// Hue turns of the light per second
+const LIGHT_CYCLE_SPEED: f32 = 0.75;
+const SHAKE_SECONDS: f32 = 0.6;
+/// Largest offset of the camera, fading out along the shake
+const SHAKE_AMPLITUDE: f32 = 0.25;
+const SHAKE_FREQUENCY: f32 = 23.;
+
+pub struct CelebrationPlugin;
+
+impl Plugin for CelebrationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (
+            start_celebration.run_if(on_event::<TaquinSolved>()),
+            (fall_confetti, sweep_tiles, cycle_light, shake_camera, end_celebration).chain().run_if(resource_exists::<Celebration>()),
+        ).chain());
+    }
+}
+
+/// Celebration playing after a solve, removed once over
+#[derive(Resource, Debug)]
+struct Celebration {
+    elapsed: f32,
+    /// Color of the main light before it cycled, given back at the end
+    light_color: Option<Color>,
+}
+
+impl Celebration {
+    fn is_finished(&self) -> bool {
+        self.elapsed >= CELEBRATION_SECONDS
+    }
+}
+
+#[derive(Component)]
+struct Confetti {
+    velocity: Vec3,
+    /// Radians per second around its own axis
+    spin: Vec3,
+}
+
+/// Glow of a tile in the sweep, removed once the tile got its glow back
+#[derive(Component, Debug)]
+#[component(storage = "SparseSet")]
+struct SweepPulse {
+    delay: f32,
+}
+
+impl SweepPulse {
+    /// Tiles light up one after the other in the order of their values
+    fn new(value: &TileValue) -> Self {
+        Self { delay: value.0 as f32 * SWEEP_STEP_SECONDS }
+    }
+
+    /// From 0 before and after the pulse to 1 in its middle
+    fn intensity(&self, elapsed: f32) -> f32 {
+        let progress = (elapsed - self.delay) / SWEEP_PULSE_SECONDS;
+        if (0. ..1.).contains(&progress) {
+            (progress * TAU / 2.).sin()
+        } else {
+            0.
+        }
+    }
+
+    fn is_finished(&self, elapsed: f32) -> bool {
+        elapsed >= self.delay + SWEEP_PULSE_SECONDS
+    }
+}
+
+/// Camera offset `elapsed` seconds into the shake, fading out to nothing
+fn shake_offset(elapsed: f32) -> Vec3 {
+    let fade = (1. - elapsed / SHAKE_SECONDS).max(0.);
+    let phase = elapsed * SHAKE_FREQUENCY * TAU;
+    // Incommensurate frequencies so the camera does not go back and forth along a line
+    Vec3::new(phase.sin(), (phase * 1.37).sin(), 0.) * SHAKE_AMPLITUDE * fade * fade
+}
+
+/// Restarts the celebration when solved again before the end of the previous one
+#[allow(clippy::too_many_arguments)]
+fn start_celebration(
+    mut commands: Commands,
+    markers: Res<Markers>,
+    previous: Option<Res<Celebration>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    confetti_query: Query<Entity, With<Confetti>>,
+    tiles_query: Query<(Entity, &TileValue), With<Handle<TileMaterial>>>,
+    light_query: Query<&PointLight, With<MainLight>>,
+) {
+    for entity in confetti_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let light_color = previous.and_then(|previous| previous.light_color).or(light_query.iter().next().map(|light| light.color));
+    commands.insert_resource(Celebration { elapsed: 0., light_color });
+    info!("celebration started");
+
+    // Bursts out of the top edge of the frame, in front of the tiles
+    let top = markers.center() + Vec3::new(0., markers.inner_height() / 2., 1.);
+    let mesh = meshes.add(shape::Quad::new(Vec2::new(0.25, 0.15)).into());
+    let mut rng = rand::thread_rng();
+    for _ in 0..CONFETTI_COUNT {
+        let material = materials.add(StandardMaterial {
+            base_color: Color::hsl(rng.gen_range(0. ..360.), 0.9, 0.6),
+            double_sided: true,
+            cull_mode: None,
+            unlit: true,
+            ..default()
+        });
+        let x = rng.gen_range(-0.5..0.5) * markers.inner_width();
+        commands.spawn((PbrBundle {
+            mesh: mesh.clone(),
+            material,
+            transform: Transform::from_translation(top + Vec3::X * x)
+                .with_rotation(Quat::from_euler(EulerRot::XYZ, rng.gen_range(0. ..TAU), rng.gen_range(0. ..TAU), 0.)),
+            ..default()
+        }, Confetti {
+            velocity: Vec3::new(x * 0.3 + rng.gen_range(-2. ..2.), rng.gen_range(0.4..1.) * CONFETTI_SPEED, rng.gen_range(0. ..3.)),
+            spin: Vec3::new(rng.gen_range(-8. ..8.), rng.gen_range(-8. ..8.), rng.gen_range(-8. ..8.)),
+        }));
+    }
+
+    for (entity, value) in tiles_query.iter() {
+        commands.entity(entity).insert((SweepPulse::new(value), GlowOverride));
+    }
+}
+
+/// Confetti falling under the bottom of the frame is gone for good
+fn fall_confetti(
+    mut commands: Commands,
+    time: Res<Time>,
+    markers: Res<Markers>,
+    mut confetti_query: Query<(Entity, &mut Transform, &mut Confetti)>,
+) {
+    let bottom = markers.center().y - markers.inner_height() / 2. - 2.;
+    let delta = time.delta_seconds();
+    for (entity, mut transform, mut confetti) in confetti_query.iter_mut() {
+        confetti.velocity.y -= GRAVITY * delta;
+        // Flutters down rather than dropping, as paper does
+        confetti.velocity.y = confetti.velocity.y.max(-GRAVITY / 4.);
+        transform.translation += confetti.velocity * delta;
+        let spin = confetti.spin * delta;
+        transform.rotate(Quat::from_euler(EulerRot::XYZ, spin.x, spin.y, spin.z));
+        if transform.translation.y < bottom {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Lights the tiles up in turn, then gives the glow back to the selection pulse, see `tile`
+fn sweep_tiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut celebration: ResMut<Celebration>,
+    mut pulse_query: Query<(Entity, &SweepPulse, &mut TileShading)>,
+) {
+    celebration.elapsed += time.delta_seconds();
+    for (entity, pulse, mut shading) in pulse_query.iter_mut() {
+        if pulse.is_finished(celebration.elapsed) {
+            shading.glow = Color::BLACK;
+            commands.entity(entity).remove::<(SweepPulse, GlowOverride)>();
+            continue;
+        }
+        // The tiles waiting for their turn keep their glow, their meshes are left alone
+        let intensity = pulse.intensity(celebration.elapsed);
+        if intensity > 0. {
+            shading.glow = SWEEP_COLOR * intensity;
+        }
+    }
+}
+
+fn cycle_light(
+    celebration: Res<Celebration>,
+    mut light_query: Query<&mut PointLight, With<MainLight>>,
+) {
+    let hue = (celebration.elapsed * LIGHT_CYCLE_SPEED).fract() * 360.;
+    for mut light in light_query.iter_mut() {
+        light.color = Color::hsl(hue, 0.8, 0.7);
+    }
+}
+
+/// Moves the camera by the change of offset only, so it ends where it was and the other modules can move it meanwhile
+fn shake_camera(
+    celebration: Res<Celebration>,
+    mut applied_offset: Local<Vec3>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    let offset = shake_offset(celebration.elapsed);
+    if offset == *applied_offset {
+        return;
+    }
+    for mut transform in camera_query.iter_mut() {
+        let (right, up) = (transform.right(), transform.up());
+        let change = offset - *applied_offset;
+        transform.translation += right * change.x + up * change.y;
+    }
+    *applied_offset = offset;
+}
+
+fn end_celebration(
+    mut commands: Commands,
+    celebration: Res<Celebration>,
+    confetti_query: Query<Entity, With<Confetti>>,
+    mut light_query: Query<&mut PointLight, With<MainLight>>,
+) {
+    if !celebration.is_finished() {
+        return;
+    }
+    for entity in confetti_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if let Some(color) = celebration.light_color {
+        for mut light in light_query.iter_mut() {
+            light.color = color;
+        }
+    }
+    commands.remove_resource::<Celebration>();
+    info!("celebration over");
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::{celebration::{shake_offset, SweepPulse, SHAKE_SECONDS, SWEEP_PULSE_SECONDS, SWEEP_STEP_SECONDS}, tile::TileValue};
+
+    #[test]
+    fn test_sweep_pulse() {
+        let pulse = SweepPulse::new(&TileValue(4));
+        let start = 4. * SWEEP_STEP_SECONDS;
+        assert_eq!(pulse.intensity(start - 0.01), 0.);
+        assert!((pulse.intensity(start + SWEEP_PULSE_SECONDS / 2.) - 1.).abs() < 1e-5);
+        assert!(!pulse.is_finished(start + SWEEP_PULSE_SECONDS / 2.));
+        assert_eq!(pulse.intensity(start + SWEEP_PULSE_SECONDS), 0.);
+        assert!(pulse.is_finished(start + SWEEP_PULSE_SECONDS));
+    }
+
+    #[test]
+    fn test_shake_offset() {
+        assert_eq!(shake_offset(0.), Vec3::ZERO);
+        assert!(shake_offset(SHAKE_SECONDS / 10.).length() > 0.);
+        assert_eq!(shake_offset(SHAKE_SECONDS), Vec3::ZERO);
+        assert_eq!(shake_offset(SHAKE_SECONDS * 2.), Vec3::ZERO);
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use bevy::{audio::PitchBundle, prelude::*};
+
+use crate::{
+    scramble::RandomWalk,
+    sound::AudioSettings,
+    taquin::{Taquin, TileMoved},
+    tile::{TileAnimationFinished, TileCoordinates, TileValue},
+    AppState,
+};
+
+/// A5, played when a tile lands in its cell
+const LANDED_FREQUENCY: f32 = 880.;
+/// A3, played when a tile leaves its cell
+const LEFT_FREQUENCY: f32 = 220.;
+const CHIME_DURATION: Duration = Duration::from_millis(120);
+/// Relative to the other sound effects, soft enough to play on every move
+const CHIME_VOLUME: f32 = 0.15;
+
+pub struct ChimesPlugin;
+
+impl Plugin for ChimesPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ChimeSounds>()
+            .add_systems(Update, (
+                chime_on_tile_left.run_if(on_event::<TileMoved>()),
+                chime_on_tile_landed,
+            ).run_if(in_state(AppState::Running).and_then(not(resource_exists::<RandomWalk>()))));
+    }
+}
+
+#[derive(Resource)]
+struct ChimeSounds {
+    landed: Handle<Pitch>,
+    left: Handle<Pitch>,
+}
+
+impl FromWorld for ChimeSounds {
+    fn from_world(world: &mut World) -> Self {
+        let mut pitches = world.resource_mut::<Assets<Pitch>>();
+        Self {
+            landed: pitches.add(Pitch::new(LANDED_FREQUENCY, CHIME_DURATION)),
+            left: pitches.add(Pitch::new(LEFT_FREQUENCY, CHIME_DURATION)),
+        }
+    }
+}
+
+/// Cell the tile of `value` sits in on the solved board
+fn goal_coordinates(value: TileValue, size: i8) -> TileCoordinates {
+    value.goal(size)
+}
+
+fn play_chime(commands: &mut Commands, settings: &AudioSettings, pitch: &Handle<Pitch>) {
+    commands.spawn(PitchBundle {
+        source: pitch.clone(),
+        settings: PlaybackSettings { volume: settings.sfx_volume(CHIME_VOLUME), ..PlaybackSettings::DESPAWN },
+    });
+}
+
+/// The tile is still sliding out, the tone plays right away
+fn chime_on_tile_left(
+    mut commands: Commands,
+    mut tile_moved_events: EventReader<TileMoved>,
+    taquin: Res<Taquin>,
+    sounds: Res<ChimeSounds>,
+    settings: Res<AudioSettings>,
+) {
+    let left = tile_moved_events.read().any(|event| {
+        let value = taquin.tiles[event.to.j as usize][event.to.i as usize];
+        goal_coordinates(value, taquin.width) == event.from
+    });
+    if left {
+        play_chime(&mut commands, &settings, &sounds.left);
+    }
+}
+
+/// The chime waits for the end of the slide, when the tile lands in its cell
+fn chime_on_tile_landed(
+    mut commands: Commands,
+    mut finished_events: EventReader<TileAnimationFinished>,
+    tiles_query: Query<(&TileValue, &TileCoordinates)>,
+    taquin: Res<Taquin>,
+    sounds: Res<ChimeSounds>,
+    settings: Res<AudioSettings>,
+) {
+    let in_place = finished_events.read()
+        .filter_map(|TileAnimationFinished(entity)| tiles_query.get(*entity).ok())
+        .any(|(value, coordinates)| goal_coordinates(*value, taquin.width) == *coordinates);
+    if in_place {
+        play_chime(&mut commands, &settings, &sounds.landed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chimes::goal_coordinates, tile::{TileCoordinates, TileValue}};
+
+    #[test]
+    fn test_goal_coordinates() {
+        assert_eq!(goal_coordinates(TileValue(1), 3), TileCoordinates::new(0, 0));
+        assert_eq!(goal_coordinates(TileValue(6), 3), TileCoordinates::new(2, 1));
+        assert_eq!(goal_coordinates(TileValue(13), 4), TileCoordinates::new(0, 3));
+    }
+}
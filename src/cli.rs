@@ -0,0 +1,624 @@
+use std::{fs, net::{Ipv4Addr, SocketAddr}, path::PathBuf, str::FromStr};
+
+use bevy::{ecs::system::Resource, log::Level, render::color::Color};
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+
+use crate::{
+    auto_solve::AutoSolveOptions,
+    board_code::BoardCode,
+    custom_image::is_image_url,
+    game_speed::GameSpeed,
+    graphics::{AntiAliasing, GraphicsSettings},
+    locale::Locale,
+    persistence::Format,
+    remote::{RemoteEndpoint, DEFAULT_REMOTE_PORT},
+    scramble::ShuffleMode,
+    seasons::SeasonalTheme,
+    solver::Strategy,
+    taquin::MAX_SOLVER_SIZE,
+    table::{FrameTint, TableSurface},
+    theme::Theme,
+    tile_label::TileLabelStyle,
+    tile_material::ColorFilter,
+};
+
+/// Biggest board the game supports, a mega board from 12 on. Its 2500 tile values fit an `i16`
+pub const MAX_SIZE: i8 = 50;
+/// Most empty slots a board can have, see `Taquin::set_empty_count`
+pub const MAX_EMPTY_SLOTS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Mode {
+    /// Opens the game window
+    #[default]
+    Play,
+    /// Explores 3x3 and samples 4x4 boards, prints their distance distributions
+    Analyze,
+    /// Solves seeded scrambles and prints and exports a difficulty histogram per size
+    ScrambleReport,
+    /// Checks the challenge files of `--challenges` and prints which ones are valid
+    Challenges,
+}
+
+/// A 3D sliding puzzle
+#[derive(Parser, Debug)]
+#[command(version)]
+pub struct Cli {
+    #[arg(long, value_enum, default_value_t)]
+    pub mode: Mode,
+
+    /// Tiles per side, 3 by default
+    #[arg(short, long, visible_alias = "width", value_parser = clap::value_parser!(i8).range(2..=MAX_SIZE as i64))]
+    pub size: Option<i8>,
+
+    /// Rows of a rectangular board, `--size` being its columns, as many as the columns by default
+    #[arg(long, visible_alias = "height", value_parser = clap::value_parser!(i8).range(2..=MAX_SIZE as i64))]
+    pub rows: Option<i8>,
+
+    /// Seeds the first shuffle, players given the same seed get the same board
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Picture to use instead of the first one of the texture pack, cropped to the frame, a picture dropped on the window does the same
+    #[arg(long, value_parser = existing_file)]
+    pub image: Option<PathBuf>,
+
+    /// Web address of a picture downloaded at start and used as `--image` is, an address pasted in the main menu does the same
+    #[arg(long, value_parser = image_url, conflicts_with = "image")]
+    pub image_url: Option<String>,
+
+    /// Folder name of the texture pack under assets/packs, its preferred size is the default size
+    #[arg(long)]
+    pub pack: Option<String>,
+
+    /// Runs without window nor rendering
+    #[arg(long)]
+    pub headless: bool,
+
+    /// RON file with defaults for the options below, the command line wins
+    #[arg(long, value_parser = existing_file)]
+    pub config: Option<PathBuf>,
+
+    /// Plays a scripted game, prints the frame times and exports them
+    #[arg(long, conflicts_with_all = ["headless", "replay", "replay_code"])]
+    pub benchmark: bool,
+
+    /// Caps the frame rate
+    #[arg(long, value_parser = positive)]
+    pub max_fps: Option<f32>,
+
+    /// Without dust in the light nor the turning environment light
+    #[arg(long)]
+    pub no_ambience: bool,
+
+    /// Gently rocks the board
+    #[arg(long)]
+    pub frame_sway: bool,
+
+    /// Cosmetic theme, one of auto, none, winter, spring, autumn, halloween, auto follows the date
+    #[arg(long, value_parser = parse_season)]
+    pub season: Option<SeasonalTheme>,
+
+    /// Low-power graphics, always on for phones and tablets
+    #[arg(long)]
+    pub battery_saver: bool,
+
+    /// Keeps every saved file beside the executable
+    #[arg(long)]
+    pub portable: bool,
+
+    /// Replay file to watch
+    #[arg(long)]
+    pub replay: Option<PathBuf>,
+
+    /// Replay to watch as shared in a link
+    #[arg(long, conflicts_with = "replay")]
+    pub replay_code: Option<String>,
+
+    #[arg(long, value_parser = parse_level)]
+    pub log_level: Option<Level>,
+
+    /// Also writes the logs to this file
+    #[arg(long)]
+    pub log_file: Option<PathBuf>,
+
+    /// One of en, fr, de, ar, he, taken from the settings then from LANG by default
+    #[arg(long, value_parser = parse_locale)]
+    pub locale: Option<Locale>,
+
+    /// Between 0.25 and 2
+    #[arg(long, value_parser = parse_game_speed)]
+    pub game_speed: Option<GameSpeed>,
+
+    /// Numbers drawn over the tiles, one of none, digits, braille. `N` shows and hides them, `L` cycles the styles
+    #[arg(long, value_parser = parse_tile_labels)]
+    pub tile_labels: Option<TileLabelStyle>,
+
+    /// Recoloring of the puzzle picture, one of none, grayscale, sepia, hue-shift, invert
+    #[arg(long, value_parser = parse_color_filter)]
+    pub color_filter: Option<ColorFilter>,
+
+    /// One of wood, marble, and debug in debug-ui builds
+    #[arg(long, value_parser = parse_table)]
+    pub table: Option<TableSurface>,
+
+    /// Color of the frame around the board, as #rrggbb
+    #[arg(long, value_parser = parse_frame_tint)]
+    pub frame_tint: Option<FrameTint>,
+
+    /// Room around the board, one of day, night, neon, for this run only, the one picked in the main menu is saved
+    #[arg(long, value_parser = parse_theme)]
+    pub theme: Option<Theme>,
+
+    /// One of off, msaa, fxaa, taa
+    #[arg(long, value_parser = parse_anti_aliasing)]
+    pub anti_aliasing: Option<AntiAliasing>,
+
+    /// Resolution of the 3D scene relative to the window, between 0.5 and 2
+    #[arg(long, value_parser = parse_render_scale)]
+    pub render_scale: Option<f32>,
+
+    /// Texels along a side of the shadow map faces, a power of two up to 4096, 0 turns shadows off
+    #[arg(long, value_parser = parse_shadow_map_size)]
+    pub shadow_map_size: Option<usize>,
+
+    /// Reach of the light and its shadows
+    #[arg(long, value_parser = positive)]
+    pub shadow_distance: Option<f32>,
+
+    /// Lumens of the light
+    #[arg(long, value_parser = positive)]
+    pub light_intensity: Option<f32>,
+
+    /// Auto-solver preset, one of easy, medium, hard, brutal
+    #[arg(long, value_parser = parse_bot)]
+    pub bot: Option<AutoSolveOptions>,
+
+    /// One of optimal, human
+    #[arg(long, value_parser = parse_strategy)]
+    pub bot_strategy: Option<Strategy>,
+
+    /// Moves per second
+    #[arg(long, value_parser = positive)]
+    pub bot_speed: Option<f32>,
+
+    /// Seconds before the first move
+    #[arg(long, value_parser = non_negative)]
+    pub bot_delay: Option<f32>,
+
+    #[arg(long)]
+    pub bot_extra_moves: Option<usize>,
+
+    /// Transposition table entries of the optimal solver, 0 disables it
+    #[arg(long)]
+    pub solver_table_size: Option<usize>,
+
+    /// The arrows slide the tile next to the empty slot instead of moving the selection
+    #[arg(long)]
+    pub classic_controls: bool,
+
+    /// Starts playing right away instead of opening the main menu
+    #[arg(long)]
+    pub skip_menu: bool,
+
+    /// Opens the window over the whole screen
+    #[arg(long, conflicts_with = "headless")]
+    pub fullscreen: bool,
+
+    /// Starts without sound, M brings it back
+    #[arg(long)]
+    pub mute: bool,
+
+    /// Shuffles the first board as soon as it is on screen, a resumed game is left as it was
+    #[arg(long)]
+    pub start_shuffled: bool,
+
+    /// Rows and columns wrap around: a tile on an edge slides into an empty slot on the opposite one
+    #[arg(long, conflicts_with = "race_join")]
+    pub wrap: bool,
+
+    /// Empty slots of the board, from 1 to 3, the last tiles of the picture leave room for them
+    #[arg(long, value_parser = parse_empty_slots, conflicts_with = "race_join")]
+    pub empty_slots: Option<usize>,
+
+    /// Tiles are also turned by the shuffle, Q and E turn the selected one back upright
+    #[arg(long, conflicts_with = "race_join")]
+    pub rotating_tiles: bool,
+
+    /// Starts on the board of a code copied with Ctrl+C, moves played on it included
+    #[arg(long, value_parser = parse_board_code, conflicts_with_all = ["replay", "replay_code", "race_host", "race_join", "start_shuffled"])]
+    pub state: Option<BoardCode>,
+
+    /// Shows the cell the selected tile would slide into, Q toggles it in game
+    #[arg(long)]
+    pub assist: bool,
+
+    /// Shuffles by playing random moves on the board instead of drawing it from the seed, W toggles it in game
+    #[arg(long, conflicts_with = "shuffle")]
+    pub random_walk: bool,
+
+    /// How boards are shuffled: permutation draws any solvable board from the seed, random-walk plays random moves
+    #[arg(long, value_parser = parse_shuffle_mode)]
+    pub shuffle: Option<ShuffleMode>,
+
+    /// Shuffle, hint and picture buttons on the frame instead of the 2D HUD, F5 toggles it in game
+    #[arg(long)]
+    pub diegetic: bool,
+
+    /// Hosts an online race on this port of localhost and waits for a rival to join it. An address with the port, as
+    /// in 0.0.0.0:7878, opens it to other machines
+    #[arg(long, value_parser = parse_race_host, conflicts_with_all = ["race_join", "replay", "replay_code"])]
+    pub race_host: Option<SocketAddr>,
+
+    /// Joins the online race hosted at this address, as in 192.168.1.20:7878, and plays its board, wrapping, empty
+    /// slots and turning tiles included
+    #[arg(long, conflicts_with_all = ["replay", "replay_code"])]
+    pub race_join: Option<String>,
+
+    /// Lets other programs query the board and play it with JSON lines, over this port of localhost, 7879 when
+    /// none is given, or over the standard input and output with `stdin`
+    #[arg(long, num_args = 0..=1, default_missing_value = DEFAULT_REMOTE_PORT, value_parser = parse_remote, conflicts_with_all = ["replay", "replay_code"])]
+    pub remote: Option<RemoteEndpoint>,
+
+    /// Plays with a single switch: Return moves the highlight over the movable tiles, holding it slides one
+    #[arg(long)]
+    pub one_switch: bool,
+
+    /// Random boards sampled by the analyze mode
+    #[arg(long, default_value_t = 100)]
+    pub samples: usize,
+
+    /// Scrambles per size of the scramble report
+    #[arg(long, default_value_t = 1000)]
+    pub scrambles: usize,
+
+    /// Sizes of the scramble report
+    #[arg(long, value_delimiter = ',', default_values_t = [3, 4], value_parser = clap::value_parser!(i8).range(2..=MAX_SOLVER_SIZE as i64))]
+    pub sizes: Vec<i8>,
+
+    /// Folder checked by the challenges mode, the one the board editor saves to by default
+    #[arg(long, value_parser = existing_file)]
+    pub challenges: Option<PathBuf>,
+}
+
+/// How the game starts, read by the plugins concerned
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LaunchOptions {
+    /// Borderless over the whole screen, see `main`
+    pub fullscreen: bool,
+    /// See `sound`
+    pub mute: bool,
+    /// See `taquin`
+    pub start_shuffled: bool,
+}
+
+/// Defaults read from `--config`
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct Config {
+    size: Option<i8>,
+    rows: Option<i8>,
+    seed: Option<u64>,
+    image: Option<PathBuf>,
+    image_url: Option<String>,
+    pack: Option<String>,
+    log_level: Option<String>,
+    locale: Option<String>,
+    game_speed: Option<f32>,
+    tile_labels: Option<String>,
+    color_filter: Option<String>,
+    table: Option<String>,
+    frame_tint: Option<String>,
+    theme: Option<String>,
+    anti_aliasing: Option<String>,
+    render_scale: Option<f32>,
+    shadow_map_size: Option<usize>,
+    shadow_distance: Option<f32>,
+    light_intensity: Option<f32>,
+    season: Option<String>,
+    bot: Option<String>,
+    shuffle: Option<String>,
+    fullscreen: Option<bool>,
+    mute: Option<bool>,
+    start_shuffled: Option<bool>,
+    wrap: Option<bool>,
+    rotating_tiles: Option<bool>,
+    empty_slots: Option<usize>,
+}
+
+impl Cli {
+    /// Fills the options missing from the command line with the ones of the config file
+    pub fn apply_config(&mut self) -> Result<(), String> {
+        let Some(path) = &self.config else {
+            return Ok(());
+        };
+        let contents = fs::read_to_string(path).map_err(|error| format!("{}: {error}", path.display()))?;
+        let config: Config = Format::Ron.parse(&contents).map_err(|error| format!("{}: {error}", path.display()))?;
+
+        if let Some(size) = config.size.filter(|_| self.size.is_none()) {
+            if !(2..=MAX_SIZE).contains(&size) {
+                return Err(format!("size {size} is not between 2 and {MAX_SIZE}"));
+            }
+            self.size = Some(size);
+        }
+        if let Some(rows) = config.rows.filter(|_| self.rows.is_none()) {
+            if !(2..=MAX_SIZE).contains(&rows) {
+                return Err(format!("rows {rows} is not between 2 and {MAX_SIZE}"));
+            }
+            self.rows = Some(rows);
+        }
+        self.seed = self.seed.or(config.seed);
+        // A picture given on the command line, by path or address, wins over the config one
+        if self.image.is_none() && self.image_url.is_none() {
+            self.image = config.image.map(|image| existing_file(&image.to_string_lossy())).transpose()?;
+        }
+        if self.image.is_none() && self.image_url.is_none() {
+            self.image_url = config.image_url.as_deref().map(image_url).transpose()?;
+        }
+        self.pack = self.pack.take().or(config.pack);
+        if self.log_level.is_none() {
+            self.log_level = config.log_level.as_deref().map(parse_level).transpose()?;
+        }
+        if self.locale.is_none() {
+            self.locale = config.locale.as_deref().map(parse_locale).transpose()?;
+        }
+        if self.game_speed.is_none() {
+            self.game_speed = config.game_speed.map(|speed| parse_game_speed(&speed.to_string())).transpose()?;
+        }
+        if self.tile_labels.is_none() {
+            self.tile_labels = config.tile_labels.as_deref().map(parse_tile_labels).transpose()?;
+        }
+        if self.color_filter.is_none() {
+            self.color_filter = config.color_filter.as_deref().map(parse_color_filter).transpose()?;
+        }
+        if self.table.is_none() {
+            self.table = config.table.as_deref().map(parse_table).transpose()?;
+        }
+        if self.frame_tint.is_none() {
+            self.frame_tint = config.frame_tint.as_deref().map(parse_frame_tint).transpose()?;
+        }
+        if self.theme.is_none() {
+            self.theme = config.theme.as_deref().map(parse_theme).transpose()?;
+        }
+        if self.anti_aliasing.is_none() {
+            self.anti_aliasing = config.anti_aliasing.as_deref().map(parse_anti_aliasing).transpose()?;
+        }
+        if self.render_scale.is_none() {
+            self.render_scale = config.render_scale.map(|scale| parse_render_scale(&scale.to_string())).transpose()?;
+        }
+        if self.shadow_map_size.is_none() {
+            self.shadow_map_size = config.shadow_map_size.map(|size| parse_shadow_map_size(&size.to_string())).transpose()?;
+        }
+        if self.shadow_distance.is_none() {
+            self.shadow_distance = config.shadow_distance.map(|distance| positive(&distance.to_string())).transpose()?;
+        }
+        if self.light_intensity.is_none() {
+            self.light_intensity = config.light_intensity.map(|intensity| positive(&intensity.to_string())).transpose()?;
+        }
+        if self.season.is_none() {
+            self.season = config.season.as_deref().map(parse_season).transpose()?;
+        }
+        if self.bot.is_none() {
+            self.bot = config.bot.as_deref().map(parse_bot).transpose()?;
+        }
+        if self.empty_slots.is_none() && self.race_join.is_none() {
+            self.empty_slots = config.empty_slots.map(|count| parse_empty_slots(&count.to_string())).transpose()?;
+        }
+        if self.shuffle.is_none() && !self.random_walk {
+            self.shuffle = config.shuffle.as_deref().map(parse_shuffle_mode).transpose()?;
+        }
+        // Flags given on the command line stay on, the config file can only turn the others on
+        self.fullscreen |= config.fullscreen.unwrap_or(false) && !self.headless;
+        self.mute |= config.mute.unwrap_or(false);
+        self.start_shuffled |= config.start_shuffled.unwrap_or(false);
+        self.wrap |= config.wrap.unwrap_or(false) && self.race_join.is_none();
+        self.rotating_tiles |= config.rotating_tiles.unwrap_or(false) && self.race_join.is_none();
+        Ok(())
+    }
+
+    pub fn shuffle_mode(&self) -> ShuffleMode {
+        if self.random_walk {
+            ShuffleMode::RandomWalk
+        } else {
+            self.shuffle.unwrap_or_default()
+        }
+    }
+
+    pub fn launch_options(&self) -> LaunchOptions {
+        LaunchOptions { fullscreen: self.fullscreen, mute: self.mute, start_shuffled: self.start_shuffled }
+    }
+
+    pub fn graphics_settings(&self) -> GraphicsSettings {
+        let mut settings = GraphicsSettings::default();
+        if let Some(anti_aliasing) = self.anti_aliasing {
+            settings.anti_aliasing = anti_aliasing;
+        }
+        if let Some(render_scale) = self.render_scale {
+            settings.render_scale = render_scale;
+        }
+        match self.shadow_map_size {
+            Some(0) => settings.shadows = false,
+            Some(size) => settings.shadow_map_size = size,
+            None => {},
+        }
+        if let Some(distance) = self.shadow_distance {
+            settings.shadow_distance = distance;
+        }
+        if let Some(intensity) = self.light_intensity {
+            settings.light_intensity = intensity;
+        }
+        settings
+    }
+
+    /// Auto-solver preset with the individual overrides applied
+    pub fn auto_solve_options(&self) -> AutoSolveOptions {
+        let mut options = self.bot.unwrap_or_default();
+        if let Some(strategy) = self.bot_strategy {
+            options.strategy = strategy;
+        }
+        if let Some(speed) = self.bot_speed {
+            options.moves_per_second = speed;
+        }
+        if let Some(delay) = self.bot_delay {
+            options.start_delay = delay;
+        }
+        if let Some(extra_moves) = self.bot_extra_moves {
+            options.extra_moves = extra_moves;
+        }
+        options
+    }
+}
+
+fn existing_file(path: &str) -> Result<PathBuf, String> {
+    fs::canonicalize(path).map_err(|error| format!("{path}: {error}"))
+}
+
+fn image_url(url: &str) -> Result<String, String> {
+    if is_image_url(url) {
+        Ok(url.to_string())
+    } else {
+        Err(format!("{url} is not an http or https address"))
+    }
+}
+
+fn parse_level(level: &str) -> Result<Level, String> {
+    Level::from_str(level).map_err(|_| format!("unknown log level {level}, expected one of trace, debug, info, warn, error"))
+}
+
+fn parse_remote(name: &str) -> Result<RemoteEndpoint, String> {
+    RemoteEndpoint::from_name(name).ok_or_else(|| format!("invalid remote {name}, expected a port or stdin"))
+}
+
+/// A port alone is only reachable from this machine
+fn parse_race_host(address: &str) -> Result<SocketAddr, String> {
+    match address.parse::<u16>() {
+        Ok(port) => Ok(SocketAddr::from((Ipv4Addr::LOCALHOST, port))),
+        Err(_) => address.parse().map_err(|_| format!("invalid race address {address}, expected a port or an address with its port")),
+    }
+}
+
+fn parse_locale(code: &str) -> Result<Locale, String> {
+    Locale::from_code(code).ok_or_else(|| format!("unsupported locale {code}, expected one of en, fr, de, ar, he"))
+}
+
+fn parse_game_speed(speed: &str) -> Result<GameSpeed, String> {
+    match speed.parse::<f32>() {
+        Ok(speed) if (0.25..=2.).contains(&speed) => Ok(GameSpeed::new(speed)),
+        _ => Err(format!("invalid game speed {speed}, expected a number between 0.25 and 2")),
+    }
+}
+
+fn parse_tile_labels(name: &str) -> Result<TileLabelStyle, String> {
+    TileLabelStyle::from_name(name).ok_or_else(|| format!("unknown tile label style {name}, expected one of none, digits, braille"))
+}
+
+fn parse_color_filter(name: &str) -> Result<ColorFilter, String> {
+    ColorFilter::from_name(name).ok_or_else(|| format!("unknown color filter {name}, expected one of none, grayscale, sepia, hue-shift, invert"))
+}
+
+fn parse_table(name: &str) -> Result<TableSurface, String> {
+    TableSurface::from_name(name).ok_or_else(|| format!("unknown table {name}, expected one of wood, marble, and debug in debug-ui builds"))
+}
+
+fn parse_frame_tint(color: &str) -> Result<FrameTint, String> {
+    Color::hex(color).map(FrameTint).map_err(|_| format!("invalid frame tint {color}, expected a color like #8b5a2b"))
+}
+
+fn parse_theme(name: &str) -> Result<Theme, String> {
+    Theme::from_name(name).ok_or_else(|| format!("unknown theme {name}, expected one of day, night, neon"))
+}
+
+fn parse_anti_aliasing(name: &str) -> Result<AntiAliasing, String> {
+    AntiAliasing::from_name(name).ok_or_else(|| format!("unknown anti-aliasing {name}, expected one of off, msaa, fxaa, taa"))
+}
+
+fn parse_render_scale(scale: &str) -> Result<f32, String> {
+    match scale.parse::<f32>() {
+        Ok(scale) if (0.5..=2.).contains(&scale) => Ok(scale),
+        _ => Err(format!("invalid render scale {scale}, expected a number between 0.5 and 2")),
+    }
+}
+
+fn parse_shadow_map_size(size: &str) -> Result<usize, String> {
+    match size.parse::<usize>() {
+        Ok(size) if size == 0 || (size.is_power_of_two() && size <= 4096) => Ok(size),
+        _ => Err(format!("invalid shadow map size {size}, expected 0 or a power of two up to 4096")),
+    }
+}
+
+fn parse_season(name: &str) -> Result<SeasonalTheme, String> {
+    SeasonalTheme::from_name(name).ok_or_else(|| format!("unknown season {name}, expected one of auto, none, winter, spring, autumn, halloween"))
+}
+
+fn parse_bot(name: &str) -> Result<AutoSolveOptions, String> {
+    AutoSolveOptions::preset(name).ok_or_else(|| format!("unknown bot preset {name}, expected one of easy, medium, hard, brutal"))
+}
+
+fn parse_shuffle_mode(name: &str) -> Result<ShuffleMode, String> {
+    ShuffleMode::from_name(name).ok_or_else(|| format!("unknown shuffle {name}, expected permutation or random-walk"))
+}
+
+fn parse_strategy(name: &str) -> Result<Strategy, String> {
+    Strategy::from_name(name).ok_or_else(|| format!("unknown solver strategy {name}, expected one of optimal, human"))
+}
+
+fn parse_board_code(code: &str) -> Result<BoardCode, String> {
+    BoardCode::from_code(code).map_err(|error| error.to_string())
+}
+
+fn parse_empty_slots(count: &str) -> Result<usize, String> {
+    count.parse::<usize>().ok().filter(|count| (1..=MAX_EMPTY_SLOTS).contains(count)).ok_or_else(|| format!("{count} empty slots, not between 1 and {MAX_EMPTY_SLOTS}"))
+}
+
+fn positive(value: &str) -> Result<f32, String> {
+    value.parse::<f32>().ok().filter(|value| *value > 0.).ok_or_else(|| format!("{value} is not a positive number"))
+}
+
+fn non_negative(value: &str) -> Result<f32, String> {
+    value.parse::<f32>().ok().filter(|value| *value >= 0.).ok_or_else(|| format!("{value} is not a positive number or zero"))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, Parser};
+
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use crate::{cli::{Cli, LaunchOptions, Mode}, remote::RemoteEndpoint};
+
+    #[test]
+    fn test_cli_validation() {
+        Cli::command().debug_assert();
+        let cli = Cli::try_parse_from(["taquin", "--size", "4", "--mode", "scramble-report", "--sizes", "3,5"]).unwrap();
+        assert_eq!((cli.size, cli.mode, cli.sizes), (Some(4), Mode::ScrambleReport, vec![3, 5]));
+        assert!(Cli::try_parse_from(["taquin", "--size", "51"]).is_err());
+        assert!(Cli::try_parse_from(["taquin", "--size", "three"]).is_err());
+        assert_eq!(Cli::try_parse_from(["taquin", "--size", "4", "--rows", "3"]).unwrap().rows, Some(3));
+        assert!(Cli::try_parse_from(["taquin", "--rows", "1"]).is_err());
+        assert!(Cli::try_parse_from(["taquin", "--bot", "impossible"]).is_err());
+        assert!(Cli::try_parse_from(["taquin", "--game-speed", "3"]).is_err());
+        assert!(Cli::try_parse_from(["taquin", "--table", "marble", "--frame-tint", "#8b5a2b"]).is_ok());
+        assert!(Cli::try_parse_from(["taquin", "--frame-tint", "brown"]).is_err());
+        assert!(Cli::try_parse_from(["taquin", "--theme", "neon"]).is_ok());
+        let cli = Cli::try_parse_from(["taquin", "--width", "5", "--height", "4", "--mute", "--start-shuffled"]).unwrap();
+        assert_eq!((cli.size, cli.rows), (Some(5), Some(4)));
+        assert_eq!(cli.launch_options(), LaunchOptions { fullscreen: false, mute: true, start_shuffled: true });
+        assert!(Cli::try_parse_from(["taquin", "--fullscreen", "--headless"]).is_err());
+        assert!(Cli::try_parse_from(["taquin", "--theme", "dusk"]).is_err());
+        assert!(Cli::try_parse_from(["taquin", "--state", "BDC"]).is_err());
+        assert!(Cli::try_parse_from(["taquin", "--wrap"]).unwrap().wrap);
+        assert!(Cli::try_parse_from(["taquin", "--rotating-tiles"]).unwrap().rotating_tiles);
+        assert_eq!(Cli::try_parse_from(["taquin", "--empty-slots", "2"]).unwrap().empty_slots, Some(2));
+        assert!(Cli::try_parse_from(["taquin", "--empty-slots", "4"]).is_err());
+        assert!(Cli::try_parse_from(["taquin", "--wrap", "--race-join", "192.168.1.20:7878"]).is_err());
+        assert_eq!(Cli::try_parse_from(["taquin", "--wrap", "--race-host", "7878"]).unwrap().race_host, Some(SocketAddr::from((Ipv4Addr::LOCALHOST, 7878))));
+        assert_eq!(Cli::try_parse_from(["taquin", "--race-host", "0.0.0.0:7878"]).unwrap().race_host, Some(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 7878))));
+        assert!(Cli::try_parse_from(["taquin", "--race-host", "everywhere"]).is_err());
+        assert_eq!(Cli::try_parse_from(["taquin", "--remote"]).unwrap().remote, Some(RemoteEndpoint::Port(7879)));
+        assert_eq!(Cli::try_parse_from(["taquin", "--remote", "stdin"]).unwrap().remote, Some(RemoteEndpoint::Stdio));
+        assert!(Cli::try_parse_from(["taquin", "--remote", "everywhere"]).is_err());
+        assert!(Cli::try_parse_from(["taquin", "--image-url", "https://example.com/cat.png"]).unwrap().image_url.is_some());
+        assert!(Cli::try_parse_from(["taquin", "--image-url", "cat.png"]).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use std::{fmt, io};
+
+use bevy::{prelude::*, tasks::{block_on, IoTaskPool, Task}};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::Achievements,
+    cosmetics::Cosmetics,
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    profile::{LocalProfile, ProfileBundle},
+    records::BestRecords,
+    settings::{Settings, SettingsImported},
+    stats::{GameHistory, GameRecorded, Stats},
+};
+
+/// Merges tried before giving up when other devices keep writing the remote profile in between
+const MAX_ATTEMPTS: usize = 3;
+
+/// Keeps the profile bundle in sync with a remote copy, when `cloud_sync.ron` gives its url
+pub struct CloudSyncPlugin;
+
+impl Plugin for CloudSyncPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Startup, load_cloud_sync)
+            .add_systems(Update, (
+                request_sync.run_if(on_event::<GameRecorded>()),
+                start_sync.run_if(resource_exists::<SyncRequested>().and_then(not(resource_exists::<SyncTask>()))),
+                receive_sync.run_if(resource_exists::<SyncTask>()),
+            ).chain().run_if(resource_exists::<CloudSync>()));
+    }
+}
+
+/// Remote copy of the profile, a WebDAV file or an S3 compatible object read with GET and written with PUT
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+struct CloudSync {
+    #[serde(default)]
+    version: u32,
+    url: String,
+    /// Sent as is in the `Authorization` header, as `Basic dXNlcjpwYXNz`, none for a presigned url
+    #[serde(default)]
+    authorization: Option<String>,
+}
+
+impl Versioned for CloudSync {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+/// Set at startup and after each game, the next sync starts once the running one is over
+#[derive(Resource)]
+struct SyncRequested;
+
+#[derive(Resource)]
+struct SyncTask(Task<Result<ProfileBundle, SyncError>>);
+
+#[derive(Debug)]
+enum SyncError {
+    Http(Box<ureq::Error>),
+    Io(io::Error),
+    Persistence(PersistenceError),
+    /// The remote profile changed during every attempt
+    Conflict,
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::Http(error) => write!(f, "request failed: {error}"),
+            SyncError::Io(error) => write!(f, "cannot read the response: {error}"),
+            SyncError::Persistence(error) => write!(f, "{error}"),
+            SyncError::Conflict => write!(f, "the remote profile keeps changing"),
+        }
+    }
+}
+
+impl From<ureq::Error> for SyncError {
+    fn from(error: ureq::Error) -> Self {
+        SyncError::Http(Box::new(error))
+    }
+}
+
+impl From<io::Error> for SyncError {
+    fn from(error: io::Error) -> Self {
+        SyncError::Io(error)
+    }
+}
+
+impl From<PersistenceError> for SyncError {
+    fn from(error: PersistenceError) -> Self {
+        SyncError::Persistence(error)
+    }
+}
+
+impl CloudSync {
+    fn request(&self, method: &str) -> ureq::Request {
+        let request = ureq::request(method, &self.url);
+        match &self.authorization {
+            Some(authorization) => request.set("Authorization", authorization),
+            None => request,
+        }
+    }
+
+    /// Remote profile and its ETag, none before the first upload
+    fn fetch(&self) -> Result<Option<(ProfileBundle, Option<String>)>, SyncError> {
+        match self.request("GET").call() {
+            Ok(response) => {
+                let etag = response.header("ETag").map(str::to_string);
+                let bundle = persistence::parse_versioned(&response.into_string()?, Format::Json)?;
+                Ok(Some((bundle, etag)))
+            },
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Merges the remote profile into `local` and uploads the result. The upload only succeeds if the remote
+    /// profile is still the one merged, otherwise it is merged again. Servers without conditional writes overwrite it
+    fn sync(&self, local: ProfileBundle) -> Result<ProfileBundle, SyncError> {
+        for _ in 0..MAX_ATTEMPTS {
+            let mut merged = local.clone();
+            let etag = match self.fetch()? {
+                Some((remote, etag)) => {
+                    merged.merge(remote);
+                    etag
+                },
+                None => None,
+            };
+            let request = self.request("PUT").set("Content-Type", "application/json");
+            let request = match &etag {
+                Some(etag) => request.set("If-Match", etag),
+                None => request.set("If-None-Match", "*"),
+            };
+            match request.send_string(&Format::Json.serialize(&merged)?) {
+                Ok(_) => return Ok(merged),
+                Err(ureq::Error::Status(412, _)) => continue,
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Err(SyncError::Conflict)
+    }
+}
+
+fn load_cloud_sync(
+    mut commands: Commands,
+    paths: Res<DataPaths>,
+) {
+    let path = paths.cloud_sync();
+    if !persistence::exists(&path) {
+        return;
+    }
+    match persistence::load_versioned::<CloudSync>(&path, Format::Ron) {
+        Ok(cloud_sync) => {
+            info!(url = cloud_sync.url, "cloud sync enabled");
+            commands.insert_resource(cloud_sync);
+            commands.insert_resource(SyncRequested);
+        },
+        Err(error) => error!("cannot load the cloud sync settings, the profile is not synced: {error}"),
+    }
+}
+
+fn request_sync(
+    mut commands: Commands,
+) {
+    commands.insert_resource(SyncRequested);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_sync(
+    mut commands: Commands,
+    cloud_sync: Res<CloudSync>,
+    history: Res<GameHistory>,
+    cosmetics: Res<Cosmetics>,
+    records: Res<BestRecords>,
+    settings: Res<Settings>,
+    stats: Res<Stats>,
+    achievements: Res<Achievements>,
+) {
+    commands.remove_resource::<SyncRequested>();
+    let cloud_sync = cloud_sync.clone();
+    let local = ProfileBundle::new(&history, &cosmetics, &records, &settings, &stats, &achievements);
+    commands.insert_resource(SyncTask(IoTaskPool::get().spawn(async move { cloud_sync.sync(local) })));
+}
+
+/// Brings in what the other devices added, the local profile already went into the uploaded one
+#[allow(clippy::too_many_arguments)]
+fn receive_sync(
+    mut commands: Commands,
+    mut task: ResMut<SyncTask>,
+    mut history: ResMut<GameHistory>,
+    mut cosmetics: ResMut<Cosmetics>,
+    mut records: ResMut<BestRecords>,
+    mut settings: ResMut<Settings>,
+    mut stats: ResMut<Stats>,
+    mut achievements: ResMut<Achievements>,
+    paths: Res<DataPaths>,
+    mut imported_events: EventWriter<SettingsImported>,
+) {
+    if !task.0.is_finished() {
+        return;
+    }
+    commands.remove_resource::<SyncTask>();
+    let merged = match block_on(&mut task.0) {
+        Ok(merged) => merged,
+        Err(error) => {
+            warn!("cannot sync the profile: {error}");
+            return;
+        },
+    };
+    let local = LocalProfile {
+        history: &mut history,
+        cosmetics: &mut cosmetics,
+        records: &mut records,
+        // Saved with the date of the settings merged, `save_settings` would date them now
+        settings: settings.bypass_change_detection(),
+        stats: &mut stats,
+        achievements: &mut achievements,
+    };
+    match merged.merge_into(local, &paths) {
+        Ok(()) => info!("profile synced"),
+        Err(error) => error!("cannot save the synced profile: {error}"),
+    }
+    imported_events.send_default();
+}
@@ -0,0 +1,87 @@
+use std::ops::Add;
+
+#[cfg(feature = "bevy")]
+use bevy::prelude::{Component, Resource};
+use serde::{Deserialize, Serialize};
+
+/// How the edges of a board behave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Resource))]
+pub enum Topology {
+    #[default]
+    Flat,
+    /// Rows and columns wrap around, a tile on an edge slides into an empty slot on the opposite edge
+    Torus,
+}
+
+/// Side of a cell a selection moves or a tile is pushed towards, as the arrows of the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
+
+    /// Step to the next cell that way, `j` growing downwards
+    pub fn offset(self) -> (i8, i8) {
+        match self {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+        }
+    }
+}
+
+/// Cell of a board, `i` counting columns from the left and `j` rows from the top
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
+pub struct TileCoordinates {
+    pub i: i8,
+    pub j: i8
+}
+
+impl TileCoordinates {
+    pub fn new(i: i8, j:i8) -> Self {
+        Self {i, j}
+    }
+
+    pub fn is_neighbour_of(&self, other: &TileCoordinates)-> bool {
+        self.get_neighbours().contains(other)
+    }
+
+    /// As `is_neighbour_of`, through the edges of a `width` by `height` board as well on a torus
+    pub fn is_neighbour_on(&self, other: &TileCoordinates, topology: Topology, width: i8, height: i8) -> bool {
+        match topology {
+            Topology::Flat => self.is_neighbour_of(other),
+            Topology::Torus => {
+                let distance = |a: i8, b: i8, size: i8| {
+                    let distance = a.abs_diff(b) as i8;
+                    distance.min(size - distance)
+                };
+                distance(self.i, other.i, width) + distance(self.j, other.j, height) == 1
+            },
+        }
+    }
+
+    fn get_neighbours(self) -> Vec<TileCoordinates>
+    {
+        vec![self + (1, 0), self + (0, 1), self + (-1, 0), self + (0, -1)]
+    }
+}
+
+impl Add<(i8, i8)> for TileCoordinates {
+    type Output = Self;
+
+    fn add(self, other: (i8, i8)) -> Self {
+        Self {
+            i: self.i + other.0,
+            j: self.j + other.1,
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use bevy::prelude::*;
+use rand::{seq::IteratorRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    stats::GameHistory,
+    table::FrameTint,
+    taquin::TaquinSolved,
+    tile::{SelectionColor, TileValue},
+};
+
+const SCREEN_KEY: KeyCode = KeyCode::U;
+const SPARKLE_COUNT: usize = 60;
+const SPARKLE_SECONDS: f32 = 1.5;
+
+pub struct CosmeticsPlugin;
+
+impl Plugin for CosmeticsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Cosmetics>()
+            .add_systems(Update, (
+                unlock_cosmetics.run_if(resource_changed::<GameHistory>()),
+                (toggle_unlocks_screen, navigate_unlocks_screen.run_if(resource_exists::<UnlocksScreen>())).chain(),
+                apply_cosmetics.run_if(resource_changed::<Cosmetics>()),
+                update_unlocks_screen.run_if(resource_changed::<Cosmetics>().or_else(resource_exists_and_changed::<UnlocksScreen>())),
+                spawn_sparkles.run_if(on_event::<TaquinSolved>()),
+                animate_sparkles,
+            ).chain());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Frame,
+    Selection,
+    Effect,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cosmetic {
+    SilverFrame,
+    GoldFrame,
+    CyanSelection,
+    GreenSelection,
+    Sparkles,
+}
+
+impl Cosmetic {
+    const ALL: [Cosmetic; 5] = [Cosmetic::SilverFrame, Cosmetic::GoldFrame, Cosmetic::CyanSelection, Cosmetic::GreenSelection, Cosmetic::Sparkles];
+
+    fn slot(self) -> Slot {
+        match self {
+            Cosmetic::SilverFrame | Cosmetic::GoldFrame => Slot::Frame,
+            Cosmetic::CyanSelection | Cosmetic::GreenSelection => Slot::Selection,
+            Cosmetic::Sparkles => Slot::Effect,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Cosmetic::SilverFrame => "silver frame",
+            Cosmetic::GoldFrame => "gold frame",
+            Cosmetic::CyanSelection => "cyan selection",
+            Cosmetic::GreenSelection => "green selection",
+            Cosmetic::Sparkles => "sparkles on solve",
+        }
+    }
+
+    fn requirement(self) -> Requirement {
+        match self {
+            Cosmetic::SilverFrame => Requirement::Solves(10),
+            Cosmetic::GoldFrame => Requirement::Solves(50),
+            Cosmetic::CyanSelection => Requirement::SolveSize(4),
+            Cosmetic::GreenSelection => Requirement::SolveUnder(60.),
+            Cosmetic::Sparkles => Requirement::Solves(25),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Requirement {
+    Solves(usize),
+    /// A board of this size or bigger
+    SolveSize(i8),
+    /// Seconds
+    SolveUnder(f32),
+}
+
+impl Requirement {
+    fn is_met(self, history: &GameHistory) -> bool {
+        match self {
+            Requirement::Solves(count) => history.games.len() >= count,
+            Requirement::SolveSize(size) => history.games.iter().any(|game| game.size >= size),
+            Requirement::SolveUnder(time) => history.games.iter().any(|game| game.time < time),
+        }
+    }
+
+    fn description(self) -> String {
+        match self {
+            Requirement::Solves(count) => format!("solve {count} puzzles"),
+            Requirement::SolveSize(size) => format!("solve a {size}x{size} puzzle"),
+            Requirement::SolveUnder(time) => format!("solve a puzzle in under {time} seconds"),
+        }
+    }
+}
+
+/// Cosmetics earned by the player and the ones worn, at most one per slot
+#[derive(Resource, Serialize, Deserialize, Debug)]
+pub struct Cosmetics {
+    pub version: u32,
+    pub unlocked: Vec<Cosmetic>,
+    pub equipped: Vec<Cosmetic>,
+}
+
+impl Versioned for Cosmetics {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl FromWorld for Cosmetics {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().cosmetics();
+        let empty = Cosmetics { version: Self::VERSION, unlocked: Vec::new(), equipped: Vec::new() };
+        if !persistence::exists(&path) {
+            return empty;
+        }
+        persistence::load_versioned(path, Format::Ron).unwrap_or_else(|error| {
+            error!("cannot load cosmetics: {error}");
+            empty
+        })
+    }
+}
+
+impl Cosmetics {
+    pub fn save(&self, paths: &DataPaths) -> Result<(), PersistenceError> {
+        persistence::save_versioned(paths.cosmetics(), self, Format::Ron)
+    }
+
+    /// Adds what is not unlocked yet, returns whether anything was
+    pub fn unlock(&mut self, cosmetics: impl IntoIterator<Item = Cosmetic>) -> bool {
+        let count = self.unlocked.len();
+        for cosmetic in cosmetics {
+            if !self.unlocked.contains(&cosmetic) {
+                info!(cosmetic = cosmetic.name(), "cosmetic unlocked");
+                self.unlocked.push(cosmetic);
+            }
+        }
+        self.unlocked.len() > count
+    }
+
+    /// Wears an unlocked cosmetic in place of the one in its slot, or takes it off if already worn
+    fn toggle(&mut self, cosmetic: Cosmetic) {
+        if !self.unlocked.contains(&cosmetic) {
+            return;
+        }
+        let worn = self.equipped.contains(&cosmetic);
+        self.equipped.retain(|equipped| equipped.slot() != cosmetic.slot());
+        if !worn {
+            self.equipped.push(cosmetic);
+        }
+    }
+
+    fn equipped(&self, slot: Slot) -> Option<Cosmetic> {
+        self.equipped.iter().copied().find(|cosmetic| cosmetic.slot() == slot)
+    }
+}
+
+/// Lists every cosmetic, `Tab` moves the highlight and `Return` wears or takes off the highlighted one
+#[derive(Resource)]
+struct UnlocksScreen {
+    entity: Entity,
+    highlighted: usize,
+}
+
+#[derive(Component)]
+struct UnlocksText;
+
+#[derive(Component)]
+struct Sparkle {
+    velocity: Vec3,
+    timer: Timer,
+}
+
+fn unlock_cosmetics(
+    history: Res<GameHistory>,
+    paths: Res<DataPaths>,
+    mut cosmetics: ResMut<Cosmetics>,
+) {
+    let earned = Cosmetic::ALL.into_iter()
+        .filter(|cosmetic| !cosmetics.unlocked.contains(cosmetic) && cosmetic.requirement().is_met(&history))
+        .collect::<Vec<Cosmetic>>();
+    // Only touched when something new is unlocked, so that its change detection means it
+    if earned.is_empty() {
+        return;
+    }
+    cosmetics.unlock(earned);
+    if let Err(error) = cosmetics.save(&paths) {
+        error!("cannot save cosmetics: {error}");
+    }
+}
+
+fn toggle_unlocks_screen(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    screen: Option<Res<UnlocksScreen>>,
+) {
+    if !keyboard_input.just_released(SCREEN_KEY) {
+        return;
+    }
+    if let Some(screen) = screen {
+        commands.entity(screen.entity).despawn_recursive();
+        commands.remove_resource::<UnlocksScreen>();
+        return;
+    }
+    let entity = commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.),
+            top: Val::Px(120.),
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(5),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), UnlocksText));
+    }).id();
+    commands.insert_resource(UnlocksScreen { entity, highlighted: 0 });
+}
+
+fn navigate_unlocks_screen(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut screen: ResMut<UnlocksScreen>,
+    mut cosmetics: ResMut<Cosmetics>,
+    paths: Res<DataPaths>,
+) {
+    if keyboard_input.just_released(KeyCode::Tab) {
+        screen.highlighted = (screen.highlighted + 1) % Cosmetic::ALL.len();
+    }
+    if keyboard_input.just_released(KeyCode::Return) {
+        cosmetics.toggle(Cosmetic::ALL[screen.highlighted]);
+        if let Err(error) = cosmetics.save(&paths) {
+            error!("cannot save cosmetics: {error}");
+        }
+    }
+}
+
+fn update_unlocks_screen(
+    screen: Option<Res<UnlocksScreen>>,
+    cosmetics: Res<Cosmetics>,
+    mut text_query: Query<&mut Text, With<UnlocksText>>,
+) {
+    let Some(screen) = screen else {
+        return;
+    };
+    let lines = Cosmetic::ALL.iter().enumerate().map(|(index, cosmetic)| {
+        let cursor = if index == screen.highlighted { ">" } else { " " };
+        let status = if cosmetics.equipped.contains(cosmetic) {
+            "worn".to_string()
+        } else if cosmetics.unlocked.contains(cosmetic) {
+            "unlocked".to_string()
+        } else {
+            format!("locked, {}", cosmetic.requirement().description())
+        };
+        format!("{cursor} {}: {status}", cosmetic.name())
+    }).collect::<Vec<String>>();
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Unlocks (Tab, Return)\n{}", lines.join("\n"));
+    }
+}
+
+fn apply_cosmetics(
+    cosmetics: Res<Cosmetics>,
+    mut frame_tint: ResMut<FrameTint>,
+    mut selection_color: ResMut<SelectionColor>,
+    mut tinted: Local<bool>,
+) {
+    match cosmetics.equipped(Slot::Frame) {
+        Some(Cosmetic::GoldFrame) => frame_tint.0 = Color::rgb(1., 0.77, 0.3),
+        Some(_) => frame_tint.0 = Color::rgb(0.8, 0.82, 0.86),
+        // Back to the default tint only when taking a frame off, not to override a chosen tint
+        None if *tinted => *frame_tint = FrameTint::default(),
+        None => {},
+    }
+    *tinted = cosmetics.equipped(Slot::Frame).is_some();
+    *selection_color = match cosmetics.equipped(Slot::Selection) {
+        Some(Cosmetic::CyanSelection) => SelectionColor(Color::CYAN),
+        Some(_) => SelectionColor(Color::LIME_GREEN),
+        None => SelectionColor::default(),
+    };
+}
+
+/// Bursts out of the tiles when a puzzle is solved
+fn spawn_sparkles(
+    mut commands: Commands,
+    cosmetics: Res<Cosmetics>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    tiles_query: Query<&Transform, With<TileValue>>,
+) {
+    if cosmetics.equipped(Slot::Effect) != Some(Cosmetic::Sparkles) {
+        return;
+    }
+    let mesh = meshes.add(shape::Quad::new(Vec2::splat(0.12)).into());
+    let material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1., 0.85, 0.3, 0.9),
+        emissive: Color::rgb(2., 1.6, 0.4),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    let mut rng = rand::thread_rng();
+    for _ in 0..SPARKLE_COUNT {
+        let Some(origin) = tiles_query.iter().choose(&mut rng) else {
+            return;
+        };
+        commands.spawn((PbrBundle {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            transform: Transform::from_translation(origin.translation + Vec3::Z * 0.2),
+            ..default()
+        }, Sparkle {
+            velocity: Vec3::new(rng.gen_range(-2. ..2.), rng.gen_range(1. ..4.), rng.gen_range(0. ..2.)),
+            timer: Timer::from_seconds(SPARKLE_SECONDS, TimerMode::Once),
+        }));
+    }
+}
+
+fn animate_sparkles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut sparkles_query: Query<(Entity, &mut Transform, &mut Sparkle)>,
+) {
+    for (entity, mut transform, mut sparkle) in sparkles_query.iter_mut() {
+        sparkle.velocity.y -= 4. * time.delta_seconds();
+        transform.translation += sparkle.velocity * time.delta_seconds();
+        transform.scale = Vec3::splat(sparkle.timer.percent_left());
+        if sparkle.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cosmetics::{Cosmetic, Cosmetics}, persistence::Versioned};
+
+    #[test]
+    fn test_cosmetics_toggle() {
+        let mut cosmetics = Cosmetics { version: Cosmetics::VERSION, unlocked: Vec::new(), equipped: Vec::new() };
+        cosmetics.toggle(Cosmetic::GoldFrame);
+        assert!(cosmetics.equipped.is_empty());
+
+        assert!(cosmetics.unlock([Cosmetic::GoldFrame, Cosmetic::SilverFrame, Cosmetic::Sparkles]));
+        assert!(!cosmetics.unlock([Cosmetic::GoldFrame]));
+        cosmetics.toggle(Cosmetic::GoldFrame);
+        cosmetics.toggle(Cosmetic::Sparkles);
+        cosmetics.toggle(Cosmetic::SilverFrame);
+        assert_eq!(cosmetics.equipped, vec![Cosmetic::Sparkles, Cosmetic::SilverFrame]);
+        cosmetics.toggle(Cosmetic::SilverFrame);
+        assert_eq!(cosmetics.equipped, vec![Cosmetic::Sparkles]);
+    }
+}
@@ -0,0 +1,301 @@
+use bevy::prelude::*;
+
+use crate::{
+    auto_solve::AutoSolveStarted,
+    focus::FocusScope,
+    i18n::Localized,
+    locale::Locale,
+    pause::GameClock,
+    taquin::{ShuffleRequested, Taquin, TaquinFailed, TaquinShuffled, TaquinSolved},
+    AppState,
+};
+
+/// Seconds given for a 4x4 board, the others get as much per tile
+const COUNTDOWN_SECONDS_4X4: f32 = 180.;
+/// Switches the game mode in the main menu
+const MODE_KEY: KeyCode = KeyCode::M;
+/// Starts another countdown game once the time is up
+const RETRY_KEY: KeyCode = KeyCode::Return;
+
+pub struct CountdownPlugin;
+
+impl Plugin for CountdownPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameMode>()
+            .add_systems(Startup, setup_countdown_hud)
+            .add_systems(Update, (pick_game_mode, update_mode_buttons).chain().run_if(in_state(AppState::MainMenu)))
+            .add_systems(OnEnter(AppState::MainMenu), leave_countdown)
+            .add_systems(Update, (
+                start_countdown.run_if(resource_equals(GameMode::Countdown).and_then(on_event::<TaquinShuffled>())),
+                leave_countdown.run_if(on_event::<AutoSolveStarted>()),
+                win_countdown.run_if(on_event::<TaquinSolved>()),
+                tick_countdown.run_if(resource_exists::<Countdown>()),
+                update_countdown_hud,
+            ).chain().run_if(in_state(AppState::Running)))
+            .add_systems(OnEnter(AppState::TimeUp), setup_time_up_screen)
+            .add_systems(Update, time_up_input.run_if(in_state(AppState::TimeUp)))
+            .add_systems(OnExit(AppState::TimeUp), close_time_up_screen);
+    }
+}
+
+/// Rules of the games started from the main menu
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+    /// No time limit
+    #[default]
+    FreePlay,
+    /// The board has to be solved before a countdown scaled by its size runs out
+    Countdown,
+    /// Two players race on boards side by side with the same tiles, see `versus`
+    Versus,
+    /// The player races the computer, solving the same tiles on a smaller board beside, see `versus`
+    Computer,
+}
+
+impl GameMode {
+    const ALL: [GameMode; 4] = [GameMode::FreePlay, GameMode::Countdown, GameMode::Versus, GameMode::Computer];
+
+    fn label(self) -> &'static str {
+        match self {
+            GameMode::FreePlay => "Free play",
+            GameMode::Countdown => "Countdown",
+            GameMode::Versus => "Versus",
+            GameMode::Computer => "Computer",
+        }
+    }
+}
+
+/// Picks the game mode in the main menu
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModeButton(pub GameMode);
+
+impl ModeButton {
+    pub fn buttons() -> Vec<(String, ModeButton)> {
+        GameMode::ALL.iter().map(|mode| (mode.label().to_string(), ModeButton(*mode))).collect()
+    }
+}
+
+/// Time left of the countdown game being played, from its shuffle to its solve or the end of the time
+#[derive(Resource, Debug)]
+struct Countdown {
+    remaining: f32,
+}
+
+#[derive(Component)]
+struct CountdownText;
+
+#[derive(Resource)]
+struct TimeUpScreen(Entity);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+enum TimeUpButton {
+    Retry,
+    Menu,
+}
+
+/// Seconds to solve a board, the ones of a 4x4 scaled by its tiles
+fn countdown_seconds(width: i8, height: i8) -> f32 {
+    COUNTDOWN_SECONDS_4X4 * (width as f32 * height as f32 - 1.) / 15.
+}
+
+fn pick_game_mode(
+    keyboard_input: Res<Input<KeyCode>>,
+    button_query: Query<(&Interaction, &ModeButton), Changed<Interaction>>,
+    mut mode: ResMut<GameMode>,
+) {
+    if let Some((_, ModeButton(picked))) = button_query.iter().find(|(interaction, _)| **interaction == Interaction::Pressed) {
+        *mode = *picked;
+    }
+    if keyboard_input.just_released(MODE_KEY) {
+        let index = GameMode::ALL.iter().position(|m| *m == *mode).unwrap_or(0);
+        *mode = GameMode::ALL[(index + 1) % GameMode::ALL.len()];
+    }
+}
+
+fn update_mode_buttons(
+    mode: Res<GameMode>,
+    mut button_query: Query<(&ModeButton, &mut BackgroundColor, Ref<ModeButton>)>,
+) {
+    for (ModeButton(button_mode), mut background, button) in button_query.iter_mut() {
+        if !mode.is_changed() && !button.is_added() {
+            continue;
+        }
+        background.0 = if *button_mode == *mode { Color::rgba(0.65, 0.65, 0.65, 0.9) } else { Color::rgba(0.65, 0.65, 0.65, 0.4) };
+    }
+}
+
+fn setup_countdown_hud(
+    mut commands: Commands,
+) {
+    commands.spawn((TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: 40.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    ).with_style(Style {
+        position_type: PositionType::Absolute,
+        top: Val::Px(60.),
+        left: Val::Percent(45.),
+        display: Display::None,
+        ..default()
+    }), CountdownText));
+}
+
+fn start_countdown(
+    mut commands: Commands,
+    taquin: Res<Taquin>,
+) {
+    let remaining = countdown_seconds(taquin.width, taquin.height);
+    info!(seconds = remaining, "countdown started");
+    commands.insert_resource(Countdown { remaining });
+}
+
+/// Games finished by the auto-solver are not won, and going back to the menu gives up
+fn leave_countdown(
+    mut commands: Commands,
+) {
+    commands.remove_resource::<Countdown>();
+}
+
+fn win_countdown(
+    mut commands: Commands,
+    countdown: Option<Res<Countdown>>,
+) {
+    if let Some(countdown) = countdown {
+        info!(remaining = countdown.remaining, "countdown won");
+        commands.remove_resource::<Countdown>();
+    }
+}
+
+/// On the `GameClock` of the solve timer, stopped while paused or in an interlude
+fn tick_countdown(
+    mut commands: Commands,
+    mut countdown: ResMut<Countdown>,
+    clock: Res<GameClock>,
+    mut failed_events: EventWriter<TaquinFailed>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    countdown.remaining -= clock.delta_seconds();
+    if countdown.remaining > 0. {
+        return;
+    }
+    info!("countdown lost");
+    commands.remove_resource::<Countdown>();
+    failed_events.send_default();
+    next_state.set(AppState::TimeUp);
+}
+
+fn update_countdown_hud(
+    countdown: Option<Res<Countdown>>,
+    locale: Res<Locale>,
+    mut text_query: Query<(&mut Text, &mut Style), With<CountdownText>>,
+) {
+    let Ok((mut text, mut style)) = text_query.get_single_mut() else {
+        return;
+    };
+    let display = if countdown.is_some() { Display::DEFAULT } else { Display::None };
+    if style.display != display {
+        style.display = display;
+    }
+    if let Some(countdown) = countdown {
+        let value = locale.format_duration(countdown.remaining);
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+fn setup_time_up_screen(
+    mut commands: Commands,
+) {
+    let entity = commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(20.),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(5),
+        ..default()
+    }, FocusScope)).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "Time is up",
+            TextStyle {
+                font_size: 100.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), Localized::new("Time is up")));
+        for (label, button) in [("Retry", TimeUpButton::Retry), ("Main menu", TimeUpButton::Menu)] {
+            parent.spawn((ButtonBundle {
+                style: Style {
+                    width: Val::Px(300.),
+                    padding: UiRect::axes(Val::Px(20.), Val::Px(8.)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                ..default()
+            }, button)).with_children(|parent| {
+                parent.spawn((TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font_size: 50.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ), Localized::new(label)));
+            });
+        }
+    }).id();
+    commands.insert_resource(TimeUpScreen(entity));
+}
+
+/// Return retries as well, with a new shuffle
+fn time_up_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    button_query: Query<(&Interaction, &TimeUpButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let pressed = button_query.iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| *button)
+        .or(keyboard_input.just_released(RETRY_KEY).then_some(TimeUpButton::Retry));
+    match pressed {
+        Some(TimeUpButton::Retry) => {
+            commands.init_resource::<ShuffleRequested>();
+            next_state.set(AppState::Running);
+        },
+        Some(TimeUpButton::Menu) => next_state.set(AppState::MainMenu),
+        None => (),
+    }
+}
+
+fn close_time_up_screen(
+    mut commands: Commands,
+    screen: Res<TimeUpScreen>,
+) {
+    commands.entity(screen.0).despawn_recursive();
+    commands.remove_resource::<TimeUpScreen>();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::countdown::countdown_seconds;
+
+    #[test]
+    fn test_countdown_seconds() {
+        assert_eq!(countdown_seconds(4, 4), 180.);
+        assert_eq!(countdown_seconds(3, 3), 96.);
+        assert!(countdown_seconds(5, 4) > countdown_seconds(4, 4));
+    }
+}
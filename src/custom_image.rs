@@ -0,0 +1,275 @@
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+    tasks::{block_on, IoTaskPool, Task},
+    window::FileDragAndDrop,
+};
+
+use crate::{i18n::Localized, marker::Markers, platform, AppState, TaquinSprites};
+
+/// Downloads bigger than this are given up, a picture for the tiles needs far less
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_DOWNLOAD_BYTES: u64 = 20 * 1024 * 1024;
+/// Seconds the failure of a download stays on screen
+const DOWNLOAD_ERROR_DURATION: f32 = 4.;
+
+pub struct CustomImagePlugin;
+
+impl Plugin for CustomImagePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, load_dropped_image.run_if(on_event::<FileDragAndDrop>()))
+            .add_systems(Update, paste_image_url.run_if(in_state(AppState::MainMenu)))
+            .add_systems(Update, (
+                start_image_download.run_if(resource_exists::<ImageUrl>()),
+                receive_image_download.run_if(resource_exists::<ImageDownload>()),
+                expire_download_indicator.run_if(any_with_component::<DownloadIndicator>()),
+            ).chain())
+            .add_systems(Update, apply_custom_image.run_if(resource_exists::<PendingCustomImage>().and_then(not(in_state(AppState::Setup)))));
+    }
+}
+
+/// Picture given with `--image`, dropped on the window or downloaded, put on the tiles once loaded
+#[derive(Resource)]
+pub struct PendingCustomImage(pub Handle<Image>);
+
+/// Address of a picture given with `--image-url` or pasted in the main menu, downloaded on the next frame
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ImageUrl(pub String);
+
+/// Picture being downloaded then decoded off the frame
+#[derive(Resource)]
+struct ImageDownload(Task<Result<Image, String>>);
+
+/// Tells a download is running, then why it failed until its timer ends
+#[derive(Component)]
+struct DownloadIndicator(Option<Timer>);
+
+/// Only the web is reached, a path goes through `--image`
+pub fn is_image_url(text: &str) -> bool {
+    ["http://", "https://"].iter().any(|scheme| text.len() > scheme.len() && text[..scheme.len()].eq_ignore_ascii_case(scheme))
+}
+
+/// Extension of the last part of the path of `url`, its query and fragment left out
+#[cfg(not(target_arch = "wasm32"))]
+fn url_extension(url: &str) -> Option<&str> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let (_, name) = path.split_once("://").map_or(path, |(_, rest)| rest).rsplit_once('/')?;
+    name.rsplit_once('.').map(|(_, extension)| extension).filter(|extension| !extension.is_empty())
+}
+
+/// The type the server tells first, then the extension of the address, a server often answers with a generic type
+#[cfg(not(target_arch = "wasm32"))]
+fn decode_image(bytes: &[u8], content_type: &str, url: &str) -> Result<Image, String> {
+    use bevy::render::texture::{CompressedImageFormats, ImageSampler, ImageType};
+
+    let decode = |image_type: ImageType| Image::from_buffer(bytes, image_type, CompressedImageFormats::NONE, true, ImageSampler::Default);
+    decode(ImageType::MimeType(content_type))
+        .or_else(|error| url_extension(url).map_or(Err(error), |extension| decode(ImageType::Extension(extension))))
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn download_image(url: &str) -> Result<Image, String> {
+    use std::io::Read;
+
+    let response = ureq::get(url).call().map_err(|error| error.to_string())?;
+    let content_type = response.content_type().to_string();
+    let mut bytes = Vec::new();
+    response.into_reader().take(MAX_DOWNLOAD_BYTES + 1).read_to_end(&mut bytes).map_err(|error| error.to_string())?;
+    if bytes.len() as u64 > MAX_DOWNLOAD_BYTES {
+        return Err(format!("the picture is bigger than {} MB", MAX_DOWNLOAD_BYTES / 1024 / 1024));
+    }
+    decode_image(&bytes, &content_type, url)
+}
+
+/// The page cannot wait on a request from a task, a picture is dropped on it instead
+#[cfg(target_arch = "wasm32")]
+fn download_image(_url: &str) -> Result<Image, String> {
+    Err("pictures cannot be downloaded in the browser, drop the file on the page instead".to_string())
+}
+
+/// Centered part of a `width` by `height` picture with the `aspect` ratio of the frame
+fn crop_to_aspect(width: u32, height: u32, aspect: f32) -> (u32, u32, u32, u32) {
+    if width as f32 / height as f32 > aspect {
+        let cropped = (height as f32 * aspect).round() as u32;
+        ((width - cropped) / 2, 0, cropped, height)
+    } else {
+        let cropped = (width as f32 / aspect).round() as u32;
+        (0, (height - cropped) / 2, width, cropped)
+    }
+}
+
+fn load_dropped_image(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut drop_events: EventReader<FileDragAndDrop>,
+) {
+    let Some(path) = drop_events.read().filter_map(|event| match event {
+        FileDragAndDrop::DroppedFile { path_buf, .. } => Some(path_buf.clone()),
+        _ => None,
+    }).last() else {
+        return;
+    };
+    info!(path = %path.display(), "picture dropped");
+    commands.insert_resource(PendingCustomImage(asset_server.load(path)));
+}
+
+fn is_control_pressed(keyboard_input: &Input<KeyCode>) -> bool {
+    keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+/// Ctrl+V of a web address, a board code is pasted by `board_code`
+fn paste_image_url(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if !keyboard_input.just_released(KeyCode::V) || !is_control_pressed(&keyboard_input) {
+        return;
+    }
+    let Ok(text) = platform::paste_text() else {
+        return;
+    };
+    let url = text.trim();
+    if is_image_url(url) {
+        info!(url, "picture address pasted in the main menu");
+        commands.insert_resource(ImageUrl(url.to_string()));
+    }
+}
+
+/// A new address replaces the download still running
+fn start_image_download(
+    mut commands: Commands,
+    url: Res<ImageUrl>,
+    indicator_query: Query<Entity, With<DownloadIndicator>>,
+) {
+    commands.remove_resource::<ImageUrl>();
+    for entity in indicator_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    info!(url = url.0, "downloading the picture");
+    let ImageUrl(url) = url.clone();
+    commands.insert_resource(ImageDownload(IoTaskPool::get().spawn(async move { download_image(&url) })));
+    commands.spawn((
+        TextBundle::from_section(
+            "Downloading the picture...",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.),
+            bottom: Val::Px(10.),
+            ..default()
+        }),
+        Localized::new("Downloading the picture..."),
+        DownloadIndicator(None),
+    ));
+}
+
+/// The downloaded picture goes the way of a dropped one, a failed download keeps the pack pictures
+fn receive_image_download(
+    mut commands: Commands,
+    mut download: ResMut<ImageDownload>,
+    mut images: ResMut<Assets<Image>>,
+    mut indicator_query: Query<(Entity, &mut Localized, &mut DownloadIndicator)>,
+) {
+    if !download.0.is_finished() {
+        return;
+    }
+    commands.remove_resource::<ImageDownload>();
+    match block_on(&mut download.0) {
+        Ok(image) => {
+            info!(width = image.width(), height = image.height(), "picture downloaded");
+            commands.insert_resource(PendingCustomImage(images.add(image)));
+            for (entity, _, _) in indicator_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+        },
+        Err(error) => {
+            warn!("cannot download the picture, the pack pictures are kept: {error}");
+            for (_, mut localized, mut indicator) in indicator_query.iter_mut() {
+                *localized = Localized::new("Cannot download the picture");
+                indicator.0 = Some(Timer::from_seconds(DOWNLOAD_ERROR_DURATION, TimerMode::Once));
+            }
+        },
+    }
+}
+
+fn expire_download_indicator(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut indicator_query: Query<(Entity, &mut DownloadIndicator)>,
+) {
+    for (entity, mut indicator) in indicator_query.iter_mut() {
+        if indicator.0.as_mut().is_some_and(|timer| timer.tick(time.delta()).finished()) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// The picture replaces the first one of the pack, cropped so the frame does not stretch it
+fn apply_custom_image(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    pending: Res<PendingCustomImage>,
+    markers: Res<Markers>,
+    mut images: ResMut<Assets<Image>>,
+    mut sprites: ResMut<TaquinSprites>,
+) {
+    match asset_server.get_load_state(&pending.0) {
+        Some(LoadState::Failed) => {
+            warn!("cannot load the custom picture, the pack pictures are kept");
+            commands.remove_resource::<PendingCustomImage>();
+            return;
+        },
+        // A downloaded picture is added to the assets already decoded, the server does not know it
+        Some(LoadState::Loaded) | None => (),
+        _ => return,
+    }
+    commands.remove_resource::<PendingCustomImage>();
+    let Some(picture) = images.get(&pending.0).and_then(|picture| picture.clone().try_into_dynamic().ok()) else {
+        warn!("custom picture format is not supported, the pack pictures are kept");
+        return;
+    };
+    let (x, y, width, height) = crop_to_aspect(picture.width(), picture.height(), markers.inner_width() / markers.inner_height());
+    let cropped = images.add(Image::from_dynamic(picture.crop_imm(x, y, width, height), true));
+    info!(width, height, "custom picture applied");
+    if let Some(first) = sprites.images.first_mut() {
+        *first = cropped;
+    } else {
+        sprites.images.push(cropped);
+    }
+    sprites.selected = 0;
+    // Re-textures the tiles, as a newly loaded pack does
+    sprites.applied = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::custom_image::{crop_to_aspect, is_image_url, url_extension};
+
+    #[test]
+    fn test_crop_to_aspect() {
+        assert_eq!(crop_to_aspect(400, 200, 1.), (100, 0, 200, 200));
+        assert_eq!(crop_to_aspect(200, 400, 1.), (0, 100, 200, 200));
+        assert_eq!(crop_to_aspect(300, 300, 1.5), (0, 50, 300, 200));
+        assert_eq!(crop_to_aspect(300, 200, 1.5), (0, 0, 300, 200));
+    }
+
+    #[test]
+    fn test_image_url() {
+        assert!(is_image_url("https://example.com/cat.png"));
+        assert!(is_image_url("HTTP://example.com/cat"));
+        assert!(!is_image_url("https://"));
+        assert!(!is_image_url("ftp://example.com/cat.png"));
+        assert!(!is_image_url("3x3-12345"));
+        assert_eq!(url_extension("https://example.com/pictures/cat.png?size=large#top"), Some("png"));
+        assert_eq!(url_extension("https://example.com/cat"), None);
+        assert_eq!(url_extension("https://example.com"), None);
+        assert_eq!(url_extension("https://example.com/cat."), None);
+    }
+}
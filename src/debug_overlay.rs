@@ -0,0 +1,124 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+
+use crate::{auto_solve::SolverTable, taquin::Taquin, AppState};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        app
+            .add_systems(Startup, setup_debug_overlay)
+            .add_systems(Update, (
+                toggle_debug_overlay,
+                update_debug_overlay.run_if(is_overlay_shown),
+            ).chain());
+    }
+}
+
+/// Developer diagnostics, hidden until `F3` is pressed
+#[derive(Component)]
+struct DebugOverlay;
+
+fn setup_debug_overlay(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::YELLOW,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.),
+            bottom: Val::Px(10.),
+            display: Display::None,
+            ..default()
+        }),
+        DebugOverlay,
+    ));
+}
+
+fn toggle_debug_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut overlay_query: Query<&mut Style, With<DebugOverlay>>,
+) {
+    if !keyboard_input.just_released(TOGGLE_KEY) {
+        return;
+    }
+    for mut style in overlay_query.iter_mut() {
+        style.display = if style.display == Display::None { Display::DEFAULT } else { Display::None };
+    }
+}
+
+fn is_overlay_shown(
+    overlay_query: Query<&Style, With<DebugOverlay>>,
+) -> bool {
+    overlay_query.iter().any(|style| style.display != Display::None)
+}
+
+/// The board as `Taquin` knows it, row after row, the empty slots as dots
+fn board_text(taquin: &Taquin) -> String {
+    let digits = taquin.tiles_nb.to_string().len();
+    taquin.tiles.iter().map(|row| {
+        row.iter().map(|value| {
+            let label = if taquin.is_empty_value(*value) { ".".to_string() } else { value.0.to_string() };
+            format!("{label:>digits$}")
+        }).collect::<Vec<String>>().join(" ")
+    }).collect::<Vec<String>>().join("\n")
+}
+
+/// Refreshed every frame while shown, the frame rate moves all the time
+fn update_debug_overlay(
+    diagnostics: Res<DiagnosticsStore>,
+    entity_query: Query<Entity>,
+    state: Res<State<AppState>>,
+    taquin: Res<Taquin>,
+    table: Res<SolverTable>,
+    mut overlay_query: Query<&mut Text, With<DebugOverlay>>,
+) {
+    let fps = diagnostics.get(FrameTimeDiagnosticsPlugin::FPS).and_then(|fps| fps.smoothed()).unwrap_or(0.);
+    let empties = taquin.get_empty_tiles_coordinates().iter().map(|empty| format!("({}, {})", empty.i, empty.j)).collect::<Vec<String>>().join(" ");
+    let search = table.last_stats.map_or_else(|| "no search yet".to_string(), |stats| {
+        format!("{} nodes, {} probes, {:.1}% hits", stats.nodes, stats.probes, stats.hit_rate() * 100.)
+    });
+    let value = format!(
+        "{fps:.0} fps, {} entities\nstate: {:?}\n{}\ninversions: {}, solvable: {}, solved: {}\nempty: {empties}\nsolver table: {} entries\nlast search: {search}",
+        entity_query.iter().len(), state.get(), board_text(&taquin), taquin.get_inversion_count(), taquin.is_solvable(), taquin.is_solved(), table.size,
+    );
+    for mut text in overlay_query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{debug_overlay::board_text, taquin::Taquin, tile::TileValue};
+
+    #[test]
+    fn test_board_text() {
+        let mut taquin = Taquin::rect(3, 4);
+        taquin.set_tiles((0..4).map(|j| (1..=3).map(|i| TileValue(j * 3 + i)).collect()).collect());
+        assert_eq!(board_text(&taquin), " 1  2  3\n 4  5  6\n 7  8  9\n10 11  .");
+        taquin.set_tiles(vec![
+            vec![TileValue(12), TileValue(2), TileValue(3)],
+            vec![TileValue(4), TileValue(5), TileValue(6)],
+            vec![TileValue(7), TileValue(8), TileValue(9)],
+            vec![TileValue(10), TileValue(11), TileValue(1)],
+        ]);
+        assert!(board_text(&taquin).starts_with(" .  2"));
+    }
+}
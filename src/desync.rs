@@ -0,0 +1,132 @@
+use std::{collections::HashSet, fmt, time::Duration};
+
+use bevy::{prelude::*, time::common_conditions::on_real_timer};
+
+use crate::{
+    marker::Markers,
+    taquin::Taquin,
+    tile::{TileCoordinates, TileId, TileSlide, TileValue},
+    AppState,
+};
+
+/// The check goes over every tile, once a second is plenty to catch a desync near where it started
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Distance a tile may stand from its cell, in world units
+const TRANSLATION_TOLERANCE: f32 = 1e-3;
+
+pub struct DesyncPlugin;
+
+impl Plugin for DesyncPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<BoardDesync>()
+            .add_systems(Update, (
+                check_board_sync.run_if(on_real_timer(CHECK_INTERVAL)),
+                report_board_desync.run_if(on_event::<BoardDesync>()),
+            ).chain().run_if(in_state(AppState::Running).and_then(not(any_with_component::<TileSlide>()))));
+    }
+}
+
+/// A way the tile entities disagree with `Taquin`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileMismatch {
+    /// The entity of `tile` is on a cell the board has `expected` on
+    Cell { tile: TileValue, at: TileCoordinates, expected: TileValue },
+    /// The entity of `tile` is `offset` away from the cell it is on
+    Translation { tile: TileValue, at: TileCoordinates, offset: Vec2 },
+    /// No entity is on the cell of `tile`
+    Missing { tile: TileValue, at: TileCoordinates },
+}
+
+impl fmt::Display for TileMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileMismatch::Cell { tile, at, expected } => write!(f, "tile {} on ({}, {}) where the board has {}", tile.0, at.i, at.j, expected.0),
+            TileMismatch::Translation { tile, at, offset } => write!(f, "tile {} is {offset} away from ({}, {})", tile.0, at.i, at.j),
+            TileMismatch::Missing { tile, at } => write!(f, "no tile on ({}, {}) where the board has {}", at.i, at.j, tile.0),
+        }
+    }
+}
+
+/// Sent when the tiles on screen drift from the board model, once per new difference
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct BoardDesync(pub Vec<TileMismatch>);
+
+/// What the tiles, as their value, cell and translation, get wrong about `taquin`
+fn board_diff(taquin: &Taquin, markers: &Markers, tiles: impl IntoIterator<Item = (TileValue, TileCoordinates, Vec3)>) -> Vec<TileMismatch> {
+    let mut mismatches = Vec::new();
+    let mut covered = HashSet::new();
+    for (tile, at, translation) in tiles {
+        covered.insert(at);
+        let expected = taquin.tiles.get(at.j as usize).and_then(|row| row.get(at.i as usize)).copied();
+        if expected != Some(tile) {
+            mismatches.push(TileMismatch::Cell { tile, at, expected: expected.unwrap_or(TileValue(0)) });
+        }
+        let offset = (translation - markers.tile_translation(taquin.width, taquin.height, at)).truncate();
+        if offset.length() > TRANSLATION_TOLERANCE {
+            mismatches.push(TileMismatch::Translation { tile, at, offset });
+        }
+    }
+    for (j, row) in taquin.tiles.iter().enumerate() {
+        for (i, tile) in row.iter().enumerate() {
+            let at = TileCoordinates::new(i as i8, j as i8);
+            if !covered.contains(&at) {
+                mismatches.push(TileMismatch::Missing { tile: *tile, at });
+            }
+        }
+    }
+    mismatches
+}
+
+/// Only checked while the tiles rest, a slide under way is not a desync
+fn check_board_sync(
+    taquin: Res<Taquin>,
+    markers: Res<Markers>,
+    tiles_query: Query<(&TileValue, &TileCoordinates, &Transform), With<TileId>>,
+    mut desync_events: EventWriter<BoardDesync>,
+    mut last: Local<Vec<TileMismatch>>,
+) {
+    let mismatches = board_diff(&taquin, &markers, tiles_query.iter().map(|(value, coordinates, transform)| (*value, *coordinates, transform.translation)));
+    if mismatches != *last && !mismatches.is_empty() {
+        desync_events.send(BoardDesync(mismatches.clone()));
+    }
+    *last = mismatches;
+}
+
+fn report_board_desync(
+    mut desync_events: EventReader<BoardDesync>,
+) {
+    for BoardDesync(mismatches) in desync_events.read() {
+        let diff = mismatches.iter().map(|mismatch| mismatch.to_string()).collect::<Vec<String>>();
+        error!("the tiles are out of sync with the board: {}", diff.join(", "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::{
+        desync::{board_diff, TileMismatch},
+        marker::Markers,
+        taquin::Taquin,
+        tile::{TileCoordinates, TileValue},
+    };
+
+    #[test]
+    fn test_board_diff() {
+        let markers = Markers { tl: Vec3::new(-1., 1., 0.), tr: Vec3::new(1., 1., 0.), bl: Vec3::new(-1., -1., 0.), br: Vec3::new(1., -1., 0.) };
+        let mut taquin = Taquin::rect(2, 2);
+        taquin.set_tiles(vec![vec![TileValue(1), TileValue(2)], vec![TileValue(3), TileValue(4)]]);
+        let tile = |value: i16, i: i8, j: i8| (TileValue(value), TileCoordinates::new(i, j), markers.tile_translation(2, 2, TileCoordinates::new(i, j)));
+        assert_eq!(board_diff(&taquin, &markers, [tile(1, 0, 0), tile(2, 1, 0), tile(3, 0, 1), tile(4, 1, 1)]), []);
+
+        let (value, at, translation) = tile(2, 1, 0);
+        let moved = (value, at, translation + Vec3::X * 0.5);
+        assert_eq!(board_diff(&taquin, &markers, [tile(1, 0, 0), moved, tile(4, 0, 1)]), [
+            TileMismatch::Translation { tile: TileValue(2), at: TileCoordinates::new(1, 0), offset: Vec2::new(0.5, 0.) },
+            TileMismatch::Cell { tile: TileValue(4), at: TileCoordinates::new(0, 1), expected: TileValue(3) },
+            TileMismatch::Missing { tile: TileValue(4), at: TileCoordinates::new(1, 1) },
+        ]);
+    }
+}
@@ -0,0 +1,110 @@
+use bevy::{
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode, WindowResized, WindowResolution},
+};
+
+use crate::settings::{Settings, VSync};
+
+const FULLSCREEN_KEY: KeyCode = KeyCode::F11;
+/// Window size the texts and buttons of the GUI were laid out for, they are scaled to the actual one
+const REFERENCE_SIZE: Vec2 = Vec2::new(1280., 720.);
+const UI_SCALE_RANGE: (f64, f64) = (0.5, 2.);
+/// Sizes offered in the settings screen, see `gui`
+pub const RESOLUTIONS: [(u32, u32); 4] = [(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (
+            toggle_fullscreen,
+            apply_display_settings.run_if(resource_changed::<Settings>()),
+            scale_ui.run_if(on_event::<WindowResized>()),
+        ).chain());
+    }
+}
+
+impl VSync {
+    pub fn present_mode(self) -> PresentMode {
+        match self {
+            VSync::On => PresentMode::AutoVsync,
+            VSync::Off => PresentMode::AutoNoVsync,
+        }
+    }
+}
+
+/// The size of the window when none is saved is the one of bevy
+pub fn window_resolution(settings: &Settings) -> WindowResolution {
+    settings.resolution.map(|(width, height)| WindowResolution::new(width as f32, height as f32)).unwrap_or_default()
+}
+
+/// Scale of the GUI for a window of `size`, the side the farthest below the reference one wins so nothing overflows
+fn ui_scale(size: Vec2) -> f64 {
+    let scale = (size / REFERENCE_SIZE).min_element() as f64;
+    scale.clamp(UI_SCALE_RANGE.0, UI_SCALE_RANGE.1)
+}
+
+/// F11 or Alt+Return, the fullscreen of `--fullscreen` is not saved so neither is this one
+fn toggle_fullscreen(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let alt = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    if !(keyboard_input.just_released(FULLSCREEN_KEY) || alt && keyboard_input.just_released(KeyCode::Return)) {
+        return;
+    }
+    for mut window in window_query.iter_mut() {
+        window.mode = match window.mode {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+        info!(mode = ?window.mode, "fullscreen toggled");
+    }
+}
+
+/// Only touches the window when a display setting changed, a window resized by hand keeps its size until another
+/// resolution is picked
+fn apply_display_settings(
+    settings: Res<Settings>,
+    mut applied: Local<Option<(VSync, Option<(u32, u32)>)>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    // The window was created with the saved ones, see `main`
+    let previous = applied.replace((settings.vsync, settings.resolution)).unwrap_or((settings.vsync, settings.resolution));
+    for mut window in window_query.iter_mut() {
+        if previous.0 != settings.vsync {
+            window.present_mode = settings.vsync.present_mode();
+        }
+        if let Some((width, height)) = settings.resolution.filter(|_| previous.1 != settings.resolution) {
+            window.resolution.set(width as f32, height as f32);
+        }
+    }
+}
+
+fn scale_ui(
+    mut scale: ResMut<UiScale>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+) {
+    if let Ok(window) = window_query.get_single() {
+        let fitting = ui_scale(Vec2::new(window.width(), window.height()));
+        if scale.0 != fitting {
+            scale.0 = fitting;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::display::{ui_scale, REFERENCE_SIZE};
+
+    #[test]
+    fn test_ui_scale() {
+        assert_eq!(ui_scale(REFERENCE_SIZE), 1.);
+        assert_eq!(ui_scale(Vec2::new(1920., 1080.)), 1.5);
+        // A narrow window scales down to its width
+        assert_eq!(ui_scale(Vec2::new(640., 1080.)), 0.5);
+        assert_eq!(ui_scale(Vec2::new(100., 100.)), 0.5);
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+
+use crate::{mega_board::MegaTile, taquin::{TaquinShuffled, TaquinSolved}, tile::TileValue, tile_material::{TileMaterial, TileShading}};
+
+const DISSOLVE_SECONDS: f32 = 0.6;
+/// Delay between a tile and the next one in a dissolve wave
+const WAVE_STEP_SECONDS: f32 = 0.04;
+
+pub struct DissolvePlugin;
+
+impl Plugin for DissolvePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (
+            dissolve_spawned_tiles,
+            dissolve_tiles_on_shuffle.run_if(on_event::<TaquinShuffled>()),
+            dissolve_tiles_on_solved.run_if(on_event::<TaquinSolved>()),
+            animate_dissolve,
+        ).chain());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DissolveKind {
+    /// The tile materializes
+    In,
+    /// The tile vanishes and comes back
+    OutAndIn,
+}
+
+/// Dissolve animation of a tile, removed once over
+#[derive(Component, Debug)]
+#[component(storage = "SparseSet")]
+pub struct Dissolve {
+    kind: DissolveKind,
+    delay: f32,
+    elapsed: f32,
+}
+
+impl Dissolve {
+    /// Tiles start one after the other in the order of their values
+    fn wave(kind: DissolveKind, value: &TileValue) -> Self {
+        Self { kind, delay: value.0 as f32 * WAVE_STEP_SECONDS, elapsed: 0. }
+    }
+
+    /// Dissolve of the tile, 0 when fully drawn
+    fn amount(&self) -> f32 {
+        let progress = ((self.elapsed - self.delay) / DISSOLVE_SECONDS).clamp(0., 1.);
+        match self.kind {
+            DissolveKind::In => 1. - progress,
+            DissolveKind::OutAndIn => 1. - (2. * progress - 1.).abs(),
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.delay + DISSOLVE_SECONDS
+    }
+}
+
+/// Hides the new tiles right away, they materialize as the animation plays.
+/// A wave over the thousands of tiles of a mega board would last minutes, they show at once
+fn dissolve_spawned_tiles(
+    mut commands: Commands,
+    mut tiles_query: Query<(Entity, &TileValue, &mut TileShading), (Added<TileShading>, Without<MegaTile>)>,
+) {
+    for (entity, value, mut shading) in tiles_query.iter_mut() {
+        shading.dissolve = 1.;
+        commands.entity(entity).insert(Dissolve::wave(DissolveKind::In, value));
+    }
+}
+
+/// Shuffled tiles materialize again at their new places
+fn dissolve_tiles_on_shuffle(
+    mut commands: Commands,
+    tiles_query: Query<(Entity, &TileValue), With<Handle<TileMaterial>>>,
+) {
+    for (entity, value) in tiles_query.iter() {
+        commands.entity(entity).insert(Dissolve::wave(DissolveKind::In, value));
+    }
+}
+
+fn dissolve_tiles_on_solved(
+    mut commands: Commands,
+    tiles_query: Query<(Entity, &TileValue), With<Handle<TileMaterial>>>,
+) {
+    for (entity, value) in tiles_query.iter() {
+        commands.entity(entity).insert(Dissolve::wave(DissolveKind::OutAndIn, value));
+    }
+}
+
+fn animate_dissolve(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tiles_query: Query<(Entity, &mut Dissolve, &mut TileShading)>,
+) {
+    for (entity, mut dissolve, mut shading) in tiles_query.iter_mut() {
+        dissolve.elapsed += time.delta_seconds();
+        // The tiles waiting for their turn in the wave keep their amount, their meshes are left alone
+        let amount = dissolve.amount();
+        if shading.dissolve != amount {
+            shading.dissolve = amount;
+        }
+        if dissolve.is_finished() {
+            commands.entity(entity).remove::<Dissolve>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{dissolve::{Dissolve, DissolveKind}, tile::TileValue};
+
+    #[test]
+    fn test_dissolve_amount() {
+        let mut dissolve = Dissolve::wave(DissolveKind::OutAndIn, &TileValue(0));
+        assert_eq!(dissolve.amount(), 0.);
+        dissolve.elapsed = 0.3;
+        assert!((dissolve.amount() - 1.).abs() < 1e-6);
+        dissolve.elapsed = 0.6;
+        assert!(dissolve.amount().abs() < 1e-6 && dissolve.is_finished());
+
+        let mut dissolve = Dissolve::wave(DissolveKind::In, &TileValue(5));
+        dissolve.elapsed = 0.2;
+        assert_eq!((dissolve.amount(), dissolve.is_finished()), (1., false));
+    }
+}
@@ -0,0 +1,326 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    auto_solve::AutoSolvePlayback,
+    graphics::MainCamera,
+    marker::Markers,
+    mods::Challenge,
+    persistence::{self, DataPaths, Format},
+    picking::{cursor_ray, pick_tile},
+    replay::ReplayPlayback,
+    taquin::{is_solver_board, place_tiles, ArrangeTiles, Taquin},
+    tile::{TileCoordinates, TileSlide, TileSelected, TileValue},
+    AppState,
+};
+
+const EDITOR_KEY: KeyCode = KeyCode::D;
+const GOAL_KEY: KeyCode = KeyCode::G;
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, enter_editor.run_if(in_state(AppState::Running)
+                .and_then(is_solver_board)
+                .and_then(not(any_with_component::<TileSlide>()))
+                .and_then(not(resource_exists::<ReplayPlayback>()))
+                .and_then(not(resource_exists::<AutoSolvePlayback>()))))
+            .add_systems(OnEnter(AppState::Editor), setup_editor)
+            .add_systems(Update, (
+                swap_clicked_cells,
+                cycle_goal_layout,
+                save_challenge,
+                cancel_editor,
+                play_edited_board,
+                update_editor_panel,
+            ).chain().run_if(in_state(AppState::Editor)))
+            .add_systems(OnExit(AppState::Editor), close_editor);
+    }
+}
+
+/// Position the board is solved into, the empty slot always ends in the last cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum GoalLayout {
+    /// The numbers in order, as every other game
+    #[default]
+    Rows,
+    Columns,
+    /// Rows in order, every other one from right to left
+    Snake,
+    Reversed,
+}
+
+impl GoalLayout {
+    fn next(self) -> Self {
+        match self {
+            GoalLayout::Rows => GoalLayout::Columns,
+            GoalLayout::Columns => GoalLayout::Snake,
+            GoalLayout::Snake => GoalLayout::Reversed,
+            GoalLayout::Reversed => GoalLayout::Rows,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            GoalLayout::Rows => "rows",
+            GoalLayout::Columns => "columns",
+            GoalLayout::Snake => "snake",
+            GoalLayout::Reversed => "reversed",
+        }
+    }
+
+    /// Goal of a `size` by `size` board row after row, none for the numbers in order
+    fn tiles(self, size: i8) -> Option<Vec<i16>> {
+        let size = size as usize;
+        let tiles_nb = size * size;
+        // Cells of the numbers from 1 on
+        let cells = match self {
+            GoalLayout::Rows => return None,
+            GoalLayout::Columns => (0..tiles_nb).map(|n| n % size * size + n / size).collect::<Vec<usize>>(),
+            GoalLayout::Snake => (0..tiles_nb).map(|n| if n / size % 2 == 1 { n / size * size + size - 1 - n % size } else { n }).collect(),
+            GoalLayout::Reversed => (0..tiles_nb - 1).rev().collect(),
+        };
+        let mut goal = vec![tiles_nb as i16; tiles_nb];
+        for (index, cell) in cells.into_iter().filter(|cell| *cell != tiles_nb - 1).enumerate() {
+            goal[cell] = index as i16 + 1;
+        }
+        Some(goal)
+    }
+}
+
+/// Board being edited, the game is left as it was when the editor is cancelled
+#[derive(Resource)]
+struct BoardEditor {
+    panel: Entity,
+    original: Vec<TileValue>,
+    goal: GoalLayout,
+    /// Cell clicked first, swapped with the next one clicked
+    picked: Option<TileCoordinates>,
+    /// Outcome of the last save
+    message: String,
+    /// Whether the edited board became the new game, the original one is put back otherwise
+    played: bool,
+}
+
+impl BoardEditor {
+    fn challenge(&self, taquin: &Taquin, name: String) -> Challenge {
+        Challenge {
+            name,
+            size: taquin.width,
+            tiles: taquin.tiles.iter().flatten().map(|value| value.0).collect(),
+            goal: self.goal.tiles(taquin.width),
+            max_moves: None,
+        }
+    }
+}
+
+#[derive(Component)]
+struct EditorText;
+
+fn enter_editor(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keyboard_input.just_released(EDITOR_KEY) {
+        next_state.set(AppState::Editor);
+    }
+}
+
+/// The virtual clock stops while editing, as in a pause, and the `GameClock` out of `AppState::Running`
+fn setup_editor(
+    mut commands: Commands,
+    taquin: Res<Taquin>,
+    selected_query: Query<Entity, With<TileSelected>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    virtual_time.pause();
+    for entity in selected_query.iter() {
+        commands.entity(entity).remove::<TileSelected>();
+    }
+    let panel = commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.),
+            top: Val::Px(120.),
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(5),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), EditorText));
+    }).id();
+    info!("board editor opened");
+    commands.insert_resource(BoardEditor {
+        panel,
+        original: taquin.tiles.iter().flatten().copied().collect(),
+        goal: GoalLayout::default(),
+        picked: None,
+        message: String::new(),
+        played: false,
+    });
+}
+
+/// A first click picks a cell, the empty one as well, a second one swaps the two
+fn swap_clicked_cells(
+    mouse_input: Res<Input<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    markers: Res<Markers>,
+    mut taquin: ResMut<Taquin>,
+    mut editor: ResMut<BoardEditor>,
+    mut tiles_query: Query<(&TileValue, &mut TileCoordinates, &mut Transform)>,
+) {
+    if !mouse_input.just_released(MouseButton::Left) {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    let tile_size = markers.tile_size(taquin.width, taquin.height);
+    let Some(cell) = cursor_ray(window, camera, camera_transform)
+        .and_then(|ray| pick_tile(ray, markers.rotation(), tile_size, tiles_query.iter().map(|(_, coordinates, transform)| (*coordinates, transform.translation)))) else {
+        return;
+    };
+    match editor.picked.take() {
+        None => editor.picked = Some(cell),
+        Some(picked) if picked == cell => (),
+        Some(picked) => {
+            taquin.swap_tiles(picked, cell);
+            let tiles = taquin.tiles.iter().flatten().copied().collect::<Vec<TileValue>>();
+            place_tiles(&mut taquin, &markers, &mut tiles_query, &tiles);
+        },
+    }
+}
+
+fn cycle_goal_layout(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut editor: ResMut<BoardEditor>,
+) {
+    if keyboard_input.just_released(GOAL_KEY) {
+        editor.goal = editor.goal.next();
+    }
+}
+
+/// Writes the position as a challenge file, once it can be played
+fn save_challenge(
+    keyboard_input: Res<Input<KeyCode>>,
+    taquin: Res<Taquin>,
+    paths: Res<DataPaths>,
+    mut editor: ResMut<BoardEditor>,
+) {
+    if !keyboard_input.just_released(KeyCode::Return) {
+        return;
+    }
+    let timestamp = persistence::timestamp();
+    let challenge = editor.challenge(&taquin, format!("Edited {timestamp}"));
+    if let Err(error) = challenge.validate() {
+        editor.message = format!("Not saved, {error}");
+        return;
+    }
+    let path = paths.challenges().join(format!("challenge-{timestamp}.ron"));
+    editor.message = match Format::Ron.serialize(&challenge).and_then(|contents| persistence::write(&path, contents)) {
+        Ok(()) => {
+            info!(path = %path.display(), "challenge saved");
+            format!("Saved to {}", path.display())
+        },
+        Err(error) => {
+            error!("cannot save the challenge: {error}");
+            "Cannot save the challenge".to_string()
+        },
+    };
+}
+
+/// Escape puts the game back as it was, see `close_editor`
+fn cancel_editor(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keyboard_input.just_released(KeyCode::Escape) {
+        next_state.set(AppState::Running);
+    }
+}
+
+/// The editor key plays the position as a new game
+fn play_edited_board(
+    keyboard_input: Res<Input<KeyCode>>,
+    taquin: Res<Taquin>,
+    mut editor: ResMut<BoardEditor>,
+    mut arrange_events: EventWriter<ArrangeTiles>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !keyboard_input.just_released(EDITOR_KEY) {
+        return;
+    }
+    let challenge = editor.challenge(&taquin, String::new());
+    match challenge.validate() {
+        Ok(()) => {
+            info!(goal = editor.goal.name(), "edited board played");
+            editor.played = true;
+            arrange_events.send(ArrangeTiles(challenge.start_tiles()));
+            next_state.set(AppState::Running);
+        },
+        Err(error) => editor.message = format!("Cannot play, {error}"),
+    }
+}
+
+fn update_editor_panel(
+    taquin: Res<Taquin>,
+    editor: Res<BoardEditor>,
+    mut text_query: Query<&mut Text, With<EditorText>>,
+) {
+    if !taquin.is_changed() && !editor.is_changed() {
+        return;
+    }
+    let status = match editor.challenge(&taquin, String::new()).validate() {
+        Ok(()) => "Solvable".to_string(),
+        Err(error) => format!("Not playable, {error}"),
+    };
+    let picked = editor.picked.map_or(String::new(), |cell| format!("\nPicked row {}, column {}", cell.j + 1, cell.i + 1));
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!(
+            "Board editor\nClick two cells to swap them, the empty one as well\nG goal: {}\nReturn saves a challenge, D plays it, Escape cancels\n{status}{picked}\n{}",
+            editor.goal.name(),
+            editor.message,
+        );
+    }
+}
+
+fn close_editor(
+    mut commands: Commands,
+    editor: Res<BoardEditor>,
+    markers: Res<Markers>,
+    mut taquin: ResMut<Taquin>,
+    mut tiles_query: Query<(&TileValue, &mut TileCoordinates, &mut Transform)>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    virtual_time.unpause();
+    if !editor.played {
+        place_tiles(&mut taquin, &markers, &mut tiles_query, &editor.original);
+    }
+    commands.entity(editor.panel).despawn_recursive();
+    commands.remove_resource::<BoardEditor>();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editor::GoalLayout;
+
+    #[test]
+    fn test_goal_layouts() {
+        assert_eq!(GoalLayout::Rows.tiles(3), None);
+        assert_eq!(GoalLayout::Columns.tiles(3), Some(vec![1, 4, 7, 2, 5, 8, 3, 6, 9]));
+        assert_eq!(GoalLayout::Snake.tiles(3), Some(vec![1, 2, 3, 6, 5, 4, 7, 8, 9]));
+        assert_eq!(GoalLayout::Snake.tiles(2), Some(vec![1, 2, 3, 4]));
+        assert_eq!(GoalLayout::Reversed.tiles(2), Some(vec![3, 2, 1, 4]));
+    }
+}
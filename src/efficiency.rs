@@ -0,0 +1,230 @@
+use std::cmp::Reverse;
+
+use bevy::{prelude::*, tasks::{block_on, AsyncComputeTaskPool, Task}};
+
+use crate::{
+    auto_solve::AutoSolveStarted,
+    solver::{self, Board},
+    taquin::{is_solver_board, Taquin, TaquinShuffled, TaquinSolved, TileMoved},
+    tile::TileCoordinates,
+    AppState,
+};
+
+/// Positions explored per board of the solve before the report gives up
+const MAX_NODES: u64 = 5_000_000;
+/// Detours separated by fewer good moves are reported as one segment
+const MERGE_GAP: usize = 3;
+const WORST_SEGMENTS: usize = 3;
+
+pub struct EfficiencyPlugin;
+
+impl Plugin for EfficiencyPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SolveRecording>()
+            .add_systems(Startup, setup_efficiency_report)
+            .add_systems(Update, (
+                start_solve_recording.run_if(on_event::<TaquinShuffled>().and_then(is_solver_board)),
+                record_solve_moves.run_if(on_event::<TileMoved>()),
+                drop_solve_recording.run_if(on_event::<AutoSolveStarted>()),
+                analyze_solve.run_if(on_event::<TaquinSolved>()),
+                receive_efficiency_report.run_if(resource_exists::<EfficiencyTask>()),
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// Moves where the player drifted away from the solution, up to the first position closer to it than where they started
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Moves played before the segment
+    pub start: usize,
+    pub board: Board,
+    pub played: usize,
+    /// Moves an optimal solution from `board` takes to get as close to the solved board
+    pub optimal: usize,
+}
+
+impl Segment {
+    pub fn lost(&self) -> usize {
+        self.played - self.optimal
+    }
+}
+
+/// A solve compared to the optimal path, with its worst segments first
+#[derive(Debug, Clone, PartialEq)]
+pub struct EfficiencyReport {
+    pub moves: usize,
+    pub optimal: usize,
+    pub segments: Vec<Segment>,
+}
+
+impl EfficiencyReport {
+    /// `distances` holds the optimal distance of each board of the solve, one more than the moves
+    pub fn from_distances(boards: &[Board], distances: &[u32]) -> Self {
+        // A move is lost when it takes the board further from the solution
+        let lost_moves = (0..distances.len().saturating_sub(1)).filter(|index| distances[index + 1] > distances[*index]);
+        let mut detours: Vec<(usize, usize)> = Vec::new();
+        for index in lost_moves {
+            match detours.last_mut() {
+                Some((_, end)) if index < *end + MERGE_GAP => *end = index + 1,
+                _ => detours.push((index, index + 1)),
+            }
+        }
+
+        let mut segments = detours.into_iter().filter_map(|(start, end)| {
+            let end = (end..distances.len()).find(|index| distances[*index] < distances[start])?;
+            Some(Segment { start, board: boards[start].clone(), played: end - start, optimal: (distances[start] - distances[end]) as usize })
+        }).collect::<Vec<Segment>>();
+        segments.sort_by_key(|segment| Reverse(segment.lost()));
+        segments.truncate(WORST_SEGMENTS);
+        Self { moves: distances.len().saturating_sub(1), optimal: distances.first().copied().unwrap_or(0) as usize, segments }
+    }
+
+    /// Solves every board of the solve, `None` if one is too hard for the node budget
+    pub fn analyze(start: &Board, moves: &[TileCoordinates]) -> Option<Self> {
+        let mut boards = vec![start.clone()];
+        for tile in moves {
+            let mut board = boards[boards.len() - 1].clone();
+            board.slide_tile(*tile);
+            boards.push(board);
+        }
+        let distances = boards.iter()
+            .map(|board| solver::solve(board, MAX_NODES).map(|solution| solution.len() as u32))
+            .collect::<Option<Vec<u32>>>()?;
+        Some(Self::from_distances(&boards, &distances))
+    }
+
+    fn format(&self) -> String {
+        let mut text = format!("{} moves, {} were enough", self.moves, self.optimal);
+        for segment in &self.segments {
+            text.push_str(&format!(
+                "\n\nMoves {} to {}: could have done it in {} instead of {}\n{}",
+                segment.start + 1,
+                segment.start + segment.played,
+                segment.optimal,
+                segment.played,
+                board_lines(&segment.board),
+            ));
+        }
+        text
+    }
+}
+
+fn board_lines(board: &Board) -> String {
+    board.tiles().chunks(board.size()).map(|row| {
+        row.iter()
+            .map(|value| if *value as usize == board.size() * board.size() { " .".to_string() } else { format!("{value:>2}") })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }).collect::<Vec<String>>().join("\n")
+}
+
+/// Shuffled board and moves of the game in progress, `None` once the auto-solver took over
+#[derive(Resource, Default)]
+struct SolveRecording {
+    start: Option<Board>,
+    moves: Vec<TileCoordinates>,
+}
+
+#[derive(Resource)]
+struct EfficiencyTask(Task<Option<EfficiencyReport>>);
+
+#[derive(Component)]
+struct EfficiencyText;
+
+fn setup_efficiency_report(
+    mut commands: Commands,
+) {
+    commands.spawn((TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: 20.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    ).with_style(Style {
+        position_type: PositionType::Absolute,
+        right: Val::Px(10.),
+        bottom: Val::Percent(22.),
+        padding: UiRect::all(Val::Px(10.)),
+        display: Display::None,
+        ..default()
+    }).with_background_color(Color::rgba(0., 0., 0., 0.7)), EfficiencyText));
+}
+
+fn start_solve_recording(
+    mut commands: Commands,
+    mut recording: ResMut<SolveRecording>,
+    taquin: Res<Taquin>,
+    mut text_query: Query<&mut Style, With<EfficiencyText>>,
+) {
+    recording.start = Some(taquin.board());
+    recording.moves.clear();
+    commands.remove_resource::<EfficiencyTask>();
+    for mut style in text_query.iter_mut() {
+        style.display = Display::None;
+    }
+}
+
+fn record_solve_moves(
+    mut recording: ResMut<SolveRecording>,
+    mut tile_moved_events: EventReader<TileMoved>,
+) {
+    recording.moves.extend(tile_moved_events.read().map(|event| event.from));
+}
+
+fn drop_solve_recording(
+    mut recording: ResMut<SolveRecording>,
+) {
+    recording.start = None;
+}
+
+/// Solving every board of a long solve takes a while, it happens off the frame
+fn analyze_solve(
+    mut commands: Commands,
+    mut recording: ResMut<SolveRecording>,
+) {
+    let Some(start) = recording.start.take() else {
+        return;
+    };
+    let moves = std::mem::take(&mut recording.moves);
+    let task = AsyncComputeTaskPool::get().spawn(async move { EfficiencyReport::analyze(&start, &moves) });
+    commands.insert_resource(EfficiencyTask(task));
+}
+
+fn receive_efficiency_report(
+    mut commands: Commands,
+    mut task: ResMut<EfficiencyTask>,
+    mut text_query: Query<(&mut Text, &mut Style), With<EfficiencyText>>,
+) {
+    if !task.0.is_finished() {
+        return;
+    }
+    commands.remove_resource::<EfficiencyTask>();
+    let Some(report) = block_on(&mut task.0) else {
+        info!("solve too hard to analyze");
+        return;
+    };
+    info!(moves = report.moves, optimal = report.optimal, segments = report.segments.len(), "solve analyzed");
+    for (mut text, mut style) in text_query.iter_mut() {
+        text.sections[0].value = report.format();
+        style.display = Display::DEFAULT;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{efficiency::EfficiencyReport, solver::Board};
+
+    #[test]
+    fn test_worst_segments() {
+        let distances = [11, 10, 11, 10, 9, 10, 9, 8, 9, 8, 7, 8, 7, 6, 5, 6, 5, 4, 3, 2, 1, 0];
+        let boards = vec![Board::solved(3); distances.len()];
+        let report = EfficiencyReport::from_distances(&boards, &distances);
+        assert_eq!((report.moves, report.optimal), (21, 11));
+        let segments = report.segments.iter().map(|segment| (segment.start, segment.played, segment.optimal)).collect::<Vec<_>>();
+        // The first four lost moves are close enough to be one segment
+        assert_eq!(segments, vec![(1, 10, 2), (14, 3, 1)]);
+        assert!(report.segments.iter().all(|segment| segment.lost() > 0));
+    }
+}
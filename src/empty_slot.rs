@@ -0,0 +1,111 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{marker::Markers, taquin::Taquin, tile::EmptyTile, tile_mesh::TileThickness};
+
+const TEXTURE_SIZE: usize = 64;
+/// Share of the side over which the walls of the hole darken towards its edges
+const OCCLUSION_WIDTH: f32 = 0.25;
+/// Opacity of the shadow in the middle of the hole, and along its edges
+const CENTER_SHADE: f32 = 0.55;
+const EDGE_SHADE: f32 = 0.9;
+
+pub struct EmptySlotPlugin;
+
+impl Plugin for EmptySlotPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HoleMaterial>()
+            .add_systems(Update, spawn_empty_slot_holes);
+    }
+}
+
+/// Shadowed recess shared by every empty slot
+#[derive(Resource)]
+struct HoleMaterial(Handle<StandardMaterial>);
+
+impl FromWorld for HoleMaterial {
+    fn from_world(world: &mut World) -> Self {
+        let texture = world.resource_mut::<Assets<Image>>().add(hole_texture());
+        Self(world.resource_mut::<Assets<StandardMaterial>>().add(StandardMaterial {
+            base_color_texture: Some(texture),
+            alpha_mode: AlphaMode::Blend,
+            perceptual_roughness: 1.,
+            reflectance: 0.,
+            ..default()
+        }))
+    }
+}
+
+/// Dark recess at the back of the empty slot, a child of the empty tile so it goes wherever the slot does
+#[derive(Component)]
+struct EmptySlotHole;
+
+/// Darkest along the edges, as the walls of a hole would shade it
+fn hole_shade(u: f32, v: f32) -> f32 {
+    let edge = u.min(1. - u).min(v).min(1. - v);
+    let t = (edge / OCCLUSION_WIDTH).clamp(0., 1.);
+    let smooth = t * t * (3. - 2. * t);
+    EDGE_SHADE + (CENTER_SHADE - EDGE_SHADE) * smooth
+}
+
+fn hole_texture() -> Image {
+    let texture_data = (0..TEXTURE_SIZE * TEXTURE_SIZE).flat_map(|index| {
+        let (u, v) = ((index % TEXTURE_SIZE) as f32 + 0.5, (index / TEXTURE_SIZE) as f32 + 0.5);
+        let shade = hole_shade(u / TEXTURE_SIZE as f32, v / TEXTURE_SIZE as f32);
+        [8, 6, 10, (shade * 255.) as u8]
+    }).collect();
+    Image::new(
+        Extent3d {
+            width: TEXTURE_SIZE as u32,
+            height: TEXTURE_SIZE as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        texture_data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+/// The empty tile of a new board is made visible to carry its hole, sunk to the back of the tiles.
+/// Seen from behind the frame the hole is culled, the numbers on the back of the tiles stay readable
+fn spawn_empty_slot_holes(
+    mut commands: Commands,
+    taquin: Res<Taquin>,
+    markers: Res<Markers>,
+    thickness: Res<TileThickness>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material: Res<HoleMaterial>,
+    empty_query: Query<Entity, Added<EmptyTile>>,
+) {
+    if empty_query.is_empty() {
+        return;
+    }
+    let mesh = meshes.add(shape::Quad::new(markers.tile_size(taquin.width, taquin.height)).into());
+    for entity in empty_query.iter() {
+        commands.entity(entity)
+            .insert((VisibilityBundle::default(), GlobalTransform::default()))
+            .with_children(|parent| {
+                parent.spawn((PbrBundle {
+                    mesh: mesh.clone(),
+                    material: material.0.clone(),
+                    transform: Transform::from_xyz(0., 0., -thickness.0),
+                    ..default()
+                }, EmptySlotHole));
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::empty_slot::{hole_shade, CENTER_SHADE, EDGE_SHADE};
+
+    #[test]
+    fn test_hole_shade() {
+        assert_eq!(hole_shade(0., 0.5), EDGE_SHADE);
+        assert_eq!(hole_shade(0.5, 0.5), CENTER_SHADE);
+        assert!(hole_shade(0.1, 0.5) > hole_shade(0.2, 0.5));
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::VecDeque;
+
+use bevy::{core::FrameCount, prelude::*};
+
+use crate::{
+    auto_solve::{AutoSolveStarted, SolverTable},
+    hints::{HintGiven, HintRequested},
+    taquin::{TaquinAction, TaquinFailed, TaquinShuffled, TaquinSolved, TileMoved},
+    tile::{TileCoordinates, TileSelected},
+    AppState,
+};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F6;
+const FILTER_KEY: KeyCode = KeyCode::F7;
+/// Entries kept, the oldest ones go first
+const CAPACITY: usize = 200;
+/// Entries on screen, the latest ones
+const VISIBLE_LINES: usize = 20;
+
+pub struct EventLogPlugin;
+
+impl Plugin for EventLogPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<EventLog>()
+            .add_systems(Startup, setup_event_log)
+            .add_systems(Update, toggle_event_log)
+            // Read last so every event of the frame is in, whichever system sent it
+            .add_systems(Last, (
+                log_input_events,
+                log_logic_events,
+                log_solver_events,
+                log_state_transitions.run_if(state_changed::<AppState>()),
+                update_event_log.run_if(resource_changed::<EventLog>()),
+            ).chain());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogCategory {
+    /// What the player asked for, and the selection it moved
+    Input,
+    /// Moves, shuffles and the end of games
+    Logic,
+    Solver,
+    State,
+}
+
+impl LogCategory {
+    const ALL: [LogCategory; 4] = [LogCategory::Input, LogCategory::Logic, LogCategory::Solver, LogCategory::State];
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct LogEntry {
+    frame: u32,
+    category: LogCategory,
+    text: String,
+}
+
+/// Game events in the order they were read, F6 shows them and F7 cycles through the categories shown
+#[derive(Resource, Debug, Default)]
+struct EventLog {
+    entries: VecDeque<LogEntry>,
+    /// Category shown, all of them when none
+    filter: Option<LogCategory>,
+    visible: bool,
+}
+
+impl EventLog {
+    fn push(&mut self, frame: &FrameCount, category: LogCategory, text: String) {
+        if self.entries.len() == CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry { frame: frame.0, category, text });
+    }
+
+    /// The latest `count` entries passing the filter, oldest first
+    fn shown(&self, count: usize) -> Vec<&LogEntry> {
+        let mut shown = self.entries.iter()
+            .rev()
+            .filter(|entry| self.filter.is_none() || self.filter == Some(entry.category))
+            .take(count)
+            .collect::<Vec<&LogEntry>>();
+        shown.reverse();
+        shown
+    }
+
+    fn next_filter(&mut self) {
+        self.filter = match self.filter {
+            None => Some(LogCategory::ALL[0]),
+            Some(filter) => LogCategory::ALL.iter().skip_while(|category| **category != filter).nth(1).copied(),
+        };
+    }
+}
+
+#[derive(Component)]
+struct EventLogText;
+
+fn setup_event_log(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 14.0,
+                color: Color::YELLOW,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.),
+            bottom: Val::Px(10.),
+            padding: UiRect::all(Val::Px(6.)),
+            display: Display::None,
+            ..default()
+        })
+        .with_background_color(Color::rgba(0., 0., 0., 0.7)),
+        EventLogText,
+    ));
+}
+
+fn toggle_event_log(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut log: ResMut<EventLog>,
+) {
+    if keyboard_input.just_released(TOGGLE_KEY) {
+        log.visible = !log.visible;
+    }
+    if keyboard_input.just_released(FILTER_KEY) {
+        log.next_filter();
+    }
+}
+
+fn log_input_events(
+    mut log: ResMut<EventLog>,
+    frame: Res<FrameCount>,
+    mut action_events: EventReader<TaquinAction>,
+    mut hint_events: EventReader<HintRequested>,
+    selection_query: Query<&TileCoordinates, Added<TileSelected>>,
+) {
+    for action in action_events.read() {
+        log.push(&frame, LogCategory::Input, format!("{action:?}"));
+    }
+    for _ in hint_events.read() {
+        log.push(&frame, LogCategory::Input, "HintRequested".to_string());
+    }
+    for coordinates in selection_query.iter() {
+        log.push(&frame, LogCategory::Input, format!("SelectionChanged to ({}, {})", coordinates.i, coordinates.j));
+    }
+}
+
+fn log_logic_events(
+    mut log: ResMut<EventLog>,
+    frame: Res<FrameCount>,
+    mut moved_events: EventReader<TileMoved>,
+    mut shuffled_events: EventReader<TaquinShuffled>,
+    mut solved_events: EventReader<TaquinSolved>,
+    mut failed_events: EventReader<TaquinFailed>,
+) {
+    for moved in moved_events.read() {
+        log.push(&frame, LogCategory::Logic, format!(
+            "TileMoved ({}, {}) to ({}, {}), move {}",
+            moved.from.i, moved.from.j, moved.to.i, moved.to.j, moved.moves,
+        ));
+    }
+    for _ in shuffled_events.read() {
+        log.push(&frame, LogCategory::Logic, "TaquinShuffled".to_string());
+    }
+    for _ in solved_events.read() {
+        log.push(&frame, LogCategory::Logic, "TaquinSolved".to_string());
+    }
+    for _ in failed_events.read() {
+        log.push(&frame, LogCategory::Logic, "TaquinFailed".to_string());
+    }
+}
+
+fn log_solver_events(
+    mut log: ResMut<EventLog>,
+    frame: Res<FrameCount>,
+    mut hint_events: EventReader<HintGiven>,
+    mut started_events: EventReader<AutoSolveStarted>,
+    table: Res<SolverTable>,
+) {
+    for HintGiven(tile) in hint_events.read() {
+        log.push(&frame, LogCategory::Solver, format!("HintGiven ({}, {})", tile.i, tile.j));
+    }
+    for _ in started_events.read() {
+        log.push(&frame, LogCategory::Solver, "AutoSolveStarted".to_string());
+    }
+    if table.is_changed() && !table.is_added() {
+        if let Some(stats) = table.last_stats {
+            log.push(&frame, LogCategory::Solver, format!("search: {} nodes, {:.1}% hits", stats.nodes, stats.hit_rate() * 100.));
+        }
+    }
+}
+
+fn log_state_transitions(
+    mut log: ResMut<EventLog>,
+    frame: Res<FrameCount>,
+    state: Res<State<AppState>>,
+) {
+    log.push(&frame, LogCategory::State, format!("entered {:?}", state.get()));
+}
+
+fn update_event_log(
+    log: Res<EventLog>,
+    mut text_query: Query<(&mut Text, &mut Style), With<EventLogText>>,
+) {
+    for (mut text, mut style) in text_query.iter_mut() {
+        style.display = if log.visible { Display::DEFAULT } else { Display::None };
+        if !log.visible {
+            continue;
+        }
+        let filter = log.filter.map_or("all".to_string(), |filter| format!("{filter:?}"));
+        let lines = log.shown(VISIBLE_LINES).into_iter()
+            .map(|entry| format!("{:>6} {:<6} {}", entry.frame, format!("{:?}", entry.category), entry.text))
+            .collect::<Vec<String>>();
+        text.sections[0].value = format!("event log: {filter} (F7)\n{}", lines.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::core::FrameCount;
+
+    use crate::event_log::{EventLog, LogCategory, CAPACITY};
+
+    #[test]
+    fn test_event_log() {
+        let mut log = EventLog::default();
+        for frame in 0..CAPACITY as u32 + 10 {
+            let category = if frame % 2 == 0 { LogCategory::Input } else { LogCategory::Logic };
+            log.push(&FrameCount(frame), category, frame.to_string());
+        }
+        assert_eq!(log.entries.len(), CAPACITY);
+        assert_eq!(log.entries[0].frame, 10);
+
+        let texts = |log: &EventLog| log.shown(2).iter().map(|entry| entry.text.clone()).collect::<Vec<String>>();
+        assert_eq!(texts(&log), ["208", "209"]);
+        log.next_filter();
+        assert_eq!(log.filter, Some(LogCategory::Input));
+        assert_eq!(texts(&log), ["206", "208"]);
+        for _ in LogCategory::ALL {
+            log.next_filter();
+        }
+        assert_eq!(log.filter, None);
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::{app::AppExit, prelude::*, window::WindowCloseRequested};
+
+use crate::{focus::FocusScope, i18n::Localized, replay::ReplayPlayback, taquin::Taquin, AppState};
+
+pub struct ExitPlugin;
+
+impl Plugin for ExitPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<Shutdown>()
+            .add_event::<ExitRequested>()
+            .add_event::<ExitConfirmed>()
+            .add_systems(Update, (
+                escape_to_exit,
+                request_exit.run_if(not(resource_exists::<ExitConfirmation>())),
+                confirm_exit.run_if(resource_exists::<ExitConfirmation>()),
+            ).chain())
+            .add_systems(Last, exit_on_shutdown.run_if(on_event::<Shutdown>()));
+    }
+}
+
+/// Sent once quitting is confirmed and the session summary closed, anything still unsaved must be written on it
+/// as the app exits at the end of the frame
+#[derive(Event, Default)]
+pub struct Shutdown;
+
+/// Asks to quit, confirmed first when a game is in progress
+#[derive(Event, Default)]
+pub struct ExitRequested;
+
+/// Sent once the player chose to quit, the session summary is shown before the `Shutdown`
+#[derive(Event, Default)]
+pub struct ExitConfirmed;
+
+/// The "quit anyway?" dialog being shown
+#[derive(Resource)]
+pub struct ExitConfirmation(Entity);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+enum ExitButton {
+    Quit,
+    Stay,
+}
+
+/// Escape pauses a game being played, see `pause`, and quits anywhere else
+fn escape_to_exit(
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<State<AppState>>,
+    replay: Option<Res<ReplayPlayback>>,
+    confirmation: Option<Res<ExitConfirmation>>,
+    mut exit_events: EventWriter<ExitRequested>,
+) {
+    let pauses = matches!(state.get(), AppState::Running | AppState::Paused | AppState::Interlude | AppState::Editor) && replay.is_none();
+    if keyboard_input.just_released(KeyCode::Escape) && !pauses && confirmation.is_none() {
+        exit_events.send_default();
+    }
+}
+
+fn request_exit(
+    mut commands: Commands,
+    taquin: Res<Taquin>,
+    mut close_events: EventReader<WindowCloseRequested>,
+    mut exit_events: EventReader<ExitRequested>,
+    mut confirmed_events: EventWriter<ExitConfirmed>,
+) {
+    let close_requested = close_events.read().count() + exit_events.read().count() > 0;
+    if !close_requested {
+        return;
+    }
+    if !taquin.is_shuffled {
+        confirmed_events.send_default();
+        return;
+    }
+
+    let dialog = commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(20.),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(10),
+        ..default()
+    }, FocusScope)).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "Quit? The game is saved to be resumed later\nY to quit, N to keep playing",
+            TextStyle {
+                font_size: 50.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ).with_text_alignment(TextAlignment::Center), Localized::new("Quit? The game is saved to be resumed later\nY to quit, N to keep playing")));
+        for (label, button) in [("Quit", ExitButton::Quit), ("Keep playing", ExitButton::Stay)] {
+            parent.spawn((ButtonBundle {
+                style: Style {
+                    width: Val::Px(300.),
+                    padding: UiRect::axes(Val::Px(20.), Val::Px(8.)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                ..default()
+            }, button)).with_children(|parent| {
+                parent.spawn((TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font_size: 50.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ), Localized::new(label)));
+            });
+        }
+    }).id();
+    commands.insert_resource(ExitConfirmation(dialog));
+}
+
+fn confirm_exit(
+    mut commands: Commands,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    button_query: Query<(&Interaction, &ExitButton), Changed<Interaction>>,
+    confirmation: Res<ExitConfirmation>,
+    mut confirmed_events: EventWriter<ExitConfirmed>,
+) {
+    let pressed = button_query.iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| *button);
+    if keyboard_input.just_released(KeyCode::Y) || pressed == Some(ExitButton::Quit) {
+        confirmed_events.send_default();
+    } else if !keyboard_input.just_released(KeyCode::N) && !keyboard_input.clear_just_released(KeyCode::Escape) && pressed.is_none() {
+        return;
+    }
+    commands.entity(confirmation.0).despawn_recursive();
+    commands.remove_resource::<ExitConfirmation>();
+}
+
+fn exit_on_shutdown(
+    mut exit_events: EventWriter<AppExit>,
+) {
+    info!("exiting");
+    exit_events.send(AppExit);
+}
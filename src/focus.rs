@@ -0,0 +1,192 @@
+use bevy::{input::InputSystem, prelude::*, ui::UiSystem};
+
+const OUTLINE_WIDTH: f32 = 3.;
+const OUTLINE_COLOR: Color = Color::rgb(1., 0.85, 0.2);
+/// Arrow keys and the D-pad, with the way they move on screen, UI coordinates going down
+const DIRECTIONS: [(KeyCode, GamepadButtonType, Vec2); 4] = [
+    (KeyCode::Left, GamepadButtonType::DPadLeft, Vec2::NEG_X),
+    (KeyCode::Right, GamepadButtonType::DPadRight, Vec2::X),
+    (KeyCode::Up, GamepadButtonType::DPadUp, Vec2::NEG_Y),
+    (KeyCode::Down, GamepadButtonType::DPadDown, Vec2::Y),
+];
+
+pub struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Focus>()
+            .add_systems(PreUpdate, navigate_focus.after(InputSystem).after(UiSystem::Focus))
+            .add_systems(Update, outline_focus.run_if(resource_changed::<Focus>()));
+    }
+}
+
+/// Root of a screen or dialog whose buttons take the focus. The one with the highest global z-index owns the
+/// navigation keys, and lets them through when it has no button, as the statistics table
+#[derive(Component)]
+pub struct FocusScope;
+
+/// Button focused, moved with Tab and the arrows or the D-pad, pressed with Return, Space or the south button
+#[derive(Resource, Debug, Default)]
+pub struct Focus(pub Option<Entity>);
+
+/// Button reached by Tab, in reading order, backward with Shift
+fn next_in_order(buttons: &[(Entity, Vec2)], focused: Option<Entity>, backward: bool) -> Option<Entity> {
+    let len = buttons.len();
+    let index = match (focused.and_then(|focused| buttons.iter().position(|(entity, _)| *entity == focused)), backward) {
+        (Some(index), false) => (index + 1) % len.max(1),
+        (Some(index), true) => (index + len - 1) % len.max(1),
+        (None, false) => 0,
+        (None, true) => len.saturating_sub(1),
+    };
+    buttons.get(index).map(|(entity, _)| *entity)
+}
+
+/// Closest button that way, the ones off its axis counting double
+fn nearest_in_direction(buttons: &[(Entity, Vec2)], from: Vec2, direction: Vec2) -> Option<Entity> {
+    buttons.iter()
+        .filter_map(|(entity, position)| {
+            let offset = *position - from;
+            let along = offset.dot(direction);
+            (along > 0.5).then(|| (*entity, along + 2. * offset.perp_dot(direction).abs()))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+/// Consumes the keys it acts on so the screen does not act on them too. Pressing goes through `Interaction`,
+/// as a click would, and is released on the next frame
+#[allow(clippy::too_many_arguments)]
+pub fn navigate_focus(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut gamepad_input: ResMut<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    mut focus: ResMut<Focus>,
+    scope_query: Query<(Entity, Option<&ZIndex>), With<FocusScope>>,
+    button_query: Query<(Entity, &Node, &GlobalTransform, &InheritedVisibility), With<Button>>,
+    parent_query: Query<&Parent>,
+    mut interaction_query: Query<&mut Interaction>,
+    mut pressed: Local<Option<Entity>>,
+) {
+    if let Some(mut interaction) = pressed.take().and_then(|entity| interaction_query.get_mut(entity).ok()) {
+        if *interaction == Interaction::Pressed {
+            *interaction = Interaction::None;
+        }
+    }
+    let top_scope = scope_query.iter()
+        .max_by_key(|(_, z_index)| match z_index {
+            Some(ZIndex::Global(z)) => *z,
+            _ => 0,
+        })
+        .map(|(entity, _)| entity);
+    let mut buttons = button_query.iter()
+        .filter(|(entity, node, _, visibility)| {
+            visibility.get() && node.size() != Vec2::ZERO
+                && parent_query.iter_ancestors(*entity).any(|ancestor| Some(ancestor) == top_scope)
+        })
+        .map(|(entity, _, transform, _)| (entity, transform.translation().truncate()))
+        .collect::<Vec<(Entity, Vec2)>>();
+    buttons.sort_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+    if buttons.iter().all(|(entity, _)| focus.0 != Some(*entity)) && focus.0.is_some() {
+        focus.0 = None;
+    }
+    if buttons.is_empty() {
+        return;
+    }
+
+    let mut gamepad_pressed = |button_type| gamepads.iter()
+        .any(|gamepad| gamepad_input.clear_just_pressed(GamepadButton::new(gamepad, button_type)));
+    if keyboard_input.clear_just_released(KeyCode::Tab) {
+        let backward = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        focus.0 = next_in_order(&buttons, focus.0, backward);
+    }
+    let current = focus.0.and_then(|focused| buttons.iter().find(|(entity, _)| *entity == focused)).map(|(_, position)| *position);
+    for (key_code, button_type, direction) in DIRECTIONS {
+        // The arrows are left to the screen until something has the focus, the D-pad takes it
+        let moved = match current {
+            Some(_) => {
+                // The board moves on the press, it is kept from it as well
+                keyboard_input.clear_just_pressed(key_code);
+                keyboard_input.clear_just_released(key_code) | gamepad_pressed(button_type)
+            },
+            None => gamepad_pressed(button_type),
+        };
+        if !moved {
+            continue;
+        }
+        focus.0 = match current {
+            Some(from) => nearest_in_direction(&buttons, from, direction).or(focus.0),
+            None => next_in_order(&buttons, None, false),
+        };
+        break;
+    }
+
+    let Some(focused) = focus.0.filter(|_| current.is_some()) else {
+        return;
+    };
+    keyboard_input.clear_just_pressed(KeyCode::Space);
+    let activated = keyboard_input.clear_just_released(KeyCode::Return) | keyboard_input.clear_just_released(KeyCode::Space)
+        | gamepad_pressed(GamepadButtonType::South);
+    if let Some(mut interaction) = interaction_query.get_mut(focused).ok().filter(|_| activated) {
+        *interaction = Interaction::Pressed;
+        *pressed = Some(focused);
+    }
+}
+
+/// Only the sides in pixels change, as the button paddings are
+fn grow_padding(padding: UiRect, by: f32) -> UiRect {
+    let grow = |val| match val {
+        Val::Px(px) => Val::Px((px + by).max(0.)),
+        val => val,
+    };
+    UiRect::new(grow(padding.left), grow(padding.right), grow(padding.top), grow(padding.bottom))
+}
+
+fn outline_focus(
+    focus: Res<Focus>,
+    mut button_query: Query<(&mut Style, &mut BorderColor), With<Button>>,
+    mut outlined: Local<Option<Entity>>,
+) {
+    if let Some((mut style, mut border)) = outlined.take().and_then(|entity| button_query.get_mut(entity).ok()) {
+        style.border = UiRect::default();
+        style.padding = grow_padding(style.padding, OUTLINE_WIDTH);
+        border.0 = Color::NONE;
+    }
+    let Some((mut style, mut border)) = focus.0.and_then(|entity| button_query.get_mut(entity).ok()) else {
+        return;
+    };
+    // The padding gives way to the outline so the layout does not move
+    style.border = UiRect::all(Val::Px(OUTLINE_WIDTH));
+    style.padding = grow_padding(style.padding, -OUTLINE_WIDTH);
+    border.0 = OUTLINE_COLOR;
+    *outlined = focus.0;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::focus::{nearest_in_direction, next_in_order};
+
+    #[test]
+    fn test_next_in_order() {
+        let buttons = [(Entity::from_raw(1), Vec2::ZERO), (Entity::from_raw(2), Vec2::X)];
+        assert_eq!(next_in_order(&buttons, None, false), Some(Entity::from_raw(1)));
+        assert_eq!(next_in_order(&buttons, None, true), Some(Entity::from_raw(2)));
+        assert_eq!(next_in_order(&buttons, Some(Entity::from_raw(2)), false), Some(Entity::from_raw(1)));
+        assert_eq!(next_in_order(&[], None, false), None);
+    }
+
+    #[test]
+    fn test_nearest_in_direction() {
+        let buttons = [
+            (Entity::from_raw(1), Vec2::new(0., 0.)),
+            (Entity::from_raw(2), Vec2::new(100., 0.)),
+            (Entity::from_raw(3), Vec2::new(60., 80.)),
+            (Entity::from_raw(4), Vec2::new(0., 100.)),
+        ];
+        assert_eq!(nearest_in_direction(&buttons, Vec2::ZERO, Vec2::X), Some(Entity::from_raw(2)));
+        assert_eq!(nearest_in_direction(&buttons, Vec2::ZERO, Vec2::Y), Some(Entity::from_raw(4)));
+        assert_eq!(nearest_in_direction(&buttons, Vec2::ZERO, Vec2::NEG_X), None);
+    }
+}
@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+
+use crate::{layout::checked_markers, marker::Markers, table::FrameMaterial, taquin::Taquin};
+
+/// Largest inner side of the frame, the board is fitted in it with square tiles
+const MAX_INNER_SIZE: Vec2 = Vec2::new(16., 16.5);
+/// Width of the border, the bottom one standing on the table
+const BORDER: f32 = 2.;
+/// The borders go from the back of the frame at -1 to its front at 1
+const DEPTH: f32 = 2.;
+
+/// Frame around the board, built from boxes for its size
+#[derive(Component)]
+pub struct Frame;
+
+/// Corners of the inside of the frame for a `width` by `height` board, centered on the table
+pub fn frame_markers(width: i8, height: i8) -> Markers {
+    let tile = (MAX_INNER_SIZE.x / width.max(1) as f32).min(MAX_INNER_SIZE.y / height.max(1) as f32);
+    let (half_width, inner_height) = (width as f32 * tile / 2., height as f32 * tile);
+    let z = 0.;
+    Markers {
+        tl: Vec3::new(-half_width, BORDER + inner_height, z),
+        tr: Vec3::new(half_width, BORDER + inner_height, z),
+        bl: Vec3::new(-half_width, BORDER, z),
+        br: Vec3::new(half_width, BORDER, z),
+    }
+}
+
+/// Four borders around `markers`, in their plane and moved by `offset`. Their meshes are tinted with `FrameTint`.
+/// The frame is open at the back, the numbers on the back of the tiles show from behind
+pub fn spawn_frame(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    markers: &Markers,
+    offset: Vec3,
+) -> Entity {
+    let (inner_width, inner_height) = (markers.inner_width(), markers.inner_height());
+    let parts = [
+        // Left and right borders run the whole height, the top and bottom ones fit between them
+        (Vec3::new(-(inner_width + BORDER) / 2., 0., 0.), Vec3::new(BORDER, inner_height + 2. * BORDER, DEPTH)),
+        (Vec3::new((inner_width + BORDER) / 2., 0., 0.), Vec3::new(BORDER, inner_height + 2. * BORDER, DEPTH)),
+        (Vec3::new(0., (inner_height + BORDER) / 2., 0.), Vec3::new(inner_width, BORDER, DEPTH)),
+        (Vec3::new(0., -(inner_height + BORDER) / 2., 0.), Vec3::new(inner_width, BORDER, DEPTH)),
+    ];
+    let material = materials.add(StandardMaterial::default());
+    commands.spawn((SpatialBundle::from_transform(Transform::from_translation(markers.center() + offset).with_rotation(markers.rotation())), Frame)).with_children(|parent| {
+        for (translation, size) in parts {
+            parent.spawn((PbrBundle {
+                mesh: meshes.add(shape::Box::new(size.x, size.y, size.z).into()),
+                material: material.clone(),
+                transform: Transform::from_translation(translation),
+                ..default()
+            }, FrameMaterial));
+        }
+    }).id()
+}
+
+/// Sizes the frame for the board and sets the markers from it, the frame is kept while the size does not change
+pub fn build_frame(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    taquin: Res<Taquin>,
+    mut markers: ResMut<Markers>,
+    frame_query: Query<Entity, With<Frame>>,
+) {
+    let fitted = checked_markers(frame_markers(taquin.width, taquin.height), taquin.width, taquin.height);
+    if !frame_query.is_empty() && *markers == fitted {
+        return;
+    }
+    for entity in frame_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    info!(width = taquin.width, height = taquin.height, "frame built");
+    spawn_frame(&mut commands, &mut meshes, &mut materials, &fitted, Vec3::ZERO);
+    *markers = fitted;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frame::{frame_markers, BORDER, MAX_INNER_SIZE};
+
+    #[test]
+    fn test_frame_markers() {
+        let square = frame_markers(4, 4);
+        assert_eq!(square.inner_width(), square.inner_height());
+        assert_eq!(square.inner_width(), MAX_INNER_SIZE.x);
+        assert_eq!(square.bl.y, BORDER);
+
+        // Rectangular boards keep square tiles, the frame shrinks around them
+        let wide = frame_markers(5, 3);
+        let tile = wide.tile_size(5, 3);
+        assert!((tile.x - tile.y).abs() < 1e-5);
+        assert_eq!(wide.inner_width(), MAX_INNER_SIZE.x);
+        assert!(wide.inner_height() < MAX_INNER_SIZE.y);
+        assert_eq!(wide.center().x, 0.);
+
+        let tall = frame_markers(2, 6);
+        assert_eq!(tall.inner_height(), MAX_INNER_SIZE.y);
+        assert!(tall.inner_width() < MAX_INNER_SIZE.x);
+    }
+}
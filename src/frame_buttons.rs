@@ -0,0 +1,183 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    auto_solve::AutoSolvePlayback,
+    focus::FocusScope,
+    graphics::MainCamera,
+    hints::HintRequested,
+    marker::Markers,
+    picking::{cursor_ray, pick_tile, screen_ray},
+    replay::ReplayPlayback,
+    taquin::{NextPictureRequested, TaquinAction},
+    AppState,
+};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F5;
+/// Side of a button and gap between the frame and the buttons, relative to the inner height of the frame
+const BUTTON_SIZE: f32 = 0.12;
+const BUTTON_GAP: f32 = 0.1;
+/// Buttons grow by this much under the cursor
+const HOVER_SCALE: f32 = 1.15;
+
+pub struct FrameButtonsPlugin;
+
+impl Plugin for FrameButtonsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<DiegeticUi>()
+            .add_systems(Update, toggle_diegetic_ui)
+            .add_systems(Update, (
+                (despawn_frame_buttons, spawn_frame_buttons).chain().run_if(resource_changed::<DiegeticUi>().or_else(resource_changed::<Markers>())),
+                press_frame_buttons.run_if(not(resource_exists::<ReplayPlayback>()).and_then(not(resource_exists::<AutoSolvePlayback>()))),
+            ).chain().run_if(in_state(AppState::Running)))
+            .add_systems(Update, (
+                hide_hud.run_if(resource_equals(DiegeticUi(true))),
+                show_hud.run_if(resource_equals(DiegeticUi(false)).and_then(resource_changed::<DiegeticUi>())),
+            ));
+    }
+}
+
+/// Shuffle, hint and picture buttons on the side of the frame, clicked in the scene, while the HUD is hidden.
+/// Menus and dialogs stay on screen. `--diegetic` turns it on, F5 toggles it
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DiegeticUi(pub bool);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameButton {
+    Shuffle,
+    Hint,
+    Picture,
+}
+
+impl FrameButton {
+    const ALL: [FrameButton; 3] = [FrameButton::Shuffle, FrameButton::Hint, FrameButton::Picture];
+
+    fn color(&self) -> Color {
+        match self {
+            FrameButton::Shuffle => Color::rgb(0.25, 0.45, 0.85),
+            FrameButton::Hint => Color::rgb(0.95, 0.75, 0.2),
+            FrameButton::Picture => Color::rgb(0.3, 0.7, 0.4),
+        }
+    }
+}
+
+/// HUD node hidden by the diegetic mode, shown again when it ends
+#[derive(Component)]
+struct HiddenHud;
+
+fn toggle_diegetic_ui(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut diegetic: ResMut<DiegeticUi>,
+) {
+    if keyboard_input.just_released(TOGGLE_KEY) {
+        diegetic.0 = !diegetic.0;
+        info!(diegetic = diegetic.0, "diegetic ui");
+    }
+}
+
+fn despawn_frame_buttons(
+    mut commands: Commands,
+    buttons_query: Query<Entity, With<FrameButton>>,
+) {
+    for entity in buttons_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Stacked down the right side of the frame, from its top corner
+fn spawn_frame_buttons(
+    mut commands: Commands,
+    diegetic: Res<DiegeticUi>,
+    markers: Res<Markers>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !diegetic.0 {
+        return;
+    }
+    let size = markers.inner_height() * BUTTON_SIZE;
+    let gap = markers.inner_height() * BUTTON_GAP;
+    let mesh = meshes.add(shape::Box::new(size, size, size / 3.).into());
+    for (index, button) in FrameButton::ALL.into_iter().enumerate() {
+        let translation = markers.tr + markers.right() * (gap + size / 2.) + markers.down() * (size / 2. + index as f32 * (size + gap / 2.)) + markers.normal() * 0.75;
+        commands.spawn((PbrBundle {
+            mesh: mesh.clone(),
+            material: materials.add(StandardMaterial {
+                base_color: button.color(),
+                perceptual_roughness: 0.4,
+                ..default()
+            }),
+            transform: Transform::from_translation(translation).with_rotation(markers.rotation()),
+            ..default()
+        }, button));
+    }
+}
+
+/// The front face of the buttons is picked as a tile would be, a click or a touch released on it presses it
+#[allow(clippy::too_many_arguments)]
+fn press_frame_buttons(
+    mouse_input: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut buttons_query: Query<(&FrameButton, &mut Transform)>,
+    markers: Res<Markers>,
+    mut action_events: EventWriter<TaquinAction>,
+    mut hint_events: EventWriter<HintRequested>,
+    mut picture_events: EventWriter<NextPictureRequested>,
+) {
+    let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    let size = markers.inner_height() * BUTTON_SIZE;
+    let faces = buttons_query.iter()
+        .map(|(button, transform)| (*button, transform.translation + markers.normal() * size / 6.))
+        .collect::<Vec<(FrameButton, Vec3)>>();
+    let hovered = cursor_ray(window, camera, camera_transform).and_then(|ray| pick_tile(ray, markers.rotation(), Vec2::splat(size), faces.iter().copied()));
+    for (button, mut transform) in buttons_query.iter_mut() {
+        let scale = Vec3::splat(if Some(*button) == hovered { HOVER_SCALE } else { 1. });
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+    }
+
+    let touched = touches.iter_just_released()
+        .filter_map(|touch| screen_ray(window, camera, camera_transform, touch.position()))
+        .filter_map(|ray| pick_tile(ray, markers.rotation(), Vec2::splat(size), faces.iter().copied()));
+    let clicked = hovered.filter(|_| mouse_input.just_released(MouseButton::Left));
+    for button in clicked.into_iter().chain(touched) {
+        info!(?button, "frame button pressed");
+        match button {
+            FrameButton::Shuffle => action_events.send(TaquinAction::Shuffle),
+            FrameButton::Hint => hint_events.send_default(),
+            FrameButton::Picture => picture_events.send_default(),
+        }
+    }
+}
+
+/// Every root UI node but the menus and dialogs, which own a focus scope
+fn hide_hud(
+    mut commands: Commands,
+    root_query: Query<Entity, (With<Node>, Without<Parent>)>,
+    scope_query: Query<(), With<FocusScope>>,
+    hidden_query: Query<(), With<HiddenHud>>,
+    mut visibility_query: Query<&mut Visibility>,
+) {
+    for entity in root_query.iter().filter(|entity| !scope_query.contains(*entity) && !hidden_query.contains(*entity)) {
+        let Ok(mut visibility) = visibility_query.get_mut(entity) else {
+            continue;
+        };
+        *visibility = Visibility::Hidden;
+        commands.entity(entity).insert(HiddenHud);
+    }
+}
+
+fn show_hud(
+    mut commands: Commands,
+    mut hud_query: Query<(Entity, &mut Visibility), With<HiddenHud>>,
+) {
+    for (entity, mut visibility) in hud_query.iter_mut() {
+        *visibility = Visibility::Inherited;
+        commands.entity(entity).remove::<HiddenHud>();
+    }
+}
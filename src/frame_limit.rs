@@ -0,0 +1,34 @@
+use std::{thread, time::Duration};
+
+use bevy::{prelude::*, utils::Instant};
+
+pub struct FrameLimitPlugin;
+
+impl Plugin for FrameLimitPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<FrameLimit>()
+            .add_systems(Last, limit_frame_rate.run_if(|limit: Res<FrameLimit>| limit.0.is_some()));
+    }
+}
+
+/// Frames per second the app is held to, on top of the vertical sync
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameLimit(pub Option<f32>);
+
+/// Sleeps away what is left of the frame budget
+fn limit_frame_rate(
+    limit: Res<FrameLimit>,
+    mut frame_end: Local<Option<Instant>>,
+) {
+    let Some(max_fps) = limit.0 else {
+        return;
+    };
+    let budget = Duration::from_secs_f32(1. / max_fps);
+    if let Some(elapsed) = frame_end.map(|end| end.elapsed()) {
+        if elapsed < budget {
+            thread::sleep(budget - elapsed);
+        }
+    }
+    *frame_end = Some(Instant::now());
+}
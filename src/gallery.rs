@@ -0,0 +1,87 @@
+use std::any::TypeId;
+
+use bevy::{asset::{LoadState, LoadedFolder}, prelude::*};
+
+use crate::TaquinSprites;
+
+/// Folder of the assets whose pictures are offered with every pack, `T` cycles through them
+const GALLERY_FOLDER: &str = "textures/taquin";
+
+pub struct GalleryPlugin;
+
+impl Plugin for GalleryPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Startup, load_gallery)
+            .add_systems(Update, collect_gallery.run_if(resource_exists::<TaquinSprites>().and_then(|gallery: Res<Gallery>| !gallery.collected)));
+    }
+}
+
+/// Pictures found in the gallery folder, after the ones of the pack
+#[derive(Resource)]
+pub struct Gallery {
+    folder: Handle<LoadedFolder>,
+    collected: bool,
+    pub images: Vec<Handle<Image>>,
+    /// File names without their extension, for the menu
+    pub names: Vec<String>,
+}
+
+/// Name of the picture at `path`, without its folder nor its extension
+fn picture_name(path: &str) -> &str {
+    let file = path.rsplit_once('/').map_or(path, |(_, file)| file);
+    file.rsplit_once('.').map_or(file, |(name, _)| name)
+}
+
+fn load_gallery(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.insert_resource(Gallery { folder: asset_server.load_folder(GALLERY_FOLDER), collected: false, images: Vec::new(), names: Vec::new() });
+}
+
+/// Adds the pictures of the folder to the pack once every one of them is loaded, in file name order
+fn collect_gallery(
+    asset_server: Res<AssetServer>,
+    folders: Res<Assets<LoadedFolder>>,
+    mut gallery: ResMut<Gallery>,
+    mut sprites: ResMut<TaquinSprites>,
+) {
+    if asset_server.get_load_state(&gallery.folder) == Some(LoadState::Failed) {
+        warn!(folder = GALLERY_FOLDER, "cannot read the picture gallery");
+        gallery.collected = true;
+        return;
+    }
+    if !asset_server.is_loaded_with_dependencies(&gallery.folder) {
+        return;
+    }
+    let Some(folder) = folders.get(&gallery.folder) else {
+        return;
+    };
+    let mut pictures = folder.handles.iter()
+        .filter(|handle| handle.type_id() == TypeId::of::<Image>())
+        .filter_map(|handle| Some((handle.path()?.path().to_string_lossy().into_owned(), handle.clone().typed::<Image>())))
+        .collect::<Vec<(String, Handle<Image>)>>();
+    pictures.sort_by(|a, b| a.0.cmp(&b.0));
+    gallery.names = pictures.iter().map(|(path, _)| picture_name(path).to_string()).collect();
+    gallery.images = pictures.into_iter().map(|(_, image)| image).collect();
+    gallery.collected = true;
+    info!(pictures = gallery.images.len(), "picture gallery loaded");
+    if !gallery.images.is_empty() {
+        sprites.images.extend(gallery.images.iter().cloned());
+        // Bakes the new pictures as well on big boards
+        sprites.applied = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gallery::picture_name;
+
+    #[test]
+    fn test_picture_name() {
+        assert_eq!(picture_name("textures/taquin/ferris.png"), "ferris");
+        assert_eq!(picture_name("sunset.large.jpg"), "sunset.large");
+        assert_eq!(picture_name("textures/taquin/plain"), "plain");
+    }
+}
@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+
+const SPEEDS: [f32; 6] = [0.25, 0.5, 0.75, 1., 1.5, 2.];
+
+pub struct GameSpeedPlugin;
+
+impl Plugin for GameSpeedPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameSpeed>()
+            .add_systems(Update, (
+                change_game_speed,
+                apply_game_speed.run_if(resource_changed::<GameSpeed>()),
+            ).chain());
+    }
+}
+
+/// Scale of the virtual clock driving tile animations and replay playback,
+/// solve times are measured on the real clock and are not affected
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct GameSpeed(f32);
+
+impl GameSpeed {
+    pub fn new(speed: f32) -> Self {
+        Self(speed.clamp(SPEEDS[0], SPEEDS[SPEEDS.len() - 1]))
+    }
+
+    fn slower(&self) -> Self {
+        Self(SPEEDS.iter().rev().copied().find(|speed| *speed < self.0).unwrap_or(self.0))
+    }
+
+    fn faster(&self) -> Self {
+        Self(SPEEDS.iter().copied().find(|speed| *speed > self.0).unwrap_or(self.0))
+    }
+}
+
+impl Default for GameSpeed {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+fn change_game_speed(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut game_speed: ResMut<GameSpeed>,
+) {
+    if keyboard_input.just_released(KeyCode::Minus) {
+        *game_speed = game_speed.slower();
+    }
+    if keyboard_input.just_released(KeyCode::Equals) {
+        *game_speed = game_speed.faster();
+    }
+}
+
+fn apply_game_speed(
+    game_speed: Res<GameSpeed>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    info!(speed = game_speed.0, "game speed changed");
+    time.set_relative_speed(game_speed.0);
+}
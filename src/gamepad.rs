@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use bevy::{
+    input::{gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest}, InputSystem},
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::{
+    focus::navigate_focus,
+    taquin::{TaquinAction, TaquinSolved},
+    tile::Direction,
+    AppState,
+};
+
+/// How far the left stick goes before it counts as a D-pad press
+const STICK_THRESHOLD: f32 = 0.6;
+const RUMBLE_DURATION: Duration = Duration::from_millis(400);
+const DPAD: [(GamepadButtonType, Direction); 4] = [
+    (GamepadButtonType::DPadLeft, Direction::Left),
+    (GamepadButtonType::DPadRight, Direction::Right),
+    (GamepadButtonType::DPadUp, Direction::Up),
+    (GamepadButtonType::DPadDown, Direction::Down),
+];
+
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(PreUpdate, read_gamepad_actions.after(InputSystem).after(navigate_focus).run_if(in_state(AppState::Running)))
+            .add_systems(Update, rumble_on_solve.run_if(on_event::<TaquinSolved>()));
+    }
+}
+
+/// Side the stick points to, along its main axis, none near the center
+fn stick_direction(stick: Vec2) -> Option<Direction> {
+    if stick.length() < STICK_THRESHOLD {
+        return None;
+    }
+    Some(match (stick.x.abs() > stick.y.abs(), stick.x > 0., stick.y > 0.) {
+        (true, true, _) => Direction::Right,
+        (true, false, _) => Direction::Left,
+        (false, _, true) => Direction::Up,
+        (false, _, false) => Direction::Down,
+    })
+}
+
+/// D-pad or left stick, south button (A, Cross) to slide, north button (Y, Triangle) to shuffle
+fn read_gamepad_actions(
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    // Direction each stick is held in, it moves the selection once per push
+    mut held: Local<HashMap<Gamepad, Direction>>,
+    mut action_events: EventWriter<TaquinAction>,
+) {
+    for gamepad in gamepads.iter() {
+        let pressed = |button_type| buttons.just_pressed(GamepadButton::new(gamepad, button_type));
+        for (button_type, direction) in DPAD {
+            if pressed(button_type) {
+                action_events.send(TaquinAction::Direction(direction));
+            }
+        }
+        if pressed(GamepadButtonType::South) {
+            action_events.send(TaquinAction::Slide);
+        }
+        if pressed(GamepadButtonType::North) {
+            action_events.send(TaquinAction::Shuffle);
+        }
+
+        let axis = |axis_type| axes.get(GamepadAxis::new(gamepad, axis_type)).unwrap_or(0.);
+        let stick = Vec2::new(axis(GamepadAxisType::LeftStickX), axis(GamepadAxisType::LeftStickY));
+        match stick_direction(stick) {
+            Some(direction) if held.get(&gamepad) != Some(&direction) => {
+                held.insert(gamepad, direction);
+                action_events.send(TaquinAction::Direction(direction));
+            },
+            Some(_) => (),
+            None => {
+                held.remove(&gamepad);
+            },
+        }
+    }
+}
+
+fn rumble_on_solve(
+    gamepads: Res<Gamepads>,
+    mut rumble_events: EventWriter<GamepadRumbleRequest>,
+) {
+    for gamepad in gamepads.iter() {
+        rumble_events.send(GamepadRumbleRequest::Add { gamepad, duration: RUMBLE_DURATION, intensity: GamepadRumbleIntensity::MAX });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::{gamepad::stick_direction, tile::Direction};
+
+    #[test]
+    fn test_stick_direction() {
+        assert_eq!(stick_direction(Vec2::new(0.2, -0.1)), None);
+        assert_eq!(stick_direction(Vec2::new(0.9, 0.3)), Some(Direction::Right));
+        assert_eq!(stick_direction(Vec2::new(-0.3, 0.8)), Some(Direction::Up));
+        assert_eq!(stick_direction(Vec2::new(0.1, -0.7)), Some(Direction::Down));
+    }
+}
@@ -0,0 +1,291 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+
+use crate::{
+    auto_solve::AutoSolvePlayback,
+    locale::Locale,
+    pause::GameClock,
+    persistence::DataPaths,
+    replay::{Replay, ReplayPlayback},
+    saved_game::GameRestored,
+    taquin::{Taquin, TaquinShuffled, TaquinSolved, TileMoved},
+    tile::{TileCoordinates, TileValue},
+    AppState,
+};
+
+/// Side in pixels of a tile of the miniature board
+const GHOST_CELL: f32 = 22.;
+
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::MainMenu), remove_ghost)
+            .add_systems(Update, (
+                start_ghost.run_if(on_event::<TaquinShuffled>()),
+                remove_ghost.run_if(on_event::<GameRestored>().or_else(resource_added::<AutoSolvePlayback>())),
+                advance_ghost.run_if(resource_exists::<Ghost>()),
+                finish_ghost.run_if(resource_exists::<Ghost>().and_then(on_event::<TaquinSolved>())),
+            ).chain().run_if(in_state(AppState::Running).and_then(not(resource_exists::<ReplayPlayback>()))))
+            .add_systems(Update, (
+                despawn_ghost_panel.run_if(resource_removed::<Ghost>()),
+                setup_ghost_panel.run_if(resource_added::<Ghost>()),
+                update_ghost_panel.run_if(resource_exists::<Ghost>()
+                    .and_then(resource_changed::<Ghost>().or_else(on_event::<TileMoved>()))),
+            ).chain());
+    }
+}
+
+/// Personal best on the board just shuffled, played back on the same clock as the player
+#[derive(Resource)]
+struct Ghost {
+    replay: Replay,
+    /// Board of the ghost after its `played` moves
+    board: Taquin,
+    played: usize,
+    started_at: f32,
+    /// Seconds the player took, once solved
+    solved_in: Option<f32>,
+}
+
+impl Ghost {
+    fn time(&self) -> f32 {
+        self.replay.moves.last().map_or(0., |last| last.time)
+    }
+}
+
+#[derive(Component)]
+struct GhostPanel;
+
+#[derive(Component)]
+struct GhostStatusText;
+
+#[derive(Component)]
+struct GhostCell(TileCoordinates);
+
+/// Fastest solve among `replays` that starts from `tiles`
+fn best_replay(replays: impl IntoIterator<Item = Replay>, tiles: &[Vec<TileValue>]) -> Option<Replay> {
+    replays.into_iter()
+        .filter(|replay| replay.tiles == tiles && !replay.moves.is_empty())
+        .filter(|replay| replay.board_at(replay.moves.len()).is_ok_and(|board| board.is_solved()))
+        .min_by(|a, b| {
+            let time = |replay: &Replay| replay.moves.last().map_or(0., |last| last.time);
+            time(a).total_cmp(&time(b))
+        })
+}
+
+fn load_replays(folder: &Path) -> Vec<Replay> {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .filter_map(|path| Replay::load(&path).map_err(|error| warn!("{}: {error}", path.display())).ok())
+        .collect()
+}
+
+/// The saved replays are only looked at once per shuffle, a seed always gives the same board
+fn start_ghost(
+    mut commands: Commands,
+    paths: Res<DataPaths>,
+    taquin: Res<Taquin>,
+    clock: Res<GameClock>,
+) {
+    commands.remove_resource::<Ghost>();
+    let Some(replay) = best_replay(load_replays(&paths.replays()), &taquin.tiles) else {
+        return;
+    };
+    info!(moves = replay.moves.len(), "ghost of the best solve found");
+    let Ok(board) = replay.board_at(0) else {
+        return;
+    };
+    commands.insert_resource(Ghost { replay, board, played: 0, started_at: clock.elapsed_seconds(), solved_in: None });
+}
+
+fn remove_ghost(mut commands: Commands) {
+    commands.remove_resource::<Ghost>();
+}
+
+fn advance_ghost(
+    mut ghost: ResMut<Ghost>,
+    clock: Res<GameClock>,
+) {
+    let elapsed = clock.elapsed_seconds() - ghost.started_at;
+    let target = ghost.replay.moves.partition_point(|replay_move| replay_move.time <= elapsed);
+    if ghost.solved_in.is_some() || target <= ghost.played {
+        return;
+    }
+    let ghost = ghost.as_mut();
+    for replay_move in &ghost.replay.moves[ghost.played..target] {
+        replay_move.play(&mut ghost.board);
+    }
+    ghost.played = target;
+}
+
+fn finish_ghost(
+    mut ghost: ResMut<Ghost>,
+    clock: Res<GameClock>,
+) {
+    let solved_in = clock.elapsed_seconds() - ghost.started_at;
+    info!(solved_in, best = ghost.time(), "ghost race finished");
+    ghost.solved_in = Some(solved_in);
+}
+
+fn setup_ghost_panel(
+    mut commands: Commands,
+    ghost: Res<Ghost>,
+    locale: Res<Locale>,
+) {
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.),
+                top: Val::Percent(21.),
+                padding: UiRect::all(Val::Px(10.)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.),
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.7).into(),
+            ..default()
+        }, GhostPanel))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ).with_text_alignment(locale.start_alignment()),
+                GhostStatusText,
+            ));
+            for j in 0..ghost.board.height {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            column_gap: Val::Px(2.),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for i in 0..ghost.board.width {
+                            parent
+                                .spawn((NodeBundle {
+                                    style: Style {
+                                        width: Val::Px(GHOST_CELL),
+                                        height: Val::Px(GHOST_CELL),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    ..default()
+                                }, GhostCell(TileCoordinates::new(i, j))))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "",
+                                        TextStyle {
+                                            font_size: 14.0,
+                                            color: Color::rgba(1., 1., 1., 0.8),
+                                            ..default()
+                                        },
+                                    ));
+                                });
+                        }
+                    });
+            }
+        });
+}
+
+fn despawn_ghost_panel(
+    mut commands: Commands,
+    panel_query: Query<Entity, With<GhostPanel>>,
+) {
+    for entity in panel_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Ahead or behind is told by the tiles in place on both boards, then by the time once solved
+fn update_ghost_panel(
+    ghost: Res<Ghost>,
+    taquin: Res<Taquin>,
+    locale: Res<Locale>,
+    mut status_query: Query<&mut Text, With<GhostStatusText>>,
+    mut cell_query: Query<(&GhostCell, &mut BackgroundColor, &Children)>,
+    mut text_query: Query<&mut Text, Without<GhostStatusText>>,
+) {
+    let race = match ghost.solved_in {
+        Some(solved_in) if solved_in < ghost.time() => format!("Best beaten by {}", locale.format_duration(ghost.time() - solved_in)),
+        Some(solved_in) => format!("{} behind your best", locale.format_duration(solved_in - ghost.time())),
+        None => {
+            let lead = taquin.tiles_in_place() as i32 - ghost.board.tiles_in_place() as i32;
+            match lead {
+                0 => "Even with your best".to_string(),
+                lead if lead > 0 => format!("Ahead by {lead} tiles"),
+                lead => format!("Behind by {} tiles", -lead),
+            }
+        },
+    };
+    for mut text in status_query.iter_mut() {
+        text.sections[0].value = format!("Ghost: {}/{} moves\n{race}", ghost.played, ghost.replay.moves.len());
+    }
+
+    for (GhostCell(coordinates), mut background, children) in cell_query.iter_mut() {
+        let Some(tile) = ghost.board.tiles.get(coordinates.j as usize).and_then(|row| row.get(coordinates.i as usize)) else {
+            continue;
+        };
+        let empty = ghost.board.is_empty_value(*tile);
+        let in_place = tile.0 as usize == coordinates.j as usize * ghost.board.width as usize + coordinates.i as usize + 1;
+        background.0 = match (empty, in_place) {
+            (true, _) => Color::NONE,
+            (false, true) => Color::rgba(0.4, 0.8, 0.5, 0.4),
+            (false, false) => Color::rgba(0.65, 0.65, 0.65, 0.4),
+        };
+        let mut texts = text_query.iter_many_mut(children.iter());
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = if empty { String::new() } else { tile.0.to_string() };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ghost::best_replay,
+        replay::{Replay, ReplayMove},
+        taquin::Taquin,
+        tile::{TileCoordinates, TileValue},
+    };
+
+    fn replay(tiles: Vec<Vec<TileValue>>, times: &[f32]) -> Replay {
+        let mut replay = Replay::new(&Taquin::rect(2, 2));
+        replay.tiles = tiles;
+        replay.moves = times.iter().map(|time| ReplayMove { tile: TileCoordinates::new(0, 1), time: *time, into: None }).collect();
+        replay
+    }
+
+    #[test]
+    fn test_best_replay() {
+        // The empty slot is the 4, sliding the 3 up solves it
+        let tiles = vec![vec![TileValue(1), TileValue(2)], vec![TileValue(4), TileValue(3)]];
+        let other = vec![vec![TileValue(1), TileValue(4)], vec![TileValue(3), TileValue(2)]];
+        let mut fast = replay(tiles.clone(), &[2.]);
+        fast.moves[0].tile = TileCoordinates::new(1, 1);
+        let mut slow = fast.clone();
+        slow.moves[0].time = 5.;
+        let unsolved = replay(tiles.clone(), &[1.]);
+        assert_eq!(best_replay([slow.clone(), unsolved, fast.clone(), replay(other, &[0.5])], &tiles), Some(fast));
+        assert_eq!(best_replay([slow.clone()], &tiles), Some(slow));
+
+        let mut taquin = Taquin::rect(2, 2);
+        taquin.set_tiles(tiles);
+        assert_eq!(taquin.tiles_in_place(), 2);
+    }
+}
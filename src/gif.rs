@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+/// Levels of red, green and blue of the fixed palette, 252 colors out of the 256 a GIF allows
+const LEVELS: [u32; 3] = [6, 7, 6];
+const MIN_CODE_SIZE: u8 = 8;
+const CLEAR_CODE: u16 = 1 << MIN_CODE_SIZE;
+const END_CODE: u16 = CLEAR_CODE + 1;
+const MAX_CODE: u16 = 4096;
+
+/// Part of the picture drawn over the previous frames, which stay as they are around it
+pub struct GifFrame {
+    pub left: u16,
+    pub top: u16,
+    pub width: u16,
+    pub height: u16,
+    /// Hundredths of a second before the next frame
+    pub delay: u16,
+    /// Palette index of every pixel, row after row, see `palette_index`
+    pub pixels: Vec<u8>,
+}
+
+/// Index of the nearest color of the fixed palette
+pub fn palette_index([r, g, b]: [u8; 3]) -> u8 {
+    let level = |value: u8, levels: u32| (value as u32 * (levels - 1) + 127) / 255;
+    (level(r, LEVELS[0]) * LEVELS[1] * LEVELS[2] + level(g, LEVELS[1]) * LEVELS[2] + level(b, LEVELS[2])) as u8
+}
+
+fn palette() -> Vec<u8> {
+    let channel = |level: u32, levels: u32| (level * 255 / (levels - 1)) as u8;
+    let mut palette = Vec::with_capacity(256 * 3);
+    for r in 0..LEVELS[0] {
+        for g in 0..LEVELS[1] {
+            for b in 0..LEVELS[2] {
+                palette.extend([channel(r, LEVELS[0]), channel(g, LEVELS[1]), channel(b, LEVELS[2])]);
+            }
+        }
+    }
+    palette.resize(256 * 3, 0);
+    palette
+}
+
+/// Codes written least significant bit first, as GIF wants them
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Variable width LZW of GIF, the table starts over once its 4096 codes are used
+fn compress(pixels: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    let mut table = HashMap::<(u16, u8), u16>::new();
+    let mut size = MIN_CODE_SIZE + 1;
+    let mut next = END_CODE + 1;
+    writer.write(CLEAR_CODE, size);
+
+    let mut prefix: Option<u16> = None;
+    for &pixel in pixels {
+        let Some(current) = prefix else {
+            prefix = Some(pixel as u16);
+            continue;
+        };
+        if let Some(&code) = table.get(&(current, pixel)) {
+            prefix = Some(code);
+            continue;
+        }
+        writer.write(current, size);
+        if next < MAX_CODE {
+            table.insert((current, pixel), next);
+            next += 1;
+            if next > 1 << size {
+                size += 1;
+            }
+        } else {
+            writer.write(CLEAR_CODE, size);
+            table.clear();
+            size = MIN_CODE_SIZE + 1;
+            next = END_CODE + 1;
+        }
+        prefix = Some(pixel as u16);
+    }
+    if let Some(current) = prefix {
+        writer.write(current, size);
+        // The decoder adds an entry for this last code as well, which may widen the end code
+        if next == 1 << size && size < 12 {
+            size += 1;
+        }
+    }
+    writer.write(END_CODE, size);
+    writer.finish()
+}
+
+/// Animated GIF looping forever, over the fixed palette
+pub fn encode(width: u16, height: u16, frames: &[GifFrame]) -> Vec<u8> {
+    let mut bytes = b"GIF89a".to_vec();
+    bytes.extend(width.to_le_bytes());
+    bytes.extend(height.to_le_bytes());
+    // Global palette of 256 colors, no background color nor aspect ratio
+    bytes.extend([0xf7, 0, 0]);
+    bytes.extend(palette());
+    bytes.extend([0x21, 0xff, 0x0b]);
+    bytes.extend(b"NETSCAPE2.0");
+    bytes.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    for frame in frames {
+        // Each frame is left in place under the next one
+        bytes.extend([0x21, 0xf9, 0x04, 0x04]);
+        bytes.extend(frame.delay.to_le_bytes());
+        bytes.extend([0x00, 0x00]);
+        bytes.push(0x2c);
+        for value in [frame.left, frame.top, frame.width, frame.height] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend([0x00, MIN_CODE_SIZE]);
+        for block in compress(&frame.pixels).chunks(255) {
+            bytes.push(block.len() as u8);
+            bytes.extend(block);
+        }
+        bytes.push(0x00);
+    }
+    bytes.push(0x3b);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gif::{compress, encode, palette_index, GifFrame, CLEAR_CODE, END_CODE, MAX_CODE, MIN_CODE_SIZE};
+
+    /// Plain LZW decoder, widening its codes as GIF decoders do
+    fn decompress(bytes: &[u8]) -> Vec<u8> {
+        let mut bits = bytes.iter().flat_map(|byte| (0..8).map(move |bit| (byte >> bit) & 1));
+        let mut read = |size: u8| (0..size).try_fold(0u16, |code, bit| bits.next().map(|value| code | (value as u16) << bit));
+        let mut table = (0..=END_CODE).map(|code| vec![code as u8]).collect::<Vec<Vec<u8>>>();
+        let (mut size, mut previous, mut pixels) = (MIN_CODE_SIZE + 1, None::<u16>, vec![]);
+        while let Some(code) = read(size) {
+            if code == CLEAR_CODE {
+                table.truncate(END_CODE as usize + 1);
+                (size, previous) = (MIN_CODE_SIZE + 1, None);
+                continue;
+            }
+            if code == END_CODE {
+                break;
+            }
+            let entry = match table.get(code as usize) {
+                Some(entry) => entry.clone(),
+                None => {
+                    let previous = &table[previous.unwrap() as usize];
+                    [previous.clone(), vec![previous[0]]].concat()
+                },
+            };
+            if let Some(previous) = previous.filter(|_| table.len() < MAX_CODE as usize) {
+                table.push([table[previous as usize].clone(), vec![entry[0]]].concat());
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+            }
+            pixels.extend(&entry);
+            previous = Some(code);
+        }
+        pixels
+    }
+
+    #[test]
+    fn test_compress_round_trip() {
+        let noise = (0..20_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8 % 40).collect::<Vec<u8>>();
+        let flat = vec![7; 5_000];
+        let ramp = (0..255).collect::<Vec<u8>>();
+        for pixels in [noise, flat, ramp, vec![3]] {
+            assert_eq!(decompress(&compress(&pixels)), pixels);
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(palette_index([0, 0, 0]), 0);
+        assert_eq!(palette_index([255, 255, 255]), 251);
+        let frame = GifFrame { left: 0, top: 0, width: 2, height: 2, delay: 10, pixels: vec![0, 251, 251, 0] };
+        let bytes = encode(2, 2, &[frame]);
+        assert!(bytes.starts_with(b"GIF89a"));
+        assert_eq!(bytes.last(), Some(&0x3b));
+    }
+}
@@ -0,0 +1,309 @@
+use bevy::{
+    core_pipeline::{
+        experimental::taa::{TemporalAntiAliasBundle, TemporalAntiAliasPlugin},
+        fxaa::Fxaa,
+    },
+    pbr::PointLightShadowMap,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages},
+        texture::ImageSampler,
+    },
+    window::{PrimaryWindow, WindowResized},
+};
+
+use crate::{frame_limit::FrameLimit, theme::Theme};
+
+const RENDER_SCALES: [f32; 7] = [0.5, 0.67, 0.75, 0.85, 1., 1.5, 2.];
+const BATTERY_SAVER_FPS: f32 = 30.;
+/// Enough for a solver demo nobody watches closely
+const UNATTENDED_FPS: f32 = 12.;
+const MOBILE: bool = cfg!(any(target_os = "android", target_os = "ios"));
+
+pub struct GraphicsPlugin;
+
+impl Plugin for GraphicsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins(TemporalAntiAliasPlugin)
+            .init_resource::<GraphicsSettings>()
+            .init_resource::<BatterySaver>()
+            .add_systems(Update, (
+                (toggle_battery_saver, apply_battery_saver.run_if(resource_changed::<BatterySaver>())).chain(),
+                change_graphics_settings,
+                (
+                    apply_anti_aliasing.run_if(resource_changed::<GraphicsSettings>()),
+                    apply_lighting.run_if(resource_changed::<GraphicsSettings>().or_else(resource_changed::<Theme>())),
+                    apply_render_scale.run_if(resource_changed::<GraphicsSettings>().or_else(on_event::<WindowResized>())),
+                ),
+            ).chain());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AntiAliasing {
+    Off,
+    /// 4 samples per pixel
+    #[default]
+    Msaa,
+    /// Cheapest, blurs the edges after rendering
+    Fxaa,
+    /// Smoothest on still scenes, may ghost behind sliding tiles
+    Taa,
+}
+
+impl AntiAliasing {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(AntiAliasing::Off),
+            "msaa" => Some(AntiAliasing::Msaa),
+            "fxaa" => Some(AntiAliasing::Fxaa),
+            "taa" => Some(AntiAliasing::Taa),
+            _ => None,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            AntiAliasing::Off => AntiAliasing::Msaa,
+            AntiAliasing::Msaa => AntiAliasing::Fxaa,
+            AntiAliasing::Fxaa => AntiAliasing::Taa,
+            AntiAliasing::Taa => AntiAliasing::Off,
+        }
+    }
+}
+
+/// Applied live to the main camera
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct GraphicsSettings {
+    pub anti_aliasing: AntiAliasing,
+    /// Resolution of the 3D scene relative to the window, the interface is always drawn at full resolution
+    pub render_scale: f32,
+    pub shadows: bool,
+    /// Texels along a side of each face of the shadow cube map
+    pub shadow_map_size: usize,
+    /// Reach of the main light, nothing further away casts a shadow
+    pub shadow_distance: f32,
+    /// Lumens of the main light
+    pub light_intensity: f32,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            anti_aliasing: AntiAliasing::default(),
+            render_scale: 1.,
+            shadows: true,
+            shadow_map_size: 1024,
+            shadow_distance: 100.,
+            light_intensity: 9000.,
+        }
+    }
+}
+
+impl GraphicsSettings {
+    const BATTERY_SAVER: Self = Self {
+        anti_aliasing: AntiAliasing::Off,
+        render_scale: 0.5,
+        shadows: false,
+        shadow_map_size: 256,
+        shadow_distance: 100.,
+        light_intensity: 9000.,
+    };
+
+    fn lower_scale(&self) -> f32 {
+        RENDER_SCALES.iter().rev().copied().find(|scale| *scale < self.render_scale).unwrap_or(self.render_scale)
+    }
+
+    fn higher_scale(&self) -> f32 {
+        RENDER_SCALES.iter().copied().find(|scale| *scale > self.render_scale).unwrap_or(self.render_scale)
+    }
+}
+
+/// Low-power preset, on by default on phones and tablets: no shadows, no anti-aliasing,
+/// half resolution and 30 frames per second
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatterySaver {
+    /// Chosen by the player
+    pub enabled: bool,
+    /// Set while an auto-solve plays with nobody touching the controls, the frame rate drops further
+    pub unattended: bool,
+}
+
+impl BatterySaver {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled, unattended: false }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.enabled || self.unattended
+    }
+}
+
+impl Default for BatterySaver {
+    fn default() -> Self {
+        Self::new(MOBILE)
+    }
+}
+
+/// Camera looking at the board
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Light over the board, the one casting shadows
+#[derive(Component)]
+pub struct MainLight;
+
+/// The camera drawing the scaled scene over the whole window, and the interface above it,
+/// and the sprite showing the scene
+#[derive(Component)]
+struct Upscaling;
+
+fn change_graphics_settings(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: ResMut<GraphicsSettings>,
+) {
+    if keyboard_input.just_released(KeyCode::G) {
+        settings.anti_aliasing = settings.anti_aliasing.next();
+    }
+    if keyboard_input.just_released(KeyCode::Comma) {
+        settings.render_scale = settings.lower_scale();
+    }
+    if keyboard_input.just_released(KeyCode::Period) {
+        settings.render_scale = settings.higher_scale();
+    }
+}
+
+fn toggle_battery_saver(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut battery_saver: ResMut<BatterySaver>,
+) {
+    if keyboard_input.just_released(KeyCode::B) {
+        battery_saver.enabled = !battery_saver.enabled;
+    }
+}
+
+/// Swaps the settings for the preset, and back to the ones in place before it when turned off
+fn apply_battery_saver(
+    battery_saver: Res<BatterySaver>,
+    mut settings: ResMut<GraphicsSettings>,
+    mut frame_limit: ResMut<FrameLimit>,
+    mut saved: Local<Option<(GraphicsSettings, FrameLimit)>>,
+) {
+    info!(enabled = battery_saver.enabled, unattended = battery_saver.unattended, "battery saver");
+    if battery_saver.is_active() {
+        saved.get_or_insert((*settings, *frame_limit));
+        *settings = GraphicsSettings::BATTERY_SAVER;
+        *frame_limit = FrameLimit(Some(if battery_saver.unattended { UNATTENDED_FPS } else { BATTERY_SAVER_FPS }));
+    } else if let Some((previous_settings, previous_frame_limit)) = saved.take() {
+        *settings = previous_settings;
+        *frame_limit = previous_frame_limit;
+    }
+}
+
+fn apply_anti_aliasing(
+    mut commands: Commands,
+    settings: Res<GraphicsSettings>,
+    mut msaa: ResMut<Msaa>,
+    camera_query: Query<Entity, With<MainCamera>>,
+) {
+    info!(anti_aliasing = ?settings.anti_aliasing, "anti-aliasing");
+    // Multisampling has to be off for the other two
+    *msaa = if settings.anti_aliasing == AntiAliasing::Msaa { Msaa::Sample4 } else { Msaa::Off };
+    for camera in camera_query.iter() {
+        let mut camera = commands.entity(camera);
+        camera.remove::<Fxaa>().remove::<TemporalAntiAliasBundle>();
+        match settings.anti_aliasing {
+            AntiAliasing::Fxaa => { camera.insert(Fxaa::default()); },
+            AntiAliasing::Taa => { camera.insert(TemporalAntiAliasBundle::default()); },
+            AntiAliasing::Off | AntiAliasing::Msaa => {},
+        }
+    }
+}
+
+/// The theme dims or brightens the intensity of the settings, its color is set in `theme`
+fn apply_lighting(
+    settings: Res<GraphicsSettings>,
+    theme: Res<Theme>,
+    mut shadow_map: ResMut<PointLightShadowMap>,
+    mut lights_query: Query<&mut PointLight, With<MainLight>>,
+) {
+    shadow_map.size = settings.shadow_map_size;
+    for mut light in lights_query.iter_mut() {
+        light.shadows_enabled = settings.shadows;
+        light.range = settings.shadow_distance;
+        light.intensity = settings.light_intensity * theme.light_intensity();
+    }
+}
+
+/// Scene target in the swapchain format, sampled smoothly when stretched over the window
+fn scaled_target(size: Extent3d) -> Image {
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("scaled_scene"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        sampler: ImageSampler::linear(),
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+/// Renders the scene to an image of the scaled window size, upscaled or downscaled by a 2D camera,
+/// or straight to the window at scale 1
+fn apply_render_scale(
+    mut commands: Commands,
+    settings: Res<GraphicsSettings>,
+    mut images: ResMut<Assets<Image>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<(Entity, &mut Camera), With<MainCamera>>,
+    upscale_query: Query<Entity, With<Upscaling>>,
+) {
+    for entity in upscale_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let Ok((camera_entity, mut camera)) = camera_query.get_single_mut() else {
+        return;
+    };
+    if settings.render_scale == 1. {
+        camera.target = RenderTarget::default();
+        commands.entity(camera_entity).remove::<UiCameraConfig>();
+        return;
+    }
+
+    let size = Extent3d {
+        width: ((window.physical_width() as f32 * settings.render_scale) as u32).max(1),
+        height: ((window.physical_height() as f32 * settings.render_scale) as u32).max(1),
+        depth_or_array_layers: 1,
+    };
+    info!(scale = settings.render_scale, width = size.width, height = size.height, "render scale");
+    let target = images.add(scaled_target(size));
+    camera.target = RenderTarget::Image(target.clone());
+    commands.entity(camera_entity).insert(UiCameraConfig { show_ui: false });
+    commands.spawn((Camera2dBundle {
+        camera: Camera {
+            order: camera.order + 1,
+            ..default()
+        },
+        ..default()
+    }, Upscaling));
+    commands.spawn((SpriteBundle {
+        texture: target,
+        sprite: Sprite {
+            custom_size: Some(Vec2::new(window.width(), window.height())),
+            ..default()
+        },
+        ..default()
+    }, Upscaling));
+}
@@ -1,23 +1,135 @@
 use bevy::{prelude::*, animation::RepeatAnimation};
 
-use std::f32::consts::PI;
+use std::{f32::consts::PI, ops::RangeInclusive};
 
-use crate::taquin::{TaquinShuffled, TaquinSolved, TileMoved, Taquin};
+use crate::{achievements::AchievementUnlocked, cli::MAX_EMPTY_SLOTS, countdown::ModeButton, focus::FocusScope, taquin::{EmptySlots, MoveCount, ShuffleSeed, TaquinShuffled, TaquinSolved, TileMoved, Taquin}, phases::{SolvePhases, SolvePhasesCompleted}, auto_solve::AutoSolvePlayback, gallery::Gallery, i18n::{Localized, Translations}, level::CampaignButton, tile_material::{SharedTileMaterial, TileMaterial}, locale::Locale, packs::TexturePacks, pause::GameClock, playback::PlaybackController, records::NewRecord, saved_game::{GameRestored, ResumeRequested, SaveSlot}, versus::ComputerButton, weekly::{WeeklyButton, WeeklyChallenge, WeeklyRecords}, replay::{ReplayPlayback, WatchReplayButton}, settings::{KeyRepeat, Settings, VSync, IDLE_HINT_DELAYS}, display::RESOLUTIONS, sound::AudioSettings, stats_screen::StatsButton, taquin::ControlScheme, theme::ThemeButton, tile::TileCoordinates, AppState, TaquinSprites};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::leaderboard::DailyButton;
+
+const MENU_SIZES: RangeInclusive<i8> = 3..=6;
+/// Square boards drawn by the chunks of `mega_board`, which hold 60 FPS up to the biggest one
+const MENU_MEGA_SIZES: [i8; 4] = [20, 30, 40, 50];
+const MENU_EMPTY_SLOTS: RangeInclusive<usize> = 1..=MAX_EMPTY_SLOTS;
+/// Goes back to the main menu from the game
+const MENU_KEY: KeyCode = KeyCode::Back;
+/// Seconds a toast stays on screen
+const TOAST_DURATION: f32 = 4.;
+/// Shows and hides the whole picture with Shift, P alone opens the pack browser
+const PREVIEW_KEY: KeyCode = KeyCode::P;
+/// Width of the picture preview, its height follows the board
+const PREVIEW_WIDTH: f32 = 220.;
+/// Opens and closes the settings screen, in the main menu or in game
+const SETTINGS_KEY: KeyCode = KeyCode::F1;
+/// Volumes the settings screen offers
+const VOLUME_LEVELS: [f32; 5] = [0., 0.25, 0.5, 0.75, 1.];
 
 pub struct GuiPlugin;
 
 impl Plugin for GuiPlugin {
     fn build(&self, app: &mut App) {
         app
+            .init_resource::<SolveTimer>()
+            .init_resource::<PicturePreview>()
+            .add_event::<TimerTick>()
             .add_systems(Startup, setup_gui)
             .add_systems(Update, (
                 taquin_shuffled_listener.run_if(on_event::<TaquinShuffled>()),
                 on_taquin_solved_reset_gui.run_if(on_event::<TaquinSolved>()),
-                on_tile_moved_increase_counter.run_if(on_event::<TileMoved>().and_then(|taquin: Res<Taquin>| taquin.is_shuffled)),
-            ));
+                update_move_counter.run_if(resource_changed::<MoveCount>()),
+                on_solve_phases_completed_show_breakdown.run_if(on_event::<SolvePhasesCompleted>()),
+                update_solve_caption.run_if(resource_changed_or_removed::<PlaybackController>()),
+                (tick_solve_timer.run_if(in_state(AppState::Running)), update_solve_timer.run_if(on_event::<TimerTick>())).chain(),
+                update_record_message.run_if(on_event::<NewRecord>().or_else(on_event::<TaquinShuffled>())),
+                update_seed_label.run_if(resource_changed::<ShuffleSeed>()),
+                (show_achievement_toasts.run_if(on_event::<AchievementUnlocked>()), expire_toasts.run_if(any_with_component::<Toast>())).chain(),
+            ))
+            .add_systems(Update, (
+                toggle_picture_preview,
+                (dock_picture_preview, update_picture_preview).run_if(picture_preview_shown),
+            ).chain().run_if(in_state(AppState::Running)))
+            .add_systems(OnEnter(AppState::MainMenu), (despawn_tiles, setup_main_menu, close_picture_preview))
+            .add_systems(Update, (
+                main_menu_input,
+                update_main_menu.run_if(resource_changed::<MainMenu>()),
+            ).chain().run_if(in_state(AppState::MainMenu)))
+            .add_systems(OnExit(AppState::MainMenu), close_main_menu)
+            .add_systems(Update, (
+                toggle_settings_screen,
+                (settings_screen_input, update_settings_screen).chain().run_if(resource_exists::<SettingsScreen>()),
+            ).chain().run_if(in_state(AppState::MainMenu).or_else(in_state(AppState::Running))))
+            .add_systems(Update, return_to_main_menu.run_if(in_state(AppState::Running).and_then(not(resource_exists::<ReplayPlayback>()))));
+    }
+}
+
+/// Dimensions and picture picked in the main menu, the game starts with them
+#[derive(Resource)]
+struct MainMenu {
+    entity: Entity,
+    width: i8,
+    height: i8,
+    image: usize,
+    empty_slots: usize,
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+enum MenuButton {
+    Width(i8),
+    Height(i8),
+    /// Both sides of a mega board
+    MegaSize(i8),
+    Image(usize),
+    EmptySlots(usize),
+    Start,
+    Resume,
+}
+
+/// Screen over the main menu or the game, its choices are saved in `Settings`
+#[derive(Resource)]
+struct SettingsScreen(Entity);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VolumeChannel {
+    Master,
+    Music,
+    Effects,
+}
+
+impl VolumeChannel {
+    fn volume(self, settings: &AudioSettings) -> f32 {
+        match self {
+            VolumeChannel::Master => settings.master,
+            VolumeChannel::Music => settings.music,
+            VolumeChannel::Effects => settings.sfx,
+        }
+    }
+
+    fn volume_mut(self, settings: &mut AudioSettings) -> &mut f32 {
+        match self {
+            VolumeChannel::Master => &mut settings.master,
+            VolumeChannel::Music => &mut settings.music,
+            VolumeChannel::Effects => &mut settings.sfx,
+        }
     }
 }
 
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+enum SettingsButton {
+    Open,
+    Close,
+    ControlScheme(ControlScheme),
+    /// Size the main menu starts with in the next runs
+    Width(i8),
+    Height(i8),
+    Volume(VolumeChannel, f32),
+    VSync(VSync),
+    /// Size of the window, see `display`
+    Resolution(u32, u32),
+    KeyRepeat(KeyRepeat),
+    /// Seconds without a move before a nudge, see `idle_hint`
+    IdleHint(Option<f32>),
+    Language(Locale),
+}
+
 #[derive(Component)]
 pub struct MainMessage {
     shuffle_anim: Handle<AnimationClip>
@@ -27,31 +139,88 @@ pub struct MainMessage {
 pub struct ShuffleKey;
 
 #[derive(Component)]
-pub struct ShuffleKeyTargetSize(f32);
+pub struct PhaseBreakdown;
 
-#[derive(Component, Default)]
-pub struct MoveCounter(usize);
+#[derive(Component)]
+pub struct SolveCaption;
+
+#[derive(Component)]
+pub struct MoveCounter;
 
-impl MoveCounter {
-    pub fn incr(&mut self) {
-        self.0 += 1;
+/// `GameClock` time when the current solve started, so neither the game speed nor the pauses change it
+#[derive(Resource, Default)]
+struct SolveTimer {
+    started_at: Option<f32>,
+    /// Tenths of a second last shown
+    shown: Option<u64>,
+}
+
+/// Sent when the solve timer reaches another tenth of a second, with the seconds played
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TimerTick(pub f32);
+
+#[derive(Component)]
+pub struct SolveTimerText;
+
+#[derive(Component)]
+pub struct RecordMessage;
+
+/// Popup at the top of the screen, gone after `TOAST_DURATION`
+#[derive(Component)]
+struct Toast(Timer);
+
+/// Seed of the board on screen, shared to race the same scramble
+#[derive(Component)]
+pub struct SeedLabel;
+
+/// Corner of the screen the picture preview is docked in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum PreviewCorner {
+    #[default]
+    BottomRight,
+    BottomLeft,
+    TopLeft,
+    TopRight,
+}
+
+impl PreviewCorner {
+    /// Clicking the preview moves it to the next corner, clockwise
+    fn next(self) -> Self {
+        match self {
+            PreviewCorner::BottomRight => PreviewCorner::BottomLeft,
+            PreviewCorner::BottomLeft => PreviewCorner::TopLeft,
+            PreviewCorner::TopLeft => PreviewCorner::TopRight,
+            PreviewCorner::TopRight => PreviewCorner::BottomRight,
+        }
     }
 
-    pub fn reset(&mut self){
-        self.0 = 0;
+    fn place(self, style: &mut Style) {
+        let (top, left) = match self {
+            PreviewCorner::BottomRight => (false, false),
+            PreviewCorner::BottomLeft => (false, true),
+            PreviewCorner::TopLeft => (true, true),
+            PreviewCorner::TopRight => (true, false),
+        };
+        let margin = Val::Px(20.);
+        (style.top, style.bottom) = if top { (margin, Val::Auto) } else { (Val::Auto, margin) };
+        (style.left, style.right) = if left { (margin, Val::Auto) } else { (Val::Auto, margin) };
     }
 }
 
-impl From<&mut MoveCounter> for String {
-    fn from(item: &mut MoveCounter) -> Self {
-        item.0.to_string()
-    }
+/// Whole picture the tiles are cut from, shown in a corner while playing. The corner is kept when it is hidden
+#[derive(Resource, Default)]
+struct PicturePreview {
+    entity: Option<Entity>,
+    corner: PreviewCorner,
 }
 
+#[derive(Component)]
+struct PicturePreviewImage;
+
 fn taquin_shuffled_listener(
     mut main_message_query: Query<(&mut AnimationPlayer, &MainMessage)>,
-    mut shuffle_key_query: Query<&mut Style, With<ShuffleKey>>,
-    mut move_counter_query: Query<(&mut Text, &mut MoveCounter)>
+    mut shuffle_key_query: Query<&mut Style, (With<ShuffleKey>, Without<PhaseBreakdown>)>,
+    mut phase_breakdown_query: Query<&mut Style, With<PhaseBreakdown>>,
 ) {
     let Ok((mut player, message)) = main_message_query.get_single_mut() else {
         return;
@@ -65,9 +234,8 @@ fn taquin_shuffled_listener(
         style.display = Display::None;
     };
 
-    if let Ok((mut text, mut counter)) = move_counter_query.get_single_mut() {
-        counter.reset();
-        text.sections[0].value = counter.as_mut().into();
+    if let Ok(mut style) = phase_breakdown_query.get_single_mut() {
+        style.display = Display::None;
     };
 }
 
@@ -80,20 +248,191 @@ fn on_taquin_solved_reset_gui(
     style.display = Display::DEFAULT;
 }
 
-fn on_tile_moved_increase_counter(
-    mut move_counter_query: Query<(&mut Text, &mut MoveCounter)>
+/// Slides carry the count they made, shuffles and restored games only change the resource
+fn update_move_counter(
+    move_count: Res<MoveCount>,
+    mut tile_moved_events: EventReader<TileMoved>,
+    mut move_counter_query: Query<&mut Text, With<MoveCounter>>,
+    locale: Res<Locale>,
+) {
+    let moves = tile_moved_events.read().last().map_or(move_count.0, |moved| moved.moves);
+    for mut text in move_counter_query.iter_mut() {
+        text.sections[0].value = locale.format_count(moves);
+    }
+}
+
+fn on_solve_phases_completed_show_breakdown(
+    solve_phases: Res<SolvePhases>,
+    mut phase_breakdown_query: Query<(&mut Text, &mut Style), With<PhaseBreakdown>>,
+    locale: Res<Locale>,
+    translations: Res<Translations>,
+) {
+    let Ok((mut text, mut style)) = phase_breakdown_query.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = solve_phases.splits().iter()
+        .map(|(name, time, moves)| translations.format("{phase}: {time}, {moves} moves", &[
+            ("phase", name.to_string()),
+            ("time", locale.format_duration(*time)),
+            ("moves", locale.format_count(*moves)),
+        ]))
+        .collect::<Vec<String>>()
+        .join("\n");
+    style.display = Display::DEFAULT;
+}
+
+/// Runs from the shuffle to the solve, the seconds of a resumed game added
+fn tick_solve_timer(
+    mut shuffle_events: EventReader<TaquinShuffled>,
+    mut solved_events: EventReader<TaquinSolved>,
+    mut restored_events: EventReader<GameRestored>,
+    clock: Res<GameClock>,
+    mut timer: ResMut<SolveTimer>,
+    mut tick_events: EventWriter<TimerTick>,
+) {
+    let now = clock.elapsed_seconds();
+    if shuffle_events.read().count() > 0 {
+        timer.started_at = Some(now);
+        timer.shown = None;
+    }
+    let restored = restored_events.read().map(|restored| restored.elapsed).sum::<f32>();
+    let Some(started_at) = timer.started_at.as_mut() else {
+        return;
+    };
+    *started_at -= restored;
+    let elapsed = now - *started_at;
+    if solved_events.read().count() > 0 {
+        timer.started_at = None;
+    }
+    let tenths = (elapsed.max(0.) * 10.).round() as u64;
+    if timer.shown != Some(tenths) {
+        timer.shown = Some(tenths);
+        tick_events.send(TimerTick(elapsed));
+    }
+}
+
+fn update_solve_timer(
+    mut tick_events: EventReader<TimerTick>,
+    locale: Res<Locale>,
+    mut timer_query: Query<&mut Text, With<SolveTimerText>>,
+) {
+    let Some(TimerTick(elapsed)) = tick_events.read().last() else {
+        return;
+    };
+    for mut text in timer_query.iter_mut() {
+        text.sections[0].value = locale.format_duration(*elapsed);
+    }
+}
+
+fn update_seed_label(
+    seed: Res<ShuffleSeed>,
+    mut seed_label_query: Query<&mut Localized, With<SeedLabel>>,
+) {
+    let Ok(mut localized) = seed_label_query.get_single_mut() else {
+        return;
+    };
+    *localized = seed.current.map_or_else(|| Localized::new(""), |seed| Localized::new("Seed {seed}").with_arg("seed", seed));
+}
+
+/// Shown on the solved screen until the next shuffle
+fn update_record_message(
+    mut shuffle_events: EventReader<TaquinShuffled>,
+    mut record_events: EventReader<NewRecord>,
+    mut record_message_query: Query<(&mut Localized, &mut Style), With<RecordMessage>>,
+) {
+    let Ok((mut localized, mut style)) = record_message_query.get_single_mut() else {
+        return;
+    };
+    if shuffle_events.read().count() > 0 {
+        style.display = Display::None;
+    }
+    let Some(record) = record_events.read().last().copied() else {
+        return;
+    };
+    *localized = Localized::new(match (record.time, record.moves) {
+        (true, true) => "New record! Best time and fewest moves",
+        (true, false) => "New record! Best time",
+        _ => "New record! Fewest moves",
+    });
+    style.display = Display::DEFAULT;
+}
+
+/// Toasts pile up below the ones still shown
+fn show_achievement_toasts(
+    mut commands: Commands,
+    mut unlocked_events: EventReader<AchievementUnlocked>,
+    toast_query: Query<(), With<Toast>>,
+) {
+    for (index, AchievementUnlocked(achievement)) in unlocked_events.read().enumerate() {
+        commands.spawn((NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10. + 50. * (toast_query.iter().count() + index) as f32),
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            z_index: ZIndex::Global(7),
+            ..default()
+        }, Toast(Timer::from_seconds(TOAST_DURATION, TimerMode::Once)))).with_children(|parent| {
+            parent.spawn(NodeBundle {
+                style: Style {
+                    padding: UiRect::axes(Val::Px(12.), Val::Px(6.)),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.7).into(),
+                ..default()
+            }).with_children(|parent| {
+                parent.spawn((TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ), Localized::new("Achievement unlocked: {achievement}").with_arg("achievement", achievement.label())));
+            });
+        });
+    }
+}
+
+fn expire_toasts(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut toast_query: Query<(Entity, &mut Toast)>,
 ) {
-    if let Ok((mut text, mut counter)) = move_counter_query.get_single_mut() {
-        counter.incr();
-        text.sections[0].value = counter.as_mut().into();
+    for (entity, mut toast) in toast_query.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn update_solve_caption(
+    playback: Option<Res<AutoSolvePlayback>>,
+    controller: Option<Res<PlaybackController>>,
+    mut caption_query: Query<(&mut Text, &mut Style), With<SolveCaption>>,
+) {
+    let Ok((mut text, mut style)) = caption_query.get_single_mut() else {
+        return;
     };
+    let played = controller.map_or(0, |controller| controller.played());
+    let caption = playback.as_ref().and_then(|playback| playback.caption(played));
+    let display = if caption.is_some() { Display::DEFAULT } else { Display::None };
+    if style.display != display {
+        style.display = display;
+    }
+    if let Some(caption) = caption.filter(|caption| text.sections[0].value != *caption) {
+        text.sections[0].value = caption.to_string();
+    }
 }
 
 fn setup_gui(
     mut commands: Commands, 
     _asset_server: Res<AssetServer>,
     mut animations: ResMut<Assets<AnimationClip>>,
-    asset_server: Res<AssetServer>
+    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
 ) {
 
     let main_message_name = Name::new("shuffle");
@@ -136,7 +475,8 @@ fn setup_gui(
                 style: Style {
                     width: Val::Percent(100.),
                     height: Val::Percent(20.),
-                    padding: UiRect::new(Val::Px(10.), Val::ZERO, Val::Px(10.), Val::ZERO),
+                    padding: UiRect { top: Val::Px(10.), ..locale.start_edge(Val::Px(10.)) },
+                    justify_content: locale.start_justify(),
                     ..default()
                 },
                 ..default()
@@ -156,7 +496,111 @@ fn setup_gui(
                         ..default()
                     })
                 ,
-                MoveCounter::default()));
+                MoveCounter));
+
+                parent.spawn((
+                    TextBundle::from_section(
+                        locale.format_duration(0.),
+                        TextStyle {
+                            font_size: 50.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    )
+                    .with_style(Style {
+                        margin: locale.start_edge(Val::Px(30.)),
+                        align_self: AlignSelf::Center,
+                        ..default()
+                    }),
+                    SolveTimerText,
+                ));
+
+                parent.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::GOLD,
+                            ..default()
+                        },
+                    )
+                    .with_style(Style {
+                        margin: locale.start_edge(Val::Px(30.)),
+                        align_self: AlignSelf::Center,
+                        display: Display::None,
+                        ..default()
+                    }),
+                    RecordMessage,
+                    Localized::new(""),
+                ));
+
+                parent.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    )
+                    .with_style(Style {
+                        margin: locale.start_edge(Val::Px(30.)),
+                        align_self: AlignSelf::Center,
+                        ..default()
+                    }),
+                    SeedLabel,
+                    Localized::new(""),
+                ));
+
+                parent.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    )
+                    .with_text_alignment(locale.start_alignment())
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        left: locale.end_edge(Val::Px(10.)).left,
+                        right: locale.end_edge(Val::Px(10.)).right,
+                        top: Val::Px(10.),
+                        display: Display::None,
+                        ..default()
+                    }),
+                    PhaseBreakdown,
+                ));
+            });
+
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    bottom: Val::Percent(21.),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            }).with_children(|parent| {
+                parent.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    )
+                    .with_text_alignment(TextAlignment::Center)
+                    .with_style(Style {
+                        display: Display::None,
+                        ..default()
+                    }),
+                    SolveCaption,
+                ));
             });
 
         parent
@@ -166,6 +610,7 @@ fn setup_gui(
                     height: Val::Percent(20.),
                     border: UiRect::top(Val::Px(2.)),
                     justify_content: JustifyContent::Center,
+                    flex_direction: locale.row_direction(),
                     ..default()
                 },
                 background_color: Color::rgba(0.65, 0.65, 0.65, 0.2).into(),
@@ -185,7 +630,7 @@ fn setup_gui(
                         position_type: PositionType::Relative,
                         ..default()
                     }), 
-                    MainMessage { shuffle_anim }, main_message_name, animation_player
+                    MainMessage { shuffle_anim }, main_message_name, animation_player, Localized::new("Taquin"),
             ));
 
                 parent.spawn((
@@ -193,7 +638,7 @@ fn setup_gui(
                         style: Style {
                             width: Val::Px(100.0),
                             height: Val::Px(100.0),
-                            margin: UiRect::left(Val::VMin(5.)),
+                            margin: locale.start_edge(Val::VMin(5.)),
                             ..default()
                         },
                         background_color: Color::WHITE.into(),
@@ -205,3 +650,453 @@ fn setup_gui(
             });
         });
 }
+
+fn picture_preview_shown(
+    preview: Res<PicturePreview>,
+) -> bool {
+    preview.entity.is_some()
+}
+
+fn toggle_picture_preview(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut preview: ResMut<PicturePreview>,
+) {
+    if !keyboard_input.just_released(PREVIEW_KEY) || !keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        return;
+    }
+    if let Some(entity) = preview.entity.take() {
+        commands.entity(entity).despawn_recursive();
+        return;
+    }
+    let mut style = Style {
+        position_type: PositionType::Absolute,
+        padding: UiRect::all(Val::Px(4.)),
+        ..default()
+    };
+    preview.corner.place(&mut style);
+    let entity = commands.spawn(ButtonBundle {
+        style,
+        background_color: Color::rgba(0., 0., 0., 0.6).into(),
+        z_index: ZIndex::Global(3),
+        ..default()
+    }).with_children(|parent| {
+        // The picture and its size are set from the tiles in `update_picture_preview`
+        parent.spawn((ImageBundle {
+            style: Style {
+                width: Val::Px(PREVIEW_WIDTH),
+                ..default()
+            },
+            ..default()
+        }, PicturePreviewImage));
+    }).id();
+    preview.entity = Some(entity);
+}
+
+fn dock_picture_preview(
+    mut preview: ResMut<PicturePreview>,
+    mut button_query: Query<(&Interaction, &mut Style), Changed<Interaction>>,
+) {
+    let Some(entity) = preview.entity else {
+        return;
+    };
+    let Ok((interaction, mut style)) = button_query.get_mut(entity) else {
+        return;
+    };
+    if *interaction == Interaction::Pressed {
+        preview.corner = preview.corner.next();
+        preview.corner.place(&mut style);
+    }
+}
+
+/// Follows the picture of the tiles as it is cycled or the pack changes, stretched like the board stretches it
+fn update_picture_preview(
+    taquin: Res<Taquin>,
+    materials: Res<Assets<TileMaterial>>,
+    shared_material: Res<SharedTileMaterial>,
+    mut image_query: Query<(&mut UiImage, &mut Style), With<PicturePreviewImage>>,
+) {
+    let Some(texture) = materials.get(&shared_material.0).and_then(|material| material.base.base_color_texture.clone()) else {
+        return;
+    };
+    let aspect_ratio = Some(taquin.width as f32 / taquin.height as f32);
+    for (mut image, mut style) in image_query.iter_mut() {
+        if image.texture != texture {
+            image.texture = texture.clone();
+        }
+        if style.aspect_ratio != aspect_ratio {
+            style.aspect_ratio = aspect_ratio;
+        }
+    }
+}
+
+fn close_picture_preview(
+    mut commands: Commands,
+    mut preview: ResMut<PicturePreview>,
+) {
+    if let Some(entity) = preview.entity.take() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// The tiles of the previous game, if any, are spawned again once the menu is closed
+fn despawn_tiles(
+    mut commands: Commands,
+    tiles_query: Query<Entity, With<TileCoordinates>>,
+) {
+    for entity in tiles_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn spawn_menu_row<B: Component>(parent: &mut ChildBuilder, label: &str, buttons: Vec<(String, B)>) {
+    parent.spawn(NodeBundle {
+        style: Style {
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(10.),
+            ..default()
+        },
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            label,
+            TextStyle {
+                font_size: 40.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), Localized::new(label)));
+        for (text, button) in buttons {
+            parent.spawn((ButtonBundle {
+                style: Style {
+                    padding: UiRect::axes(Val::Px(20.), Val::Px(8.)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                ..default()
+            }, button)).with_children(|parent| {
+                parent.spawn((TextBundle::from_section(
+                    text.clone(),
+                    TextStyle {
+                        font_size: 40.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ), Localized::new(text)));
+            });
+        }
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn setup_main_menu(
+    mut commands: Commands,
+    taquin: Res<Taquin>,
+    empty_slots: Res<EmptySlots>,
+    sprites: Res<TaquinSprites>,
+    packs: Res<TexturePacks>,
+    gallery: Res<Gallery>,
+    slot: Res<SaveSlot>,
+    weekly: Res<WeeklyChallenge>,
+    weekly_records: Res<WeeklyRecords>,
+) {
+    let images = packs.selected().manifest.images.iter()
+        .map(|image| image.rsplit_once('.').map_or(image.as_str(), |(name, _)| name).to_string())
+        .chain(gallery.names.iter().cloned())
+        .take(sprites.images.len())
+        .collect::<Vec<String>>();
+    let entity = commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(30.),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(5),
+        ..default()
+    }, FocusScope)).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "Taquin",
+            TextStyle {
+                font_size: 100.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), Localized::new("Taquin")));
+        spawn_menu_row(parent, "Columns", MENU_SIZES.map(|size| (size.to_string(), MenuButton::Width(size))).collect());
+        spawn_menu_row(parent, "Rows", MENU_SIZES.map(|size| (size.to_string(), MenuButton::Height(size))).collect());
+        spawn_menu_row(parent, "Mega board", MENU_MEGA_SIZES.map(|size| (format!("{size}x{size}"), MenuButton::MegaSize(size))).to_vec());
+        spawn_menu_row(parent, "Picture", images.into_iter().enumerate().map(|(index, name)| (name, MenuButton::Image(index))).collect());
+        spawn_menu_row(parent, "Empty slots", MENU_EMPTY_SLOTS.map(|count| (count.to_string(), MenuButton::EmptySlots(count))).collect());
+        spawn_menu_row(parent, "Mode", ModeButton::buttons());
+        spawn_menu_row(parent, "Computer", ComputerButton::buttons());
+        spawn_menu_row(parent, "Theme", ThemeButton::buttons());
+        let mut actions = vec![("Start".to_string(), MenuButton::Start)];
+        if slot.0.is_some() {
+            actions.push(("Resume".to_string(), MenuButton::Resume));
+        }
+        spawn_menu_row(parent, "", actions);
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 30.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ).with_text_alignment(TextAlignment::Center), Localized::new("Weekly challenge: {challenge}\n{records}")
+            .with_arg("challenge", weekly.describe())
+            .with_arg("records", weekly_records.describe(weekly.week))));
+        spawn_menu_row(parent, "", vec![("Play the weekly challenge".to_string(), WeeklyButton)]);
+        // The web build reaches no leaderboard
+        #[cfg(not(target_arch = "wasm32"))]
+        spawn_menu_row(parent, "", vec![("Play the daily puzzle".to_string(), DailyButton)]);
+        spawn_menu_row(parent, "", vec![("Campaign".to_string(), CampaignButton)]);
+        spawn_menu_row(parent, "", vec![("Statistics".to_string(), StatsButton)]);
+        spawn_menu_row(parent, "", vec![("Watch a replay".to_string(), WatchReplayButton)]);
+        spawn_menu_row(parent, "", vec![("Settings".to_string(), SettingsButton::Open)]);
+    }).id();
+    commands.insert_resource(MainMenu {
+        entity,
+        // Dimensions given on the command line are kept even out of the menu range
+        width: taquin.width,
+        height: taquin.height,
+        image: sprites.selected.min(sprites.images.len().saturating_sub(1)),
+        empty_slots: empty_slots.0,
+    });
+}
+
+/// Buttons, or Left and Right for the columns, Up and Down for the rows, Return to start and C to resume the saved game.
+/// Tab moves the focus through the buttons, the picture ones included, the arrows and Return then act on it, see `focus`
+/// M switches the game mode, see `countdown`, L opens the campaign, see `level`
+#[allow(clippy::too_many_arguments)]
+fn main_menu_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    button_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut menu: ResMut<MainMenu>,
+    mut taquin: ResMut<Taquin>,
+    mut sprites: ResMut<TaquinSprites>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut resume_events: EventWriter<ResumeRequested>,
+    mut settings: ResMut<Settings>,
+    mut empty_slots: ResMut<EmptySlots>,
+) {
+    let mut pressed = button_query.iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| *button)
+        .collect::<Vec<MenuButton>>();
+    if keyboard_input.just_released(KeyCode::Left) {
+        pressed.push(MenuButton::Width((menu.width - 1).clamp(*MENU_SIZES.start(), *MENU_SIZES.end())));
+    }
+    if keyboard_input.just_released(KeyCode::Right) {
+        pressed.push(MenuButton::Width((menu.width + 1).clamp(*MENU_SIZES.start(), *MENU_SIZES.end())));
+    }
+    if keyboard_input.just_released(KeyCode::Up) {
+        pressed.push(MenuButton::Height((menu.height - 1).clamp(*MENU_SIZES.start(), *MENU_SIZES.end())));
+    }
+    if keyboard_input.just_released(KeyCode::Down) {
+        pressed.push(MenuButton::Height((menu.height + 1).clamp(*MENU_SIZES.start(), *MENU_SIZES.end())));
+    }
+    // Alt+Return toggles the fullscreen, see `display`
+    if keyboard_input.just_released(KeyCode::Return) && !keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]) {
+        pressed.push(MenuButton::Start);
+    }
+    if keyboard_input.just_released(KeyCode::C) {
+        pressed.push(MenuButton::Resume);
+    }
+    for button in pressed {
+        match button {
+            MenuButton::Width(width) if width != menu.width => menu.width = width,
+            MenuButton::Height(height) if height != menu.height => menu.height = height,
+            MenuButton::MegaSize(size) if (size, size) != (menu.width, menu.height) => (menu.width, menu.height) = (size, size),
+            MenuButton::Image(image) if image != menu.image => menu.image = image,
+            MenuButton::EmptySlots(count) if count != menu.empty_slots => menu.empty_slots = count,
+            MenuButton::Start => {
+                info!(width = menu.width, height = menu.height, image = menu.image, empty_slots = menu.empty_slots, "game started from the main menu");
+                *taquin = Taquin::rect(menu.width, menu.height);
+                empty_slots.0 = menu.empty_slots;
+                sprites.selected = menu.image;
+                if settings.board_size() != Some((menu.width, menu.height)) {
+                    (settings.width, settings.height) = (Some(menu.width), Some(menu.height));
+                }
+                next_state.set(AppState::SetupTiles);
+            },
+            MenuButton::Resume => {
+                sprites.selected = menu.image;
+                resume_events.send_default();
+            },
+            _ => (),
+        }
+    }
+}
+
+fn update_main_menu(
+    menu: Res<MainMenu>,
+    mut button_query: Query<(&MenuButton, &mut BackgroundColor)>,
+) {
+    for (button, mut background) in button_query.iter_mut() {
+        let picked = [MenuButton::Width(menu.width), MenuButton::Height(menu.height), MenuButton::Image(menu.image), MenuButton::EmptySlots(menu.empty_slots)].contains(button)
+            || *button == MenuButton::MegaSize(menu.width) && menu.width == menu.height;
+        background.0 = if picked { Color::rgba(0.65, 0.65, 0.65, 0.9) } else { Color::rgba(0.65, 0.65, 0.65, 0.4) };
+    }
+}
+
+fn close_main_menu(
+    mut commands: Commands,
+    menu: Res<MainMenu>,
+) {
+    commands.entity(menu.entity).despawn_recursive();
+    commands.remove_resource::<MainMenu>();
+}
+
+/// F1 or the settings button of the main menu opens it, F1 or its close button closes it
+fn toggle_settings_screen(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    button_query: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    screen: Option<Res<SettingsScreen>>,
+) {
+    let pressed = |wanted: SettingsButton| button_query.iter().any(|(interaction, button)| *interaction == Interaction::Pressed && *button == wanted);
+    let toggled = keyboard_input.just_released(SETTINGS_KEY);
+    if let Some(screen) = screen {
+        if toggled || pressed(SettingsButton::Close) {
+            commands.entity(screen.0).despawn_recursive();
+            commands.remove_resource::<SettingsScreen>();
+        }
+        return;
+    }
+    if !toggled && !pressed(SettingsButton::Open) {
+        return;
+    }
+    let levels = |channel| VOLUME_LEVELS.iter().map(|level| (format!("{}%", (level * 100.) as u32), SettingsButton::Volume(channel, *level))).collect();
+    let entity = commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(20.),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.85).into(),
+        // Above the main menu
+        z_index: ZIndex::Global(6),
+        ..default()
+    }, FocusScope)).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "Settings",
+            TextStyle {
+                font_size: 60.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), Localized::new("Settings")));
+        spawn_menu_row(parent, "Controls", vec![
+            ("Cursor".to_string(), SettingsButton::ControlScheme(ControlScheme::Cursor)),
+            ("Classic".to_string(), SettingsButton::ControlScheme(ControlScheme::Classic)),
+        ]);
+        spawn_menu_row(parent, "Columns", MENU_SIZES.map(|size| (size.to_string(), SettingsButton::Width(size))).collect());
+        spawn_menu_row(parent, "Rows", MENU_SIZES.map(|size| (size.to_string(), SettingsButton::Height(size))).collect());
+        spawn_menu_row(parent, "Theme", ThemeButton::buttons());
+        spawn_menu_row(parent, "Volume", levels(VolumeChannel::Master));
+        spawn_menu_row(parent, "Music", levels(VolumeChannel::Music));
+        spawn_menu_row(parent, "Effects", levels(VolumeChannel::Effects));
+        spawn_menu_row(parent, "VSync", vec![
+            ("On".to_string(), SettingsButton::VSync(VSync::On)),
+            ("Off".to_string(), SettingsButton::VSync(VSync::Off)),
+        ]);
+        spawn_menu_row(parent, "Window", RESOLUTIONS.map(|(width, height)| (format!("{width}x{height}"), SettingsButton::Resolution(width, height))).to_vec());
+        spawn_menu_row(parent, "Key repeat", KeyRepeat::PRESETS.map(|(name, repeat)| (name.to_string(), SettingsButton::KeyRepeat(repeat))).to_vec());
+        spawn_menu_row(parent, "Nudge when stuck", IDLE_HINT_DELAYS.map(|(name, delay)| (name.to_string(), SettingsButton::IdleHint(delay))).to_vec());
+        spawn_menu_row(parent, "Language", Locale::ALL.map(|locale| (locale.label().to_string(), SettingsButton::Language(locale))).to_vec());
+        parent.spawn((TextBundle::from_section(
+            "Keys: F2 while playing. Texture pack: P. Fullscreen: F11",
+            TextStyle {
+                font_size: 30.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), Localized::new("Keys: F2 while playing. Texture pack: P. Fullscreen: F11")));
+        spawn_menu_row(parent, "", vec![("Close".to_string(), SettingsButton::Close)]);
+    }).id();
+    commands.insert_resource(SettingsScreen(entity));
+}
+
+/// The theme buttons are handled in `theme`. A language picked here is also the one of the next runs
+fn settings_screen_input(
+    button_query: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    mut settings: ResMut<Settings>,
+    mut control_scheme: ResMut<ControlScheme>,
+    mut audio_settings: ResMut<AudioSettings>,
+    mut locale: ResMut<Locale>,
+) {
+    for (_, button) in button_query.iter().filter(|(interaction, _)| **interaction == Interaction::Pressed) {
+        match *button {
+            SettingsButton::ControlScheme(scheme) if scheme != *control_scheme => *control_scheme = scheme,
+            SettingsButton::Width(width) if settings.width != Some(width) => {
+                settings.height = settings.height.or(settings.width);
+                settings.width = Some(width);
+            },
+            SettingsButton::Height(height) if settings.height != Some(height) => {
+                settings.width = settings.width.or(settings.height);
+                settings.height = Some(height);
+            },
+            SettingsButton::Volume(channel, level) if channel.volume(&audio_settings) != level => *channel.volume_mut(&mut audio_settings) = level,
+            SettingsButton::VSync(vsync) if settings.vsync != vsync => settings.vsync = vsync,
+            SettingsButton::Resolution(width, height) if settings.resolution != Some((width, height)) => settings.resolution = Some((width, height)),
+            SettingsButton::KeyRepeat(repeat) if settings.key_repeat != repeat => settings.key_repeat = repeat,
+            SettingsButton::IdleHint(delay) if settings.idle_hint_delay != delay => settings.idle_hint_delay = delay,
+            SettingsButton::Language(language) if *locale != language => {
+                *locale = language;
+                settings.language = Some(language);
+            },
+            _ => (),
+        }
+    }
+}
+
+fn update_settings_screen(
+    settings: Res<Settings>,
+    control_scheme: Res<ControlScheme>,
+    audio_settings: Res<AudioSettings>,
+    locale: Res<Locale>,
+    mut button_query: Query<(&SettingsButton, &mut BackgroundColor, Ref<SettingsButton>)>,
+) {
+    let changed = settings.is_changed() || control_scheme.is_changed() || audio_settings.is_changed() || locale.is_changed();
+    for (button, mut background, added) in button_query.iter_mut() {
+        if !changed && !added.is_added() {
+            continue;
+        }
+        let picked = match *button {
+            SettingsButton::ControlScheme(scheme) => scheme == *control_scheme,
+            SettingsButton::Width(width) => settings.width == Some(width),
+            SettingsButton::Height(height) => settings.height.or(settings.width) == Some(height),
+            SettingsButton::Volume(channel, level) => (channel.volume(&audio_settings) - level).abs() < 0.01,
+            SettingsButton::VSync(vsync) => vsync == settings.vsync,
+            SettingsButton::Resolution(width, height) => settings.resolution == Some((width, height)),
+            SettingsButton::KeyRepeat(repeat) => repeat == settings.key_repeat,
+            SettingsButton::IdleHint(delay) => delay == settings.idle_hint_delay,
+            SettingsButton::Language(language) => language == *locale,
+            SettingsButton::Open | SettingsButton::Close => false,
+        };
+        background.0 = if picked { Color::rgba(0.65, 0.65, 0.65, 0.9) } else { Color::rgba(0.65, 0.65, 0.65, 0.4) };
+    }
+}
+
+fn return_to_main_menu(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keyboard_input.just_released(MENU_KEY) {
+        commands.remove_resource::<AutoSolvePlayback>();
+        commands.remove_resource::<PlaybackController>();
+        next_state.set(AppState::MainMenu);
+    }
+}
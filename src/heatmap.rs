@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+
+use crate::{
+    marker::Markers,
+    taquin::{Taquin, TaquinShuffled, TaquinSolved, TileMoved},
+    tile::TileCoordinates,
+    AppState,
+};
+
+const HEATMAP_DEPTH_OFFSET: f32 = 0.05;
+
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<TileTraffic>()
+            .add_systems(Update, (
+                reset_tile_traffic.run_if(on_event::<TaquinShuffled>()),
+                record_tile_traffic.run_if(on_event::<TileMoved>()),
+                spawn_heatmap.run_if(on_event::<TaquinSolved>()),
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// How many times each cell has been moved through since the last shuffle
+#[derive(Resource, Default)]
+pub struct TileTraffic {
+    pub counts: Vec<Vec<u32>>,
+    recording: bool,
+}
+
+impl TileTraffic {
+    pub fn max(&self) -> u32 {
+        self.counts.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    fn record(&mut self, coordinates: TileCoordinates) {
+        if let Some(count) = self.counts.get_mut(coordinates.j as usize).and_then(|row| row.get_mut(coordinates.i as usize)) {
+            *count += 1;
+        }
+    }
+}
+
+#[derive(Component)]
+struct HeatmapCell;
+
+/// Blue for cold cells, red for the most travelled ones
+fn heat_color(heat: f32) -> Color {
+    Color::rgba(heat, 0.2, 1. - heat, 0.35 + heat * 0.35)
+}
+
+fn reset_tile_traffic(
+    mut commands: Commands,
+    taquin: Res<Taquin>,
+    mut traffic: ResMut<TileTraffic>,
+    heatmap_query: Query<Entity, With<HeatmapCell>>,
+) {
+    traffic.counts = vec![vec![0; taquin.width as usize]; taquin.height as usize];
+    traffic.recording = true;
+    for entity in heatmap_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn record_tile_traffic(
+    mut traffic: ResMut<TileTraffic>,
+    mut tile_moved_events: EventReader<TileMoved>,
+) {
+    for event in tile_moved_events.read() {
+        if traffic.recording {
+            traffic.record(event.from);
+            traffic.record(event.to);
+        }
+    }
+}
+
+fn spawn_heatmap(
+    mut commands: Commands,
+    mut traffic: ResMut<TileTraffic>,
+    taquin: Res<Taquin>,
+    markers: Res<Markers>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !traffic.recording {
+        return;
+    }
+    traffic.recording = false;
+
+    let max = traffic.max().max(1) as f32;
+    let mesh = meshes.add(shape::Quad::new(markers.tile_size(taquin.width, taquin.height)).into());
+    for (j, row) in traffic.counts.iter().enumerate() {
+        for (i, count) in row.iter().enumerate() {
+            let translation = markers.tile_translation(taquin.width, taquin.height, TileCoordinates::new(i as i8, j as i8));
+            commands.spawn((PbrBundle {
+                mesh: mesh.clone(),
+                material: materials.add(StandardMaterial {
+                    base_color: heat_color(*count as f32 / max),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                }),
+                transform: Transform::from_translation(translation + Vec3::Z * HEATMAP_DEPTH_OFFSET),
+                ..default()
+            }, HeatmapCell));
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use bevy::{prelude::*, tasks::{block_on, AsyncComputeTaskPool, Task}};
+
+use crate::{
+    auto_solve::AutoSolvePlayback,
+    i18n::Localized,
+    replay::ReplayPlayback,
+    solver::{self, Board},
+    solver_cache::SolverCache,
+    taquin::{is_solver_board, Taquin, TaquinShuffled},
+    tile::{GlowOverride, TileCoordinates, TileIndex, TileSelected},
+    tile_material::TileShading,
+    AppState,
+};
+
+/// Hints given for free in each game
+const FREE_HINTS: usize = 3;
+/// Moves added to the score by each hint past the free ones
+const HINT_PENALTY: usize = 5;
+/// Positions explored before a hint gives up
+const MAX_NODES: u64 = 20_000_000;
+const HINT_KEY: KeyCode = KeyCode::H;
+/// Seconds the hinted tile glows for
+const PULSE_DURATION: f32 = 2.;
+/// Glows per second
+const PULSE_FREQUENCY: f32 = 2.;
+const PULSE_COLOR: Color = Color::rgb(0.2, 0.9, 1.);
+/// Fainter than a hint, a nudge is not asked for
+const NUDGE_COLOR: Color = Color::rgb(0.08, 0.36, 0.4);
+
+pub struct HintsPlugin;
+
+impl Plugin for HintsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HintBudget>()
+            .add_event::<HintRequested>()
+            .add_event::<HintGiven>()
+            .add_event::<HintNudgeRequested>()
+            .add_event::<HintNudged>()
+            .add_systems(Startup, setup_hint_hud)
+            .add_systems(Update, (
+                reset_hint_budget.run_if(on_event::<TaquinShuffled>()),
+                ask_for_hint,
+                request_hint.run_if(on_event::<HintRequested>().and_then(is_solver_board)),
+                request_nudge.run_if(on_event::<HintNudgeRequested>().and_then(not(resource_exists::<HintTask>())).and_then(is_solver_board)),
+                receive_hint.run_if(resource_exists::<HintTask>()),
+                (select_hinted_tile, nudge_hinted_tile, apply_deferred, pulse_hinted_tile).chain(),
+                update_hint_hud.run_if(resource_changed::<HintBudget>()),
+            ).chain().run_if(in_state(AppState::Running)
+                .and_then(not(resource_exists::<ReplayPlayback>()))
+                .and_then(not(resource_exists::<AutoSolvePlayback>()))));
+    }
+}
+
+/// Asks for the next move of an optimal solution
+#[derive(Event, Default)]
+pub struct HintRequested;
+
+/// Tile to slide next, sent once the hint is counted in the budget
+#[derive(Event, Debug, Clone, Copy)]
+pub struct HintGiven(pub TileCoordinates);
+
+/// Asks for a faint glow on the tile of the next optimal move, free and leaving the selection alone, see `idle_hint`
+#[derive(Event, Default)]
+pub struct HintNudgeRequested;
+
+/// Tile glowing faintly for a nudge
+#[derive(Event, Debug, Clone, Copy)]
+pub struct HintNudged(pub TileCoordinates);
+
+/// Hints used since the shuffle, the ones past the free budget cost moves
+#[derive(Resource, Debug, Default)]
+pub struct HintBudget {
+    pub used: usize,
+}
+
+impl HintBudget {
+    pub fn free_left(&self) -> usize {
+        FREE_HINTS.saturating_sub(self.used)
+    }
+
+    /// Moves added to the score of the game
+    pub fn penalty(&self) -> usize {
+        self.used.saturating_sub(FREE_HINTS) * HINT_PENALTY
+    }
+}
+
+#[derive(Resource)]
+struct HintTask {
+    task: Task<Option<Vec<TileCoordinates>>>,
+    board: Board,
+    /// Asked by `HintNudgeRequested`, which does not count in the budget
+    nudge: bool,
+}
+
+/// Glow of the hinted tile, over the selection color until the timer finishes
+#[derive(Component)]
+struct HintPulse {
+    timer: Timer,
+    color: Color,
+}
+
+impl HintPulse {
+    fn new(color: Color) -> Self {
+        Self { timer: Timer::from_seconds(PULSE_DURATION, TimerMode::Once), color }
+    }
+}
+
+#[derive(Component)]
+struct HintButton;
+
+#[derive(Component)]
+struct HintBudgetText;
+
+fn setup_hint_hud(
+    mut commands: Commands,
+) {
+    commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.),
+            top: Val::Percent(21.),
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(10.),
+            ..default()
+        },
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((ButtonBundle {
+            style: Style {
+                padding: UiRect::axes(Val::Px(12.), Val::Px(6.)),
+                ..default()
+            },
+            background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+            ..default()
+        }, HintButton)).with_children(|parent| {
+            parent.spawn((TextBundle::from_section(
+                "Hint",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ), Localized::new("Hint")));
+        });
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), HintBudgetText, Localized::new("{free} free").with_arg("free", FREE_HINTS)));
+    });
+}
+
+fn reset_hint_budget(
+    mut commands: Commands,
+    mut budget: ResMut<HintBudget>,
+) {
+    *budget = HintBudget::default();
+    commands.remove_resource::<HintTask>();
+}
+
+fn ask_for_hint(
+    keyboard_input: Res<Input<KeyCode>>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<HintButton>)>,
+    mut hint_events: EventWriter<HintRequested>,
+) {
+    if keyboard_input.just_released(HINT_KEY) || button_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        hint_events.send_default();
+    }
+}
+
+/// The first move of an optimal solution when the solver cache has it, otherwise the board is solved off the frame
+fn start_hint(commands: &mut Commands, taquin: &Taquin, cache: &mut SolverCache, nudge: bool) -> Option<TileCoordinates> {
+    if !taquin.is_shuffled || taquin.is_solved() {
+        return None;
+    }
+    let board = taquin.board();
+    if let Some(tile) = cache.get(&board).and_then(|cached| cached.first_move) {
+        return Some(tile);
+    }
+    let task_board = board.clone();
+    let task = AsyncComputeTaskPool::get().spawn(async move { solver::solve(&task_board, MAX_NODES) });
+    commands.insert_resource(HintTask { task, board, nudge });
+    None
+}
+
+/// A hint asked while a nudge is being solved takes its answer over
+fn request_hint(
+    mut commands: Commands,
+    mut hint_events: EventReader<HintRequested>,
+    taquin: Res<Taquin>,
+    task: Option<ResMut<HintTask>>,
+    mut cache: ResMut<SolverCache>,
+    mut budget: ResMut<HintBudget>,
+    mut given_events: EventWriter<HintGiven>,
+) {
+    hint_events.clear();
+    if let Some(mut task) = task {
+        task.nudge = false;
+        return;
+    }
+    if let Some(tile) = start_hint(&mut commands, &taquin, &mut cache, false) {
+        budget.used += 1;
+        given_events.send(HintGiven(tile));
+    }
+}
+
+fn request_nudge(
+    mut commands: Commands,
+    mut nudge_events: EventReader<HintNudgeRequested>,
+    taquin: Res<Taquin>,
+    mut cache: ResMut<SolverCache>,
+    mut nudged_events: EventWriter<HintNudged>,
+) {
+    nudge_events.clear();
+    if let Some(tile) = start_hint(&mut commands, &taquin, &mut cache, true) {
+        nudged_events.send(HintNudged(tile));
+    }
+}
+
+fn receive_hint(
+    mut commands: Commands,
+    mut task: ResMut<HintTask>,
+    mut cache: ResMut<SolverCache>,
+    mut budget: ResMut<HintBudget>,
+    mut given_events: EventWriter<HintGiven>,
+    mut nudged_events: EventWriter<HintNudged>,
+) {
+    if !task.task.is_finished() {
+        return;
+    }
+    commands.remove_resource::<HintTask>();
+    let Some(solution) = block_on(&mut task.task) else {
+        warn!("no hint, the board is too hard for the solver");
+        return;
+    };
+    cache.insert_solution(&task.board, &solution);
+    match solution.first() {
+        Some(tile) if task.nudge => nudged_events.send(HintNudged(*tile)),
+        Some(tile) => {
+            budget.used += 1;
+            given_events.send(HintGiven(*tile));
+        },
+        None => (),
+    }
+}
+
+/// Moves the selection onto the hinted tile, Space slides it, and makes it glow
+fn select_hinted_tile(
+    mut commands: Commands,
+    mut given_events: EventReader<HintGiven>,
+    selected_query: Query<Entity, With<TileSelected>>,
+    index: Res<TileIndex>,
+) {
+    let Some(HintGiven(tile)) = given_events.read().last().copied() else {
+        return;
+    };
+    let Some(entity) = index.at(tile) else {
+        return;
+    };
+    for selected in selected_query.iter().filter(|selected| *selected != entity) {
+        commands.entity(selected).remove::<TileSelected>();
+    }
+    commands.entity(entity).insert((TileSelected, HintPulse::new(PULSE_COLOR), GlowOverride));
+}
+
+/// Only makes the tile glow, the player may be about to move another one
+fn nudge_hinted_tile(
+    mut commands: Commands,
+    mut nudged_events: EventReader<HintNudged>,
+    index: Res<TileIndex>,
+) {
+    let Some(entity) = nudged_events.read().last().and_then(|HintNudged(tile)| index.at(*tile)) else {
+        return;
+    };
+    commands.entity(entity).insert((HintPulse::new(NUDGE_COLOR), GlowOverride));
+}
+
+/// Fades the emissive color in and out, then gives the glow back to the selection pulse, see `tile`
+fn pulse_hinted_tile(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pulse_query: Query<(Entity, &mut HintPulse, &mut TileShading)>,
+) {
+    for (entity, mut pulse, mut shading) in pulse_query.iter_mut() {
+        if pulse.timer.tick(time.delta()).finished() {
+            shading.glow = Color::BLACK;
+            commands.entity(entity).remove::<(HintPulse, GlowOverride)>();
+            continue;
+        }
+        let glow = 0.5 - 0.5 * (pulse.timer.elapsed_secs() * PULSE_FREQUENCY * std::f32::consts::TAU).cos();
+        shading.glow = pulse.color * glow;
+    }
+}
+
+fn update_hint_hud(
+    budget: Res<HintBudget>,
+    mut text_query: Query<&mut Localized, With<HintBudgetText>>,
+) {
+    let value = match budget.free_left() {
+        0 => Localized::new("+{penalty} moves, next hint +{next}").with_arg("penalty", budget.penalty()).with_arg("next", HINT_PENALTY),
+        free => Localized::new("{free} free").with_arg("free", free),
+    };
+    for mut localized in text_query.iter_mut() {
+        if *localized != value {
+            *localized = value.clone();
+        }
+    }
+}
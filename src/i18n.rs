@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, ui::UiSystem};
+
+use crate::locale::Locale;
+
+pub struct I18nPlugin;
+
+impl Plugin for I18nPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Translations>()
+            .add_systems(Update, load_translations.run_if(resource_changed::<Locale>()))
+            // Before the texts are measured, so a new text never shows its english source for a frame
+            .add_systems(PostUpdate, localize_texts.before(UiSystem::Layout));
+    }
+}
+
+/// Texts of the GUI in the language of the locale. The english text is the key, a text missing from
+/// `assets/i18n/<code>.ron` stays in english
+#[derive(Resource, Debug, Default)]
+pub struct Translations {
+    strings: HashMap<String, String>,
+}
+
+impl Translations {
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let strings = ron::from_str(contents).map_err(|error| error.to_string())?;
+        Ok(Self { strings })
+    }
+
+    /// English has no file, its texts are the keys
+    fn load(locale: Locale) -> Self {
+        if locale == Locale::English {
+            return Self::default();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        let contents = {
+            let path = bevy::asset::io::file::FileAssetReader::get_base_path().join("assets").join("i18n").join(format!("{}.ron", locale.code()));
+            std::fs::read_to_string(&path).map_err(|error| format!("{}: {error}", path.display()))
+        };
+        // A browser cannot read the assets folder, the translations are built in there
+        #[cfg(target_arch = "wasm32")]
+        let contents = match locale {
+            Locale::French => Ok(include_str!("../assets/i18n/fr.ron").to_string()),
+            Locale::German => Ok(include_str!("../assets/i18n/de.ron").to_string()),
+            _ => Err(format!("no translations for {}", locale.code())),
+        };
+        contents.and_then(|contents| Self::parse(&contents)).unwrap_or_else(|error| {
+            warn!("texts stay in english: {error}");
+            Self::default()
+        })
+    }
+
+    pub fn get<'a>(&'a self, source: &'a str) -> &'a str {
+        self.strings.get(source).map_or(source, String::as_str)
+    }
+
+    /// Translation of `source` with each `{name}` replaced by its value
+    pub fn format(&self, source: &str, args: &[(&str, String)]) -> String {
+        args.iter().fold(self.get(source).to_string(), |text, (name, value)| text.replace(&format!("{{{name}}}"), value))
+    }
+}
+
+/// Text of a `Text` entity given in english, shown in the language of the locale and updated when it changes
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Localized {
+    source: String,
+    args: Vec<(&'static str, String)>,
+}
+
+impl Localized {
+    pub fn new(source: impl Into<String>) -> Self {
+        Self { source: source.into(), args: Vec::new() }
+    }
+
+    /// Value of the `{name}` placeholder of the source
+    pub fn with_arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+}
+
+fn load_translations(
+    locale: Res<Locale>,
+    mut translations: ResMut<Translations>,
+) {
+    *translations = Translations::load(*locale);
+    info!(locale = locale.code(), texts = translations.strings.len(), "translations loaded");
+}
+
+fn localize_texts(
+    translations: Res<Translations>,
+    mut text_query: Query<(Ref<Localized>, &mut Text)>,
+) {
+    for (localized, mut text) in text_query.iter_mut() {
+        if !translations.is_changed() && !localized.is_changed() {
+            continue;
+        }
+        let value = translations.format(&localized.source, &localized.args);
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::i18n::Translations;
+
+    #[test]
+    fn test_translations() {
+        let french = Translations::parse(include_str!("../assets/i18n/fr.ron")).unwrap();
+        assert_eq!(french.get("Columns"), "Colonnes");
+        assert_eq!(french.get("1280x720"), "1280x720");
+        assert_eq!(french.format("Seed {seed}", &[("seed", "42".to_string())]), "Graine 42");
+        let german = Translations::parse(include_str!("../assets/i18n/de.ron")).unwrap();
+        // Both languages translate the same texts
+        let mut french_keys = french.strings.keys().collect::<Vec<_>>();
+        let mut german_keys = german.strings.keys().collect::<Vec<_>>();
+        french_keys.sort();
+        german_keys.sort();
+        assert_eq!(french_keys, german_keys);
+    }
+}
@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+
+use crate::{
+    auto_solve::AutoSolvePlayback,
+    hints::{HintGiven, HintNudgeRequested, HintNudged},
+    i18n::Localized,
+    replay::ReplayPlayback,
+    settings::Settings,
+    taquin::{is_solver_board, Taquin, TaquinShuffled, TileMoved},
+    tile::TileSlide,
+    AppState,
+};
+
+/// Nudges after which the wait stops doubling
+const MAX_DOUBLINGS: u32 = 3;
+/// Seconds the toast stays unless a move closes it first
+const TOAST_DURATION: f32 = 8.;
+
+pub struct IdleHintPlugin;
+
+impl Plugin for IdleHintPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Inactivity>()
+            .add_systems(Update, (
+                reset_inactivity,
+                track_inactivity.run_if(is_solver_board),
+                show_idle_toast.run_if(on_event::<HintNudged>()),
+                close_idle_toast.run_if(any_with_component::<IdleToast>()),
+            ).chain().run_if(in_state(AppState::Running)
+                .and_then(not(resource_exists::<ReplayPlayback>()))
+                .and_then(not(resource_exists::<AutoSolvePlayback>()))))
+            .add_systems(OnExit(AppState::Running), despawn_idle_toast);
+    }
+}
+
+/// Seconds without a move on the shuffled board, fed by `TileMoved`, and the nudges given in the game
+#[derive(Resource, Debug, Default)]
+pub struct Inactivity {
+    idle: f32,
+    nudges: u32,
+    /// The toast was closed by the player, no more nudges until the next shuffle
+    dismissed: bool,
+}
+
+impl Inactivity {
+    /// Every nudge waits twice as long as the one before, a player thinking a move through is not nagged
+    fn wait(&self, delay: f32) -> f32 {
+        delay * 2_f32.powi(self.nudges.min(MAX_DOUBLINGS) as i32)
+    }
+
+    /// Whether a nudge is due after `delta` more idle seconds, with nudges every `delay` seconds or none
+    fn tick(&mut self, delta: f32, delay: Option<f32>) -> bool {
+        let Some(delay) = delay.filter(|_| !self.dismissed) else {
+            return false;
+        };
+        self.idle += delta;
+        if self.idle < self.wait(delay) {
+            return false;
+        }
+        self.idle = 0.;
+        self.nudges += 1;
+        true
+    }
+}
+
+/// Closes after `TOAST_DURATION`, a click on it turns the nudges off for the game
+#[derive(Component)]
+struct IdleToast(Timer);
+
+#[derive(Component)]
+struct IdleToastButton;
+
+/// A move or a hint starts the wait over and closes the toast, a shuffle also forgets the nudges given
+fn reset_inactivity(
+    mut commands: Commands,
+    mut inactivity: ResMut<Inactivity>,
+    mut moved_events: EventReader<TileMoved>,
+    mut shuffled_events: EventReader<TaquinShuffled>,
+    mut given_events: EventReader<HintGiven>,
+    toast_query: Query<Entity, With<IdleToast>>,
+) {
+    let shuffled = shuffled_events.read().count() > 0;
+    if shuffled {
+        *inactivity = Inactivity::default();
+    }
+    if !shuffled && moved_events.read().count() == 0 && given_events.read().count() == 0 {
+        return;
+    }
+    inactivity.idle = 0.;
+    for entity in toast_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Time spent watching tiles slide is not idle, nor the time on a board solved or not shuffled yet
+fn track_inactivity(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    taquin: Res<Taquin>,
+    slide_query: Query<(), With<TileSlide>>,
+    mut inactivity: ResMut<Inactivity>,
+    mut nudge_events: EventWriter<HintNudgeRequested>,
+) {
+    if !taquin.is_shuffled || taquin.is_solved() || !slide_query.is_empty() {
+        return;
+    }
+    if inactivity.tick(time.delta_seconds(), settings.idle_hint_delay) {
+        info!(nudges = inactivity.nudges, "player idle, nudging");
+        nudge_events.send_default();
+    }
+}
+
+/// Shown along with the glow, so it never points at nothing
+fn show_idle_toast(
+    mut commands: Commands,
+    toast_query: Query<(), With<IdleToast>>,
+) {
+    if !toast_query.is_empty() {
+        return;
+    }
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Percent(12.),
+            width: Val::Percent(100.),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        z_index: ZIndex::Global(7),
+        ..default()
+    }, IdleToast(Timer::from_seconds(TOAST_DURATION, TimerMode::Once)))).with_children(|parent| {
+        parent.spawn((ButtonBundle {
+            style: Style {
+                padding: UiRect::axes(Val::Px(12.), Val::Px(6.)),
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.7).into(),
+            ..default()
+        }, IdleToastButton)).with_children(|parent| {
+            parent.spawn((TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ), Localized::new("Stuck? Press H for a hint")));
+        });
+    });
+}
+
+fn close_idle_toast(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut inactivity: ResMut<Inactivity>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<IdleToastButton>)>,
+    mut toast_query: Query<(Entity, &mut IdleToast)>,
+) {
+    let clicked = button_query.iter().any(|interaction| *interaction == Interaction::Pressed);
+    if clicked {
+        info!("idle nudges dismissed until the next shuffle");
+        inactivity.dismissed = true;
+    }
+    for (entity, mut toast) in toast_query.iter_mut() {
+        if toast.0.tick(time.delta()).finished() || clicked {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn despawn_idle_toast(
+    mut commands: Commands,
+    toast_query: Query<Entity, With<IdleToast>>,
+) {
+    for entity in toast_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::idle_hint::Inactivity;
+
+    #[test]
+    fn test_inactivity() {
+        let mut inactivity = Inactivity::default();
+        assert!(!inactivity.tick(100., None));
+        assert_eq!(inactivity.idle, 0.);
+
+        assert!(!inactivity.tick(29., Some(30.)));
+        assert!(inactivity.tick(1., Some(30.)));
+        // The next nudge waits twice as long
+        assert!(!inactivity.tick(59., Some(30.)));
+        assert!(inactivity.tick(1., Some(30.)));
+        // Up to a limit
+        inactivity.nudges = 10;
+        inactivity.idle = 0.;
+        assert!(inactivity.tick(240., Some(30.)));
+
+        inactivity.dismissed = true;
+        assert!(!inactivity.tick(1000., Some(30.)));
+    }
+}
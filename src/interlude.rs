@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+
+pub struct InterludePlugin;
+
+impl Plugin for InterludePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<BeginInterlude>()
+            .add_event::<EndInterlude>()
+            .add_systems(Update, begin_interlude.run_if(in_state(AppState::Running).and_then(on_event::<BeginInterlude>())))
+            .add_systems(Update, end_interlude.run_if(in_state(AppState::Interlude).and_then(on_event::<EndInterlude>())))
+            .add_systems(OnEnter(AppState::Interlude), freeze_game)
+            .add_systems(OnExit(AppState::Interlude), thaw_game);
+    }
+}
+
+/// Sent before a modal OS dialog, such as a file picker, is opened during a game. The dialog opens
+/// once `AppState::Interlude` is entered, so the timer, the tile animations and the input are frozen
+/// while it blocks the frame
+#[derive(Event, Debug, Clone, Default)]
+pub struct BeginInterlude {
+    /// What the dialog is for, in the logs
+    pub reason: String,
+}
+
+/// Sent once the dialog is closed, the game goes on as after a pause
+#[derive(Event, Default)]
+pub struct EndInterlude;
+
+fn begin_interlude(
+    mut begin_events: EventReader<BeginInterlude>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if let Some(begin) = begin_events.read().last() {
+        info!(reason = begin.reason, "interlude");
+        next_state.set(AppState::Interlude);
+    }
+}
+
+fn end_interlude(
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    next_state.set(AppState::Running);
+}
+
+/// The `GameClock` of the solve clocks stops as well, out of `AppState::Running`
+fn freeze_game(
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    virtual_time.pause();
+}
+
+fn thaw_game(
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    virtual_time.unpause();
+}
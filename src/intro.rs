@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+
+use crate::{graphics::MainCamera, marker::Markers, settings::Settings, tile::Easing, tutorial, AppState, SkipMainMenu};
+
+/// Seconds from the start of the flight to the playing position
+const FLIGHT_DURATION: f32 = 2.5;
+/// Where the camera starts from, above and behind the playing position
+const FLIGHT_START: Vec3 = Vec3::new(0., 45., 25.);
+
+pub struct IntroPlugin;
+
+impl Plugin for IntroPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::Intro), start_intro)
+            .add_systems(Update, fly_camera.run_if(in_state(AppState::Intro).and_then(resource_exists::<IntroFlight>())))
+            .add_systems(OnExit(AppState::Intro), land_camera);
+    }
+}
+
+/// Camera flying in over the board, from `from` to the playing position `to`
+#[derive(Resource)]
+struct IntroFlight {
+    from: Transform,
+    to: Transform,
+    elapsed: f32,
+}
+
+impl IntroFlight {
+    fn transform(&self) -> Transform {
+        let t = Easing::EaseOut.ease((self.elapsed / FLIGHT_DURATION).min(1.));
+        Transform {
+            translation: self.from.translation.lerp(self.to.translation, t),
+            rotation: self.from.rotation.slerp(self.to.rotation, t),
+            ..self.to
+        }
+    }
+}
+
+/// Only the first game starts with the flight, and not the automated ones. The tutorial follows on the first launch
+fn start_intro(
+    mut commands: Commands,
+    markers: Res<Markers>,
+    settings: Res<Settings>,
+    skip_main_menu: Option<Res<SkipMainMenu>>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut played: Local<bool>,
+) {
+    let Ok(mut camera) = camera_query.get_single_mut() else {
+        next_state.set(tutorial::state_after_intro(&settings, skip_main_menu.is_some()));
+        return;
+    };
+    if *played || skip_main_menu.is_some() {
+        next_state.set(tutorial::state_after_intro(&settings, skip_main_menu.is_some()));
+        return;
+    }
+    *played = true;
+    let to = *camera;
+    let from = Transform::from_translation(to.translation + FLIGHT_START).looking_at(markers.center(), Vec3::Y);
+    *camera = from;
+    info!("intro started");
+    commands.insert_resource(IntroFlight { from, to, elapsed: 0. });
+}
+
+/// Any key, button or touch skips to the end of the flight
+#[allow(clippy::too_many_arguments)]
+fn fly_camera(
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    touches: Res<Touches>,
+    time: Res<Time<Real>>,
+    mut flight: ResMut<IntroFlight>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+    settings: Res<Settings>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let skipped = keyboard_input.get_just_pressed().count() + mouse_input.get_just_pressed().count()
+        + gamepad_input.get_just_pressed().count() + touches.iter_just_pressed().count() > 0;
+    flight.elapsed += time.delta_seconds();
+    if skipped || flight.elapsed >= FLIGHT_DURATION {
+        next_state.set(tutorial::state_after_intro(&settings, false));
+        return;
+    }
+    for mut camera in camera_query.iter_mut() {
+        *camera = flight.transform();
+    }
+}
+
+fn land_camera(
+    mut commands: Commands,
+    flight: Option<Res<IntroFlight>>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    let Some(flight) = flight else {
+        return;
+    };
+    for mut camera in camera_query.iter_mut() {
+        *camera = flight.to;
+    }
+    commands.remove_resource::<IntroFlight>();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::intro::{IntroFlight, FLIGHT_DURATION};
+
+    #[test]
+    fn test_intro_flight() {
+        let to = Transform::from_xyz(0., 30., 40.).looking_at(Vec3::Y, Vec3::Y);
+        let from = Transform::from_xyz(0., 75., 65.).looking_at(Vec3::Y, Vec3::Y);
+        let mut flight = IntroFlight { from, to, elapsed: 0. };
+        assert_eq!(flight.transform().translation, from.translation);
+        // Eased out, most of the way is flown in the first half
+        flight.elapsed = FLIGHT_DURATION / 2.;
+        assert!(flight.transform().translation.distance(to.translation) < from.translation.distance(to.translation) / 4.);
+        flight.elapsed = FLIGHT_DURATION * 2.;
+        assert!(flight.transform().translation.distance(to.translation) < 1e-4);
+    }
+}
@@ -0,0 +1,281 @@
+use std::{collections::BTreeMap, fmt};
+
+use bevy::prelude::*;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    persistence::{Format, PersistenceError, Versioned},
+    settings::Settings,
+    AppState,
+};
+
+const SCREEN_KEY: KeyCode = KeyCode::F2;
+/// Keys a binding can be given, the others are kept for the menus and the screens
+const BINDABLE_KEYS: [KeyCode; 56] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G, KeyCode::H, KeyCode::I,
+    KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N, KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R,
+    KeyCode::S, KeyCode::T, KeyCode::U, KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Numpad0, KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3, KeyCode::Numpad4,
+    KeyCode::Numpad5, KeyCode::Numpad6, KeyCode::Numpad7, KeyCode::Numpad8, KeyCode::Numpad9,
+    KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down,
+    KeyCode::Space, KeyCode::Comma, KeyCode::Period, KeyCode::Semicolon, KeyCode::Slash, KeyCode::Back,
+];
+
+pub struct KeyBindingsPlugin;
+
+impl Plugin for KeyBindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<KeyBindings>()
+            .add_systems(Update, (
+                (toggle_bindings_screen, navigate_bindings_screen.run_if(resource_exists::<BindingsScreen>())).chain(),
+                update_bindings_screen.run_if(resource_exists::<BindingsScreen>()),
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// What a bound key does on the board
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Binding {
+    Left,
+    Right,
+    Up,
+    Down,
+    Slide,
+    Shuffle,
+    /// Next picture of the pack
+    Texture,
+    /// Switches between the cursor and the classic controls
+    ControlScheme,
+}
+
+impl Binding {
+    const ALL: [Binding; 8] = [
+        Binding::Left, Binding::Right, Binding::Up, Binding::Down,
+        Binding::Slide, Binding::Shuffle, Binding::Texture, Binding::ControlScheme,
+    ];
+
+    fn default_key(self) -> KeyCode {
+        match self {
+            Binding::Left => KeyCode::Left,
+            Binding::Right => KeyCode::Right,
+            Binding::Up => KeyCode::Up,
+            Binding::Down => KeyCode::Down,
+            Binding::Slide => KeyCode::Space,
+            Binding::Shuffle => KeyCode::R,
+            Binding::Texture => KeyCode::T,
+            Binding::ControlScheme => KeyCode::K,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Binding::Left => "Left",
+            Binding::Right => "Right",
+            Binding::Up => "Up",
+            Binding::Down => "Down",
+            Binding::Slide => "Slide",
+            Binding::Shuffle => "Shuffle",
+            Binding::Texture => "Next picture",
+            Binding::ControlScheme => "Control scheme",
+        }
+    }
+}
+
+/// A key written by its name, as `Left`, `Space` or `Q`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct KeyName(KeyCode);
+
+impl fmt::Display for KeyName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl Serialize for KeyName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        BINDABLE_KEYS.into_iter()
+            .map(KeyName)
+            .find(|key| key.to_string() == name)
+            .ok_or_else(|| de::Error::custom(format!("unknown key {name}")))
+    }
+}
+
+/// Keys of the board actions, saved in `Settings`. The bindings missing from the file keep their default key
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyBindings {
+    #[serde(default)]
+    pub version: u32,
+    keys: BTreeMap<Binding, KeyName>,
+}
+
+impl Versioned for KeyBindings {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl FromWorld for KeyBindings {
+    fn from_world(world: &mut World) -> Self {
+        world.resource::<Settings>().key_bindings.clone()
+    }
+}
+
+impl KeyBindings {
+    /// Every binding on its default key
+    pub fn new() -> Self {
+        Self { version: Self::VERSION, keys: BTreeMap::new() }
+    }
+
+    pub fn key(&self, binding: Binding) -> KeyCode {
+        self.keys.get(&binding).map_or(binding.default_key(), |key| key.0)
+    }
+
+    pub fn just_released(&self, keyboard_input: &Input<KeyCode>, binding: Binding) -> bool {
+        keyboard_input.just_released(self.key(binding))
+    }
+
+    pub fn just_pressed(&self, keyboard_input: &Input<KeyCode>, binding: Binding) -> bool {
+        keyboard_input.just_pressed(self.key(binding))
+    }
+
+    pub fn pressed(&self, keyboard_input: &Input<KeyCode>, binding: Binding) -> bool {
+        keyboard_input.pressed(self.key(binding))
+    }
+
+    /// Gives `key` to `binding`, a binding which had it takes the previous key of `binding`
+    fn set(&mut self, binding: Binding, key: KeyCode) {
+        let previous = self.key(binding);
+        if let Some(other) = Binding::ALL.into_iter().find(|other| *other != binding && self.key(*other) == key) {
+            self.keys.insert(other, KeyName(previous));
+        }
+        self.keys.insert(binding, KeyName(key));
+    }
+}
+
+/// Lists the bindings, `Tab` moves the highlight and `Return` waits for the new key of the highlighted one
+#[derive(Resource)]
+struct BindingsScreen {
+    entity: Entity,
+    highlighted: usize,
+}
+
+/// Set while the bindings screen waits for a key, the board ignores the keys meanwhile
+#[derive(Resource)]
+pub struct RebindingKey(Binding);
+
+#[derive(Component)]
+struct BindingsText;
+
+fn toggle_bindings_screen(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    screen: Option<Res<BindingsScreen>>,
+) {
+    if !keyboard_input.just_released(SCREEN_KEY) {
+        return;
+    }
+    if let Some(screen) = screen {
+        commands.entity(screen.entity).despawn_recursive();
+        commands.remove_resource::<BindingsScreen>();
+        commands.remove_resource::<RebindingKey>();
+        return;
+    }
+    let entity = commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.),
+            top: Val::Px(120.),
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(5),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), BindingsText));
+    }).id();
+    commands.insert_resource(BindingsScreen { entity, highlighted: 0 });
+}
+
+fn navigate_bindings_screen(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    rebinding: Option<Res<RebindingKey>>,
+    mut screen: ResMut<BindingsScreen>,
+    mut bindings: ResMut<KeyBindings>,
+) {
+    if let Some(rebinding) = rebinding {
+        if let Some(key) = keyboard_input.get_just_released().find(|key| BINDABLE_KEYS.contains(key)) {
+            info!(binding = ?rebinding.0, ?key, "key bound");
+            bindings.set(rebinding.0, *key);
+            commands.remove_resource::<RebindingKey>();
+        }
+        return;
+    }
+    if keyboard_input.just_released(KeyCode::Tab) {
+        screen.highlighted = (screen.highlighted + 1) % Binding::ALL.len();
+    }
+    if keyboard_input.just_released(KeyCode::Return) {
+        commands.insert_resource(RebindingKey(Binding::ALL[screen.highlighted]));
+    }
+}
+
+fn update_bindings_screen(
+    screen: Res<BindingsScreen>,
+    bindings: Res<KeyBindings>,
+    rebinding: Option<Res<RebindingKey>>,
+    mut text_query: Query<&mut Text, With<BindingsText>>,
+) {
+    let lines = Binding::ALL.iter().enumerate().map(|(index, binding)| {
+        let cursor = if index == screen.highlighted { ">" } else { " " };
+        let key = match &rebinding {
+            Some(rebinding) if rebinding.0 == *binding => "press a key".to_string(),
+            _ => KeyName(bindings.key(*binding)).to_string(),
+        };
+        format!("{cursor} {}: {key}", binding.label())
+    }).collect::<Vec<String>>();
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Keys (Tab, Return to rebind)\n{}", lines.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::{keybindings::{Binding, KeyBindings}, persistence::Format};
+
+    #[test]
+    fn test_key_bindings() {
+        let mut bindings = KeyBindings::new();
+        assert_eq!(bindings.key(Binding::Shuffle), KeyCode::R);
+        // Z was free, then taking the slide key swaps the two
+        bindings.set(Binding::Up, KeyCode::Z);
+        bindings.set(Binding::Slide, KeyCode::Z);
+        assert_eq!((bindings.key(Binding::Slide), bindings.key(Binding::Up)), (KeyCode::Z, KeyCode::Space));
+
+        let contents = Format::Ron.serialize(&bindings).unwrap();
+        assert!(contents.contains("\"Space\""));
+        assert_eq!(Format::Ron.parse::<KeyBindings>(&contents).unwrap(), bindings);
+        assert!(Format::Ron.parse::<KeyBindings>(r#"(version: 1, keys: {Slide: "Hyper"})"#).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::{frame::frame_markers, marker::Markers};
+
+/// Shortest side of the inside of the frame, a shorter one is taken for corners left unset
+const MIN_SIDE: f32 = 0.01;
+/// Slack on the corners, in ratio of the longest side
+const TOLERANCE: f32 = 1e-3;
+
+/// Why the markers cannot lay the board out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayoutError {
+    /// A corner is not a number or infinite
+    NotFinite(&'static str),
+    /// Corners at the same place, the markers were not set
+    Degenerate { width: f32, height: f32 },
+    /// The bottom side is not the top one moved down, the tiles would be skewed
+    Skewed { gap: f32 },
+    /// The sides are not square with each other
+    NotSquare { angle: f32 },
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::NotFinite(corner) => write!(f, "the {corner} marker is not a finite position, check its transform"),
+            LayoutError::Degenerate { width, height } => write!(f, "the markers span {width}x{height}, some corners are missing or on top of each other"),
+            LayoutError::Skewed { gap } => write!(f, "the bottom corners are {gap} off the top ones, the markers do not form a rectangle"),
+            LayoutError::NotSquare { angle } => write!(f, "the sides of the frame meet at {angle}°, the top left corner should be square"),
+        }
+    }
+}
+
+/// The corners of a rectangle of any size and orientation lay a board out, the tiles are placed in its plane
+pub fn validate_markers(markers: &Markers) -> Result<(), LayoutError> {
+    let corners = [("top left", markers.tl), ("top right", markers.tr), ("bottom left", markers.bl), ("bottom right", markers.br)];
+    if let Some((corner, _)) = corners.into_iter().find(|(_, position)| !position.is_finite()) {
+        return Err(LayoutError::NotFinite(corner));
+    }
+    let (top, left) = (markers.tr - markers.tl, markers.bl - markers.tl);
+    let (width, height) = (top.length(), left.length());
+    if width < MIN_SIDE || height < MIN_SIDE {
+        return Err(LayoutError::Degenerate { width, height });
+    }
+    let gap = (markers.br - markers.bl).distance(top);
+    if gap > TOLERANCE * width.max(height) {
+        return Err(LayoutError::Skewed { gap });
+    }
+    if (top / width).dot(left / height).abs() > TOLERANCE {
+        return Err(LayoutError::NotSquare { angle: top.angle_between(left).to_degrees() });
+    }
+    Ok(())
+}
+
+/// The markers when they lay the board out, the procedural layout of `frame` for a `width` by `height` board
+/// otherwise, so the game still starts
+pub fn checked_markers(markers: Markers, width: i8, height: i8) -> Markers {
+    match validate_markers(&markers) {
+        Ok(()) => markers,
+        Err(error) => {
+            error!(?markers, "invalid frame markers, the board is laid out procedurally: {error}");
+            frame_markers(width, height)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::{
+        frame::frame_markers,
+        layout::{checked_markers, validate_markers, LayoutError},
+        marker::Markers,
+        tile::TileCoordinates,
+    };
+
+    #[test]
+    fn test_validate_markers() {
+        let markers = frame_markers(4, 3);
+        assert_eq!(validate_markers(&markers), Ok(()));
+        assert!(matches!(validate_markers(&Markers::default()), Err(LayoutError::Degenerate { .. })));
+        assert_eq!(validate_markers(&Markers { tr: Vec3::NAN, ..markers }), Err(LayoutError::NotFinite("top right")));
+        assert!(matches!(validate_markers(&Markers { br: markers.br + Vec3::Y, ..markers }), Err(LayoutError::Skewed { .. })));
+        // A parallelogram has parallel sides but a slanted corner
+        let slant = Vec3::X * 2.;
+        let slanted = Markers { bl: markers.bl + slant, br: markers.br + slant, ..markers };
+        assert!(matches!(validate_markers(&slanted), Err(LayoutError::NotSquare { .. })));
+        assert_eq!(checked_markers(slanted, 4, 3), markers);
+    }
+
+    #[test]
+    fn test_tilted_markers() {
+        let flat = frame_markers(4, 4);
+        let tilt = Quat::from_rotation_x(-0.4) * Quat::from_rotation_z(0.3);
+        let tilted = Markers { tl: tilt * flat.tl, tr: tilt * flat.tr, bl: tilt * flat.bl, br: tilt * flat.br };
+        assert_eq!(validate_markers(&tilted), Ok(()));
+        assert!((tilted.inner_width() - flat.inner_width()).abs() < 1e-4);
+        assert!(tilted.rotation().abs_diff_eq(tilt, 1e-5));
+        let cell = TileCoordinates::new(2, 1);
+        assert!(tilted.tile_translation(4, 4, cell).abs_diff_eq(tilt * flat.tile_translation(4, 4, cell), 1e-4));
+        assert_eq!(flat.rotation(), Quat::IDENTITY);
+    }
+}
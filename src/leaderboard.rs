@@ -0,0 +1,622 @@
+use std::{fmt, io};
+
+use bevy::{prelude::*, tasks::{block_on, IoTaskPool, Task}};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    i18n::Localized,
+    locale::Locale,
+    pause::GameClock,
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    platform,
+    taquin::{seeded_tiles, ArrangeTiles, Taquin, TaquinShuffled, TaquinSolved, TileMoved},
+    AppState,
+};
+
+const DAY_SECONDS: u64 = 24 * 60 * 60;
+/// Every daily puzzle is on a board of this size so the times compare across days
+const DAILY_SIZE: i8 = 4;
+/// Fastest players of the day shown, friends are shown whatever their rank
+const TOP_COUNT: usize = 10;
+/// Letters of the friend codes, the ones easily mistaken for others left out
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+/// Letters of a friend code, shown in two halves
+const CODE_LENGTH: usize = 8;
+
+/// Daily puzzle, the same board for every player on a given day. Its times go to the leaderboard whose url
+/// `leaderboard.ron` gives, along with the times of the friends whose codes were added
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Friends>()
+            .add_systems(Startup, load_leaderboard)
+            .add_systems(Update, start_daily_puzzle.run_if(in_state(AppState::MainMenu)))
+            .add_systems(OnEnter(AppState::MainMenu), leave_daily_puzzle)
+            .add_systems(OnEnter(AppState::Running), arrange_daily_board.run_if(resource_exists::<ActiveDaily>()))
+            .add_systems(Update, (
+                leave_daily_puzzle_on_shuffle.run_if(on_event::<TaquinShuffled>()),
+                count_daily_moves.run_if(on_event::<TileMoved>()),
+                finish_daily_puzzle.run_if(on_event::<TaquinSolved>()),
+                press_friend_buttons,
+            ).chain().run_if(in_state(AppState::Running).and_then(resource_exists::<ActiveDaily>())))
+            .add_systems(Update, (
+                start_fetch.run_if(resource_exists::<FetchRequested>().and_then(not(resource_exists::<FetchTask>()))),
+                receive_fetch.run_if(resource_exists::<FetchTask>()),
+            ).chain().run_if(resource_exists::<Leaderboard>()))
+            .add_systems(Update, (
+                despawn_daily_panel.run_if(not(resource_exists::<ActiveDaily>())),
+                setup_daily_panel.run_if(resource_added::<ActiveDaily>()),
+                update_daily_panel.run_if(resource_exists::<ActiveDaily>()),
+            ).chain());
+    }
+}
+
+/// Puzzle of a day, it only depends on the day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DailyPuzzle {
+    /// Days since the unix epoch
+    day: u64,
+    seed: u64,
+}
+
+impl DailyPuzzle {
+    fn for_day(day: u64) -> Self {
+        Self { day, seed: StdRng::seed_from_u64(day).gen() }
+    }
+}
+
+/// Picks the daily puzzle in the main menu
+#[derive(Component)]
+pub struct DailyButton;
+
+/// The service keeping the times of the daily puzzles, an opt-in as the player name and friend code are sent to it.
+/// Both requests go to `{url}/daily/{day}`, `day` counting the days since the unix epoch:
+/// - a solve POSTs its `DailyTime` as json
+/// - a GET with the `top` count and the comma separated `friends` codes as query answers the `DailyStandings` as json
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+struct Leaderboard {
+    #[serde(default)]
+    version: u32,
+    url: String,
+    /// Shown to the other players
+    name: String,
+}
+
+impl Versioned for Leaderboard {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+/// Code of this player, drawn once, and the codes of the friends added from the clipboard
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Friends {
+    #[serde(default)]
+    pub version: u32,
+    pub code: String,
+    #[serde(default)]
+    pub friends: Vec<String>,
+}
+
+impl Versioned for Friends {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl FromWorld for Friends {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().friends();
+        if persistence::exists(&path) {
+            match persistence::load_versioned(&path, Format::Ron) {
+                Ok(friends) => return friends,
+                Err(error) => error!("cannot load the friends, a new friend code is drawn: {error}"),
+            }
+        }
+        let friends = Friends { version: Self::VERSION, code: draw_friend_code(&mut rand::thread_rng()), friends: Vec::new() };
+        if let Err(error) = persistence::save_versioned(&path, &friends, Format::Ron) {
+            error!("cannot save the friend code: {error}");
+        }
+        friends
+    }
+}
+
+impl Friends {
+    /// Whether the code was new, the code of this player is not a friend of their own
+    fn add(&mut self, code: String) -> bool {
+        if code == self.code || self.friends.contains(&code) {
+            return false;
+        }
+        self.friends.push(code);
+        true
+    }
+}
+
+fn draw_friend_code(rng: &mut impl Rng) -> String {
+    let letters = (0..CODE_LENGTH).map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char).collect::<String>();
+    format!("{}-{}", &letters[..CODE_LENGTH / 2], &letters[CODE_LENGTH / 2..])
+}
+
+/// The code as shown, whatever the case and the dash of the text pasted
+fn parse_friend_code(text: &str) -> Option<String> {
+    let letters = text.trim().chars().filter(|letter| *letter != '-').map(|letter| letter.to_ascii_uppercase()).collect::<String>();
+    if letters.len() != CODE_LENGTH || !letters.bytes().all(|letter| CODE_ALPHABET.contains(&letter)) {
+        return None;
+    }
+    Some(format!("{}-{}", &letters[..CODE_LENGTH / 2], &letters[CODE_LENGTH / 2..]))
+}
+
+/// A time of the daily puzzle, as sent and as answered
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct DailyTime {
+    code: String,
+    name: String,
+    /// Seconds from the first move to the solve, the pauses left out
+    time: f32,
+    moves: usize,
+}
+
+/// Times of a day as the leaderboard answers them
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+struct DailyStandings {
+    /// Fastest first
+    #[serde(default)]
+    top: Vec<DailyTime>,
+    /// The friends who played that day, fastest first
+    #[serde(default)]
+    friends: Vec<DailyTime>,
+}
+
+#[derive(Debug)]
+enum LeaderboardError {
+    Http(Box<ureq::Error>),
+    Io(io::Error),
+}
+
+impl fmt::Display for LeaderboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LeaderboardError::Http(error) => write!(f, "request failed: {error}"),
+            LeaderboardError::Io(error) => write!(f, "cannot read the response: {error}"),
+        }
+    }
+}
+
+impl From<ureq::Error> for LeaderboardError {
+    fn from(error: ureq::Error) -> Self {
+        LeaderboardError::Http(Box::new(error))
+    }
+}
+
+impl From<io::Error> for LeaderboardError {
+    fn from(error: io::Error) -> Self {
+        LeaderboardError::Io(error)
+    }
+}
+
+impl Leaderboard {
+    fn day_url(&self, day: u64) -> String {
+        format!("{}/daily/{day}", self.url.trim_end_matches('/'))
+    }
+
+    /// Sends the time if there is one, then reads the standings of the day
+    fn fetch(&self, day: u64, time: Option<DailyTime>, friends: &[String]) -> Result<DailyStandings, LeaderboardError> {
+        let url = self.day_url(day);
+        if let Some(time) = time {
+            let body = serde_json::to_string(&time).map_err(io::Error::from)?;
+            ureq::post(&url).set("Content-Type", "application/json").send_string(&body)?;
+        }
+        let response = ureq::get(&url)
+            .query("top", &TOP_COUNT.to_string())
+            .query("friends", &friends.join(","))
+            .call()?;
+        let mut standings: DailyStandings = serde_json::from_str(&response.into_string()?).map_err(io::Error::from)?;
+        standings.top.truncate(TOP_COUNT);
+        Ok(standings)
+    }
+}
+
+/// Daily puzzle being played
+#[derive(Resource, Debug)]
+struct ActiveDaily {
+    puzzle: DailyPuzzle,
+    /// Set once the board is arranged, a shuffle after that leaves the puzzle
+    arranged: bool,
+    moves: usize,
+    /// `GameClock` time of the first move
+    started_at: Option<f32>,
+    /// Seconds the player took, once solved
+    solved_in: Option<f32>,
+}
+
+/// Set as the puzzle starts, once it is solved and once a friend is added, the next fetch starts once the
+/// running one is over
+#[derive(Resource)]
+struct FetchRequested {
+    day: u64,
+    /// Time to send
+    time: Option<DailyTime>,
+}
+
+#[derive(Resource)]
+struct FetchTask(Task<Result<DailyStandings, LeaderboardError>>);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum FriendButton {
+    CopyCode,
+    AddFromClipboard,
+}
+
+#[derive(Component)]
+struct DailyPanel;
+
+#[derive(Component)]
+struct DailyStandingsText;
+
+fn load_leaderboard(
+    mut commands: Commands,
+    paths: Res<DataPaths>,
+) {
+    let path = paths.leaderboard();
+    if !persistence::exists(&path) {
+        return;
+    }
+    match persistence::load_versioned::<Leaderboard>(&path, Format::Ron) {
+        Ok(leaderboard) => {
+            info!(url = leaderboard.url, "leaderboard enabled");
+            commands.insert_resource(leaderboard);
+        },
+        Err(error) => error!("cannot load the leaderboard settings, the daily times stay here: {error}"),
+    }
+}
+
+/// The day is the one of the press, a game started before midnight is still the puzzle of the day before
+fn start_daily_puzzle(
+    mut commands: Commands,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<DailyButton>)>,
+    mut taquin: ResMut<Taquin>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !button_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        return;
+    }
+    let puzzle = DailyPuzzle::for_day(persistence::timestamp() / DAY_SECONDS);
+    info!(day = puzzle.day, "daily puzzle started");
+    *taquin = Taquin::new(DAILY_SIZE);
+    commands.insert_resource(ActiveDaily { puzzle, arranged: false, moves: 0, started_at: None, solved_in: None });
+    commands.insert_resource(FetchRequested { day: puzzle.day, time: None });
+    commands.remove_resource::<DailyStandings>();
+    next_state.set(AppState::SetupTiles);
+}
+
+fn leave_daily_puzzle(
+    mut commands: Commands,
+) {
+    commands.remove_resource::<ActiveDaily>();
+}
+
+fn arrange_daily_board(
+    active: Res<ActiveDaily>,
+    mut arrange_events: EventWriter<ArrangeTiles>,
+) {
+    if !active.arranged {
+        arrange_events.send(ArrangeTiles(seeded_tiles(DAILY_SIZE, DAILY_SIZE, active.puzzle.seed)));
+    }
+}
+
+fn leave_daily_puzzle_on_shuffle(
+    mut commands: Commands,
+    mut active: ResMut<ActiveDaily>,
+) {
+    if active.arranged {
+        info!("daily puzzle left");
+        commands.remove_resource::<ActiveDaily>();
+        return;
+    }
+    active.arranged = true;
+}
+
+/// The clock starts with the first move, the time spent looking at the board before is free
+fn count_daily_moves(
+    active: Option<ResMut<ActiveDaily>>,
+    mut tile_moved_events: EventReader<TileMoved>,
+    clock: Res<GameClock>,
+) {
+    let moves = tile_moved_events.read().count();
+    // Removed by a shuffle earlier this frame
+    let Some(mut active) = active.filter(|active| active.arranged && active.solved_in.is_none()) else {
+        return;
+    };
+    if active.started_at.is_none() {
+        active.started_at = Some(clock.elapsed_seconds());
+    }
+    active.moves += moves;
+}
+
+/// Only the first solve of the arranged board counts, its time is sent with the next fetch
+fn finish_daily_puzzle(
+    mut commands: Commands,
+    active: Option<ResMut<ActiveDaily>>,
+    clock: Res<GameClock>,
+    leaderboard: Option<Res<Leaderboard>>,
+    friends: Res<Friends>,
+) {
+    // Removed by a shuffle earlier this frame
+    let Some(mut active) = active.filter(|active| active.arranged && active.solved_in.is_none()) else {
+        return;
+    };
+    let Some(started_at) = active.started_at else {
+        return;
+    };
+    let solved_in = clock.elapsed_seconds() - started_at;
+    info!(day = active.puzzle.day, moves = active.moves, solved_in, "daily puzzle solved");
+    active.solved_in = Some(solved_in);
+    if let Some(leaderboard) = leaderboard {
+        let time = DailyTime { code: friends.code.clone(), name: leaderboard.name.clone(), time: solved_in, moves: active.moves };
+        commands.insert_resource(FetchRequested { day: active.puzzle.day, time: Some(time) });
+    }
+}
+
+/// Copying puts the code of this player in the clipboard, adding reads a code a friend shared
+fn press_friend_buttons(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &FriendButton), Changed<Interaction>>,
+    active: Option<Res<ActiveDaily>>,
+    mut friends: ResMut<Friends>,
+    paths: Res<DataPaths>,
+) {
+    let Some(active) = active else {
+        return;
+    };
+    for (_, button) in button_query.iter().filter(|(interaction, _)| **interaction == Interaction::Pressed) {
+        match button {
+            FriendButton::CopyCode => match platform::copy_text(&friends.code) {
+                Ok(()) => info!(code = friends.code, "friend code copied"),
+                Err(error) => warn!("cannot copy the friend code: {error}"),
+            },
+            FriendButton::AddFromClipboard => {
+                let code = match platform::paste_text().map(|text| parse_friend_code(&text)) {
+                    Ok(Some(code)) => code,
+                    Ok(None) => {
+                        warn!("no friend code in the clipboard");
+                        continue;
+                    },
+                    Err(error) => {
+                        warn!("cannot read the clipboard: {error}");
+                        continue;
+                    },
+                };
+                if !friends.add(code.clone()) {
+                    continue;
+                }
+                info!(code, "friend added");
+                if let Err(error) = persistence::save_versioned(paths.friends(), &*friends, Format::Ron) {
+                    error!("cannot save the friends: {error}");
+                }
+                commands.insert_resource(FetchRequested { day: active.puzzle.day, time: None });
+            },
+        }
+    }
+}
+
+fn start_fetch(
+    mut commands: Commands,
+    mut requested: ResMut<FetchRequested>,
+    leaderboard: Res<Leaderboard>,
+    friends: Res<Friends>,
+) {
+    let (day, time) = (requested.day, requested.time.take());
+    commands.remove_resource::<FetchRequested>();
+    let leaderboard = leaderboard.clone();
+    let friends = friends.friends.clone();
+    commands.insert_resource(FetchTask(IoTaskPool::get().spawn(async move { leaderboard.fetch(day, time, &friends) })));
+}
+
+fn receive_fetch(
+    mut commands: Commands,
+    mut task: ResMut<FetchTask>,
+) {
+    if !task.0.is_finished() {
+        return;
+    }
+    commands.remove_resource::<FetchTask>();
+    match block_on(&mut task.0) {
+        Ok(standings) => commands.insert_resource(standings),
+        Err(error) => warn!("cannot reach the leaderboard: {error}"),
+    }
+}
+
+fn setup_daily_panel(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    panel_query: Query<Entity, With<DailyPanel>>,
+) {
+    for entity in panel_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.),
+                top: Val::Percent(12.),
+                padding: UiRect::all(Val::Px(10.)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.),
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.7).into(),
+            ..default()
+        }, DailyPanel))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ).with_text_alignment(locale.start_alignment()),
+                DailyStandingsText,
+                Localized::new(""),
+            ));
+            for (text, button) in [("Copy my friend code", FriendButton::CopyCode), ("Add a friend from the clipboard", FriendButton::AddFromClipboard)] {
+                parent.spawn((ButtonBundle {
+                    style: Style {
+                        padding: UiRect::axes(Val::Px(10.), Val::Px(4.)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                    ..default()
+                }, button)).with_children(|parent| {
+                    parent.spawn((TextBundle::from_section(
+                        text,
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ), Localized::new(text)));
+                });
+            }
+        });
+}
+
+fn despawn_daily_panel(
+    mut commands: Commands,
+    panel_query: Query<Entity, With<DailyPanel>>,
+) {
+    for entity in panel_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn describe_times(times: &[DailyTime], locale: Locale) -> String {
+    times.iter().enumerate()
+        .map(|(index, time)| format!("{}. {} {} ({})", index + 1, time.name, locale.format_duration(time.time), time.moves))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// The standings once the leaderboard answered, the time of the player as soon as they solve it
+fn update_daily_panel(
+    active: Res<ActiveDaily>,
+    standings: Option<Res<DailyStandings>>,
+    leaderboard: Option<Res<Leaderboard>>,
+    friends: Res<Friends>,
+    locale: Res<Locale>,
+    mut status_query: Query<&mut Localized, With<DailyStandingsText>>,
+) {
+    let status = match (standings.as_deref(), leaderboard.is_some()) {
+        (Some(standings), _) => Localized::new("Daily puzzle, top {count}\n{top}\nFriends\n{friends}\nYour time: {time}\nYour friend code: {code}")
+            .with_arg("count", TOP_COUNT)
+            .with_arg("top", describe_times(&standings.top, *locale))
+            .with_arg("friends", describe_times(&standings.friends, *locale)),
+        (None, true) => Localized::new("Daily puzzle, reaching the leaderboard...\nYour time: {time}\nYour friend code: {code}"),
+        (None, false) => Localized::new("Daily puzzle, no leaderboard set in leaderboard.ron\nYour time: {time}\nYour friend code: {code}"),
+    };
+    let status = status
+        .with_arg("time", active.solved_in.map_or_else(|| "-".to_string(), |solved_in| locale.format_duration(solved_in)))
+        .with_arg("code", &friends.code);
+    for mut localized in status_query.iter_mut() {
+        if *localized != status {
+            *localized = status.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{Ipv4Addr, TcpListener},
+        thread,
+    };
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::leaderboard::{draw_friend_code, parse_friend_code, DailyPuzzle, DailyStandings, DailyTime, Friends, Leaderboard, TOP_COUNT};
+
+    #[test]
+    fn test_daily_puzzle() {
+        assert_eq!(DailyPuzzle::for_day(20000), DailyPuzzle::for_day(20000));
+        assert_ne!(DailyPuzzle::for_day(20000).seed, DailyPuzzle::for_day(20001).seed);
+    }
+
+    #[test]
+    fn test_friend_codes() {
+        let code = draw_friend_code(&mut StdRng::seed_from_u64(42));
+        assert_eq!(code.len(), 9);
+        assert_eq!(parse_friend_code(&code), Some(code.clone()));
+        // As typed by a friend
+        assert_eq!(parse_friend_code(&format!(" {} ", code.replace('-', "").to_lowercase())), Some(code.clone()));
+        assert_eq!(parse_friend_code("ABCD-EFG0"), None);
+        assert_eq!(parse_friend_code("ABCD-EFGHJ"), None);
+
+        let mut friends = Friends { version: 1, code: code.clone(), friends: Vec::new() };
+        assert!(!friends.add(code));
+        assert!(friends.add("ABCD-EFGH".to_string()));
+        assert!(!friends.add("ABCD-EFGH".to_string()));
+
+        // A leaderboard without friends to answer leaves them out
+        let standings = serde_json::from_str::<DailyStandings>(r#"{"top": [{"code": "ABCD-EFGH", "name": "Ada", "time": 31.5, "moves": 54}]}"#).unwrap();
+        assert_eq!((standings.top.len(), standings.friends.len()), (1, 0));
+    }
+
+    /// Answers one request with `body`, gives back the request line and the body received
+    fn answer(listener: &TcpListener, body: &str) -> (String, String) {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some((_, value)) = header.split_once(':').filter(|(name, _)| name.eq_ignore_ascii_case("content-length")) {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut received = vec![0; length];
+        reader.read_exact(&mut received).unwrap();
+        write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len()).unwrap();
+        (request_line.trim_end().to_string(), String::from_utf8(received).unwrap())
+    }
+
+    #[test]
+    fn test_fetch() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let leaderboard = Leaderboard { version: 1, url: format!("http://{}/", listener.local_addr().unwrap()), name: "Ada".to_string() };
+        let time = DailyTime { code: "ABCD-EFGH".to_string(), name: "Ada".to_string(), time: 31.5, moves: 54 };
+        let friend = DailyTime { code: "JKLM-NPQR".to_string(), name: "Grace".to_string(), time: 40., moves: 61 };
+        // More players than shown
+        let top = (0..TOP_COUNT + 2)
+            .map(|rank| DailyTime { code: "STUV-WXYZ".to_string(), name: format!("Player {rank}"), time: 20. + rank as f32, moves: 50 })
+            .collect::<Vec<DailyTime>>();
+        let answered = serde_json::to_string(&DailyStandings { top, friends: vec![friend.clone()] }).unwrap();
+
+        let sent = time.clone();
+        let fetch = thread::spawn(move || leaderboard.fetch(20000, Some(sent), &["JKLM-NPQR".to_string()]));
+        let (post_line, posted) = answer(&listener, "");
+        let (get_line, _) = answer(&listener, &answered);
+        let standings = fetch.join().unwrap().unwrap();
+
+        assert_eq!(post_line, "POST /daily/20000 HTTP/1.1");
+        assert_eq!(serde_json::from_str::<DailyTime>(&posted).unwrap(), time);
+        assert_eq!(get_line, format!("GET /daily/20000?top={TOP_COUNT}&friends=JKLM-NPQR HTTP/1.1"));
+        assert_eq!(standings.top.len(), TOP_COUNT);
+        assert_eq!(standings.top[0].name, "Player 0");
+        assert_eq!(standings.friends, vec![friend]);
+    }
+}
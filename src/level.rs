@@ -0,0 +1,450 @@
+use std::collections::{BTreeMap, HashSet};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::MAX_SIZE,
+    focus::FocusScope,
+    i18n::Localized,
+    pause::GameClock,
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    taquin::{seeded_tiles, ArrangeTiles, MoveCount, ShuffleSeed, Taquin, TaquinShuffled, TaquinSolved},
+    AppState,
+};
+
+const SCREEN_KEY: KeyCode = KeyCode::L;
+const MAX_STARS: u8 = 3;
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Campaign>()
+            .init_resource::<CampaignProgress>()
+            .add_systems(Startup, setup_level_hud)
+            .add_systems(Update, (
+                toggle_level_select,
+                (start_level, update_level_select).chain().run_if(resource_exists::<LevelSelect>()),
+            ).chain().run_if(in_state(AppState::MainMenu)))
+            .add_systems(OnExit(AppState::MainMenu), close_level_select)
+            .add_systems(OnEnter(AppState::MainMenu), leave_level)
+            .add_systems(OnEnter(AppState::Running), arrange_level_board.run_if(resource_exists::<ActiveLevel>()))
+            .add_systems(Update, (
+                leave_level_on_shuffle.run_if(on_event::<TaquinShuffled>()),
+                rate_level.run_if(on_event::<TaquinSolved>()),
+                update_level_hud,
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// A board of the campaign, its seed draws the same scramble for every player
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Level {
+    pub name: String,
+    pub width: i8,
+    pub height: i8,
+    pub seed: u64,
+    /// Solving within them earns a star each
+    pub moves: usize,
+    pub time: f32,
+}
+
+impl Level {
+    /// One star for the solve, one for the moves target and one for the time target
+    fn stars(&self, moves: usize, time: f32) -> u8 {
+        1 + u8::from(moves <= self.moves) + u8::from(time <= self.time)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("a level has no name".to_string());
+        }
+        if !(2..=MAX_SIZE).contains(&self.width) || !(2..=MAX_SIZE).contains(&self.height) {
+            return Err(format!("{}: {}x{} board is not supported", self.name, self.width, self.height));
+        }
+        if self.moves == 0 || self.time <= 0. {
+            return Err(format!("{}: the targets must be positive", self.name));
+        }
+        Ok(())
+    }
+}
+
+/// Levels of `assets/levels.ron`, played in order
+#[derive(Resource, Deserialize, Debug)]
+pub struct Campaign {
+    pub levels: Vec<Level>,
+}
+
+impl Campaign {
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let campaign: Self = Format::Ron.parse(contents).map_err(|error| error.to_string())?;
+        let mut names = HashSet::new();
+        for level in campaign.levels.iter() {
+            level.validate()?;
+            if !names.insert(level.name.as_str()) {
+                return Err(format!("{}: two levels have this name", level.name));
+            }
+        }
+        Ok(campaign)
+    }
+}
+
+/// A browser cannot read the assets folder, the levels are built in there
+impl FromWorld for Campaign {
+    fn from_world(_world: &mut World) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        let contents = {
+            let path = bevy::asset::io::file::FileAssetReader::get_base_path().join("assets").join("levels.ron");
+            std::fs::read_to_string(&path).map_err(|error| format!("{}: {error}", path.display()))
+        };
+        #[cfg(target_arch = "wasm32")]
+        let contents = Ok::<_, String>(include_str!("../assets/levels.ron").to_string());
+        contents.and_then(|contents| Self::parse(&contents)).unwrap_or_else(|error| {
+            error!("cannot load the campaign: {error}");
+            Self { levels: Vec::new() }
+        })
+    }
+}
+
+/// Best stars of each level, by name so levels can be added or reordered
+#[derive(Resource, Serialize, Deserialize, Debug, PartialEq)]
+pub struct CampaignProgress {
+    pub version: u32,
+    pub stars: BTreeMap<String, u8>,
+}
+
+impl Versioned for CampaignProgress {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl FromWorld for CampaignProgress {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().campaign();
+        if !persistence::exists(&path) {
+            return Self::new();
+        }
+        persistence::load_versioned(path, Format::Ron).unwrap_or_else(|error| {
+            error!("cannot load the campaign progress: {error}");
+            Self::new()
+        })
+    }
+}
+
+impl CampaignProgress {
+    /// No level played yet
+    fn new() -> Self {
+        Self { version: Self::VERSION, stars: BTreeMap::new() }
+    }
+
+    fn stars(&self, level: &Level) -> u8 {
+        self.stars.get(&level.name).copied().unwrap_or(0)
+    }
+
+    /// The first level is open, the others once the previous one is solved
+    fn is_unlocked(&self, campaign: &Campaign, index: usize) -> bool {
+        index == 0 || campaign.levels.get(index - 1).is_some_and(|previous| self.stars(previous) > 0)
+    }
+}
+
+fn stars_label(stars: u8) -> String {
+    format!("{}{}", "*".repeat(stars as usize), "-".repeat((MAX_STARS - stars) as usize))
+}
+
+/// Opens the level select screen from the main menu, as L does
+#[derive(Component)]
+pub struct CampaignButton;
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum LevelButton {
+    Level(usize),
+    Close,
+}
+
+/// Level select screen over the main menu
+#[derive(Resource)]
+struct LevelSelect(Entity);
+
+/// Level being played
+#[derive(Resource, Debug)]
+struct ActiveLevel {
+    index: usize,
+    /// Set once the board is arranged, a shuffle after that leaves the level
+    arranged: bool,
+    /// `GameClock` time when the board was arranged
+    started_at: f32,
+    outcome: Option<Localized>,
+}
+
+#[derive(Component)]
+struct LevelHud;
+
+fn setup_level_hud(
+    mut commands: Commands,
+) {
+    commands.spawn((TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: 24.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    ).with_style(Style {
+        position_type: PositionType::Absolute,
+        left: Val::Px(10.),
+        top: Val::Percent(33.),
+        ..default()
+    }), LevelHud, Localized::new("")));
+}
+
+fn toggle_level_select(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    open_query: Query<&Interaction, (Changed<Interaction>, With<CampaignButton>)>,
+    close_query: Query<(&Interaction, &LevelButton), Changed<Interaction>>,
+    screen: Option<Res<LevelSelect>>,
+    campaign: Res<Campaign>,
+    progress: Res<CampaignProgress>,
+) {
+    let opened = open_query.iter().any(|interaction| *interaction == Interaction::Pressed);
+    let closed = close_query.iter().any(|(interaction, button)| *interaction == Interaction::Pressed && *button == LevelButton::Close);
+    let toggled = keyboard_input.just_released(SCREEN_KEY);
+    if let Some(screen) = screen {
+        if toggled || closed {
+            commands.entity(screen.0).despawn_recursive();
+            commands.remove_resource::<LevelSelect>();
+        }
+        return;
+    }
+    if !toggled && !opened {
+        return;
+    }
+    let text_style = TextStyle {
+        font_size: 36.0,
+        color: Color::WHITE,
+        ..default()
+    };
+    let entity = commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(12.),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.85).into(),
+        // Above the main menu
+        z_index: ZIndex::Global(6),
+        ..default()
+    }, FocusScope)).with_children(|parent| {
+        parent.spawn((TextBundle::from_section("Campaign", TextStyle { font_size: 60.0, ..text_style.clone() }), Localized::new("Campaign")));
+        if campaign.levels.is_empty() {
+            parent.spawn((TextBundle::from_section("", text_style.clone()), Localized::new("No level, see assets/levels.ron")));
+        }
+        let buttons = campaign.levels.iter().enumerate().map(|(index, level)| {
+            let label = if progress.is_unlocked(&campaign, index) {
+                Localized::new("{number}. {level} {size} {stars}").with_arg("stars", stars_label(progress.stars(level)))
+            } else {
+                Localized::new("{number}. {level} {size} locked")
+            };
+            let label = label.with_arg("number", index + 1).with_arg("level", &level.name).with_arg("size", format!("{}x{}", level.width, level.height));
+            (label, LevelButton::Level(index))
+        });
+        for (label, button) in buttons.chain(std::iter::once((Localized::new("Close"), LevelButton::Close))) {
+            parent.spawn((ButtonBundle {
+                style: Style {
+                    padding: UiRect::axes(Val::Px(20.), Val::Px(6.)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                ..default()
+            }, button)).with_children(|parent| {
+                parent.spawn((TextBundle::from_section("", text_style.clone()), label));
+            });
+        }
+    }).id();
+    commands.insert_resource(LevelSelect(entity));
+}
+
+/// Locked levels cannot be picked
+fn start_level(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &LevelButton), Changed<Interaction>>,
+    campaign: Res<Campaign>,
+    progress: Res<CampaignProgress>,
+    mut taquin: ResMut<Taquin>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let picked = button_query.iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .find_map(|(_, button)| match *button {
+            LevelButton::Level(index) => Some(index),
+            LevelButton::Close => None,
+        });
+    let Some((index, level)) = picked.filter(|index| progress.is_unlocked(&campaign, *index)).and_then(|index| campaign.levels.get(index).map(|level| (index, level))) else {
+        return;
+    };
+    info!(level = level.name, width = level.width, height = level.height, "level started");
+    *taquin = Taquin::rect(level.width, level.height);
+    commands.insert_resource(ActiveLevel { index, arranged: false, started_at: 0., outcome: None });
+    next_state.set(AppState::SetupTiles);
+}
+
+/// Locked levels are dimmed
+fn update_level_select(
+    campaign: Res<Campaign>,
+    progress: Res<CampaignProgress>,
+    mut button_query: Query<(&LevelButton, &mut BackgroundColor), Added<LevelButton>>,
+) {
+    for (button, mut background) in button_query.iter_mut() {
+        if let LevelButton::Level(index) = *button {
+            if !progress.is_unlocked(&campaign, index) {
+                background.0 = Color::rgba(0.3, 0.3, 0.3, 0.4);
+            }
+        }
+    }
+}
+
+fn close_level_select(
+    mut commands: Commands,
+    screen: Option<Res<LevelSelect>>,
+) {
+    if let Some(screen) = screen {
+        commands.entity(screen.0).despawn_recursive();
+        commands.remove_resource::<LevelSelect>();
+    }
+}
+
+fn leave_level(
+    mut commands: Commands,
+) {
+    commands.remove_resource::<ActiveLevel>();
+}
+
+fn arrange_level_board(
+    active: Res<ActiveLevel>,
+    campaign: Res<Campaign>,
+    mut arrange_events: EventWriter<ArrangeTiles>,
+) {
+    if let Some(level) = campaign.levels.get(active.index).filter(|_| !active.arranged) {
+        arrange_events.send(ArrangeTiles(seeded_tiles(level.width, level.height, level.seed)));
+    }
+}
+
+/// The arranged board is the one the seed of the level draws, it is shown as the seed of the board
+fn leave_level_on_shuffle(
+    mut commands: Commands,
+    active: Option<ResMut<ActiveLevel>>,
+    campaign: Res<Campaign>,
+    clock: Res<GameClock>,
+    mut seed: ResMut<ShuffleSeed>,
+) {
+    let Some(mut active) = active else {
+        return;
+    };
+    if active.arranged {
+        info!("level left");
+        commands.remove_resource::<ActiveLevel>();
+        return;
+    }
+    active.arranged = true;
+    active.started_at = clock.elapsed_seconds();
+    seed.current = campaign.levels.get(active.index).map(|level| level.seed);
+}
+
+/// Only better ratings are saved, the outcome stays on screen until the level is left
+fn rate_level(
+    active: Option<ResMut<ActiveLevel>>,
+    campaign: Res<Campaign>,
+    mut progress: ResMut<CampaignProgress>,
+    moves: Res<MoveCount>,
+    clock: Res<GameClock>,
+    paths: Res<DataPaths>,
+) {
+    let Some(mut active) = active.filter(|active| active.arranged && active.outcome.is_none()) else {
+        return;
+    };
+    let Some(level) = campaign.levels.get(active.index) else {
+        return;
+    };
+    let elapsed = clock.elapsed_seconds() - active.started_at;
+    let stars = level.stars(moves.0, elapsed);
+    info!(level = level.name, moves = moves.0, elapsed, stars, "level solved");
+    let first_solve = progress.stars(level) == 0;
+    if stars > progress.stars(level) {
+        progress.stars.insert(level.name.clone(), stars);
+        if let Err(error) = persistence::save_versioned(paths.campaign(), &*progress, Format::Ron) {
+            error!("cannot save the campaign progress: {error}");
+        }
+    }
+    let outcome = match campaign.levels.get(active.index + 1) {
+        Some(next) if first_solve => Localized::new("{level} solved in {moves} moves: {stars}\n{next} unlocked").with_arg("next", &next.name),
+        None if first_solve => Localized::new("{level} solved in {moves} moves: {stars}\nCampaign complete!"),
+        _ => Localized::new("{level} solved in {moves} moves: {stars}"),
+    };
+    active.outcome = Some(outcome.with_arg("level", &level.name).with_arg("moves", moves.0).with_arg("stars", stars_label(stars)));
+}
+
+fn update_level_hud(
+    active: Option<Res<ActiveLevel>>,
+    campaign: Res<Campaign>,
+    mut hud_query: Query<&mut Localized, With<LevelHud>>,
+) {
+    let Ok(mut localized) = hud_query.get_single_mut() else {
+        return;
+    };
+    let value = match active.as_deref() {
+        Some(ActiveLevel { outcome: Some(outcome), .. }) => outcome.clone(),
+        Some(active) => campaign.levels.get(active.index).map_or_else(|| Localized::new(""), |level| {
+            Localized::new("Level {number}: {level}\nTargets: {moves} moves, {time}s")
+                .with_arg("number", active.index + 1)
+                .with_arg("level", &level.name)
+                .with_arg("moves", level.moves)
+                .with_arg("time", format!("{:.0}", level.time))
+        }),
+        None => Localized::new(""),
+    };
+    if *localized != value {
+        *localized = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::level::{Campaign, CampaignProgress, Level};
+
+    #[test]
+    fn test_campaign() {
+        let campaign = Campaign::parse(include_str!("../assets/levels.ron")).unwrap();
+        assert!(!campaign.levels.is_empty());
+        let first = &campaign.levels[0];
+        assert_eq!(first.stars(first.moves, first.time), 3);
+        assert_eq!(first.stars(first.moves + 1, first.time), 2);
+        assert_eq!(first.stars(first.moves + 1, first.time + 1.), 1);
+
+        let mut progress = CampaignProgress { version: 1, stars: BTreeMap::new() };
+        assert!(progress.is_unlocked(&campaign, 0));
+        assert!(!progress.is_unlocked(&campaign, 1));
+        progress.stars.insert(first.name.clone(), 1);
+        assert!(progress.is_unlocked(&campaign, 1));
+        assert!(!progress.is_unlocked(&campaign, 2));
+    }
+
+    #[test]
+    fn test_invalid_campaign() {
+        let level = Level { name: "Twice".to_string(), width: 3, height: 3, seed: 1, moves: 10, time: 10. };
+        assert!(level.validate().is_ok());
+        assert!(Level { width: 1, ..level.clone() }.validate().is_err());
+        assert!(Campaign::parse("(levels: [(name: \"Twice\", width: 3, height: 3, seed: 1, moves: 10, time: 10.), (name: \"Twice\", width: 3, height: 3, seed: 2, moves: 10, time: 10.)])").is_err());
+    }
+}
@@ -0,0 +1,28 @@
+// Queries of bevy systems are complex types by nature
+#![allow(clippy::type_complexity)]
+
+/// Cells of a board, shared by the game and the solver
+pub mod coordinates;
+/// The board of the game, its moves, solvability and shuffles, without anything drawn
+pub mod puzzle;
+/// Slides of the tiles to their cells and their easings, the slides themselves, lerped or played by animation clips,
+/// with the `bevy` feature
+pub mod slide;
+/// Flat boards, their moves and the searches solving them, usable without the game
+pub mod solver;
+/// Position hashes stable across runs, for saved tables and caches, reached through `Board::hash` and `Taquin::hash`
+pub(crate) mod zobrist;
+
+/// The items kept stable between releases, `use bevy_taquin_3d::prelude::*` is enough to embed the board or the solver.
+/// The plugin and the event of the library are the slides of the tiles; the plugins and events of the game, and its
+/// `Settings` holding the volumes, theme and key bindings of the window it opens, stay in the binary
+pub mod prelude {
+    pub use crate::{
+        coordinates::{Direction, TileCoordinates},
+        puzzle::{seeded_rotations, seeded_tiles, Taquin, TileValue},
+        slide::Easing,
+        solver::{solve, solve_with_extra_moves, Board, SearchLimits, SearchStats, Solution, Strategy},
+    };
+    #[cfg(feature = "bevy")]
+    pub use crate::slide::{tile_name, SlidePlugin, TileAnimationFinished, TileAnimations, TileLerp, TileSlide};
+}
@@ -0,0 +1,154 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    net::TcpStream,
+};
+
+/// Lines longer than this are not waited for, the peer is dropped
+pub const MAX_LINE_BYTES: usize = 64 * 1024;
+/// A peer this far behind in reading is dropped rather than queued for
+const MAX_QUEUED_BYTES: usize = 4 * 1024 * 1024;
+
+/// Stream of newline separated messages, read and written without blocking the frame. What the peer cannot
+/// take yet is queued and written on the next calls, the lines it sends are taken out as they complete
+pub struct LineStream {
+    stream: TcpStream,
+    /// Bytes of a line not fully received yet
+    incoming: Vec<u8>,
+    /// Bytes sent but not written yet
+    outgoing: VecDeque<u8>,
+}
+
+impl LineStream {
+    /// `received` is what was read already, before the stream was handed over
+    pub fn new(stream: TcpStream, received: Vec<u8>) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        stream.set_nonblocking(true)?;
+        Ok(Self { stream, incoming: received, outgoing: VecDeque::new() })
+    }
+
+    /// Queues the line and writes what the peer takes, an error once it is gone or too far behind
+    pub fn send(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.outgoing.len() + line.len() > MAX_QUEUED_BYTES {
+            return Err(io::Error::other("the peer stopped reading"));
+        }
+        self.outgoing.extend(line);
+        self.flush()
+    }
+
+    /// Writes the queued bytes until the peer takes no more
+    pub fn flush(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            let (front, _) = self.outgoing.as_slices();
+            match self.stream.write(front) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                },
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    /// Lines fully received since the last call, with their newline. The queued bytes are written first,
+    /// so a peer is caught up with each frame even when nothing new is sent
+    pub fn receive(&mut self) -> io::Result<Vec<Vec<u8>>> {
+        self.flush()?;
+        let mut chunk = [0; 1024];
+        let mut lines = Vec::new();
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                // Taken out chunk by chunk, a peer sending fast does not pile up more than a line
+                Ok(read) => {
+                    self.incoming.extend_from_slice(&chunk[..read]);
+                    lines.append(&mut take_lines(&mut self.incoming)?);
+                },
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(lines)
+    }
+}
+
+/// Blocking counterpart of `LineStream::receive` for the standard input: each line is handed to `line`
+/// until it returns false, the end of `reader` or a line longer than `MAX_LINE_BYTES`, which is an error.
+/// A last line without its newline is handed over at the end
+pub fn read_lines(mut reader: impl Read, mut line: impl FnMut(Vec<u8>) -> bool) -> io::Result<()> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 1024];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+        for received in take_lines(&mut buffer)? {
+            if !line(received) {
+                return Ok(());
+            }
+        }
+    }
+    if !buffer.is_empty() {
+        line(buffer);
+    }
+    Ok(())
+}
+
+/// Complete lines are taken out of `buffer`, what is left of a line still arriving stays in it unless it is
+/// longer than `MAX_LINE_BYTES`
+pub fn take_lines(buffer: &mut Vec<u8>) -> io::Result<Vec<Vec<u8>>> {
+    let mut lines = Vec::new();
+    while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+        lines.push(buffer.drain(..=end).collect());
+    }
+    if buffer.len() > MAX_LINE_BYTES {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("a line is longer than {MAX_LINE_BYTES} bytes")));
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::line_stream::{read_lines, take_lines, MAX_LINE_BYTES};
+
+    #[test]
+    fn test_take_lines() {
+        let mut buffer = b"one\ntwo\nthr".to_vec();
+        assert_eq!(take_lines(&mut buffer).unwrap(), vec![b"one\n".to_vec(), b"two\n".to_vec()]);
+        assert_eq!(buffer, b"thr");
+        buffer.extend_from_slice(b"ee\n");
+        assert_eq!(take_lines(&mut buffer).unwrap(), vec![b"three\n".to_vec()]);
+
+        let mut endless = vec![b'a'; MAX_LINE_BYTES + 1];
+        assert!(take_lines(&mut endless).is_err());
+    }
+
+    #[test]
+    fn test_read_lines() {
+        let mut lines = Vec::new();
+        read_lines(Cursor::new(b"one\ntwo".to_vec()), |line| {
+            lines.push(line);
+            true
+        }).unwrap();
+        assert_eq!(lines, vec![b"one\n".to_vec(), b"two".to_vec()]);
+
+        // The lines before the endless one are handed over, it is not read to its end
+        let mut input = b"one\n".to_vec();
+        input.extend(vec![b'a'; MAX_LINE_BYTES * 4]);
+        let mut lines = Vec::new();
+        assert!(read_lines(Cursor::new(input), |line| {
+            lines.push(line);
+            true
+        }).is_err());
+        assert_eq!(lines, vec![b"one\n".to_vec()]);
+    }
+}
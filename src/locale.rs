@@ -0,0 +1,169 @@
+use std::env;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Language of the texts, see `i18n`, and formats of the numbers. Right to left locales lay the GUI out the other way
+/// when it is built, a switch at runtime only changes the texts
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    French,
+    German,
+    Arabic,
+    Hebrew,
+}
+
+impl Locale {
+    /// Choices of the settings screen
+    pub const ALL: [Locale; 5] = [Locale::English, Locale::French, Locale::German, Locale::Arabic, Locale::Hebrew];
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.get(..2)?.to_ascii_lowercase().as_str() {
+            "en" => Some(Locale::English),
+            "fr" => Some(Locale::French),
+            "de" => Some(Locale::German),
+            "ar" => Some(Locale::Arabic),
+            "he" => Some(Locale::Hebrew),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::French => "fr",
+            Locale::German => "de",
+            Locale::Arabic => "ar",
+            Locale::Hebrew => "he",
+        }
+    }
+
+    /// Name of the language in itself, as the settings screen shows it
+    pub fn label(self) -> &'static str {
+        match self {
+            Locale::English => "English",
+            Locale::French => "Français",
+            Locale::German => "Deutsch",
+            Locale::Arabic => "العربية",
+            Locale::Hebrew => "עברית",
+        }
+    }
+
+    /// Locale of the `LANG` environment variable, english when unknown
+    pub fn from_env() -> Self {
+        env::var("LANG").ok().and_then(|lang| Self::from_code(&lang)).unwrap_or_default()
+    }
+
+    pub fn decimal_separator(&self) -> char {
+        match self {
+            Locale::English | Locale::Hebrew => '.',
+            Locale::French | Locale::German => ',',
+            Locale::Arabic => '٫',
+        }
+    }
+
+    pub fn grouping_separator(&self) -> char {
+        match self {
+            Locale::English | Locale::Hebrew => ',',
+            Locale::French => '\u{202f}',
+            Locale::German => '.',
+            Locale::Arabic => '٬',
+        }
+    }
+
+    pub fn is_right_to_left(&self) -> bool {
+        matches!(self, Locale::Arabic | Locale::Hebrew)
+    }
+
+    /// Row direction following the reading order
+    pub fn row_direction(&self) -> FlexDirection {
+        if self.is_right_to_left() { FlexDirection::RowReverse } else { FlexDirection::Row }
+    }
+
+    /// Alignment of the side where reading starts
+    pub fn start_alignment(&self) -> TextAlignment {
+        if self.is_right_to_left() { TextAlignment::Right } else { TextAlignment::Left }
+    }
+
+    pub fn start_justify(&self) -> JustifyContent {
+        if self.is_right_to_left() { JustifyContent::FlexEnd } else { JustifyContent::FlexStart }
+    }
+
+    /// Rect with `value` on the side where reading starts
+    pub fn start_edge(&self, value: Val) -> UiRect {
+        if self.is_right_to_left() { UiRect::right(value) } else { UiRect::left(value) }
+    }
+
+    /// Rect with `value` on the side where reading ends
+    pub fn end_edge(&self, value: Val) -> UiRect {
+        if self.is_right_to_left() { UiRect::left(value) } else { UiRect::right(value) }
+    }
+
+    /// Whether minutes are zero padded in durations (mm:ss rather than m:ss)
+    fn pads_minutes(&self) -> bool {
+        matches!(self, Locale::French | Locale::German)
+    }
+
+    /// Field delimiter for csv exports, spreadsheets expect `;` when the decimal separator is a comma
+    pub fn csv_delimiter(&self) -> char {
+        if self.decimal_separator() == ',' { ';' } else { ',' }
+    }
+
+    pub fn format_count(&self, count: usize) -> String {
+        let digits = count.to_string();
+        let mut formatted = String::new();
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index).is_multiple_of(3) {
+                formatted.push(self.grouping_separator());
+            }
+            formatted.push(digit);
+        }
+        formatted
+    }
+
+    pub fn format_decimal(&self, value: f32, decimals: usize) -> String {
+        format!("{value:.decimals$}").replace('.', &self.decimal_separator().to_string())
+    }
+
+    /// Formats seconds as minutes, seconds and tenths
+    pub fn format_duration(&self, seconds: f32) -> String {
+        let tenths = (seconds.max(0.) * 10.).round() as u64;
+        let minutes = tenths / 600;
+        let seconds = (tenths % 600) / 10;
+        let tenths = tenths % 10;
+        let separator = self.decimal_separator();
+        if self.pads_minutes() {
+            format!("{minutes:02}:{seconds:02}{separator}{tenths}")
+        } else {
+            format!("{minutes}:{seconds:02}{separator}{tenths}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::locale::Locale;
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(Locale::English.format_count(12), "12");
+        assert_eq!(Locale::English.format_count(1234567), "1,234,567");
+        assert_eq!(Locale::German.format_count(1234), "1.234");
+    }
+
+    #[test]
+    fn test_code() {
+        for locale in Locale::ALL {
+            assert_eq!(Locale::from_code(locale.code()), Some(locale));
+        }
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(Locale::English.format_duration(65.34), "1:05.3");
+        assert_eq!(Locale::French.format_duration(65.34), "01:05,3");
+        assert_eq!(Locale::English.format_duration(0.), "0:00.0");
+    }
+}
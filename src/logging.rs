@@ -0,0 +1,29 @@
+use std::{fs::File, path::Path, sync::Mutex};
+
+use bevy::{log::{Level, LogPlugin}, prelude::*};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+const DEFAULT_FILTER: &str = "wgpu=error,naga=warn";
+
+pub fn log_plugin(level: Level) -> LogPlugin {
+    LogPlugin { level, ..default() }
+}
+
+/// Replaces bevy's `LogPlugin` with a subscriber also writing to `path`
+pub fn init_file_logging(level: Level, path: &Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = File::create(path)?;
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("{level},{DEFAULT_FILTER}")));
+
+    let subscriber = Registry::default()
+        .with(filter)
+        .with(fmt::Layer::default().with_writer(std::io::stderr))
+        .with(fmt::Layer::default().with_ansi(false).with_writer(Mutex::new(file)));
+    if subscriber.try_init().is_err() {
+        eprintln!("a global logger is already set, file logging is disabled");
+    }
+    Ok(())
+}
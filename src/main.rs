@@ -1,228 +1,729 @@
-use std::{f32::consts::PI, env};
+// Queries of bevy systems are complex types by nature
+#![allow(clippy::type_complexity)]
 
-use bevy::{prelude::*, render::{render_resource::{TextureFormat, TextureDimension, Extent3d}, mesh::VertexAttributeValues}};
+use std::{f32::consts::PI, path::PathBuf, process, time::Duration};
+
+use bevy::{
+    prelude::*,
+    app::ScheduleRunnerPlugin,
+    log::{Level, LogPlugin},
+    render::{settings::WgpuSettings, RenderPlugin},
+    window::{ExitCondition, WindowMode},
+    winit::WinitPlugin,
+};
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use cli::{Cli, Mode};
 use gui::GuiPlugin;
-use marker::{Markers, Marker, setup_markers};
-use scene_hook::{SceneHook, HookPlugin};
-use taquin::{Taquin, TaquinPlugin};
-use tile::{EmptyTile, TileCoordinates, TileValue, TileSelected, TilePlugin};
+use heatmap::HeatmapPlugin;
+use phases::PhasesPlugin;
+use stats::StatsPlugin;
+use profile::ProfilePlugin;
+use metrics::MetricsPlugin;
+use switch_scan::{SwitchScanning, SwitchScanPlugin};
+use game_speed::GameSpeedPlugin;
+use tile_label::{label_texture, TileLabelPlugin, TileLabelStyle};
+use auto_solve::{AutoSolvePlugin, SolverTable};
+use analysis::AnalysisPlugin;
+use solver_cache::SolverCachePlugin;
+use debug_overlay::DebugOverlayPlugin;
+use tile_bake::TileBakePlugin;
+use warm_up::WarmUpPlugin;
+use exit::ExitPlugin;
+use tile_material::{SharedTileMaterial, TileMaterial, TileMaterialPlugin, TileShading};
+use dissolve::DissolvePlugin;
+use table::TablePlugin;
+use graphics::{BatterySaver, GraphicsPlugin, MainCamera, MainLight};
+use frame_limit::{FrameLimit, FrameLimitPlugin};
+use benchmark::BenchmarkPlugin;
+use ambience::{Ambience, AmbiencePlugin};
+use seasons::SeasonsPlugin;
+use cosmetics::CosmeticsPlugin;
+use packs::{PacksPlugin, TexturePacks};
+use mods::ModsPlugin;
+use picking::PickingPlugin;
+use sharing::SharingPlugin;
+use trainer::TrainerPlugin;
+use efficiency::EfficiencyPlugin;
+use hints::HintsPlugin;
+use assist::{AssistMode, AssistPlugin};
+use quadrants::QuadrantsPlugin;
+use pause::PausePlugin;
+use chimes::ChimesPlugin;
+use records::RecordsPlugin;
+use saved_game::SavedGamePlugin;
+use session::SessionPlugin;
+use scramble::ScramblePlugin;
+use weekly::WeeklyPlugin;
+use interlude::InterludePlugin;
+use shuffle_confirm::ShuffleConfirmPlugin;
+use custom_image::{CustomImagePlugin, ImageUrl, PendingCustomImage};
+use solve_gif::SolveGifPlugin;
+use capture::CapturePlugin;
+use gallery::GalleryPlugin;
+use editor::EditorPlugin;
+use gamepad::GamepadPlugin;
+use keybindings::KeyBindingsPlugin;
+use touch::TouchPlugin;
+use stats_screen::StatsScreenPlugin;
+use achievements::AchievementsPlugin;
+use countdown::CountdownPlugin;
+use playback::PlaybackPlugin;
+use attract::AttractPlugin;
+use focus::FocusPlugin;
+use ghost::GhostPlugin;
+use frame_buttons::{DiegeticUi, FrameButtonsPlugin};
+use versus::VersusPlugin;
+use race::{Race, RaceHost, RacePlugin};
+use sound::SoundPlugin;
+use music::MusicPlugin;
+use desync::DesyncPlugin;
+use frame::build_frame;
+use tile_mesh::{tile_mesh, TileThickness};
+use camera::CameraPlugin;
+use intro::IntroPlugin;
+use tutorial::TutorialPlugin;
+use celebration::CelebrationPlugin;
+use theme::ThemePlugin;
+use empty_slot::EmptySlotPlugin;
+use settings::{Settings, SettingsPlugin};
+use display::DisplayPlugin;
+use board_code::{BoardCodePlugin, PastedBoard};
+use level::LevelPlugin;
+use rotation::{RotatingTiles, RotationPlugin};
+use mega_board::{is_mega, MegaBoardPlugin, MegaTile};
+use remote::{RemoteControl, RemotePlugin};
+use idle_hint::IdleHintPlugin;
+use layout::validate_markers;
+use locale::Locale;
+use i18n::I18nPlugin;
+use persistence::DataPaths;
+use marker::Markers;
+use replay::{Replay, ReplayPlayback, ReplayPlugin};
+use bevy_taquin_3d::solver;
+use taquin::{ControlScheme, EmptySlots, Taquin, TaquinPlugin, DEFAULT_TILE_THICKNESS};
+use tile::{tile_name, EmptyTile, TileAnimations, TileCoordinates, TileId, TileValue, TileSelected, TilePlugin, Topology};
 
 
-mod scene_hook;
 mod taquin;
 mod tile;
 mod gui;
 mod marker;
+mod replay;
+mod heatmap;
+mod phases;
+mod persistence;
+mod stats;
+mod profile;
+mod logging;
+mod metrics;
+mod locale;
+mod i18n;
+mod switch_scan;
+mod game_speed;
+mod tile_label;
+mod auto_solve;
+mod analysis;
+mod solver_cache;
+mod debug_overlay;
+mod tile_bake;
+mod warm_up;
+mod exit;
+mod cli;
+mod tile_material;
+mod dissolve;
+mod table;
+mod graphics;
+mod frame_limit;
+mod benchmark;
+mod ambience;
+mod seasons;
+mod cosmetics;
+mod packs;
+mod mods;
+mod picking;
+mod archive;
+mod sharing;
+mod trainer;
+mod efficiency;
+mod hints;
+mod assist;
+mod quadrants;
+mod pause;
+mod chimes;
+mod records;
+mod saved_game;
+mod session;
+mod scramble;
+mod weekly;
+mod interlude;
+mod shuffle_confirm;
+mod custom_image;
+mod gif;
+mod solve_gif;
+mod png;
+mod capture;
+mod gallery;
+mod editor;
+mod gamepad;
+mod keybindings;
+mod touch;
+mod platform;
+mod stats_screen;
+mod achievements;
+mod countdown;
+mod playback;
+mod attract;
+mod focus;
+mod ghost;
+mod frame_buttons;
+mod versus;
+mod race;
+mod sound;
+mod music;
+mod desync;
+mod frame;
+mod tile_mesh;
+mod camera;
+mod intro;
+mod tutorial;
+mod celebration;
+mod theme;
+mod empty_slot;
+mod settings;
+mod display;
+mod board_code;
+mod level;
+mod rotation;
+mod mega_board;
+mod remote;
+mod line_stream;
+mod idle_hint;
+mod layout;
+#[cfg(feature = "narration")]
+mod narration;
+#[cfg(all(feature = "cloud-sync", not(target_arch = "wasm32")))]
+mod cloud_sync;
+#[cfg(not(target_arch = "wasm32"))]
+mod leaderboard;
+#[cfg(feature = "debug-ui")]
+mod event_log;
+#[cfg(feature = "debug-ui")]
+mod time_travel;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    
-    App::new()
-        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
-        .add_plugins(HookPlugin)
+    let mut cli = Cli::parse_from(platform::launch_args());
+    if let Err(error) = cli.apply_config() {
+        Cli::command().error(ErrorKind::InvalidValue, error).exit();
+    }
+    let paths = DataPaths::new(cli.portable);
+    match cli.mode {
+        Mode::Play => (),
+        Mode::Analyze => {
+            if let Err(error) = analysis::run(&paths, cli.samples) {
+                eprintln!("cannot save the analysis: {error}");
+                process::exit(1);
+            }
+            return;
+        },
+        Mode::ScrambleReport => {
+            let sizes = cli.sizes.iter().map(|size| *size as usize).collect::<Vec<usize>>();
+            if let Err(error) = analysis::scramble_report(&paths, &sizes, cli.scrambles, cli.seed.unwrap_or(0)) {
+                eprintln!("cannot save the scramble report: {error}");
+                process::exit(1);
+            }
+            return;
+        },
+        Mode::Challenges => {
+            let folder = cli.challenges.clone().unwrap_or_else(|| paths.challenges());
+            match mods::check_challenges(&folder) {
+                Ok(true) => return,
+                Ok(false) => process::exit(1),
+                Err(error) => {
+                    eprintln!("{}: {error}", folder.display());
+                    process::exit(1);
+                },
+            }
+        },
+    }
+    let replay = cli.replay.as_ref().map(|path| {
+        Replay::load(path).unwrap_or_else(|error| {
+            eprintln!("{}: {error}", path.display());
+            process::exit(1);
+        })
+    }).or_else(|| cli.replay_code.as_ref().map(|code| {
+        Replay::from_code(code).unwrap_or_else(|error| {
+            eprintln!("replay code: {error}");
+            process::exit(1);
+        })
+    }));
+    let settings = Settings::load(&paths);
+    // A saved pack may have been removed since, unlike one given with `--pack`
+    let packs = match (cli.pack.as_deref(), settings.pack.as_deref()) {
+        (None, Some(saved)) => TexturePacks::discover(Some(saved)).or_else(|error| {
+            eprintln!("{error}, the default pack is used");
+            TexturePacks::discover(None)
+        }),
+        (pack, _) => TexturePacks::discover(pack),
+    }.unwrap_or_else(|error| {
+        Cli::command().error(ErrorKind::InvalidValue, error).exit();
+    });
+    let (size, rows) = match (cli.size, settings.board_size()) {
+        (Some(size), _) => (size, size),
+        (None, Some(board_size)) => board_size,
+        (None, None) => {
+            let size = packs.selected().preferred_size().unwrap_or(3);
+            (size, size)
+        },
+    };
+    let (width, height) = replay.as_ref().map_or((size, cli.rows.unwrap_or(rows)), |replay| (replay.size, replay.height()));
+    // A replay plays on the board it was recorded on
+    let topology = replay.as_ref().map_or(if cli.wrap { Topology::Torus } else { Topology::Flat }, |replay| replay.topology);
+    let empty_slots = replay.as_ref().map_or(cli.empty_slots.unwrap_or(1), |replay| replay.empty_count);
+    let race = cli.race_join.as_deref().map(|address| {
+        race::join(address).unwrap_or_else(|error| {
+            eprintln!("cannot join the race at {address}: {error}");
+            process::exit(1);
+        })
+    });
+    // A race plays on the board of the host
+    let (width, height) = race.as_ref().map_or((width, height), |(_, hello)| (hello.width, hello.height));
+    let topology = race.as_ref().map_or(topology, |(_, hello)| hello.topology);
+    let empty_slots = race.as_ref().map_or(empty_slots, |(_, hello)| hello.empty_count);
+    let (width, height) = cli.state.as_ref().map_or((width, height), |board| (board.width, board.height));
+    // A board code also says how the board is played
+    let topology = cli.state.as_ref().map_or(topology, |board| board.topology);
+    let empty_slots = cli.state.as_ref().map_or(empty_slots, |board| board.empty_count);
+    let log_level = cli.log_level.unwrap_or(Level::INFO);
+    let locale = cli.locale.or(settings.language).unwrap_or_else(Locale::from_env);
+    let auto_solve = cli.auto_solve_options();
+    let graphics = cli.graphics_settings();
+    let shuffle_mode = cli.shuffle_mode();
+    let control_scheme = if cli.classic_controls { ControlScheme::Classic } else { settings.control_scheme };
+    let launch_options = cli.launch_options();
+    let window_mode = if launch_options.fullscreen { WindowMode::BorderlessFullscreen } else { WindowMode::Windowed };
+
+    // Closing the window goes through the exit confirmation, see `exit`. On the web the canvas fills the element embedding it
+    let default_plugins = DefaultPlugins
+        .set(ImagePlugin::default_nearest())
+        .set(WindowPlugin { primary_window: Some(Window {
+            fit_canvas_to_parent: true,
+            mode: window_mode,
+            present_mode: settings.vsync.present_mode(),
+            resolution: display::window_resolution(&settings),
+            ..default()
+        }), close_when_requested: false, ..default() });
+    let default_plugins = if cli.headless {
+        default_plugins
+            .set(WindowPlugin { primary_window: None, exit_condition: ExitCondition::DontExit, close_when_requested: false })
+            .set(RenderPlugin { render_creation: WgpuSettings { backends: None, ..default() }.into() })
+            .disable::<WinitPlugin>()
+    } else {
+        default_plugins
+    };
+    let default_plugins = match &cli.log_file {
+        Some(path) => {
+            if let Err(error) = logging::init_file_logging(log_level, path) {
+                eprintln!("{}: {error}", path.display());
+                process::exit(1);
+            }
+            default_plugins.disable::<LogPlugin>()
+        },
+        None => default_plugins.set(logging::log_plugin(log_level)),
+    };
+
+    let mut app = App::new();
+    app
+        .insert_resource(auto_solve)
+        .insert_resource(graphics)
+        .insert_resource(paths)
+        .insert_resource(locale)
+        .insert_resource(packs)
+        .insert_resource(settings)
+        .insert_resource(launch_options)
+        .add_plugins(default_plugins)
         .add_plugins(TilePlugin)
         .add_plugins(GuiPlugin)
-        .add_plugins(TaquinPlugin {size: args.get(1).unwrap_or(&"3".to_string()).parse::<i8>().unwrap_or(3)})
+        .add_plugins(I18nPlugin)
+        .add_plugins(TaquinPlugin { width, height, seed: cli.seed, control_scheme, topology, empty_slots, tile_thickness: DEFAULT_TILE_THICKNESS })
+        .add_plugins(ReplayPlugin)
+        .add_plugins(HeatmapPlugin)
+        .add_plugins(PhasesPlugin)
+        .add_plugins(StatsPlugin)
+        .add_plugins(ProfilePlugin)
+        .add_plugins(MetricsPlugin)
+        .add_plugins(SwitchScanPlugin)
+        .add_plugins(GameSpeedPlugin)
+        .add_plugins(TileLabelPlugin)
+        .add_plugins(SolverCachePlugin)
+        .add_plugins(AutoSolvePlugin)
+        .add_plugins(AnalysisPlugin)
+        .add_plugins(DebugOverlayPlugin)
+        .add_plugins(TileBakePlugin)
+        .add_plugins(WarmUpPlugin)
+        .add_plugins(ExitPlugin)
+        .add_plugins(TileMaterialPlugin)
+        .add_plugins(DissolvePlugin)
+        .add_plugins(TablePlugin)
+        .add_plugins(GraphicsPlugin)
+        .add_plugins(FrameLimitPlugin)
+        .add_plugins(AmbiencePlugin)
+        .add_plugins(SeasonsPlugin)
+        .add_plugins(CosmeticsPlugin)
+        .add_plugins(PacksPlugin)
+        .add_plugins(ModsPlugin)
+        .add_plugins(PickingPlugin)
+        .add_plugins(SharingPlugin)
+        .add_plugins(TrainerPlugin)
+        .add_plugins(EfficiencyPlugin)
+        .add_plugins(HintsPlugin)
+        .add_plugins(AssistPlugin)
+        .add_plugins(QuadrantsPlugin)
+        .add_plugins(PausePlugin)
+        .add_plugins(ChimesPlugin)
+        .add_plugins(RecordsPlugin)
+        .add_plugins(SavedGamePlugin)
+        .add_plugins(SessionPlugin)
+        .add_plugins(ScramblePlugin)
+        .add_plugins(WeeklyPlugin)
+        .add_plugins(InterludePlugin)
+        .add_plugins(ShuffleConfirmPlugin)
+        .add_plugins(CustomImagePlugin)
+        .add_plugins(SolveGifPlugin)
+        .add_plugins(CapturePlugin)
+        .add_plugins(GalleryPlugin)
+        .add_plugins(EditorPlugin)
+        .add_plugins(GamepadPlugin)
+        .add_plugins(KeyBindingsPlugin)
+        .add_plugins(TouchPlugin)
+        .add_plugins(StatsScreenPlugin)
+        .add_plugins(AchievementsPlugin)
+        .add_plugins(CountdownPlugin)
+        .add_plugins(PlaybackPlugin)
+        .add_plugins(AttractPlugin)
+        .add_plugins(FocusPlugin)
+        .add_plugins(GhostPlugin)
+        .add_plugins(FrameButtonsPlugin)
+        .add_plugins(VersusPlugin)
+        .add_plugins(RacePlugin)
+        .add_plugins(SoundPlugin)
+        .add_plugins(MusicPlugin)
+        .add_plugins(DesyncPlugin)
+        .add_plugins(CameraPlugin)
+        .add_plugins(IntroPlugin)
+        .add_plugins(TutorialPlugin)
+        .add_plugins(CelebrationPlugin)
+        .add_plugins(ThemePlugin)
+        .add_plugins(EmptySlotPlugin)
+        .add_plugins(SettingsPlugin)
+        .add_plugins(DisplayPlugin)
+        .add_plugins(BoardCodePlugin)
+        .add_plugins(LevelPlugin)
+        .add_plugins(RotationPlugin)
+        .add_plugins(MegaBoardPlugin)
+        .add_plugins(RemotePlugin)
+        .add_plugins(IdleHintPlugin)
         .add_state::<AppState>()
         .init_resource::<Markers>()
-        .add_systems(OnEnter(AppState::Setup), setup_scene)
-        .add_systems(Update, setup_markers.run_if(in_state(AppState::Setup)))
+        .add_systems(OnEnter(AppState::Setup), (setup_scene, build_frame))
         .add_systems(Update, check_setup_finished.run_if(in_state(AppState::Setup)))
-        .add_systems(OnEnter(AppState::SetupTiles), setup_tiles)
-        .run();
+        // The board size may have changed in the main menu, the frame is fitted to it before the tiles
+        .add_systems(OnEnter(AppState::SetupTiles), (build_frame, setup_tiles).chain());
+
+    #[cfg(feature = "narration")]
+    app.add_plugins(narration::NarrationPlugin);
+    #[cfg(all(feature = "cloud-sync", not(target_arch = "wasm32")))]
+    app.add_plugins(cloud_sync::CloudSyncPlugin);
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugins(leaderboard::LeaderboardPlugin);
+    #[cfg(feature = "debug-ui")]
+    app.add_plugins(event_log::EventLogPlugin);
+    #[cfg(feature = "debug-ui")]
+    app.add_plugins(time_travel::TimeTravelPlugin);
+
+    if cli.headless {
+        app.add_plugins(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1. / 60.)));
+    }
+    if let Some(image) = cli.image {
+        app.insert_resource(PuzzleImage(image));
+    }
+    if let Some(url) = cli.image_url {
+        app.insert_resource(ImageUrl(url));
+    }
+    if let Some(game_speed) = cli.game_speed {
+        app.insert_resource(game_speed);
+    }
+    if let Some(tile_labels) = cli.tile_labels {
+        app.insert_resource(tile_labels);
+    }
+    if let Some(color_filter) = cli.color_filter {
+        app.insert_resource(color_filter);
+    }
+    // Browsers pace the frames themselves and cannot sleep
+    if cli.max_fps.is_some() && !cfg!(target_arch = "wasm32") {
+        app.insert_resource(FrameLimit(cli.max_fps));
+    }
+    if let Some(season) = cli.season {
+        app.insert_resource(season);
+    }
+    if cli.no_ambience || cli.frame_sway {
+        app.insert_resource(Ambience { enabled: !cli.no_ambience, frame_sway: cli.frame_sway });
+    }
+    if cli.battery_saver {
+        app.insert_resource(BatterySaver::new(true));
+    }
+    if cli.benchmark {
+        app.add_plugins(BenchmarkPlugin);
+    }
+    if let Some(table) = cli.table {
+        app.insert_resource(table);
+    }
+    if let Some(frame_tint) = cli.frame_tint {
+        app.insert_resource(frame_tint);
+    }
+    if let Some(theme) = cli.theme {
+        app.insert_resource(theme);
+    }
+    if let Some(size) = cli.solver_table_size {
+        app.insert_resource(SolverTable::new(size));
+    }
+    if cli.one_switch {
+        app.init_resource::<SwitchScanning>();
+    }
+    if cli.assist {
+        app.insert_resource(AssistMode(true));
+    }
+    let rotating_tiles = race.as_ref().map_or(cli.rotating_tiles, |(_, hello)| hello.rotating_tiles);
+    if cli.state.as_ref().map_or(rotating_tiles, |board| board.rotations.is_some()) {
+        app.insert_resource(RotatingTiles(true));
+    }
+    if cli.diegetic {
+        app.insert_resource(DiegeticUi(true));
+    }
+    app.insert_resource(shuffle_mode);
+    if let Some((connection, hello)) = race {
+        app.insert_resource(connection).insert_resource(Race::new(hello.seed));
+    }
+    if let Some(address) = cli.race_host {
+        match RaceHost::bind(address) {
+            Ok(host) => app.insert_resource(host),
+            Err(error) => {
+                eprintln!("cannot host a race on {address}: {error}");
+                process::exit(1);
+            },
+        };
+    }
+    if let Some(endpoint) = cli.remote {
+        match RemoteControl::open(endpoint) {
+            Ok(remote) => app.insert_resource(remote),
+            Err(error) => {
+                eprintln!("cannot open the remote control: {error}");
+                process::exit(1);
+            },
+        };
+    }
+    if cli.skip_menu || cli.headless || cli.benchmark || replay.is_some() || cli.race_host.is_some() || cli.race_join.is_some() || cli.remote.is_some() || cli.state.is_some() {
+        app.insert_resource(SkipMainMenu);
+    }
+    if let Some(board) = cli.state.take() {
+        app.insert_resource(PastedBoard(board));
+    }
+    if let Some(replay) = replay {
+        app.insert_resource(ReplayPlayback::new(replay));
+    }
+    app.run();
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, States)]
 pub enum AppState {
     #[default]
     Setup,
+    /// Size and picture are picked here before the tiles are spawned
+    MainMenu,
     SetupTiles,
+    /// Pipelines are compiled offscreen before the player can move a tile
+    WarmUp,
+    /// The camera flies in over the board before the first game, no input reaches the game meanwhile, see `intro`
+    Intro,
+    /// First launch, the player is walked through the moves on the board before the first game, see `tutorial`
+    Tutorial,
     Running,
+    /// Systems of `Running` are frozen, see `pause`
+    Paused,
+    /// Frozen as well while a modal OS dialog is open, see `interlude`
+    Interlude,
+    /// The tiles are put anywhere to make a challenge, see `editor`
+    Editor,
+    /// A countdown game ran out of time, frozen until retried, see `countdown`
+    TimeUp,
 }
 
-#[derive(Component)]
-struct FrameScene;
-
+/// Whether the board takes moves, the tutorial being played on the board as a game is
+pub fn is_board_playable(
+    state: Res<State<AppState>>,
+) -> bool {
+    matches!(state.get(), AppState::Running | AppState::Tutorial)
+}
 
+/// Goes straight to the game, for replays, benchmarks, headless runs, `--remote`, `--state` and `--skip-menu`
 #[derive(Resource)]
-struct TaquinSprites {
-    bevy: Handle<Image>,
-    rust: Handle<Image>
-}
+struct SkipMainMenu;
 
-struct TaquinSpritesLoaded {
-    bevy: bool,
-    rust: bool
-}
+/// Picture given with `--image`, replacing the first picture of the texture pack
+#[derive(Resource)]
+struct PuzzleImage(PathBuf);
 
-impl TaquinSpritesLoaded {
-    fn is_ready(&self) -> bool {
-        self.bevy && self.rust
-    }
+/// Pictures of the texture pack in use, followed by the gallery ones
+#[derive(Resource)]
+struct TaquinSprites {
+    images: Vec<Handle<Image>>,
+    /// Whether the tiles show these pictures, or the ones of the previous pack
+    applied: bool,
+    /// Picture the tiles are spawned with
+    selected: usize,
 }
 
-impl FromWorld for TaquinSpritesLoaded {
-    fn from_world(_world: &mut World) -> Self {
-        TaquinSpritesLoaded { bevy: false, rust: false }
+impl TaquinSprites {
+    /// Pictures made at runtime, such as a cropped custom picture, are not known to the asset server
+    fn is_loaded(&self, asset_server: &AssetServer) -> bool {
+        self.images.iter().all(|image| asset_server.get_load_state(image).is_none() || asset_server.is_loaded_with_dependencies(image))
     }
 }
 
 fn setup_scene(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut images: ResMut<Assets<Image>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>
+    packs: Res<TexturePacks>,
+    puzzle_image: Option<Res<PuzzleImage>>,
+    settings: Res<Settings>,
 ) {
-    let debug_material = materials.add(StandardMaterial {
-        base_color_texture: Some(images.add(uv_debug_texture())),
-        ..default()
-    });
-
-    commands.insert_resource(TaquinSprites {
-        bevy: asset_server.load("textures/taquin/bevy.png"),
-        rust: asset_server.load("textures/taquin/rust.png")
-    });
-        
-    commands.spawn((SceneBundle {
-        scene: asset_server.load("models/frame.glb#Scene0"),
-        transform: Transform::from_rotation(Quat::from_axis_angle(Vec3::Y, PI)),
-        ..default()
-    }, FrameScene, SceneHook::new(|entity, commands| {
-        match entity.get::<Name>().map(|t|t.as_str()) {
-            Some("TL") | Some("TR") | Some("BL") | Some("BR") => commands.insert(Marker),
-            _ => commands,
-        };
-    })));
-
+    let mut images = packs.selected().image_paths().map(|path| asset_server.load(path)).collect::<Vec<Handle<Image>>>();
+    // The picture given on the command line is the first one, it is the one shown
+    let selected = if puzzle_image.is_some() { 0 } else { settings.picture.min(images.len().saturating_sub(1)) };
+    if let Some(image) = puzzle_image {
+        images[0] = asset_server.load(image.0.clone());
+        commands.insert_resource(PendingCustomImage(images[0].clone()));
+    }
+    commands.insert_resource(TaquinSprites { images, applied: true, selected });
 
-    commands.spawn(PointLightBundle {
-        point_light: PointLight {
-            intensity: 9000.0,
-            range: 100.,
-            shadows_enabled: true,
-            ..default()
-        },
+    // Intensity, range and shadows come from the graphics settings
+    commands.spawn((PointLightBundle {
         transform: Transform::from_xyz(8.0, 16.0, 8.0),
         ..default()
-    });
-
-    // ground plane
-    commands.spawn(PbrBundle {
-        mesh: meshes.add(shape::Plane::from_size(50.0).into()),
-        material: debug_material,
-        ..default()
-    });
+    }, MainLight));
 
-    commands.spawn(Camera3dBundle {
+    commands.spawn((Camera3dBundle {
         transform: Transform::from_xyz(0.0, 30., 40.0).looking_at(Vec3::new(0., 1., 0.), Vec3::Y),
         ..default()
-    });
+    }, MainCamera));
 }
 
 
 fn check_setup_finished(
-    taquin_sprite_folder: ResMut<TaquinSprites>,
-    mut events: EventReader<AssetEvent<Image>>,
+    asset_server: Res<AssetServer>,
+    taquin_sprites: Res<TaquinSprites>,
     mut next_state: ResMut<NextState<AppState>>,
-    mut taquin_sprites_loaded: Local<TaquinSpritesLoaded>,
-    markers: Res<Markers>
+    markers: Res<Markers>,
+    time: Res<Time>,
+    skip_main_menu: Option<Res<SkipMainMenu>>,
 ) {
-
-    for event in events.read() {
-        if event.is_loaded_with_dependencies(&taquin_sprite_folder.bevy) {
-            taquin_sprites_loaded.bevy = true;
-        } else if event.is_loaded_with_dependencies(&taquin_sprite_folder.rust) {
-            taquin_sprites_loaded.rust = true;
-        }
-    }
-
-    if markers.is_ready() && taquin_sprites_loaded.is_ready() {
-        next_state.set(AppState::SetupTiles);
+    if validate_markers(&markers).is_ok() && taquin_sprites.is_loaded(&asset_server) {
+        info!(elapsed = time.elapsed_seconds(), "assets and frame markers ready");
+        next_state.set(if skip_main_menu.is_some() { AppState::SetupTiles } else { AppState::MainMenu });
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn setup_tiles(
     mut commands: Commands,
     taquin_sprite_handles: Res<TaquinSprites>,
     markers: Res<Markers>,
+    thickness: Res<TileThickness>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut taquin : ResMut<Taquin>,
+    mut images: ResMut<Assets<Image>>,
+    mut clips: ResMut<Assets<AnimationClip>>,
+    mut materials: ResMut<Assets<TileMaterial>>,
+    mut back_materials: ResMut<Assets<StandardMaterial>>,
+    shared_material: Res<SharedTileMaterial>,
+    mut taquin: ResMut<Taquin>,
+    empty_slots: Res<EmptySlots>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    let tile_width = markers.inner_width() / taquin.size as f32;
-    let tile_height = markers.inner_height() / taquin.size as f32;
-    let tile_ratio = 1. / taquin.size as f32;
-    let origin = markers.tl;
-
-    taquin.tiles = (0..taquin.size).map(|j| {
-        (0..taquin.size).map(|i| {
-            let translation = Vec3 { 
-                x: origin.x + i as f32 * tile_width + tile_width / 2., 
-                y: origin.y - j as f32 * tile_height - tile_height / 2., 
-                z: 0.75
-            };
-            let value = j * taquin.size + i + 1;
-            if i == taquin.size - 1 && j == taquin.size - 1 {
-                commands.spawn((Transform::from_translation(translation), EmptyTile, TileCoordinates::new(i, j)));
-                return TileValue(taquin.size * taquin.size);
+    taquin.set_empty_count(empty_slots.0);
+    let _span = info_span!("setup_tiles", width = taquin.width, height = taquin.height, resumed = taquin.is_shuffled).entered();
+    let tile_size = markers.tile_size(taquin.width, taquin.height);
+    // The picture is stretched over the frame, a tile covers a cell of it on each axis
+    let (u_ratio, v_ratio) = (1. / taquin.width as f32, 1. / taquin.height as f32);
+    // A resumed game sets the board beforehand, the tiles are spawned where they were left
+    let saved_tiles = (taquin.tiles.len() == taquin.height as usize).then(|| taquin.tiles.clone());
+    if let Some(material) = materials.get_mut(&shared_material.0) {
+        material.base.base_color_texture = taquin_sprite_handles.images.get(taquin_sprite_handles.selected).or(taquin_sprite_handles.images.first()).cloned();
+    }
+    let cell = |i, j| markers.tile_translation(taquin.width, taquin.height, TileCoordinates::new(i, j));
+    let animations = TileAnimations::new(&mut clips, cell(1, 0) - cell(0, 0), cell(0, 1) - cell(0, 0));
+    let mega = is_mega(taquin.width, taquin.height);
+    // Tiles face out of the board plane, which a tilted frame turns
+    let rotation = markers.rotation();
+
+    let tiles = (0..taquin.height).map(|j| {
+        (0..taquin.width).map(|i| {
+            let translation = cell(i, j);
+            let value = saved_tiles.as_ref().map_or(TileValue::for_cell(TileCoordinates::new(i, j), taquin.width).0, |tiles| tiles[j as usize][i as usize].0);
+            if taquin.is_empty_value(TileValue(value)) {
+                commands.spawn((Transform::from_translation(translation).with_rotation(rotation), EmptyTile, TileCoordinates::new(i, j), TileValue(value), TileId::from(TileValue(value))));
+                return TileValue(value);
             }
-            let mut block = Mesh::from(shape::Quad::new(Vec2::new(tile_width, tile_height)));
-            if let Some(attr) = block.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
-                *attr = VertexAttributeValues::Float32x2(vec![
-                    [0. + i as f32 * tile_ratio, (j + 1) as f32 * tile_ratio],
-                    [0. + i as f32 * tile_ratio, j  as f32 * tile_ratio],
-                    [(i + 1) as f32 * tile_ratio, j  as f32 * tile_ratio],
-                    [(i + 1) as f32 * tile_ratio, (j + 1) as f32 * tile_ratio],
-                ]);
+            // Thousands of meshes and players would not hold the frame rate, the chunks of `mega_board` draw these tiles
+            if mega {
+                let mut tile_command = commands.spawn((
+                    Transform::from_translation(translation).with_rotation(rotation),
+                    TileShading::default(),
+                    TileCoordinates::new(i, j),
+                    TileValue(value),
+                    TileId::from(TileValue(value)),
+                    MegaTile::default(),
+                ));
+                if value == 1 {
+                    tile_command.insert(TileSelected);
+                }
+                return TileValue(value);
             }
-            let mut tile_command = commands.spawn((PbrBundle {
-                    mesh: meshes.add(block),
-                    material: materials.add(StandardMaterial {
-                        base_color_texture: Some(taquin_sprite_handles.bevy.clone()),
-                        alpha_mode: AlphaMode::Blend,
-                        ..default()
-                    }),
-                    transform: Transform::from_translation(translation),
+            // The picture part is the one of the cell the tile belongs to
+            let TileCoordinates { i: u, j: v } = TileValue(value).goal(taquin.width);
+            let uv = Rect::new(u as f32 * u_ratio, v as f32 * v_ratio, (u + 1) as f32 * u_ratio, (v + 1) as f32 * v_ratio);
+            let mut tile_command = commands.spawn((MaterialMeshBundle {
+                    mesh: meshes.add(tile_mesh(tile_size, thickness.0, uv)),
+                    material: shared_material.0.clone(),
+                    transform: Transform::from_translation(translation).with_rotation(rotation),
                     ..default()
                 }, 
+                TileShading::default(),
                 TileCoordinates::new(i, j),
+                TileValue(value),
+                TileId::from(TileValue(value)),
+                tile_name(),
+                AnimationPlayer::default(),
+                animations.clone(),
             ));
-            if i == 0 && j == 0 {
+            if value == 1 {
                 tile_command.insert(TileSelected);
             }
+            // The number is engraved on the back, facing away from the picture
+            tile_command.with_children(|parent| {
+                parent.spawn(PbrBundle {
+                    mesh: meshes.add(shape::Quad::new(tile_size * 0.8).into()),
+                    material: back_materials.add(StandardMaterial {
+                        base_color_texture: Some(images.add(label_texture(value, TileLabelStyle::Digits))),
+                        alpha_mode: AlphaMode::Blend,
+                        ..default()
+                    }),
+                    transform: Transform::from_xyz(0., 0., -thickness.0 - 0.01).with_rotation(Quat::from_rotation_y(PI)),
+                    ..default()
+                });
+            });
             TileValue(value)
         }).collect()
     }).collect();
+    taquin.set_tiles(tiles);
 
-    next_state.set(AppState::Running);
-}
-
-/// Creates a colorful test pattern
-fn uv_debug_texture() -> Image {
-    const TEXTURE_SIZE: usize = 8;
-
-    let mut palette: [u8; 32] = [
-        255, 102, 159, 255, 255, 159, 102, 255, 236, 255, 102, 255, 121, 255, 102, 255, 102, 255,
-        198, 255, 102, 198, 255, 255, 121, 102, 255, 255, 236, 102, 255, 255,
-    ];
-
-    let mut texture_data = [0; TEXTURE_SIZE * TEXTURE_SIZE * 4];
-    for y in 0..TEXTURE_SIZE {
-        let offset = TEXTURE_SIZE * y * 4;
-        texture_data[offset..(offset + TEXTURE_SIZE * 4)].copy_from_slice(&palette);
-        palette.rotate_right(4);
-    }
-
-    Image::new_fill(
-        Extent3d {
-            width: TEXTURE_SIZE as u32,
-            height: TEXTURE_SIZE as u32,
-            depth_or_array_layers: 1,
-        },
-        TextureDimension::D2,
-        &texture_data,
-        TextureFormat::Rgba8UnormSrgb,
-    )
+    next_state.set(AppState::WarmUp);
 }
\ No newline at end of file
@@ -1,9 +1,13 @@
 use bevy::prelude::*;
 
-#[derive(Component)]
-pub struct Marker;
+use crate::tile::TileCoordinates;
 
-#[derive(Resource, Default)]
+/// Height of the tiles in front of the board plane
+const TILE_DEPTH: f32 = 0.75;
+
+/// Corners of the inside of the frame, set by `frame::build_frame` once `layout` checked them.
+/// They may be tilted or turned, the board lies in their plane
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
 pub struct Markers {
     pub tl : Vec3,
     pub tr : Vec3,
@@ -12,30 +16,48 @@ pub struct Markers {
 }
 
 impl Markers {
-    pub fn is_ready(&self) -> bool {
-        self.tl != Vec3::default() && self.tr != Vec3::default() && self.bl != Vec3::default() && self.br != Vec3::default()
+    /// Along the rows of the board, from its left side to its right one
+    pub fn right(&self) -> Vec3 {
+        (self.tr - self.tl).normalize_or_zero()
+    }
+
+    /// Along the columns of the board, from its top to its bottom
+    pub fn down(&self) -> Vec3 {
+        (self.bl - self.tl).normalize_or_zero()
+    }
+
+    /// Out of the front of the board, where the pictures face
+    pub fn normal(&self) -> Vec3 {
+        self.down().cross(self.right()).normalize_or_zero()
+    }
+
+    /// Turns a tile laid in the xy plane facing z into the board plane
+    pub fn rotation(&self) -> Quat {
+        Quat::from_mat3(&Mat3::from_cols(self.right(), -self.down(), self.normal()))
     }
 
     pub fn inner_width(&self) -> f32 {
-        self.tr.x - self.tl.x
+        self.tl.distance(self.tr)
     }
 
     pub fn inner_height(&self) -> f32 {
-        self.tr.y - self.br.y
+        self.tl.distance(self.bl)
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.tl + self.br) / 2.
     }
-}
 
-pub fn setup_markers(
-    mut markers: ResMut<Markers>,
-    query: Query<(&Name, &GlobalTransform), With<Marker>>
-) {
-    for (name, global_transform) in query.iter() {
-        match name.as_str() {
-            "TL" => markers.tl = global_transform.translation(),
-            "TR" => markers.tr = global_transform.translation(),
-            "BL" => markers.bl = global_transform.translation(),
-            "BR" => markers.br = global_transform.translation(),
-            _ => (),
-        };
-    }
-}
\ No newline at end of file
+    /// Tiles stretch to fill the frame, rectangular boards get rectangular tiles
+    pub fn tile_size(&self, width: i8, height: i8) -> Vec2 {
+        Vec2::new(self.inner_width() / width as f32, self.inner_height() / height as f32)
+    }
+
+    pub fn tile_translation(&self, width: i8, height: i8, coordinates: TileCoordinates) -> Vec3 {
+        let tile_size = self.tile_size(width, height);
+        self.tl
+            + self.right() * (coordinates.i as f32 + 0.5) * tile_size.x
+            + self.down() * (coordinates.j as f32 + 0.5) * tile_size.y
+            + self.normal() * TILE_DEPTH
+    }
+}
@@ -0,0 +1,246 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, MeshVertexAttribute, VertexAttributeValues},
+        render_resource::PrimitiveTopology,
+        view::NoFrustumCulling,
+    },
+    transform::TransformSystem,
+};
+
+use crate::{
+    marker::Markers,
+    taquin::{Taquin, MAX_SOLVER_SIZE},
+    tile::TileValue,
+    tile_material::{SharedTileMaterial, TileMaterial, TileShading},
+    tile_mesh::{tile_mesh, TileThickness},
+    AppState,
+};
+
+/// Boards with a side this long or longer are mega boards: their tiles are drawn by a few shared meshes,
+/// slide without animation players and the solver leaves them alone
+pub const MEGA_BOARD_SIZE: i8 = MAX_SOLVER_SIZE + 1;
+/// Tiles drawn by a chunk mesh, a moving or glowing tile uploads its chunk again rather than the whole board
+const CHUNK_TILES: usize = 64;
+
+pub struct MegaBoardPlugin;
+
+impl Plugin for MegaBoardPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::MainMenu), despawn_board_chunks)
+            // Once the slides moved the tiles for the frame
+            .add_systems(PostUpdate, (build_board_chunks, update_board_chunks).chain()
+                .after(TransformSystem::TransformPropagate)
+                .run_if(any_with_component::<MegaTile>()));
+    }
+}
+
+pub fn is_mega(width: i8, height: i8) -> bool {
+    width.max(height) >= MEGA_BOARD_SIZE
+}
+
+/// Tile of a mega board, drawn as a part of the mesh of its chunk rather than by a mesh of its own
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct MegaTile {
+    /// Chunk drawing the tile and the first vertex of the tile in its mesh, none until the chunks are built
+    part: Option<(Entity, usize)>,
+}
+
+/// Mesh drawing `CHUNK_TILES` tiles of a mega board with the shared material
+#[derive(Component)]
+struct BoardChunk;
+
+/// Vertices of a tile of the mega board around its origin, placed by the transform of each tile
+#[derive(Resource, Debug)]
+struct MegaTileShape {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    /// Where each vertex samples a picture part going from 0 to 1
+    uvs: Vec<Vec2>,
+    indices: Vec<u32>,
+}
+
+impl MegaTileShape {
+    fn new(size: Vec2, thickness: f32) -> Self {
+        let mesh = tile_mesh(size, thickness, Rect::new(0., 0., 1., 1.));
+        let vectors = |attribute: MeshVertexAttribute| match mesh.attribute(attribute) {
+            Some(VertexAttributeValues::Float32x3(values)) => values.iter().map(|value| Vec3::from_array(*value)).collect(),
+            _ => Vec::new(),
+        };
+        let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(values)) => values.iter().map(|value| Vec2::from_array(*value)).collect(),
+            _ => Vec::new(),
+        };
+        Self {
+            positions: vectors(Mesh::ATTRIBUTE_POSITION),
+            normals: vectors(Mesh::ATTRIBUTE_NORMAL),
+            uvs,
+            indices: mesh.indices().map_or_else(Vec::new, |indices| indices.iter().map(|index| index as u32).collect()),
+        }
+    }
+
+    fn vertices(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Writes the vertices of a tile with `transform` over its part of the chunk mesh
+    fn place(&self, transform: &Transform, positions: &mut [[f32; 3]], normals: &mut [[f32; 3]]) {
+        for (placed, position) in positions.iter_mut().zip(&self.positions) {
+            *placed = transform.transform_point(*position).to_array();
+        }
+        for (placed, normal) in normals.iter_mut().zip(&self.normals) {
+            *placed = (transform.rotation * *normal).to_array();
+        }
+    }
+
+    /// Mesh of the tiles of a chunk, each with its transform, its part of the picture and its shading
+    fn chunk_mesh(&self, tiles: &[(Transform, Rect, TileShading)]) -> Mesh {
+        let vertices = self.vertices();
+        let mut positions = vec![[0.; 3]; tiles.len() * vertices];
+        let mut normals = vec![[0.; 3]; tiles.len() * vertices];
+        let mut uvs = Vec::with_capacity(tiles.len() * vertices);
+        let mut colors = Vec::with_capacity(tiles.len() * vertices);
+        let mut indices = Vec::with_capacity(tiles.len() * self.indices.len());
+        for (index, (transform, uv, shading)) in tiles.iter().enumerate() {
+            let first = index * vertices;
+            self.place(transform, &mut positions[first..first + vertices], &mut normals[first..first + vertices]);
+            uvs.extend(self.uvs.iter().map(|corner| (uv.min + *corner * uv.size()).to_array()));
+            colors.extend(std::iter::repeat_n(shading.vertex_color(), vertices));
+            indices.extend(self.indices.iter().map(|vertex| first as u32 + vertex));
+        }
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+}
+
+/// Part of the picture of the tile with `value`, as `setup_tiles` cuts it for the other boards
+fn picture_part(value: TileValue, width: i8, height: i8) -> Rect {
+    let goal = value.goal(width);
+    let (u_ratio, v_ratio) = (1. / width as f32, 1. / height as f32);
+    Rect::new(goal.i as f32 * u_ratio, goal.j as f32 * v_ratio, (goal.i + 1) as f32 * u_ratio, (goal.j + 1) as f32 * v_ratio)
+}
+
+/// The tiles of a new mega board by chunks in the order of their values, a chunk starting as a block of rows
+fn build_board_chunks(
+    mut commands: Commands,
+    mut tiles_query: Query<(&TileValue, &Transform, &TileShading, &mut MegaTile), Added<MegaTile>>,
+    taquin: Res<Taquin>,
+    markers: Res<Markers>,
+    thickness: Res<TileThickness>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    shared_material: Res<SharedTileMaterial>,
+) {
+    if tiles_query.is_empty() {
+        return;
+    }
+    let _span = info_span!("build_board_chunks", width = taquin.width, height = taquin.height).entered();
+    let shape = MegaTileShape::new(markers.tile_size(taquin.width, taquin.height), thickness.0);
+    let mut tiles = tiles_query.iter_mut().collect::<Vec<_>>();
+    tiles.sort_by_key(|(value, ..)| value.0);
+    let mut chunks = 0;
+    for chunk_tiles in tiles.chunks_mut(CHUNK_TILES) {
+        let parts = chunk_tiles.iter()
+            .map(|(value, transform, shading, _)| (**transform, picture_part(**value, taquin.width, taquin.height), **shading))
+            .collect::<Vec<(Transform, Rect, TileShading)>>();
+        // Tiles leave the bounds of their chunk as they slide, the chunk is always drawn
+        let chunk = commands.spawn((MaterialMeshBundle::<TileMaterial> {
+            mesh: meshes.add(shape.chunk_mesh(&parts)),
+            material: shared_material.0.clone(),
+            ..default()
+        }, NoFrustumCulling, BoardChunk)).id();
+        for (index, (.., tile)) in chunk_tiles.iter_mut().enumerate() {
+            tile.part = Some((chunk, index * shape.vertices()));
+        }
+        chunks += 1;
+    }
+    info!(tiles = tiles.len(), chunks, "mega board built");
+    commands.insert_resource(shape);
+}
+
+/// A tile moved or shaded again rewrites its own vertices, only its chunk is uploaded again
+fn update_board_chunks(
+    shape: Option<Res<MegaTileShape>>,
+    tiles_query: Query<(&Transform, &TileShading, &MegaTile), Or<(Changed<Transform>, Changed<TileShading>)>>,
+    chunk_query: Query<&Handle<Mesh>, With<BoardChunk>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Some(shape) = shape else {
+        return;
+    };
+    let vertices = shape.vertices();
+    for (transform, shading, tile) in tiles_query.iter() {
+        let Some((chunk, first)) = tile.part else {
+            continue;
+        };
+        let Some(mesh) = chunk_query.get(chunk).ok().and_then(|handle| meshes.get_mut(handle)) else {
+            continue;
+        };
+        let range = first..first + vertices;
+        let mut normals = vec![[0.; 3]; vertices];
+        if let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION) {
+            shape.place(transform, &mut positions[range.clone()], &mut normals);
+        }
+        if let Some(VertexAttributeValues::Float32x3(mesh_normals)) = mesh.attribute_mut(Mesh::ATTRIBUTE_NORMAL) {
+            mesh_normals[range.clone()].copy_from_slice(&normals);
+        }
+        if let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR) {
+            colors[range].fill(shading.vertex_color());
+        }
+    }
+}
+
+/// The tiles themselves go with `despawn_tiles` of `gui`
+fn despawn_board_chunks(
+    mut commands: Commands,
+    chunk_query: Query<Entity, With<BoardChunk>>,
+) {
+    for entity in chunk_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<MegaTileShape>();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+
+    use crate::{
+        mega_board::{is_mega, picture_part, MegaTileShape},
+        tile::TileValue,
+        tile_material::TileShading,
+    };
+
+    #[test]
+    fn test_chunk_mesh() {
+        assert!(!is_mega(11, 4) && is_mega(4, 12) && is_mega(50, 50));
+        let shape = MegaTileShape::new(Vec2::ONE, 0.2);
+        let vertices = shape.vertices();
+        let tiles = [
+            (Transform::from_xyz(2., 0., 0.), picture_part(TileValue(1), 20, 20), TileShading::default()),
+            (Transform::from_xyz(5., 0., 0.), picture_part(TileValue(400), 20, 20), TileShading { glow: Color::WHITE, dissolve: 0.5 }),
+        ];
+        let mesh = shape.chunk_mesh(&tiles);
+        assert_eq!(mesh.count_vertices(), 2 * vertices);
+        assert_eq!(mesh.indices().unwrap().len(), 2 * shape.indices.len());
+        let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
+            panic!("no positions");
+        };
+        // Every vertex of the second tile is around its transform
+        assert!(positions[vertices..].iter().all(|position| (position[0] - 5.).abs() <= 0.5));
+        let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0) else {
+            panic!("no uvs");
+        };
+        assert!(uvs[..vertices].iter().all(|uv| uv[0] <= 0.051 && uv[1] <= 0.051));
+        assert!(uvs[vertices..].iter().all(|uv| uv[0] >= 0.949 && uv[1] >= 0.949));
+        let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute(Mesh::ATTRIBUTE_COLOR) else {
+            panic!("no colors");
+        };
+        assert_eq!(colors[vertices][3], 0.5);
+    }
+}
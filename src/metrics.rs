@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    stats::GameRecorded,
+};
+
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<MetricsSettings>()
+            .init_resource::<Metrics>()
+            .add_systems(Update, record_metrics.run_if(on_event::<GameRecorded>().and_then(|settings: Res<MetricsSettings>| settings.enabled)));
+    }
+}
+
+/// Metrics are only collected once the player sets `enabled` in the metrics settings file
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+pub struct MetricsSettings {
+    pub enabled: bool,
+    /// Aggregates are POSTed as json to this url after every solve when set
+    pub endpoint: Option<String>,
+}
+
+impl FromWorld for MetricsSettings {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().metrics_settings();
+        let settings = MetricsSettings { enabled: false, endpoint: None };
+        if persistence::exists(&path) {
+            return persistence::load_versioned::<MetricsSettingsFile>(&path, Format::Ron).map_or_else(|error| {
+                error!("cannot load metrics settings, metrics are disabled: {error}");
+                settings
+            }, |file| file.metrics);
+        }
+        let file = MetricsSettingsFile { version: MetricsSettingsFile::VERSION, metrics: settings.clone() };
+        if let Err(error) = persistence::save_versioned(&path, &file, Format::Ron) {
+            warn!("cannot write metrics settings: {error}");
+        }
+        settings
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MetricsSettingsFile {
+    #[serde(default)]
+    version: u32,
+    metrics: MetricsSettings,
+}
+
+impl Versioned for MetricsSettingsFile {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SizeMetrics {
+    pub solves: u32,
+    pub total_time: f32,
+    pub total_moves: u64,
+}
+
+impl SizeMetrics {
+    pub fn average_time(&self) -> f32 {
+        self.total_time / self.solves.max(1) as f32
+    }
+
+    pub fn average_moves(&self) -> f32 {
+        self.total_moves as f32 / self.solves.max(1) as f32
+    }
+}
+
+/// Anonymous aggregates per grid size, no individual game is kept
+#[derive(Resource, Serialize, Deserialize, Debug)]
+pub struct Metrics {
+    #[serde(default)]
+    pub version: u32,
+    pub sizes: BTreeMap<i8, SizeMetrics>,
+}
+
+impl Versioned for Metrics {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl FromWorld for Metrics {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().metrics();
+        persistence::load_versioned(path, Format::Ron).unwrap_or(Metrics { version: Self::VERSION, sizes: BTreeMap::new() })
+    }
+}
+
+#[derive(Serialize)]
+struct MetricsReport {
+    size: i8,
+    solves: u32,
+    average_time: f32,
+    average_moves: f32,
+}
+
+impl Metrics {
+    fn report(&self) -> Vec<MetricsReport> {
+        self.sizes.iter().map(|(size, metrics)| MetricsReport {
+            size: *size,
+            solves: metrics.solves,
+            average_time: metrics.average_time(),
+            average_moves: metrics.average_moves(),
+        }).collect()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn post_metrics(endpoint: String, body: String) {
+    std::thread::spawn(move || {
+        if let Err(error) = ureq::post(&endpoint).set("Content-Type", "application/json").send_string(&body) {
+            warn!("cannot send metrics to {endpoint}: {error}");
+        }
+    });
+}
+
+/// The web build has no blocking requests nor threads, the metrics stay local
+#[cfg(target_arch = "wasm32")]
+fn post_metrics(endpoint: String, _body: String) {
+    warn!("metrics are not sent to {endpoint} from a browser");
+}
+
+fn record_metrics(
+    mut metrics: ResMut<Metrics>,
+    settings: Res<MetricsSettings>,
+    paths: Res<DataPaths>,
+    mut recorded_events: EventReader<GameRecorded>,
+) {
+    for GameRecorded(game) in recorded_events.read() {
+        let size_metrics = metrics.sizes.entry(game.size).or_default();
+        size_metrics.solves += 1;
+        size_metrics.total_time += game.time;
+        size_metrics.total_moves += game.moves as u64;
+    }
+    if let Err(error) = persistence::save_versioned(paths.metrics(), metrics.as_ref(), Format::Ron) {
+        error!("cannot save metrics: {error}");
+    }
+
+    let Some(endpoint) = settings.endpoint.clone() else {
+        return;
+    };
+    match serde_json::to_string(&metrics.report()) {
+        Ok(body) => post_metrics(endpoint, body),
+        Err(error) => error!("cannot serialize metrics: {error}"),
+    }
+}
@@ -0,0 +1,514 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::MAX_SIZE,
+    persistence::{Format, PersistenceError},
+    solver::{self, Board},
+    stats::GameRecorded,
+    table::{FrameTint, TableSurface},
+    taquin::{ArrangeTiles, Taquin, MAX_SOLVER_SIZE},
+    tile::{SelectionColor, TileValue},
+    tile_material::ColorFilter,
+    AppState,
+};
+
+/// Folder of the mods, under the assets
+const MODS_FOLDER: &str = "mods";
+pub const MANIFEST_FILE: &str = "mod.ron";
+/// With Shift, M alone mutes the sound, see `sound`
+const SCREEN_KEY: KeyCode = KeyCode::M;
+/// Positions the solver explores per checked challenge before giving up on it
+const CHECK_MAX_NODES: u64 = 50_000_000;
+
+pub struct ModsPlugin;
+
+impl Plugin for ModsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<ApplyTheme>()
+            .init_resource::<Mods>()
+            .add_systems(Update, (
+                (toggle_mods_screen, navigate_mods_screen.run_if(resource_exists::<ModsScreen>())).chain(),
+                apply_theme.run_if(on_event::<ApplyTheme>()),
+                check_goals.run_if(on_event::<GameRecorded>()),
+                update_mods_screen.run_if(resource_exists_and_changed::<ModsScreen>()),
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// `mod.ron` of a mod folder
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ModManifest {
+    pub name: String,
+    pub author: String,
+    pub version: String,
+    #[serde(default)]
+    pub challenges: Vec<Challenge>,
+    #[serde(default)]
+    pub goals: Vec<Goal>,
+    #[serde(default)]
+    pub themes: Vec<Theme>,
+}
+
+/// A given starting position
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Challenge {
+    pub name: String,
+    pub size: i8,
+    /// Row after row, the empty slot being `size * size`
+    pub tiles: Vec<i16>,
+    /// Position to reach instead of the numbers in order, with the empty slot in the last cell as well
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<Vec<i16>>,
+    /// Moves the position must be solvable within, checked by the challenges mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_moves: Option<usize>,
+}
+
+/// Limits to solve within, checked on every solve
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Goal {
+    pub name: String,
+    #[serde(default)]
+    pub size: Option<i8>,
+    #[serde(default)]
+    pub max_moves: Option<usize>,
+    /// Seconds
+    #[serde(default)]
+    pub max_time: Option<f32>,
+}
+
+/// Look of the board, with the names and colors of the command line options
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    #[serde(default)]
+    pub frame_tint: Option<String>,
+    #[serde(default)]
+    pub selection_color: Option<String>,
+    #[serde(default)]
+    pub color_filter: Option<String>,
+    #[serde(default)]
+    pub table: Option<String>,
+}
+
+impl ModManifest {
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let manifest: Self = Format::Ron.parse(contents).map_err(|error| error.to_string())?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("the mod has no name".to_string());
+        }
+        for challenge in &self.challenges {
+            challenge.validate().map_err(|error| format!("challenge {}: {error}", challenge.name))?;
+        }
+        for goal in &self.goals {
+            goal.validate().map_err(|error| format!("goal {}: {error}", goal.name))?;
+        }
+        for theme in &self.themes {
+            theme.validate().map_err(|error| format!("theme {}: {error}", theme.name))?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `tiles` holds each number from 1 to `tiles_nb` once
+fn is_permutation(tiles: &[i16], tiles_nb: usize) -> bool {
+    let mut sorted = tiles.to_vec();
+    sorted.sort();
+    sorted.len() == tiles_nb && sorted.iter().enumerate().all(|(index, value)| *value as usize == index + 1)
+}
+
+impl Challenge {
+    pub fn validate(&self) -> Result<(), String> {
+        // The optimal moves of a challenge come from the solver
+        if !(2..=MAX_SOLVER_SIZE).contains(&self.size) {
+            return Err(format!("size {} is not between 2 and {MAX_SOLVER_SIZE}", self.size));
+        }
+        let tiles_nb = self.size as usize * self.size as usize;
+        if !is_permutation(&self.tiles, tiles_nb) {
+            return Err(format!("the tiles are not the numbers from 1 to {tiles_nb}"));
+        }
+        if let Some(goal) = &self.goal {
+            if !is_permutation(goal, tiles_nb) {
+                return Err(format!("the goal is not the numbers from 1 to {tiles_nb}"));
+            }
+            if goal.last().is_some_and(|value| *value as usize != tiles_nb) {
+                return Err("the goal does not end with the empty slot".to_string());
+            }
+        }
+        let taquin = self.taquin();
+        if taquin.is_solved() {
+            return Err("the board is already solved".to_string());
+        }
+        if !taquin.is_solvable() {
+            return Err("the board cannot be solved".to_string());
+        }
+        Ok(())
+    }
+
+    /// The position renumbered so that the goal becomes the numbers in order, which is what the board is solved into
+    pub fn start_tiles(&self) -> Vec<TileValue> {
+        let Some(goal) = &self.goal else {
+            return self.tiles.iter().map(|value| TileValue(*value)).collect();
+        };
+        let mut renumbered = vec![0; goal.len() + 1];
+        for (index, value) in goal.iter().enumerate() {
+            renumbered[*value as usize] = index as i16 + 1;
+        }
+        self.tiles.iter().map(|value| TileValue(renumbered[*value as usize])).collect()
+    }
+
+    fn taquin(&self) -> Taquin {
+        let mut taquin = Taquin::new(self.size);
+        taquin.set_tiles(self.start_tiles().chunks(self.size as usize).map(|row| row.to_vec()).collect());
+        taquin
+    }
+}
+
+/// Optimal moves of the challenge file `contents`, none when the solver gives up before finding them
+fn check_challenge(contents: &str, max_nodes: u64) -> Result<Option<usize>, String> {
+    let challenge: Challenge = Format::Ron.parse(contents).map_err(|error| error.to_string())?;
+    challenge.validate()?;
+    let board = Board::new(challenge.size as usize, challenge.start_tiles().iter().map(|value| value.0 as u8).collect());
+    match (solver::solve(&board, max_nodes).map(|solution| solution.len()), challenge.max_moves) {
+        (Some(moves), Some(max_moves)) if moves > max_moves => Err(format!("needs {moves} moves, more than the {max_moves} allowed")),
+        (moves, _) => Ok(moves),
+    }
+}
+
+/// Challenges mode: checks every `.ron` challenge file of `folder` and prints a line per file,
+/// returns whether they are all valid
+pub fn check_challenges(folder: &Path) -> Result<bool, PersistenceError> {
+    let mut files = fs::read_dir(folder)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .collect::<Vec<PathBuf>>();
+    files.sort();
+    let mut valid = 0;
+    for path in &files {
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        match fs::read_to_string(path).map_err(|error| error.to_string()).and_then(|contents| check_challenge(&contents, CHECK_MAX_NODES)) {
+            Ok(Some(moves)) => {
+                valid += 1;
+                println!("{name}: valid, solved in {moves} moves");
+            },
+            Ok(None) => {
+                valid += 1;
+                println!("{name}: valid, too hard for the solver to check its moves");
+            },
+            Err(error) => println!("{name}: invalid, {error}"),
+        }
+    }
+    println!("{valid} of {} challenges valid in {}", files.len(), folder.display());
+    Ok(valid == files.len())
+}
+
+impl Goal {
+    fn validate(&self) -> Result<(), String> {
+        if self.max_moves.is_none() && self.max_time.is_none() {
+            return Err("neither max_moves nor max_time is given".to_string());
+        }
+        if self.size.is_some_and(|size| !(2..=MAX_SIZE).contains(&size)) {
+            return Err(format!("size is not between 2 and {MAX_SIZE}"));
+        }
+        Ok(())
+    }
+
+    fn is_met(&self, size: i8, moves: usize, time: f32) -> bool {
+        self.size.is_none_or(|goal_size| goal_size == size)
+            && self.max_moves.is_none_or(|max_moves| moves <= max_moves)
+            && self.max_time.is_none_or(|max_time| time <= max_time)
+    }
+}
+
+impl Theme {
+    fn validate(&self) -> Result<(), String> {
+        for color in [&self.frame_tint, &self.selection_color].into_iter().flatten() {
+            Color::hex(color).map_err(|_| format!("invalid color {color}, expected a color like #8b5a2b"))?;
+        }
+        if let Some(filter) = self.color_filter.as_deref().filter(|filter| ColorFilter::from_name(filter).is_none()) {
+            return Err(format!("unknown color filter {filter}"));
+        }
+        if let Some(table) = self.table.as_deref().filter(|table| TableSurface::from_name(table).is_none()) {
+            return Err(format!("unknown table {table}"));
+        }
+        Ok(())
+    }
+}
+
+/// A folder of the `mods` folder, listed with its error when its manifest is invalid
+#[derive(Debug)]
+pub struct Mod {
+    pub folder: String,
+    pub manifest: Result<ModManifest, String>,
+}
+
+/// Every mod found at startup
+#[derive(Resource, Debug)]
+pub struct Mods {
+    pub mods: Vec<Mod>,
+}
+
+/// Folder holding a folder per mod
+pub fn mods_root() -> PathBuf {
+    FileAssetReader::get_base_path().join("assets").join(MODS_FOLDER)
+}
+
+impl FromWorld for Mods {
+    fn from_world(_world: &mut World) -> Self {
+        Self::discover()
+    }
+}
+
+impl Mods {
+    pub fn discover() -> Self {
+        let root = mods_root();
+        let Ok(entries) = fs::read_dir(&root) else {
+            return Self { mods: Vec::new() };
+        };
+        let mut mods = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_dir())
+            .map(|path| {
+                let folder = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                let manifest = fs::read_to_string(path.join(MANIFEST_FILE))
+                    .map_err(|error| error.to_string())
+                    .and_then(|contents| ModManifest::parse(&contents));
+                if let Err(error) = &manifest {
+                    warn!(folder, "invalid mod: {error}");
+                }
+                Mod { folder, manifest }
+            })
+            .collect::<Vec<Mod>>();
+        mods.sort_by(|a, b| a.folder.cmp(&b.folder));
+        info!(mods = mods.len(), "mods found");
+        Self { mods }
+    }
+
+    fn manifests(&self) -> impl Iterator<Item = &ModManifest> {
+        self.mods.iter().filter_map(|loaded| loaded.manifest.as_ref().ok())
+    }
+
+    /// What `Return` acts on in the mods screen
+    fn entries(&self) -> Vec<Entry<'_>> {
+        self.manifests().flat_map(|manifest| {
+            manifest.challenges.iter().map(Entry::Challenge).chain(manifest.themes.iter().map(Entry::Theme))
+        }).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Entry<'a> {
+    Challenge(&'a Challenge),
+    Theme(&'a Theme),
+}
+
+#[derive(Event)]
+struct ApplyTheme(Theme);
+
+/// Lists the mods, `Tab` moves the highlight over the challenges and themes, `Return` starts or applies it
+#[derive(Resource)]
+struct ModsScreen {
+    entity: Entity,
+    highlighted: usize,
+    /// Goals met this session
+    met_goals: Vec<String>,
+}
+
+#[derive(Component)]
+struct ModsText;
+
+fn toggle_mods_screen(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    screen: Option<Res<ModsScreen>>,
+) {
+    if !keyboard_input.just_released(SCREEN_KEY) || !keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        return;
+    }
+    if let Some(screen) = screen {
+        commands.entity(screen.entity).despawn_recursive();
+        commands.remove_resource::<ModsScreen>();
+        return;
+    }
+    let entity = commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.),
+            top: Val::Px(120.),
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(5),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), ModsText));
+    }).id();
+    commands.insert_resource(ModsScreen { entity, highlighted: 0, met_goals: Vec::new() });
+}
+
+fn navigate_mods_screen(
+    keyboard_input: Res<Input<KeyCode>>,
+    mods: Res<Mods>,
+    taquin: Res<Taquin>,
+    mut screen: ResMut<ModsScreen>,
+    mut arrange_events: EventWriter<ArrangeTiles>,
+    mut theme_events: EventWriter<ApplyTheme>,
+) {
+    let entries = mods.entries();
+    if entries.is_empty() {
+        return;
+    }
+    if keyboard_input.just_released(KeyCode::Tab) {
+        screen.highlighted = (screen.highlighted + 1) % entries.len();
+    }
+    if !keyboard_input.just_released(KeyCode::Return) {
+        return;
+    }
+    match entries[screen.highlighted] {
+        Entry::Challenge(challenge) if !taquin.is_square() || challenge.size != taquin.width => {
+            warn!(challenge = challenge.name, size = challenge.size, "challenge made for another board size");
+        },
+        Entry::Challenge(challenge) => {
+            info!(challenge = challenge.name, "challenge started");
+            arrange_events.send(ArrangeTiles(challenge.start_tiles()));
+        },
+        Entry::Theme(theme) => theme_events.send(ApplyTheme(theme.clone())),
+    }
+}
+
+fn apply_theme(
+    mut theme_events: EventReader<ApplyTheme>,
+    mut frame_tint: ResMut<FrameTint>,
+    mut selection_color: ResMut<SelectionColor>,
+    mut color_filter: ResMut<ColorFilter>,
+    mut table: ResMut<TableSurface>,
+) {
+    for ApplyTheme(theme) in theme_events.read() {
+        if let Some(color) = theme.frame_tint.as_deref().and_then(|color| Color::hex(color).ok()) {
+            frame_tint.0 = color;
+        }
+        if let Some(color) = theme.selection_color.as_deref().and_then(|color| Color::hex(color).ok()) {
+            selection_color.0 = color;
+        }
+        if let Some(filter) = theme.color_filter.as_deref().and_then(ColorFilter::from_name) {
+            *color_filter = filter;
+        }
+        if let Some(surface) = theme.table.as_deref().and_then(TableSurface::from_name) {
+            *table = surface;
+        }
+        info!(theme = theme.name, "theme applied");
+    }
+}
+
+fn check_goals(
+    mods: Res<Mods>,
+    mut recorded_events: EventReader<GameRecorded>,
+    screen: Option<ResMut<ModsScreen>>,
+) {
+    let mut met = Vec::new();
+    for GameRecorded(game) in recorded_events.read() {
+        for goal in mods.manifests().flat_map(|manifest| &manifest.goals) {
+            if goal.is_met(game.size, game.score(), game.time) {
+                info!(goal = goal.name, "goal met");
+                met.push(goal.name.clone());
+            }
+        }
+    }
+    if let Some(mut screen) = screen.filter(|_| !met.is_empty()) {
+        screen.met_goals.extend(met);
+    }
+}
+
+fn update_mods_screen(
+    mods: Res<Mods>,
+    screen: Res<ModsScreen>,
+    mut text_query: Query<&mut Text, With<ModsText>>,
+) {
+    let mut lines = Vec::new();
+    let mut entry = 0;
+    let mut cursor = || {
+        entry += 1;
+        if entry - 1 == screen.highlighted { ">" } else { " " }
+    };
+    for loaded in &mods.mods {
+        let manifest = match &loaded.manifest {
+            Ok(manifest) => manifest,
+            Err(error) => {
+                lines.push(format!("{}: invalid, {error}", loaded.folder));
+                continue;
+            },
+        };
+        lines.push(format!("{} {} by {}", manifest.name, manifest.version, manifest.author));
+        for challenge in &manifest.challenges {
+            lines.push(format!("{} challenge {}, {}x{}", cursor(), challenge.name, challenge.size, challenge.size));
+        }
+        for theme in &manifest.themes {
+            lines.push(format!("{} theme {}", cursor(), theme.name));
+        }
+        for goal in &manifest.goals {
+            let status = if screen.met_goals.contains(&goal.name) { ", met" } else { "" };
+            lines.push(format!("  goal {}{status}", goal.name));
+        }
+    }
+    if mods.mods.is_empty() {
+        lines.push(format!("No mod, add folders with a {MANIFEST_FILE} to assets/{MODS_FOLDER}"));
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Mods (Tab, Return)\n{}", lines.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mods::{check_challenge, ModManifest}, tile::TileValue};
+
+    #[test]
+    fn test_mod_manifest() {
+        let manifest = ModManifest::parse(r##"(
+            name: "Classics",
+            author: "Someone",
+            version: "1.0",
+            challenges: [(name: "Swapped corner", size: 3, tiles: [1, 2, 3, 4, 5, 6, 7, 9, 8])],
+            goals: [(name: "Quick 3x3", size: Some(3), max_time: Some(30.))],
+            themes: [(name: "Night", frame_tint: Some("#202040"), color_filter: Some("invert"))],
+        )"##).unwrap();
+        assert_eq!((manifest.challenges.len(), manifest.goals.len(), manifest.themes.len()), (1, 1, 1));
+        assert!(manifest.goals[0].is_met(3, 100, 20.) && !manifest.goals[0].is_met(4, 100, 20.));
+
+        let unsolvable = r#"(name: "Bad", author: "Someone", version: "1.0", challenges: [(name: "Sam Loyd", size: 3, tiles: [2, 1, 3, 4, 5, 6, 7, 8, 9])])"#;
+        assert!(ModManifest::parse(unsolvable).is_err());
+        let reversed_goal = r#"(name: "Good", author: "Someone", version: "1.0", challenges: [(name: "Back", size: 2, tiles: [1, 3, 2, 4], goal: Some([3, 2, 1, 4]))])"#;
+        let reversed_goal = ModManifest::parse(reversed_goal).unwrap();
+        assert_eq!(reversed_goal.challenges[0].start_tiles(), [3, 1, 2, 4].map(TileValue));
+        let missing_tile = r#"(name: "Bad", author: "Someone", version: "1.0", challenges: [(name: "Short", size: 2, tiles: [1, 2, 3])])"#;
+        assert!(ModManifest::parse(missing_tile).is_err());
+        let bad_theme = r#"(name: "Bad", author: "Someone", version: "1.0", themes: [(name: "Neon", table: Some("glass"))])"#;
+        assert!(ModManifest::parse(bad_theme).is_err());
+        assert!(ModManifest::parse(include_str!("../assets/mods/classics/mod.ron")).is_ok());
+    }
+
+    #[test]
+    fn test_check_challenge() {
+        assert_eq!(check_challenge(r#"(name: "Corner", size: 3, tiles: [1, 2, 3, 4, 5, 6, 7, 9, 8], max_moves: Some(1))"#, 1000), Ok(Some(1)));
+        assert!(check_challenge(r#"(name: "Par", size: 3, tiles: [1, 2, 3, 4, 5, 9, 7, 8, 6], max_moves: Some(0))"#, 1000).is_err());
+        assert!(check_challenge(r#"(name: "Loyd", size: 3, tiles: [2, 1, 3, 4, 5, 6, 7, 8, 9])"#, 1000).is_err());
+        assert!(check_challenge("(name: \"Broken\", size: 3)", 1000).is_err());
+    }
+}
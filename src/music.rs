@@ -0,0 +1,342 @@
+use std::any::TypeId;
+
+use bevy::{
+    asset::{LoadState, LoadedFolder},
+    audio::{Decodable, PlaybackMode, Source, Volume},
+    prelude::*,
+    tasks::{block_on, AsyncComputeTaskPool, Task},
+};
+
+use crate::{
+    pause::PauseMenu,
+    sound::AudioSettings,
+    taquin::TaquinSolved,
+};
+
+/// Under the assets, every sound file in it is a track
+const MUSIC_FOLDER: &str = "sounds/music";
+/// Seconds the next track takes to fade in while the previous one fades out
+const CROSSFADE: f32 = 3.;
+/// Music volume while the tada plays, held then released back to full
+const DUCK_VOLUME: f32 = 0.3;
+const DUCK_HOLD: f32 = 1.5;
+const DUCK_RELEASE: f32 = 1.;
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Playlist>()
+            .add_systems(Startup, load_music)
+            .add_systems(Update, (
+                collect_tracks.run_if(resource_exists::<MusicFolder>()),
+                duck_music.run_if(on_event::<TaquinSolved>()),
+                measure_tracks,
+                advance_playlist,
+                fade_music,
+            ).chain())
+            .add_systems(Update, (
+                add_music_controls,
+                press_music_buttons,
+                update_music_controls.run_if(any_with_component::<MusicText>()),
+            ).chain());
+    }
+}
+
+#[derive(Resource)]
+struct MusicFolder(Handle<LoadedFolder>);
+
+struct Track {
+    name: String,
+    source: Handle<AudioSource>,
+}
+
+/// The tracks of the music folder, played one after the other and from the first again
+#[derive(Resource, Default)]
+struct Playlist {
+    tracks: Vec<Track>,
+    /// Track playing, the next one fades in as it nears its end
+    current: usize,
+    paused: bool,
+    /// Asks the track playing to make way for the next one
+    skip: bool,
+    /// Real time the tada last played
+    ducked_at: Option<f32>,
+}
+
+/// A track playing, two of them while they crossfade
+#[derive(Component)]
+struct MusicTrack {
+    /// Seconds played, the pauses left out
+    played: f32,
+    /// Seconds of the track, once measured
+    length: Option<f32>,
+    /// Seconds since the next track started, it fades out meanwhile
+    fading_out: Option<f32>,
+}
+
+/// The track is decoded once on the side to know its length, the audio files do not tell it
+#[derive(Component)]
+struct TrackLength(Task<f32>);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum MusicButton {
+    Next,
+    Pause,
+}
+
+#[derive(Component)]
+struct MusicText;
+
+/// Volume of a track against its full volume, rising as it starts and falling as the next one comes in
+fn fade_gain(played: f32, fading_out: Option<f32>) -> f32 {
+    let fade_in = (played / CROSSFADE).min(1.);
+    let fade_out = fading_out.map_or(1., |since| 1. - (since / CROSSFADE).min(1.));
+    fade_in * fade_out
+}
+
+/// Volume of the music `since` seconds after the tada, back to full after its hold and release
+fn duck_gain(since: Option<f32>) -> f32 {
+    let Some(since) = since else {
+        return 1.;
+    };
+    let release = ((since - DUCK_HOLD) / DUCK_RELEASE).clamp(0., 1.);
+    DUCK_VOLUME + (1. - DUCK_VOLUME) * release
+}
+
+fn load_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    commands.insert_resource(MusicFolder(asset_server.load_folder(MUSIC_FOLDER)));
+}
+
+/// The playlist is in file name order, once every track is loaded
+fn collect_tracks(
+    mut commands: Commands,
+    folder: Res<MusicFolder>,
+    asset_server: Res<AssetServer>,
+    folders: Res<Assets<LoadedFolder>>,
+    mut playlist: ResMut<Playlist>,
+) {
+    if asset_server.get_load_state(&folder.0) == Some(LoadState::Failed) {
+        info!("no music in {MUSIC_FOLDER}");
+        commands.remove_resource::<MusicFolder>();
+        return;
+    }
+    let Some(loaded) = folders.get(&folder.0).filter(|_| asset_server.is_loaded_with_dependencies(&folder.0)) else {
+        return;
+    };
+    let mut tracks = loaded.handles.iter()
+        .filter(|handle| handle.type_id() == TypeId::of::<AudioSource>())
+        .map(|handle| Track {
+            name: asset_server.get_path(handle.id())
+                .and_then(|path| path.path().file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+                .unwrap_or_default(),
+            source: handle.clone().typed::<AudioSource>(),
+        })
+        .collect::<Vec<Track>>();
+    tracks.sort_by(|a, b| a.name.cmp(&b.name));
+    info!(tracks = tracks.len(), "music loaded");
+    playlist.tracks = tracks;
+    commands.remove_resource::<MusicFolder>();
+}
+
+fn duck_music(
+    mut playlist: ResMut<Playlist>,
+    time: Res<Time<Real>>,
+) {
+    playlist.ducked_at = Some(time.elapsed_seconds());
+}
+
+fn measure_tracks(
+    mut commands: Commands,
+    mut track_query: Query<(Entity, &mut MusicTrack, &mut TrackLength)>,
+) {
+    for (entity, mut track, mut length) in track_query.iter_mut() {
+        if !length.0.is_finished() {
+            continue;
+        }
+        track.length = Some(block_on(&mut length.0));
+        commands.entity(entity).remove::<TrackLength>();
+    }
+}
+
+/// The next track starts as the one playing nears its end, or right away when skipped
+fn advance_playlist(
+    mut commands: Commands,
+    mut playlist: ResMut<Playlist>,
+    mut track_query: Query<(&mut MusicTrack, Option<&AudioSink>)>,
+    sources: Res<Assets<AudioSource>>,
+) {
+    if playlist.tracks.is_empty() || playlist.paused {
+        return;
+    }
+    let playing = track_query.iter_mut().find(|(track, _)| track.fading_out.is_none());
+    if let Some((mut track, sink)) = playing {
+        let ending = track.length.is_some_and(|length| track.played >= length - CROSSFADE) || sink.is_some_and(|sink| sink.empty());
+        if !ending && !playlist.skip {
+            return;
+        }
+        track.fading_out = Some(0.);
+        playlist.skip = false;
+        playlist.current = (playlist.current + 1) % playlist.tracks.len();
+    }
+
+    let source = playlist.tracks[playlist.current].source.clone();
+    let mut entity = commands.spawn((AudioBundle {
+        source: source.clone(),
+        settings: PlaybackSettings { mode: PlaybackMode::Once, volume: Volume::new_absolute(0.), ..default() },
+    }, MusicTrack { played: 0., length: None, fading_out: None }));
+    if let Some(audio) = sources.get(&source).cloned() {
+        entity.insert(TrackLength(AsyncComputeTaskPool::get().spawn(async move {
+            let decoder = audio.decoder();
+            let samples_per_second = decoder.sample_rate() as f32 * decoder.channels() as f32;
+            decoder.count() as f32 / samples_per_second
+        })));
+    }
+}
+
+/// The volume of the sinks is set each frame, the audio settings and the mute apply to the tracks playing
+fn fade_music(
+    mut commands: Commands,
+    playlist: Res<Playlist>,
+    settings: Res<AudioSettings>,
+    time: Res<Time<Real>>,
+    mut track_query: Query<(Entity, &mut MusicTrack, Option<&AudioSink>)>,
+) {
+    let duck = duck_gain(playlist.ducked_at.map(|ducked_at| time.elapsed_seconds() - ducked_at));
+    for (entity, mut track, sink) in track_query.iter_mut() {
+        let Some(sink) = sink else {
+            continue;
+        };
+        if playlist.paused {
+            sink.pause();
+            continue;
+        }
+        if sink.is_paused() {
+            sink.play();
+        }
+        track.played += time.delta_seconds();
+        if let Some(fading_out) = track.fading_out.as_mut() {
+            *fading_out += time.delta_seconds();
+            if *fading_out >= CROSSFADE {
+                commands.entity(entity).despawn();
+                continue;
+            }
+        }
+        sink.set_volume(fade_gain(track.played, track.fading_out) * duck * settings.music * settings.master_volume());
+    }
+}
+
+/// Next track and pause buttons under the ones of the pause overlay, when there is music
+fn add_music_controls(
+    mut commands: Commands,
+    playlist: Res<Playlist>,
+    menu_query: Query<Entity, Added<PauseMenu>>,
+) {
+    if playlist.tracks.is_empty() {
+        return;
+    }
+    for menu in menu_query.iter() {
+        commands.entity(menu).with_children(|parent| {
+            parent.spawn((TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 30.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ), MusicText));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(20.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for button in [MusicButton::Next, MusicButton::Pause] {
+                        parent.spawn((ButtonBundle {
+                            style: Style {
+                                width: Val::Px(140.),
+                                padding: UiRect::axes(Val::Px(10.), Val::Px(6.)),
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                            ..default()
+                        }, button)).with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font_size: 30.0,
+                                    color: Color::WHITE,
+                                    ..default()
+                                },
+                            ));
+                        });
+                    }
+                });
+        });
+    }
+}
+
+fn press_music_buttons(
+    button_query: Query<(&Interaction, &MusicButton), Changed<Interaction>>,
+    mut playlist: ResMut<Playlist>,
+) {
+    for (_, button) in button_query.iter().filter(|(interaction, _)| **interaction == Interaction::Pressed) {
+        match button {
+            MusicButton::Next => playlist.skip = true,
+            MusicButton::Pause => playlist.paused = !playlist.paused,
+        }
+        info!(?button, paused = playlist.paused, "music");
+    }
+}
+
+fn update_music_controls(
+    playlist: Res<Playlist>,
+    mut music_text_query: Query<&mut Text, With<MusicText>>,
+    button_query: Query<(&MusicButton, &Children)>,
+    mut text_query: Query<&mut Text, Without<MusicText>>,
+) {
+    let Some(track) = playlist.tracks.get(playlist.current) else {
+        return;
+    };
+    for mut text in music_text_query.iter_mut() {
+        text.sections[0].value = format!("Music: {}", track.name);
+    }
+    for (button, children) in button_query.iter() {
+        let label = match button {
+            MusicButton::Next => "Next",
+            MusicButton::Pause if playlist.paused => "Play",
+            MusicButton::Pause => "Pause",
+        };
+        let mut texts = text_query.iter_many_mut(children.iter());
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = label.to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::music::{duck_gain, fade_gain, CROSSFADE, DUCK_HOLD, DUCK_RELEASE, DUCK_VOLUME};
+
+    #[test]
+    fn test_gains() {
+        assert_eq!(fade_gain(0., None), 0.);
+        assert_eq!(fade_gain(CROSSFADE / 2., None), 0.5);
+        assert_eq!(fade_gain(60., None), 1.);
+        assert_eq!(fade_gain(60., Some(CROSSFADE / 2.)), 0.5);
+        assert_eq!(fade_gain(60., Some(CROSSFADE)), 0.);
+
+        assert_eq!(duck_gain(None), 1.);
+        assert_eq!(duck_gain(Some(0.)), DUCK_VOLUME);
+        assert_eq!(duck_gain(Some(DUCK_HOLD)), DUCK_VOLUME);
+        assert_eq!(duck_gain(Some(DUCK_HOLD + DUCK_RELEASE)), 1.);
+    }
+}
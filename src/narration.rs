@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+use tts::Tts;
+
+use crate::{
+    taquin::{Taquin, TaquinShuffled, TaquinSolved, TileMoved},
+    tile::{TileSelected, TileValue},
+    AppState,
+};
+
+const RATE_STEP: f32 = 0.1;
+
+pub struct NarrationPlugin;
+
+impl Plugin for NarrationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<NarrationSettings>()
+            .add_event::<Narrate>()
+            .add_systems(Startup, setup_tts)
+            .add_systems(Update, (
+                narrate_shuffle.run_if(on_event::<TaquinShuffled>()),
+                narrate_moves.run_if(on_event::<TileMoved>()),
+                narrate_selection,
+                narrate_solved.run_if(on_event::<TaquinSolved>()),
+            ).run_if(in_state(AppState::Running)))
+            .add_systems(Update, (narration_controls, speak).chain());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Shuffle, completed rows and solve only
+    Milestones,
+    /// Milestones and every move
+    Moves,
+    /// Moves and selection changes
+    Verbose,
+}
+
+impl Verbosity {
+    fn next(self) -> Self {
+        match self {
+            Verbosity::Milestones => Verbosity::Moves,
+            Verbosity::Moves => Verbosity::Verbose,
+            Verbosity::Verbose => Verbosity::Milestones,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct NarrationSettings {
+    /// Speech rate between 0 (slowest) and 1 (fastest) of the backend range
+    pub rate: f32,
+    pub verbosity: Verbosity,
+}
+
+impl Default for NarrationSettings {
+    fn default() -> Self {
+        Self { rate: 0.5, verbosity: Verbosity::Moves }
+    }
+}
+
+/// A sentence to be spoken, interrupting the previous one
+#[derive(Event, Debug, Clone)]
+pub struct Narrate(pub String);
+
+/// Up to the 2500 tiles of the largest mega board
+pub fn number_to_words(number: i16) -> String {
+    const UNITS: [&str; 20] = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+    ];
+    const TENS: [&str; 10] = ["", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+
+    let number = number.unsigned_abs() as usize;
+    match number {
+        0..=19 => UNITS[number].to_string(),
+        20..=99 if number.is_multiple_of(10) => TENS[number / 10].to_string(),
+        20..=99 => format!("{} {}", TENS[number / 10], UNITS[number % 10]),
+        100..=999 if number.is_multiple_of(100) => format!("{} hundred", UNITS[number / 100]),
+        100..=999 => format!("{} hundred {}", UNITS[number / 100], number_to_words((number % 100) as i16)),
+        _ if number.is_multiple_of(1000) => format!("{} thousand", number_to_words((number / 1000) as i16)),
+        _ => format!("{} thousand {}", number_to_words((number / 1000) as i16), number_to_words((number % 1000) as i16)),
+    }
+}
+
+fn direction_name(event: &TileMoved) -> &'static str {
+    match (event.to.i - event.from.i, event.to.j - event.from.j) {
+        (-1, 0) => "left",
+        (1, 0) => "right",
+        (0, -1) => "up",
+        _ => "down",
+    }
+}
+
+/// Number of rows in place from the top of the board
+fn solved_rows(taquin: &Taquin) -> usize {
+    (0..taquin.height as usize).take_while(|row| taquin.is_row_solved(*row)).count()
+}
+
+fn setup_tts(world: &mut World) {
+    match Tts::default() {
+        Ok(tts) => world.insert_non_send_resource(tts),
+        Err(error) => error!("text to speech is unavailable: {error}"),
+    }
+}
+
+fn narrate_shuffle(
+    mut narrate_events: EventWriter<Narrate>,
+) {
+    narrate_events.send(Narrate("puzzle shuffled".to_string()));
+}
+
+fn narrate_moves(
+    taquin: Res<Taquin>,
+    settings: Res<NarrationSettings>,
+    mut tile_moved_events: EventReader<TileMoved>,
+    mut narrate_events: EventWriter<Narrate>,
+    mut announced_rows: Local<usize>,
+) {
+    for event in tile_moved_events.read() {
+        if settings.verbosity >= Verbosity::Moves {
+            let value = taquin.tiles[event.to.j as usize][event.to.i as usize];
+            narrate_events.send(Narrate(format!("tile {} {}", number_to_words(value.0), direction_name(event))));
+        }
+    }
+
+    let rows = solved_rows(&taquin);
+    if rows > *announced_rows && rows < taquin.height as usize {
+        narrate_events.send(Narrate(format!("{} {} complete", number_to_words(rows as i16), if rows == 1 { "row" } else { "rows" })));
+    }
+    *announced_rows = rows;
+}
+
+fn narrate_selection(
+    settings: Res<NarrationSettings>,
+    selected_query: Query<&TileValue, Added<TileSelected>>,
+    mut narrate_events: EventWriter<Narrate>,
+) {
+    if settings.verbosity < Verbosity::Verbose {
+        return;
+    }
+    for value in selected_query.iter() {
+        narrate_events.send(Narrate(format!("tile {} selected", number_to_words(value.0))));
+    }
+}
+
+fn narrate_solved(
+    mut narrate_events: EventWriter<Narrate>,
+) {
+    narrate_events.send(Narrate("puzzle solved".to_string()));
+}
+
+fn narration_controls(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: ResMut<NarrationSettings>,
+    mut narrate_events: EventWriter<Narrate>,
+) {
+    if keyboard_input.just_released(KeyCode::BracketLeft) {
+        settings.rate = (settings.rate - RATE_STEP).max(0.);
+        narrate_events.send(Narrate("slower".to_string()));
+    }
+    if keyboard_input.just_released(KeyCode::BracketRight) {
+        settings.rate = (settings.rate + RATE_STEP).min(1.);
+        narrate_events.send(Narrate("faster".to_string()));
+    }
+    // Ctrl+V pastes a board code, see `board_code`
+    if keyboard_input.just_released(KeyCode::V) && !keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        settings.verbosity = settings.verbosity.next();
+        narrate_events.send(Narrate(format!("{:?} verbosity", settings.verbosity).to_lowercase()));
+    }
+}
+
+fn speak(
+    tts: Option<NonSendMut<Tts>>,
+    settings: Res<NarrationSettings>,
+    mut narrate_events: EventReader<Narrate>,
+) {
+    let Some(mut tts) = tts else {
+        narrate_events.clear();
+        return;
+    };
+    if settings.is_changed() {
+        let rate = tts.min_rate() + (tts.max_rate() - tts.min_rate()) * settings.rate;
+        if let Err(error) = tts.set_rate(rate) {
+            warn!("cannot change speech rate: {error}");
+        }
+    }
+    let sentences = narrate_events.read().map(|Narrate(sentence)| sentence.as_str()).collect::<Vec<&str>>();
+    if sentences.is_empty() {
+        return;
+    }
+    if let Err(error) = tts.speak(sentences.join(", "), true) {
+        warn!("cannot speak: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::narration::number_to_words;
+
+    #[test]
+    fn test_number_to_words() {
+        assert_eq!(number_to_words(7), "seven");
+        assert_eq!(number_to_words(40), "forty");
+        assert_eq!(number_to_words(63), "sixty three");
+        assert_eq!(number_to_words(121), "one hundred twenty one");
+        assert_eq!(number_to_words(2500), "two thousand five hundred");
+    }
+}
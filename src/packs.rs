@@ -0,0 +1,344 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    cli::MAX_SIZE,
+    gallery::Gallery,
+    persistence::Format,
+    taquin::Taquin,
+    tile_bake::BakedTileTextures,
+    tile_material::{SharedTileMaterial, TileMaterial},
+    AppState, TaquinSprites,
+};
+
+/// Folder of the packs, under the assets
+const PACKS_FOLDER: &str = "packs";
+pub const MANIFEST_FILE: &str = "pack.ron";
+/// Pack shown first and picked when none is asked for
+const DEFAULT_PACK: &str = "default";
+const BROWSER_KEY: KeyCode = KeyCode::P;
+
+pub struct PacksPlugin;
+
+impl Plugin for PacksPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<PackApplied>()
+            .add_systems(Update, (
+                (toggle_pack_browser, navigate_pack_browser.run_if(resource_exists::<PackBrowser>())).chain(),
+                update_pack_browser.run_if(resource_exists_and_changed::<PackBrowser>()),
+            ).chain().run_if(in_state(AppState::Running)))
+            .add_systems(Update, (
+                load_selected_pack.run_if(resource_changed::<TexturePacks>()),
+                apply_loaded_pack,
+                apply_tile_border,
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// Sent once the tiles show the pictures of a newly selected pack
+#[derive(Event, Default)]
+pub struct PackApplied;
+
+/// `pack.ron` of a pack folder
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct PackManifest {
+    pub name: String,
+    pub author: String,
+    /// Pictures of the pack, relative to its folder, `T` cycles through them
+    pub images: Vec<String>,
+    /// Board sizes the pictures are cut best for, the first one is the default size
+    #[serde(default)]
+    pub sizes: Vec<i8>,
+    #[serde(default)]
+    pub border: Option<TileBorder>,
+}
+
+/// Line drawn along the edges of every tile
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct TileBorder {
+    /// Fraction of the tile side
+    pub width: f32,
+    /// As #rrggbb
+    pub color: String,
+}
+
+impl PackManifest {
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let manifest: Self = Format::Ron.parse(contents).map_err(|error| error.to_string())?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("the pack has no name".to_string());
+        }
+        if self.images.is_empty() {
+            return Err("the pack has no image".to_string());
+        }
+        if let Some(size) = self.sizes.iter().find(|size| !(2..=MAX_SIZE).contains(*size)) {
+            return Err(format!("size {size} is not between 2 and {MAX_SIZE}"));
+        }
+        if let Some(border) = &self.border {
+            if !(border.width > 0. && border.width < 0.5) {
+                return Err(format!("border width {} is not between 0 and 0.5", border.width));
+            }
+            Color::hex(&border.color).map_err(|_| format!("invalid border color {}, expected a color like #202020", border.color))?;
+        }
+        Ok(())
+    }
+
+    fn border_color(&self) -> Option<Color> {
+        self.border.as_ref().and_then(|border| Color::hex(&border.color).ok())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TexturePack {
+    /// Name of the pack folder, what `--pack` expects
+    pub folder: String,
+    pub manifest: PackManifest,
+}
+
+impl TexturePack {
+    /// Reads and checks the manifest, and that every image is there
+    fn load(folder: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(folder.join(MANIFEST_FILE)).map_err(|error| error.to_string())?;
+        let manifest = PackManifest::parse(&contents)?;
+        if let Some(image) = manifest.images.iter().find(|image| !folder.join(image).is_file()) {
+            return Err(format!("missing image {image}"));
+        }
+        let folder = folder.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        Ok(Self { folder, manifest })
+    }
+
+    /// Paths of the images for the asset server
+    pub fn image_paths(&self) -> impl Iterator<Item = String> + '_ {
+        self.manifest.images.iter().map(|image| format!("{PACKS_FOLDER}/{}/{image}", self.folder))
+    }
+
+    pub fn preferred_size(&self) -> Option<i8> {
+        self.manifest.sizes.first().copied()
+    }
+}
+
+/// Folder holding a folder per pack
+pub fn packs_root() -> PathBuf {
+    FileAssetReader::get_base_path().join("assets").join(PACKS_FOLDER)
+}
+
+/// Every valid pack found in the `packs` folder of the assets, and the one in use
+#[derive(Resource, Debug)]
+pub struct TexturePacks {
+    pub packs: Vec<TexturePack>,
+    pub selected: usize,
+}
+
+impl TexturePacks {
+    /// Finds the packs and selects `name`, or the default pack
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn discover(name: Option<&str>) -> Result<Self, String> {
+        let root = packs_root();
+        let entries = fs::read_dir(&root).map_err(|error| format!("{}: {error}", root.display()))?;
+        let mut packs = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_dir())
+            .filter_map(|path| TexturePack::load(&path)
+                .inspect_err(|error| warn!(pack = %path.display(), "ignored texture pack: {error}"))
+                .ok())
+            .collect::<Vec<TexturePack>>();
+        packs.sort_by(|a, b| (a.folder != DEFAULT_PACK, &a.folder).cmp(&(b.folder != DEFAULT_PACK, &b.folder)));
+        let name = name.unwrap_or(DEFAULT_PACK);
+        let selected = packs.iter().position(|pack| pack.folder == name).ok_or_else(|| {
+            let known = packs.iter().map(|pack| pack.folder.as_str()).collect::<Vec<&str>>();
+            format!("unknown texture pack {name}, expected one of {}", known.join(", "))
+        })?;
+        info!(packs = packs.len(), pack = name, "texture packs found");
+        Ok(Self { packs, selected })
+    }
+
+    /// A browser cannot list the assets, the web build only has the default pack, its manifest built in
+    #[cfg(target_arch = "wasm32")]
+    pub fn discover(name: Option<&str>) -> Result<Self, String> {
+        if let Some(name) = name.filter(|name| *name != DEFAULT_PACK) {
+            return Err(format!("unknown texture pack {name}, the web build only has the {DEFAULT_PACK} pack"));
+        }
+        let manifest = PackManifest::parse(include_str!("../assets/packs/default/pack.ron"))?;
+        Ok(Self { packs: vec![TexturePack { folder: DEFAULT_PACK.to_string(), manifest }], selected: 0 })
+    }
+
+    pub fn selected(&self) -> &TexturePack {
+        &self.packs[self.selected]
+    }
+}
+
+/// Lists the packs, `Tab` moves the highlight and `Return` switches to the highlighted one
+#[derive(Resource)]
+struct PackBrowser {
+    entity: Entity,
+    highlighted: usize,
+}
+
+#[derive(Component)]
+struct PackBrowserText;
+
+fn toggle_pack_browser(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    packs: Res<TexturePacks>,
+    browser: Option<Res<PackBrowser>>,
+) {
+    // With Shift it shows the picture preview, see `gui`
+    if !keyboard_input.just_released(BROWSER_KEY) || keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        return;
+    }
+    if let Some(browser) = browser {
+        commands.entity(browser.entity).despawn_recursive();
+        commands.remove_resource::<PackBrowser>();
+        return;
+    }
+    let entity = commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.),
+            top: Val::Px(120.),
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(5),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), PackBrowserText));
+    }).id();
+    commands.insert_resource(PackBrowser { entity, highlighted: packs.selected });
+}
+
+fn navigate_pack_browser(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut browser: ResMut<PackBrowser>,
+    mut packs: ResMut<TexturePacks>,
+) {
+    if keyboard_input.just_released(KeyCode::Tab) {
+        browser.highlighted = (browser.highlighted + 1) % packs.packs.len();
+    }
+    if keyboard_input.just_released(KeyCode::Return) && browser.highlighted != packs.selected {
+        packs.selected = browser.highlighted;
+        // Redraws the list with the new pack in use
+        browser.set_changed();
+    }
+}
+
+fn update_pack_browser(
+    browser: Res<PackBrowser>,
+    packs: Res<TexturePacks>,
+    mut text_query: Query<&mut Text, With<PackBrowserText>>,
+) {
+    let lines = packs.packs.iter().enumerate().map(|(index, pack)| {
+        let cursor = if index == browser.highlighted { ">" } else { " " };
+        let in_use = if index == packs.selected { ", in use" } else { "" };
+        let sizes = pack.manifest.sizes.iter().map(|size| format!("{size}x{size}")).collect::<Vec<String>>();
+        let sizes = if sizes.is_empty() { String::new() } else { format!(", best in {}", sizes.join(" ")) };
+        format!("{cursor} {} by {}, {} images{sizes}{in_use}", pack.manifest.name, pack.manifest.author, pack.manifest.images.len())
+    }).collect::<Vec<String>>();
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Texture packs (Tab, Return)\n{}", lines.join("\n"));
+    }
+}
+
+/// Starts loading the pictures of the pack chosen in the browser, the gallery ones stay after them
+fn load_selected_pack(
+    asset_server: Res<AssetServer>,
+    packs: Res<TexturePacks>,
+    gallery: Res<Gallery>,
+    mut sprites: ResMut<TaquinSprites>,
+    mut pending: Local<bool>,
+) {
+    // Skips the pictures already loaded by the setup
+    if !*pending {
+        *pending = true;
+        return;
+    }
+    sprites.images = packs.selected().image_paths().map(|path| asset_server.load(path)).chain(gallery.images.iter().cloned()).collect();
+    sprites.applied = false;
+}
+
+/// Puts the first picture of the pack on the tiles once every picture is loaded
+fn apply_loaded_pack(
+    asset_server: Res<AssetServer>,
+    mut sprites: ResMut<TaquinSprites>,
+    mut materials: ResMut<Assets<TileMaterial>>,
+    shared_material: Res<SharedTileMaterial>,
+    mut applied_events: EventWriter<PackApplied>,
+) {
+    if sprites.applied || !sprites.is_loaded(&asset_server) {
+        return;
+    }
+    sprites.applied = true;
+    // Baked tiles are baked again from it on `PackApplied`
+    if let Some(material) = materials.get_mut(&shared_material.0) {
+        material.base.base_color_texture = sprites.images.first().cloned();
+    }
+    applied_events.send_default();
+}
+
+/// Borders of the pack on the tiles of a new board, or on every tile when the pack changes
+fn apply_tile_border(
+    packs: Res<TexturePacks>,
+    taquin: Res<Taquin>,
+    mut materials: ResMut<Assets<TileMaterial>>,
+    shared_material: Res<SharedTileMaterial>,
+    spawned_query: Query<(), Added<Handle<TileMaterial>>>,
+    baked_query: Query<&Handle<TileMaterial>, With<BakedTileTextures>>,
+) {
+    if !packs.is_changed() && spawned_query.is_empty() {
+        return;
+    }
+    let manifest = &packs.selected().manifest;
+    let width = manifest.border.as_ref().map_or(0., |border| border.width);
+    let color = manifest.border_color().unwrap_or(Color::NONE);
+    // Baked tiles show their own picture, the others a window on the whole one
+    let handles = std::iter::once((&shared_material.0, Vec2::new(taquin.width as f32, taquin.height as f32)))
+        .chain(baked_query.iter().map(|handle| (handle, Vec2::ONE)));
+    for (handle, grid) in handles {
+        if let Some(material) = materials.get_mut(handle) {
+            material.extension.border_width = width;
+            material.extension.border_color = color.as_linear_rgba_f32().into();
+            material.extension.border_grid = grid;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packs::PackManifest;
+
+    #[test]
+    fn test_pack_manifest() {
+        let manifest = PackManifest::parse(r##"(
+            name: "Mountains",
+            author: "Someone",
+            images: ["alps.png"],
+            sizes: [4],
+            border: Some((width: 0.04, color: "#202020")),
+        )"##).unwrap();
+        assert_eq!((manifest.images.len(), manifest.sizes.as_slice()), (1, [4].as_slice()));
+        assert!(manifest.border_color().is_some());
+
+        assert!(PackManifest::parse(include_str!("../assets/packs/default/pack.ron")).is_ok());
+        assert!(PackManifest::parse(r#"(name: "Minimal", author: "Someone", images: ["a.png"])"#).is_ok());
+        assert!(PackManifest::parse(r#"(name: "Empty", author: "Someone", images: [])"#).is_err());
+        assert!(PackManifest::parse(r#"(name: "Huge", author: "Someone", images: ["a.png"], sizes: [51])"#).is_err());
+        assert!(PackManifest::parse(r##"(name: "Thick", author: "Someone", images: ["a.png"], border: Some((width: 0.6, color: "#000000")))"##).is_err());
+    }
+}
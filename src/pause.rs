@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+
+use crate::{
+    exit::{ExitConfirmation, ExitRequested},
+    focus::FocusScope,
+    i18n::Localized,
+    replay::ReplayPlayback,
+    taquin::ShuffleRequested,
+    AppState,
+};
+
+const PAUSE_KEY: KeyCode = KeyCode::Escape;
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameClock>()
+            .add_systems(First, tick_game_clock)
+            .add_systems(Update, pause_game.run_if(in_state(AppState::Running)
+                .and_then(not(resource_exists::<ReplayPlayback>()))
+                .and_then(not(resource_exists::<ExitConfirmation>()))))
+            .add_systems(OnEnter(AppState::Paused), setup_pause_overlay)
+            .add_systems(Update, pause_overlay_input.run_if(in_state(AppState::Paused).and_then(not(resource_exists::<ExitConfirmation>()))))
+            .add_systems(OnExit(AppState::Paused), close_pause_overlay);
+    }
+}
+
+/// Real seconds of game, stopped while paused, in an interlude or in the editor. The clocks measuring a solve read it,
+/// they need not skip the seconds paused. Not scaled by the game speed, unlike `Time<Virtual>`
+#[derive(Resource, Debug, Default)]
+pub struct GameClock {
+    elapsed: f32,
+    /// Seconds added this frame, none out of `AppState::Running`
+    delta: f32,
+}
+
+impl GameClock {
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// For the clocks counting down or piling up seconds of game frame after frame
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta
+    }
+}
+
+/// Root of the pause overlay, other screens add their own rows of buttons under it, as `music`
+#[derive(Component)]
+pub struct PauseMenu;
+
+#[derive(Resource)]
+struct PauseOverlay {
+    entity: Entity,
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+enum PauseButton {
+    Resume,
+    Restart,
+    Quit,
+}
+
+impl PauseButton {
+    const ALL: [PauseButton; 3] = [PauseButton::Resume, PauseButton::Restart, PauseButton::Quit];
+
+    fn label(self) -> &'static str {
+        match self {
+            PauseButton::Resume => "Resume",
+            PauseButton::Restart => "Restart",
+            PauseButton::Quit => "Quit",
+        }
+    }
+}
+
+fn tick_game_clock(
+    time: Res<Time<Real>>,
+    state: Res<State<AppState>>,
+    mut clock: ResMut<GameClock>,
+) {
+    clock.delta = if *state.get() == AppState::Running { time.delta_seconds() } else { 0. };
+    clock.elapsed += clock.delta;
+}
+
+fn pause_game(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keyboard_input.just_released(PAUSE_KEY) {
+        next_state.set(AppState::Paused);
+    }
+}
+
+/// The virtual clock stops so tile animations and timers on it do not advance
+fn setup_pause_overlay(
+    mut commands: Commands,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    virtual_time.pause();
+    let entity = commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(20.),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(5),
+        ..default()
+    }, FocusScope, PauseMenu)).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "Paused",
+            TextStyle {
+                font_size: 100.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), Localized::new("Paused")));
+        for button in PauseButton::ALL {
+            parent.spawn((ButtonBundle {
+                style: Style {
+                    width: Val::Px(300.),
+                    padding: UiRect::axes(Val::Px(20.), Val::Px(8.)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                ..default()
+            }, button)).with_children(|parent| {
+                parent.spawn((TextBundle::from_section(
+                    button.label(),
+                    TextStyle {
+                        font_size: 50.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ), Localized::new(button.label())));
+            });
+        }
+    }).id();
+    commands.insert_resource(PauseOverlay { entity });
+}
+
+/// Escape resumes as well, Quit goes through the exit confirmation
+fn pause_overlay_input(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    button_query: Query<(&Interaction, &PauseButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit_events: EventWriter<ExitRequested>,
+) {
+    let pressed = button_query.iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| *button)
+        .or(keyboard_input.just_released(PAUSE_KEY).then_some(PauseButton::Resume));
+    match pressed {
+        Some(PauseButton::Resume) => next_state.set(AppState::Running),
+        Some(PauseButton::Restart) => {
+            commands.init_resource::<ShuffleRequested>();
+            next_state.set(AppState::Running);
+        },
+        Some(PauseButton::Quit) => exit_events.send_default(),
+        None => (),
+    }
+}
+
+fn close_pause_overlay(
+    mut commands: Commands,
+    overlay: Res<PauseOverlay>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    virtual_time.unpause();
+    commands.entity(overlay.entity).despawn_recursive();
+    commands.remove_resource::<PauseOverlay>();
+}
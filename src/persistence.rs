@@ -0,0 +1,274 @@
+use std::{cmp::Ordering, env, fmt, io, path::{Path, PathBuf}};
+
+use bevy::prelude::*;
+use directories::ProjectDirs;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::platform;
+
+/// Where every persisted file lives: platform directories, or beside the executable in portable mode
+#[derive(Resource, Debug, Clone)]
+pub struct DataPaths {
+    data: PathBuf,
+}
+
+impl DataPaths {
+    pub fn new(portable: bool) -> Self {
+        if portable {
+            return Self::portable();
+        }
+        match ProjectDirs::from("", "", "bevy-taquin-3d") {
+            Some(dirs) => Self { data: dirs.data_dir().to_path_buf() },
+            None => Self::portable(),
+        }
+    }
+
+    fn portable() -> Self {
+        let dir = env::current_exe().ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+        Self { data: dir }
+    }
+
+    pub fn replays(&self) -> PathBuf {
+        self.data.join("replays")
+    }
+
+    pub fn history(&self) -> PathBuf {
+        self.data.join("history.ron")
+    }
+
+    pub fn exports(&self) -> PathBuf {
+        self.data.join("exports")
+    }
+
+    /// Screenshots and result cards
+    pub fn captures(&self) -> PathBuf {
+        self.data.join("captures")
+    }
+
+    /// Where shared packs and mods are dropped to be imported
+    pub fn imports(&self) -> PathBuf {
+        self.data.join("imports")
+    }
+
+    pub fn profile(&self) -> PathBuf {
+        self.data.join("profile.json")
+    }
+
+    pub fn metrics(&self) -> PathBuf {
+        self.data.join("metrics.ron")
+    }
+
+    pub fn metrics_settings(&self) -> PathBuf {
+        self.data.join("metrics_settings.ron")
+    }
+
+    pub fn distances(&self) -> PathBuf {
+        self.data.join("distances.ron")
+    }
+
+    pub fn solver_cache(&self) -> PathBuf {
+        self.data.join("solver_cache.ron")
+    }
+
+    pub fn cosmetics(&self) -> PathBuf {
+        self.data.join("cosmetics.ron")
+    }
+
+    pub fn trainer(&self) -> PathBuf {
+        self.data.join("trainer.ron")
+    }
+
+    pub fn records(&self) -> PathBuf {
+        self.data.join("records.ron")
+    }
+
+    pub fn saved_game(&self) -> PathBuf {
+        self.data.join("saved_game.ron")
+    }
+
+    pub fn weekly(&self) -> PathBuf {
+        self.data.join("weekly.ron")
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn leaderboard(&self) -> PathBuf {
+        self.data.join("leaderboard.ron")
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn friends(&self) -> PathBuf {
+        self.data.join("friends.ron")
+    }
+
+    /// Stars earned on the levels of the campaign
+    pub fn campaign(&self) -> PathBuf {
+        self.data.join("campaign.ron")
+    }
+
+    /// Positions made in the board editor
+    pub fn challenges(&self) -> PathBuf {
+        self.data.join("challenges")
+    }
+
+    pub fn stats(&self) -> PathBuf {
+        self.data.join("stats.ron")
+    }
+
+    pub fn achievements(&self) -> PathBuf {
+        self.data.join("achievements.ron")
+    }
+
+    /// Key bindings saved apart before they moved into the settings, read once to move them
+    pub fn key_bindings(&self) -> PathBuf {
+        self.data.join("keybindings.ron")
+    }
+
+    /// Volumes saved apart before they moved into the settings, read once to move them
+    pub fn audio_settings(&self) -> PathBuf {
+        self.data.join("audio.ron")
+    }
+
+    pub fn settings(&self) -> PathBuf {
+        self.data.join("settings.ron")
+    }
+
+    /// Theme saved apart before it moved into the settings, read once to move it
+    pub fn theme(&self) -> PathBuf {
+        self.data.join("theme.ron")
+    }
+
+    #[cfg(all(feature = "cloud-sync", not(target_arch = "wasm32")))]
+    pub fn cloud_sync(&self) -> PathBuf {
+        self.data.join("cloud_sync.ron")
+    }
+}
+
+#[derive(Debug)]
+pub enum PersistenceError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+    Serialize(ron::Error),
+    Json(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for PersistenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistenceError::Io(error) => write!(f, "cannot access file: {error}"),
+            PersistenceError::Parse(error) => write!(f, "malformed file: {error}"),
+            PersistenceError::Serialize(error) => write!(f, "cannot serialize: {error}"),
+            PersistenceError::Json(error) => write!(f, "json error: {error}"),
+            PersistenceError::Invalid(reason) => write!(f, "invalid data: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for PersistenceError {}
+
+impl From<io::Error> for PersistenceError {
+    fn from(error: io::Error) -> Self {
+        PersistenceError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for PersistenceError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        PersistenceError::Parse(error)
+    }
+}
+
+impl From<ron::Error> for PersistenceError {
+    fn from(error: ron::Error) -> Self {
+        PersistenceError::Serialize(error)
+    }
+}
+
+impl From<serde_json::Error> for PersistenceError {
+    fn from(error: serde_json::Error) -> Self {
+        PersistenceError::Json(error)
+    }
+}
+
+impl PersistenceError {
+    pub fn unsupported_version(version: u32) -> Self {
+        PersistenceError::Invalid(format!("no migration from schema version {version}"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ron,
+    Json,
+}
+
+impl Format {
+    pub fn parse<T: DeserializeOwned>(self, contents: &str) -> Result<T, PersistenceError> {
+        Ok(match self {
+            Format::Ron => ron::from_str(contents)?,
+            Format::Json => serde_json::from_str(contents)?,
+        })
+    }
+
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<String, PersistenceError> {
+        Ok(match self {
+            Format::Ron => ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?,
+            Format::Json => serde_json::to_string_pretty(value)?,
+        })
+    }
+}
+
+/// A persisted document carrying a schema version, older documents are upgraded on load
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Schema version written with every document
+    const VERSION: u32;
+
+    /// Builds the current schema from `contents` written with an older schema `version`
+    fn migrate(version: u32, contents: &str, format: Format) -> Result<Self, PersistenceError>;
+}
+
+/// Documents written before versioning have no `version` field and are version 0
+#[derive(Deserialize)]
+struct VersionProbe {
+    #[serde(default)]
+    version: u32,
+}
+
+pub fn load_versioned<T: Versioned>(path: impl AsRef<Path>, format: Format) -> Result<T, PersistenceError> {
+    parse_versioned(&platform::read_to_string(path.as_ref())?, format)
+}
+
+pub fn parse_versioned<T: Versioned>(contents: &str, format: Format) -> Result<T, PersistenceError> {
+    let version = format.parse::<VersionProbe>(contents)?.version;
+    match version.cmp(&T::VERSION) {
+        Ordering::Equal => format.parse(contents),
+        Ordering::Less => T::migrate(version, contents, format),
+        Ordering::Greater => Err(PersistenceError::Invalid(format!("schema version {version} is newer than this game"))),
+    }
+}
+
+pub fn save_versioned<T: Versioned>(path: impl AsRef<Path>, value: &T, format: Format) -> Result<(), PersistenceError> {
+    write(path, format.serialize(value)?)
+}
+
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<(), PersistenceError> {
+    platform::write(path.as_ref(), contents.as_ref())?;
+    Ok(())
+}
+
+/// Whether a document was saved at `path`, see `platform` for where the web build keeps them
+pub fn exists(path: impl AsRef<Path>) -> bool {
+    platform::exists(path.as_ref())
+}
+
+pub fn remove(path: impl AsRef<Path>) -> Result<(), PersistenceError> {
+    platform::remove(path.as_ref())?;
+    Ok(())
+}
+
+/// Seconds since the unix epoch
+pub fn timestamp() -> u64 {
+    platform::timestamp()
+}
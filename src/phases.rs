@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+
+use crate::{
+    pause::GameClock,
+    taquin::{Taquin, TaquinShuffled, TileMoved},
+    AppState,
+};
+
+pub struct PhasesPlugin;
+
+impl Plugin for PhasesPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SolvePhases>()
+            .add_event::<SolvePhasesCompleted>()
+            .add_systems(Update, (
+                start_solve_phases.run_if(on_event::<TaquinShuffled>()),
+                track_solve_phases.run_if(on_event::<TileMoved>()),
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+#[derive(Event, Default)]
+pub struct SolvePhasesCompleted;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolvePhase {
+    pub name: String,
+    /// Number of top rows that must be in place for the phase to be completed
+    pub rows: usize,
+    /// Seconds and moves since the shuffle when the phase was completed
+    pub completed_at: Option<(f32, usize)>,
+}
+
+#[derive(Resource, Default)]
+pub struct SolvePhases {
+    pub phases: Vec<SolvePhase>,
+    started_at: f32,
+    moves: usize,
+}
+
+impl SolvePhases {
+    /// One phase per row until only the last two rows remain, which are solved together
+    pub fn new(width: i8, height: i8) -> Self {
+        let height = height as usize;
+        let mut phases = (1..height.saturating_sub(1)).map(|row| SolvePhase {
+            name: format!("Row {row}"),
+            rows: row,
+            completed_at: None,
+        }).collect::<Vec<SolvePhase>>();
+        phases.push(SolvePhase { name: format!("Last 2x{width}"), rows: height, completed_at: None });
+        Self { phases, started_at: 0., moves: 0 }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.phases.last().is_some_and(|phase| phase.completed_at.is_some())
+    }
+
+    /// Time and moves spent in each phase, measured from the completion of the previous one
+    pub fn splits(&self) -> Vec<(&str, f32, usize)> {
+        let mut previous = (0., 0);
+        self.phases.iter().filter_map(|phase| {
+            let (time, moves) = phase.completed_at?;
+            let split = (phase.name.as_str(), time - previous.0, moves - previous.1);
+            previous = (time, moves);
+            Some(split)
+        }).collect()
+    }
+
+    fn update(&mut self, taquin: &Taquin, elapsed: f32) {
+        for phase in self.phases.iter_mut().filter(|phase| phase.completed_at.is_none()) {
+            if !(0..phase.rows).all(|row| taquin.is_row_solved(row)) {
+                return;
+            }
+            phase.completed_at = Some((elapsed, self.moves));
+        }
+    }
+}
+
+fn start_solve_phases(
+    mut solve_phases: ResMut<SolvePhases>,
+    taquin: Res<Taquin>,
+    clock: Res<GameClock>,
+) {
+    *solve_phases = SolvePhases::new(taquin.width, taquin.height);
+    solve_phases.started_at = clock.elapsed_seconds();
+    solve_phases.update(&taquin, 0.);
+}
+
+fn track_solve_phases(
+    mut solve_phases: ResMut<SolvePhases>,
+    taquin: Res<Taquin>,
+    clock: Res<GameClock>,
+    mut tile_moved_events: EventReader<TileMoved>,
+    mut completed_events: EventWriter<SolvePhasesCompleted>,
+) {
+    if solve_phases.phases.is_empty() || solve_phases.is_completed() {
+        return;
+    }
+    solve_phases.moves += tile_moved_events.read().count();
+    let elapsed = clock.elapsed_seconds() - solve_phases.started_at;
+    solve_phases.update(&taquin, elapsed);
+    if solve_phases.is_completed() {
+        completed_events.send_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{phases::SolvePhases, taquin::Taquin, tile::TileValue};
+
+    #[test]
+    fn test_phases_by_size() {
+        let names = |width, height| SolvePhases::new(width, height).phases.into_iter().map(|p| p.name).collect::<Vec<String>>();
+        assert_eq!(names(3, 3), vec!["Row 1", "Last 2x3"]);
+        assert_eq!(names(4, 4), vec!["Row 1", "Row 2", "Last 2x4"]);
+        assert_eq!(names(5, 3), vec!["Row 1", "Last 2x5"]);
+    }
+
+    #[test]
+    fn test_phase_completion() {
+        let mut taquin = Taquin::new(3);
+        taquin.set_tiles(vec![
+            vec![TileValue(1), TileValue(2), TileValue(3)],
+            vec![TileValue(4), TileValue(9), TileValue(6)],
+            vec![TileValue(7), TileValue(5), TileValue(8)],
+        ]);
+        let mut phases = SolvePhases::new(3, 3);
+        phases.moves = 4;
+        phases.update(&taquin, 2.);
+        assert_eq!(phases.phases[0].completed_at, Some((2., 4)));
+        assert!(!phases.is_completed());
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    auto_solve::AutoSolvePlayback,
+    graphics::MainCamera,
+    marker::Markers,
+    replay::ReplayPlayback,
+    taquin::{SlideSelectedTile, Taquin},
+    tile::TileSelected,
+    tile_material::TileShading,
+    AppState,
+};
+
+pub struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HoveredTile>()
+            .add_systems(Update, (
+                hover_tiles,
+                select_hovered_tile.run_if(resource_changed::<HoveredTile>()),
+                click_tiles,
+            ).chain().run_if(in_state(AppState::Running)
+                .and_then(not(resource_exists::<ReplayPlayback>()))
+                .and_then(not(resource_exists::<AutoSolvePlayback>()))));
+    }
+}
+
+/// Tile under the mouse cursor, selected as the arrows would
+#[derive(Resource, Debug, Default)]
+pub struct HoveredTile(pub Option<Entity>);
+
+/// First tile whose quad, centered on its translation in the board plane turned by `plane`, the ray goes through
+pub(crate) fn pick_tile<T>(ray: Ray, plane: Quat, tile_size: Vec2, tiles: impl IntoIterator<Item = (T, Vec3)>) -> Option<T> {
+    let half_size = tile_size / 2.;
+    tiles.into_iter().find(|(_, translation)| {
+        ray.intersect_plane(*translation, plane * Vec3::Z)
+            .map(|distance| plane.inverse() * (ray.get_point(distance) - *translation))
+            .is_some_and(|offset| offset.x.abs() <= half_size.x && offset.y.abs() <= half_size.y)
+    }).map(|(entity, _)| entity)
+}
+
+/// Ray from the camera through the cursor, the camera may render to an image of another size than the window
+pub(crate) fn cursor_ray(window: &Window, camera: &Camera, camera_transform: &GlobalTransform) -> Option<Ray> {
+    screen_ray(window, camera, camera_transform, window.cursor_position()?)
+}
+
+/// Ray from the camera through a point of the window, a touch for instance
+pub(crate) fn screen_ray(window: &Window, camera: &Camera, camera_transform: &GlobalTransform, position: Vec2) -> Option<Ray> {
+    let viewport = camera.logical_viewport_size()?;
+    camera.viewport_to_world(camera_transform, position * viewport / Vec2::new(window.width(), window.height()))
+}
+
+fn hover_tiles(
+    mut cursor_events: EventReader<CursorMoved>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    // The empty tile has no shading, the tiles of a mega board have no material of their own
+    tiles_query: Query<(Entity, &Transform), With<TileShading>>,
+    markers: Res<Markers>,
+    taquin: Res<Taquin>,
+    mut hovered: ResMut<HoveredTile>,
+) {
+    if cursor_events.read().count() == 0 {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    let tile = cursor_ray(window, camera, camera_transform)
+        .and_then(|ray| pick_tile(ray, markers.rotation(), markers.tile_size(taquin.width, taquin.height), tiles_query.iter().map(|(entity, transform)| (entity, transform.translation))));
+    // Only touched when the cursor goes onto another tile, so that its change detection means it
+    if tile != hovered.0 {
+        hovered.0 = tile;
+    }
+}
+
+/// Selects the tile the cursor went onto, the arrows still move the selection afterwards
+fn select_hovered_tile(
+    mut commands: Commands,
+    hovered: Res<HoveredTile>,
+    selected_query: Query<Entity, With<TileSelected>>,
+) {
+    let Some(tile) = hovered.0 else {
+        return;
+    };
+    for entity in selected_query.iter().filter(|entity| *entity != tile) {
+        commands.entity(entity).remove::<TileSelected>();
+    }
+    commands.entity(tile).insert(TileSelected);
+}
+
+/// Slides the hovered tile, which is the selected one by then, as space would
+fn click_tiles(
+    mouse_input: Res<Input<MouseButton>>,
+    hovered: Res<HoveredTile>,
+    selected_query: Query<Entity, With<TileSelected>>,
+    mut slide_events: EventWriter<SlideSelectedTile>,
+) {
+    if !mouse_input.just_released(MouseButton::Left) {
+        return;
+    }
+    if hovered.0.is_some_and(|tile| selected_query.contains(tile)) {
+        slide_events.send_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::picking::pick_tile;
+
+    #[test]
+    fn test_pick_tile() {
+        let tiles = [(Entity::from_raw(1), Vec3::new(-1., 0., 0.75)), (Entity::from_raw(2), Vec3::new(1., 0., 0.75))];
+        let ray = |x: f32| Ray { origin: Vec3::new(x, 0.5, 10.), direction: Vec3::NEG_Z };
+        assert_eq!(pick_tile(ray(1.2), Quat::IDENTITY, Vec2::splat(2.), tiles), Some(Entity::from_raw(2)));
+        assert_eq!(pick_tile(ray(-0.5), Quat::IDENTITY, Vec2::splat(2.), tiles), Some(Entity::from_raw(1)));
+        assert_eq!(pick_tile(ray(2.5), Quat::IDENTITY, Vec2::splat(2.), tiles), None);
+        assert_eq!(pick_tile(Ray { origin: Vec3::new(1., 0., 10.), direction: Vec3::X }, Quat::IDENTITY, Vec2::splat(2.), tiles), None);
+        // A frame turned to face the right side is picked from there
+        let turned = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let side_ray = Ray { origin: Vec3::new(10., 0.5, 1.5), direction: Vec3::NEG_X };
+        assert_eq!(pick_tile(side_ray, turned, Vec2::splat(2.), [(Entity::from_raw(3), Vec3::new(0.75, 0., 1.))]), Some(Entity::from_raw(3)));
+    }
+}
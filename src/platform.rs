@@ -0,0 +1,197 @@
+use std::{io, path::Path};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{env, fs, time::{SystemTime, UNIX_EPOCH}};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    fs::read_to_string(path)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn create_dir_all(path: &Path) -> io::Result<()> {
+    fs::create_dir_all(path)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn exists(path: &Path) -> bool {
+    path.exists()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove(path: &Path) -> io::Result<()> {
+    fs::remove_file(path)
+}
+
+/// Seconds since the unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+pub fn timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Arguments the game was launched with, the program name first
+#[cfg(not(target_arch = "wasm32"))]
+pub fn launch_args() -> Vec<String> {
+    env::args().collect()
+}
+
+/// Shows the fragment a link needs to open the game as it is now, the page url on the web
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_url_fragment(fragment: &str) {
+    bevy::log::info!("link fragment: #{fragment}");
+}
+
+/// Puts text in the system clipboard
+#[cfg(not(target_arch = "wasm32"))]
+pub fn copy_text(text: &str) -> io::Result<()> {
+    arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)).map_err(clipboard_error)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn paste_text() -> io::Result<String> {
+    arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()).map_err(clipboard_error)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn clipboard_error(error: arboard::Error) -> io::Error {
+    io::Error::other(error)
+}
+
+/// The browser has no files, persisted documents are kept in the local storage of the page under their path
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> io::Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "no local storage"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage_error(error: wasm_bindgen::JsValue) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{error:?}"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage_key(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    local_storage()?.get_item(&storage_key(path)).map_err(storage_error)?
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+}
+
+/// Only text fits in the local storage, binary exports such as archives and gifs are refused
+#[cfg(target_arch = "wasm32")]
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let contents = std::str::from_utf8(contents).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    local_storage()?.set_item(&storage_key(path), contents).map_err(storage_error)
+}
+
+/// The local storage has no folders to create
+#[cfg(target_arch = "wasm32")]
+pub fn create_dir_all(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn exists(path: &Path) -> bool {
+    local_storage().ok()
+        .and_then(|storage| storage.get_item(&storage_key(path)).ok().flatten())
+        .is_some()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn remove(path: &Path) -> io::Result<()> {
+    local_storage()?.remove_item(&storage_key(path)).map_err(storage_error)
+}
+
+/// Seconds since the unix epoch, `SystemTime` panics in a browser
+#[cfg(target_arch = "wasm32")]
+pub fn timestamp() -> u64 {
+    (js_sys::Date::now() / 1000.) as u64
+}
+
+/// The query parameters of the page then its fragment, as the command line would give them
+#[cfg(target_arch = "wasm32")]
+pub fn launch_args() -> Vec<String> {
+    let location = web_sys::window().map(|window| window.location());
+    let query = location.as_ref().and_then(|location| location.search().ok()).unwrap_or_default();
+    let fragment = location.as_ref().and_then(|location| location.hash().ok()).unwrap_or_default();
+    std::iter::once(env!("CARGO_PKG_NAME").to_string()).chain(query_args(&query)).chain(query_args(&fragment)).collect()
+}
+
+/// Replaces the fragment of the page url without reloading it
+#[cfg(target_arch = "wasm32")]
+pub fn set_url_fragment(fragment: &str) {
+    if let Some(Err(error)) = web_sys::window().map(|window| window.location().set_hash(fragment)) {
+        bevy::log::warn!("cannot set the page url: {error:?}");
+    }
+}
+
+/// The clipboard of the browser is only reachable asynchronously, the url fragment carries the text instead
+#[cfg(target_arch = "wasm32")]
+pub fn copy_text(_text: &str) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "no clipboard in the browser, the page url has it"))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn paste_text() -> io::Result<String> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "no clipboard in the browser, open the shared link instead"))
+}
+
+/// Options of a page query or fragment, `?size=4&seed=7&headless` gives `--size 4 --seed 7 --headless`
+#[cfg(any(target_arch = "wasm32", test))]
+fn query_args(query: &str) -> Vec<String> {
+    query.trim_start_matches(['?', '#']).split('&')
+        .filter(|parameter| !parameter.is_empty())
+        .flat_map(|parameter| {
+            let (name, value) = parameter.split_once('=').map_or((parameter, None), |(name, value)| (name, Some(value)));
+            std::iter::once(format!("--{}", decode(name))).chain(value.map(decode))
+        })
+        .collect()
+}
+
+/// Undoes the url encoding of a query parameter, `+` and `%XX`
+#[cfg(any(target_arch = "wasm32", test))]
+fn decode(encoded: &str) -> String {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => match rest.get(..2).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()) {
+                Some(decoded) => {
+                    bytes.push(decoded);
+                    rest = &rest[2..];
+                },
+                None => bytes.push(byte),
+            },
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::platform::query_args;
+
+    #[test]
+    fn test_query_args() {
+        assert_eq!(query_args("?size=4&seed=7&headless"), ["--size", "4", "--seed", "7", "--headless"]);
+        assert_eq!(query_args("?locale=fr&tile-labels=big+digits&pack=my%20pack"), ["--locale", "fr", "--tile-labels", "big digits", "--pack", "my pack"]);
+        assert_eq!(query_args(""), Vec::<String>::new());
+        assert_eq!(query_args("?ratio=50%"), ["--ratio", "50%"]);
+        assert_eq!(query_args("#replay-code=BCC"), ["--replay-code", "BCC"]);
+    }
+}
@@ -0,0 +1,336 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+
+use crate::{
+    locale::Locale,
+    marker::Markers,
+    taquin::Taquin,
+    tile::{TileCoordinates, TileId, TileIndex, TileSlide},
+    AppState,
+};
+
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 8.;
+
+pub struct PlaybackPlugin;
+
+impl Plugin for PlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (
+                despawn_playback_hud.run_if(resource_removed::<PlaybackController>()),
+                setup_playback_hud.run_if(resource_added::<PlaybackController>()),
+            ).chain())
+            .add_systems(Update, (
+                playback_controls,
+                playback_scrubber,
+                update_playback_hud,
+            ).chain().run_if(in_state(AppState::Running).and_then(resource_exists::<PlaybackController>())));
+    }
+}
+
+/// Play, pause, step, seek and speed of the moves played back, whether they come from a replay or the
+/// auto-solver. Inserted along their playback, the keys and the scrubber drive it without knowing which one plays
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct PlaybackController {
+    /// Shown in the HUD
+    pub label: &'static str,
+    speed: f32,
+    paused: bool,
+    /// Seconds of the playback clock, the moves are timed on it
+    elapsed: f32,
+    played: usize,
+    len: usize,
+    seek: Option<usize>,
+    step: bool,
+}
+
+impl PlaybackController {
+    pub fn new(label: &'static str, len: usize) -> Self {
+        Self { label, speed: 1., paused: false, elapsed: 0., played: 0, len, seek: None, step: false }
+    }
+
+    pub fn play(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Plays the next move right away, paused or not
+    pub fn step(&mut self) {
+        self.step = self.played < self.len;
+    }
+
+    /// Puts the board back as it was after `played` moves, done by the playback owning the moves
+    pub fn seek(&mut self, played: usize) {
+        self.seek = Some(played.min(self.len));
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    pub fn played(&self) -> usize {
+        self.played
+    }
+
+    pub fn total(&self) -> usize {
+        self.len
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.len == 0 {
+            return 1.;
+        }
+        self.played as f32 / self.len as f32
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.played >= self.len
+    }
+
+    /// Seek asked for, for the playback to rebuild its board and call `seeked`
+    pub fn take_seek(&mut self) -> Option<usize> {
+        self.seek.take()
+    }
+
+    /// The board is the one after `played` moves, the last one played at `elapsed` on the playback clock
+    pub fn seeked(&mut self, played: usize, elapsed: f32) {
+        self.played = played.min(self.len);
+        self.elapsed = elapsed;
+        self.step = false;
+    }
+
+    /// Runs the playback clock for `delta` seconds of game time, true once the move timed at `next_time` is due
+    pub fn tick(&mut self, delta: f32, next_time: f32) -> bool {
+        if self.is_finished() || self.seek.is_some() {
+            return false;
+        }
+        if self.step {
+            return true;
+        }
+        if self.paused {
+            return false;
+        }
+        self.elapsed += delta * self.speed;
+        self.elapsed >= next_time
+    }
+
+    /// The move due, timed at `time`, is played
+    pub fn advance(&mut self, time: f32) {
+        self.played += 1;
+        self.elapsed = self.elapsed.max(time);
+        self.step = false;
+    }
+}
+
+/// Puts every tile on its cell of `taquin` at once, the slides under way dropped
+pub fn snap_tiles(
+    commands: &mut Commands,
+    taquin: &Taquin,
+    markers: &Markers,
+    index: &TileIndex,
+    tiles_query: &mut Query<(&mut TileCoordinates, &mut Transform)>,
+) {
+    for (j, row) in taquin.tiles.iter().enumerate() {
+        for (i, value) in row.iter().enumerate() {
+            let coordinates = TileCoordinates::new(i as i8, j as i8);
+            let Some(entity) = index.entity(TileId::from(*value)) else {
+                continue;
+            };
+            if let Ok((mut tile_coordinates, mut transform)) = tiles_query.get_mut(entity) {
+                *tile_coordinates = coordinates;
+                transform.translation = markers.tile_translation(taquin.width, taquin.height, coordinates);
+                commands.entity(entity).remove::<TileSlide>();
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+struct PlaybackHud;
+
+#[derive(Component)]
+struct PlaybackScrubber;
+
+#[derive(Component)]
+struct PlaybackProgressFill;
+
+#[derive(Component)]
+struct PlaybackStatusText;
+
+fn setup_playback_hud(mut commands: Commands, locale: Res<Locale>) {
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(20.),
+                left: Val::Percent(10.),
+                width: Val::Percent(80.),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.),
+                align_items: if locale.is_right_to_left() { AlignItems::FlexEnd } else { AlignItems::FlexStart },
+                ..default()
+            },
+            ..default()
+        }, PlaybackHud))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ).with_text_alignment(locale.start_alignment()),
+                PlaybackStatusText,
+            ));
+            parent
+                .spawn((NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.),
+                        height: Val::Px(16.),
+                        flex_direction: locale.row_direction(),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                    ..default()
+                }, Interaction::default(), RelativeCursorPosition::default(), PlaybackScrubber))
+                .with_children(|parent| {
+                    parent.spawn((NodeBundle {
+                        style: Style {
+                            width: Val::Percent(0.),
+                            height: Val::Percent(100.),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    }, PlaybackProgressFill));
+                });
+        });
+}
+
+fn despawn_playback_hud(
+    mut commands: Commands,
+    hud_query: Query<Entity, With<PlaybackHud>>,
+) {
+    for entity in hud_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Space pauses, up and down change the speed, right steps and left goes one move back
+fn playback_controls(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut controller: ResMut<PlaybackController>,
+) {
+    if keyboard_input.just_released(KeyCode::Space) {
+        if controller.is_paused() { controller.play() } else { controller.pause() }
+    }
+    if keyboard_input.just_released(KeyCode::Up) {
+        let speed = controller.speed() * 2.;
+        controller.set_speed(speed);
+    }
+    if keyboard_input.just_released(KeyCode::Down) {
+        let speed = controller.speed() / 2.;
+        controller.set_speed(speed);
+    }
+    if keyboard_input.just_released(KeyCode::Left) {
+        let played = controller.played().saturating_sub(1);
+        controller.seek(played);
+    }
+    if keyboard_input.just_released(KeyCode::Right) {
+        controller.step();
+    }
+    if keyboard_input.just_released(KeyCode::Home) {
+        controller.seek(0);
+    }
+}
+
+fn playback_scrubber(
+    mut controller: ResMut<PlaybackController>,
+    scrubber_query: Query<(&Interaction, &RelativeCursorPosition), With<PlaybackScrubber>>,
+    locale: Res<Locale>,
+) {
+    let Ok((Interaction::Pressed, cursor_position)) = scrubber_query.get_single() else {
+        return;
+    };
+    let Some(position) = cursor_position.normalized else {
+        return;
+    };
+    let progress = if locale.is_right_to_left() { 1. - position.x } else { position.x };
+    let played = (progress.clamp(0., 1.) * controller.total() as f32).round() as usize;
+    if played != controller.played() {
+        controller.seek(played);
+    }
+}
+
+fn update_playback_hud(
+    controller: Res<PlaybackController>,
+    mut fill_query: Query<&mut Style, With<PlaybackProgressFill>>,
+    mut text_query: Query<&mut Text, With<PlaybackStatusText>>,
+    locale: Res<Locale>,
+) {
+    if let Ok(mut style) = fill_query.get_single_mut() {
+        let width = Val::Percent(controller.progress() * 100.);
+        if style.width != width {
+            style.width = width;
+        }
+    }
+    if let Ok(mut text) = text_query.get_single_mut() {
+        let value = format!(
+            "{} {}/{}  x{}{}",
+            controller.label,
+            locale.format_count(controller.played()),
+            locale.format_count(controller.total()),
+            locale.format_decimal(controller.speed(), 2),
+            if controller.is_paused() { "  (paused)" } else { "" },
+        );
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::playback::PlaybackController;
+
+    #[test]
+    fn test_tick() {
+        let mut controller = PlaybackController::new("Test", 2);
+        assert!(!controller.tick(0.5, 1.));
+        controller.set_speed(2.);
+        assert!(controller.tick(0.5, 1.));
+        controller.advance(1.);
+        controller.pause();
+        assert!(!controller.tick(10., 3.));
+        controller.step();
+        assert!(controller.tick(0., 3.));
+        controller.advance(3.);
+        assert!(controller.is_finished());
+        controller.step();
+        assert!(!controller.tick(10., 3.));
+    }
+
+    #[test]
+    fn test_seek() {
+        let mut controller = PlaybackController::new("Test", 4);
+        controller.seek(9);
+        assert!(!controller.tick(10., 0.));
+        assert_eq!(controller.take_seek(), Some(4));
+        controller.seeked(1, 2.);
+        assert_eq!(controller.played(), 1);
+        assert!(controller.tick(1., 3.));
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::{self, Write};
+
+use flate2::{write::ZlibEncoder, Compression};
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+const BIT_DEPTH: u8 = 8;
+/// Red, green and blue, without alpha
+const COLOR_TYPE_RGB: u8 = 2;
+
+/// Length, kind, data, then the CRC of the kind and the data
+fn push_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32fast::hash(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// PNG of `width` by `height` pixels, `rgb` holding three bytes a pixel, row after row
+pub fn encode(width: u32, height: u32, rgb: &[u8]) -> io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(13);
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // Deflate, adaptive filtering and no interlacing, the only methods there are
+    header.extend([BIT_DEPTH, COLOR_TYPE_RGB, 0, 0, 0]);
+
+    // Every row starts with its filter, none here
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in rgb.chunks(width as usize * 3).take(height as usize) {
+        encoder.write_all(&[0])?;
+        encoder.write_all(row)?;
+    }
+    let data = encoder.finish()?;
+
+    let mut png = SIGNATURE.to_vec();
+    push_chunk(&mut png, b"IHDR", &header);
+    push_chunk(&mut png, b"IDAT", &data);
+    push_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::ZlibDecoder;
+
+    use crate::png::{encode, SIGNATURE};
+
+    #[test]
+    fn test_encode() {
+        let rgb = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        let png = encode(2, 2, &rgb).unwrap();
+        assert_eq!(png[..8], SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(png[16..24], [0, 0, 0, 2, 0, 0, 0, 2]);
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]));
+
+        let data_length = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+        let mut pixels = Vec::new();
+        ZlibDecoder::new(&png[41..41 + data_length]).read_to_end(&mut pixels).unwrap();
+        assert_eq!(pixels, [0, 255, 0, 0, 0, 255, 0, 0, 0, 0, 255, 255, 255, 255]);
+    }
+}
@@ -0,0 +1,238 @@
+use std::{collections::BTreeMap, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    achievements::{Achievement, Achievements},
+    cosmetics::{Cosmetic, Cosmetics},
+    keybindings::KeyBindings,
+    records::{BestRecords, SizeRecord},
+    settings::{Settings, SettingsImported},
+    stats::{GameHistory, GameRecord, SizeStats, Stats},
+};
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (export_profile, import_profile));
+    }
+}
+
+/// Everything a player wants to carry over to another machine
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProfileBundle {
+    pub version: u32,
+    pub games: Vec<GameRecord>,
+    #[serde(default)]
+    pub unlocked: Vec<Cosmetic>,
+    #[serde(default)]
+    pub records: BTreeMap<i8, SizeRecord>,
+    /// With the volumes, the theme and the key bindings
+    #[serde(default)]
+    pub settings: Option<Settings>,
+    /// Totals of each size, the play time of unsolved games is nowhere else
+    #[serde(default)]
+    pub stats: BTreeMap<i8, SizeStats>,
+    /// Achievements earned, with when
+    #[serde(default)]
+    pub achievements: BTreeMap<Achievement, u64>,
+    /// Tiles slid in every game, some achievements count them
+    #[serde(default)]
+    pub slides: usize,
+}
+
+/// The resources of this machine a bundle is merged into
+pub struct LocalProfile<'a> {
+    pub history: &'a mut GameHistory,
+    pub cosmetics: &'a mut Cosmetics,
+    pub records: &'a mut BestRecords,
+    pub settings: &'a mut Settings,
+    pub stats: &'a mut Stats,
+    pub achievements: &'a mut Achievements,
+}
+
+impl ProfileBundle {
+    pub fn new(history: &GameHistory, cosmetics: &Cosmetics, records: &BestRecords, settings: &Settings, stats: &Stats, achievements: &Achievements) -> Self {
+        Self {
+            version: Self::VERSION,
+            games: history.games.clone(),
+            unlocked: cosmetics.unlocked.clone(),
+            records: records.sizes.clone(),
+            settings: Some(settings.clone()),
+            stats: stats.sizes.clone(),
+            achievements: achievements.unlocked.clone(),
+            slides: achievements.slides,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+        persistence::load_versioned(path, Format::Json)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistenceError> {
+        persistence::save_versioned(path, self, Format::Json)
+    }
+
+    /// Union of the games, the cosmetics and the achievements, best of each record, the most recently changed settings,
+    /// the totals of each size played the longest
+    pub fn merge(&mut self, other: ProfileBundle) {
+        for game in other.games {
+            if !self.games.contains(&game) {
+                self.games.push(game);
+            }
+        }
+        self.games.sort_by_key(|game| game.date);
+        for cosmetic in other.unlocked {
+            if !self.unlocked.contains(&cosmetic) {
+                self.unlocked.push(cosmetic);
+            }
+        }
+        for (size, record) in other.records {
+            self.records.entry(size).or_default().merge(&record);
+        }
+        if let Some(settings) = other.settings {
+            // The tutorial played on one device is not shown on the others
+            let tutorial_done = settings.tutorial_done || self.settings.as_ref().is_some_and(|current| current.tutorial_done);
+            if self.settings.as_ref().is_none_or(|current| settings.changed_at > current.changed_at) {
+                self.settings = Some(settings);
+            }
+            if let Some(current) = self.settings.as_mut() {
+                current.tutorial_done = tutorial_done;
+            }
+        }
+        for (size, stats) in other.stats {
+            self.stats.entry(size).or_default().merge(&stats);
+        }
+        for (achievement, date) in other.achievements {
+            let earliest = self.achievements.entry(achievement).or_insert(date);
+            *earliest = (*earliest).min(date);
+        }
+        self.slides = self.slides.max(other.slides);
+    }
+
+    /// Merges the bundle into the resources of this machine and saves the ones it changed. The display settings
+    /// stay those of this machine
+    pub fn merge_into(self, local: LocalProfile, paths: &DataPaths) -> Result<(), PersistenceError> {
+        let LocalProfile { history, cosmetics, records, settings, stats, achievements } = local;
+        let mut merged = ProfileBundle::new(history, cosmetics, records, settings, stats, achievements);
+        merged.merge(self);
+        if cosmetics.unlock(merged.unlocked) {
+            cosmetics.save(paths)?;
+        }
+        if merged.games.len() > history.games.len() {
+            history.games = merged.games;
+            history.save(paths)?;
+        }
+        if merged.records != records.sizes {
+            records.sizes = merged.records;
+            persistence::save_versioned(paths.records(), records, Format::Ron)?;
+        }
+        let merged_settings = merged.settings.map(|merged| Settings { vsync: settings.vsync, resolution: settings.resolution, ..merged });
+        if let Some(merged) = merged_settings.filter(|merged| merged != settings) {
+            *settings = merged;
+            persistence::save_versioned(paths.settings(), settings, Format::Ron)?;
+        }
+        if merged.stats != stats.sizes {
+            stats.sizes = merged.stats;
+            stats.save(paths)?;
+        }
+        if merged.achievements != achievements.unlocked || merged.slides != achievements.slides {
+            (achievements.unlocked, achievements.slides) = (merged.achievements, merged.slides);
+            persistence::save_versioned(paths.achievements(), achievements, Format::Ron)?;
+        }
+        Ok(())
+    }
+}
+
+/// The key bindings of a version 1 bundle, apart from its settings
+#[derive(Deserialize)]
+struct SeparateKeyBindings {
+    #[serde(default)]
+    key_bindings: Option<KeyBindings>,
+}
+
+impl Versioned for ProfileBundle {
+    const VERSION: u32 = 2;
+
+    fn migrate(version: u32, contents: &str, format: Format) -> Result<Self, PersistenceError> {
+        match version {
+            // The key bindings go into the settings, as the volumes and the theme, which these bundles did not carry
+            1 => {
+                let mut bundle = Self { version: Self::VERSION, ..format.parse(contents)? };
+                let SeparateKeyBindings { key_bindings } = format.parse(contents)?;
+                if let (Some(settings), Some(key_bindings)) = (bundle.settings.as_mut(), key_bindings) {
+                    settings.key_bindings = key_bindings;
+                }
+                Ok(bundle)
+            },
+            _ => Err(PersistenceError::unsupported_version(version)),
+        }
+    }
+}
+
+fn is_control_pressed(keyboard_input: &Input<KeyCode>) -> bool {
+    keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+#[allow(clippy::too_many_arguments)]
+fn export_profile(
+    keyboard_input: Res<Input<KeyCode>>,
+    history: Res<GameHistory>,
+    cosmetics: Res<Cosmetics>,
+    records: Res<BestRecords>,
+    settings: Res<Settings>,
+    stats: Res<Stats>,
+    achievements: Res<Achievements>,
+    paths: Res<DataPaths>,
+) {
+    if !(keyboard_input.just_released(KeyCode::E) && is_control_pressed(&keyboard_input)) {
+        return;
+    }
+    let path = paths.profile();
+    match ProfileBundle::new(&history, &cosmetics, &records, &settings, &stats, &achievements).save(&path) {
+        Ok(()) => info!("profile exported to {}", path.display()),
+        Err(error) => error!("cannot export profile: {error}"),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn import_profile(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut history: ResMut<GameHistory>,
+    mut cosmetics: ResMut<Cosmetics>,
+    mut records: ResMut<BestRecords>,
+    mut settings: ResMut<Settings>,
+    mut stats: ResMut<Stats>,
+    mut achievements: ResMut<Achievements>,
+    paths: Res<DataPaths>,
+    mut imported_events: EventWriter<SettingsImported>,
+) {
+    if !(keyboard_input.just_released(KeyCode::I) && is_control_pressed(&keyboard_input)) {
+        return;
+    }
+    let path = paths.profile();
+    let bundle = match ProfileBundle::load(&path) {
+        Ok(bundle) => bundle,
+        Err(error) => {
+            error!("cannot import profile: {error}");
+            return;
+        }
+    };
+    let local = LocalProfile {
+        history: &mut history,
+        cosmetics: &mut cosmetics,
+        records: &mut records,
+        // Saved with the date of the settings merged, `save_settings` would date them now
+        settings: settings.bypass_change_detection(),
+        stats: &mut stats,
+        achievements: &mut achievements,
+    };
+    match bundle.merge_into(local, &paths) {
+        Ok(()) => info!("profile imported from {}", path.display()),
+        Err(error) => error!("cannot save imported profile: {error}"),
+    }
+    imported_events.send_default();
+}
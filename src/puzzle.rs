@@ -0,0 +1,644 @@
+#[cfg(feature = "bevy")]
+use bevy::prelude::{Component, Resource};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{coordinates::{Direction, TileCoordinates, Topology}, solver::Board, zobrist};
+
+/// Biggest side the solver handles, its boards hold their values in bytes. Square boards up to it are shuffled
+/// by the solver, bigger ones as rectangular boards are
+pub const MAX_SOLVER_SIZE: i8 = 11;
+
+/// From 1, row after row of the solved board. Wide enough for the values of a 50x50 board
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
+pub struct TileValue(pub i16);
+
+impl TileValue {
+    pub fn is_empty(&self, tiles_nb: usize) -> bool {
+        self.0 as usize == tiles_nb
+    }
+
+    /// Value of the tile whose cell is `cell` on the solved board of `width` columns
+    pub fn for_cell(cell: TileCoordinates, width: i8) -> Self {
+        Self(cell.j as i16 * width as i16 + cell.i as i16 + 1)
+    }
+
+    /// Cell of the tile on the solved board of `width` columns
+    pub fn goal(&self, width: i8) -> TileCoordinates {
+        let width = width as i16;
+        TileCoordinates::new(((self.0 - 1) % width) as i8, ((self.0 - 1) / width) as i8)
+    }
+}
+
+#[derive(Default)]
+#[cfg_attr(feature = "bevy", derive(Resource))]
+pub struct Taquin {
+    /// Columns of the board
+    pub width: i8,
+    /// Rows of the board, equal to `width` but on rectangular boards
+    pub height: i8,
+    pub tiles_nb: usize,
+    /// Rows of the board, only changed through `set_tiles` and `swap_tiles` so the cached checks follow
+    pub tiles: Vec<Vec<TileValue>>,
+    pub is_shuffled: bool,
+    /// Whether tiles slide through the edges, flat unless set after the board is made
+    pub topology: Topology,
+    /// Zobrist hash of `tiles`, kept up to date by `set_tiles` and `swap_tiles`
+    hash: u64,
+    /// Values of `tiles` row after row, the checks run on it
+    cells: Vec<i16>,
+    /// Index of the first empty slot in `cells`
+    empty: usize,
+    /// Empty slots of the board, the highest values stand for them
+    empty_count: usize,
+    /// Pairs of tiles out of order, the empty slot left out
+    inversions: usize,
+    /// Cells not holding their own value, the empty slot included
+    misplaced: usize,
+    /// Quarter turns clockwise of each tile by value, empty until a tile turns
+    rotations: Vec<u8>,
+    /// Tiles of `rotations` not upright
+    turned: usize,
+}
+
+impl Taquin {
+    pub fn new(size: i8) -> Self {
+        Self::rect(size, size)
+    }
+
+    pub fn rect(width: i8, height: i8) -> Self {
+        Self { width, height, tiles_nb: width as usize * height as usize, empty_count: 1, ..Default::default() }
+    }
+
+    /// Empty slots of the board, 1 unless set with `set_empty_count`
+    pub fn empty_count(&self) -> usize {
+        self.empty_count.max(1)
+    }
+
+    /// The `count` highest values become empty slots, the cached checks are counted again for them
+    pub fn set_empty_count(&mut self, count: usize) {
+        self.empty_count = count.clamp(1, self.tiles_nb.saturating_sub(1).max(1));
+        if !self.tiles.is_empty() {
+            self.set_tiles(self.tiles.clone());
+        }
+    }
+
+    /// As `seeded_tiles`, drawn again from the next seeds while the board would look solved with its empty slots
+    pub fn draw_tiles(&self, seed: u64) -> Vec<TileValue> {
+        let mut board = Taquin::rect(self.width, self.height);
+        board.set_empty_count(self.empty_count());
+        let mut seed = seed;
+        loop {
+            let tiles = seeded_tiles(self.width, self.height, seed);
+            board.set_tiles(tiles.chunks(self.width as usize).map(|row| row.to_vec()).collect());
+            if !board.is_solved() {
+                return tiles;
+            }
+            seed = seed.wrapping_add(1);
+        }
+    }
+
+    /// Whether the tile with `value` stands for an empty slot
+    pub fn is_empty_value(&self, value: TileValue) -> bool {
+        value.0 as usize > self.tiles_nb.saturating_sub(self.empty_count())
+    }
+
+    /// The solver and the features built on it only handle square boards
+    pub fn is_square(&self) -> bool {
+        self.width == self.height
+    }
+
+    /// Zobrist keys are drawn per width, rows only add cells.
+    /// The cached checks are counted from scratch here, moves then only update them
+    pub fn set_tiles(&mut self, tiles: Vec<Vec<TileValue>>) {
+        self.cells = tiles.iter().flatten().map(|value| value.0).collect();
+        self.hash = zobrist::hash(self.width as usize, self.cells.iter().map(|value| *value as u16));
+        self.empty = self.first_empty();
+        self.inversions = (0..self.cells.len())
+            .map(|a| (a + 1..self.cells.len()).filter(|b| self.is_inversion(self.cells[a], self.cells[*b])).count())
+            .sum();
+        self.misplaced = (0..self.cells.len()).filter(|index| !self.is_in_place(*index)).count();
+        self.tiles = tiles;
+    }
+
+    /// Identifies the current position, equal for equal boards of the same size
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Copy for the solver, which only handles square boards up to `MAX_SOLVER_SIZE`
+    pub fn board(&self) -> Board {
+        Board::new(self.width as usize, self.cells.iter().map(|value| *value as u8).collect()).with_topology(self.topology)
+    }
+
+    /// Next cell holding a tile `direction` of the selection, going round to the other side of the board
+    pub fn get_next_selection_coordinates(&self, current_coordinates: &TileCoordinates, direction: Direction) -> TileCoordinates {
+        let (i, j) = direction.offset();
+        let mut coordinates = *current_coordinates;
+        loop {
+            coordinates = TileCoordinates::new((coordinates.i + i).rem_euclid(self.width), (coordinates.j + j).rem_euclid(self.height));
+            if coordinates == *current_coordinates || !self.is_empty_value(self.tiles[coordinates.j as usize][coordinates.i as usize]) {
+                return coordinates
+            }
+        }
+    }
+
+    /// Whether `a` placed before `b` is a pair out of order, the empty slots being in order with any tile
+    fn is_inversion(&self, a: i16, b: i16) -> bool {
+        !self.is_empty_value(TileValue(a)) && !self.is_empty_value(TileValue(b)) && a > b
+    }
+
+    /// Empty slots can trade places, any of them is in place in a cell of another
+    fn is_in_place(&self, index: usize) -> bool {
+        let value = self.cells[index];
+        value as usize == index + 1 || (self.is_empty_value(TileValue(value)) && self.is_empty_value(TileValue(index as i16 + 1)))
+    }
+
+    fn first_empty(&self) -> usize {
+        self.cells.iter().position(|value| self.is_empty_value(TileValue(*value))).unwrap_or(0)
+    }
+
+    /// Change of the inversion count when the values at `low` and `high` trade places,
+    /// only the pairs they make with each other and with the cells between them are affected
+    fn inversion_delta(&self, low: usize, high: usize) -> isize {
+        let (a, b) = (self.cells[low], self.cells[high]);
+        let order = |first: i16, second: i16| self.is_inversion(first, second) as isize;
+        let between = self.cells[low + 1..high].iter()
+            .map(|value| order(b, *value) + order(*value, a) - order(a, *value) - order(*value, b))
+            .sum::<isize>();
+        between + order(b, a) - order(a, b)
+    }
+
+    /// Pairs of tiles out of order, kept up to date by the moves
+    pub fn get_inversion_count(&self) -> usize {
+        self.inversions
+    }
+
+    /// The first empty slot, row after row
+    pub fn get_empty_tile_coordinates(&self) -> TileCoordinates {
+        let width = self.width.max(1) as usize;
+        TileCoordinates::new((self.empty % width) as i8, (self.empty / width) as i8)
+    }
+
+    /// Every empty slot, row after row
+    pub fn get_empty_tiles_coordinates(&self) -> Vec<TileCoordinates> {
+        let width = self.width.max(1) as usize;
+        self.cells.iter().enumerate()
+            .filter(|(_, value)| self.is_empty_value(TileValue(**value)))
+            .map(|(index, _)| TileCoordinates::new((index % width) as i8, (index / width) as i8))
+            .collect()
+    }
+
+    /// With an even width, a vertical slide changes the inversion parity along with the row of the
+    /// empty slot, so their sum must keep the parity of the solved board.
+    /// On a torus, a slide through an edge of odd length changes the parity alone, every board can be solved.
+    /// So can every board with several empty slots, trading two of them changes the parity without a move
+    pub fn is_solvable(&self) -> bool {
+        if self.empty_count() > 1 {
+            return true;
+        }
+        if self.topology == Topology::Torus && (self.width & 1 == 1 || self.height & 1 == 1) {
+            return true;
+        }
+        let inversion_count = self.get_inversion_count();
+        let empty_tile_coordinates = self.get_empty_tile_coordinates();
+
+        if self.width & 1 == 1 {
+            return inversion_count & 1 == 0;
+        }
+
+        let rows_below_empty = (self.height - 1 - empty_tile_coordinates.j) as usize;
+        (inversion_count + rows_below_empty) & 1 == 0
+    }
+
+    /// Every tile in its cell and upright
+    pub fn is_solved(&self) -> bool {
+        self.misplaced == 0 && self.turned == 0
+    }
+
+    /// Quarter turns clockwise of the tile with `value`, from 0 to 3
+    pub fn rotation(&self, value: TileValue) -> u8 {
+        self.rotations.get(value.0 as usize - 1).copied().unwrap_or(0)
+    }
+
+    /// Turns the tile with `value` by `quarter_turns` clockwise, counterclockwise when negative
+    pub fn rotate(&mut self, value: TileValue, quarter_turns: i8) {
+        let mut rotations = std::mem::take(&mut self.rotations);
+        rotations.resize(self.tiles_nb, 0);
+        let rotation = &mut rotations[value.0 as usize - 1];
+        *rotation = (*rotation as i8 + quarter_turns).rem_euclid(4) as u8;
+        self.set_rotations(rotations);
+    }
+
+    /// Rotations of the tiles by value, as drawn by `seeded_rotations`
+    pub fn set_rotations(&mut self, rotations: Vec<u8>) {
+        self.turned = rotations.iter().filter(|rotation| **rotation != 0).count();
+        self.rotations = rotations;
+    }
+
+    pub fn is_row_solved(&self, j: usize) -> bool {
+        self.tiles.get(j).is_some_and(|row| {
+            row.iter().enumerate().all(|(i, tile)| tile.0 as usize == j * self.width as usize + i + 1)
+        })
+    }
+
+    /// Tiles already where they belong, the empty slots left out
+    pub fn tiles_in_place(&self) -> usize {
+        let empty_in_place = (0..self.cells.len()).filter(|index| self.is_empty_value(TileValue(self.cells[*index])) && self.is_in_place(*index)).count();
+        self.cells.len() - self.misplaced - empty_in_place
+    }
+
+    /// Constant time but for the inversions, counted over the cells between `a` and `b` only
+    pub fn swap_tiles(&mut self, a: TileCoordinates, b: TileCoordinates) {
+        let cell = |coordinates: TileCoordinates| coordinates.j as usize * self.width as usize + coordinates.i as usize;
+        let (a_cell, b_cell) = (cell(a), cell(b));
+        if a_cell == b_cell {
+            return;
+        }
+        self.hash = zobrist::swap(self.hash, self.width as usize, (a_cell, self.cells[a_cell] as u16), (b_cell, self.cells[b_cell] as u16));
+        let inversions = self.inversions as isize + self.inversion_delta(a_cell.min(b_cell), a_cell.max(b_cell));
+        self.inversions = inversions as usize;
+        let misplaced_before = !self.is_in_place(a_cell) as usize + !self.is_in_place(b_cell) as usize;
+        self.cells.swap(a_cell, b_cell);
+        self.misplaced = self.misplaced + !self.is_in_place(a_cell) as usize + !self.is_in_place(b_cell) as usize - misplaced_before;
+        if self.empty == a_cell || self.empty == b_cell {
+            self.empty = self.first_empty();
+        }
+        let temp_tile = self.tiles[a.j as usize][a.i as usize];
+        self.tiles[a.j as usize][a.i as usize] = self.tiles[b.j as usize][b.i as usize];
+        self.tiles[b.j as usize][b.i as usize] = temp_tile;
+    }
+
+    /// Tile that slides into an empty slot when pushed towards `direction`, if there is one on that side.
+    /// With several empty slots, the first one with a tile on that side takes it
+    pub fn get_tile_towards_empty(&self, direction: Direction) -> Option<TileCoordinates> {
+        let (i, j) = direction.offset();
+        let offset = (-i, -j);
+        self.get_empty_tiles_coordinates().into_iter().find_map(|empty| {
+            let tile = empty + offset;
+            let tile = match self.topology {
+                Topology::Flat => ((0..self.width).contains(&tile.i) && (0..self.height).contains(&tile.j)).then_some(tile),
+                Topology::Torus => Some(TileCoordinates::new(tile.i.rem_euclid(self.width), tile.j.rem_euclid(self.height))),
+            }?;
+            (!self.is_empty_value(self.tiles[tile.j as usize][tile.i as usize])).then_some(tile)
+        })
+    }
+
+    /// Empty slot the tile at `tile` slides into, the first one next to it
+    pub fn get_empty_next_to(&self, tile: &TileCoordinates) -> Option<TileCoordinates> {
+        let value = self.tiles.get(tile.j as usize)?.get(tile.i as usize)?;
+        if self.is_empty_value(*value) {
+            return None;
+        }
+        self.get_empty_tiles_coordinates().into_iter().find(|empty| tile.is_neighbour_on(empty, self.topology, self.width, self.height))
+    }
+
+    /// Whether the tile at `tile` can slide into an empty slot
+    pub fn is_movable(&self, tile: &TileCoordinates) -> bool {
+        self.get_empty_next_to(tile).is_some()
+    }
+
+    /// Empty slot on the `direction` side of `tile`, if that side holds one
+    pub fn get_empty_towards(&self, tile: &TileCoordinates, direction: Direction) -> Option<TileCoordinates> {
+        let offset = direction.offset();
+        self.get_empty_tiles_coordinates().into_iter().find(|empty| {
+            let cell = *tile + offset;
+            match self.topology {
+                Topology::Flat => *empty == cell,
+                Topology::Torus => *empty == TileCoordinates::new(cell.i.rem_euclid(self.width), cell.j.rem_euclid(self.height)),
+            }
+        })
+    }
+
+    /// Slides the tile at `tile` into an empty slot, the first one next to it, returns false if there is none
+    pub fn slide(&mut self, tile: TileCoordinates) -> bool {
+        match self.get_empty_next_to(&tile) {
+            Some(empty) => self.slide_into(tile, empty),
+            None => false,
+        }
+    }
+
+    /// Slides the tile at `tile` into the empty slot at `empty`, returns false if they are not neighbours
+    pub fn slide_into(&mut self, tile: TileCoordinates, empty: TileCoordinates) -> bool {
+        if !self.get_empty_tiles_coordinates().contains(&empty)
+            || self.get_empty_tiles_coordinates().contains(&tile)
+            || !tile.is_neighbour_on(&empty, self.topology, self.width, self.height) {
+            return false;
+        }
+        self.swap_tiles(tile, empty);
+        true
+    }
+}
+
+/// Solvable and unsolved board drawn from `seed`, row after row, whatever the board on screen.
+/// Square boards the solver handles are drawn by it so their seeds are shared with the scramble reports
+pub fn seeded_tiles(width: i8, height: i8, seed: u64) -> Vec<TileValue> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    if width == height && width <= MAX_SOLVER_SIZE {
+        let board = loop {
+            let board = Board::random(width as usize, &mut rng);
+            if !board.is_solved() {
+                break board;
+            }
+        };
+        return board.tiles().iter().map(|value| TileValue(*value as i16)).collect();
+    }
+
+    let mut taquin = Taquin::rect(width, height);
+    let mut tiles = (1..=taquin.tiles_nb as i16).map(TileValue).collect::<Vec<TileValue>>();
+    loop {
+        tiles.shuffle(&mut rng);
+        taquin.set_tiles(tiles.chunks(width as usize).map(|row| row.to_vec()).collect());
+        if taquin.is_solvable() && !taquin.is_solved() {
+            return tiles;
+        }
+    }
+}
+
+/// Quarter turns of the tiles of a board of `tiles_nb` cells drawn from `seed`, the empty slot stays upright
+pub fn seeded_rotations(tiles_nb: usize, seed: u64) -> Vec<u8> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut rotations = (1..tiles_nb).map(|_| rng.gen_range(0..4)).collect::<Vec<u8>>();
+    rotations.push(0);
+    rotations
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{coordinates::{Direction, TileCoordinates, Topology}, puzzle::{seeded_rotations, seeded_tiles, Taquin, TileValue}};
+
+    fn rows(width: i8, tiles: &[TileValue]) -> Vec<Vec<TileValue>> {
+        tiles.chunks(width as usize).map(|row| row.to_vec()).collect()
+    }
+
+    /// Every position reachable from the solved board by sliding tiles
+    fn reachable(width: i8, height: i8, topology: Topology) -> HashSet<Vec<i16>> {
+        let mut taquin = Taquin::rect(width, height);
+        taquin.topology = topology;
+        let solved = (1..=taquin.tiles_nb as i16).collect::<Vec<i16>>();
+        let mut seen = HashSet::from([solved.clone()]);
+        let mut frontier = vec![solved];
+        while let Some(position) = frontier.pop() {
+            for direction in Direction::ALL {
+                taquin.set_tiles(rows(width, &position.iter().copied().map(TileValue).collect::<Vec<TileValue>>()));
+                let Some(tile) = taquin.get_tile_towards_empty(direction) else {
+                    continue;
+                };
+                assert!(taquin.slide(tile));
+                let next = taquin.tiles.iter().flatten().map(|value| value.0).collect::<Vec<i16>>();
+                if seen.insert(next.clone()) {
+                    frontier.push(next);
+                }
+            }
+        }
+        seen
+    }
+
+    fn shuffled(tiles: Vec<Vec<TileValue>>) -> Taquin {
+        let mut taquin = Taquin::new(2);
+        taquin.set_tiles(tiles);
+        taquin.is_shuffled = true;
+        taquin
+    }
+
+    fn permutations(values: Vec<i16>) -> Vec<Vec<i16>> {
+        if values.len() <= 1 {
+            return vec![values];
+        }
+        (0..values.len()).flat_map(|index| {
+            let mut rest = values.clone();
+            let first = rest.remove(index);
+            permutations(rest).into_iter().map(move |mut permutation| {
+                permutation.insert(0, first);
+                permutation
+            })
+        }).collect()
+    }
+
+    #[test]
+    fn test_is_solvable() {
+        assert!(shuffled(vec![vec![TileValue(1), TileValue(2)], vec![TileValue(3), TileValue(4)]]).is_solvable());
+        assert!(shuffled(vec![vec![TileValue(4), TileValue(3)], vec![TileValue(2), TileValue(1)]]).is_solvable());
+        assert!(shuffled(vec![vec![TileValue(2), TileValue(3)], vec![TileValue(1), TileValue(4)]]).is_solvable());
+    }
+
+    #[test]
+    fn test_is_not_solvable() {
+        assert!(!shuffled(vec![vec![TileValue(2), TileValue(1)], vec![TileValue(3), TileValue(4)]]).is_solvable());
+        assert!(!shuffled(vec![vec![TileValue(4), TileValue(1)], vec![TileValue(2), TileValue(3)]]).is_solvable());
+    }
+
+    #[test]
+    fn test_solvability_of_every_small_board() {
+        for (width, height) in [(2, 2), (3, 2), (2, 3)] {
+            let reachable = reachable(width, height, Topology::Flat);
+            let positions = permutations((1..=(width * height) as i16).collect());
+            // Exactly half of the positions can be reached
+            assert_eq!(reachable.len() * 2, positions.len());
+            let mut taquin = Taquin::rect(width, height);
+            for position in positions {
+                taquin.set_tiles(rows(width, &position.iter().copied().map(TileValue).collect::<Vec<TileValue>>()));
+                assert_eq!(taquin.is_solvable(), reachable.contains(&position), "{position:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_torus_solvability() {
+        for (width, height) in [(2, 2), (3, 2), (2, 3), (4, 2)] {
+            let reachable = reachable(width, height, Topology::Torus);
+            let positions = permutations((1..=(width * height) as i16).collect());
+            // An edge of odd length makes every position reachable, else half of them as on a flat board
+            let all = width % 2 == 1 || height % 2 == 1;
+            assert_eq!(reachable.len() * if all { 1 } else { 2 }, positions.len());
+            let mut taquin = Taquin::rect(width, height);
+            taquin.topology = Topology::Torus;
+            for position in positions {
+                taquin.set_tiles(rows(width, &position.iter().copied().map(TileValue).collect::<Vec<TileValue>>()));
+                assert_eq!(taquin.is_solvable(), reachable.contains(&position), "{position:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_torus_slides() {
+        let mut taquin = Taquin::rect(3, 2);
+        taquin.topology = Topology::Torus;
+        taquin.set_tiles(rows(3, &(1..=6).map(TileValue).collect::<Vec<TileValue>>()));
+        assert_eq!(taquin.get_tile_towards_empty(Direction::Right), Some(TileCoordinates::new(1, 1)));
+        assert_eq!(taquin.get_tile_towards_empty(Direction::Left), Some(TileCoordinates::new(0, 1)));
+        assert_eq!(taquin.get_tile_towards_empty(Direction::Up), Some(TileCoordinates::new(2, 0)));
+        assert!(taquin.slide(TileCoordinates::new(0, 1)));
+        assert_eq!(taquin.get_empty_tile_coordinates(), TileCoordinates::new(0, 1));
+        assert!(!taquin.slide(TileCoordinates::new(2, 0)));
+    }
+
+    #[test]
+    fn test_tile_towards_empty() {
+        let mut taquin = Taquin::new(2);
+        taquin.set_tiles(vec![vec![TileValue(1), TileValue(2)], vec![TileValue(3), TileValue(4)]]);
+        assert_eq!(taquin.get_tile_towards_empty(Direction::Right), Some(TileCoordinates::new(0, 1)));
+        assert_eq!(taquin.get_tile_towards_empty(Direction::Down), Some(TileCoordinates::new(1, 0)));
+        assert_eq!(taquin.get_tile_towards_empty(Direction::Left), None);
+        assert_eq!(taquin.get_tile_towards_empty(Direction::Up), None);
+    }
+
+    #[test]
+    fn test_rectangular_board() {
+        let mut taquin = Taquin::rect(4, 3);
+        taquin.set_tiles((1..=12).map(TileValue).collect::<Vec<_>>().chunks(4).map(|row| row.to_vec()).collect());
+        assert!(taquin.is_solvable());
+        assert!(taquin.is_row_solved(2));
+        assert_eq!(taquin.get_tile_towards_empty(Direction::Right), Some(TileCoordinates::new(2, 2)));
+        assert_eq!(taquin.get_next_selection_coordinates(&TileCoordinates::new(0, 0), Direction::Up), TileCoordinates::new(0, 2));
+        assert_eq!(taquin.get_next_selection_coordinates(&TileCoordinates::new(2, 2), Direction::Right), TileCoordinates::new(0, 2));
+
+        // A vertical slide keeps the board solvable, swapping two tiles does not
+        taquin.swap_tiles(TileCoordinates::new(3, 2), TileCoordinates::new(3, 1));
+        assert!(taquin.is_solvable());
+        taquin.swap_tiles(TileCoordinates::new(0, 0), TileCoordinates::new(1, 0));
+        assert!(!taquin.is_solvable());
+    }
+
+    /// The checks kept up to date by the moves agree with a board counted from scratch
+    #[test]
+    fn test_incremental_checks() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for (width, height) in [(4, 4), (5, 3)] {
+            let mut taquin = Taquin::rect(width, height);
+            taquin.set_tiles(rows(width, &seeded_tiles(width, height, 11)));
+            for _ in 0..500 {
+                let (a, b) = (rng.gen_range(0..taquin.tiles_nb), rng.gen_range(0..taquin.tiles_nb));
+                let cell = |index: usize| TileCoordinates::new((index % width as usize) as i8, (index / width as usize) as i8);
+                taquin.swap_tiles(cell(a), cell(b));
+
+                let mut counted = Taquin::rect(width, height);
+                counted.set_tiles(taquin.tiles.clone());
+                assert_eq!(taquin.get_inversion_count(), counted.get_inversion_count());
+                assert_eq!(taquin.get_empty_tile_coordinates(), counted.get_empty_tile_coordinates());
+                assert_eq!((taquin.is_solved(), taquin.tiles_in_place()), (counted.is_solved(), counted.tiles_in_place()));
+                assert_eq!(taquin.hash(), counted.hash());
+            }
+        }
+    }
+
+    #[test]
+    fn test_several_empty_slots() {
+        let mut taquin = Taquin::rect(3, 2);
+        taquin.set_tiles(rows(3, &(1..=6).map(TileValue).collect::<Vec<TileValue>>()));
+        taquin.set_empty_count(2);
+        assert!(taquin.is_solved());
+        assert_eq!(taquin.get_empty_tiles_coordinates(), vec![TileCoordinates::new(1, 1), TileCoordinates::new(2, 1)]);
+        // The empty slots trading places leave the board solved
+        taquin.swap_tiles(TileCoordinates::new(1, 1), TileCoordinates::new(2, 1));
+        assert!(taquin.is_solved());
+        assert!(taquin.is_movable(&TileCoordinates::new(1, 0)) && taquin.is_movable(&TileCoordinates::new(2, 0)));
+        assert!(!taquin.is_movable(&TileCoordinates::new(0, 0)) && !taquin.is_movable(&TileCoordinates::new(1, 1)));
+        assert!(taquin.slide(TileCoordinates::new(0, 1)));
+        assert_eq!((taquin.is_solved(), taquin.tiles_in_place()), (false, 3));
+        assert_eq!(taquin.get_tile_towards_empty(Direction::Left), Some(TileCoordinates::new(1, 1)));
+        assert_ne!(taquin.draw_tiles(3), (1..=6).map(TileValue).collect::<Vec<TileValue>>());
+
+        // Every position can be reached, the empty slots being alike
+        for (width, height) in [(2, 2), (3, 2)] {
+            let mut taquin = Taquin::rect(width, height);
+            taquin.set_empty_count(2);
+            let looks = |taquin: &Taquin| taquin.tiles.iter().flatten().map(|value| if taquin.is_empty_value(*value) { 0 } else { value.0 }).collect::<Vec<i16>>();
+            let solved = (1..=(width * height) as i16).map(TileValue).collect::<Vec<TileValue>>();
+            taquin.set_tiles(rows(width, &solved));
+            let mut seen = HashSet::from([looks(&taquin)]);
+            let mut frontier = vec![solved];
+            while let Some(position) = frontier.pop() {
+                for cell in (0..width * height).map(|index| TileCoordinates::new(index % width, index / width)) {
+                    for direction in Direction::ALL {
+                        taquin.set_tiles(rows(width, &position));
+                        let Some(empty) = taquin.get_empty_towards(&cell, direction) else {
+                            continue;
+                        };
+                        if taquin.slide_into(cell, empty) && seen.insert(looks(&taquin)) {
+                            frontier.push(taquin.tiles.iter().flatten().copied().collect());
+                        }
+                    }
+                }
+            }
+            let positions = (1..=(width * height) as usize).product::<usize>() / 2;
+            assert_eq!(seen.len(), positions);
+        }
+    }
+
+    #[test]
+    fn test_rotations() {
+        let mut taquin = Taquin::new(2);
+        taquin.set_tiles(rows(2, &(1..=4).map(TileValue).collect::<Vec<TileValue>>()));
+        taquin.rotate(TileValue(2), -1);
+        assert_eq!(taquin.rotation(TileValue(2)), 3);
+        assert!(!taquin.is_solved());
+        taquin.rotate(TileValue(2), 1);
+        assert!(taquin.is_solved());
+        let rotations = seeded_rotations(9, 5);
+        assert_eq!(rotations, seeded_rotations(9, 5));
+        assert_eq!((rotations.len(), rotations[8]), (9, 0));
+        taquin.set_rotations(vec![0, 2, 0, 0]);
+        assert_eq!(taquin.rotation(TileValue(2)), 2);
+        assert!(!taquin.is_solved());
+    }
+
+    #[test]
+    fn test_seeded_tiles() {
+        assert_eq!(seeded_tiles(4, 4, 42), seeded_tiles(4, 4, 42));
+        assert_ne!(seeded_tiles(4, 4, 42), seeded_tiles(4, 4, 43));
+
+        for (width, height) in [(3, 3), (4, 3), (2, 5)] {
+            let tiles = seeded_tiles(width, height, 7);
+            let mut taquin = Taquin::rect(width, height);
+            taquin.set_tiles(tiles.chunks(width as usize).map(|row| row.to_vec()).collect());
+            assert!(taquin.is_solvable());
+            assert!(!taquin.is_solved());
+        }
+    }
+
+    /// Chi-squared over many seeds: every solvable unsolved board should come up as often, on the square boards
+    /// drawn by the solver as on the rectangular ones
+    #[test]
+    fn test_seeded_tiles_are_uniform() {
+        const DRAWS_PER_BOARD: usize = 60;
+        for (width, height) in [(2, 2), (3, 2), (2, 3)] {
+            let solved = (1..=(width * height) as i16).collect::<Vec<i16>>();
+            let boards = reachable(width, height, Topology::Flat).into_iter().filter(|board| *board != solved).collect::<HashSet<Vec<i16>>>();
+            let draws = boards.len() * DRAWS_PER_BOARD;
+            let mut counts = HashMap::<Vec<i16>, usize>::new();
+            for seed in 0..draws as u64 {
+                let board = seeded_tiles(width, height, seed).iter().map(|value| value.0).collect::<Vec<i16>>();
+                assert!(boards.contains(&board), "{width}x{height} seed {seed} drew {board:?}");
+                *counts.entry(board).or_default() += 1;
+            }
+            assert_eq!(counts.len(), boards.len(), "{width}x{height} boards never drawn");
+
+            let expected = DRAWS_PER_BOARD as f64;
+            let chi_squared = counts.values().map(|count| (*count as f64 - expected).powi(2) / expected).sum::<f64>();
+            // Wilson-Hilferty approximation of the 99.95th percentile of the chi-squared distribution
+            let freedom = (boards.len() - 1) as f64;
+            let spread = 2. / (9. * freedom);
+            let critical = freedom * (1. - spread + 3.29 * spread.sqrt()).powi(3);
+            assert!(chi_squared < critical, "{width}x{height}: chi-squared {chi_squared} over {critical}");
+        }
+    }
+
+    #[test]
+    fn test_mega_board_tiles() {
+        let tiles = seeded_tiles(50, 50, 7);
+        let mut values = tiles.iter().map(|value| value.0).collect::<Vec<i16>>();
+        values.sort();
+        assert_eq!(values, (1..=2500).collect::<Vec<i16>>());
+        let mut taquin = Taquin::rect(50, 50);
+        taquin.set_tiles(tiles.chunks(50).map(|row| row.to_vec()).collect());
+        assert!(taquin.is_solvable() && !taquin.is_solved());
+        assert_eq!(TileValue(2500).goal(50), TileCoordinates::new(49, 49));
+        assert_eq!(TileValue::for_cell(TileCoordinates::new(49, 49), 50), TileValue(2500));
+    }
+}
@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+
+use crate::{
+    marker::Markers,
+    taquin::{is_solver_board, Taquin},
+    tile::TileCoordinates,
+    AppState,
+};
+
+const OVERLAY_KEY: KeyCode = KeyCode::Z;
+/// Opacity of the regions left to solve
+const REGION_ALPHA: f32 = 0.3;
+/// Opacity change per second when a region is completed or broken again
+const FADE_SPEED: f32 = 1.;
+/// Distance in front of the tiles, so the tint covers them
+const OVERLAY_OFFSET: f32 = 0.04;
+const REGION_COLORS: [Color; 4] = [
+    Color::rgb(0.9, 0.3, 0.3),
+    Color::rgb(0.3, 0.5, 0.9),
+    Color::rgb(0.3, 0.8, 0.4),
+    Color::rgb(0.9, 0.8, 0.2),
+];
+
+pub struct QuadrantsPlugin;
+
+impl Plugin for QuadrantsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<QuadrantOverlay>()
+            .add_systems(Update, (
+                toggle_quadrant_overlay,
+                rebuild_quadrant_overlay.run_if(is_solver_board),
+                fade_quadrant_regions.run_if(|overlay: Res<QuadrantOverlay>| overlay.enabled),
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// Cells of each region in the recommended solve order: the top row, then the left column
+/// of what is left, again and again until the last 2x2 square
+pub fn solve_regions(size: i8) -> Vec<Vec<TileCoordinates>> {
+    let mut regions = Vec::new();
+    let mut top = 0;
+    let mut left = 0;
+    while size - top > 2 || size - left > 2 {
+        if size - top >= size - left {
+            regions.push((left..size).map(|i| TileCoordinates::new(i, top)).collect());
+            top += 1;
+        } else {
+            regions.push((top..size).map(|j| TileCoordinates::new(left, j)).collect());
+            left += 1;
+        }
+    }
+    regions.push((top..size).flat_map(|j| (left..size).map(move |i| TileCoordinates::new(i, j))).collect());
+    regions
+}
+
+fn is_region_completed(taquin: &Taquin, cells: &[TileCoordinates]) -> bool {
+    cells.iter().all(|cell| {
+        taquin.tiles.get(cell.j as usize).and_then(|row| row.get(cell.i as usize))
+            .is_some_and(|tile| tile.goal(taquin.width) == *cell)
+    })
+}
+
+/// Tints the board by solve region, Z toggles it
+#[derive(Resource, Default)]
+struct QuadrantOverlay {
+    enabled: bool,
+    /// Board size the quads were spawned for
+    size: i8,
+    regions: Vec<(Vec<TileCoordinates>, Handle<StandardMaterial>)>,
+}
+
+#[derive(Component)]
+struct RegionQuad;
+
+fn toggle_quadrant_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut overlay: ResMut<QuadrantOverlay>,
+) {
+    if keyboard_input.just_released(OVERLAY_KEY) {
+        overlay.enabled = !overlay.enabled;
+        info!(enabled = overlay.enabled, "quadrant overlay");
+    }
+}
+
+/// Spawns a quad over each cell when the overlay is turned on or the board size changes
+fn rebuild_quadrant_overlay(
+    mut commands: Commands,
+    mut overlay: ResMut<QuadrantOverlay>,
+    taquin: Res<Taquin>,
+    markers: Res<Markers>,
+    quads_query: Query<Entity, With<RegionQuad>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !overlay.is_changed() && overlay.size == taquin.width {
+        return;
+    }
+    for entity in quads_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    overlay.size = taquin.width;
+    overlay.regions.clear();
+    if !overlay.enabled {
+        return;
+    }
+    let mesh = meshes.add(Mesh::from(shape::Quad::new(markers.tile_size(taquin.width, taquin.height))));
+    for (index, cells) in solve_regions(taquin.width).into_iter().enumerate() {
+        let alpha = if is_region_completed(&taquin, &cells) { 0. } else { REGION_ALPHA };
+        let material = materials.add(StandardMaterial {
+            base_color: REGION_COLORS[index % REGION_COLORS.len()].with_a(alpha),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+        for cell in &cells {
+            commands.spawn((PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_translation(markers.tile_translation(taquin.width, taquin.height, *cell) + Vec3::Z * OVERLAY_OFFSET),
+                ..default()
+            }, RegionQuad));
+        }
+        overlay.regions.push((cells, material));
+    }
+}
+
+/// Completed regions fade out, and back in if a tile is moved out of them
+fn fade_quadrant_regions(
+    time: Res<Time>,
+    overlay: Res<QuadrantOverlay>,
+    taquin: Res<Taquin>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (cells, handle) in &overlay.regions {
+        let target = if is_region_completed(&taquin, cells) { 0. } else { REGION_ALPHA };
+        let Some(material) = materials.get(handle).filter(|material| material.base_color.a() != target) else {
+            continue;
+        };
+        let alpha = material.base_color.a();
+        let step = FADE_SPEED * time.delta_seconds();
+        let alpha = if alpha < target { (alpha + step).min(target) } else { (alpha - step).max(target) };
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color.set_a(alpha);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{quadrants::solve_regions, tile::TileCoordinates};
+
+    #[test]
+    fn test_solve_regions() {
+        let regions = solve_regions(3);
+        assert_eq!(regions.len(), 3);
+        assert_eq!(regions[0], vec![TileCoordinates::new(0, 0), TileCoordinates::new(1, 0), TileCoordinates::new(2, 0)]);
+        assert_eq!(regions[1], vec![TileCoordinates::new(0, 1), TileCoordinates::new(0, 2)]);
+        assert_eq!(regions[2].len(), 4);
+
+        for size in 3..=6 {
+            let regions = solve_regions(size);
+            assert_eq!(regions.len(), 2 * (size as usize - 2) + 1);
+            assert_eq!(regions.iter().map(Vec::len).sum::<usize>(), (size * size) as usize);
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use std::{
+    io::{self, Read},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::{MAX_EMPTY_SLOTS, MAX_SIZE},
+    i18n::Localized,
+    line_stream::{take_lines, LineStream},
+    locale::Locale,
+    pause::GameClock,
+    rotation::RotatingTiles,
+    taquin::{MoveCount, ShuffleRequested, ShuffleSeed, Taquin, TaquinShuffled, TaquinSolved, TileMoved},
+    tile::Topology,
+    AppState,
+};
+
+/// Bumped whenever the messages change, both sides of a race must agree on it
+const PROTOCOL_VERSION: u32 = 2;
+/// How long joining waits for the host to answer
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+/// Width in pixels of the progress bar of the rival
+const BAR_WIDTH: f32 = 200.;
+
+pub struct RacePlugin;
+
+impl Plugin for RacePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::MainMenu), leave_race)
+            .add_systems(Update, (
+                accept_rival.run_if(resource_exists::<RaceHost>()),
+                receive_rival_progress.run_if(resource_exists::<RaceConnection>()),
+            ).run_if(in_state(AppState::Running)))
+            .add_systems(Update, (
+                start_race,
+                start_race_clock.run_if(on_event::<TaquinShuffled>()),
+                finish_race.run_if(on_event::<TaquinSolved>()),
+                send_progress.run_if(resource_exists::<RaceConnection>()
+                    .and_then(on_event::<TaquinShuffled>().or_else(on_event::<TileMoved>()).or_else(on_event::<TaquinSolved>()))),
+            ).chain().run_if(in_state(AppState::Running).and_then(resource_exists::<Race>())))
+            .add_systems(Update, (
+                despawn_race_panel.run_if(not(resource_exists::<Race>()).and_then(not(resource_exists::<RaceHost>()))),
+                setup_race_panel.run_if(resource_added::<Race>().or_else(resource_added::<RaceHost>())),
+                update_race_panel.run_if(resource_exists::<Race>().and_then(resource_changed::<Race>())),
+            ).chain());
+    }
+}
+
+/// Sent once by the host as the rival joins: the board to shuffle and the seed to shuffle it with
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RaceHello {
+    version: u32,
+    pub width: i8,
+    pub height: i8,
+    pub topology: Topology,
+    pub empty_count: usize,
+    /// Whether the shuffle also turns the tiles, see `rotation`
+    pub rotating_tiles: bool,
+    pub seed: u64,
+}
+
+/// Where a player stands, sent after each of their moves
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+struct RaceProgress {
+    moves: usize,
+    /// Tiles where they belong, see `Taquin::tiles_in_place`
+    in_place: usize,
+    /// Seconds from the shuffle, once solved
+    solved_in: Option<f32>,
+}
+
+/// One JSON object per line
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+enum RaceMessage {
+    Hello(RaceHello),
+    Progress(RaceProgress),
+}
+
+/// Waits for a rival, `--race-host` opens it
+#[derive(Resource)]
+pub struct RaceHost {
+    listener: TcpListener,
+    port: u16,
+}
+
+impl RaceHost {
+    pub fn bind(address: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        info!(%address, "waiting for a rival");
+        Ok(Self { listener, port: address.port() })
+    }
+}
+
+/// The stream to the rival, read and written without blocking each frame
+#[derive(Resource)]
+pub struct RaceConnection(LineStream);
+
+impl RaceConnection {
+    fn new(stream: TcpStream, buffer: Vec<u8>) -> io::Result<Self> {
+        LineStream::new(stream, buffer).map(Self)
+    }
+
+    /// What the rival cannot take yet is sent on the next frames
+    fn send(&mut self, message: &RaceMessage) -> io::Result<()> {
+        self.0.send(&encode(message)?)
+    }
+
+    /// Messages fully received since the last call, an error once the rival is gone or sends lines too long
+    fn receive(&mut self) -> io::Result<Vec<RaceMessage>> {
+        self.0.receive()?.iter().map(|line| serde_json::from_slice(line).map_err(io::Error::from)).collect()
+    }
+}
+
+/// The race against the rival on the other end of `RaceConnection`
+#[derive(Resource, Default)]
+pub struct Race {
+    /// Seed of the race shuffle, until it is asked for
+    pending_seed: Option<u64>,
+    started_at: Option<f32>,
+    /// Seconds the player took, once solved
+    solved_in: Option<f32>,
+    rival: RaceProgress,
+    rival_left: bool,
+}
+
+impl Race {
+    pub fn new(seed: u64) -> Self {
+        Self { pending_seed: Some(seed), ..default() }
+    }
+}
+
+#[derive(Component)]
+struct RacePanel;
+
+#[derive(Component)]
+struct RaceStatusText;
+
+#[derive(Component)]
+struct RaceBarFill;
+
+impl RaceHello {
+    /// The board of the host is played as is, it goes through the checks of a board code
+    fn validate(&self) -> Result<(), String> {
+        if self.version != PROTOCOL_VERSION {
+            return Err(format!("the host speaks version {} of the race, this game speaks version {PROTOCOL_VERSION}", self.version));
+        }
+        if !(2..=MAX_SIZE).contains(&self.width) || !(2..=MAX_SIZE).contains(&self.height) {
+            return Err(format!("the host board is {}x{}, its sides are not between 2 and {MAX_SIZE}", self.width, self.height));
+        }
+        if !(1..=MAX_EMPTY_SLOTS).contains(&self.empty_count) {
+            return Err(format!("the host board has {} empty slots, not between 1 and {MAX_EMPTY_SLOTS}", self.empty_count));
+        }
+        if self.empty_count >= self.width as usize * self.height as usize {
+            return Err(format!("{} empty slots leave no tile on a {}x{} board", self.empty_count, self.width, self.height));
+        }
+        Ok(())
+    }
+}
+
+fn encode(message: &RaceMessage) -> io::Result<Vec<u8>> {
+    let mut bytes = serde_json::to_vec(message)?;
+    bytes.push(b'\n');
+    Ok(bytes)
+}
+
+/// Complete lines are taken out of `buffer`, what is left of a message still arriving stays in it
+fn take_messages(buffer: &mut Vec<u8>) -> io::Result<Vec<RaceMessage>> {
+    take_lines(buffer)?.iter().map(|line| serde_json::from_slice(line).map_err(io::Error::from)).collect()
+}
+
+/// Connects to the host and waits for the board of the race, before the game opens
+pub fn join(address: &str) -> Result<(RaceConnection, RaceHello), String> {
+    let address = address.to_socket_addrs()
+        .map_err(|error| error.to_string())?
+        .next()
+        .ok_or_else(|| "no such address".to_string())?;
+    let mut stream = TcpStream::connect_timeout(&address, JOIN_TIMEOUT).map_err(|error| error.to_string())?;
+    stream.set_read_timeout(Some(JOIN_TIMEOUT)).map_err(|error| error.to_string())?;
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 1024];
+    let hello = loop {
+        let read = stream.read(&mut chunk).map_err(|error| error.to_string())?;
+        if read == 0 {
+            return Err("the host left".to_string());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        let messages = take_messages(&mut buffer).map_err(|error| error.to_string())?;
+        match messages.first() {
+            Some(RaceMessage::Hello(hello)) => break *hello,
+            Some(message) => return Err(format!("unexpected {message:?}")),
+            None => continue,
+        }
+    };
+    hello.validate()?;
+    info!(%address, ?hello, "race joined");
+    let connection = RaceConnection::new(stream, buffer).map_err(|error| error.to_string())?;
+    Ok((connection, hello))
+}
+
+/// The first rival to connect gets the board on screen and a fresh seed, the host stops listening
+fn accept_rival(
+    mut commands: Commands,
+    host: Res<RaceHost>,
+    taquin: Res<Taquin>,
+    rotating_tiles: Res<RotatingTiles>,
+) {
+    let (stream, address) = match host.listener.accept() {
+        Ok(accepted) => accepted,
+        Err(error) if error.kind() == io::ErrorKind::WouldBlock => return,
+        Err(error) => {
+            warn!("cannot accept a rival: {error}");
+            return;
+        },
+    };
+    let hello = RaceHello {
+        version: PROTOCOL_VERSION,
+        width: taquin.width,
+        height: taquin.height,
+        topology: taquin.topology,
+        empty_count: taquin.empty_count(),
+        rotating_tiles: rotating_tiles.0,
+        seed: rand::random(),
+    };
+    let connection = RaceConnection::new(stream, Vec::new()).and_then(|mut connection| {
+        connection.send(&RaceMessage::Hello(hello))?;
+        Ok(connection)
+    });
+    match connection {
+        Ok(connection) => {
+            info!(%address, "rival joined");
+            commands.remove_resource::<RaceHost>();
+            commands.insert_resource(connection);
+            commands.insert_resource(Race::new(hello.seed));
+        },
+        Err(error) => warn!(%address, "cannot start the race: {error}"),
+    }
+}
+
+fn receive_rival_progress(
+    mut commands: Commands,
+    mut connection: ResMut<RaceConnection>,
+    mut race: ResMut<Race>,
+) {
+    match connection.receive() {
+        Ok(messages) => {
+            for message in messages {
+                match message {
+                    RaceMessage::Progress(progress) => race.rival = progress,
+                    RaceMessage::Hello(hello) => warn!(?hello, "unexpected hello"),
+                }
+            }
+        },
+        Err(error) => {
+            info!("rival left: {error}");
+            commands.remove_resource::<RaceConnection>();
+            race.rival_left = true;
+        },
+    }
+}
+
+/// Both sides shuffle with the seed of the host as soon as they play
+fn start_race(
+    mut commands: Commands,
+    mut race: ResMut<Race>,
+    mut seed: ResMut<ShuffleSeed>,
+) {
+    let Some(pending_seed) = race.pending_seed else {
+        return;
+    };
+    race.pending_seed = None;
+    seed.next = Some(pending_seed);
+    commands.init_resource::<ShuffleRequested>();
+}
+
+fn start_race_clock(
+    mut race: ResMut<Race>,
+    clock: Res<GameClock>,
+) {
+    race.started_at = Some(clock.elapsed_seconds());
+    race.solved_in = None;
+}
+
+fn finish_race(
+    mut race: ResMut<Race>,
+    clock: Res<GameClock>,
+) {
+    let Some(started_at) = race.started_at else {
+        return;
+    };
+    let solved_in = clock.elapsed_seconds() - started_at;
+    info!(solved_in, rival = ?race.rival.solved_in, "race finished");
+    race.solved_in = Some(solved_in);
+}
+
+fn send_progress(
+    mut commands: Commands,
+    mut connection: ResMut<RaceConnection>,
+    mut race: ResMut<Race>,
+    taquin: Res<Taquin>,
+    move_count: Res<MoveCount>,
+) {
+    let progress = RaceProgress { moves: move_count.0, in_place: taquin.tiles_in_place(), solved_in: race.solved_in };
+    if let Err(error) = connection.send(&RaceMessage::Progress(progress)) {
+        info!("rival left: {error}");
+        commands.remove_resource::<RaceConnection>();
+        race.rival_left = true;
+    }
+}
+
+/// Going back to the menu ends the race, the rival sees it left
+fn leave_race(mut commands: Commands) {
+    commands.remove_resource::<RaceHost>();
+    commands.remove_resource::<RaceConnection>();
+    commands.remove_resource::<Race>();
+}
+
+fn setup_race_panel(
+    mut commands: Commands,
+    host: Option<Res<RaceHost>>,
+    locale: Res<Locale>,
+    panel_query: Query<Entity, With<RacePanel>>,
+) {
+    // The waiting panel of the host makes way for the race one
+    for entity in panel_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let status = host.map_or_else(|| Localized::new(""), |host| Localized::new("Waiting for a rival on port {port}").with_arg("port", host.port));
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.),
+                top: Val::Percent(12.),
+                padding: UiRect::all(Val::Px(10.)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.),
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.7).into(),
+            ..default()
+        }, RacePanel))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ).with_text_alignment(locale.start_alignment()),
+                RaceStatusText,
+                status,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Px(10.),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((NodeBundle {
+                        style: Style {
+                            width: Val::Percent(0.),
+                            height: Val::Percent(100.),
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.4, 0.8, 0.5).into(),
+                        ..default()
+                    }, RaceBarFill));
+                });
+        });
+}
+
+fn despawn_race_panel(
+    mut commands: Commands,
+    panel_query: Query<Entity, With<RacePanel>>,
+) {
+    for entity in panel_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// The bar fills with the tiles the rival has in place
+fn update_race_panel(
+    race: Res<Race>,
+    taquin: Res<Taquin>,
+    locale: Res<Locale>,
+    mut status_query: Query<&mut Localized, With<RaceStatusText>>,
+    mut fill_query: Query<&mut Style, With<RaceBarFill>>,
+) {
+    let rival = race.rival;
+    let tiles = taquin.tiles_nb - taquin.empty_count();
+    let status = match (race.solved_in, rival.solved_in) {
+        (Some(solved_in), Some(rival_time)) if rival_time < solved_in => Localized::new("Rival won in {time}").with_arg("time", locale.format_duration(rival_time)),
+        (None, Some(rival_time)) => Localized::new("Rival won in {time}").with_arg("time", locale.format_duration(rival_time)),
+        (Some(solved_in), _) => Localized::new("You won in {time}!").with_arg("time", locale.format_duration(solved_in)),
+        (None, None) if race.rival_left => Localized::new("Rival left the race"),
+        (None, None) => Localized::new("Rival: {moves} moves, {in_place}/{tiles} tiles")
+            .with_arg("moves", rival.moves)
+            .with_arg("in_place", rival.in_place)
+            .with_arg("tiles", tiles),
+    };
+    for mut localized in status_query.iter_mut() {
+        if *localized != status {
+            *localized = status.clone();
+        }
+    }
+    let filled = rival.in_place as f32 / tiles.max(1) as f32;
+    for mut style in fill_query.iter_mut() {
+        style.width = Val::Percent(filled * 100.);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cli::MAX_SIZE,
+        line_stream::MAX_LINE_BYTES,
+        race::{encode, take_messages, RaceHello, RaceMessage, RaceProgress, PROTOCOL_VERSION},
+        tile::Topology,
+    };
+
+    #[test]
+    fn test_take_messages() {
+        let hello = RaceMessage::Hello(RaceHello { version: PROTOCOL_VERSION, width: 4, height: 3, topology: Topology::Torus, empty_count: 2, rotating_tiles: true, seed: 42 });
+        let progress = RaceMessage::Progress(RaceProgress { moves: 12, in_place: 5, solved_in: Some(31.5) });
+        let bytes = [encode(&hello).unwrap(), encode(&progress).unwrap()].concat();
+
+        // A message cut in two is only read once its end arrives
+        let (start, end) = bytes.split_at(bytes.len() - 4);
+        let mut buffer = start.to_vec();
+        assert_eq!(take_messages(&mut buffer).unwrap(), vec![hello]);
+        buffer.extend_from_slice(end);
+        assert_eq!(take_messages(&mut buffer).unwrap(), vec![progress]);
+        assert!(buffer.is_empty());
+
+        let mut garbage = b"{\"Progress\":3}\n".to_vec();
+        assert!(take_messages(&mut garbage).is_err());
+        // A rival never ending its line is not waited for
+        let mut endless = vec![b' '; MAX_LINE_BYTES + 1];
+        assert!(take_messages(&mut endless).is_err());
+    }
+
+    #[test]
+    fn test_hello_validation() {
+        let hello = RaceHello { version: PROTOCOL_VERSION, width: 4, height: 3, topology: Topology::Flat, empty_count: 2, rotating_tiles: false, seed: 42 };
+        assert!(hello.validate().is_ok());
+        assert!(RaceHello { version: PROTOCOL_VERSION + 1, ..hello }.validate().is_err());
+        assert!(RaceHello { width: 1, ..hello }.validate().is_err());
+        assert!(RaceHello { height: MAX_SIZE + 1, ..hello }.validate().is_err());
+        assert!(RaceHello { empty_count: 0, ..hello }.validate().is_err());
+        assert!(RaceHello { width: 2, height: 2, empty_count: 3, ..hello }.validate().is_ok());
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    stats::GameRecorded,
+    AppState,
+};
+
+pub struct RecordsPlugin;
+
+impl Plugin for RecordsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<BestRecords>()
+            .add_event::<NewRecord>()
+            .add_systems(Update, update_records.run_if(in_state(AppState::Running).and_then(on_event::<GameRecorded>())));
+    }
+}
+
+/// Sent when a solve beats the best time or move count of its size
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct NewRecord {
+    pub time: bool,
+    pub moves: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SizeRecord {
+    /// Seconds
+    pub best_time: Option<f32>,
+    /// Moves with the hint penalties
+    pub best_moves: Option<usize>,
+}
+
+impl SizeRecord {
+    /// Keeps the better of each, tells which ones were beaten
+    pub fn update(&mut self, time: f32, moves: usize) -> NewRecord {
+        let record = NewRecord {
+            time: self.best_time.is_none_or(|best| time < best),
+            moves: self.best_moves.is_none_or(|best| moves < best),
+        };
+        if record.time {
+            self.best_time = Some(time);
+        }
+        if record.moves {
+            self.best_moves = Some(moves);
+        }
+        record
+    }
+
+    /// Keeps the better of each from another device
+    pub fn merge(&mut self, other: &SizeRecord) {
+        self.best_time = self.best_time.into_iter().chain(other.best_time).reduce(f32::min);
+        self.best_moves = self.best_moves.into_iter().chain(other.best_moves).min();
+    }
+}
+
+/// Best time and move count of each size, kept between sessions
+#[derive(Resource, Serialize, Deserialize, Debug)]
+pub struct BestRecords {
+    pub version: u32,
+    pub sizes: BTreeMap<i8, SizeRecord>,
+}
+
+impl Versioned for BestRecords {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl FromWorld for BestRecords {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().records();
+        let empty = BestRecords { version: Self::VERSION, sizes: BTreeMap::new() };
+        if !persistence::exists(&path) {
+            return empty;
+        }
+        persistence::load_versioned(path, Format::Ron).unwrap_or_else(|error| {
+            error!("cannot load best records: {error}");
+            empty
+        })
+    }
+}
+
+fn update_records(
+    mut recorded_events: EventReader<GameRecorded>,
+    mut records: ResMut<BestRecords>,
+    paths: Res<DataPaths>,
+    mut record_events: EventWriter<NewRecord>,
+) {
+    let Some(GameRecorded(game)) = recorded_events.read().last() else {
+        return;
+    };
+    let record = records.sizes.entry(game.size).or_default().update(game.time, game.score());
+    if !record.time && !record.moves {
+        return;
+    }
+    info!(size = game.size, time = record.time, moves = record.moves, "new record");
+    if let Err(error) = persistence::save_versioned(paths.records(), &*records, Format::Ron) {
+        error!("cannot save best records: {error}");
+    }
+    record_events.send(record);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::records::{NewRecord, SizeRecord};
+
+    #[test]
+    fn test_size_record() {
+        let mut record = SizeRecord::default();
+        assert_eq!(record.update(30., 50), NewRecord { time: true, moves: true });
+        assert_eq!(record.update(25., 60), NewRecord { time: true, moves: false });
+        assert_eq!(record.update(40., 50), NewRecord { time: false, moves: false });
+        assert_eq!(record, SizeRecord { best_time: Some(25.), best_moves: Some(50) });
+
+        record.merge(&SizeRecord { best_time: Some(20.), best_moves: None });
+        assert_eq!(record, SizeRecord { best_time: Some(20.), best_moves: Some(50) });
+    }
+}
@@ -0,0 +1,456 @@
+use std::{
+    collections::VecDeque,
+    io,
+    net::{Ipv4Addr, TcpListener},
+    sync::{mpsc::{Receiver, SyncSender, TryRecvError, TrySendError}, Mutex, PoisonError},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auto_solve::AutoSolvePlayback,
+    line_stream::{self, LineStream},
+    replay::ReplayPlayback,
+    taquin::{MoveCount, ShuffleRequested, ShuffleSeed, SlideSelectedTile, Taquin, TaquinAction, TaquinShuffled, TaquinSolved, TileMoved},
+    tile::{Direction, TileCoordinates, TileIndex, TileSelected, TileSlide},
+    AppState,
+};
+
+/// Bumped whenever the commands or the events change, sent to every client as it connects
+const PROTOCOL_VERSION: u32 = 1;
+/// Port of `--remote` given without one, as clap reads it
+pub const DEFAULT_REMOTE_PORT: &str = "7879";
+/// Moves waiting for their turn beyond these are refused, a bot sending faster than the tiles slide is told so
+const MAX_PENDING_MOVES: usize = 64;
+/// Events waiting for the standard output to be read, a consumer further behind is dropped
+const MAX_QUEUED_EVENTS: usize = 1024;
+
+pub struct RemotePlugin;
+
+impl Plugin for RemotePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // Read where the keyboard is, so the moves asked join the same queue in `Update`
+            .add_systems(PreUpdate, (
+                accept_remote_clients,
+                greet_remote_clients,
+                read_remote_commands,
+                play_remote_moves.run_if(not(any_with_component::<TileSlide>()).and_then(not(resource_exists::<AutoSolvePlayback>()))),
+            ).chain().run_if(resource_exists::<RemoteControl>().and_then(in_state(AppState::Running)).and_then(not(resource_exists::<ReplayPlayback>()))))
+            // Once the moves of the frame are played
+            .add_systems(PostUpdate, send_remote_events.run_if(resource_exists::<RemoteControl>()));
+    }
+}
+
+/// Where the commands of `--remote` come from, the events going back the same way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteEndpoint {
+    /// Clients connecting to this port of localhost
+    Port(u16),
+    /// The standard input, the events going to the standard output
+    Stdio,
+}
+
+impl RemoteEndpoint {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "stdin" => Some(RemoteEndpoint::Stdio),
+            _ => name.parse().ok().map(RemoteEndpoint::Port),
+        }
+    }
+}
+
+/// What a client asks, one JSON object per line, as in `{"command":"move","tile":{"i":1,"j":2}}`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum RemoteCommand {
+    /// Answered with the board
+    State,
+    /// Slides the tile of this cell into the empty slot next to it, once the tiles sliding landed
+    Move { tile: TileCoordinates },
+    /// Slides the tile pushed towards `direction` into the empty slot, as an arrow of the classic controls
+    Push { direction: Direction },
+    /// Shuffles with `seed`, a random one when none
+    Shuffle {
+        #[serde(default)]
+        seed: Option<u64>,
+    },
+}
+
+/// The board as a client sees it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct BoardState {
+    width: i8,
+    height: i8,
+    /// Values row after row, the numbers in order once solved, the empty slots being the last ones
+    tiles: Vec<Vec<i16>>,
+    empty: Vec<TileCoordinates>,
+    moves: usize,
+    shuffled: bool,
+    solved: bool,
+    seed: Option<u64>,
+}
+
+impl BoardState {
+    fn new(taquin: &Taquin, move_count: &MoveCount, seed: &ShuffleSeed) -> Self {
+        Self {
+            width: taquin.width,
+            height: taquin.height,
+            tiles: taquin.tiles.iter().map(|row| row.iter().map(|value| value.0).collect()).collect(),
+            empty: taquin.get_empty_tiles_coordinates(),
+            moves: move_count.0,
+            shuffled: taquin.is_shuffled,
+            solved: taquin.is_solved(),
+            seed: seed.current,
+        }
+    }
+}
+
+/// What the game tells its clients, one JSON object per line
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum RemoteEvent {
+    Hello { version: u32 },
+    State(BoardState),
+    Moved { from: TileCoordinates, to: TileCoordinates, moves: usize },
+    /// With the new board
+    Shuffled(BoardState),
+    Solved { moves: usize },
+    Error { message: String },
+}
+
+enum RemoteConnection {
+    Tcp(LineStream),
+    /// Lines of the standard input and events for the standard output, read and written by threads of their
+    /// own as neither can be done without blocking
+    Stdio {
+        lines: Mutex<Receiver<String>>,
+        events: SyncSender<Vec<u8>>,
+    },
+}
+
+struct RemoteClient {
+    connection: RemoteConnection,
+    /// Whether it got the hello and the board, sent once the board is set up
+    greeted: bool,
+}
+
+impl RemoteClient {
+    fn new(connection: RemoteConnection) -> Self {
+        Self { connection, greeted: false }
+    }
+
+    fn send(&mut self, event: &RemoteEvent) -> io::Result<()> {
+        let bytes = encode(event)?;
+        match &mut self.connection {
+            RemoteConnection::Tcp(stream) => stream.send(&bytes),
+            RemoteConnection::Stdio { events, .. } => events.try_send(bytes).map_err(|error| match error {
+                TrySendError::Full(_) => io::Error::other("the standard output is not read"),
+                TrySendError::Disconnected(_) => io::ErrorKind::BrokenPipe.into(),
+            }),
+        }
+    }
+
+    /// Lines fully received since the last call, an error once the client is gone
+    fn receive(&mut self) -> io::Result<Vec<String>> {
+        match &mut self.connection {
+            RemoteConnection::Tcp(stream) => {
+                Ok(stream.receive()?.iter().map(|line| String::from_utf8_lossy(line).into_owned()).collect())
+            },
+            RemoteConnection::Stdio { lines, .. } => {
+                let lines = lines.get_mut().unwrap_or_else(PoisonError::into_inner);
+                let mut received = Vec::new();
+                loop {
+                    match lines.try_recv() {
+                        Ok(line) => received.push(line),
+                        Err(TryRecvError::Empty) => return Ok(received),
+                        // The lines read before the end are still played, the client is dropped on the next call
+                        Err(TryRecvError::Disconnected) if !received.is_empty() => return Ok(received),
+                        Err(TryRecvError::Disconnected) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Lets other programs play, `--remote` opens it
+#[derive(Resource)]
+pub struct RemoteControl {
+    listener: Option<TcpListener>,
+    clients: Vec<RemoteClient>,
+    /// Cells of the tiles to slide, one after the other as the tiles land
+    pending_moves: VecDeque<TileCoordinates>,
+}
+
+impl RemoteControl {
+    pub fn open(endpoint: RemoteEndpoint) -> io::Result<Self> {
+        let mut remote = Self { listener: None, clients: Vec::new(), pending_moves: VecDeque::new() };
+        match endpoint {
+            // Bots run on the same machine, the board is not offered to the network
+            RemoteEndpoint::Port(port) => {
+                let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+                listener.set_nonblocking(true)?;
+                info!(port, "remote control listening");
+                remote.listener = Some(listener);
+            },
+            RemoteEndpoint::Stdio => {
+                let (lines, events) = (read_stdin()?, write_stdout()?);
+                remote.clients.push(RemoteClient::new(RemoteConnection::Stdio { lines: Mutex::new(lines), events }));
+                info!("remote control reading the standard input");
+            },
+        }
+        Ok(remote)
+    }
+
+    /// Clients the event cannot be sent to are gone
+    fn broadcast(&mut self, event: &RemoteEvent) {
+        self.clients.retain_mut(|client| match client.send(event) {
+            Ok(()) => true,
+            Err(error) => {
+                info!("remote client left: {error}");
+                false
+            },
+        });
+    }
+}
+
+/// A line longer than `MAX_LINE_BYTES` ends the reading, as it drops a client of the port
+#[cfg(not(target_arch = "wasm32"))]
+fn read_stdin() -> io::Result<Receiver<String>> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::Builder::new().name("remote stdin".to_string()).spawn(move || {
+        let read = line_stream::read_lines(io::stdin().lock(), |line| sender.send(String::from_utf8_lossy(&line).into_owned()).is_ok());
+        if let Err(error) = read {
+            warn!("remote standard input no longer read: {error}");
+        }
+    })?;
+    Ok(receiver)
+}
+
+/// A consumer that stops reading blocks this thread rather than the game
+#[cfg(not(target_arch = "wasm32"))]
+fn write_stdout() -> io::Result<SyncSender<Vec<u8>>> {
+    use std::io::Write;
+
+    let (sender, receiver) = std::sync::mpsc::sync_channel::<Vec<u8>>(MAX_QUEUED_EVENTS);
+    std::thread::Builder::new().name("remote stdout".to_string()).spawn(move || {
+        for bytes in receiver {
+            let mut stdout = io::stdout().lock();
+            if stdout.write_all(&bytes).and_then(|()| stdout.flush()).is_err() {
+                break;
+            }
+        }
+    })?;
+    Ok(sender)
+}
+
+/// A browser has neither a standard input nor threads
+#[cfg(target_arch = "wasm32")]
+fn read_stdin() -> io::Result<Receiver<String>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "a browser has no standard input"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_stdout() -> io::Result<SyncSender<Vec<u8>>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "a browser has no standard output"))
+}
+
+fn encode(event: &RemoteEvent) -> io::Result<Vec<u8>> {
+    let mut bytes = serde_json::to_vec(event)?;
+    bytes.push(b'\n');
+    Ok(bytes)
+}
+
+/// Blank lines are skipped, a client typing by hand may send them
+fn parse_command(line: &str) -> Option<Result<RemoteCommand, String>> {
+    let line = line.trim();
+    (!line.is_empty()).then(|| serde_json::from_str(line).map_err(|error| format!("invalid command {line}: {error}")))
+}
+
+fn accept_remote_clients(
+    mut remote: ResMut<RemoteControl>,
+) {
+    let Some(listener) = remote.listener.as_ref() else {
+        return;
+    };
+    let (stream, address) = match listener.accept() {
+        Ok(accepted) => accepted,
+        Err(error) if error.kind() == io::ErrorKind::WouldBlock => return,
+        Err(error) => {
+            warn!("cannot accept a remote client: {error}");
+            return;
+        },
+    };
+    match LineStream::new(stream, Vec::new()) {
+        Ok(stream) => {
+            info!(%address, "remote client connected");
+            remote.clients.push(RemoteClient::new(RemoteConnection::Tcp(stream)));
+        },
+        Err(error) => warn!(%address, "cannot set up the remote client: {error}"),
+    }
+}
+
+/// The version and the board, before anything else
+fn greet_remote_clients(
+    mut remote: ResMut<RemoteControl>,
+    taquin: Res<Taquin>,
+    move_count: Res<MoveCount>,
+    seed: Res<ShuffleSeed>,
+) {
+    remote.clients.retain_mut(|client| {
+        if client.greeted {
+            return true;
+        }
+        client.greeted = true;
+        let greeting = client.send(&RemoteEvent::Hello { version: PROTOCOL_VERSION })
+            .and_then(|()| client.send(&RemoteEvent::State(BoardState::new(&taquin, &move_count, &seed))));
+        if let Err(error) = &greeting {
+            info!("remote client left: {error}");
+        }
+        greeting.is_ok()
+    });
+}
+
+/// The state is answered right away, the moves join the moves of the players
+fn read_remote_commands(
+    mut commands: Commands,
+    mut remote: ResMut<RemoteControl>,
+    taquin: Res<Taquin>,
+    move_count: Res<MoveCount>,
+    mut seed: ResMut<ShuffleSeed>,
+    mut action_events: EventWriter<TaquinAction>,
+) {
+    let RemoteControl { clients, pending_moves, .. } = remote.as_mut();
+    clients.retain_mut(|client| {
+        let lines = match client.receive() {
+            Ok(lines) => lines,
+            Err(error) => {
+                info!("remote client left: {error}");
+                return false;
+            },
+        };
+        for command in lines.iter().filter_map(|line| parse_command(line)) {
+            debug!(?command, "remote command");
+            let answer = match command {
+                Ok(RemoteCommand::State) => Some(RemoteEvent::State(BoardState::new(&taquin, &move_count, &seed))),
+                Ok(RemoteCommand::Move { tile }) if tile.i < 0 || tile.j < 0 || tile.i >= taquin.width || tile.j >= taquin.height => {
+                    Some(RemoteEvent::Error { message: format!("cell {},{} is off the board", tile.i, tile.j) })
+                },
+                Ok(RemoteCommand::Move { .. }) if pending_moves.len() >= MAX_PENDING_MOVES => {
+                    Some(RemoteEvent::Error { message: format!("{MAX_PENDING_MOVES} moves are already waiting") })
+                },
+                Ok(RemoteCommand::Move { tile }) => {
+                    pending_moves.push_back(tile);
+                    None
+                },
+                Ok(RemoteCommand::Push { direction }) => {
+                    action_events.send(TaquinAction::Push(direction));
+                    None
+                },
+                Ok(RemoteCommand::Shuffle { seed: next }) => {
+                    if next.is_some() {
+                        seed.next = next;
+                    }
+                    pending_moves.clear();
+                    commands.init_resource::<ShuffleRequested>();
+                    None
+                },
+                Err(message) => Some(RemoteEvent::Error { message }),
+            };
+            if let Some(answer) = answer {
+                if let Err(error) = client.send(&answer) {
+                    info!("remote client left: {error}");
+                    return false;
+                }
+            }
+        }
+        true
+    });
+}
+
+/// As an auto-solve plays: the selection goes onto the tile, which slides on the next frame
+fn play_remote_moves(
+    mut commands: Commands,
+    mut remote: ResMut<RemoteControl>,
+    taquin: Res<Taquin>,
+    selected_tile_query: Query<(Entity, &TileCoordinates), With<TileSelected>>,
+    index: Res<TileIndex>,
+    mut slide_events: EventWriter<SlideSelectedTile>,
+) {
+    let Some(tile) = remote.pending_moves.front().copied() else {
+        return;
+    };
+    // Whether it can slide is only known once the moves before it are played
+    if !taquin.is_movable(&tile) {
+        remote.pending_moves.pop_front();
+        remote.broadcast(&RemoteEvent::Error { message: format!("tile {},{} cannot slide", tile.i, tile.j) });
+        return;
+    }
+    let Ok((selected_entity, selected_coordinates)) = selected_tile_query.get_single() else {
+        return;
+    };
+    if *selected_coordinates != tile {
+        if let Some(entity) = index.at(tile) {
+            commands.entity(selected_entity).remove::<TileSelected>();
+            commands.entity(entity).insert(TileSelected);
+        }
+        return;
+    }
+    remote.pending_moves.pop_front();
+    slide_events.send_default();
+}
+
+/// Every move, shuffle and solve of the board, whoever played it
+fn send_remote_events(
+    mut remote: ResMut<RemoteControl>,
+    mut moved_events: EventReader<TileMoved>,
+    mut shuffled_events: EventReader<TaquinShuffled>,
+    mut solved_events: EventReader<TaquinSolved>,
+    taquin: Res<Taquin>,
+    move_count: Res<MoveCount>,
+    seed: Res<ShuffleSeed>,
+) {
+    if shuffled_events.read().count() > 0 {
+        remote.broadcast(&RemoteEvent::Shuffled(BoardState::new(&taquin, &move_count, &seed)));
+    }
+    for moved in moved_events.read() {
+        remote.broadcast(&RemoteEvent::Moved { from: moved.from, to: moved.to, moves: moved.moves });
+    }
+    if solved_events.read().count() > 0 {
+        remote.broadcast(&RemoteEvent::Solved { moves: move_count.0 });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        line_stream::take_lines,
+        remote::{encode, parse_command, RemoteCommand, RemoteEndpoint, RemoteEvent},
+        tile::{Direction, TileCoordinates},
+    };
+
+    #[test]
+    fn test_remote_messages() {
+        assert_eq!(RemoteEndpoint::from_name("7879"), Some(RemoteEndpoint::Port(7879)));
+        assert_eq!(RemoteEndpoint::from_name("stdin"), Some(RemoteEndpoint::Stdio));
+        assert_eq!(RemoteEndpoint::from_name("stdout"), None);
+
+        // A command cut in two is only read once its end arrives
+        let mut buffer = b"{\"command\":\"state\"}\n{\"command\":\"mo".to_vec();
+        assert_eq!(take_lines(&mut buffer).unwrap(), vec![b"{\"command\":\"state\"}\n".to_vec()]);
+        buffer.extend_from_slice(b"ve\",\"tile\":{\"i\":1,\"j\":2}}\n");
+        let lines = take_lines(&mut buffer).unwrap();
+        assert!(buffer.is_empty());
+        assert_eq!(parse_command(&String::from_utf8_lossy(&lines[0])), Some(Ok(RemoteCommand::Move { tile: TileCoordinates::new(1, 2) })));
+
+        assert_eq!(parse_command("{\"command\":\"push\",\"direction\":\"up\"}\r"), Some(Ok(RemoteCommand::Push { direction: Direction::Up })));
+        assert_eq!(parse_command("{\"command\":\"shuffle\"}"), Some(Ok(RemoteCommand::Shuffle { seed: None })));
+        assert_eq!(parse_command("  "), None);
+        assert!(parse_command("{\"command\":\"fly\"}").is_some_and(|command| command.is_err()));
+
+        let moved = RemoteEvent::Moved { from: TileCoordinates::new(0, 1), to: TileCoordinates::new(0, 2), moves: 3 };
+        assert_eq!(encode(&moved).unwrap(), b"{\"event\":\"moved\",\"from\":{\"i\":0,\"j\":1},\"to\":{\"i\":0,\"j\":2},\"moves\":3}\n");
+    }
+}
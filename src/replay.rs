@@ -0,0 +1,639 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    focus::FocusScope,
+    i18n::Localized,
+    marker::Markers,
+    pause::GameClock,
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    platform,
+    playback::{self, PlaybackController},
+    rotation::RotatingTiles,
+    taquin::{slide_tile, EmptySlots, Taquin, TaquinShuffled, TaquinSolved, TileMoved},
+    tile::{EmptyTile, TileCoordinates, TileIndex, TileSlide, TileValue, Topology},
+    AppState,
+};
+
+/// Url safe base64 digits of the replay and board codes, each carries 5 bits and whether more follow
+const CODE_DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const CODE_VERSION: u32 = 1;
+/// Where the slid tile stands next to the empty slot, a move of a code is its index here
+const SLIDE_OFFSETS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+/// Saved replays listed by the main menu, the latest ones
+const LISTED_REPLAYS: usize = 10;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ReplayRecorder>()
+            .add_systems(Update, (
+                record_replay,
+                share_replay_code.run_if(on_event::<TaquinSolved>()),
+            ).chain().run_if(in_state(AppState::Running).and_then(not(resource_exists::<ReplayPlayback>()))))
+            .add_systems(OnEnter(AppState::Running), start_replay_playback.run_if(resource_exists::<ReplayPlayback>()))
+            .add_systems(Update, (
+                toggle_replay_list,
+                watch_listed_replay.run_if(resource_exists::<ReplayList>()),
+            ).chain().run_if(in_state(AppState::MainMenu)))
+            .add_systems(OnExit(AppState::MainMenu), close_replay_list.run_if(resource_exists::<ReplayList>()))
+            // A game started from the menu afterwards is played
+            .add_systems(OnEnter(AppState::MainMenu), stop_replay_playback)
+            .add_systems(Update, (
+                replay_camera_presets,
+                apply_replay_seek,
+                advance_replay.run_if(not(any_with_component::<TileSlide>())),
+            ).chain().run_if(in_state(AppState::Running).and_then(resource_exists::<ReplayPlayback>())));
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ReplayMove {
+    /// Coordinates of the tile slid into the empty slot
+    pub tile: TileCoordinates,
+    /// Seconds elapsed since the shuffle
+    pub time: f32,
+    /// Empty slot the tile slid into, only kept when the board has several
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub into: Option<TileCoordinates>,
+}
+
+impl ReplayMove {
+    /// Empty slot the tile slides into on `taquin`, the first one next to it unless recorded
+    pub fn empty_on(&self, taquin: &Taquin) -> Option<TileCoordinates> {
+        self.into.or_else(|| taquin.get_empty_next_to(&self.tile))
+    }
+
+    /// Plays the move on `taquin`, false if it is not a legal slide there
+    pub fn play(&self, taquin: &mut Taquin) -> bool {
+        self.empty_on(taquin).is_some_and(|empty| taquin.slide_into(self.tile, empty))
+    }
+}
+
+/// A recorded game: the shuffled board and every move played from it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Replay {
+    #[serde(default)]
+    pub version: u32,
+    /// Columns of the board, its rows are the ones of `tiles`
+    pub size: i8,
+    pub tiles: Vec<Vec<TileValue>>,
+    pub moves: Vec<ReplayMove>,
+    /// Recorded with `--wrap` when a torus
+    #[serde(default)]
+    pub topology: Topology,
+    #[serde(default = "default_empty_count")]
+    pub empty_count: usize,
+}
+
+/// Replays written before the boards with several empty slots had one
+fn default_empty_count() -> usize {
+    1
+}
+
+impl Replay {
+    pub fn new(taquin: &Taquin) -> Self {
+        Self { version: Self::VERSION, size: taquin.width, tiles: taquin.tiles.clone(), moves: vec![], topology: taquin.topology, empty_count: taquin.empty_count() }
+    }
+
+    pub fn height(&self) -> i8 {
+        self.tiles.len() as i8
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PersistenceError> {
+        let replay: Replay = persistence::load_versioned(path, Format::Ron)?;
+        replay.validate()?;
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PersistenceError> {
+        persistence::save_versioned(path, self, Format::Ron)
+    }
+
+    /// Replays the first `move_count` moves on the initial board
+    pub fn board_at(&self, move_count: usize) -> Result<Taquin, PersistenceError> {
+        let mut taquin = Taquin::rect(self.size, self.height());
+        taquin.topology = self.topology;
+        taquin.set_empty_count(self.empty_count);
+        taquin.set_tiles(self.tiles.clone());
+        for (index, replay_move) in self.moves.iter().take(move_count).enumerate() {
+            if !replay_move.play(&mut taquin) {
+                return Err(PersistenceError::Invalid(format!("move {} ({:?}) is not a legal slide", index + 1, replay_move.tile)));
+            }
+        }
+        Ok(taquin)
+    }
+
+    /// Slides through the edges or between several empty slots have no code
+    pub fn has_code(&self) -> bool {
+        self.topology == Topology::Flat && self.empty_count == 1
+    }
+
+    /// Short text for a link: the version, the board size, the tiles, then each move with the tenths of
+    /// seconds since the previous one
+    pub fn to_code(&self) -> Result<String, PersistenceError> {
+        if !self.has_code() {
+            return Err(PersistenceError::Invalid("slides through the edges or between several empty slots have no code".to_string()));
+        }
+        let mut code = String::new();
+        for number in [CODE_VERSION, self.size as u32, self.height() as u32] {
+            push_code_number(&mut code, number);
+        }
+        for value in self.tiles.iter().flatten() {
+            push_code_number(&mut code, value.0 as u32);
+        }
+        let mut taquin = self.board_at(0)?;
+        let mut tenths = 0;
+        for (index, replay_move) in self.moves.iter().enumerate() {
+            let empty = taquin.get_empty_tile_coordinates();
+            let offset = (replay_move.tile.i - empty.i, replay_move.tile.j - empty.j);
+            let Some(direction) = SLIDE_OFFSETS.iter().position(|o| *o == offset).filter(|_| taquin.slide(replay_move.tile)) else {
+                return Err(PersistenceError::Invalid(format!("move {} ({:?}) is not a legal slide", index + 1, replay_move.tile)));
+            };
+            let move_tenths = (replay_move.time * 10.).round().max(0.) as u32;
+            push_code_number(&mut code, move_tenths.saturating_sub(tenths) * 4 + direction as u32);
+            tenths = tenths.max(move_tenths);
+        }
+        Ok(code)
+    }
+
+    pub fn from_code(code: &str) -> Result<Self, PersistenceError> {
+        let mut numbers = read_code_numbers(code)?.into_iter();
+        let mut next = || numbers.next().ok_or_else(|| PersistenceError::Invalid("replay code is cut".to_string()));
+        let version = next()?;
+        if version != CODE_VERSION {
+            return Err(PersistenceError::unsupported_version(version));
+        }
+        let (width, height) = (next()?, next()?);
+        if !(2..=i8::MAX as u32).contains(&width) || !(2..=i8::MAX as u32).contains(&height) || width * height > i16::MAX as u32 {
+            return Err(PersistenceError::Invalid(format!("{width}x{height} board is not supported")));
+        }
+        let tiles = (0..height)
+            .map(|_| (0..width).map(|_| Ok(TileValue(next()? as i16))).collect())
+            .collect::<Result<_, PersistenceError>>()?;
+        let mut replay = Replay { version: Self::VERSION, size: width as i8, tiles, moves: vec![], topology: Topology::Flat, empty_count: 1 };
+        replay.validate()?;
+        let mut taquin = replay.board_at(0)?;
+        let mut tenths = 0u32;
+        for number in numbers {
+            let empty = taquin.get_empty_tile_coordinates();
+            let (i, j) = SLIDE_OFFSETS[(number % 4) as usize];
+            let tile = TileCoordinates::new(empty.i + i, empty.j + j);
+            if tile.i < 0 || tile.j < 0 || tile.i >= taquin.width || tile.j >= taquin.height {
+                return Err(PersistenceError::Invalid(format!("move {} leaves the board", replay.moves.len() + 1)));
+            }
+            tenths = tenths.saturating_add(number / 4);
+            taquin.slide(tile);
+            replay.moves.push(ReplayMove { tile, time: tenths as f32 / 10., into: None });
+        }
+        Ok(replay)
+    }
+
+    fn validate(&self) -> Result<(), PersistenceError> {
+        if self.size < 2 || self.tiles.len() < 2 {
+            return Err(PersistenceError::Invalid(format!("{}x{} board is too small", self.size, self.tiles.len())));
+        }
+        let mut values = self.tiles.iter().flatten().map(|t| t.0).collect::<Vec<i16>>();
+        values.sort();
+        if self.tiles.iter().any(|row| row.len() != self.size as usize)
+            || values != (1..=self.size as i16 * self.height() as i16).collect::<Vec<i16>>() {
+            return Err(PersistenceError::Invalid("board is not a permutation of the tiles".to_string()));
+        }
+        self.board_at(self.moves.len()).map(|_| ())
+    }
+}
+
+impl Versioned for Replay {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, contents: &str, format: Format) -> Result<Self, PersistenceError> {
+        match version {
+            // unversioned replays share the version 1 layout
+            0 => Ok(Self { version: Self::VERSION, ..format.parse(contents)? }),
+            _ => Err(PersistenceError::unsupported_version(version)),
+        }
+    }
+}
+
+pub(crate) fn push_code_number(code: &mut String, mut number: u32) {
+    loop {
+        let digit = number & 31;
+        number >>= 5;
+        let more = if number > 0 { 32 } else { 0 };
+        code.push(CODE_DIGITS[(digit | more) as usize] as char);
+        if number == 0 {
+            return;
+        }
+    }
+}
+
+pub(crate) fn read_code_numbers(code: &str) -> Result<Vec<u32>, PersistenceError> {
+    let mut numbers = vec![];
+    let (mut number, mut shift) = (0u32, 0);
+    for character in code.bytes() {
+        let Some(digit) = CODE_DIGITS.iter().position(|d| *d == character) else {
+            return Err(PersistenceError::Invalid(format!("unexpected {:?} in the code", character as char)));
+        };
+        if shift > 25 {
+            return Err(PersistenceError::Invalid("code number is too large".to_string()));
+        }
+        number |= (digit as u32 & 31) << shift;
+        if digit & 32 == 0 {
+            numbers.push(number);
+            (number, shift) = (0, 0);
+        } else {
+            shift += 5;
+        }
+    }
+    if shift > 0 {
+        return Err(PersistenceError::Invalid("code is cut".to_string()));
+    }
+    Ok(numbers)
+}
+
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    current: Option<Replay>,
+    /// Replay of the last game solved
+    pub last: Option<Replay>,
+    started_at: f32,
+}
+
+/// Replays keep no rotations, the games with rotating tiles are not recorded
+#[allow(clippy::too_many_arguments)]
+fn record_replay(
+    mut recorder: ResMut<ReplayRecorder>,
+    paths: Res<DataPaths>,
+    taquin: Res<Taquin>,
+    rotating: Res<RotatingTiles>,
+    clock: Res<GameClock>,
+    mut shuffle_events: EventReader<TaquinShuffled>,
+    mut tile_moved_events: EventReader<TileMoved>,
+    mut solved_events: EventReader<TaquinSolved>,
+) {
+    if shuffle_events.read().last().is_some() {
+        recorder.current = (!rotating.0).then(|| Replay::new(&taquin));
+        recorder.started_at = clock.elapsed_seconds();
+    }
+
+    let elapsed = clock.elapsed_seconds() - recorder.started_at;
+    for event in tile_moved_events.read() {
+        if let Some(replay) = recorder.current.as_mut() {
+            replay.moves.push(ReplayMove { tile: event.from, time: elapsed, into: (taquin.empty_count() > 1).then_some(event.to) });
+        }
+    }
+
+    if solved_events.read().last().is_none() {
+        return;
+    }
+    let Some(replay) = recorder.current.take() else {
+        // Nor is the previous game shared or drawn as the last one solved
+        recorder.last = None;
+        return;
+    };
+    let path = paths.replays().join(format!("{}.ron", persistence::timestamp()));
+    match replay.save(&path) {
+        Ok(()) => info!("replay saved to {}", path.display()),
+        Err(error) => error!("{error}"),
+    }
+    recorder.last = Some(replay);
+}
+
+/// Puts the code of the game just solved in the page url, so the link opens straight into its replay. Games on a
+/// torus or with several empty slots have none to share
+fn share_replay_code(
+    recorder: Res<ReplayRecorder>,
+) {
+    let Some(replay) = recorder.last.as_ref().filter(|replay| replay.has_code()) else {
+        return;
+    };
+    match replay.to_code() {
+        Ok(code) => platform::set_url_fragment(&format!("replay-code={code}")),
+        Err(error) => error!("cannot share the replay: {error}"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraPreset {
+    Overview,
+    Front,
+    Close,
+}
+
+impl CameraPreset {
+    fn transform(&self, markers: &Markers) -> Transform {
+        let center = markers.center();
+        let distance = markers.inner_width().max(markers.inner_height());
+        match self {
+            CameraPreset::Overview => Transform::from_xyz(0.0, 30., 40.0).looking_at(Vec3::new(0., 1., 0.), Vec3::Y),
+            CameraPreset::Front => Transform::from_translation(center + Vec3::Z * distance * 1.6).looking_at(center, Vec3::Y),
+            CameraPreset::Close => Transform::from_translation(center + Vec3::new(distance * 0.4, -distance * 0.3, distance * 0.9)).looking_at(center, Vec3::Y),
+        }
+    }
+}
+
+/// Playback of a replay loaded from disk, gameplay input is disabled while it exists. Its play state is
+/// the `PlaybackController` inserted when the game starts
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay }
+    }
+}
+
+/// Opens the list of the saved replays from the main menu
+#[derive(Component)]
+pub struct WatchReplayButton;
+
+/// Replay of the list to watch
+#[derive(Component)]
+struct ListedReplayButton(PathBuf);
+
+#[derive(Resource)]
+struct ReplayList(Entity);
+
+/// The `LISTED_REPLAYS` latest replays of `folder` that load, with their path. They are named after the time they
+/// were saved at
+fn latest_replays(folder: &Path) -> Vec<(PathBuf, Replay)> {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort_by_key(|path| path.file_stem().and_then(|stem| stem.to_str()?.parse::<u64>().ok()));
+    paths.into_iter().rev()
+        .filter_map(|path| Replay::load(&path).map_err(|error| warn!("{}: {error}", path.display())).ok().map(|replay| (path, replay)))
+        .take(LISTED_REPLAYS)
+        .collect()
+}
+
+fn replay_label(path: &Path, replay: &Replay) -> Localized {
+    let date = path.file_stem()
+        .and_then(|stem| stem.to_str()?.parse::<i64>().ok())
+        .and_then(|timestamp| chrono::DateTime::from_timestamp(timestamp, 0))
+        .map_or_else(String::new, |date| date.format("%Y-%m-%d %H:%M").to_string());
+    Localized::new("{date}: {width}x{height}, {moves} moves")
+        .with_arg("date", date)
+        .with_arg("width", replay.size)
+        .with_arg("height", replay.height())
+        .with_arg("moves", replay.moves.len())
+}
+
+fn toggle_replay_list(
+    mut commands: Commands,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<WatchReplayButton>)>,
+    list: Option<Res<ReplayList>>,
+    paths: Res<DataPaths>,
+    rotating: Res<RotatingTiles>,
+) {
+    if !button_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        return;
+    }
+    if let Some(list) = list {
+        commands.entity(list.0).despawn_recursive();
+        commands.remove_resource::<ReplayList>();
+        return;
+    }
+    let text_style = TextStyle {
+        font_size: 30.0,
+        color: Color::WHITE,
+        ..default()
+    };
+    let replays = latest_replays(&paths.replays());
+    let entity = commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.),
+            top: Val::Px(120.),
+            padding: UiRect::all(Val::Px(10.)),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(10.),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        // Above the main menu
+        z_index: ZIndex::Global(6),
+        ..default()
+    }, FocusScope)).with_children(|parent| {
+        if rotating.0 {
+            parent.spawn((TextBundle::from_section("", text_style.clone()), Localized::new("Games with rotating tiles are not saved as replays")));
+        }
+        if replays.is_empty() {
+            parent.spawn((TextBundle::from_section("", text_style.clone()), Localized::new("No replay saved yet, solve a game first")));
+        }
+        for (path, replay) in replays {
+            let label = replay_label(&path, &replay);
+            parent.spawn((ButtonBundle {
+                style: Style {
+                    padding: UiRect::axes(Val::Px(20.), Val::Px(8.)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                ..default()
+            }, ListedReplayButton(path))).with_children(|parent| {
+                parent.spawn((TextBundle::from_section("", text_style.clone()), label));
+            });
+        }
+    }).id();
+    commands.insert_resource(ReplayList(entity));
+}
+
+/// The board is set up as `--replay` does, on the board the replay was recorded on
+fn watch_listed_replay(
+    mut commands: Commands,
+    button_query: Query<(&Interaction, &ListedReplayButton), Changed<Interaction>>,
+    mut taquin: ResMut<Taquin>,
+    mut topology: ResMut<Topology>,
+    mut empty_slots: ResMut<EmptySlots>,
+    mut rotating: ResMut<RotatingTiles>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some((_, ListedReplayButton(path))) = button_query.iter().find(|(interaction, _)| **interaction == Interaction::Pressed) else {
+        return;
+    };
+    let replay = match Replay::load(path) {
+        Ok(replay) => replay,
+        Err(error) => {
+            error!("{}: {error}", path.display());
+            return;
+        },
+    };
+    info!(path = %path.display(), moves = replay.moves.len(), "replay watched from the main menu");
+    *taquin = Taquin::rect(replay.size, replay.height());
+    *topology = replay.topology;
+    empty_slots.0 = replay.empty_count;
+    *rotating = RotatingTiles(false);
+    commands.insert_resource(ReplayPlayback::new(replay));
+    next_state.set(AppState::SetupTiles);
+}
+
+fn close_replay_list(
+    mut commands: Commands,
+    list: Res<ReplayList>,
+) {
+    commands.entity(list.0).despawn_recursive();
+    commands.remove_resource::<ReplayList>();
+}
+
+fn stop_replay_playback(
+    mut commands: Commands,
+) {
+    commands.remove_resource::<ReplayPlayback>();
+}
+
+/// The board is arranged by a first seek
+fn start_replay_playback(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+) {
+    let mut controller = PlaybackController::new("Replay", playback.replay.moves.len());
+    controller.seek(0);
+    commands.insert_resource(controller);
+}
+
+fn replay_camera_presets(
+    keyboard_input: Res<Input<KeyCode>>,
+    markers: Res<Markers>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+) {
+    let preset = if keyboard_input.just_released(KeyCode::Key1) {
+        CameraPreset::Overview
+    } else if keyboard_input.just_released(KeyCode::Key2) {
+        CameraPreset::Front
+    } else if keyboard_input.just_released(KeyCode::Key3) {
+        CameraPreset::Close
+    } else {
+        return;
+    };
+    for mut transform in camera_query.iter_mut() {
+        *transform = preset.transform(&markers);
+    }
+}
+
+fn apply_replay_seek(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    mut controller: ResMut<PlaybackController>,
+    mut taquin: ResMut<Taquin>,
+    markers: Res<Markers>,
+    index: Res<TileIndex>,
+    mut tiles_query: Query<(&mut TileCoordinates, &mut Transform)>,
+) {
+    let Some(move_count) = controller.take_seek() else {
+        return;
+    };
+    let board = match playback.replay.board_at(move_count) {
+        Ok(board) => board,
+        Err(error) => {
+            error!("{error}");
+            return;
+        }
+    };
+    taquin.set_tiles(board.tiles);
+    playback::snap_tiles(&mut commands, &taquin, &markers, &index, &mut tiles_query);
+    controller.seeked(move_count, move_count.checked_sub(1).map_or(0., |index| playback.replay.moves[index].time));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn advance_replay(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    mut controller: ResMut<PlaybackController>,
+    mut taquin: ResMut<Taquin>,
+    index: Res<TileIndex>,
+    time: Res<Time>,
+    mut tiles_query: Query<(Entity, &Transform, &mut TileCoordinates), Without<EmptyTile>>,
+    mut empty_tile_query: Query<(&mut Transform, &mut TileCoordinates), With<EmptyTile>>,
+) {
+    let Some(next_move) = playback.replay.moves.get(controller.played()).copied() else {
+        return;
+    };
+    if !controller.tick(time.delta_seconds(), next_move.time) {
+        return;
+    }
+
+    // A seek of this frame moved the tiles after the index was refreshed, the move waits for the next one
+    let Some((entity, tile_transform, mut tile_coords)) = index.at(next_move.tile)
+        .and_then(|entity| tiles_query.get_mut(entity).ok())
+        .filter(|(_, _, tile_coords)| **tile_coords == next_move.tile) else {
+        return;
+    };
+    let empty = next_move.empty_on(&taquin).filter(|empty| tile_coords.is_neighbour_on(empty, taquin.topology, taquin.width, taquin.height));
+    let Some((mut empty_tile_transform, mut empty_tile_coords)) = empty.and_then(|empty| empty_tile_query.iter_mut().find(|(_, coordinates)| **coordinates == empty)) else {
+        error!("replay desynchronized at move {}", controller.played() + 1);
+        controller.pause();
+        return;
+    };
+    slide_tile(
+        &mut commands,
+        taquin.as_mut(),
+        (entity, tile_transform, tile_coords.as_mut()),
+        (empty_tile_transform.as_mut(), empty_tile_coords.as_mut()),
+    );
+    controller.advance(next_move.time);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tile::{TileCoordinates, TileValue, Topology}, replay::{Replay, ReplayMove}, persistence::{self, Format, Versioned}};
+
+    fn replay() -> Replay {
+        Replay {
+            version: Replay::VERSION,
+            size: 2,
+            tiles: vec![vec![TileValue(1), TileValue(2)], vec![TileValue(4), TileValue(3)]],
+            moves: vec![ReplayMove { tile: TileCoordinates::new(1, 1), time: 0.5, into: None }],
+            topology: Topology::Flat,
+            empty_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_board_at() {
+        let replay = replay();
+        assert_eq!(replay.board_at(0).unwrap().tiles, replay.tiles);
+        assert!(replay.board_at(1).unwrap().is_solved());
+    }
+
+    #[test]
+    fn test_invalid_move() {
+        let mut replay = replay();
+        replay.moves.push(ReplayMove { tile: TileCoordinates::new(0, 0), time: 1., into: None });
+        assert!(replay.validate().is_err());
+    }
+
+    #[test]
+    fn test_replay_code() {
+        let mut replay = replay();
+        replay.moves.extend([
+            ReplayMove { tile: TileCoordinates::new(0, 1), time: 1.2, into: None },
+            ReplayMove { tile: TileCoordinates::new(1, 1), time: 9.7, into: None },
+        ]);
+        let code = replay.to_code().unwrap();
+        assert_eq!(Replay::from_code(&code).unwrap(), replay);
+        assert!(code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert!(Replay::from_code(&code[..4]).is_err());
+        assert!(Replay::from_code("B#").is_err());
+        // the empty slot is on the left edge, nothing comes from its left
+        assert!(Replay::from_code(&format!("{}B", &code[..7])).is_err());
+        let torus = Replay { topology: Topology::Torus, ..replay.clone() };
+        assert!(replay.has_code() && !torus.has_code() && torus.to_code().is_err());
+    }
+
+    #[test]
+    fn test_unversioned_replay_migration() {
+        let legacy = "(size: 2, tiles: [[(1), (2)], [(4), (3)]], moves: [(tile: (i: 1, j: 1), time: 0.5)])";
+        let replay: Replay = persistence::parse_versioned(legacy, Format::Ron).unwrap();
+        assert_eq!(replay, self::replay());
+    }
+}
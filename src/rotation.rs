@@ -0,0 +1,147 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+
+use crate::{
+    marker::Markers,
+    taquin::{seeded_rotations, MoveCount, ShuffleSeed, Taquin, TaquinShuffled, TaquinSolved},
+    tile::{TileCoordinates, TileSlide, TileSelected, TileValue},
+    AppState,
+};
+
+/// Turn the selected tile counterclockwise and clockwise, in place of the assist and history export keys
+pub const ROTATE_LEFT_KEY: KeyCode = KeyCode::Q;
+pub const ROTATE_RIGHT_KEY: KeyCode = KeyCode::E;
+/// Below this angle a turning tile snaps to its rotation
+const TURN_PRECISION: f32 = 0.01;
+
+pub struct RotationPlugin;
+
+impl Plugin for RotationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<RotatingTiles>()
+            .add_systems(Update, (
+                draw_rotations.run_if(resource_equals(RotatingTiles(true)).and_then(on_event::<TaquinShuffled>())),
+                rotate_selected_tile.run_if(resource_equals(RotatingTiles(true)).and_then(not(any_with_component::<TileSlide>()))),
+            ).chain().run_if(in_state(AppState::Running)))
+            .add_systems(Update, (
+                turn_tiles_to_rotations.run_if(resource_changed::<Taquin>()),
+                turn_tiles,
+            ).chain());
+    }
+}
+
+/// Harder boards where the tiles are also turned by the shuffle and must be set upright, `--rotating-tiles`
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct RotatingTiles(pub bool);
+
+/// Rotations of a board placed from its code, taken by the next `draw_rotations` in place of drawn ones
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ArrangedRotations(pub Vec<u8>);
+
+/// Turn of a tile towards `target`, eased as the slides
+#[derive(Component, Debug, Clone, Copy)]
+#[component(storage = "SparseSet")]
+struct TileTurn {
+    target: Quat,
+}
+
+/// Turned around the axis facing the camera, clockwise as seen from it
+fn tile_rotation(quarter_turns: u8) -> Quat {
+    Quat::from_rotation_z(-(quarter_turns as f32) * FRAC_PI_2)
+}
+
+/// Drawn from the seed of the board when it has one, so a seed gives the same board. A pasted board keeps its own
+fn draw_rotations(
+    mut commands: Commands,
+    seed: Res<ShuffleSeed>,
+    arranged: Option<Res<ArrangedRotations>>,
+    mut taquin: ResMut<Taquin>,
+) {
+    if let Some(arranged) = arranged {
+        commands.remove_resource::<ArrangedRotations>();
+        taquin.set_rotations(arranged.0.clone());
+        info!("tiles turned as the board code says");
+        return;
+    }
+    let seed = seed.current.unwrap_or_else(rand::random);
+    let rotations = seeded_rotations(taquin.tiles_nb, seed);
+    taquin.set_rotations(rotations);
+    info!(seed, "tiles turned");
+}
+
+fn rotate_selected_tile(
+    keyboard_input: Res<Input<KeyCode>>,
+    selected_query: Query<&TileValue, With<TileSelected>>,
+    mut taquin: ResMut<Taquin>,
+    mut move_count: ResMut<MoveCount>,
+    mut solved_events: EventWriter<TaquinSolved>,
+) {
+    let quarter_turns = match (keyboard_input.just_released(ROTATE_LEFT_KEY), keyboard_input.just_released(ROTATE_RIGHT_KEY)) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => return,
+    };
+    let Ok(value) = selected_query.get_single() else {
+        return;
+    };
+    if taquin.is_empty_value(*value) {
+        return;
+    }
+    taquin.rotate(*value, quarter_turns);
+    if taquin.is_shuffled {
+        move_count.0 += 1;
+    }
+    debug!(?value, rotation = taquin.rotation(*value), "tile turned");
+    if taquin.is_solved() {
+        info!(width = taquin.width, height = taquin.height, shuffled = taquin.is_shuffled, "taquin solved");
+        solved_events.send_default();
+    }
+}
+
+/// Any change of the rotations, a shuffle, a turn or a new board, starts the tiles turning to them in the board plane
+fn turn_tiles_to_rotations(
+    mut commands: Commands,
+    taquin: Res<Taquin>,
+    markers: Res<Markers>,
+    tiles_query: Query<(Entity, &TileValue, &Transform, Option<&TileTurn>), With<TileCoordinates>>,
+) {
+    for (entity, value, transform, turn) in tiles_query.iter() {
+        let target = markers.rotation() * tile_rotation(taquin.rotation(*value));
+        if turn.map_or(transform.rotation, |turn| turn.target).angle_between(target) > TURN_PRECISION {
+            commands.entity(entity).insert(TileTurn { target });
+        }
+    }
+}
+
+fn turn_tiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tiles_query: Query<(Entity, &mut Transform, &TileTurn)>,
+) {
+    // Closes a quarter of the angle every 60th of a second
+    let factor = 1. - 0.75_f32.powf(time.delta_seconds() * 60.);
+    for (entity, mut transform, turn) in tiles_query.iter_mut() {
+        transform.rotation = transform.rotation.slerp(turn.target, factor);
+        if transform.rotation.angle_between(turn.target) < TURN_PRECISION {
+            transform.rotation = turn.target;
+            commands.entity(entity).remove::<TileTurn>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::rotation::tile_rotation;
+
+    #[test]
+    fn test_tile_rotation() {
+        assert_eq!(tile_rotation(0), Quat::IDENTITY);
+        // A quarter turn clockwise brings the top of the tile to its right
+        assert!((tile_rotation(1) * Vec3::Y).abs_diff_eq(Vec3::X, 1e-6));
+        assert!((tile_rotation(2) * Vec3::Y).abs_diff_eq(-Vec3::Y, 1e-6));
+    }
+}
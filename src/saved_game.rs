@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    exit::Shutdown,
+    pause::GameClock,
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    replay::ReplayPlayback,
+    stats::CurrentGame,
+    taquin::{ShuffleSeed, Taquin, TaquinShuffled, TaquinSolved},
+    tile::TileValue,
+    AppState,
+};
+
+const SAVE_KEY: KeyCode = KeyCode::S;
+
+pub struct SavedGamePlugin;
+
+impl Plugin for SavedGamePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SaveSlot>()
+            .add_event::<ResumeRequested>()
+            .add_event::<GameRestored>()
+            .add_systems(Update, resume_saved_game.run_if(in_state(AppState::MainMenu).and_then(on_event::<ResumeRequested>())))
+            .add_systems(OnEnter(AppState::Running), restore_saved_game.run_if(resource_exists::<ResumedGame>()))
+            .add_systems(Update, (
+                save_game_on_key,
+                delete_saved_game.run_if(on_event::<TaquinSolved>()),
+            ).run_if(in_state(AppState::Running)))
+            .add_systems(Last, save_game_on_shutdown.run_if(on_event::<Shutdown>()));
+    }
+}
+
+/// Asks to go on with the saved game, from the main menu
+#[derive(Event, Default)]
+pub struct ResumeRequested;
+
+/// Sent along the `TaquinShuffled` of a resumed game, the counters go on from the saved ones
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GameRestored {
+    pub moves: usize,
+    /// Seconds already played
+    pub elapsed: f32,
+}
+
+/// A game left unsolved on quit, to be resumed on the next launch
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedGame {
+    pub version: u32,
+    /// Columns of the board, the rows follow from the number of tiles
+    pub size: i8,
+    /// Values row after row, the empty slot as the number of tiles
+    pub tiles: Vec<TileValue>,
+    pub moves: usize,
+    pub elapsed: f32,
+    pub seed: Option<u64>,
+}
+
+impl SavedGame {
+    fn height(&self) -> i8 {
+        (self.tiles.len() / self.size.max(1) as usize) as i8
+    }
+}
+
+impl Versioned for SavedGame {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+/// The saved game found on disk, offered in the main menu
+#[derive(Resource, Debug)]
+pub struct SaveSlot(pub Option<SavedGame>);
+
+impl FromWorld for SaveSlot {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().saved_game();
+        if !persistence::exists(&path) {
+            return Self(None);
+        }
+        match persistence::load_versioned::<SavedGame>(path, Format::Ron) {
+            Ok(saved) if saved.size >= 2 && saved.height() >= 2 && saved.tiles.len() == (saved.size * saved.height()) as usize => Self(Some(saved)),
+            Ok(saved) => {
+                error!(size = saved.size, tiles = saved.tiles.len(), "saved game does not fit its size");
+                Self(None)
+            },
+            Err(error) => {
+                error!("cannot load saved game: {error}");
+                Self(None)
+            },
+        }
+    }
+}
+
+/// Counters of the saved game, sent once its tiles are set up
+#[derive(Resource)]
+struct ResumedGame(SavedGame);
+
+fn is_control_pressed(keyboard_input: &Input<KeyCode>) -> bool {
+    keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+/// The board is set before `setup_tiles`, which spawns the tiles where they were left
+fn resume_saved_game(
+    mut commands: Commands,
+    slot: Res<SaveSlot>,
+    mut taquin: ResMut<Taquin>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(saved) = slot.0.clone() else {
+        return;
+    };
+    info!(size = saved.size, moves = saved.moves, "saved game resumed");
+    *taquin = Taquin::rect(saved.size, saved.height());
+    taquin.set_tiles(saved.tiles.chunks(saved.size as usize).map(|row| row.to_vec()).collect());
+    taquin.is_shuffled = true;
+    commands.insert_resource(ResumedGame(saved));
+    next_state.set(AppState::SetupTiles);
+}
+
+fn restore_saved_game(
+    mut commands: Commands,
+    resumed: Res<ResumedGame>,
+    mut seed: ResMut<ShuffleSeed>,
+    mut shuffle_events: EventWriter<TaquinShuffled>,
+    mut restored_events: EventWriter<GameRestored>,
+) {
+    let ResumedGame(saved) = &*resumed;
+    seed.current = saved.seed;
+    shuffle_events.send_default();
+    restored_events.send(GameRestored { moves: saved.moves, elapsed: saved.elapsed });
+    commands.remove_resource::<ResumedGame>();
+}
+
+fn save_game(
+    taquin: &Taquin,
+    seed: &ShuffleSeed,
+    current_game: &CurrentGame,
+    now: f32,
+    paths: &DataPaths,
+    slot: &mut SaveSlot,
+) {
+    let Some(elapsed) = current_game.elapsed(now).filter(|_| taquin.is_shuffled && !taquin.is_solved()) else {
+        return;
+    };
+    let saved = SavedGame {
+        version: SavedGame::VERSION,
+        size: taquin.width,
+        tiles: taquin.tiles.iter().flatten().copied().collect(),
+        moves: current_game.moves(),
+        elapsed,
+        seed: seed.current,
+    };
+    match persistence::save_versioned(paths.saved_game(), &saved, Format::Ron) {
+        Ok(()) => info!(size = saved.size, moves = saved.moves, "game saved"),
+        Err(error) => error!("cannot save the game: {error}"),
+    }
+    slot.0 = Some(saved);
+}
+
+/// Ctrl + S, S alone starts the auto-solver
+fn save_game_on_key(
+    keyboard_input: Res<Input<KeyCode>>,
+    taquin: Res<Taquin>,
+    seed: Res<ShuffleSeed>,
+    current_game: Res<CurrentGame>,
+    clock: Res<GameClock>,
+    paths: Res<DataPaths>,
+    mut slot: ResMut<SaveSlot>,
+) {
+    if keyboard_input.just_released(SAVE_KEY) && is_control_pressed(&keyboard_input) {
+        save_game(&taquin, &seed, &current_game, clock.elapsed_seconds(), &paths, &mut slot);
+    }
+}
+
+/// Replays are not the player's game and are not saved
+fn save_game_on_shutdown(
+    taquin: Res<Taquin>,
+    seed: Res<ShuffleSeed>,
+    current_game: Res<CurrentGame>,
+    clock: Res<GameClock>,
+    paths: Res<DataPaths>,
+    mut slot: ResMut<SaveSlot>,
+    replay: Option<Res<ReplayPlayback>>,
+) {
+    if replay.is_none() {
+        save_game(&taquin, &seed, &current_game, clock.elapsed_seconds(), &paths, &mut slot);
+    }
+}
+
+/// A solved game is over, there is nothing left to resume
+fn delete_saved_game(
+    paths: Res<DataPaths>,
+    mut slot: ResMut<SaveSlot>,
+) {
+    if slot.0.take().is_none() {
+        return;
+    }
+    if let Err(error) = persistence::remove(paths.saved_game()) {
+        error!("cannot delete the saved game: {error}");
+    }
+}
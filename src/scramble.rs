@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use rand::seq::IteratorRandom;
+
+use crate::{
+    replay::ReplayPlayback,
+    taquin::{slide_tile, ShuffleRequested, ShuffleSeed, Taquin, TaquinShuffled},
+    tile::{Easing, EmptyTile, TileCoordinates, TileIndex, TileSlide},
+    is_board_playable,
+};
+
+const MODE_KEY: KeyCode = KeyCode::W;
+/// Random moves played per cell of the board
+const WALK_MOVES_PER_CELL: usize = 10;
+/// Slide speed during the walk, relative to a normal slide
+const WALK_SLIDE_SPEED: f32 = 6.;
+
+pub struct ScramblePlugin;
+
+impl Plugin for ScramblePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ShuffleMode>()
+            .add_systems(Update, (
+                toggle_shuffle_mode,
+                start_random_walk.run_if(resource_equals(ShuffleMode::RandomWalk)
+                    .and_then(resource_exists::<ShuffleRequested>())
+                    .and_then(not(resource_exists::<RandomWalk>()))
+                    .and_then(not(resource_exists::<ReplayPlayback>()))),
+                step_random_walk.run_if(resource_exists::<RandomWalk>()),
+            ).chain().run_if(is_board_playable.and_then(not(any_with_component::<TileSlide>()))));
+    }
+}
+
+/// How R scrambles the board
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ShuffleMode {
+    /// Tiles are put at once on a board drawn from the shuffle seed, every solvable board as likely
+    #[default]
+    Seeded,
+    /// Random legal moves are played from the current board, solvable by construction but closer to the solved board
+    RandomWalk,
+}
+
+impl ShuffleMode {
+    /// Name in `--shuffle` and the config file
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "permutation" => Some(ShuffleMode::Seeded),
+            "random-walk" => Some(ShuffleMode::RandomWalk),
+            _ => None,
+        }
+    }
+}
+
+/// Scramble being played, the player cannot move until it ends
+#[derive(Resource, Debug)]
+pub struct RandomWalk {
+    moves_left: usize,
+    /// Empty cell before the last move, not gone back to so the walk does not undo itself
+    previous: Option<TileCoordinates>,
+}
+
+/// Cells whose tile can slide into `empty`, but the one that just left it
+/// Picked from without collecting them, the walk plays a move every frame
+fn walk_candidates(empty: TileCoordinates, previous: Option<TileCoordinates>, width: i8, height: i8) -> impl Iterator<Item = TileCoordinates> {
+    [(1, 0), (-1, 0), (0, 1), (0, -1)].into_iter()
+        .map(move |offset| empty + offset)
+        .filter(move |cell| (0..width).contains(&cell.i) && (0..height).contains(&cell.j))
+        .filter(move |cell| Some(*cell) != previous)
+}
+
+fn toggle_shuffle_mode(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mode: ResMut<ShuffleMode>,
+) {
+    if keyboard_input.just_released(MODE_KEY) {
+        *mode = match *mode {
+            ShuffleMode::Seeded => ShuffleMode::RandomWalk,
+            ShuffleMode::RandomWalk => ShuffleMode::Seeded,
+        };
+        info!(mode = ?*mode, "shuffle mode");
+    }
+}
+
+/// The walk depends on the board it starts from, there is no seed to share
+fn start_random_walk(
+    mut commands: Commands,
+    taquin: Res<Taquin>,
+    mut seed: ResMut<ShuffleSeed>,
+) {
+    commands.remove_resource::<ShuffleRequested>();
+    seed.current = None;
+    let moves = WALK_MOVES_PER_CELL * taquin.tiles_nb;
+    info!(width = taquin.width, height = taquin.height, moves, "random walk started");
+    commands.insert_resource(RandomWalk { moves_left: moves, previous: None });
+}
+
+/// One move per slide, the walk goes on past its length while it is back on the solved board
+fn step_random_walk(
+    mut commands: Commands,
+    mut walk: ResMut<RandomWalk>,
+    mut taquin: ResMut<Taquin>,
+    index: Res<TileIndex>,
+    mut tiles_query: Query<(Entity, &Transform, &mut TileCoordinates), Without<EmptyTile>>,
+    mut empty_tile_query: Query<(&mut Transform, &mut TileCoordinates), With<EmptyTile>>,
+    mut shuffle_events: EventWriter<TaquinShuffled>,
+) {
+    if walk.moves_left == 0 && !taquin.is_solved() {
+        commands.remove_resource::<RandomWalk>();
+        taquin.is_shuffled = true;
+        info!("taquin shuffled");
+        shuffle_events.send_default();
+        return;
+    }
+    // With several empty slots, one of them at random
+    let Some((mut empty_tile_transform, mut empty_tile_coords)) = empty_tile_query.iter_mut().choose(&mut rand::thread_rng()) else {
+        return;
+    };
+    let empty = *empty_tile_coords;
+    let Some(cell) = walk_candidates(empty, walk.previous, taquin.width, taquin.height).choose(&mut rand::thread_rng()) else {
+        return;
+    };
+    let Some((entity, tile_transform, mut tile_coords)) = index.at(cell).and_then(|entity| tiles_query.get_mut(entity).ok()) else {
+        return;
+    };
+    let target = empty_tile_transform.translation;
+    slide_tile(
+        &mut commands,
+        taquin.as_mut(),
+        (entity, tile_transform, tile_coords.as_mut()),
+        (empty_tile_transform.as_mut(), empty_tile_coords.as_mut()),
+    );
+    commands.entity(entity).insert(TileSlide::new(target).with_speed(WALK_SLIDE_SPEED).with_easing(Easing::EaseOut));
+    walk.previous = Some(empty);
+    walk.moves_left = walk.moves_left.saturating_sub(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{scramble::walk_candidates, tile::TileCoordinates};
+
+    #[test]
+    fn test_walk_candidates() {
+        let corner = walk_candidates(TileCoordinates::new(0, 0), None, 3, 3).collect::<Vec<TileCoordinates>>();
+        assert_eq!(corner, vec![TileCoordinates::new(1, 0), TileCoordinates::new(0, 1)]);
+
+        let center = walk_candidates(TileCoordinates::new(1, 1), Some(TileCoordinates::new(1, 0)), 3, 3).collect::<Vec<TileCoordinates>>();
+        assert_eq!(center.len(), 3);
+        assert!(!center.contains(&TileCoordinates::new(1, 0)));
+    }
+}
@@ -0,0 +1,187 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use chrono::Datelike;
+use rand::Rng;
+
+use crate::{persistence, table::FrameTint};
+
+const PARTICLE_COUNT: usize = 150;
+/// Half the side of the square particles fall over
+const PARTICLE_AREA: f32 = 15.;
+const PARTICLE_HEIGHT: f32 = 20.;
+
+pub struct SeasonsPlugin;
+
+impl Plugin for SeasonsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SeasonalTheme>()
+            .add_systems(Startup, (apply_seasonal_frame, spawn_seasonal_particles))
+            .add_systems(Update, fall_seasonal_particles);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Winter,
+    Spring,
+    Autumn,
+    Halloween,
+}
+
+impl Season {
+    /// Theme of the day, if any
+    pub fn from_date(month: u32, day: u32) -> Option<Self> {
+        match (month, day) {
+            (12, _) | (1, _) => Some(Season::Winter),
+            (4, _) | (5, _) => Some(Season::Spring),
+            (10, 15..) => Some(Season::Halloween),
+            (9, 22..) | (10, _) | (11, _) => Some(Season::Autumn),
+            _ => None,
+        }
+    }
+
+    fn today() -> Option<Self> {
+        let now = persistence::timestamp();
+        chrono::DateTime::from_timestamp(now as i64, 0).and_then(|date| Self::from_date(date.month(), date.day()))
+    }
+
+    fn frame_tint(self) -> Color {
+        match self {
+            Season::Winter => Color::rgb(0.8, 0.9, 1.),
+            Season::Spring => Color::rgb(0.75, 0.95, 0.7),
+            Season::Autumn => Color::rgb(0.8, 0.5, 0.25),
+            Season::Halloween => Color::rgb(0.95, 0.45, 0.05),
+        }
+    }
+
+    /// Color of the falling particles, snow, petals or leaves
+    fn particle_color(self) -> Color {
+        match self {
+            Season::Winter => Color::rgba(1., 1., 1., 0.9),
+            Season::Spring => Color::rgba(1., 0.75, 0.85, 0.9),
+            Season::Autumn => Color::rgba(0.85, 0.4, 0.1, 0.9),
+            Season::Halloween => Color::rgba(0.2, 0.1, 0.25, 0.9),
+        }
+    }
+
+    /// Units per second downwards, and of the side to side flutter
+    fn fall(self) -> (f32, f32) {
+        match self {
+            Season::Winter => (1.5, 0.5),
+            Season::Spring => (1., 1.),
+            Season::Autumn | Season::Halloween => (2., 1.5),
+        }
+    }
+}
+
+/// Cosmetic theme in place, the one of the date unless overridden
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeasonalTheme(pub Option<Season>);
+
+impl SeasonalTheme {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Self::default()),
+            "none" => Some(Self(None)),
+            "winter" => Some(Self(Some(Season::Winter))),
+            "spring" => Some(Self(Some(Season::Spring))),
+            "autumn" => Some(Self(Some(Season::Autumn))),
+            "halloween" => Some(Self(Some(Season::Halloween))),
+            _ => None,
+        }
+    }
+}
+
+impl Default for SeasonalTheme {
+    fn default() -> Self {
+        Self(Season::today())
+    }
+}
+
+#[derive(Component)]
+struct SeasonalParticle {
+    /// Offsets the flutter of each particle
+    phase: f32,
+}
+
+/// Only replaces the default tint, a tint chosen by the player wins
+fn apply_seasonal_frame(
+    theme: Res<SeasonalTheme>,
+    mut tint: ResMut<FrameTint>,
+) {
+    if let Some(season) = theme.0.filter(|_| *tint == FrameTint::default()) {
+        info!(?season, "seasonal theme");
+        tint.0 = season.frame_tint();
+    }
+}
+
+fn spawn_seasonal_particles(
+    mut commands: Commands,
+    theme: Res<SeasonalTheme>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let Some(season) = theme.0 else {
+        return;
+    };
+    let mesh = meshes.add(shape::Quad::new(Vec2::splat(0.15)).into());
+    let material = materials.add(StandardMaterial {
+        base_color: season.particle_color(),
+        alpha_mode: AlphaMode::Blend,
+        double_sided: true,
+        cull_mode: None,
+        unlit: season == Season::Winter,
+        ..default()
+    });
+    let mut rng = rand::thread_rng();
+    for _ in 0..PARTICLE_COUNT {
+        let translation = Vec3::new(
+            rng.gen_range(-PARTICLE_AREA..PARTICLE_AREA),
+            rng.gen_range(0. ..PARTICLE_HEIGHT),
+            rng.gen_range(-PARTICLE_AREA..PARTICLE_AREA),
+        );
+        commands.spawn((PbrBundle {
+            mesh: mesh.clone(),
+            material: material.clone(),
+            transform: Transform::from_translation(translation).with_rotation(Quat::from_rotation_x(rng.gen_range(0. ..TAU))),
+            ..default()
+        }, SeasonalParticle { phase: rng.gen_range(0. ..TAU) }));
+    }
+}
+
+/// Particles flutter down and start again from the top once on the table
+fn fall_seasonal_particles(
+    time: Res<Time>,
+    theme: Res<SeasonalTheme>,
+    mut particles_query: Query<(&mut Transform, &SeasonalParticle)>,
+) {
+    let Some(season) = theme.0 else {
+        return;
+    };
+    let (speed, flutter) = season.fall();
+    let delta = time.delta_seconds();
+    for (mut transform, particle) in particles_query.iter_mut() {
+        let sway = (time.elapsed_seconds() + particle.phase).sin() * flutter;
+        transform.translation += Vec3::new(sway, -speed, sway * 0.5) * delta;
+        transform.rotate_local_x(flutter * delta);
+        if transform.translation.y < 0. {
+            transform.translation.y += PARTICLE_HEIGHT;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::seasons::Season;
+
+    #[test]
+    fn test_season_from_date() {
+        assert_eq!(Season::from_date(12, 24), Some(Season::Winter));
+        assert_eq!(Season::from_date(10, 31), Some(Season::Halloween));
+        assert_eq!(Season::from_date(10, 1), Some(Season::Autumn));
+        assert_eq!(Season::from_date(9, 21), None);
+        assert_eq!(Season::from_date(7, 14), None);
+    }
+}
@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+use crate::{
+    auto_solve::AutoSolvePlayback,
+    exit::{ExitConfirmed, Shutdown},
+    i18n::Localized,
+    locale::Locale,
+    persistence::{self, DataPaths},
+    replay::ReplayPlayback,
+    stats::{GameHistory, GameRecorded, SessionRecord},
+    taquin::{Taquin, TileMoved},
+    AppState,
+};
+
+/// Seconds the summary stays before the game quits on its own
+const SUMMARY_DURATION: f32 = 5.;
+
+pub struct SessionPlugin;
+
+impl Plugin for SessionPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Session>()
+            .add_systems(Update, (
+                count_session_games.run_if(on_event::<GameRecorded>()),
+                count_session_moves.run_if(on_event::<TileMoved>()
+                    .and_then(not(resource_exists::<ReplayPlayback>()))
+                    .and_then(not(resource_exists::<AutoSolvePlayback>()))),
+            ).run_if(in_state(AppState::Running)))
+            .add_systems(Update, (
+                show_session_summary.run_if(on_event::<ExitConfirmed>().and_then(not(resource_exists::<SessionSummary>()))),
+                close_session_summary.run_if(resource_exists::<SessionSummary>()),
+            ).chain());
+    }
+}
+
+/// What was played since launch
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Session {
+    pub record: SessionRecord,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            record: SessionRecord { started: persistence::timestamp(), ended: 0, games: 0, best_time: None, moves: 0 },
+        }
+    }
+}
+
+impl Session {
+    pub fn is_empty(&self) -> bool {
+        self.record.games == 0 && self.record.moves == 0
+    }
+
+    fn summary(&self, locale: &Locale) -> Localized {
+        let best = self.record.best_time.map_or_else(|| "-".to_string(), |time| locale.format_duration(time));
+        Localized::new("Games solved: {games}\nBest solve: {best}\nMoves played: {moves}")
+            .with_arg("games", locale.format_count(self.record.games))
+            .with_arg("best", best)
+            .with_arg("moves", locale.format_count(self.record.moves))
+    }
+}
+
+/// Shown until the game quits, nothing is left to clean up
+#[derive(Resource)]
+struct SessionSummary(Timer);
+
+fn count_session_games(
+    mut recorded_events: EventReader<GameRecorded>,
+    mut session: ResMut<Session>,
+) {
+    for GameRecorded(game) in recorded_events.read() {
+        session.record.games += 1;
+        session.record.best_time = Some(session.record.best_time.map_or(game.time, |best| best.min(game.time)));
+    }
+}
+
+fn count_session_moves(
+    mut tile_moved_events: EventReader<TileMoved>,
+    taquin: Res<Taquin>,
+    mut session: ResMut<Session>,
+) {
+    let moves = tile_moved_events.read().count();
+    if taquin.is_shuffled {
+        session.record.moves += moves;
+    }
+}
+
+/// Sessions where nothing was played quit right away and stay out of the history
+fn show_session_summary(
+    mut commands: Commands,
+    mut session: ResMut<Session>,
+    mut history: ResMut<GameHistory>,
+    paths: Res<DataPaths>,
+    locale: Res<Locale>,
+    mut shutdown_events: EventWriter<Shutdown>,
+) {
+    if session.is_empty() {
+        shutdown_events.send_default();
+        return;
+    }
+    session.record.ended = persistence::timestamp();
+    history.sessions.push(session.record.clone());
+    if let Err(error) = history.save(&paths) {
+        error!("cannot save game history: {error}");
+    }
+    info!(games = session.record.games, moves = session.record.moves, "session ended");
+
+    commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(30.),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(10),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "See you soon!",
+            TextStyle {
+                font_size: 100.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), Localized::new("See you soon!")));
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 50.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ).with_text_alignment(TextAlignment::Center), session.summary(&locale)));
+    });
+    commands.insert_resource(SessionSummary(Timer::from_seconds(SUMMARY_DURATION, TimerMode::Once)));
+}
+
+/// Any key quits before the end of the timer
+fn close_session_summary(
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time<Real>>,
+    mut summary: ResMut<SessionSummary>,
+    mut shutdown_events: EventWriter<Shutdown>,
+) {
+    // The key that confirmed the exit is still released on the first frame
+    if summary.is_added() {
+        return;
+    }
+    if summary.0.tick(time.delta()).finished() || keyboard_input.get_just_released().next().is_some() {
+        shutdown_events.send_default();
+    }
+}
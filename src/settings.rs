@@ -0,0 +1,327 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    keybindings::KeyBindings,
+    locale::Locale,
+    packs::TexturePacks,
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    sound::AudioSettings,
+    taquin::ControlScheme,
+    theme::{Theme, ThemeSettings},
+    TaquinSprites,
+};
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<SettingsImported>()
+            .add_systems(Update, (
+                apply_imported_settings.run_if(on_event::<SettingsImported>()),
+                sync_control_scheme.run_if(resource_changed::<ControlScheme>()),
+                sync_audio_settings.run_if(resource_changed::<AudioSettings>()),
+                sync_theme.run_if(resource_changed::<Theme>()),
+                sync_key_bindings.run_if(resource_changed::<KeyBindings>()),
+                sync_texture_pack.run_if(resource_changed::<TexturePacks>()),
+                sync_picture.run_if(resource_exists_and_changed::<TaquinSprites>()),
+                save_settings.run_if(resource_changed::<Settings>()),
+            ).chain());
+    }
+}
+
+/// Choices of the player kept between runs, loaded before the app is built as the board size and the texture pack
+/// are needed to build it. The command line wins over them for its run.
+/// The volumes, the theme and the key bindings are read from the resources of `sound`, `theme` and `keybindings`
+/// made from them, changes to these resources are copied back here to be saved
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Settings {
+    #[serde(default)]
+    pub version: u32,
+    pub control_scheme: ControlScheme,
+    /// Size of the board of the last game started from the main menu, the one of the texture pack when none
+    pub width: Option<i8>,
+    pub height: Option<i8>,
+    /// Folder of the texture pack, the default one when none
+    pub pack: Option<String>,
+    /// Picture of the pack the tiles show
+    pub picture: usize,
+    #[serde(default)]
+    pub vsync: VSync,
+    /// Size of the window out of fullscreen, the one the system picks when none
+    #[serde(default)]
+    pub resolution: Option<(u32, u32)>,
+    #[serde(default)]
+    pub key_repeat: KeyRepeat,
+    /// Language picked in the settings screen, the one of the system when none. `--locale` wins over it
+    #[serde(default)]
+    pub language: Option<Locale>,
+    /// The tutorial of the first launch was played through or skipped, see `tutorial`
+    #[serde(default)]
+    pub tutorial_done: bool,
+    /// Seconds without a move before a tile of the next move glows, see `idle_hint`. Never when none
+    #[serde(default = "default_idle_hint_delay")]
+    pub idle_hint_delay: Option<f32>,
+    #[serde(default = "AudioSettings::new")]
+    pub audio: AudioSettings,
+    #[serde(default = "default_theme")]
+    pub theme: Theme,
+    #[serde(default = "KeyBindings::new")]
+    pub key_bindings: KeyBindings,
+    /// Seconds since the unix epoch of the last save, the most recent settings win a profile merge, see `profile`
+    #[serde(default)]
+    pub changed_at: u64,
+}
+
+/// Choices of the settings screen for `Settings::idle_hint_delay`
+pub const IDLE_HINT_DELAYS: [(&str, Option<f32>); 4] = [("Off", None), ("15 s", Some(15.)), ("30 s", Some(30.)), ("60 s", Some(60.))];
+
+fn default_idle_hint_delay() -> Option<f32> {
+    Some(30.)
+}
+
+fn default_theme() -> Theme {
+    Theme::Day
+}
+
+/// Sent once a profile import or sync replaced the `Settings`, so the resources made from them follow
+#[derive(Event, Debug, Clone, Copy, Default)]
+pub struct SettingsImported;
+
+/// Whether frames wait for the refresh of the screen, see `display`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VSync {
+    #[default]
+    On,
+    /// Frames are shown as soon as they are drawn, they may tear
+    Off,
+}
+
+/// How a held arrow key repeats its move, see `read_keyboard_actions` in `taquin`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct KeyRepeat {
+    /// Seconds the key is held before it repeats
+    pub delay: f32,
+    /// Repeats a second after that
+    pub rate: f32,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self { delay: 0.3, rate: 12. }
+    }
+}
+
+impl KeyRepeat {
+    /// Choices of the settings screen
+    pub const PRESETS: [(&'static str, KeyRepeat); 3] = [
+        ("Slow", KeyRepeat { delay: 0.5, rate: 6. }),
+        ("Normal", KeyRepeat { delay: 0.3, rate: 12. }),
+        ("Fast", KeyRepeat { delay: 0.2, rate: 25. }),
+    ];
+
+    /// Repeats due while the key went from held `from` seconds to held `to` seconds
+    pub fn repeats(&self, from: f32, to: f32) -> u32 {
+        let count = |held: f32| if held < self.delay { 0 } else { ((held - self.delay) * self.rate) as u32 + 1 };
+        count(to).saturating_sub(count(from))
+    }
+}
+
+impl Versioned for Settings {
+    const VERSION: u32 = 2;
+
+    fn migrate(version: u32, contents: &str, format: Format) -> Result<Self, PersistenceError> {
+        match version {
+            // The volumes, the theme and the key bindings were in files of their own, see `adopt_separate_files`
+            1 => Ok(Self { version: Self::VERSION, ..format.parse(contents)? }),
+            _ => Err(PersistenceError::unsupported_version(version)),
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { version: Self::VERSION, control_scheme: ControlScheme::default(), width: None, height: None, pack: None, picture: 0, vsync: VSync::On, resolution: None, key_repeat: KeyRepeat::default(), language: None, tutorial_done: false, idle_hint_delay: default_idle_hint_delay(), audio: AudioSettings::new(), theme: default_theme(), key_bindings: KeyBindings::new(), changed_at: 0 }
+    }
+}
+
+impl Settings {
+    pub fn load(paths: &DataPaths) -> Self {
+        let path = paths.settings();
+        let mut settings = if persistence::exists(&path) {
+            persistence::load_versioned(&path, Format::Ron).unwrap_or_else(|error| {
+                error!("cannot load the settings, the default ones are used: {error}");
+                Self::default()
+            })
+        } else {
+            Self::default()
+        };
+        settings.adopt_separate_files(paths);
+        settings
+    }
+
+    /// Moves in the volumes, the theme and the key bindings saved in files of their own before version 2.
+    /// The files are removed once the settings holding them are saved
+    fn adopt_separate_files(&mut self, paths: &DataPaths) {
+        let files = [paths.audio_settings(), paths.theme(), paths.key_bindings()];
+        if !files.iter().any(persistence::exists) {
+            return;
+        }
+        if let Some(audio) = load_separate_file(&files[0]) {
+            self.audio = audio;
+        }
+        if let Some(ThemeSettings { theme, .. }) = load_separate_file(&files[1]) {
+            self.theme = theme;
+        }
+        if let Some(key_bindings) = load_separate_file(&files[2]) {
+            self.key_bindings = key_bindings;
+        }
+        if let Err(error) = persistence::save_versioned(paths.settings(), self, Format::Ron) {
+            error!("cannot save the settings, the separate files are kept: {error}");
+            return;
+        }
+        for path in files.iter().filter(|path| persistence::exists(path)) {
+            if let Err(error) = persistence::remove(path) {
+                error!("cannot remove {}: {error}", path.display());
+            }
+        }
+    }
+
+    /// Board size when the command line gives none
+    pub fn board_size(&self) -> Option<(i8, i8)> {
+        self.width.map(|width| (width, self.height.unwrap_or(width)))
+    }
+}
+
+/// A file that cannot be read is dropped with the others, its defaults stay
+fn load_separate_file<T: Versioned>(path: &Path) -> Option<T> {
+    if !persistence::exists(path) {
+        return None;
+    }
+    persistence::load_versioned(path, Format::Ron).map_err(|error| error!("cannot move {} into the settings: {error}", path.display())).ok()
+}
+
+/// The resources made from the settings are replaced along with them, which copies nothing back as they are equal
+fn apply_imported_settings(
+    settings: Res<Settings>,
+    mut audio_settings: ResMut<AudioSettings>,
+    mut theme: ResMut<Theme>,
+    mut key_bindings: ResMut<KeyBindings>,
+) {
+    if *audio_settings != settings.audio {
+        *audio_settings = settings.audio;
+    }
+    if *theme != settings.theme {
+        *theme = settings.theme;
+    }
+    if *key_bindings != settings.key_bindings {
+        *key_bindings = settings.key_bindings.clone();
+    }
+}
+
+/// The scheme set from the command line is not saved, only the ones switched to in game
+fn sync_control_scheme(
+    control_scheme: Res<ControlScheme>,
+    mut settings: ResMut<Settings>,
+) {
+    if !control_scheme.is_added() && settings.control_scheme != *control_scheme {
+        settings.control_scheme = *control_scheme;
+    }
+}
+
+fn sync_audio_settings(
+    audio_settings: Res<AudioSettings>,
+    mut settings: ResMut<Settings>,
+) {
+    if settings.audio != *audio_settings {
+        settings.audio = *audio_settings;
+    }
+}
+
+fn sync_theme(
+    theme: Res<Theme>,
+    mut settings: ResMut<Settings>,
+) {
+    if settings.theme != *theme {
+        settings.theme = *theme;
+    }
+}
+
+fn sync_key_bindings(
+    key_bindings: Res<KeyBindings>,
+    mut settings: ResMut<Settings>,
+) {
+    if settings.key_bindings != *key_bindings {
+        settings.key_bindings = key_bindings.clone();
+    }
+}
+
+fn sync_texture_pack(
+    packs: Res<TexturePacks>,
+    mut settings: ResMut<Settings>,
+) {
+    let folder = Some(packs.selected().folder.clone());
+    if !packs.is_added() && settings.pack != folder {
+        settings.pack = folder;
+    }
+}
+
+/// Only the pictures of the pack are kept, a custom picture or a gallery one may be gone by the next run
+fn sync_picture(
+    sprites: Res<TaquinSprites>,
+    packs: Res<TexturePacks>,
+    mut settings: ResMut<Settings>,
+) {
+    let picture = if sprites.selected < packs.selected().manifest.images.len() { sprites.selected } else { 0 };
+    if !sprites.is_added() && settings.picture != picture {
+        settings.picture = picture;
+    }
+}
+
+fn save_settings(
+    paths: Res<DataPaths>,
+    mut settings: ResMut<Settings>,
+) {
+    // Nothing to write until a setting changes
+    if settings.is_added() {
+        return;
+    }
+    // Not a change of its own, the settings would be saved again on the next frame
+    settings.bypass_change_detection().changed_at = persistence::timestamp();
+    if let Err(error) = persistence::save_versioned(paths.settings(), settings.as_ref(), Format::Ron) {
+        error!("cannot save the settings: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{keybindings::KeyBindings, persistence::{self, Format, Versioned}, settings::{KeyRepeat, Settings, VSync}, sound::AudioSettings, taquin::ControlScheme, theme::Theme};
+
+    #[test]
+    fn test_settings_round_trip() {
+        let settings = Settings { control_scheme: ControlScheme::Classic, width: Some(5), height: Some(4), pack: Some("default".to_string()), picture: 2, ..Settings::default() };
+        let contents = Format::Ron.serialize(&settings).unwrap();
+        assert_eq!(persistence::parse_versioned::<Settings>(&contents, Format::Ron).unwrap(), settings);
+        assert_eq!(settings.board_size(), Some((5, 4)));
+        assert_eq!(Settings::default().board_size(), None);
+        // Written before the display settings
+        let older = persistence::parse_versioned::<Settings>("(version: 1, control_scheme: Cursor, width: None, height: None, pack: None, picture: 0)", Format::Ron).unwrap();
+        assert_eq!((older.vsync, older.resolution, older.key_repeat, older.language, older.tutorial_done), (VSync::On, None, KeyRepeat::default(), None, false));
+        assert_eq!(older.idle_hint_delay, Some(30.));
+        // Written when the volumes, the theme and the key bindings had files of their own
+        assert_eq!((older.version, older.audio, older.theme, older.key_bindings), (Settings::VERSION, AudioSettings::new(), Theme::Day, KeyBindings::new()));
+    }
+
+    #[test]
+    fn test_key_repeat() {
+        let repeat = KeyRepeat { delay: 0.5, rate: 10. };
+        assert_eq!(repeat.repeats(0., 0.4), 0);
+        assert_eq!(repeat.repeats(0.4, 0.5), 1);
+        assert_eq!(repeat.repeats(0.5, 0.55), 0);
+        // A long frame catches up on every repeat it covered
+        assert_eq!(repeat.repeats(0.55, 0.85), 3);
+    }
+}
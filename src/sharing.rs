@@ -0,0 +1,277 @@
+use std::{collections::BTreeMap, fs, path::{Component, Path}};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    archive::{self, ArchiveFile},
+    mods::{self, ModManifest, Mods},
+    packs::{self, PackManifest, TexturePacks},
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+};
+
+/// Lists every other file of the archive with its hash
+const CHECKSUMS_FILE: &str = "checksums.ron";
+
+pub struct SharingPlugin;
+
+impl Plugin for SharingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (export_shared, import_shared));
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharedKind {
+    TexturePack,
+    /// A mod, with its challenges, goals and themes
+    ChallengeSet,
+}
+
+impl SharedKind {
+    fn manifest_file(self) -> &'static str {
+        match self {
+            SharedKind::TexturePack => packs::MANIFEST_FILE,
+            SharedKind::ChallengeSet => mods::MANIFEST_FILE,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Checksums {
+    version: u32,
+    kind: SharedKind,
+    folder: String,
+    /// BLAKE3 hash in hexadecimal of each file, by path within the folder
+    files: BTreeMap<String, String>,
+}
+
+impl Versioned for Checksums {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+/// A pack or mod folder, as shared in a zip archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedFolder {
+    pub kind: SharedKind,
+    pub folder: String,
+    /// Names relative to the folder
+    pub files: Vec<ArchiveFile>,
+}
+
+/// A path of the archive that stays in the folder it is extracted to
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains('\\') && Path::new(name).components().all(|component| matches!(component, Component::Normal(_)))
+}
+
+fn hash(contents: &[u8]) -> String {
+    blake3::hash(contents).to_hex().to_string()
+}
+
+fn read_folder(root: &Path, folder: &Path, files: &mut Vec<ArchiveFile>) -> Result<(), String> {
+    let entries = fs::read_dir(folder).map_err(|error| format!("{}: {error}", folder.display()))?;
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.is_dir() {
+            read_folder(root, &path, files)?;
+            continue;
+        }
+        let name = path.strip_prefix(root).map_err(|error| error.to_string())?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let contents = fs::read(&path).map_err(|error| format!("{}: {error}", path.display()))?;
+        files.push(ArchiveFile { name, contents });
+    }
+    Ok(())
+}
+
+impl SharedFolder {
+    pub fn read(kind: SharedKind, path: &Path) -> Result<Self, String> {
+        let folder = path.file_name().map(|name| name.to_string_lossy().into_owned()).ok_or("no folder name")?;
+        let mut files = Vec::new();
+        read_folder(path, path, &mut files)?;
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Self { kind, folder, files })
+    }
+
+    /// Zip archive of the folder and the checksums of its files
+    pub fn to_zip(&self) -> Result<Vec<u8>, String> {
+        let checksums = Checksums {
+            version: Checksums::VERSION,
+            kind: self.kind,
+            folder: self.folder.clone(),
+            files: self.files.iter().map(|file| (file.name.clone(), hash(&file.contents))).collect(),
+        };
+        let checksums = Format::Ron.serialize(&checksums).map_err(|error| error.to_string())?;
+        let mut files = vec![ArchiveFile { name: CHECKSUMS_FILE.to_string(), contents: checksums.into_bytes() }];
+        files.extend(self.files.iter().map(|file| ArchiveFile { name: format!("{}/{}", self.folder, file.name), contents: file.contents.clone() }));
+        Ok(archive::write_zip(&files))
+    }
+
+    /// Reads an archive written by `to_zip`, every file must be listed with the right hash
+    pub fn from_zip(bytes: &[u8]) -> Result<Self, String> {
+        let mut files = archive::read_zip(bytes)?;
+        let checksums = files.iter().position(|file| file.name == CHECKSUMS_FILE).map(|index| files.remove(index)).ok_or("no checksums in the archive")?;
+        let checksums = String::from_utf8(checksums.contents).map_err(|_| "checksums are not UTF-8".to_string())?;
+        let checksums: Checksums = persistence::parse_versioned(&checksums, Format::Ron).map_err(|error| error.to_string())?;
+        if !is_safe_name(&checksums.folder) || checksums.folder.contains('/') {
+            return Err(format!("invalid folder name {}", checksums.folder));
+        }
+
+        let prefix = format!("{}/", checksums.folder);
+        let files = files.into_iter().map(|file| {
+            let name = file.name.strip_prefix(&prefix).filter(|name| is_safe_name(name)).ok_or_else(|| format!("unexpected file {}", file.name))?;
+            match checksums.files.get(name) {
+                Some(expected) if *expected == hash(&file.contents) => Ok(ArchiveFile { name: name.to_string(), contents: file.contents }),
+                Some(_) => Err(format!("{name} does not match its checksum")),
+                None => Err(format!("{name} is not in the checksums")),
+            }
+        }).collect::<Result<Vec<ArchiveFile>, String>>()?;
+        if let Some(missing) = checksums.files.keys().find(|name| !files.iter().any(|file| file.name == **name)) {
+            return Err(format!("missing file {missing}"));
+        }
+        let shared = Self { kind: checksums.kind, folder: checksums.folder, files };
+        shared.validate()?;
+        Ok(shared)
+    }
+
+    /// Checks the manifest as the pack or mod would be checked once installed
+    fn validate(&self) -> Result<(), String> {
+        let manifest_file = self.kind.manifest_file();
+        let manifest = self.files.iter().find(|file| file.name == manifest_file).ok_or_else(|| format!("no {manifest_file}"))?;
+        let manifest = std::str::from_utf8(&manifest.contents).map_err(|_| format!("{manifest_file} is not UTF-8"))?;
+        match self.kind {
+            SharedKind::TexturePack => {
+                let manifest = PackManifest::parse(manifest)?;
+                if let Some(image) = manifest.images.iter().find(|image| !self.files.iter().any(|file| file.name == **image)) {
+                    return Err(format!("missing image {image}"));
+                }
+            },
+            SharedKind::ChallengeSet => {
+                ModManifest::parse(manifest)?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Writes the folder in the packs or mods folder, an installed folder is never overwritten
+    fn install(&self) -> Result<(), String> {
+        let root = match self.kind {
+            SharedKind::TexturePack => packs::packs_root(),
+            SharedKind::ChallengeSet => mods::mods_root(),
+        };
+        let folder = root.join(&self.folder);
+        if folder.exists() {
+            return Err(format!("{} is already installed", self.folder));
+        }
+        for file in &self.files {
+            persistence::write(folder.join(&file.name), &file.contents).map_err(|error| error.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+fn is_control_pressed(keyboard_input: &Input<KeyCode>) -> bool {
+    keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+fn export_folder(kind: SharedKind, path: &Path, paths: &DataPaths) {
+    let shared = SharedFolder::read(kind, path);
+    let archive_path = paths.exports().join("shared").join(format!("{}.zip", shared.as_ref().map_or("", |shared| shared.folder.as_str())));
+    match shared.and_then(|shared| shared.to_zip()).and_then(|zip| persistence::write(&archive_path, zip).map_err(|error| error.to_string())) {
+        Ok(()) => info!("{kind:?} exported to {}", archive_path.display()),
+        Err(error) => error!("cannot export {}: {error}", path.display()),
+    }
+}
+
+/// Ctrl+X writes the texture pack in use and every valid mod to zip archives
+fn export_shared(
+    keyboard_input: Res<Input<KeyCode>>,
+    packs: Res<TexturePacks>,
+    mods: Res<Mods>,
+    paths: Res<DataPaths>,
+) {
+    if !(keyboard_input.just_released(KeyCode::X) && is_control_pressed(&keyboard_input)) {
+        return;
+    }
+    export_folder(SharedKind::TexturePack, &packs::packs_root().join(&packs.selected().folder), &paths);
+    for loaded in mods.mods.iter().filter(|loaded| loaded.manifest.is_ok()) {
+        export_folder(SharedKind::ChallengeSet, &mods::mods_root().join(&loaded.folder), &paths);
+    }
+}
+
+/// Ctrl+O installs the archives dropped in the imports folder, the new packs and mods are listed right away
+fn import_shared(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut packs: ResMut<TexturePacks>,
+    mut mods: ResMut<Mods>,
+    paths: Res<DataPaths>,
+) {
+    if !(keyboard_input.just_released(KeyCode::O) && is_control_pressed(&keyboard_input)) {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(paths.imports()) else {
+        info!("no archive to import, drop them in {}", paths.imports().display());
+        return;
+    };
+    let archives = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "zip"));
+    let mut installed = 0;
+    for path in archives {
+        let result = fs::read(&path).map_err(|error| error.to_string())
+            .and_then(|bytes| SharedFolder::from_zip(&bytes))
+            .and_then(|shared| shared.install().map(|()| shared));
+        match result {
+            Ok(shared) => {
+                info!(kind = ?shared.kind, folder = shared.folder, "imported {}", path.display());
+                installed += 1;
+            },
+            Err(error) => warn!("cannot import {}: {error}", path.display()),
+        }
+    }
+    if installed == 0 {
+        return;
+    }
+    // Keeps the pack in use without reloading its pictures
+    match TexturePacks::discover(Some(&packs.selected().folder)) {
+        Ok(discovered) => *packs.bypass_change_detection() = discovered,
+        Err(error) => error!("cannot list the texture packs again: {error}"),
+    }
+    *mods = Mods::discover();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        archive::{self, ArchiveFile},
+        sharing::{SharedFolder, SharedKind},
+    };
+
+    #[test]
+    fn test_shared_folder_round_trip() {
+        let shared = SharedFolder {
+            kind: SharedKind::TexturePack,
+            folder: "mountains".to_string(),
+            files: vec![
+                ArchiveFile { name: "alps.png".to_string(), contents: vec![1, 2, 3] },
+                ArchiveFile { name: "pack.ron".to_string(), contents: br#"(name: "Mountains", author: "Someone", images: ["alps.png"])"#.to_vec() },
+            ],
+        };
+        let zip = shared.to_zip().unwrap();
+        assert_eq!(SharedFolder::from_zip(&zip), Ok(shared.clone()));
+
+        let mut files = archive::read_zip(&zip).unwrap();
+        let image = files.iter_mut().find(|file| file.name == "mountains/alps.png").unwrap();
+        image.contents = vec![3, 2, 1];
+        assert!(SharedFolder::from_zip(&archive::write_zip(&files)).is_err());
+
+        files.push(ArchiveFile { name: "../escape.txt".to_string(), contents: vec![] });
+        assert!(SharedFolder::from_zip(&archive::write_zip(&files)).is_err());
+    }
+}
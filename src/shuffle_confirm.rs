@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+
+use crate::{
+    focus::FocusScope,
+    i18n::Localized,
+    pause::GameClock,
+    replay::ReplayPlayback,
+    scramble::RandomWalk,
+    stats::CurrentGame,
+    taquin::{ShuffleRequested, ShuffleSeed, TaquinAction},
+    is_board_playable,
+};
+
+/// Games with more moves than this ask before being shuffled away
+const CONFIRM_MOVES: usize = 10;
+
+pub struct ShuffleConfirmPlugin;
+
+impl Plugin for ShuffleConfirmPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // Both read every action even when they have nothing to do, so none is left for later
+            .add_systems(Update, (request_shuffle, confirm_shuffle).chain().run_if(is_board_playable
+                .and_then(not(resource_exists::<ReplayPlayback>()))
+                .and_then(not(resource_exists::<RandomWalk>()))));
+    }
+}
+
+/// The "shuffle anyway?" dialog being shown
+#[derive(Resource)]
+pub struct ShuffleConfirmation {
+    entity: Entity,
+    /// Shift was held, the same board is shuffled again
+    same_seed: bool,
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+enum ShuffleButton {
+    Shuffle,
+    Keep,
+}
+
+fn shuffle_now(commands: &mut Commands, seed: &mut ShuffleSeed, same_seed: bool) {
+    if same_seed {
+        seed.next = seed.current;
+    }
+    commands.init_resource::<ShuffleRequested>();
+}
+
+/// R or the shuffle button of a gamepad shuffles, Shift + R shuffles the same board again
+fn request_shuffle(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut action_events: EventReader<TaquinAction>,
+    current_game: Res<CurrentGame>,
+    clock: Res<GameClock>,
+    mut seed: ResMut<ShuffleSeed>,
+    confirmation: Option<Res<ShuffleConfirmation>>,
+) {
+    if action_events.read().filter(|action| **action == TaquinAction::Shuffle).count() == 0 || confirmation.is_some() {
+        return;
+    }
+    let same_seed = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let in_progress = current_game.elapsed(clock.elapsed_seconds()).is_some();
+    if !in_progress || current_game.moves() <= CONFIRM_MOVES {
+        shuffle_now(&mut commands, &mut seed, same_seed);
+        return;
+    }
+
+    let entity = commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(20.),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(10),
+        ..default()
+    }, FocusScope)).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 50.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ).with_text_alignment(TextAlignment::Center), Localized::new("{moves} moves played, shuffle anyway?\nY or shuffle again to shuffle, N or slide to keep playing").with_arg("moves", current_game.moves())));
+        for (label, button) in [("Shuffle", ShuffleButton::Shuffle), ("Keep playing", ShuffleButton::Keep)] {
+            parent.spawn((ButtonBundle {
+                style: Style {
+                    width: Val::Px(300.),
+                    padding: UiRect::axes(Val::Px(20.), Val::Px(8.)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                ..default()
+            }, button)).with_children(|parent| {
+                parent.spawn((TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font_size: 50.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ), Localized::new(label)));
+            });
+        }
+    }).id();
+    commands.insert_resource(ShuffleConfirmation { entity, same_seed });
+}
+
+/// A gamepad answers with its shuffle and slide buttons
+fn confirm_shuffle(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut action_events: EventReader<TaquinAction>,
+    button_query: Query<(&Interaction, &ShuffleButton), Changed<Interaction>>,
+    confirmation: Option<Res<ShuffleConfirmation>>,
+    mut seed: ResMut<ShuffleSeed>,
+) {
+    let actions = action_events.read().copied().collect::<Vec<TaquinAction>>();
+    let Some(confirmation) = confirmation else {
+        return;
+    };
+    let pressed = button_query.iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| *button);
+    if keyboard_input.just_released(KeyCode::Y) || actions.contains(&TaquinAction::Shuffle) || pressed == Some(ShuffleButton::Shuffle) {
+        shuffle_now(&mut commands, &mut seed, confirmation.same_seed);
+    } else if !keyboard_input.just_released(KeyCode::N) && !actions.contains(&TaquinAction::Slide) && pressed.is_none() {
+        return;
+    }
+    commands.entity(confirmation.entity).despawn_recursive();
+    commands.remove_resource::<ShuffleConfirmation>();
+}
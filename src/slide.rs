@@ -0,0 +1,406 @@
+#[cfg(feature = "bevy")]
+use std::collections::HashMap;
+
+#[cfg(feature = "bevy")]
+use bevy::{animation::animation_player, prelude::*, transform::TransformSystem};
+
+/// Seconds an eased slide lasts at speed 1, lerped or played by a clip
+#[cfg(feature = "bevy")]
+const EASED_SLIDE_DURATION: f32 = 0.25;
+/// Strength of the overshoot, at 1 the tile goes about 4% of the way past its cell
+const OVERSHOOT: f32 = 1.;
+/// Keyframes of a slide clip after the first, the clips interpolate linearly between them
+#[cfg(feature = "bevy")]
+const SLIDE_KEYFRAMES: usize = 24;
+/// Easings a slide clip is built for
+#[cfg(feature = "bevy")]
+const SLIDE_EASINGS: [Easing; 3] = [Easing::Overshoot, Easing::EaseOut, Easing::Bounce];
+/// Name of every tile, the slide clips animate the tile playing them
+#[cfg(feature = "bevy")]
+const TILE_NAME: &str = "tile";
+
+/// Slides the tiles given a `TileSlide`, with their `TileAnimations` or lerped, and sends `TileAnimationFinished`
+/// as they land
+#[cfg(feature = "bevy")]
+pub struct SlidePlugin;
+
+#[cfg(feature = "bevy")]
+impl Plugin for SlidePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<TileAnimationFinished>()
+            .add_systems(PostUpdate, (
+                (stop_removed_slides, start_slides).chain().before(animation_player),
+                place_sliding_tiles.after(animation_player).before(TransformSystem::TransformPropagate),
+                (start_lerped_slides, advance_lerped_slides).chain().after(stop_removed_slides).before(TransformSystem::TransformPropagate),
+            ));
+    }
+}
+
+/// Curve a tile follows to its cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Easing {
+    /// Closes a quarter of the distance every 60th of a second
+    #[default]
+    Approach,
+    /// Fast start, slow landing
+    EaseOut,
+    /// Lands with a few shrinking bounces
+    Bounce,
+    /// Goes slightly past its cell and settles back
+    Overshoot,
+}
+
+impl Easing {
+    /// Share of the way covered once `t` of the slide is played, both from 0 to 1
+    pub fn ease(self, t: f32) -> f32 {
+        match self {
+            Easing::Approach => t,
+            Easing::EaseOut => 1. - (1. - t).powi(3),
+            Easing::Bounce => {
+                let (n, d) = (7.5625, 2.75);
+                if t < 1. / d {
+                    n * t * t
+                } else if t < 2. / d {
+                    let t = t - 1.5 / d;
+                    n * t * t + 0.75
+                } else if t < 2.5 / d {
+                    let t = t - 2.25 / d;
+                    n * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / d;
+                    n * t * t + 0.984375
+                }
+            },
+            Easing::Overshoot => {
+                let t = t - 1.;
+                1. + (OVERSHOOT + 1.) * t * t * t + OVERSHOOT * t * t
+            },
+        }
+    }
+}
+
+/// Slide of a tile to `target`, any number of tiles can slide at once. Added and removed at every move,
+/// so it lives in a sparse set rather than moving the whole tile between tables each time
+#[cfg(feature = "bevy")]
+#[derive(Component, Debug, Clone, Copy)]
+#[component(storage = "SparseSet")]
+pub struct TileLerp {
+    pub target: Vec3,
+    /// Relative to a normal slide
+    pub speed: f32,
+    pub easing: Easing,
+    /// Where an eased slide starts, set on its first frame
+    from: Option<Vec3>,
+    /// Share of an eased slide played
+    progress: f32,
+    /// Where the tile leaves the board and where it comes back, for a slide through the edges of a torus
+    wrap: Option<(Vec3, Vec3)>,
+}
+
+#[cfg(feature = "bevy")]
+impl TileLerp {
+    pub fn new(target: Vec3) -> Self {
+        Self { target, speed: 1., easing: Easing::default(), from: None, progress: 0., wrap: None }
+    }
+
+    pub fn with_speed(self, speed: f32) -> Self {
+        Self { speed, ..self }
+    }
+
+    pub fn with_easing(self, easing: Easing) -> Self {
+        Self { easing, ..self }
+    }
+
+    /// Slides out to `exit` first, then in from `entry` to `target`
+    pub fn with_wrap(self, exit: Vec3, entry: Vec3) -> Self {
+        Self { wrap: Some((exit, entry)), ..self }
+    }
+
+    /// Moves `translation` along the slide for `delta` seconds of game time, true once it landed on `target`
+    pub fn advance(&mut self, translation: &mut Vec3, delta: f32) -> bool {
+        let Some((exit, entry)) = self.wrap else {
+            return self.advance_to(self.target, translation, delta);
+        };
+        if self.advance_to(exit, translation, delta) {
+            *translation = entry;
+            *self = Self { from: None, progress: 0., wrap: None, ..*self };
+        }
+        false
+    }
+
+    fn advance_to(&mut self, target: Vec3, translation: &mut Vec3, delta: f32) -> bool {
+        let delta = delta * self.speed;
+        let landed = if self.easing == Easing::Approach {
+            // Closes a quarter of the distance every 60th of a second, scaled by the game and slide speeds
+            let factor = 1. - 0.75_f32.powf(delta * 60.);
+            *translation = translation.lerp(target, factor);
+            translation.abs_diff_eq(target, 0.01)
+        } else {
+            let from = *self.from.get_or_insert(*translation);
+            self.progress = (self.progress + delta / EASED_SLIDE_DURATION).min(1.);
+            *translation = from.lerp(target, self.easing.ease(self.progress));
+            self.progress >= 1.
+        };
+        if landed {
+            *translation = target;
+        }
+        landed
+    }
+}
+
+/// Sent when a tile lands at the end of its `TileSlide`
+#[cfg(feature = "bevy")]
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TileAnimationFinished(pub Entity);
+
+/// Slide of a tile to `target`, played by the `AnimationPlayer` of the tile with one of its `TileAnimations`,
+/// or by a `TileLerp` for the tiles of mega boards, which have no player.
+/// Any number of tiles can slide at once. Added and removed at every move, so it lives in a sparse set rather than
+/// moving the whole tile between tables each time
+#[cfg(feature = "bevy")]
+#[derive(Component, Debug, Clone, Copy)]
+#[component(storage = "SparseSet")]
+pub struct TileSlide {
+    pub target: Vec3,
+    /// Relative to a normal slide
+    pub speed: f32,
+    pub easing: Easing,
+    /// Where the tile leaves the board and where it comes back, for a slide through the edges of a torus
+    wrap: Option<(Vec3, Vec3)>,
+}
+
+#[cfg(feature = "bevy")]
+impl TileSlide {
+    pub fn new(target: Vec3) -> Self {
+        Self { target, speed: 1., easing: Easing::Overshoot, wrap: None }
+    }
+
+    pub fn with_speed(self, speed: f32) -> Self {
+        Self { speed, ..self }
+    }
+
+    pub fn with_easing(self, easing: Easing) -> Self {
+        Self { easing, ..self }
+    }
+
+    /// Slides out to `exit` first, then in from `entry` to `target`
+    pub fn with_wrap(self, exit: Vec3, entry: Vec3) -> Self {
+        Self { wrap: Some((exit, entry)), ..self }
+    }
+
+    /// Where the clip playing ends
+    fn leg_target(&self) -> Vec3 {
+        self.wrap.map_or(self.target, |(exit, _)| exit)
+    }
+}
+
+/// Slide clips of the tiles, for each side a tile comes from and each easing. Built for the cells of the board when
+/// the tiles are spawned, they bring the tile from one cell away to zero: `place_sliding_tiles` adds the cell it
+/// slides to
+#[cfg(feature = "bevy")]
+#[derive(Component, Debug, Default, Clone)]
+pub struct TileAnimations {
+    pub up: HashMap<Easing, Handle<AnimationClip>>,
+    pub right: HashMap<Easing, Handle<AnimationClip>>,
+    pub down: HashMap<Easing, Handle<AnimationClip>>,
+    pub left: HashMap<Easing, Handle<AnimationClip>>,
+    /// From a cell to the next one of its row and of its column
+    steps: (Vec3, Vec3),
+}
+
+#[cfg(feature = "bevy")]
+impl TileAnimations {
+    pub fn new(clips: &mut Assets<AnimationClip>, column_step: Vec3, row_step: Vec3) -> Self {
+        let mut eased_clips = |offset: Vec3| SLIDE_EASINGS.into_iter()
+            .map(|easing| (easing, clips.add(slide_clip(offset, easing))))
+            .collect::<HashMap<Easing, Handle<AnimationClip>>>();
+        Self {
+            up: eased_clips(row_step),
+            right: eased_clips(-column_step),
+            down: eased_clips(-row_step),
+            left: eased_clips(column_step),
+            steps: (column_step, row_step),
+        }
+    }
+
+    /// Clip of a tile `offset` away from where it slides to, the side closest to it
+    fn clip(&self, offset: Vec3, easing: Easing) -> Handle<AnimationClip> {
+        let (column_step, row_step) = self.steps;
+        let sides = [(row_step, &self.up), (-column_step, &self.right), (-row_step, &self.down), (column_step, &self.left)];
+        let clips = sides.into_iter()
+            .min_by(|(a, _), (b, _)| a.distance_squared(offset).total_cmp(&b.distance_squared(offset)))
+            .map_or(&self.up, |(_, clips)| clips);
+        clips.get(&easing).or_else(|| clips.get(&Easing::Overshoot)).cloned().unwrap_or_default()
+    }
+}
+
+/// Every tile carries it for the slide clips to find it
+#[cfg(feature = "bevy")]
+pub fn tile_name() -> Name {
+    Name::new(TILE_NAME)
+}
+
+/// Offsets of a tile coming from `offset` to zero along `easing`, from the first keyframe to the last
+#[cfg(feature = "bevy")]
+fn slide_keyframes(offset: Vec3, easing: Easing) -> Vec<Vec3> {
+    (0..=SLIDE_KEYFRAMES).map(|index| offset * (1. - easing.ease(index as f32 / SLIDE_KEYFRAMES as f32))).collect()
+}
+
+#[cfg(feature = "bevy")]
+fn slide_clip(offset: Vec3, easing: Easing) -> AnimationClip {
+    let mut clip = AnimationClip::default();
+    clip.add_curve_to_path(
+        EntityPath {
+            parts: vec![tile_name()],
+        },
+        VariableCurve {
+            keyframe_timestamps: (0..=SLIDE_KEYFRAMES).map(|index| index as f32 / SLIDE_KEYFRAMES as f32 * EASED_SLIDE_DURATION).collect(),
+            keyframes: Keyframes::Translation(slide_keyframes(offset, easing)),
+        },
+    );
+    clip
+}
+
+/// A slide dropped before it landed, as when a replay jumps, leaves the tile where it was put.
+/// A player without a clip leaves the transform alone
+#[cfg(feature = "bevy")]
+pub fn stop_removed_slides(
+    mut commands: Commands,
+    mut removed: RemovedComponents<TileSlide>,
+    mut player_query: Query<&mut AnimationPlayer, Without<TileSlide>>,
+    lerp_query: Query<(), (With<TileLerp>, Without<TileSlide>)>,
+) {
+    for entity in removed.read() {
+        if let Ok(mut player) = player_query.get_mut(entity) {
+            *player = AnimationPlayer::default();
+        }
+        if lerp_query.contains(entity) {
+            commands.entity(entity).remove::<TileLerp>();
+        }
+    }
+}
+
+/// Tiles without a player, those of mega boards, slide along the same easings computed each frame
+#[cfg(feature = "bevy")]
+pub fn start_lerped_slides(
+    mut commands: Commands,
+    tile_query: Query<(Entity, &TileSlide), (Changed<TileSlide>, Without<AnimationPlayer>)>,
+) {
+    for (entity, slide) in tile_query.iter() {
+        let mut lerp = TileLerp::new(slide.target).with_speed(slide.speed).with_easing(slide.easing);
+        if let Some((exit, entry)) = slide.wrap {
+            lerp = lerp.with_wrap(exit, entry);
+        }
+        commands.entity(entity).insert(lerp);
+    }
+}
+
+#[cfg(feature = "bevy")]
+pub fn advance_lerped_slides(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tile_query: Query<(Entity, &mut Transform, &mut TileLerp), With<TileSlide>>,
+    mut finished_events: EventWriter<TileAnimationFinished>,
+) {
+    for (entity, mut transform, mut lerp) in tile_query.iter_mut() {
+        if lerp.advance(&mut transform.translation, time.delta_seconds()) {
+            commands.entity(entity).remove::<(TileSlide, TileLerp)>();
+            finished_events.send(TileAnimationFinished(entity));
+        }
+    }
+}
+
+/// Plays the clip of the slide from where the tile is, again for the second leg of a slide through the edges
+#[cfg(feature = "bevy")]
+pub fn start_slides(
+    mut tile_query: Query<(&Transform, &TileSlide, &TileAnimations, &mut AnimationPlayer), Changed<TileSlide>>,
+) {
+    for (transform, slide, animations, mut player) in tile_query.iter_mut() {
+        let clip = animations.clip(transform.translation - slide.leg_target(), slide.easing);
+        player.play(clip).set_speed(slide.speed).replay();
+    }
+}
+
+/// The clip played the offset from the cell the tile slides to
+#[cfg(feature = "bevy")]
+pub fn place_sliding_tiles(
+    mut commands: Commands,
+    mut tile_query: Query<(Entity, &mut Transform, &mut TileSlide, &mut AnimationPlayer)>,
+    mut finished_events: EventWriter<TileAnimationFinished>,
+) {
+    for (entity, mut transform, mut slide, mut player) in tile_query.iter_mut() {
+        transform.translation += slide.leg_target();
+        if !player.is_finished() {
+            continue;
+        }
+        if let Some((_, entry)) = slide.wrap.take() {
+            transform.translation = entry;
+            continue;
+        }
+        transform.translation = slide.target;
+        *player = AnimationPlayer::default();
+        commands.entity(entity).remove::<TileSlide>();
+        finished_events.send(TileAnimationFinished(entity));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "bevy")]
+    use bevy::prelude::*;
+
+    use crate::slide::Easing;
+    #[cfg(feature = "bevy")]
+    use crate::slide::{slide_keyframes, TileLerp};
+
+    #[test]
+    fn test_easing() {
+        for easing in [Easing::EaseOut, Easing::Bounce, Easing::Overshoot] {
+            assert_eq!(easing.ease(0.), 0.);
+            assert!((easing.ease(1.) - 1.).abs() < 1e-5);
+        }
+        assert!(Easing::EaseOut.ease(0.5) > 0.5);
+        assert!((1..10).map(|step| Easing::Overshoot.ease(step as f32 / 10.)).any(|covered| covered > 1.));
+    }
+
+    #[cfg(feature = "bevy")]
+    #[test]
+    fn test_advance() {
+        for easing in [Easing::Approach, Easing::EaseOut, Easing::Bounce, Easing::Overshoot] {
+            let mut lerp = TileLerp::new(Vec3::X).with_easing(easing);
+            let mut translation = Vec3::ZERO;
+            let frames = (0..100).take_while(|_| !lerp.advance(&mut translation, 1. / 60.)).count();
+            assert!(frames < 60, "{easing:?} took {frames} frames");
+            assert_eq!(translation, Vec3::X);
+        }
+    }
+
+    #[cfg(feature = "bevy")]
+    #[test]
+    fn test_wrap() {
+        let mut lerp = TileLerp::new(Vec3::X).with_wrap(-Vec3::X, 2. * Vec3::X);
+        let mut translation = Vec3::ZERO;
+        let mut left_side = false;
+        let frames = (0..200).take_while(|_| {
+            let landed = lerp.advance(&mut translation, 1. / 60.);
+            left_side |= translation.x < -0.5;
+            !landed
+        }).count();
+        assert!(left_side && frames < 120, "{frames} frames");
+        assert_eq!(translation, Vec3::X);
+    }
+
+    #[cfg(feature = "bevy")]
+    #[test]
+    fn test_slide_keyframes() {
+        let offset = Vec3::new(-1.2, 0., 0.);
+        for easing in [Easing::Overshoot, Easing::EaseOut, Easing::Bounce] {
+            let keyframes = slide_keyframes(offset, easing);
+            assert_eq!(keyframes.first(), Some(&offset));
+            assert!(keyframes.last().unwrap().abs_diff_eq(Vec3::ZERO, 1e-5));
+        }
+        // The tile goes past its cell before settling
+        assert!(slide_keyframes(offset, Easing::Overshoot).iter().any(|keyframe| keyframe.x > 0.));
+    }
+}
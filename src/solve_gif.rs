@@ -0,0 +1,284 @@
+use std::path::PathBuf;
+
+use bevy::{prelude::*, tasks::{block_on, AsyncComputeTaskPool, Task}};
+
+use crate::{
+    gif::{self, GifFrame},
+    persistence::{self, DataPaths, PersistenceError},
+    replay::{Replay, ReplayPlayback, ReplayRecorder},
+    taquin::{Taquin, TaquinShuffled, TaquinSolved},
+    tile::{TileCoordinates, TileValue},
+    AppState, TaquinSprites,
+};
+
+const GIF_KEY: KeyCode = KeyCode::F;
+/// Pixels across the GIF, the cells are square
+const GIF_WIDTH: u32 = 320;
+/// Hundredths of a second between frames, the moves are played at this fixed step whatever their real pace
+const FRAME_DELAY: u16 = 4;
+const SLIDE_FRAMES: u32 = 3;
+/// Hundredths of a second on the shuffled board, and on the solved one before the GIF loops
+const FIRST_FRAME_DELAY: u16 = 100;
+const LAST_FRAME_DELAY: u16 = 300;
+pub(crate) const BACKGROUND: [u8; 3] = [30, 30, 30];
+const TILE_EDGE: [u8; 3] = [15, 15, 15];
+
+pub struct SolveGifPlugin;
+
+impl Plugin for SolveGifPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Startup, setup_gif_message)
+            .add_systems(Update, (
+                offer_gif.run_if(on_event::<TaquinSolved>()),
+                withdraw_gif_offer.run_if(on_event::<TaquinShuffled>()),
+                start_gif.run_if(resource_exists::<GifOffer>().and_then(not(resource_exists::<GifTask>()))),
+                receive_gif.run_if(resource_exists::<GifTask>()),
+            ).chain().run_if(in_state(AppState::Running).and_then(not(resource_exists::<ReplayPlayback>()))));
+    }
+}
+
+/// Set once a game is solved, until the next shuffle
+#[derive(Resource)]
+struct GifOffer;
+
+/// Frames rendered and encoded off the frame
+#[derive(Resource)]
+struct GifTask(Task<Result<PathBuf, PersistenceError>>);
+
+#[derive(Component)]
+struct GifMessage;
+
+/// Copy of the picture on the tiles, sampled on the CPU, also drawn on the result cards of `capture`
+pub(crate) struct Picture {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) rgba: Vec<u8>,
+}
+
+impl Picture {
+    pub(crate) fn from_image(image: &Image) -> Option<Self> {
+        let rgba = image.clone().try_into_dynamic().ok()?.to_rgba8();
+        Some(Self { width: rgba.width(), height: rgba.height(), rgba: rgba.into_raw() })
+    }
+
+    /// Nearest pixel at `u` and `v` between 0 and 1, transparent parts showing the background
+    fn sample(&self, u: f32, v: f32) -> [u8; 3] {
+        let x = ((u * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v * self.height as f32) as u32).min(self.height - 1);
+        let index = (y * self.width + x) as usize * 4;
+        let alpha = self.rgba[index + 3] as u32;
+        [0, 1, 2].map(|channel| ((self.rgba[index + channel] as u32 * alpha + BACKGROUND[channel] as u32 * (255 - alpha)) / 255) as u8)
+    }
+}
+
+/// Board of the replay drawn with square cells of `cell` pixels
+pub(crate) struct GifBoard<'a> {
+    pub(crate) picture: &'a Picture,
+    pub(crate) columns: u32,
+    pub(crate) rows: u32,
+    pub(crate) cell: u32,
+    /// The highest values stand for the empty slots
+    pub(crate) empty_count: usize,
+}
+
+impl GifBoard<'_> {
+    /// Color of the tile `value` at `x` and `y` pixels from its corner, the tile shows its part of the picture
+    fn tile_color(&self, value: TileValue, x: u32, y: u32) -> [u8; 3] {
+        if x == 0 || y == 0 || x == self.cell - 1 || y == self.cell - 1 {
+            return TILE_EDGE;
+        }
+        let (u, v) = ((value.0 as u32 - 1) % self.columns, (value.0 as u32 - 1) / self.columns);
+        let local = |position: u32| (position as f32 + 0.5) / self.cell as f32;
+        self.picture.sample((u as f32 + local(x)) / self.columns as f32, (v as f32 + local(y)) / self.rows as f32)
+    }
+
+    /// Color at `x` and `y` pixels from the corner of a cell holding `value`, the background for an empty slot
+    pub(crate) fn cell_color(&self, value: TileValue, x: u32, y: u32) -> [u8; 3] {
+        let first_empty = (self.columns * self.rows) as usize + 1 - self.empty_count;
+        if value.0 as usize >= first_empty {
+            return BACKGROUND;
+        }
+        self.tile_color(value, x, y)
+    }
+
+    /// Cells from `min` to `max`, the tile of `from` being `t` of the way to the empty slot `to`
+    fn render(&self, tiles: &[Vec<TileValue>], slide: Option<(TileCoordinates, TileCoordinates, f32)>, min: TileCoordinates, max: TileCoordinates, delay: u16) -> GifFrame {
+        let (left, top) = (min.i as u32 * self.cell, min.j as u32 * self.cell);
+        let (right, bottom) = ((max.i as u32 + 1) * self.cell, (max.j as u32 + 1) * self.cell);
+        let sliding = slide.map(|(from, to, t)| {
+            let lerp = |a: i8, b: i8| ((a as f32 + (b - a) as f32 * t) * self.cell as f32).round() as u32;
+            (tiles[from.j as usize][from.i as usize], from, lerp(from.i, to.i), lerp(from.j, to.j))
+        });
+
+        let mut pixels = Vec::with_capacity(((right - left) * (bottom - top)) as usize);
+        for y in top..bottom {
+            for x in left..right {
+                let cell = TileCoordinates::new((x / self.cell) as i8, (y / self.cell) as i8);
+                let value = tiles[cell.j as usize][cell.i as usize];
+                let mut color = if sliding.is_some_and(|(_, from, _, _)| from == cell) {
+                    BACKGROUND
+                } else {
+                    self.cell_color(value, x % self.cell, y % self.cell)
+                };
+                if let Some((value, _, tile_x, tile_y)) = sliding {
+                    if (tile_x..tile_x + self.cell).contains(&x) && (tile_y..tile_y + self.cell).contains(&y) {
+                        color = self.tile_color(value, x - tile_x, y - tile_y);
+                    }
+                }
+                pixels.push(gif::palette_index(color));
+            }
+        }
+        GifFrame { left: left as u16, top: top as u16, width: (right - left) as u16, height: (bottom - top) as u16, delay, pixels }
+    }
+}
+
+/// The whole shuffled board, then only the two cells of each slide, drawn over the previous frames
+fn render_solve(replay: &Replay, picture: &Picture) -> (u16, u16, Vec<GifFrame>) {
+    let (columns, rows) = (replay.size as u32, replay.height() as u32);
+    let board = GifBoard { picture, columns, rows, cell: GIF_WIDTH / columns, empty_count: replay.empty_count.max(1) };
+    let mut taquin = Taquin::rect(replay.size, replay.height());
+    // A slide through the edges crosses the row or column in the GIF
+    taquin.topology = replay.topology;
+    taquin.set_empty_count(replay.empty_count);
+    taquin.set_tiles(replay.tiles.clone());
+
+    let corner = TileCoordinates::new(replay.size - 1, replay.height() - 1);
+    let mut frames = vec![board.render(&taquin.tiles, None, TileCoordinates::new(0, 0), corner, FIRST_FRAME_DELAY)];
+    for replay_move in &replay.moves {
+        let (from, to) = (replay_move.tile, replay_move.empty_on(&taquin).unwrap_or_else(|| taquin.get_empty_tile_coordinates()));
+        let min = TileCoordinates::new(from.i.min(to.i), from.j.min(to.j));
+        let max = TileCoordinates::new(from.i.max(to.i), from.j.max(to.j));
+        for step in 1..=SLIDE_FRAMES {
+            let t = step as f32 / SLIDE_FRAMES as f32;
+            frames.push(board.render(&taquin.tiles, Some((from, to, t)), min, max, FRAME_DELAY));
+        }
+        if !replay_move.play(&mut taquin) {
+            warn!(tile = ?from, "illegal move, the GIF stops there");
+            break;
+        }
+    }
+    if let Some(last) = frames.last_mut() {
+        last.delay = LAST_FRAME_DELAY;
+    }
+    ((columns * board.cell) as u16, (rows * board.cell) as u16, frames)
+}
+
+fn setup_gif_message(
+    mut commands: Commands,
+) {
+    commands.spawn((TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: 24.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    ).with_style(Style {
+        position_type: PositionType::Absolute,
+        left: Val::Px(10.),
+        bottom: Val::Px(10.),
+        ..default()
+    }), GifMessage));
+}
+
+fn set_message(message_query: &mut Query<&mut Text, With<GifMessage>>, value: String) {
+    for mut text in message_query.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+fn offer_gif(
+    mut commands: Commands,
+    mut message_query: Query<&mut Text, With<GifMessage>>,
+) {
+    commands.insert_resource(GifOffer);
+    set_message(&mut message_query, "F to save a GIF of the solve".to_string());
+}
+
+fn withdraw_gif_offer(
+    mut commands: Commands,
+    mut message_query: Query<&mut Text, With<GifMessage>>,
+) {
+    commands.remove_resource::<GifOffer>();
+    set_message(&mut message_query, String::new());
+}
+
+fn start_gif(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    recorder: Res<ReplayRecorder>,
+    sprites: Res<TaquinSprites>,
+    images: Res<Assets<Image>>,
+    paths: Res<DataPaths>,
+    mut message_query: Query<&mut Text, With<GifMessage>>,
+) {
+    if !keyboard_input.just_released(GIF_KEY) {
+        return;
+    }
+    let Some(replay) = recorder.last.clone() else {
+        set_message(&mut message_query, "No replay of this game to make a GIF of".to_string());
+        return;
+    };
+    let Some(picture) = sprites.images.get(sprites.selected).and_then(|handle| images.get(handle)).and_then(Picture::from_image) else {
+        set_message(&mut message_query, "Cannot read the picture of the tiles".to_string());
+        return;
+    };
+    let path = paths.exports().join(format!("solve-{}.gif", persistence::timestamp()));
+    info!(moves = replay.moves.len(), "rendering the solve GIF");
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let (width, height, frames) = render_solve(&replay, &picture);
+        persistence::write(&path, gif::encode(width, height, &frames)).map(|()| path)
+    });
+    commands.insert_resource(GifTask(task));
+    commands.remove_resource::<GifOffer>();
+    set_message(&mut message_query, "Saving the GIF…".to_string());
+}
+
+fn receive_gif(
+    mut commands: Commands,
+    mut task: ResMut<GifTask>,
+    mut message_query: Query<&mut Text, With<GifMessage>>,
+) {
+    if !task.0.is_finished() {
+        return;
+    }
+    commands.remove_resource::<GifTask>();
+    let message = match block_on(&mut task.0) {
+        Ok(path) => {
+            info!("solve GIF saved to {}", path.display());
+            format!("GIF saved to {}", path.display())
+        },
+        Err(error) => {
+            error!("cannot save the solve GIF: {error}");
+            "Cannot save the GIF".to_string()
+        },
+    };
+    set_message(&mut message_query, message);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{replay::{Replay, ReplayMove}, solve_gif::{render_solve, Picture, FIRST_FRAME_DELAY, LAST_FRAME_DELAY, SLIDE_FRAMES}, tile::{TileCoordinates, TileValue, Topology}};
+
+    #[test]
+    fn test_render_solve() {
+        let picture = Picture { width: 2, height: 1, rgba: vec![255, 0, 0, 255, 0, 0, 255, 255] };
+        let replay = Replay {
+            version: 1,
+            size: 2,
+            tiles: vec![vec![TileValue(1), TileValue(2)], vec![TileValue(4), TileValue(3)]],
+            moves: vec![ReplayMove { tile: TileCoordinates::new(1, 1), time: 0.5, into: None }],
+            topology: Topology::Flat,
+            empty_count: 1,
+        };
+        let (width, height, frames) = render_solve(&replay, &picture);
+        assert_eq!((width, height), (320, 320));
+        assert_eq!(frames.len(), 1 + SLIDE_FRAMES as usize);
+        assert_eq!(frames[0].delay, FIRST_FRAME_DELAY);
+        assert_eq!(frames[0].pixels.len(), 320 * 320);
+        // The slide only redraws the bottom row
+        assert_eq!((frames[1].top, frames[1].width, frames[1].height), (160, 320, 160));
+        assert_eq!(frames.last().unwrap().delay, LAST_FRAME_DELAY);
+    }
+}
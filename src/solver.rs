@@ -0,0 +1,721 @@
+use std::collections::{HashMap, VecDeque};
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{coordinates::{TileCoordinates, Topology}, zobrist};
+
+/// Mixed into the hash of a board on a torus
+const TORUS_HASH: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Flat copy of a board for searching, `tiles[j * size + i]` holds the value at `(i, j)`
+/// and the empty tile has the value `size * size`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Board {
+    size: usize,
+    tiles: Vec<u8>,
+    empty: usize,
+    hash: u64,
+    topology: Topology,
+}
+
+impl Board {
+    /// Board of `size` by `size` cells from its values row after row
+    pub fn new(size: usize, tiles: Vec<u8>) -> Self {
+        let empty = tiles.iter().position(|value| *value as usize == size * size).unwrap_or(0);
+        let hash = zobrist::hash(size, tiles.iter().map(|value| u16::from(*value)));
+        Self { size, tiles, empty, hash, topology: Topology::Flat }
+    }
+
+    /// The same board, its tiles sliding through the edges on a torus
+    pub fn with_topology(self, topology: Topology) -> Self {
+        Self { topology, ..self }
+    }
+
+    /// Board with every tile in place
+    pub fn solved(size: usize) -> Self {
+        Self::new(size, (1..=(size * size) as u8).collect())
+    }
+
+    /// Uniformly random solvable board
+    pub fn random(size: usize, rng: &mut impl Rng) -> Self {
+        let mut tiles = (1..=(size * size) as u8).collect::<Vec<u8>>();
+        loop {
+            tiles.shuffle(rng);
+            let board = Self::new(size, tiles.clone());
+            if board.is_solvable() {
+                return board;
+            }
+        }
+    }
+
+    /// Whether the solved board can be reached by sliding tiles, any board on a torus of odd size
+    pub fn is_solvable(&self) -> bool {
+        if self.topology == Topology::Torus && self.size % 2 == 1 {
+            return true;
+        }
+        let values = self.tiles.iter().filter(|value| **value as usize != self.size * self.size).collect::<Vec<&u8>>();
+        let inversions = (0..values.len()).map(|a| (a + 1..values.len()).filter(|b| values[a] > values[*b]).count()).sum::<usize>();
+        if self.size % 2 == 1 {
+            inversions % 2 == 0
+        } else {
+            (inversions + self.empty / self.size) % 2 == (self.size - 1) % 2
+        }
+    }
+
+    /// Packs the board in 4 bits per cell, only unique up to 4x4
+    pub fn key(&self) -> u64 {
+        self.tiles.iter().fold(0, |key, value| key << 4 | (*value as u64 - 1))
+    }
+
+    /// Zobrist hash of the board, stable across runs for on disk caches, a torus has its own as its solutions differ
+    pub fn hash(&self) -> u64 {
+        match self.topology {
+            Topology::Flat => self.hash,
+            Topology::Torus => self.hash ^ TORUS_HASH,
+        }
+    }
+
+    /// Slides `tile` into the empty slot, returns false if they are not neighbours
+    pub fn slide_tile(&mut self, tile: TileCoordinates) -> bool {
+        let index = tile.j as usize * self.size + tile.i as usize;
+        if !self.movable().any(|movable| movable == index) {
+            return false;
+        }
+        self.slide(index);
+        true
+    }
+
+    /// Boards one move away
+    pub fn successors(&self) -> impl Iterator<Item = Board> + '_ {
+        self.movable().map(|index| {
+            let mut board = self.clone();
+            board.slide(index);
+            board
+        })
+    }
+
+    /// Cells per side
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Values row after row
+    pub fn tiles(&self) -> &[u8] {
+        &self.tiles
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.tiles.iter().enumerate().all(|(index, value)| *value as usize == index + 1)
+    }
+
+    fn coordinates(&self, index: usize) -> TileCoordinates {
+        TileCoordinates::new((index % self.size) as i8, (index / self.size) as i8)
+    }
+
+    /// Indices of the tiles that can slide into the empty slot
+    fn movable(&self) -> impl Iterator<Item = usize> {
+        neighbours(self.size, self.empty, self.topology)
+    }
+
+    fn position(&self, value: u8) -> usize {
+        self.tiles.iter().position(|tile| *tile == value).unwrap_or(0)
+    }
+
+    fn is_in_place(&self, index: usize) -> bool {
+        self.tiles[index] as usize == index + 1
+    }
+
+    fn slide(&mut self, index: usize) {
+        self.hash = zobrist::swap(self.hash, self.size, (index, self.tiles[index].into()), (self.empty, self.tiles[self.empty].into()));
+        self.tiles.swap(index, self.empty);
+        self.empty = index;
+    }
+
+    /// Manhattan distance plus two moves for each pair of tiles in their goal row or column but in reverse order.
+    /// On a torus the distances go through the edges and the pairs are left out, they can pass each other that way
+    fn heuristic(&self) -> u32 {
+        let size = self.size;
+        let goal = |value: u8| ((value as usize - 1) % size, (value as usize - 1) / size);
+        let axis_distance = |a: usize, b: usize| match self.topology {
+            Topology::Flat => a.abs_diff(b),
+            Topology::Torus => a.abs_diff(b).min(size - a.abs_diff(b)),
+        };
+        let mut distance = 0;
+        for (index, value) in self.tiles.iter().enumerate() {
+            if index == self.empty {
+                continue;
+            }
+            let (gi, gj) = goal(*value);
+            distance += (axis_distance(gi, index % size) + axis_distance(gj, index / size)) as u32;
+        }
+        if self.topology == Topology::Torus {
+            return distance;
+        }
+
+        let mut conflicts = 0;
+        for line in 0..size {
+            let row = (0..size).map(|i| line * size + i).filter(|index| *index != self.empty && goal(self.tiles[*index]).1 == line);
+            conflicts += line_conflicts(row.map(|index| goal(self.tiles[index]).0).collect());
+            let column = (0..size).map(|j| j * size + line).filter(|index| *index != self.empty && goal(self.tiles[*index]).0 == line);
+            conflicts += line_conflicts(column.map(|index| goal(self.tiles[index]).1).collect());
+        }
+        distance + 2 * conflicts
+    }
+}
+
+/// Cells next to `index` on a board of `size` by `size`
+fn neighbours(size: usize, index: usize, topology: Topology) -> impl Iterator<Item = usize> {
+    let (i, j) = (index % size, index / size);
+    let cells = match topology {
+        Topology::Flat => [
+            (j > 0).then(|| index - size),
+            (i + 1 < size).then(|| index + 1),
+            (j + 1 < size).then(|| index + size),
+            (i > 0).then(|| index - 1),
+        ],
+        // Through the edges, on a board of 2 both ways lead to the same cell
+        Topology::Torus => {
+            let (up, right) = ((j + size - 1) % size * size + i, j * size + (i + 1) % size);
+            let (down, left) = ((j + 1) % size * size + i, j * size + (i + size - 1) % size);
+            [Some(up), Some(right), (down != up).then_some(down), (left != right).then_some(left)]
+        },
+    };
+    cells.into_iter().flatten()
+}
+
+/// Tiles to remove from a line so the goal positions of the others are increasing
+fn line_conflicts(goals: Vec<usize>) -> u32 {
+    let mut longest = vec![1; goals.len()];
+    for a in 0..goals.len() {
+        for b in 0..a {
+            if goals[b] < goals[a] {
+                longest[a] = longest[a].max(longest[b] + 1);
+            }
+        }
+    }
+    (goals.len() - longest.into_iter().max().unwrap_or(0)) as u32
+}
+
+/// Default number of transposition table entries, about 32 MB
+pub const DEFAULT_TABLE_SIZE: usize = 1 << 20;
+
+/// Bounds of an IDA* search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// Positions explored before giving up
+    pub max_nodes: u64,
+    /// Entries of the transposition table, 0 disables it
+    pub table_size: usize,
+}
+
+impl SearchLimits {
+    /// Limits with the default transposition table
+    pub fn new(max_nodes: u64) -> Self {
+        Self { max_nodes, table_size: DEFAULT_TABLE_SIZE }
+    }
+}
+
+/// Counters of the IDA* searches behind a solution
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchStats {
+    pub nodes: u64,
+    /// Transposition table lookups and how many found the position
+    pub probes: u64,
+    pub hits: u64,
+}
+
+impl SearchStats {
+    /// Share of the probes that found the position
+    pub fn hit_rate(&self) -> f32 {
+        self.hits as f32 / self.probes.max(1) as f32
+    }
+
+    fn add(&mut self, other: SearchStats) {
+        self.nodes += other.nodes;
+        self.probes += other.probes;
+        self.hits += other.hits;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TableEntry {
+    hash: u64,
+    /// Deepening iteration the position was last reached in
+    iteration: u32,
+    /// Fewest moves from the start it was reached with during `iteration`
+    cost: u32,
+    /// Smallest estimate past the bound backed up from its moves when reached with `cost`,
+    /// `u32::MAX` while they are being searched
+    next_bound: u32,
+}
+
+/// Positions already searched, in the slot picked by their Zobrist hash, newer positions replacing older ones
+struct TranspositionTable {
+    entries: Vec<Option<TableEntry>>,
+    iteration: u32,
+}
+
+impl TranspositionTable {
+    fn get(&self, hash: u64, stats: &mut SearchStats) -> Option<TableEntry> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        stats.probes += 1;
+        let entry = self.entries[hash as usize % self.entries.len()].filter(|entry| entry.hash == hash)?;
+        stats.hits += 1;
+        Some(entry)
+    }
+
+    fn insert(&mut self, hash: u64, cost: u32, next_bound: u32) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let slot = hash as usize % self.entries.len();
+        self.entries[slot] = Some(TableEntry { hash, iteration: self.iteration, cost, next_bound });
+    }
+}
+
+enum Search {
+    Found,
+    Next(u32),
+    Exhausted,
+}
+
+struct IdaStar {
+    board: Board,
+    path: Vec<usize>,
+    table: TranspositionTable,
+    stats: SearchStats,
+    max_nodes: u64,
+}
+
+impl IdaStar {
+    fn search(&mut self, cost: u32, bound: u32, previous: Option<usize>) -> Search {
+        let hash = self.board.hash();
+        if let Some(entry) = self.table.get(hash, &mut self.stats) {
+            // Reached before in this iteration with as few moves, either on the current path or already searched
+            // without success. Its moves cannot solve it within the bound from here, and their estimates are the
+            // ones backed up then, later by the moves it took since
+            if entry.iteration == self.table.iteration && entry.cost <= cost {
+                return Search::Next(entry.next_bound.saturating_add(cost - entry.cost));
+            }
+        }
+        let estimate = cost + self.board.heuristic();
+        if estimate > bound {
+            return Search::Next(estimate);
+        }
+        if self.board.is_solved() {
+            return Search::Found;
+        }
+        self.stats.nodes += 1;
+        if self.stats.nodes > self.max_nodes {
+            return Search::Exhausted;
+        }
+        self.table.insert(hash, cost, u32::MAX);
+
+        let mut next_bound = u32::MAX;
+        let moves = self.board.movable().filter(|index| Some(*index) != previous).collect::<Vec<usize>>();
+        for index in moves {
+            let empty = self.board.empty;
+            self.board.slide(index);
+            self.path.push(index);
+            match self.search(cost + 1, bound, Some(empty)) {
+                Search::Found => return Search::Found,
+                Search::Exhausted => return Search::Exhausted,
+                Search::Next(bound) => next_bound = next_bound.min(bound),
+            }
+            self.path.pop();
+            self.board.slide(empty);
+        }
+        // Only kept for this iteration: the move back was left out of the search, the backed up bound is no
+        // lower bound for the position reached from another neighbour in the next ones
+        self.table.insert(hash, cost, next_bound);
+        Search::Next(next_bound)
+    }
+}
+
+/// Shortest sequence of tiles to slide into the empty slot, `None` if the board is
+/// unsolvable or the search visits more than `max_nodes` positions
+pub fn solve(board: &Board, max_nodes: u64) -> Option<Vec<TileCoordinates>> {
+    solve_with_stats(board, SearchLimits::new(max_nodes)).0
+}
+
+/// Same as `solve`, with the transposition table sized by `limits` and the search counters
+pub fn solve_with_stats(board: &Board, limits: SearchLimits) -> (Option<Vec<TileCoordinates>>, SearchStats) {
+    let table = TranspositionTable { entries: vec![None; limits.table_size], iteration: 0 };
+    let mut search = IdaStar { board: board.clone(), path: Vec::new(), table, stats: SearchStats::default(), max_nodes: limits.max_nodes };
+    let mut bound = board.heuristic();
+    loop {
+        search.table.iteration += 1;
+        match search.search(0, bound, None) {
+            Search::Found => break,
+            Search::Next(next_bound) if next_bound != u32::MAX => bound = next_bound,
+            _ => return (None, search.stats),
+        }
+    }
+
+    let mut replay = board.clone();
+    let moves = search.path.into_iter().map(|index| {
+        let coordinates = replay.coordinates(index);
+        replay.slide(index);
+        coordinates
+    }).collect();
+    (Some(moves), search.stats)
+}
+
+/// Tiles to slide in order, with captions telling what the moves from an index on achieve
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Solution {
+    pub moves: Vec<TileCoordinates>,
+    pub captions: Vec<(usize, String)>,
+    pub stats: SearchStats,
+}
+
+/// Human readable name of a cell, such as "the top-right corner" or "row 2, column 3"
+fn cell_name(size: usize, index: usize) -> String {
+    let (i, j) = (index % size, index / size);
+    let row = if j == 0 { Some("top") } else if j + 1 == size { Some("bottom") } else { None };
+    let column = if i == 0 { Some("left") } else if i + 1 == size { Some("right") } else { None };
+    match (row, column) {
+        (Some(row), Some(column)) => format!("the {row}-{column} corner"),
+        (Some(row), None) => format!("the {row} row, column {}", i + 1),
+        (None, Some(column)) => format!("the {column} column, row {}", j + 1),
+        (None, None) => format!("row {}, column {}", j + 1, i + 1),
+    }
+}
+
+/// Solves the way people learn to: the top rows one by one, then the last two rows
+/// column by column, each row or column ending with a short cycle in a 2x3 block
+/// and the puzzle ending by rotating the last 2x2 block.
+/// On a torus the tiles only slide through the edges once, when the board could not be solved without it
+pub fn solve_human_like(board: &Board) -> Option<Solution> {
+    let size = board.size;
+    let mut solver = HumanSolver { board: board.clone(), locked: vec![false; size * size], moves: Vec::new(), captions: Vec::new() };
+    let cell = |i: usize, j: usize| j * size + i;
+
+    if !board.clone().with_topology(Topology::Flat).is_solvable() && board.is_solvable() {
+        solver.slide_through_edge()?;
+    }
+
+    for j in 0..size.saturating_sub(2) {
+        for i in 0..size - 2 {
+            solver.place(cell(i, j))?;
+        }
+        solver.place_pair(cell(size - 2, j), cell(size - 1, j), cell(size - 2, j + 1), (size - 3, j), format!("row {}", j + 1))?;
+    }
+    for i in 0..size.saturating_sub(2) {
+        solver.place_pair(cell(i, size - 2), cell(i, size - 1), cell(i + 1, size - 1), (i, size - 2), format!("column {}", i + 1))?;
+    }
+    if size >= 2 {
+        let block = [cell(size - 2, size - 2), cell(size - 1, size - 2), cell(size - 2, size - 1), cell(size - 1, size - 1)];
+        solver.caption("rotate the last 2x2 block".to_string());
+        solver.solve_block(&block, &block)?;
+    }
+
+    let mut replay = board.clone();
+    let moves = solver.moves.into_iter().map(|index| {
+        let coordinates = replay.coordinates(index);
+        replay.slide(index);
+        coordinates
+    }).collect();
+    Some(Solution { moves, captions: solver.captions, stats: SearchStats::default() })
+}
+
+struct HumanSolver {
+    board: Board,
+    locked: Vec<bool>,
+    moves: Vec<usize>,
+    captions: Vec<(usize, String)>,
+}
+
+impl HumanSolver {
+    fn slide(&mut self, index: usize) {
+        self.moves.push(index);
+        self.board.slide(index);
+    }
+
+    /// Describes the next moves, replacing the previous caption if it did not need any
+    fn caption(&mut self, text: String) {
+        if self.captions.last().is_some_and(|(start, _)| *start == self.moves.len()) {
+            self.captions.pop();
+        }
+        self.captions.push((self.moves.len(), text));
+    }
+
+    /// Shortest path from `from` to a cell matching `is_target`, through unlocked cells not in `blocked`
+    fn path(&self, from: usize, is_target: impl Fn(usize) -> bool, blocked: &[usize]) -> Option<Vec<usize>> {
+        let mut previous = HashMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(index) = queue.pop_front() {
+            if is_target(index) {
+                let mut path = Vec::new();
+                let mut cell = index;
+                while cell != from {
+                    path.push(cell);
+                    cell = previous[&cell];
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in neighbours(self.board.size, index, Topology::Flat) {
+                if !self.locked[next] && !blocked.contains(&next) && !previous.contains_key(&next) {
+                    previous.insert(next, index);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Walks the empty slot to the left edge and slides the tile of the right edge through it,
+    /// which changes the parity of the board on a torus of odd size
+    fn slide_through_edge(&mut self) -> Option<()> {
+        let size = self.board.size;
+        self.caption("slide a tile through the edge".to_string());
+        self.move_empty(|index| index % size == 0, &[])?;
+        self.slide(self.board.empty + size - 1);
+        Some(())
+    }
+
+    fn move_empty(&mut self, is_target: impl Fn(usize) -> bool, blocked: &[usize]) -> Option<()> {
+        for index in self.path(self.board.empty, is_target, blocked)? {
+            self.slide(index);
+        }
+        Some(())
+    }
+
+    /// Walks the tile with `value` to `target`, bringing the empty slot in front of it at each step
+    fn move_tile(&mut self, value: u8, target: usize) -> Option<()> {
+        let mut position = self.board.position(value);
+        for next in self.path(position, |index| index == target, &[])? {
+            self.move_empty(|index| index == next, &[position])?;
+            self.slide(position);
+            position = next;
+        }
+        Some(())
+    }
+
+    fn place(&mut self, index: usize) -> Option<()> {
+        self.caption(format!("bring tile {} to {}", index + 1, cell_name(self.board.size, index)));
+        self.move_tile(index as u8 + 1, index)?;
+        self.locked[index] = true;
+        Some(())
+    }
+
+    /// Places the last two tiles of a row or a column: the tile of `second` goes to its cell,
+    /// the tile of `first` is brought to `staging` and both are finished in the 2x3 block
+    /// whose top left cell is `corner`, completing `line`
+    fn place_pair(&mut self, first: usize, second: usize, staging: usize, corner: (usize, usize), line: String) -> Option<()> {
+        let size = self.board.size;
+        let block = (0..6).map(|cell| (corner.1 + cell / 3) * size + corner.0 + cell % 3).collect::<Vec<usize>>();
+
+        self.caption(format!("bring tile {} to {}", second + 1, cell_name(size, second)));
+        self.move_tile(second as u8 + 1, second)?;
+        self.locked[second] = true;
+        if !block.contains(&self.board.position(first as u8 + 1)) {
+            self.caption(format!("park tile {} next to it", first + 1));
+            self.move_tile(first as u8 + 1, staging)?;
+        }
+        self.locked[second] = false;
+        self.caption(format!("rotate the 2x3 block to finish {line}"));
+
+        let mut required = block.iter().copied().filter(|index| self.locked[*index]).collect::<Vec<usize>>();
+        required.extend([first, second]);
+        self.solve_block(&block, &required)
+    }
+
+    /// Breadth first search of the moves inside `block` putting the `required` cells in place,
+    /// which are locked afterwards
+    fn solve_block(&mut self, block: &[usize], required: &[usize]) -> Option<()> {
+        if required.iter().all(|index| self.board.is_in_place(*index)) {
+            for index in required {
+                self.locked[*index] = true;
+            }
+            return Some(());
+        }
+        let pinned = required.iter().map(|index| self.board.position(*index as u8 + 1)).collect::<Vec<usize>>();
+        self.move_empty(|index| block.contains(&index), &pinned)?;
+        for index in block {
+            self.locked[*index] = false;
+        }
+
+        let key = |board: &Board| block.iter().map(|index| board.tiles[*index]).collect::<Vec<u8>>();
+        let mut previous: HashMap<Vec<u8>, Option<(Vec<u8>, usize)>> = HashMap::from([(key(&self.board), None)]);
+        let mut queue = VecDeque::from([self.board.clone()]);
+        while let Some(board) = queue.pop_front() {
+            if required.iter().all(|index| board.is_in_place(*index)) {
+                let mut moves = Vec::new();
+                let mut state = key(&board);
+                while let Some(Some((parent, index))) = previous.get(&state) {
+                    moves.push(*index);
+                    state = parent.clone();
+                }
+                for index in moves.into_iter().rev() {
+                    self.slide(index);
+                }
+                for index in required {
+                    self.locked[*index] = true;
+                }
+                return Some(());
+            }
+            // Flat moves only, a tile wrapping through the edge would undo the rows already placed
+            for index in neighbours(board.size, board.empty, Topology::Flat).filter(|index| block.contains(index)) {
+                let mut next = board.clone();
+                next.slide(index);
+                previous.entry(key(&next)).or_insert_with(|| {
+                    queue.push_back(next);
+                    Some((key(&board), index))
+                });
+            }
+        }
+        None
+    }
+}
+
+/// How a board gets solved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strategy {
+    /// Shortest solution found by IDA*
+    #[default]
+    Optimal,
+    /// Longer row by row solution, see `solve_human_like`
+    HumanLike,
+}
+
+impl Strategy {
+    /// Strategy named "optimal" or "human", as given on the command line
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "optimal" => Some(Strategy::Optimal),
+            "human" => Some(Strategy::HumanLike),
+            _ => None,
+        }
+    }
+
+    /// Solution of `board`, None if the search gives up within `limits`
+    pub fn solve(&self, board: &Board, limits: SearchLimits) -> Option<Solution> {
+        match self {
+            Strategy::Optimal => {
+                let (moves, stats) = solve_with_stats(board, limits);
+                moves.map(|moves| Solution { moves, captions: Vec::new(), stats })
+            },
+            Strategy::HumanLike => solve_human_like(board),
+        }
+    }
+}
+
+/// Solution at least `extra_moves` longer than the one of `strategy`, made of deliberate
+/// detours away from the goal each followed by a new solve
+pub fn solve_with_extra_moves(board: &Board, strategy: Strategy, extra_moves: usize, limits: SearchLimits, rng: &mut impl Rng) -> Option<Solution> {
+    let mut solution = strategy.solve(board, limits)?;
+    let mut stats = solution.stats;
+    let target_length = solution.moves.len() + extra_moves;
+    let mut board = board.clone();
+    let mut detour = Vec::new();
+    while detour.len() + solution.moves.len() < target_length {
+        let moves = board.movable().collect::<Vec<usize>>();
+        let index = *moves.choose(rng)?;
+        detour.push(board.coordinates(index));
+        board.slide(index);
+        solution = strategy.solve(&board, limits)?;
+        stats.add(solution.stats);
+    }
+
+    for (start, _) in solution.captions.iter_mut() {
+        *start += detour.len();
+    }
+    detour.append(&mut solution.moves);
+    Some(Solution { moves: detour, captions: solution.captions, stats })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    use crate::{coordinates::{TileCoordinates, Topology}, solver::{solve, solve_human_like, solve_with_stats, Board, SearchLimits}};
+
+    #[test]
+    fn test_solve() {
+        let board = Board::new(3, vec![1, 2, 3, 4, 5, 6, 9, 7, 8]);
+        assert_eq!(solve(&board, 1_000), Some(vec![TileCoordinates::new(1, 2), TileCoordinates::new(2, 2)]));
+
+        let board = Board::new(3, vec![8, 6, 7, 2, 5, 4, 3, 9, 1]);
+        assert_eq!(solve(&board, 10_000_000).map(|solution| solution.len()), Some(31));
+    }
+
+    #[test]
+    fn test_transposition_table() {
+        let board = Board::new(3, vec![8, 6, 7, 2, 5, 4, 3, 9, 1]);
+        let (_, plain_stats) = solve_with_stats(&board, SearchLimits { max_nodes: 10_000_000, table_size: 0 });
+        let (_, stats) = solve_with_stats(&board, SearchLimits { max_nodes: 10_000_000, table_size: 1 << 16 });
+        assert!(stats.nodes < plain_stats.nodes, "{} nodes with the table, {} without", stats.nodes, plain_stats.nodes);
+        assert_eq!(plain_stats.probes, 0);
+
+        // Small tables replace their entries all the time, the solutions stay optimal whatever they kept
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..40 {
+            let board = Board::random(3, &mut rng);
+            let plain = solve_with_stats(&board, SearchLimits { max_nodes: 10_000_000, table_size: 0 }).0.map(|moves| moves.len());
+            for table_size in [16, 64, 256, 1024, 1 << 16] {
+                let moves = solve_with_stats(&board, SearchLimits { max_nodes: 10_000_000, table_size }).0;
+                assert_eq!(moves.map(|moves| moves.len()), plain, "{board:?} with {table_size} entries");
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_human_like() {
+        for (size, tiles) in [
+            (3, vec![8, 6, 7, 2, 5, 4, 3, 9, 1]),
+            (4, vec![14, 15, 8, 12, 10, 11, 9, 13, 2, 6, 5, 1, 3, 7, 4, 16]),
+        ] {
+            let mut board = Board::new(size, tiles);
+            let solution = solve_human_like(&board).expect("solvable board");
+            for tile in solution.moves {
+                board.slide(tile.j as usize * size + tile.i as usize);
+            }
+            assert!(board.is_solved());
+        }
+    }
+
+    #[test]
+    fn test_solve_human_like_captions() {
+        let solution = solve_human_like(&Board::new(3, vec![1, 2, 3, 4, 5, 6, 7, 9, 8])).expect("solvable board");
+        assert_eq!(solution.moves, vec![TileCoordinates::new(2, 2)]);
+        assert_eq!(solution.captions, vec![(0, "rotate the last 2x2 block".to_string())]);
+
+        let solution = solve_human_like(&Board::new(3, vec![1, 2, 3, 4, 5, 6, 9, 7, 8])).expect("solvable board");
+        assert_eq!(solution.captions, vec![(0, "bring tile 7 to the bottom-left corner".to_string()), (1, "rotate the last 2x2 block".to_string())]);
+    }
+
+    #[test]
+    fn test_torus() {
+        // The tile of the left edge slid through it, a flat board cannot get there
+        let board = Board::new(3, vec![1, 2, 3, 4, 5, 6, 9, 8, 7]);
+        assert!(!board.is_solvable());
+        let board = board.with_topology(Topology::Torus);
+        assert!(board.is_solvable());
+        assert_eq!(solve(&board, 1_000), Some(vec![TileCoordinates::new(2, 2)]));
+
+        let mut boards = [vec![1, 2, 3, 4, 5, 6, 9, 8, 7], vec![8, 6, 7, 2, 5, 4, 3, 9, 1], vec![6, 8, 7, 2, 5, 4, 3, 9, 1]]
+            .map(|tiles| Board::new(3, tiles).with_topology(Topology::Torus))
+            .to_vec();
+        // Any board of odd size, half of them on even sizes
+        let mut rng = StdRng::seed_from_u64(5);
+        for size in [3, 4, 5] {
+            let mut tiles = (1..=(size * size) as u8).collect::<Vec<u8>>();
+            for _ in 0..20 {
+                tiles.shuffle(&mut rng);
+                boards.push(Board::new(size, tiles.clone()).with_topology(Topology::Torus));
+            }
+        }
+        for mut board in boards.into_iter().filter(Board::is_solvable) {
+            let start = board.clone();
+            let solution = solve_human_like(&board).unwrap_or_else(|| panic!("{start:?} is solvable"));
+            for tile in solution.moves {
+                assert!(board.slide_tile(tile));
+            }
+            assert!(board.is_solved(), "{start:?}");
+        }
+    }
+}
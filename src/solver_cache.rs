@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, tasks::{block_on, AsyncComputeTaskPool, Task}};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    exit::Shutdown,
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    solver::Board,
+    tile::TileCoordinates,
+};
+
+const DEFAULT_CAPACITY: usize = 50_000;
+/// Seconds between two saves of a changed cache, a rewrite of the whole file is not worth it after every solve
+const SAVE_INTERVAL: f32 = 60.;
+
+pub struct SolverCachePlugin;
+
+impl Plugin for SolverCachePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SolverCache>()
+            .insert_resource(SolverCacheSaving { timer: Timer::from_seconds(SAVE_INTERVAL, TimerMode::Repeating), task: None })
+            .add_systems(Last, save_solver_cache);
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CachedSolve {
+    /// Optimal number of moves
+    pub length: u32,
+    /// First tile to slide, `None` for a solved board
+    pub first_move: Option<TileCoordinates>,
+    last_used: u64,
+}
+
+/// Optimal lengths and first moves of boards already solved, keyed by board hash
+/// and bounded to the `capacity` most recently used entries
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+pub struct SolverCache {
+    #[serde(default)]
+    version: u32,
+    capacity: usize,
+    clock: u64,
+    entries: HashMap<u64, CachedSolve>,
+    /// New solves not saved yet, the recency of the lookups is saved along with them
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl Versioned for SolverCache {
+    const VERSION: u32 = 2;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        match version {
+            // version 1 was keyed by FNV-1a fingerprints, its entries cannot be found anymore
+            1 => Ok(Self::new(DEFAULT_CAPACITY)),
+            _ => Err(PersistenceError::unsupported_version(version)),
+        }
+    }
+}
+
+impl SolverCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { version: Self::VERSION, capacity, clock: 0, entries: HashMap::new(), dirty: false }
+    }
+
+    /// Cache saved by a previous session, empty if there is none or it cannot be read
+    pub fn load(paths: &DataPaths) -> Self {
+        let path = paths.solver_cache();
+        if !persistence::exists(&path) {
+            return Self::new(DEFAULT_CAPACITY);
+        }
+        persistence::load_versioned(&path, Format::Ron).unwrap_or_else(|error| {
+            warn!("cannot load solver cache, starting empty: {error}");
+            Self::new(DEFAULT_CAPACITY)
+        })
+    }
+
+    pub fn get(&mut self, board: &Board) -> Option<CachedSolve> {
+        self.clock += 1;
+        let clock = self.clock;
+        let entry = self.entries.get_mut(&board.hash())?;
+        entry.last_used = clock;
+        Some(*entry)
+    }
+
+    fn insert(&mut self, board: &Board, length: u32, first_move: Option<TileCoordinates>) {
+        self.clock += 1;
+        self.entries.insert(board.hash(), CachedSolve { length, first_move, last_used: self.clock });
+        self.dirty = true;
+    }
+
+    /// Caches every position along an optimal `solution` of `board`
+    pub fn insert_solution(&mut self, board: &Board, solution: &[TileCoordinates]) {
+        let mut board = board.clone();
+        for (index, tile) in solution.iter().enumerate() {
+            self.insert(&board, (solution.len() - index) as u32, Some(*tile));
+            if !board.slide_tile(*tile) {
+                return;
+            }
+        }
+        self.insert(&board, 0, None);
+        self.evict();
+    }
+
+    /// Rebuilds an optimal solution by following cached first moves, if the whole path is cached
+    pub fn solution(&mut self, board: &Board) -> Option<Vec<TileCoordinates>> {
+        let mut board = board.clone();
+        let mut solution = Vec::new();
+        let mut expected = self.get(&board)?.length;
+        while expected > 0 {
+            let tile = self.get(&board).filter(|cached| cached.length == expected)?.first_move?;
+            if !board.slide_tile(tile) {
+                return None;
+            }
+            solution.push(tile);
+            expected -= 1;
+        }
+        board.is_solved().then_some(solution)
+    }
+
+    fn evict(&mut self) {
+        if self.entries.len() <= self.capacity {
+            return;
+        }
+        let mut last_used = self.entries.values().map(|entry| entry.last_used).collect::<Vec<u64>>();
+        last_used.sort_unstable();
+        let threshold = last_used[self.entries.len() - self.capacity];
+        self.entries.retain(|_, entry| entry.last_used >= threshold);
+    }
+}
+
+impl FromWorld for SolverCache {
+    fn from_world(world: &mut World) -> Self {
+        Self::load(world.resource::<DataPaths>())
+    }
+}
+
+/// Timer of the next save and the save being written
+#[derive(Resource)]
+struct SolverCacheSaving {
+    timer: Timer,
+    task: Option<Task<Result<(), PersistenceError>>>,
+}
+
+fn log_save(result: Result<(), PersistenceError>) {
+    if let Err(error) = result {
+        error!("cannot save solver cache: {error}");
+    }
+}
+
+/// A changed cache is written by a background task every `SAVE_INTERVAL`, and on `Shutdown` before the app exits
+fn save_solver_cache(
+    mut cache: ResMut<SolverCache>,
+    paths: Res<DataPaths>,
+    time: Res<Time<Real>>,
+    mut saving: ResMut<SolverCacheSaving>,
+    mut shutdown_events: EventReader<Shutdown>,
+) {
+    let shutdown = shutdown_events.read().count() > 0;
+    if let Some(mut task) = saving.task.take() {
+        if shutdown || task.is_finished() {
+            log_save(block_on(&mut task));
+        } else {
+            saving.task = Some(task);
+        }
+    }
+    let due = saving.timer.tick(time.delta()).just_finished();
+    if !cache.dirty || saving.task.is_some() || !(due || shutdown) {
+        return;
+    }
+    cache.bypass_change_detection().dirty = false;
+    let (snapshot, path) = (cache.clone(), paths.solver_cache());
+    let task = AsyncComputeTaskPool::get().spawn(async move { persistence::save_versioned(path, &snapshot, Format::Ron) });
+    if shutdown {
+        log_save(block_on(task));
+    } else {
+        saving.task = Some(task);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{solver::{self, Board}, solver_cache::SolverCache};
+
+    #[test]
+    fn test_cached_solution() {
+        let board = Board::new(3, vec![4, 1, 3, 7, 2, 6, 5, 8, 9]);
+        let solution = solver::solve(&board, 10_000).expect("solvable board");
+        let mut cache = SolverCache::new(100);
+        assert_eq!(cache.solution(&board), None);
+
+        cache.insert_solution(&board, &solution);
+        assert_eq!(cache.solution(&board), Some(solution.clone()));
+        assert_eq!(cache.get(&board).map(|cached| cached.length), Some(solution.len() as u32));
+        // Lookups alone do not make the cache worth saving again
+        cache.dirty = false;
+        assert!(cache.solution(&board).is_some());
+        assert!(!cache.dirty);
+
+        let mut small_cache = SolverCache::new(2);
+        small_cache.insert_solution(&board, &solution);
+        assert_eq!(small_cache.entries.len(), 2);
+    }
+}
@@ -0,0 +1,300 @@
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::{
+    audio::{AddAudioSource, AudioSourceBundle, Decodable, GlobalVolume, Source, Volume, VolumeLevel},
+    prelude::*,
+    reflect::TypePath,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::LaunchOptions,
+    persistence::{Format, PersistenceError, Versioned},
+    scramble::RandomWalk,
+    settings::Settings,
+    taquin::{TaquinShuffled, TileMoved},
+    tile::TileSelected,
+    AppState,
+};
+
+const MUTE_KEY: KeyCode = KeyCode::M;
+const SAMPLE_RATE: u32 = 44_100;
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_audio_source::<SoundEffect>()
+            .init_resource::<AudioSettings>()
+            .init_resource::<SoundEffects>()
+            .add_systems(Startup, mute_on_launch)
+            // The main menu has its own use for M, see `countdown`
+            .add_systems(Update, toggle_mute.run_if(not(in_state(AppState::MainMenu))))
+            .add_systems(Update, apply_audio_settings.run_if(resource_changed::<AudioSettings>()))
+            .add_systems(Update, (
+                play_slide_sound.run_if(on_event::<TileMoved>()),
+                play_selection_sound,
+                play_shuffle_sound.run_if(on_event::<TaquinShuffled>()),
+            ).run_if(in_state(AppState::Running).and_then(not(resource_exists::<RandomWalk>()))));
+    }
+}
+
+/// Volumes from 0 to 1, saved between runs in `Settings`. The sound effects and the music are relative to the master volume
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct AudioSettings {
+    #[serde(default)]
+    pub version: u32,
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+    /// M toggles it, the volumes are kept
+    pub muted: bool,
+}
+
+impl Versioned for AudioSettings {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl FromWorld for AudioSettings {
+    fn from_world(world: &mut World) -> Self {
+        world.resource::<Settings>().audio
+    }
+}
+
+impl AudioSettings {
+    /// The default volumes
+    pub fn new() -> Self {
+        Self { version: Self::VERSION, master: 1., music: 0.5, sfx: 0.8, muted: false }
+    }
+
+    /// Volume of a sound effect played `relative` as loud as the others
+    pub fn sfx_volume(&self, relative: f32) -> Volume {
+        Volume::new_relative(self.sfx * relative)
+    }
+
+    /// Zero while muted
+    pub fn master_volume(&self) -> f32 {
+        if self.muted { 0. } else { self.master }
+    }
+}
+
+/// Sound synthesized from noise and tones, there is no file to ship for it
+#[derive(Asset, TypePath, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundEffect {
+    /// A short tick, when the selection moves
+    Click,
+    /// Wood rubbing on wood, when a tile slides
+    Slide,
+    /// Air swept by the tiles, when the board is shuffled
+    Whoosh,
+}
+
+impl SoundEffect {
+    fn duration(self) -> Duration {
+        Duration::from_millis(match self {
+            SoundEffect::Click => 30,
+            SoundEffect::Slide => 90,
+            SoundEffect::Whoosh => 400,
+        })
+    }
+
+    /// Loudness against the other effects
+    fn volume(self) -> f32 {
+        match self {
+            SoundEffect::Click => 0.3,
+            SoundEffect::Slide => 0.5,
+            SoundEffect::Whoosh => 0.6,
+        }
+    }
+}
+
+impl Decodable for SoundEffect {
+    type DecoderItem = f32;
+    type Decoder = SoundEffectDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        SoundEffectDecoder {
+            effect: *self,
+            index: 0,
+            samples: (self.duration().as_secs_f32() * SAMPLE_RATE as f32) as u32,
+            noise: 0x9E37_79B9,
+            filtered: 0.,
+        }
+    }
+}
+
+pub struct SoundEffectDecoder {
+    effect: SoundEffect,
+    index: u32,
+    samples: u32,
+    /// State of the xorshift the noise is drawn from
+    noise: u32,
+    /// Low-passed noise
+    filtered: f32,
+}
+
+impl SoundEffectDecoder {
+    /// White noise between -1 and 1
+    fn white_noise(&mut self) -> f32 {
+        self.noise ^= self.noise << 13;
+        self.noise ^= self.noise >> 17;
+        self.noise ^= self.noise << 5;
+        self.noise as f32 / u32::MAX as f32 * 2. - 1.
+    }
+
+    /// Noise through a low-pass filter, smoother as `smoothing` goes to 0
+    fn low_passed_noise(&mut self, smoothing: f32) -> f32 {
+        let white = self.white_noise();
+        self.filtered += (white - self.filtered) * smoothing;
+        self.filtered
+    }
+}
+
+impl Iterator for SoundEffectDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.index >= self.samples {
+            return None;
+        }
+        let time = self.index as f32 / SAMPLE_RATE as f32;
+        let progress = self.index as f32 / self.samples as f32;
+        self.index += 1;
+        let sample = match self.effect {
+            SoundEffect::Click => {
+                let tone = (2. * PI * 1800. * time).sin();
+                (tone * 0.6 + self.white_noise() * 0.4) * (1. - progress).powi(4)
+            },
+            SoundEffect::Slide => self.low_passed_noise(0.15) * 4. * progress * (1. - progress) * 1.5,
+            SoundEffect::Whoosh => {
+                // The filter opens then closes as the air goes by
+                let swell = (PI * progress).sin();
+                self.low_passed_noise(0.02 + 0.25 * swell) * swell * swell * 2.
+            },
+        };
+        Some(sample.clamp(-1., 1.))
+    }
+}
+
+impl Source for SoundEffectDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((self.samples - self.index) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.effect.duration())
+    }
+}
+
+#[derive(Resource)]
+struct SoundEffects {
+    click: Handle<SoundEffect>,
+    slide: Handle<SoundEffect>,
+    whoosh: Handle<SoundEffect>,
+}
+
+impl FromWorld for SoundEffects {
+    fn from_world(world: &mut World) -> Self {
+        let mut effects = world.resource_mut::<Assets<SoundEffect>>();
+        Self {
+            click: effects.add(SoundEffect::Click),
+            slide: effects.add(SoundEffect::Slide),
+            whoosh: effects.add(SoundEffect::Whoosh),
+        }
+    }
+}
+
+fn play_sound_effect(commands: &mut Commands, settings: &AudioSettings, handle: &Handle<SoundEffect>, effect: SoundEffect) {
+    commands.spawn(AudioSourceBundle {
+        source: handle.clone(),
+        settings: PlaybackSettings { volume: settings.sfx_volume(effect.volume()), ..PlaybackSettings::DESPAWN },
+    });
+}
+
+fn toggle_mute(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: ResMut<AudioSettings>,
+) {
+    // Shift + M opens the mods screen, see `mods`
+    if keyboard_input.just_released(MUTE_KEY) && !keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        settings.muted = !settings.muted;
+        info!(muted = settings.muted, "audio");
+    }
+}
+
+/// `--mute` mutes as M would. Set before the first frame, the saved settings are left alone until a volume changes
+fn mute_on_launch(
+    launch_options: Res<LaunchOptions>,
+    mut settings: ResMut<AudioSettings>,
+) {
+    if launch_options.mute {
+        settings.bypass_change_detection().muted = true;
+    }
+}
+
+/// The master volume goes through the global volume, sounds already playing keep theirs
+fn apply_audio_settings(
+    settings: Res<AudioSettings>,
+    mut global_volume: ResMut<GlobalVolume>,
+) {
+    global_volume.volume = VolumeLevel::new(settings.master_volume());
+}
+
+fn play_slide_sound(
+    mut commands: Commands,
+    settings: Res<AudioSettings>,
+    effects: Res<SoundEffects>,
+) {
+    play_sound_effect(&mut commands, &settings, &effects.slide, SoundEffect::Slide);
+}
+
+fn play_selection_sound(
+    mut commands: Commands,
+    settings: Res<AudioSettings>,
+    effects: Res<SoundEffects>,
+    selected_query: Query<(), Added<TileSelected>>,
+) {
+    if !selected_query.is_empty() {
+        play_sound_effect(&mut commands, &settings, &effects.click, SoundEffect::Click);
+    }
+}
+
+fn play_shuffle_sound(
+    mut commands: Commands,
+    settings: Res<AudioSettings>,
+    effects: Res<SoundEffects>,
+) {
+    play_sound_effect(&mut commands, &settings, &effects.whoosh, SoundEffect::Whoosh);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::audio::{Decodable, Source};
+
+    use crate::sound::{SoundEffect, SAMPLE_RATE};
+
+    #[test]
+    fn test_sound_effects() {
+        for effect in [SoundEffect::Click, SoundEffect::Slide, SoundEffect::Whoosh] {
+            let decoder = effect.decoder();
+            assert_eq!(decoder.sample_rate(), SAMPLE_RATE);
+            let samples = decoder.collect::<Vec<f32>>();
+            assert_eq!(samples.len() as f32, (effect.duration().as_secs_f32() * SAMPLE_RATE as f32).floor());
+            assert!(samples.iter().all(|sample| (-1. ..=1.).contains(sample)));
+            assert!(samples.iter().any(|sample| sample.abs() > 0.05), "{effect:?} is silent");
+        }
+    }
+}
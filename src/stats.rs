@@ -0,0 +1,500 @@
+use std::{collections::BTreeMap, path::Path};
+
+use bevy::{prelude::*, tasks::{block_on, AsyncComputeTaskPool, Task}};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auto_solve::AutoSolveStarted,
+    hints::HintBudget,
+    pause::GameClock,
+    saved_game::GameRestored,
+    locale::Locale,
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    rotation::RotatingTiles,
+    solver::{self, Board},
+    solver_cache::SolverCache,
+    taquin::{is_solver_board, is_square_board, ShuffleSeed, Taquin, TaquinShuffled, TaquinSolved, TileMoved},
+    tile::TileCoordinates,
+    AppState,
+};
+
+/// Positions explored for the optimal length of a game, a game too hard for them is recorded without one
+const OPTIMAL_MAX_NODES: u64 = 20_000_000;
+
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GameHistory>()
+            // Built from the history the first time
+            .init_resource::<Stats>()
+            .init_resource::<CurrentGame>()
+            .add_event::<GameRecorded>()
+            .add_systems(Update, (
+                start_game.run_if(on_event::<TaquinShuffled>()),
+                solve_start_board.run_if(on_event::<TaquinShuffled>().and_then(is_solver_board)),
+                count_game_moves.run_if(on_event::<TileMoved>()),
+                restore_game.run_if(on_event::<GameRestored>()),
+                abandon_game.run_if(on_event::<AutoSolveStarted>()),
+                receive_optimal_moves.run_if(resource_exists::<OptimalMovesTask>()),
+                record_game.run_if(on_event::<TaquinSolved>().and_then(is_square_board)),
+                count_play_time.run_if(is_square_board),
+                add_solve_to_stats.run_if(on_event::<GameRecorded>()),
+                save_stats.run_if(on_event::<TaquinShuffled>().or_else(on_event::<GameRecorded>())),
+            ).chain().run_if(in_state(AppState::Running)))
+            .add_systems(OnExit(AppState::Running), save_stats)
+            // E turns the tiles in a game where they rotate, see `rotation`
+            .add_systems(Update, export_history.run_if(not(in_state(AppState::Running)).or_else(resource_equals(RotatingTiles(false)))));
+    }
+}
+
+/// Sent once a solved game has been added to the history
+#[derive(Event, Debug, Clone)]
+pub struct GameRecorded(pub GameRecord);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    /// Seconds since the unix epoch when the game was solved
+    pub date: u64,
+    pub size: i8,
+    pub seed: Option<u64>,
+    pub moves: usize,
+    /// Solve time in seconds
+    pub time: f32,
+    pub optimal_moves: Option<usize>,
+    /// Zobrist hash of the shuffled board, to find other games started from the same position
+    #[serde(default)]
+    pub position: Option<u64>,
+    /// Hints used during the game, free ones included
+    #[serde(default)]
+    pub hints: usize,
+    /// Moves added by the hints used past the free ones
+    #[serde(default)]
+    pub penalty: usize,
+}
+
+impl GameRecord {
+    /// Moves counted against the player, hints included
+    pub fn score(&self) -> usize {
+        self.moves + self.penalty
+    }
+
+    fn formatted_date(&self) -> String {
+        chrono::DateTime::from_timestamp(self.date as i64, 0)
+            .map_or_else(String::new, |date| date.format("%Y-%m-%d %H:%M:%S").to_string())
+    }
+}
+
+/// A play session, from launch to quit
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionRecord {
+    /// Seconds since the unix epoch
+    pub started: u64,
+    pub ended: u64,
+    /// Games solved
+    pub games: usize,
+    /// Fastest solve in seconds
+    pub best_time: Option<f32>,
+    /// Moves played, unsolved games included
+    pub moves: usize,
+}
+
+#[derive(Serialize)]
+struct ExportedGame {
+    date: String,
+    size: i8,
+    seed: Option<u64>,
+    moves: usize,
+    time: f32,
+    optimal_moves: Option<usize>,
+    hints: usize,
+    penalty: usize,
+}
+
+impl From<&GameRecord> for ExportedGame {
+    fn from(record: &GameRecord) -> Self {
+        Self {
+            date: record.formatted_date(),
+            size: record.size,
+            seed: record.seed,
+            moves: record.moves,
+            time: record.time,
+            optimal_moves: record.optimal_moves,
+            hints: record.hints,
+            penalty: record.penalty,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Every solved game, persisted between runs
+#[derive(Resource, Serialize, Deserialize, Debug)]
+pub struct GameHistory {
+    #[serde(default)]
+    pub version: u32,
+    pub games: Vec<GameRecord>,
+    #[serde(default)]
+    pub sessions: Vec<SessionRecord>,
+}
+
+impl Versioned for GameHistory {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, contents: &str, format: Format) -> Result<Self, PersistenceError> {
+        match version {
+            // unversioned histories share the version 1 layout
+            0 => Ok(Self { version: Self::VERSION, ..format.parse(contents)? }),
+            _ => Err(PersistenceError::unsupported_version(version)),
+        }
+    }
+}
+
+impl FromWorld for GameHistory {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().history();
+        if !persistence::exists(&path) {
+            return GameHistory { version: Self::VERSION, games: vec![], sessions: vec![] };
+        }
+        persistence::load_versioned(path, Format::Ron).unwrap_or_else(|error| {
+            error!("cannot load game history: {error}");
+            GameHistory { version: Self::VERSION, games: vec![], sessions: vec![] }
+        })
+    }
+}
+
+impl GameHistory {
+    pub fn save(&self, paths: &DataPaths) -> Result<(), PersistenceError> {
+        persistence::save_versioned(paths.history(), self, Format::Ron)
+    }
+
+    pub fn to_csv(&self, locale: &Locale) -> String {
+        let delimiter = locale.csv_delimiter().to_string();
+        let mut csv = ["date", "size", "seed", "moves", "time", "optimal_moves", "hints", "penalty"].join(&delimiter);
+        csv.push('\n');
+        for game in self.games.iter() {
+            csv.push_str(&[
+                game.formatted_date(),
+                game.size.to_string(),
+                game.seed.map_or_else(String::new, |seed| seed.to_string()),
+                game.moves.to_string(),
+                locale.format_decimal(game.time, 2),
+                game.optimal_moves.map_or_else(String::new, |moves| moves.to_string()),
+                game.hints.to_string(),
+                game.penalty.to_string(),
+            ].join(&delimiter));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> Result<String, PersistenceError> {
+        Ok(serde_json::to_string_pretty(&self.games.iter().map(ExportedGame::from).collect::<Vec<ExportedGame>>())?)
+    }
+
+    pub fn export(&self, format: ExportFormat, path: impl AsRef<Path>, locale: &Locale) -> Result<(), PersistenceError> {
+        match format {
+            ExportFormat::Csv => persistence::write(path, self.to_csv(locale)),
+            ExportFormat::Json => persistence::write(path, self.to_json()?),
+        }
+    }
+}
+
+/// Totals of one board size
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SizeStats {
+    pub solves: usize,
+    /// Moves of every solve, with the hint penalties
+    pub total_moves: usize,
+    pub best_moves: Option<usize>,
+    /// Seconds of every solve
+    pub total_time: f32,
+    pub best_time: Option<f32>,
+    /// Seconds spent on the size, unsolved games included
+    pub play_time: f32,
+}
+
+impl SizeStats {
+    pub fn add_solve(&mut self, game: &GameRecord) {
+        self.solves += 1;
+        self.total_moves += game.score();
+        self.best_moves = Some(self.best_moves.map_or(game.score(), |best| best.min(game.score())));
+        self.total_time += game.time;
+        self.best_time = Some(self.best_time.map_or(game.time, |best| best.min(game.time)));
+    }
+
+    /// Totals of the copy played the longest, the same games may be counted in both, with the bests of both
+    pub fn merge(&mut self, other: &SizeStats) {
+        let best_moves = self.best_moves.into_iter().chain(other.best_moves).min();
+        let best_time = self.best_time.into_iter().chain(other.best_time).reduce(f32::min);
+        if other.play_time > self.play_time {
+            *self = other.clone();
+        }
+        (self.best_moves, self.best_time) = (best_moves, best_time);
+    }
+
+    pub fn average_moves(&self) -> Option<f32> {
+        (self.solves > 0).then(|| self.total_moves as f32 / self.solves as f32)
+    }
+
+    pub fn average_time(&self) -> Option<f32> {
+        (self.solves > 0).then(|| self.total_time / self.solves as f32)
+    }
+}
+
+/// Solves and play time per board size, kept across sessions
+#[derive(Resource, Serialize, Deserialize, Debug)]
+pub struct Stats {
+    #[serde(default)]
+    pub version: u32,
+    pub sizes: BTreeMap<i8, SizeStats>,
+}
+
+impl Versioned for Stats {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl Stats {
+    /// Totals of the games already in the history, their solve time counted as play time
+    pub fn from_history(history: &GameHistory) -> Self {
+        let mut sizes = BTreeMap::<i8, SizeStats>::new();
+        for game in history.games.iter() {
+            let size = sizes.entry(game.size).or_default();
+            size.add_solve(game);
+            size.play_time += game.time;
+        }
+        Self { version: Self::VERSION, sizes }
+    }
+
+    pub fn save(&self, paths: &DataPaths) -> Result<(), PersistenceError> {
+        persistence::save_versioned(paths.stats(), self, Format::Ron)
+    }
+}
+
+impl FromWorld for Stats {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().stats();
+        if !persistence::exists(&path) {
+            return Stats::from_history(world.resource::<GameHistory>());
+        }
+        persistence::load_versioned(path, Format::Ron).unwrap_or_else(|error| {
+            error!("cannot load the statistics: {error}");
+            Stats::from_history(world.resource::<GameHistory>())
+        })
+    }
+}
+
+/// Moves and start time of the game being played since the last shuffle
+#[derive(Resource, Default)]
+pub struct CurrentGame {
+    started_at: Option<f32>,
+    moves: usize,
+    position: Option<u64>,
+    seed: Option<u64>,
+    /// Board at the shuffle when the solver handles it, its optimal length is recorded with the game
+    board: Option<Board>,
+}
+
+impl CurrentGame {
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Seconds played at `now` on the `GameClock`, none when no game is being played
+    pub fn elapsed(&self, now: f32) -> Option<f32> {
+        self.started_at.map(|started_at| now - started_at)
+    }
+}
+
+fn start_game(
+    mut current_game: ResMut<CurrentGame>,
+    taquin: Res<Taquin>,
+    seed: Res<ShuffleSeed>,
+    clock: Res<GameClock>,
+) {
+    *current_game = CurrentGame { started_at: Some(clock.elapsed_seconds()), moves: 0, position: Some(taquin.hash()), seed: seed.current, board: None };
+}
+
+/// Optimal length of the shuffled board being solved off the frame, for the game started from it
+#[derive(Resource)]
+struct OptimalMovesTask {
+    task: Task<Option<Vec<TileCoordinates>>>,
+    board: Board,
+    position: u64,
+}
+
+/// The optimal length is solved while the game is played, unless the solver cache has it. A new shuffle drops the
+/// solve of the previous board
+fn solve_start_board(
+    mut commands: Commands,
+    mut current_game: ResMut<CurrentGame>,
+    taquin: Res<Taquin>,
+    mut cache: ResMut<SolverCache>,
+) {
+    commands.remove_resource::<OptimalMovesTask>();
+    let board = taquin.board();
+    current_game.board = Some(board.clone());
+    if cache.get(&board).is_some() {
+        return;
+    }
+    let task_board = board.clone();
+    let task = AsyncComputeTaskPool::get().spawn(async move { solver::solve(&task_board, OPTIMAL_MAX_NODES) });
+    commands.insert_resource(OptimalMovesTask { task, board, position: taquin.hash() });
+}
+
+/// Caches the solve, and fills the optimal length of the game if it was recorded before the solver was done
+fn receive_optimal_moves(
+    mut commands: Commands,
+    mut task: ResMut<OptimalMovesTask>,
+    mut cache: ResMut<SolverCache>,
+    mut history: ResMut<GameHistory>,
+    paths: Res<DataPaths>,
+) {
+    if !task.task.is_finished() {
+        return;
+    }
+    commands.remove_resource::<OptimalMovesTask>();
+    let Some(solution) = block_on(&mut task.task) else {
+        warn!("no optimal length for the game, the board is too hard for the solver");
+        return;
+    };
+    cache.insert_solution(&task.board, &solution);
+    let Some(game) = history.games.last_mut().filter(|game| game.position == Some(task.position) && game.optimal_moves.is_none()) else {
+        return;
+    };
+    game.optimal_moves = Some(solution.len());
+    if let Err(error) = history.save(&paths) {
+        error!("cannot save game history: {error}");
+    }
+}
+
+fn count_game_moves(
+    mut current_game: ResMut<CurrentGame>,
+    mut tile_moved_events: EventReader<TileMoved>,
+) {
+    current_game.moves += tile_moved_events.read().count();
+}
+
+/// A resumed game goes on from its saved counters
+fn restore_game(
+    mut current_game: ResMut<CurrentGame>,
+    mut restored_events: EventReader<GameRestored>,
+) {
+    for restored in restored_events.read() {
+        current_game.moves += restored.moves;
+        if let Some(started_at) = current_game.started_at.as_mut() {
+            *started_at -= restored.elapsed;
+        }
+    }
+}
+
+/// Games finished by the auto-solver are not the player's and stay out of the history
+fn abandon_game(
+    mut current_game: ResMut<CurrentGame>,
+) {
+    current_game.started_at = None;
+}
+
+/// Games on rectangular boards stay out of the history, records being kept per square size. Those the solver cannot
+/// take, on mega boards or with several empty slots, are recorded without an optimal length
+#[allow(clippy::too_many_arguments)]
+fn record_game(
+    mut current_game: ResMut<CurrentGame>,
+    mut history: ResMut<GameHistory>,
+    mut cache: ResMut<SolverCache>,
+    paths: Res<DataPaths>,
+    taquin: Res<Taquin>,
+    clock: Res<GameClock>,
+    hint_budget: Res<HintBudget>,
+    mut recorded_events: EventWriter<GameRecorded>,
+) {
+    let Some(started_at) = current_game.started_at.take() else {
+        return;
+    };
+    let game = GameRecord {
+        date: persistence::timestamp(),
+        size: taquin.width,
+        seed: current_game.seed,
+        moves: current_game.moves,
+        time: clock.elapsed_seconds() - started_at,
+        optimal_moves: current_game.board.as_ref().and_then(|board| cache.get(board)).map(|cached| cached.length as usize),
+        position: current_game.position,
+        hints: hint_budget.used,
+        penalty: hint_budget.penalty(),
+    };
+    history.games.push(game.clone());
+    if let Err(error) = history.save(&paths) {
+        error!("cannot save game history: {error}");
+    }
+    recorded_events.send(GameRecorded(game));
+}
+
+fn count_play_time(
+    current_game: Res<CurrentGame>,
+    mut stats: ResMut<Stats>,
+    taquin: Res<Taquin>,
+    clock: Res<GameClock>,
+) {
+    if current_game.started_at.is_some() {
+        stats.sizes.entry(taquin.width).or_default().play_time += clock.delta_seconds();
+    }
+}
+
+fn add_solve_to_stats(
+    mut recorded_events: EventReader<GameRecorded>,
+    mut stats: ResMut<Stats>,
+) {
+    for GameRecorded(game) in recorded_events.read() {
+        stats.sizes.entry(game.size).or_default().add_solve(game);
+    }
+}
+
+/// The play time piles up every frame, it is written at each shuffle and solve and when the game is left
+fn save_stats(
+    stats: Res<Stats>,
+    paths: Res<DataPaths>,
+) {
+    if let Err(error) = stats.save(&paths) {
+        error!("cannot save the statistics: {error}");
+    }
+}
+
+fn export_history(
+    keyboard_input: Res<Input<KeyCode>>,
+    history: Res<GameHistory>,
+    paths: Res<DataPaths>,
+    locale: Res<Locale>,
+) {
+    if !keyboard_input.just_released(KeyCode::E) || keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let format = if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        ExportFormat::Json
+    } else {
+        ExportFormat::Csv
+    };
+    let path = paths.exports().join(format!("history-{}.{}", persistence::timestamp(), format.extension()));
+    match history.export(format, &path, &locale) {
+        Ok(()) => info!("game history exported to {}", path.display()),
+        Err(error) => error!("cannot export game history: {error}"),
+    }
+}
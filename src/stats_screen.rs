@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+
+use crate::{
+    focus::FocusScope,
+    i18n::Localized,
+    locale::Locale,
+    stats::{GameHistory, Stats},
+    AppState,
+};
+
+const SCREEN_KEY: KeyCode = KeyCode::F4;
+/// Solves drawn in the sparkline, the latest ones
+const SPARKLINE_GAMES: usize = 30;
+const SPARKLINE_HEIGHT: f32 = 60.;
+/// Seconds between two rebuilds for the play time alone
+const REFRESH_PERIOD: f32 = 1.;
+
+pub struct StatsScreenPlugin;
+
+impl Plugin for StatsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (
+                toggle_stats_screen,
+                navigate_stats_screen.run_if(resource_exists::<StatsScreen>()),
+                update_stats_screen.run_if(resource_exists::<StatsScreen>()),
+            ).chain().run_if(in_state(AppState::MainMenu).or_else(in_state(AppState::Running))));
+    }
+}
+
+/// Opens the statistics from the main menu, as F4 does
+#[derive(Component)]
+pub struct StatsButton;
+
+/// Table of every size played, `Tab` picks the size of the sparkline
+#[derive(Resource)]
+struct StatsScreen {
+    entity: Entity,
+    size: Option<i8>,
+}
+
+/// Heights of the sparkline bars, in percent of the most moves
+fn sparkline_heights(moves: &[usize]) -> Vec<f32> {
+    let most = moves.iter().copied().max().unwrap_or(0).max(1);
+    moves.iter().map(|moves| *moves as f32 * 100. / most as f32).collect()
+}
+
+fn toggle_stats_screen(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<StatsButton>)>,
+    screen: Option<Res<StatsScreen>>,
+    stats: Res<Stats>,
+) {
+    let pressed = button_query.iter().filter(|interaction| **interaction == Interaction::Pressed).count() > 0;
+    if !pressed && !keyboard_input.just_released(SCREEN_KEY) {
+        return;
+    }
+    if let Some(screen) = screen {
+        commands.entity(screen.entity).despawn_recursive();
+        commands.remove_resource::<StatsScreen>();
+        return;
+    }
+    let entity = commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.),
+            top: Val::Px(120.),
+            padding: UiRect::all(Val::Px(10.)),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(10.),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        // Above the main menu
+        z_index: ZIndex::Global(6),
+        ..default()
+    }, FocusScope)).id();
+    commands.insert_resource(StatsScreen { entity, size: stats.sizes.keys().next().copied() });
+}
+
+fn navigate_stats_screen(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut screen: ResMut<StatsScreen>,
+    stats: Res<Stats>,
+) {
+    if !keyboard_input.just_released(KeyCode::Tab) {
+        return;
+    }
+    let next = stats.sizes.keys().copied().find(|size| Some(*size) > screen.size);
+    screen.size = next.or_else(|| stats.sizes.keys().next().copied());
+}
+
+/// Rebuilt when the totals change, at most every `REFRESH_PERIOD` as the play time changes them every frame
+fn update_stats_screen(
+    mut commands: Commands,
+    screen: Res<StatsScreen>,
+    stats: Res<Stats>,
+    history: Res<GameHistory>,
+    locale: Res<Locale>,
+    time: Res<Time<Real>>,
+    mut refreshed_at: Local<f32>,
+) {
+    let due = stats.is_changed() && time.elapsed_seconds() - *refreshed_at >= REFRESH_PERIOD;
+    if !screen.is_changed() && !due {
+        return;
+    }
+    *refreshed_at = time.elapsed_seconds();
+    let text_style = TextStyle {
+        font_size: 24.0,
+        color: Color::WHITE,
+        ..default()
+    };
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    let mut lines = Vec::new();
+    for (size, size_stats) in stats.sizes.iter() {
+        let cursor = if Some(*size) == screen.size { ">" } else { " " };
+        lines.push(format!(
+            "{cursor} {size}x{size}  {}  {}/{}  {}/{}  {}",
+            locale.format_count(size_stats.solves),
+            optional(size_stats.best_moves.map(|moves| locale.format_count(moves))),
+            optional(size_stats.average_moves().map(|moves| locale.format_decimal(moves, 1))),
+            optional(size_stats.best_time.map(|time| locale.format_duration(time))),
+            optional(size_stats.average_time().map(|time| locale.format_duration(time))),
+            locale.format_duration(size_stats.play_time),
+        ));
+    }
+    let table = if lines.is_empty() {
+        Localized::new("Statistics (F4, Tab for the moves of another size)\nSize  Solves  Best/avg moves  Best/avg time  Played\nNo game played yet")
+    } else {
+        Localized::new("Statistics (F4, Tab for the moves of another size)\nSize  Solves  Best/avg moves  Best/avg time  Played\n{rows}").with_arg("rows", lines.join("\n"))
+    };
+    let moves = history.games.iter()
+        .filter(|game| Some(game.size) == screen.size)
+        .map(|game| game.score())
+        .collect::<Vec<usize>>();
+    let moves = &moves[moves.len().saturating_sub(SPARKLINE_GAMES)..];
+
+    let mut entity = commands.entity(screen.entity);
+    entity.despawn_descendants();
+    entity.with_children(|parent| {
+        parent.spawn((TextBundle::from_section("", text_style.clone()).with_text_alignment(locale.start_alignment()), table));
+        if moves.is_empty() {
+            return;
+        }
+        parent.spawn((TextBundle::from_section("", text_style), Localized::new("Moves of the last {count} solves").with_arg("count", moves.len())));
+        parent.spawn(NodeBundle {
+            style: Style {
+                height: Val::Px(SPARKLINE_HEIGHT),
+                flex_direction: locale.row_direction(),
+                align_items: AlignItems::FlexEnd,
+                column_gap: Val::Px(2.),
+                ..default()
+            },
+            ..default()
+        }).with_children(|parent| {
+            for height in sparkline_heights(moves) {
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(6.),
+                        height: Val::Percent(height.max(2.)),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    ..default()
+                });
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats_screen::sparkline_heights;
+
+    #[test]
+    fn test_sparkline_heights() {
+        assert_eq!(sparkline_heights(&[50, 100, 25]), [50., 100., 25.]);
+        assert_eq!(sparkline_heights(&[0]), [0.]);
+        assert!(sparkline_heights(&[]).is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    replay::ReplayPlayback,
+    taquin::{SlideSelectedTile, Taquin, TileMoved},
+    tile::{TileCoordinates, TileIndex, TileSlide, TileSelected},
+    AppState,
+};
+
+const SWITCH_KEY: KeyCode = KeyCode::Return;
+
+pub struct SwitchScanPlugin;
+
+impl Plugin for SwitchScanPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (
+                restart_scan_on_tile_moved.run_if(on_event::<TileMoved>()),
+                switch_input.run_if(not(any_with_component::<TileSlide>())),
+                scan_movable_tiles,
+            ).chain().run_if(
+                in_state(AppState::Running)
+                    .and_then(resource_exists::<SwitchScanning>())
+                    .and_then(not(resource_exists::<ReplayPlayback>()))
+            ));
+    }
+}
+
+/// One-switch mode: the selection cycles through the tiles next to the empty slot,
+/// a short press skips to the next one and a long press slides the selected tile
+#[derive(Resource, Debug)]
+pub struct SwitchScanning {
+    timer: Timer,
+    long_press: Duration,
+    held: Duration,
+    index: usize,
+}
+
+impl SwitchScanning {
+    pub fn new(interval: Duration, long_press: Duration) -> Self {
+        Self { timer: Timer::new(interval, TimerMode::Repeating), long_press, held: Duration::ZERO, index: 0 }
+    }
+}
+
+impl Default for SwitchScanning {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(1500), Duration::from_millis(600))
+    }
+}
+
+/// Tiles that can slide into an empty slot, row after row so the scan is predictable
+fn movable_tiles(taquin: &Taquin) -> Vec<TileCoordinates> {
+    (0..taquin.height).flat_map(|j| (0..taquin.width).map(move |i| TileCoordinates::new(i, j)))
+        .filter(|tile| taquin.is_movable(tile))
+        .collect()
+}
+
+fn restart_scan_on_tile_moved(
+    mut scanning: ResMut<SwitchScanning>,
+) {
+    scanning.index = 0;
+    scanning.timer.reset();
+}
+
+fn switch_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut scanning: ResMut<SwitchScanning>,
+    mut slide_events: EventWriter<SlideSelectedTile>,
+) {
+    if keyboard_input.pressed(SWITCH_KEY) {
+        scanning.held += time.delta();
+        return;
+    }
+    if !keyboard_input.just_released(SWITCH_KEY) {
+        return;
+    }
+
+    if scanning.held >= scanning.long_press {
+        slide_events.send_default();
+    } else {
+        scanning.index += 1;
+    }
+    scanning.held = Duration::ZERO;
+    scanning.timer.reset();
+}
+
+fn scan_movable_tiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    taquin: Res<Taquin>,
+    mut scanning: ResMut<SwitchScanning>,
+    selected_tile_query: Query<(Entity, &TileCoordinates), With<TileSelected>>,
+    index: Res<TileIndex>,
+) {
+    if scanning.held == Duration::ZERO && scanning.timer.tick(time.delta()).just_finished() {
+        scanning.index += 1;
+    }
+
+    let candidates = movable_tiles(&taquin);
+    let target = candidates[scanning.index % candidates.len()];
+    let Ok((selected_entity, selected_coordinates)) = selected_tile_query.get_single() else {
+        return;
+    };
+    if *selected_coordinates == target {
+        return;
+    }
+    if let Some(entity) = index.at(target) {
+        commands.entity(selected_entity).remove::<TileSelected>();
+        commands.entity(entity).insert(TileSelected);
+    }
+}
@@ -0,0 +1,253 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::VertexAttributeValues,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor},
+    },
+};
+
+use crate::theme::Theme;
+
+const TABLE_SIZE: f32 = 50.;
+const BACKDROP_HEIGHT: f32 = 30.;
+/// Times the surface texture repeats along a side of the table
+const TEXTURE_REPEAT: f32 = 4.;
+const TEXTURE_SIZE: usize = 256;
+
+pub struct TablePlugin;
+
+impl Plugin for TablePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<TableSurface>()
+            .init_resource::<FrameTint>()
+            .add_systems(Startup, (spawn_table, spawn_backdrop))
+            .add_systems(Update, (
+                apply_table_surface.run_if(resource_changed::<TableSurface>().or_else(resource_changed::<Theme>())),
+                apply_backdrop_colors.run_if(resource_changed::<Theme>()),
+                apply_frame_tint,
+            ));
+    }
+}
+
+/// What the board rests on
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableSurface {
+    #[default]
+    Wood,
+    /// Polished enough to mirror the lights, bevy has no screen-space reflections yet
+    Marble,
+    /// The UV test pattern
+    #[cfg(feature = "debug-ui")]
+    Debug,
+}
+
+impl TableSurface {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "wood" => Some(TableSurface::Wood),
+            "marble" => Some(TableSurface::Marble),
+            #[cfg(feature = "debug-ui")]
+            "debug" => Some(TableSurface::Debug),
+            _ => None,
+        }
+    }
+
+    fn material(self, images: &mut Assets<Image>) -> StandardMaterial {
+        match self {
+            TableSurface::Wood => StandardMaterial {
+                base_color_texture: Some(images.add(procedural_texture(wood))),
+                perceptual_roughness: 0.45,
+                reflectance: 0.4,
+                ..default()
+            },
+            TableSurface::Marble => StandardMaterial {
+                base_color_texture: Some(images.add(procedural_texture(marble))),
+                perceptual_roughness: 0.08,
+                reflectance: 0.8,
+                ..default()
+            },
+            #[cfg(feature = "debug-ui")]
+            TableSurface::Debug => StandardMaterial {
+                base_color_texture: Some(images.add(Image {
+                    sampler: repeating(ImageSamplerDescriptor::nearest()),
+                    ..uv_debug_texture()
+                })),
+                ..default()
+            },
+        }
+    }
+}
+
+/// Base color of the frame around the board
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct FrameTint(pub Color);
+
+impl Default for FrameTint {
+    fn default() -> Self {
+        Self(Color::WHITE)
+    }
+}
+
+/// Mesh of the frame model, tinted with `FrameTint`
+#[derive(Component)]
+pub struct FrameMaterial;
+
+#[derive(Component)]
+struct Table;
+
+/// Wall behind the table, fading into the clear color at its top
+#[derive(Component)]
+struct Backdrop;
+
+/// Seamless texture sampled with repetition, `color` is given coordinates between 0 and 1
+fn procedural_texture(color: fn(f32, f32) -> [u8; 4]) -> Image {
+    let texture_data = (0..TEXTURE_SIZE * TEXTURE_SIZE).flat_map(|index| {
+        color((index % TEXTURE_SIZE) as f32 / TEXTURE_SIZE as f32, (index / TEXTURE_SIZE) as f32 / TEXTURE_SIZE as f32)
+    }).collect();
+    let mut image = Image::new(
+        Extent3d {
+            width: TEXTURE_SIZE as u32,
+            height: TEXTURE_SIZE as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        texture_data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.sampler = repeating(ImageSamplerDescriptor::linear());
+    image
+}
+
+fn repeating(descriptor: ImageSamplerDescriptor) -> ImageSampler {
+    ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        ..descriptor
+    })
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [u8; 4] {
+    let channel = |index: usize| ((a[index] + (b[index] - a[index]) * t) * 255.) as u8;
+    [channel(0), channel(1), channel(2), 255]
+}
+
+/// Growth rings along `v`, wavy enough to look like planks
+fn wood(u: f32, v: f32) -> [u8; 4] {
+    let rings = (8. * u + 0.3 * (TAU * 2. * v).sin() + 0.05 * (TAU * (3. * u + 5. * v)).sin()).fract();
+    mix([0.55, 0.35, 0.18], [0.33, 0.19, 0.09], rings.powi(3))
+}
+
+/// Thin grey veins over white stone
+fn marble(u: f32, v: f32) -> [u8; 4] {
+    let turbulence = 0.5 * (TAU * (3. * u + 2. * v)).sin() + 0.25 * (TAU * (7. * v - 5. * u)).sin();
+    let veins = (TAU * (2. * u + v) + 3. * turbulence).sin();
+    mix([0.93, 0.92, 0.9], [0.45, 0.46, 0.5], (1. - veins.abs()).powi(6))
+}
+
+/// Creates a colorful test pattern
+#[cfg(feature = "debug-ui")]
+fn uv_debug_texture() -> Image {
+    const TEXTURE_SIZE: usize = 8;
+
+    let mut palette: [u8; 32] = [
+        255, 102, 159, 255, 255, 159, 102, 255, 236, 255, 102, 255, 121, 255, 102, 255, 102, 255,
+        198, 255, 102, 198, 255, 255, 121, 102, 255, 255, 236, 102, 255, 255,
+    ];
+
+    let mut texture_data = [0; TEXTURE_SIZE * TEXTURE_SIZE * 4];
+    for y in 0..TEXTURE_SIZE {
+        let offset = TEXTURE_SIZE * y * 4;
+        texture_data[offset..(offset + TEXTURE_SIZE * 4)].copy_from_slice(&palette);
+        palette.rotate_right(4);
+    }
+
+    Image::new_fill(
+        Extent3d {
+            width: TEXTURE_SIZE as u32,
+            height: TEXTURE_SIZE as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &texture_data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn spawn_table(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let mut plane = Mesh::from(shape::Plane::from_size(TABLE_SIZE));
+    if let Some(VertexAttributeValues::Float32x2(uvs)) = plane.attribute_mut(Mesh::ATTRIBUTE_UV_0) {
+        for uv in uvs.iter_mut() {
+            *uv = [uv[0] * TEXTURE_REPEAT, uv[1] * TEXTURE_REPEAT];
+        }
+    }
+    commands.spawn((PbrBundle {
+        mesh: meshes.add(plane),
+        ..default()
+    }, Table));
+}
+
+/// Darkening upwards, its colors come from the theme
+fn spawn_backdrop(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((PbrBundle {
+        mesh: meshes.add(shape::Quad::new(Vec2::new(TABLE_SIZE, BACKDROP_HEIGHT)).into()),
+        material: materials.add(StandardMaterial {
+            unlit: true,
+            ..default()
+        }),
+        transform: Transform::from_xyz(0., BACKDROP_HEIGHT / 2., -TABLE_SIZE / 2.),
+        ..default()
+    }, Backdrop));
+}
+
+fn apply_backdrop_colors(
+    theme: Res<Theme>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    backdrop_query: Query<&Handle<Mesh>, With<Backdrop>>,
+) {
+    let (top, bottom) = (theme.clear_color().as_linear_rgba_f32(), theme.backdrop_color().as_linear_rgba_f32());
+    for handle in backdrop_query.iter() {
+        if let Some(wall) = meshes.get_mut(handle) {
+            wall.insert_attribute(Mesh::ATTRIBUTE_COLOR, vec![bottom, top, top, bottom]);
+        }
+    }
+}
+
+/// The surface texture is darkened or colored by the theme
+fn apply_table_surface(
+    surface: Res<TableSurface>,
+    theme: Res<Theme>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut table_query: Query<&mut Handle<StandardMaterial>, With<Table>>,
+) {
+    for mut material in table_query.iter_mut() {
+        *material = materials.add(StandardMaterial {
+            base_color: theme.table_tint(),
+            ..surface.material(&mut images)
+        });
+    }
+}
+
+/// Tints the whole frame when the tint changes, and the frame meshes as the model spawns
+fn apply_frame_tint(
+    tint: Res<FrameTint>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    frame_query: Query<(&Handle<StandardMaterial>, Ref<FrameMaterial>)>,
+) {
+    for (handle, _) in frame_query.iter().filter(|(_, frame)| tint.is_changed() || frame.is_added()) {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = tint.0;
+        }
+    }
+}
@@ -1,10 +1,23 @@
-use bevy::{prelude::*, input::{keyboard::KeyboardInput, ButtonState}};
-use rand::Rng;
+use std::collections::VecDeque;
 
-use crate::{tile::{TileCoordinates, TileValue, EmptyTile, TileSelected, TileLerp}, AppState, TaquinSprites};
+use bevy::{prelude::*, input::InputSystem};
+use serde::{Deserialize, Serialize};
+
+pub use bevy_taquin_3d::puzzle::{seeded_rotations, seeded_tiles, Taquin, MAX_SOLVER_SIZE};
+
+use crate::{cli::LaunchOptions, focus::navigate_focus, saved_game::GameRestored, keybindings::{Binding, KeyBindings, RebindingKey}, tile::{Direction, Easing, TileCoordinates, TileIndex, Topology, TileValue, EmptyTile, TileSelected, TileSlide}, replay::ReplayPlayback, auto_solve::AutoSolvePlayback, marker::Markers, scramble::{RandomWalk, ShuffleMode}, settings::Settings, sound::AudioSettings, tile_bake::BakedTileTextures, tile_material::{SharedTileMaterial, TileMaterial}, tile_mesh::TileThickness, is_board_playable, AppState, TaquinSprites};
 
 pub struct TaquinPlugin {
-    pub(crate) size: i8
+    pub(crate) width: i8,
+    pub(crate) height: i8,
+    pub(crate) seed: Option<u64>,
+    pub(crate) control_scheme: ControlScheme,
+    /// Whether tiles slide through the edges, `--wrap`
+    pub(crate) topology: Topology,
+    /// Empty slots of the first board, `--empty-slots`
+    pub(crate) empty_slots: usize,
+    /// Depth of the tiles, their number shows on the back
+    pub(crate) tile_thickness: f32,
 }
 
 impl Plugin for TaquinPlugin {
@@ -12,24 +25,134 @@ impl Plugin for TaquinPlugin {
         app
             .add_event::<TaquinShuffled>()
             .add_event::<TaquinSolved>()
+            .add_event::<TaquinFailed>()
             .add_event::<TileMoved>()
-            .insert_resource(Taquin::new(self.size))
+            .add_event::<SlideSelectedTile>()
+            .add_event::<NextPictureRequested>()
+            .add_event::<TextureToggled>()
+            .add_event::<ArrangeTiles>()
+            .add_event::<TaquinAction>()
+            .insert_resource(Taquin::rect(self.width, self.height))
+            .insert_resource(self.control_scheme)
+            .insert_resource(self.topology)
+            .insert_resource(EmptySlots(self.empty_slots))
+            .insert_resource(TileThickness(self.tile_thickness))
+            .insert_resource(ShuffleSeed { current: None, next: self.seed })
             .init_resource::<TaquinSoundHandles>()
-            .add_systems(Update, (move_tile_selection, (on_taquin_solved_play_tada, on_taquin_solved_reset_is_shuffled).chain().run_if(on_event::<TaquinSolved>())).run_if(in_state(AppState::Running)))
-            .add_systems(Update, (move_selected_tile, shuffle).run_if(in_state(AppState::Running).and_then(not(any_with_component::<TileLerp>()))))
-            .add_systems(Update, toggle_taquin_texture)
+            .init_resource::<MoveCount>()
+            .init_resource::<ActionQueue>()
+            .add_systems(PreUpdate, read_keyboard_actions.after(InputSystem).after(navigate_focus).run_if(is_board_playable.and_then(not(resource_exists::<RebindingKey>()))))
+            .add_systems(Update, (on_taquin_solved_play_tada, on_taquin_solved_reset_is_shuffled).chain().run_if(is_board_playable.and_then(on_event::<TaquinSolved>())))
+            .add_systems(Update, (
+                (clear_action_queue.run_if(on_event::<TaquinShuffled>()), queue_actions, run_queued_actions).chain().run_if(not(resource_exists::<AutoSolvePlayback>())),
+                apply_deferred,
+                move_selected_tile.run_if(not(any_with_component::<TileSlide>())),
+            ).chain().run_if(is_board_playable.and_then(not(resource_exists::<ReplayPlayback>())).and_then(not(resource_exists::<RandomWalk>()))))
+            .add_systems(Update, shuffle.run_if(is_board_playable.and_then(resource_equals(ShuffleMode::Seeded)).and_then(resource_exists::<ShuffleRequested>()).and_then(not(any_with_component::<TileSlide>())).and_then(not(resource_exists::<ReplayPlayback>())).and_then(not(resource_exists::<RandomWalk>()))))
+            .add_systems(OnEnter(AppState::Running), shuffle_on_launch)
+            .add_systems(Update, arrange_tiles.run_if(in_state(AppState::Running).and_then(on_event::<ArrangeTiles>())))
+            .add_systems(Update, reset_move_count.before(move_selected_tile).run_if(is_board_playable.and_then(on_event::<TaquinShuffled>().or_else(on_event::<GameRestored>()))))
+            .add_systems(Update, (toggle_taquin_texture, toggle_control_scheme).run_if(not(resource_exists::<RebindingKey>())))
+            .add_systems(Update, sync_topology.run_if(resource_changed::<Topology>().or_else(resource_changed::<Taquin>())))
         ;
     }
 }
 
+pub const DEFAULT_TILE_THICKNESS: f32 = 0.4;
+
+/// Side each direction binding stands for, whatever key it is bound to
+const DIRECTION_BINDINGS: [(Binding, Direction); 4] = [
+    (Binding::Left, Direction::Left),
+    (Binding::Right, Direction::Right),
+    (Binding::Up, Direction::Up),
+    (Binding::Down, Direction::Down),
+];
+/// Moves asked beyond these while others wait are dropped, a held key does not run on long after its release
+const MAX_QUEUED_ACTIONS: usize = 8;
+
 #[derive(Event, Default)]
 pub struct TaquinShuffled;
 
+/// What the keyboard and the gamepads ask of the board, see `read_keyboard_actions` and `gamepad`
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaquinAction {
+    /// Towards the side of the arrow key, moves the selection or slides the tile pushed that way, see `ControlScheme`
+    Direction(Direction),
+    /// Slides the tile pushed towards the side of the arrow key into the empty slot, whatever the control scheme, see `touch`
+    Push(Direction),
+    /// Slides the selected tile
+    Slide,
+    /// Shuffles, after asking when a game is well under way, see `shuffle_confirm`
+    Shuffle,
+}
+
+/// What the arrow keys do, saved in the settings
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControlScheme {
+    /// Move the selection, space slides the selected tile
+    #[default]
+    Cursor,
+    /// Slide the tile next to the empty slot in the direction of the arrow, as in the classic 15 puzzle
+    Classic,
+}
+
+/// Seed of the scrambles, a seed always gives the same board so two players can race it
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShuffleSeed {
+    /// Seed of the board on screen, none when it was not shuffled or was arranged
+    pub current: Option<u64>,
+    /// Seed of the next shuffle, set by `--seed`, random when none
+    pub next: Option<u64>,
+}
+
 #[derive(Event, Default)]
 pub struct TaquinSolved;
 
+/// Sent when a game is lost, as when a countdown runs out, see `countdown`
+#[derive(Event, Default)]
+pub struct TaquinFailed;
+
+/// Puts the tiles in the given position, row after row with the empty slot as `width * height`, as a shuffle would
+#[derive(Event, Debug, Clone)]
+pub struct ArrangeTiles(pub Vec<TileValue>);
+
+/// Asks for a shuffle, kept until the tiles stop sliding. R sets it, see `shuffle_confirm`
+#[derive(Resource, Default)]
+pub struct ShuffleRequested;
+
+/// Empty slots of the boards set up from now on, picked in the main menu, see `setup_tiles` in `main`
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptySlots(pub usize);
+
+/// Asks to slide the selected tile, as pressing space does. With several empty slots a push picks the one on its
+/// side, else the tile slides into the first one next to it
+#[derive(Event, Default)]
+pub struct SlideSelectedTile(pub Option<Direction>);
+
+/// Asks for the next picture on the tiles, as the texture key does
 #[derive(Event, Default)]
-pub struct TileMoved;
+pub struct NextPictureRequested;
+
+/// Sent once the tiles show the next picture
+#[derive(Event, Default)]
+pub struct TextureToggled;
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TileMoved {
+    pub from: TileCoordinates,
+    pub to: TileCoordinates,
+    /// Moves of the game with this one, see `MoveCount`
+    pub moves: usize,
+}
+
+/// Moves of the keyboard, the gamepads and the touches, played in order. A slide waits for the tile sliding before it
+/// to land, the moves of the selection asked after it wait with it
+#[derive(Resource, Debug, Default)]
+struct ActionQueue(VecDeque<TaquinAction>);
+
+/// Moves played since the shuffle, a resumed game going on from its saved ones. Those played on the solved board do not count
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MoveCount(pub usize);
 
 #[derive(Resource)]
 struct TaquinSoundHandles {
@@ -45,237 +168,313 @@ impl FromWorld for TaquinSoundHandles {
     }
 }
 
-#[derive(Resource, Default)]
-pub struct Taquin {
-    pub size: i8,
-    pub tiles_nb: usize,
-    pub tiles: Vec<Vec<TileValue>>,
-    pub is_shuffled: bool,
+/// Run condition of the features kept per square size, whatever the number of empty slots
+pub fn is_square_board(taquin: Res<Taquin>) -> bool {
+    taquin.is_square()
 }
 
-impl Taquin {
-    pub fn new(size: i8) -> Self {
-        Self { size, tiles_nb: (size * size) as usize, tiles: vec![], is_shuffled: false }
-    }
+/// Run condition of the features built on the solver, which knows a single empty slot and no mega board
+pub fn is_solver_board(taquin: Res<Taquin>) -> bool {
+    taquin.is_square() && taquin.width <= MAX_SOLVER_SIZE && taquin.empty_count() == 1
+}
 
-    pub fn get_next_selection_coordinates(&self, current_coordinates: &TileCoordinates, direction: KeyCode) -> TileCoordinates {
-        let mut coordinates = *current_coordinates;
-        match direction {
-            KeyCode::Left => {
-                loop {
-                    coordinates.i -= 1;
-                    if coordinates.i < 0 {
-                        coordinates.i = self.size - 1;
-                    }
-                    if !self.tiles[coordinates.j as usize][coordinates.i as usize].is_empty(self.size) {
-                        return coordinates
-                    }
-                }
-            },
-            KeyCode::Right => {
-                loop {
-                    coordinates.i += 1;
-                    if coordinates.i >= self.size {
-                        coordinates.i = 0;
-                    }
-                    if !self.tiles[coordinates.j as usize][coordinates.i as usize].is_empty(self.size) {
-                        return coordinates
-                    }
-                }
-            },
-            KeyCode::Up => {
-                loop {
-                    coordinates.j -= 1;
-                    if coordinates.j < 0 {
-                        coordinates.j = self.size - 1;
-                    }
-                    if !self.tiles[coordinates.j as usize][coordinates.i as usize].is_empty(self.size) {
-                        return coordinates
-                    }
-                }
-            },
-            KeyCode::Down => {
-                loop {
-                    coordinates.j += 1;
-                    if coordinates.j >= self.size {
-                        coordinates.j = 0;
-                    }
-                    if !self.tiles[coordinates.j as usize][coordinates.i as usize].is_empty(self.size) {
-                        return coordinates
-                    }
-                }
-            }
-            _ => coordinates
-        }
+/// Moves a tile entity into the empty slot, keeping `Taquin` and the empty tile entity in sync.
+/// A tile landing in its own cell bounces into it
+pub(crate) fn slide_tile(
+    commands: &mut Commands,
+    taquin: &mut Taquin,
+    (entity, tile_transform, tile_coords): (Entity, &Transform, &mut TileCoordinates),
+    (empty_tile_transform, empty_tile_coords): (&mut Transform, &mut TileCoordinates),
+) {
+    // More than a cell apart, the tile slides through the edges of a torus
+    let cells = (tile_coords.i - empty_tile_coords.i).abs() + (tile_coords.j - empty_tile_coords.j).abs();
+    std::mem::swap(empty_tile_coords, tile_coords);
+    taquin.swap_tiles(*tile_coords, *empty_tile_coords);
+    let value = taquin.tiles[tile_coords.j as usize][tile_coords.i as usize];
+    let in_place = value.goal(taquin.width) == *tile_coords;
+    let easing = if in_place { Easing::Bounce } else { Easing::Overshoot };
+    let (from, target) = (tile_transform.translation, empty_tile_transform.translation);
+    let mut slide = TileSlide::new(target).with_easing(easing);
+    if cells > 1 {
+        let step = (from - target) / cells as f32;
+        slide = slide.with_wrap(from + step, target - step);
     }
+    commands.entity(entity).insert(slide);
+    empty_tile_transform.translation = from;
+}
 
-    fn get_inversion_count(
-        &self
-    ) -> usize
-    {
-        let mut inversion_counter: usize = 0;
-        let flat_tiles = self.tiles.iter().flatten().collect::<Vec<&TileValue>>();
-        (0..(self.tiles_nb - 1)).for_each(|i| {
-            ((i + 1)..self.tiles_nb).for_each(|j| {
-                if flat_tiles[i].0 != self.tiles_nb as i8 && flat_tiles[j].0 != self.tiles_nb as i8 && flat_tiles[i] > flat_tiles[j] {
-                    inversion_counter += 1;
-                }
-            })
-        });
-        return inversion_counter;
+/// A board made anew, from the main menu or a replay, is flat until this catches up
+fn sync_topology(
+    topology: Res<Topology>,
+    mut taquin: ResMut<Taquin>,
+) {
+    if taquin.topology != *topology {
+        taquin.topology = *topology;
     }
+}
 
-    pub fn get_empty_tile_coordinates(&self) -> TileCoordinates
-    {
-        let mut ret_i = 0;
-        let mut ret_j = 0;
-
-        self.tiles.iter().enumerate().for_each(|(j, row)| {
-           row.iter().enumerate().for_each(|(i, tile)|  {
-                if tile.0 == self.tiles_nb as i8 {
-                    ret_i = i;
-                    ret_j = j;
-                }
-           })
-        });
-
-        TileCoordinates::new(ret_i as i8, ret_j as i8)
+/// Arrows, space and R unless rebound, see `keybindings`. The arrows and space act when pressed, a held arrow repeats
+/// as the settings say
+fn read_keyboard_actions(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    // Arrow repeating and for how long it is held, the last one pressed takes over
+    mut held: Local<Option<(Binding, Direction, f32)>>,
+    mut action_events: EventWriter<TaquinAction>,
+) {
+    for (binding, direction) in DIRECTION_BINDINGS.into_iter().filter(|(binding, _)| bindings.just_pressed(&keyboard_input, *binding)) {
+        action_events.send(TaquinAction::Direction(direction));
+        *held = Some((binding, direction, 0.));
     }
-
-    pub fn is_solvable(&self) -> bool {
-        let inversion_count = self.get_inversion_count();
-        let empty_tile_coordinates = self.get_empty_tile_coordinates();
-
-        if self.size & 1 == 1 {
-            return inversion_count & 1 == 0;
-        }
-    
-        if empty_tile_coordinates.j & 1 == 1 {
-            return inversion_count & 1 == 0;
-        }
-    
-        inversion_count & 1 == 1
+    *held = match *held {
+        Some((binding, direction, duration)) if bindings.pressed(&keyboard_input, binding) => {
+            let now = duration + time.delta_seconds();
+            for _ in 0..settings.key_repeat.repeats(duration, now) {
+                action_events.send(TaquinAction::Direction(direction));
+            }
+            Some((binding, direction, now))
+        },
+        _ => None,
+    };
+    if bindings.just_pressed(&keyboard_input, Binding::Slide) {
+        action_events.send(TaquinAction::Slide);
     }
-
-    pub fn is_solved(&self) -> bool {
-        self.tiles.iter()
-            .flatten()
-            .collect::<Vec<&TileValue>>()
-            .windows(2)
-            .filter(|a| {
-                a.get(1).is_some() && a[0] > a[1] 
-            })
-            .count() == 0
+    if bindings.just_released(&keyboard_input, Binding::Shuffle) {
+        action_events.send(TaquinAction::Shuffle);
     }
+}
 
-    pub fn swap_tiles(&mut self, a: TileCoordinates, b: TileCoordinates) {
-        let temp_tile = self.tiles[a.j as usize][a.i as usize];
-        self.tiles[a.j as usize][a.i as usize] = self.tiles[b.j as usize][b.i as usize];
-        self.tiles[b.j as usize][b.i as usize] = temp_tile;
+/// Moves asked before the shuffle were meant for the previous board
+fn clear_action_queue(
+    mut queue: ResMut<ActionQueue>,
+) {
+    queue.0.clear();
+}
+
+/// Every move asked this frame, the shuffles are left to `shuffle_confirm`
+fn queue_actions(
+    mut action_events: EventReader<TaquinAction>,
+    mut queue: ResMut<ActionQueue>,
+) {
+    for action in action_events.read().filter(|action| **action != TaquinAction::Shuffle) {
+        if queue.0.len() < MAX_QUEUED_ACTIONS {
+            queue.0.push_back(*action);
+        }
     }
 }
 
-fn move_tile_selection(
-    selected_tile_query: Query<(Entity, &TileCoordinates), With<TileSelected>>,
-    tiles_query: Query<(Entity, &TileCoordinates), Without<TileSelected>>,
-    taquin : Res<Taquin>,
+/// Moves the selection through the queue up to the first slide, which is asked once no tile slides.
+/// The arrows move the selection in the cursor controls and push the tile that way into the empty slot in the classic ones
+#[allow(clippy::too_many_arguments)]
+fn run_queued_actions(
     mut commands: Commands,
-    mut keyboard_input_events: EventReader<KeyboardInput>
+    mut queue: ResMut<ActionQueue>,
+    control_scheme: Res<ControlScheme>,
+    taquin: Res<Taquin>,
+    index: Res<TileIndex>,
+    selected_query: Query<(Entity, &TileCoordinates), With<TileSelected>>,
+    slide_query: Query<(), With<TileSlide>>,
+    mut slide_events: EventWriter<SlideSelectedTile>,
 ) {
-    let Ok((selected_tile_entity, selected_tile_coordinates)) = selected_tile_query.get_single() else {
-        return;
-    };
-
-    let Some(KeyboardInput {key_code: Some(key_code), state : ButtonState::Released, ..}) = keyboard_input_events.read().next() else {
+    let Ok((selected_entity, selected)) = selected_query.get_single() else {
         return;
     };
-
-    let selected_tile_new_coordinates = taquin.get_next_selection_coordinates(selected_tile_coordinates, *key_code);
-    if selected_tile_new_coordinates != *selected_tile_coordinates {
-        for (tile_entity, tile_coordinates) in tiles_query.iter() {
-            if *tile_coordinates == selected_tile_new_coordinates {
-                commands.entity(selected_tile_entity).remove::<TileSelected>();
-                commands.entity(tile_entity).insert(TileSelected);
-            }
+    let mut selection = *selected;
+    while let Some(action) = queue.0.front().copied() {
+        let push = match action {
+            TaquinAction::Direction(direction) if *control_scheme == ControlScheme::Cursor => {
+                selection = taquin.get_next_selection_coordinates(&selection, direction);
+                queue.0.pop_front();
+                continue;
+            },
+            TaquinAction::Direction(direction) | TaquinAction::Push(direction) => Some(direction),
+            TaquinAction::Slide => None,
+            TaquinAction::Shuffle => {
+                queue.0.pop_front();
+                continue;
+            },
+        };
+        if !slide_query.is_empty() {
+            break;
+        }
+        queue.0.pop_front();
+        if let Some(direction) = push {
+            // Nothing to push that way
+            let Some(tile) = taquin.get_tile_towards_empty(direction) else {
+                continue;
+            };
+            selection = tile;
+        }
+        slide_events.send(SlideSelectedTile(push));
+        break;
+    }
+    if selection != *selected {
+        if let Some(tile_entity) = index.at(selection) {
+            commands.entity(selected_entity).remove::<TileSelected>();
+            commands.entity(tile_entity).insert(TileSelected);
         }
     }
+}
 
+fn toggle_control_scheme(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut control_scheme: ResMut<ControlScheme>,
+) {
+    if bindings.just_released(&keyboard_input, Binding::ControlScheme) {
+        *control_scheme = match *control_scheme {
+            ControlScheme::Cursor => ControlScheme::Classic,
+            ControlScheme::Classic => ControlScheme::Cursor,
+        };
+        info!(control_scheme = ?*control_scheme, "control scheme");
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn move_selected_tile(
     mut commands: Commands,
     mut selected_tile_query: Query<(Entity, &Transform, &mut TileCoordinates), (With<TileSelected>, Without<EmptyTile>)>,
     mut empty_tile_query: Query<(&mut Transform, &mut TileCoordinates), (With<EmptyTile>, Without<TileSelected>)>,
-    keyboard_input: Res<Input<KeyCode>>,
-    mut taquin : ResMut<Taquin>,
+    index: Res<TileIndex>,
+    mut slide_events: EventReader<SlideSelectedTile>,
+    mut taquin: ResMut<Taquin>,
+    mut move_count: ResMut<MoveCount>,
     mut solved_events: EventWriter<TaquinSolved>,
     mut tile_moved_events: EventWriter<TileMoved>,
 ) {
-    if !keyboard_input.just_released(KeyCode::Space) {
-        return;
-    }
-    let Ok((mut empty_tile_transform, mut empty_tile_coords)) = empty_tile_query.get_single_mut() else {
+    let Some(SlideSelectedTile(push)) = slide_events.read().last() else {
         return;
     };
     let Ok((entity, selected_tile_transform, mut selected_tile_coords)) = selected_tile_query.get_single_mut() else {
         return;
     };
-
-    if selected_tile_coords.is_neighbour_of(empty_tile_coords.as_ref()) {
-        std::mem::swap(empty_tile_coords.as_mut(), selected_tile_coords.as_mut());
-        taquin.swap_tiles(*selected_tile_coords, *empty_tile_coords);
-        commands.entity(entity).insert(TileLerp(empty_tile_transform.translation));
-        empty_tile_transform.translation = selected_tile_transform.translation;
-        tile_moved_events.send_default();
+    let target = push.and_then(|direction| taquin.get_empty_towards(&selected_tile_coords, direction))
+        .or_else(|| taquin.get_empty_next_to(&selected_tile_coords));
+    let empty_tile = target.and_then(|target| index.at(target)).and_then(|entity| empty_tile_query.get_mut(entity).ok());
+
+    if let Some((mut empty_tile_transform, mut empty_tile_coords)) = empty_tile {
+        let from = *selected_tile_coords;
+        slide_tile(
+            &mut commands,
+            taquin.as_mut(),
+            (entity, selected_tile_transform, selected_tile_coords.as_mut()),
+            (empty_tile_transform.as_mut(), empty_tile_coords.as_mut()),
+        );
+        if taquin.is_shuffled {
+            move_count.0 += 1;
+        }
+        debug!(?from, to = ?*selected_tile_coords, "tile moved");
+        tile_moved_events.send(TileMoved { from, to: *selected_tile_coords, moves: move_count.0 });
     }
 
     if taquin.is_solved() {
+        info!(width = taquin.width, height = taquin.height, shuffled = taquin.is_shuffled, "taquin solved");
         solved_events.send_default();
     }
 }
 
+fn reset_move_count(
+    mut move_count: ResMut<MoveCount>,
+    mut shuffle_events: EventReader<TaquinShuffled>,
+    mut restored_events: EventReader<GameRestored>,
+) {
+    if shuffle_events.read().count() > 0 {
+        move_count.0 = 0;
+    }
+    if let Some(restored) = restored_events.read().last() {
+        move_count.0 = restored.moves;
+    }
+}
+
 fn shuffle(
+    mut commands: Commands,
     mut taquin : ResMut<Taquin>,
     mut shuffle_events: EventWriter<TaquinShuffled>,
-    keyboard_input: Res<Input<KeyCode>>,
-    mut tiles_query: Query<(&mut Transform, &mut TileCoordinates)>,
+    markers: Res<Markers>,
+    mut tiles_query: Query<(&TileValue, &mut TileCoordinates, &mut Transform)>,
+    mut seed: ResMut<ShuffleSeed>,
+) {
+    commands.remove_resource::<ShuffleRequested>();
+
+    let current = seed.next.take().unwrap_or_else(rand::random);
+    seed.current = Some(current);
+    let _span = info_span!("shuffle", width = taquin.width, height = taquin.height, seed = current).entered();
+    let tiles = taquin.draw_tiles(current);
+    place_tiles(&mut taquin, &markers, &mut tiles_query, &tiles);
+    info!("taquin shuffled");
+    shuffle_events.send_default();
+}
+
+/// `--start-shuffled` shuffles the first board once it is playable, a resumed game is already shuffled
+fn shuffle_on_launch(
+    mut commands: Commands,
+    launch_options: Res<LaunchOptions>,
+    taquin: Res<Taquin>,
+    mut done: Local<bool>,
 ) {
-    if !keyboard_input.just_released(KeyCode::R) {
+    if !launch_options.start_shuffled || *done {
         return;
     }
+    *done = true;
+    if !taquin.is_shuffled {
+        info!("shuffled on launch");
+        commands.init_resource::<ShuffleRequested>();
+    }
+}
 
-    loop {
-        if do_shuffle(taquin.as_mut(), &mut tiles_query) == true {
-            taquin.is_shuffled = true;
-            shuffle_events.send_default();
-            break;
-        }
+fn arrange_tiles(
+    mut arrange_events: EventReader<ArrangeTiles>,
+    markers: Res<Markers>,
+    mut taquin: ResMut<Taquin>,
+    mut tiles_query: Query<(&TileValue, &mut TileCoordinates, &mut Transform)>,
+    mut shuffle_events: EventWriter<TaquinShuffled>,
+    mut seed: ResMut<ShuffleSeed>,
+) {
+    let Some(ArrangeTiles(tiles)) = arrange_events.read().last() else {
+        return;
+    };
+    if tiles.len() != taquin.tiles_nb {
+        warn!(tiles = tiles.len(), width = taquin.width, height = taquin.height, "position made for another board size");
+        return;
     }
+    place_tiles(&mut taquin, &markers, &mut tiles_query, tiles);
+    seed.current = None;
+    shuffle_events.send_default();
 }
 
-fn do_shuffle(taquin : &mut Taquin, tiles_query: &mut Query<(&mut Transform, &mut TileCoordinates)>) -> bool {
-    let mut rng = rand::thread_rng();
-    for _i in 0..taquin.tiles_nb.pow(2) {
-        let n1: usize = rng.gen_range(0..taquin.tiles_nb as usize);
-        let n2: usize = rng.gen_range(0..taquin.tiles_nb as usize);
-        if n1 == n2 {
-            continue;
-        }
-        let mut tiles_iter = tiles_query.iter_mut();
-        if let (Some(mut tile1), Some(mut tile2)) = (tiles_iter.nth(n1), tiles_iter.nth(n2)) {
-            std::mem::swap(tile1.0.as_mut(), tile2.0.as_mut());
-            std::mem::swap(tile1.1.as_mut(), tile2.1.as_mut());
-            taquin.swap_tiles(*tile1.1, *tile2.1);
+/// Moves the tile entities to the cells of `tiles`, row after row
+pub(crate) fn place_tiles(
+    taquin: &mut Taquin,
+    markers: &Markers,
+    tiles_query: &mut Query<(&TileValue, &mut TileCoordinates, &mut Transform)>,
+    tiles: &[TileValue],
+) {
+    let (width, height) = (taquin.width, taquin.height);
+    // Cell of each value, so a mega board is not searched once per tile
+    let mut cells = vec![None; tiles.len() + 1];
+    for (index, value) in tiles.iter().enumerate() {
+        if let Some(cell) = cells.get_mut(value.0 as usize) {
+            *cell = Some(index);
         }
     }
-
-    !taquin.is_solved() && taquin.is_solvable()
+    for (value, mut coordinates, mut transform) in tiles_query.iter_mut() {
+        let Some(index) = cells.get(value.0 as usize).copied().flatten() else {
+            continue;
+        };
+        *coordinates = TileCoordinates::new((index % width as usize) as i8, (index / width as usize) as i8);
+        transform.translation = markers.tile_translation(width, height, *coordinates);
+    }
+    taquin.set_tiles(tiles.chunks(width as usize).map(|row| row.to_vec()).collect());
+    taquin.is_shuffled = true;
 }
 
 fn on_taquin_solved_play_tada(
     taquin: Res<Taquin>,
     mut commands: Commands,
-    handles: Res<TaquinSoundHandles>
+    handles: Res<TaquinSoundHandles>,
+    settings: Res<AudioSettings>,
 ) {
     if !taquin.is_shuffled {
         return;
@@ -283,7 +482,7 @@ fn on_taquin_solved_play_tada(
 
     commands.spawn(AudioBundle {
         source: handles.tada.clone(),
-        settings: PlaybackSettings::DESPAWN,
+        settings: PlaybackSettings { volume: settings.sfx_volume(1.), ..PlaybackSettings::DESPAWN },
     });
 }
 
@@ -293,82 +492,31 @@ fn on_taquin_solved_reset_is_shuffled(
     taquin.is_shuffled = false;
 }
 
+/// One update of the shared material switches the picture of every tile, baked tiles switch their own
+#[allow(clippy::too_many_arguments)]
 fn toggle_taquin_texture(
     keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut picture_events: EventReader<NextPictureRequested>,
     taquin_sprite_handles: Res<TaquinSprites>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    materials_query: Query<&Handle<StandardMaterial>, With<TileCoordinates>>,
+    mut materials: ResMut<Assets<TileMaterial>>,
+    shared_material: Res<SharedTileMaterial>,
+    baked_query: Query<(&Handle<TileMaterial>, &BakedTileTextures)>,
+    mut toggled_events: EventWriter<TextureToggled>,
 ) {
-    if !keyboard_input.just_released(KeyCode::T) {
+    if !bindings.just_released(&keyboard_input, Binding::Texture) & (picture_events.read().count() == 0) {
         return;
     }
-    for material_handle in materials_query.iter() {
+    let handles = std::iter::once((&shared_material.0, &taquin_sprite_handles.images))
+        .chain(baked_query.iter().map(|(handle, baked)| (handle, &baked.images)));
+    for (material_handle, images) in handles {
         let Some(material) = materials.get_mut(material_handle) else {
-            return;
+            continue;
         };
-    
-        if material.base_color_texture == Some(taquin_sprite_handles.bevy.clone()) {
-            material.base_color_texture = Some(taquin_sprite_handles.rust.clone());
-        } else {
-            material.base_color_texture = Some(taquin_sprite_handles.bevy.clone());
-        }
+        // Cycles through the pictures of the pack, then the gallery ones
+        let current = images.iter().position(|image| material.base.base_color_texture.as_ref() == Some(image));
+        let next = current.map_or(0, |index| (index + 1) % images.len());
+        material.base.base_color_texture = images.get(next).cloned();
     }
+    toggled_events.send_default();
 }
-
-#[cfg(test)]
-mod tests {
-    use bevy::prelude::*;
-
-    use crate::{TileValue, Taquin};
-
-    #[test]
-    fn test_is_solvable() {
-        let mut app = App::new();
-
-        app.world.insert_resource(Taquin {
-            size: 2,
-            tiles_nb: 4,
-            tiles: vec![vec![TileValue(1), TileValue(2)], vec![TileValue(3), TileValue(4)]],
-            is_shuffled: true,
-        });
-        assert_eq!(app.world.resource::<Taquin>().is_solvable(), true);
-
-        app.world.insert_resource(Taquin {
-            size: 2,
-            tiles_nb: 4,
-            tiles: vec![vec![TileValue(4), TileValue(3)], vec![TileValue(2), TileValue(1)]],
-            is_shuffled: true,
-        });
-        assert_eq!(app.world.resource::<Taquin>().is_solvable(), true);
-
-        app.world.insert_resource(Taquin {
-            size: 2,
-            tiles_nb: 4,
-            tiles: vec![vec![TileValue(2), TileValue(3)], vec![TileValue(1), TileValue(4)]],
-            is_shuffled: true,
-        });
-        assert_eq!(app.world.resource::<Taquin>().is_solvable(), true);
-    }
-
-    
-    #[test]
-    fn test_is_not_solvable() {
-        let mut app = App::new();
-
-        app.world.insert_resource(Taquin {
-            size: 2,
-            tiles_nb: 4,
-            tiles: vec![vec![TileValue(2), TileValue(1)], vec![TileValue(3), TileValue(4)]],
-            is_shuffled: true,
-        });
-        assert_eq!(app.world.resource::<Taquin>().is_solvable(), false);
-
-        app.world.insert_resource(Taquin {
-            size: 2,
-            tiles_nb: 4,
-            tiles: vec![vec![TileValue(4), TileValue(1)], vec![TileValue(2), TileValue(3)]],
-            is_shuffled: true,
-        });
-        assert_eq!(app.world.resource::<Taquin>().is_solvable(), false);
-    }
-}
\ No newline at end of file
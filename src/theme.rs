@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    graphics::MainLight,
+    persistence::{Format, PersistenceError, Versioned},
+    settings::Settings,
+    table::FrameTint,
+    AppState,
+};
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Theme>()
+            // The buttons are in the main menu and the settings screen, see `gui`
+            .add_systems(Update, (pick_theme, update_theme_buttons).chain().run_if(in_state(AppState::MainMenu).or_else(in_state(AppState::Running))))
+            .add_systems(Update, (
+                apply_theme,
+            ).run_if(resource_changed::<Theme>()));
+    }
+}
+
+/// Look of the room around the board, picked in the main menu and saved between runs in `Settings`.
+/// The table, backdrop and light intensity follow it in `table` and `graphics`
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Day,
+    Night,
+    Neon,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Day, Theme::Night, Theme::Neon];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "day" => Some(Theme::Day),
+            "night" => Some(Theme::Night),
+            "neon" => Some(Theme::Neon),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Theme::Day => "Day",
+            Theme::Night => "Night",
+            Theme::Neon => "Neon",
+        }
+    }
+
+    /// Also the top of the backdrop, which fades into it
+    pub fn clear_color(self) -> Color {
+        match self {
+            Theme::Day => Color::rgb(0.05, 0.06, 0.09),
+            Theme::Night => Color::rgb(0.01, 0.01, 0.03),
+            Theme::Neon => Color::rgb(0.04, 0., 0.08),
+        }
+    }
+
+    /// Bottom of the backdrop, just above the table
+    pub fn backdrop_color(self) -> Color {
+        match self {
+            Theme::Day => Color::rgb(0.2, 0.17, 0.22),
+            Theme::Night => Color::rgb(0.05, 0.06, 0.12),
+            Theme::Neon => Color::rgb(0.3, 0.02, 0.35),
+        }
+    }
+
+    fn light_color(self) -> Color {
+        match self {
+            Theme::Day => Color::WHITE,
+            Theme::Night => Color::rgb(0.6, 0.7, 1.),
+            Theme::Neon => Color::rgb(1., 0.3, 0.9),
+        }
+    }
+
+    /// Share of the light intensity of the graphics settings
+    pub fn light_intensity(self) -> f32 {
+        match self {
+            Theme::Day => 1.,
+            Theme::Night => 0.45,
+            Theme::Neon => 0.8,
+        }
+    }
+
+    /// Multiplies the texture of the table surface
+    pub fn table_tint(self) -> Color {
+        match self {
+            Theme::Day => Color::WHITE,
+            Theme::Night => Color::rgb(0.45, 0.5, 0.6),
+            Theme::Neon => Color::rgb(0.25, 0.2, 0.3),
+        }
+    }
+
+    fn frame_tint(self) -> FrameTint {
+        match self {
+            Theme::Day => FrameTint::default(),
+            Theme::Night => FrameTint(Color::rgb(0.55, 0.6, 0.75)),
+            Theme::Neon => FrameTint(Color::rgb(0.2, 1., 0.95)),
+        }
+    }
+}
+
+/// Picks the theme in the main menu and the settings screen
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThemeButton(pub Theme);
+
+impl ThemeButton {
+    pub fn buttons() -> Vec<(String, ThemeButton)> {
+        Theme::ALL.iter().map(|theme| (theme.label().to_string(), ThemeButton(*theme))).collect()
+    }
+}
+
+/// The theme as saved in a file of its own before it moved into `Settings`, the enum alone carries no schema version
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ThemeSettings {
+    #[serde(default)]
+    pub version: u32,
+    pub theme: Theme,
+}
+
+impl Versioned for ThemeSettings {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl FromWorld for Theme {
+    fn from_world(world: &mut World) -> Self {
+        world.resource::<Settings>().theme
+    }
+}
+
+fn pick_theme(
+    button_query: Query<(&Interaction, &ThemeButton), Changed<Interaction>>,
+    mut theme: ResMut<Theme>,
+) {
+    if let Some((_, ThemeButton(picked))) = button_query.iter().find(|(interaction, _)| **interaction == Interaction::Pressed) {
+        if *theme != *picked {
+            *theme = *picked;
+            info!(theme = ?*theme, "theme picked");
+        }
+    }
+}
+
+fn update_theme_buttons(
+    theme: Res<Theme>,
+    mut button_query: Query<(&ThemeButton, &mut BackgroundColor, Ref<ThemeButton>)>,
+) {
+    for (ThemeButton(button_theme), mut background, button) in button_query.iter_mut() {
+        if !theme.is_changed() && !button.is_added() {
+            continue;
+        }
+        background.0 = if *button_theme == *theme { Color::rgba(0.65, 0.65, 0.65, 0.9) } else { Color::rgba(0.65, 0.65, 0.65, 0.4) };
+    }
+}
+
+/// Only replaces the tint of the previous theme, a tint chosen by the player, a mod or a cosmetic wins
+fn apply_theme(
+    theme: Res<Theme>,
+    mut clear_color: ResMut<ClearColor>,
+    mut frame_tint: ResMut<FrameTint>,
+    mut applied: Local<Option<Theme>>,
+    mut lights_query: Query<&mut PointLight, With<MainLight>>,
+) {
+    clear_color.0 = theme.clear_color();
+    if *frame_tint == applied.unwrap_or(Theme::Day).frame_tint() {
+        *frame_tint = theme.frame_tint();
+    }
+    for mut light in lights_query.iter_mut() {
+        light.color = theme.light_color();
+    }
+    *applied = Some(*theme);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{persistence::{self, Format}, theme::{Theme, ThemeSettings}};
+
+    #[test]
+    fn test_theme_settings_round_trip() {
+        for theme in Theme::ALL {
+            let contents = Format::Ron.serialize(&ThemeSettings { version: 1, theme }).unwrap();
+            assert_eq!(persistence::parse_versioned::<ThemeSettings>(&contents, Format::Ron).unwrap().theme, theme);
+        }
+        assert!(persistence::parse_versioned::<ThemeSettings>("(version: 2, theme: Night)", Format::Ron).is_err());
+    }
+}
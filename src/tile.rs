@@ -1,113 +1,208 @@
+use std::{collections::HashMap, f32::consts::TAU};
+
 use bevy::prelude::*;
-use std::ops::Add;
 
-use crate::AppState;
+pub use bevy_taquin_3d::{coordinates::{Direction, TileCoordinates, Topology}, puzzle::TileValue, slide::{tile_name, Easing, TileAnimationFinished, TileAnimations, TileSlide}};
+use bevy_taquin_3d::slide::SlidePlugin;
+
+use crate::{tile_material::TileShading, is_board_playable};
+
+/// Seconds of a breath of the selection glow
+const SELECTION_PULSE_PERIOD: f32 = 1.2;
+/// Share of the selection color left at the dimmest of the breath
+const SELECTION_PULSE_MIN: f32 = 0.35;
 
 pub struct TilePlugin;
 
 impl Plugin for TilePlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app
+            .add_plugins(SlidePlugin)
+            .init_resource::<SelectionColor>()
+            .init_resource::<TileIndex>()
             .add_systems(Update, (
-                on_tile_selected_changed, on_tile_selected_removal, move_tile
-            ).run_if(in_state(AppState::Running)));
+                (on_tile_selected_changed, on_tile_selected_removal, pulse_selected_tile).chain(),
+            ).run_if(is_board_playable))
+            .add_systems(PostUpdate, sync_tile_index);
     }
 }
 
 #[derive(Component, Debug)]
 pub struct EmptyTile;
 
-#[derive(Component, Debug, Clone, Copy, PartialEq, PartialOrd)]
-pub struct TileValue(pub i8);
+/// Stable identity of a tile entity: its value, the cell it belongs to, whatever cell it is in.
+/// The empty tile is the last one
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TileId(pub u16);
 
-impl TileValue {
-    pub fn is_empty(&self, taquin_size: i8) -> bool {
-        return self.0 == taquin_size * taquin_size;
+impl From<TileValue> for TileId {
+    fn from(value: TileValue) -> Self {
+        TileId(value.0 as u16)
     }
 }
 
-#[derive(Component, Debug)]
-pub struct TileSelected;
-
-#[derive(Component, Debug, PartialEq, Clone, Copy)]
-pub struct TileCoordinates {
-    pub i: i8,
-    pub j: i8
+/// Tile entities by id and by the cell they are in, so that a move finds its tile without going through
+/// all of them. Refreshed in `PostUpdate` from the tiles spawned, moved or despawned during the frame
+#[derive(Resource, Debug, Default)]
+pub struct TileIndex {
+    by_id: HashMap<TileId, Entity>,
+    by_cell: HashMap<TileCoordinates, Entity>,
+    /// Where each entity was last indexed, so that despawning a mega board does not search the maps per tile
+    by_entity: HashMap<Entity, (TileId, TileCoordinates)>,
 }
 
-impl TileCoordinates {
-    pub fn new(i: i8, j:i8) -> Self {
-        Self {i, j}
+impl TileIndex {
+    pub fn entity(&self, id: TileId) -> Option<Entity> {
+        self.by_id.get(&id).copied()
     }
 
-    pub fn is_neighbour_of(&self, other: &TileCoordinates)-> bool {
-        self.get_neighbours().contains(other)
+    /// Tile in `cell`, the empty one included
+    pub fn at(&self, cell: TileCoordinates) -> Option<Entity> {
+        self.by_cell.get(&cell).copied()
     }
 
-    fn get_neighbours(self) -> Vec<TileCoordinates>
-    {
-        vec![self + (1, 0), self + (0, 1), self + (-1, 0), self + (0, -1)]
+    fn insert(&mut self, entity: Entity, id: TileId, cell: TileCoordinates) {
+        self.by_id.insert(id, entity);
+        self.by_cell.insert(cell, entity);
+        self.by_entity.insert(entity, (id, cell));
     }
-}
 
-impl Add<(i8, i8)> for TileCoordinates {
-    type Output = Self;
-
-    fn add(self, other: (i8, i8)) -> Self {
-        Self {
-            i: self.i + other.0,
-            j: self.j + other.1,
+    fn remove(&mut self, entity: Entity) {
+        let Some((id, cell)) = self.by_entity.remove(&entity) else {
+            return;
+        };
+        // Another tile may have taken the cell since
+        if self.by_id.get(&id) == Some(&entity) {
+            self.by_id.remove(&id);
+        }
+        if self.by_cell.get(&cell) == Some(&entity) {
+            self.by_cell.remove(&cell);
         }
     }
 }
 
+/// Moves to another tile at every move of an auto-solve
 #[derive(Component, Debug)]
-pub struct TileLerp(pub Vec3);
+#[component(storage = "SparseSet")]
+pub struct TileSelected;
+
+/// Glow of the selected tile, at the brightest of its pulse
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct SelectionColor(pub Color);
+
+impl Default for SelectionColor {
+    fn default() -> Self {
+        Self(Color::RED)
+    }
+}
 
+/// Breathing glow of the selected tile, restarted bright whenever the selection moves onto a tile
 #[derive(Component, Debug, Default)]
-pub struct TileAnimations {
-    pub up: Handle<AnimationClip>,
-    pub right: Handle<AnimationClip>,
-    pub down: Handle<AnimationClip>,
-    pub left: Handle<AnimationClip>,
+#[component(storage = "SparseSet")]
+pub struct SelectionPulse {
+    elapsed: f32,
+}
+
+impl SelectionPulse {
+    /// Share of the selection color, 1 when the pulse starts
+    fn intensity(&self) -> f32 {
+        let breath = 0.5 + 0.5 * (self.elapsed * TAU / SELECTION_PULSE_PERIOD).cos();
+        SELECTION_PULSE_MIN + (1. - SELECTION_PULSE_MIN) * breath
+    }
+}
+
+/// Another module drives the glow of the tile for a while, such as a hint, the selection pulse leaves it alone meanwhile
+#[derive(Component, Debug)]
+#[component(storage = "SparseSet")]
+pub struct GlowOverride;
+
+/// Leaves the shading untouched when the tile already glows so, a change uploads its mesh again
+fn set_glow(shading: &mut Mut<TileShading>, color: Color) {
+    if shading.glow != color {
+        shading.glow = color;
+    }
 }
 
 fn on_tile_selected_changed(
-    query: Query<&Handle<StandardMaterial>, Changed<TileSelected>>,
-    mut materials: ResMut<Assets<StandardMaterial>>
+    mut commands: Commands,
+    query: Query<Entity, (Changed<TileSelected>, With<TileShading>)>,
 ) {
-    for material in &query {
-        if let Some(material) = materials.get_mut(material) {
-            material.emissive = Color::RED;
-        } 
+    for entity in query.iter() {
+        commands.entity(entity).insert(SelectionPulse::default());
     }
 }
 
+/// The pulse goes with the selection, the tile left behind stops glowing
 fn on_tile_selected_removal(
-    mut removed: RemovedComponents<TileSelected>, mut query: Query<&Handle<StandardMaterial>>,
-    mut materials: ResMut<Assets<StandardMaterial>>
+    mut commands: Commands,
+    mut removed: RemovedComponents<TileSelected>,
+    mut query: Query<(&mut TileShading, Has<TileSelected>)>,
 ) {
     for entity in removed.read() {
-        if let Ok(material_handle) = query.get_mut(entity) {
-            if let Some(material) = materials.get_mut(material_handle) {
-                material.emissive = Color::BLACK;
-            } 
+        // Despawned, or selected again in the same frame
+        let Ok((mut shading, selected)) = query.get_mut(entity) else {
+            continue;
+        };
+        if !selected {
+            commands.entity(entity).remove::<SelectionPulse>();
+            set_glow(&mut shading, Color::BLACK);
         }
     }
 }
 
-fn move_tile(
-    mut commands: Commands,
-    mut tile_query: Query<(Entity, &mut Transform, &TileLerp)>, 
+fn pulse_selected_tile(
+    time: Res<Time>,
+    selection_color: Res<SelectionColor>,
+    mut query: Query<(&mut SelectionPulse, &mut TileShading), (With<TileSelected>, Without<GlowOverride>)>,
 ) {
-    let Ok((entity, mut transform, tile_lerp)) = tile_query.get_single_mut() else {
-        return;
-    };
+    for (mut pulse, mut shading) in query.iter_mut() {
+        pulse.elapsed += time.delta_seconds();
+        set_glow(&mut shading, selection_color.0 * pulse.intensity());
+    }
+}
 
-    transform.translation = transform.translation.lerp(tile_lerp.0, 0.25);
+/// Every move swaps the cells of a tile and of the empty one, both are changed so no cell is left stale
+fn sync_tile_index(
+    mut index: ResMut<TileIndex>,
+    tiles_query: Query<(Entity, &TileId, &TileCoordinates), Changed<TileCoordinates>>,
+    mut removed: RemovedComponents<TileId>,
+) {
+    for entity in removed.read() {
+        index.remove(entity);
+    }
+    for (entity, id, cell) in tiles_query.iter() {
+        index.insert(entity, *id, *cell);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::tile::{SelectionPulse, TileCoordinates, TileId, TileIndex, SELECTION_PULSE_MIN, SELECTION_PULSE_PERIOD};
+
+    #[test]
+    fn test_tile_index() {
+        let mut index = TileIndex::default();
+        let (tile, empty) = (Entity::from_raw(1), Entity::from_raw(2));
+        index.insert(tile, TileId(1), TileCoordinates::new(0, 0));
+        index.insert(empty, TileId(4), TileCoordinates::new(1, 0));
+        // A slide moves both
+        index.insert(tile, TileId(1), TileCoordinates::new(1, 0));
+        index.insert(empty, TileId(4), TileCoordinates::new(0, 0));
+        assert_eq!(index.at(TileCoordinates::new(1, 0)), Some(tile));
+        assert_eq!(index.entity(TileId(4)), Some(empty));
+        index.remove(tile);
+        assert_eq!((index.entity(TileId(1)), index.at(TileCoordinates::new(1, 0))), (None, None));
+    }
 
-    if transform.translation.abs_diff_eq(tile_lerp.0, 0.01) {
-        transform.translation = tile_lerp.0;
-        commands.entity(entity).remove::<TileLerp>();
+    #[test]
+    fn test_selection_pulse() {
+        let mut pulse = SelectionPulse::default();
+        assert_eq!(pulse.intensity(), 1.);
+        pulse.elapsed = SELECTION_PULSE_PERIOD / 2.;
+        assert!((pulse.intensity() - SELECTION_PULSE_MIN).abs() < 1e-5);
+        pulse.elapsed = SELECTION_PULSE_PERIOD;
+        assert!((pulse.intensity() - 1.).abs() < 1e-5);
     }
-}
\ No newline at end of file
+}
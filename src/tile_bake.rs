@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+
+use crate::{mega_board::is_mega, packs::PackApplied, taquin::Taquin, tile::TileValue, tile_material::{SharedTileMaterial, TileMaterial}, tile_mesh::set_tile_uvs, AppState, TaquinSprites};
+
+/// Boards bigger than this get one small texture per tile instead of a window on the whole picture,
+/// neighbouring fragments then sample the same small texture which is kinder to the texture cache.
+/// The tiles then leave the shared material for copies of it showing their texture
+pub const BAKE_THRESHOLD: i8 = 8;
+
+pub struct TileBakePlugin;
+
+impl Plugin for TileBakePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // Coming back from a pause finds the tiles baked already
+            .add_systems(OnEnter(AppState::Running), bake_tile_textures.run_if(is_baked_board.and_then(not(any_with_component::<BakedTileTextures>()))))
+            .add_systems(Update, bake_tile_textures.run_if(on_event::<PackApplied>().and_then(is_baked_board)));
+    }
+}
+
+/// Tiles of mega boards have no mesh of their own to give a texture, their chunks keep sampling the picture
+fn is_baked_board(taquin: Res<Taquin>) -> bool {
+    taquin.width.max(taquin.height) > BAKE_THRESHOLD && !is_mega(taquin.width, taquin.height)
+}
+
+/// Cropped copies of the pictures of the texture pack for one tile, in the same order
+#[derive(Component)]
+pub struct BakedTileTextures {
+    pub images: Vec<Handle<Image>>,
+}
+
+/// The `columns` by `rows` sub-images of `picture`, row after row
+fn crop_tiles(picture: &Image, columns: u32, rows: u32) -> Option<Vec<Image>> {
+    let picture = picture.clone().try_into_dynamic().ok()?;
+    let (width, height) = (picture.width() / columns, picture.height() / rows);
+    Some((0..columns * rows)
+        .map(|index| Image::from_dynamic(picture.crop_imm(index % columns * width, index / columns * height, width, height), true))
+        .collect())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn bake_tile_textures(
+    mut commands: Commands,
+    taquin: Res<Taquin>,
+    sprites: Res<TaquinSprites>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<TileMaterial>>,
+    shared_material: Res<SharedTileMaterial>,
+    tiles_query: Query<(Entity, &TileValue, &Handle<Mesh>)>,
+) {
+    let _span = info_span!("bake_tile_textures", width = taquin.width, height = taquin.height).entered();
+    let crop = |handle: &Handle<Image>| images.get(handle).and_then(|picture| crop_tiles(picture, taquin.width as u32, taquin.height as u32));
+    let Some(cropped) = sprites.images.iter().map(crop).collect::<Option<Vec<Vec<Image>>>>() else {
+        warn!("cannot crop the taquin pictures, tiles keep sampling the whole picture");
+        return;
+    };
+    // Tiles of every picture, picture after picture
+    let tiles = cropped.into_iter()
+        .map(|tiles| tiles.into_iter().map(|image| images.add(image)).collect::<Vec<Handle<Image>>>())
+        .collect::<Vec<Vec<Handle<Image>>>>();
+
+    // The shared material keeps the picture shown, the filter and the border
+    let Some(shared) = materials.get(&shared_material.0).cloned() else {
+        return;
+    };
+    let picture = sprites.images.iter().position(|image| shared.base.base_color_texture.as_ref() == Some(image)).unwrap_or(0);
+    for (entity, value, mesh_handle) in tiles_query.iter() {
+        let index = value.0 as usize - 1;
+        let mut material = shared.clone();
+        material.base.base_color_texture = Some(tiles[picture][index].clone());
+        material.extension.border_grid = Vec2::ONE;
+        if let Some(mesh) = meshes.get_mut(mesh_handle) {
+            set_tile_uvs(mesh, Rect::new(0., 0., 1., 1.));
+        }
+        commands.entity(entity).insert((materials.add(material), BakedTileTextures { images: tiles.iter().map(|tiles| tiles[index].clone()).collect() }));
+    }
+    info!(tiles = taquin.tiles_nb, pictures = tiles.len(), "tile textures baked");
+}
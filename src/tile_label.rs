@@ -0,0 +1,251 @@
+use bevy::{prelude::*, render::{primitives::Aabb, render_resource::{Extent3d, TextureDimension, TextureFormat}}};
+
+use crate::{exit::ExitConfirmation, shuffle_confirm::ShuffleConfirmation, tile::{EmptyTile, TileValue}, AppState};
+
+const TEXTURE_SIZE: usize = 128;
+const LABEL_COLOR: [u8; 4] = [255, 214, 0, 255];
+const BACKGROUND_COLOR: [u8; 4] = [0, 0, 0, 200];
+
+pub struct TileLabelPlugin;
+
+impl Plugin for TileLabelPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<TileLabelStyle>()
+            .init_resource::<ShownTileLabelStyle>()
+            .add_systems(Update, (
+                (
+                    cycle_tile_label_style,
+                    // `N` answers the dialogs there
+                    toggle_tile_labels.run_if(not(resource_exists::<ExitConfirmation>()).and_then(not(resource_exists::<ShuffleConfirmation>()))),
+                ),
+                (despawn_tile_labels, spawn_tile_labels).chain().run_if(resource_changed::<TileLabelStyle>().or_else(tiles_spawned)),
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// High contrast label drawn over each tile for low-vision players
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileLabelStyle {
+    #[default]
+    None,
+    /// Digits filling most of the tile
+    Digits,
+    /// Braille numeric indicator followed by one cell per digit
+    Braille,
+}
+
+impl TileLabelStyle {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(TileLabelStyle::None),
+            "digits" => Some(TileLabelStyle::Digits),
+            "braille" => Some(TileLabelStyle::Braille),
+            _ => None,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            TileLabelStyle::None => TileLabelStyle::Digits,
+            TileLabelStyle::Digits => TileLabelStyle::Braille,
+            TileLabelStyle::Braille => TileLabelStyle::None,
+        }
+    }
+}
+
+/// Style `N` brings the labels back with, the last one shown
+#[derive(Resource)]
+struct ShownTileLabelStyle(TileLabelStyle);
+
+impl Default for ShownTileLabelStyle {
+    fn default() -> Self {
+        ShownTileLabelStyle(TileLabelStyle::Digits)
+    }
+}
+
+#[derive(Component)]
+struct TileLabel;
+
+/// Rows of a 3x5 digit, most significant bit on the left
+pub(crate) fn digit_glyph(digit: u32) -> [u8; 5] {
+    match digit {
+        0 => [0b111, 0b101, 0b101, 0b101, 0b111],
+        1 => [0b010, 0b110, 0b010, 0b010, 0b111],
+        2 => [0b111, 0b001, 0b111, 0b100, 0b111],
+        3 => [0b111, 0b001, 0b111, 0b001, 0b111],
+        4 => [0b101, 0b101, 0b111, 0b001, 0b001],
+        5 => [0b111, 0b100, 0b111, 0b001, 0b111],
+        6 => [0b111, 0b100, 0b111, 0b101, 0b111],
+        7 => [0b111, 0b001, 0b010, 0b010, 0b010],
+        8 => [0b111, 0b101, 0b111, 0b101, 0b111],
+        _ => [0b111, 0b101, 0b111, 0b001, 0b111],
+    }
+}
+
+/// Raised dots of a braille cell, bit `n - 1` set for dot `n`
+/// (dots 1 to 3 down the left column, 4 to 6 down the right one)
+fn braille_cell(digit: Option<u32>) -> u8 {
+    match digit {
+        None => 0b111100,
+        Some(1) => 0b000001,
+        Some(2) => 0b000011,
+        Some(3) => 0b001001,
+        Some(4) => 0b011001,
+        Some(5) => 0b010001,
+        Some(6) => 0b001011,
+        Some(7) => 0b011011,
+        Some(8) => 0b010011,
+        Some(9) => 0b001010,
+        _ => 0b011010,
+    }
+}
+
+fn digits(value: i16) -> Vec<u32> {
+    value.to_string().chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+/// Cells lit by a label, in a grid of `columns` by `rows` cells
+fn label_cells(value: i16, style: TileLabelStyle) -> (usize, usize, Vec<(usize, usize)>) {
+    let mut cells = Vec::new();
+    match style {
+        TileLabelStyle::None => (1, 1, cells),
+        TileLabelStyle::Digits => {
+            let digits = digits(value);
+            for (index, digit) in digits.iter().enumerate() {
+                for (row, bits) in digit_glyph(*digit).iter().enumerate() {
+                    cells.extend((0..3).filter(|column| bits & (0b100 >> column) != 0).map(|column| (index * 4 + column, row)));
+                }
+            }
+            (digits.len() * 4 - 1, 5, cells)
+        },
+        TileLabelStyle::Braille => {
+            let symbols = std::iter::once(None).chain(digits(value).into_iter().map(Some)).collect::<Vec<Option<u32>>>();
+            for (index, symbol) in symbols.iter().enumerate() {
+                let dots = braille_cell(*symbol);
+                cells.extend((0..6).filter(|dot| dots & (1 << dot) != 0).map(|dot| (index * 3 + dot / 3, dot % 3)));
+            }
+            (symbols.len() * 3 - 1, 3, cells)
+        },
+    }
+}
+
+pub(crate) fn label_texture(value: i16, style: TileLabelStyle) -> Image {
+    let (columns, rows, cells) = label_cells(value, style);
+    let cell_size = (TEXTURE_SIZE * 4 / 5) / columns.max(rows);
+    let origin = ((TEXTURE_SIZE - columns * cell_size) / 2, (TEXTURE_SIZE - rows * cell_size) / 2);
+    let mut texture_data = BACKGROUND_COLOR.repeat(TEXTURE_SIZE * TEXTURE_SIZE);
+
+    for (column, row) in cells {
+        let (x0, y0) = (origin.0 + column * cell_size, origin.1 + row * cell_size);
+        for y in y0..y0 + cell_size {
+            for x in x0..x0 + cell_size {
+                // Braille dots are drawn round so they read as dots and not as a grid
+                if style == TileLabelStyle::Braille {
+                    let (dx, dy) = (x as f32 - (x0 as f32 + cell_size as f32 / 2.), y as f32 - (y0 as f32 + cell_size as f32 / 2.));
+                    if dx * dx + dy * dy > (cell_size as f32 * 0.45).powi(2) {
+                        continue;
+                    }
+                }
+                let offset = (y * TEXTURE_SIZE + x) * 4;
+                texture_data[offset..offset + 4].copy_from_slice(&LABEL_COLOR);
+            }
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: TEXTURE_SIZE as u32,
+            height: TEXTURE_SIZE as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        texture_data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn cycle_tile_label_style(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut style: ResMut<TileLabelStyle>,
+) {
+    if keyboard_input.just_released(KeyCode::L) {
+        *style = style.next();
+    }
+}
+
+/// Hides the labels, or shows them again in the style they had
+fn toggle_tile_labels(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut style: ResMut<TileLabelStyle>,
+    mut shown: ResMut<ShownTileLabelStyle>,
+) {
+    if !keyboard_input.just_released(KeyCode::N) {
+        return;
+    }
+    if *style == TileLabelStyle::None {
+        *style = shown.0;
+    } else {
+        shown.0 = *style;
+        *style = TileLabelStyle::None;
+    }
+}
+
+/// Tiles of a new board get their bounds the frame after they are spawned, the labels are sized from them
+fn tiles_spawned(
+    tiles_query: Query<(), (With<TileValue>, Added<Aabb>)>,
+) -> bool {
+    !tiles_query.is_empty()
+}
+
+fn despawn_tile_labels(
+    mut commands: Commands,
+    labels_query: Query<Entity, With<TileLabel>>,
+) {
+    for entity in labels_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn spawn_tile_labels(
+    mut commands: Commands,
+    style: Res<TileLabelStyle>,
+    tiles_query: Query<(Entity, &TileValue, &Aabb), Without<EmptyTile>>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if *style == TileLabelStyle::None {
+        return;
+    }
+
+    for (entity, value, aabb) in tiles_query.iter() {
+        let mesh = meshes.add(shape::Quad::new(2. * aabb.half_extents.truncate()).into());
+        let material = materials.add(StandardMaterial {
+            base_color_texture: Some(images.add(label_texture(value.0, *style))),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+        let label = commands.spawn((PbrBundle {
+            mesh,
+            material,
+            transform: Transform::from_xyz(0., 0., 0.01),
+            ..default()
+        }, TileLabel)).id();
+        commands.entity(entity).add_child(label);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tile_label::{label_cells, TileLabelStyle};
+
+    #[test]
+    fn test_braille_label_cells() {
+        // numeric indicator (dots 3, 4, 5, 6) then "a" (dot 1) for 1
+        let (columns, rows, cells) = label_cells(1, TileLabelStyle::Braille);
+        assert_eq!((columns, rows), (5, 3));
+        assert_eq!(cells, vec![(0, 2), (1, 0), (1, 1), (1, 2), (3, 0)]);
+    }
+}
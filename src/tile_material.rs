@@ -0,0 +1,176 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    pbr::{ExtendedMaterial, MaterialExtension},
+    prelude::*,
+    render::{mesh::VertexAttributeValues, render_resource::{AsBindGroup, ShaderRef}},
+};
+
+use crate::AppState;
+
+/// Hue rotation of the hue shift filter, a third of the color wheel
+const HUE_SHIFT: f32 = TAU / 3.;
+
+/// Material of the tiles, the standard one with the puzzle texture recolored before lighting,
+/// outlined by the border of the texture pack and eaten away by noise while the tile dissolves
+pub type TileMaterial = ExtendedMaterial<StandardMaterial, TileExtension>;
+
+/// Material every tile is drawn with, the UVs of their meshes pick their part of the picture.
+/// Only the tiles of baked boards have materials of their own, see `tile_bake`
+#[derive(Resource)]
+pub struct SharedTileMaterial(pub Handle<TileMaterial>);
+
+impl FromWorld for SharedTileMaterial {
+    fn from_world(world: &mut World) -> Self {
+        Self(world.resource_mut::<Assets<TileMaterial>>().add(TileMaterial {
+            base: StandardMaterial {
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            },
+            extension: TileExtension::default(),
+        }))
+    }
+}
+
+/// Look of a single tile over the shared material, passed to `tile_material.wgsl` as the vertex colors of its mesh
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct TileShading {
+    /// Emissive color, black when the tile does not glow
+    pub glow: Color,
+    /// From 0, fully drawn, to 1, fully dissolved
+    pub dissolve: f32,
+}
+
+impl Default for TileShading {
+    fn default() -> Self {
+        Self { glow: Color::BLACK, dissolve: 0. }
+    }
+}
+
+impl TileShading {
+    /// The linear glow then the dissolve, as the shader reads them
+    pub(crate) fn vertex_color(&self) -> [f32; 4] {
+        let [r, g, b, _] = self.glow.as_linear_rgba_f32();
+        [r, g, b, self.dissolve]
+    }
+}
+
+pub struct TileMaterialPlugin;
+
+impl Plugin for TileMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins(MaterialPlugin::<TileMaterial>::default())
+            .init_resource::<SharedTileMaterial>()
+            .init_resource::<ColorFilter>()
+            .add_systems(Update, (
+                cycle_color_filter.run_if(in_state(AppState::Running)),
+                apply_color_filter.run_if(resource_changed::<ColorFilter>()),
+            ).chain())
+            // After the systems of the other plugins changing the shading in Update
+            .add_systems(PostUpdate, apply_tile_shading);
+    }
+}
+
+/// Recoloring of the puzzle texture
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorFilter {
+    #[default]
+    None,
+    Grayscale,
+    Sepia,
+    HueShift,
+    Invert,
+}
+
+impl ColorFilter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(ColorFilter::None),
+            "grayscale" => Some(ColorFilter::Grayscale),
+            "sepia" => Some(ColorFilter::Sepia),
+            "hue-shift" => Some(ColorFilter::HueShift),
+            "invert" => Some(ColorFilter::Invert),
+            _ => None,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            ColorFilter::None => ColorFilter::Grayscale,
+            ColorFilter::Grayscale => ColorFilter::Sepia,
+            ColorFilter::Sepia => ColorFilter::HueShift,
+            ColorFilter::HueShift => ColorFilter::Invert,
+            ColorFilter::Invert => ColorFilter::None,
+        }
+    }
+
+    /// Matches the cases of `apply_filter` in `tile_material.wgsl`
+    fn shader_index(self) -> u32 {
+        self as u32
+    }
+}
+
+/// Fields sharing the binding are laid out as the `TileSettings` struct of `tile_material.wgsl`
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct TileExtension {
+    #[uniform(100)]
+    pub filter: u32,
+    /// Radians, only read by the hue shift filter
+    #[uniform(100)]
+    pub hue_shift: f32,
+    /// Fraction of the tile side, 0 without border
+    #[uniform(100)]
+    pub border_width: f32,
+    /// Tiles along each side of the picture the tile samples, 1 when it has a picture of its own
+    #[uniform(100)]
+    pub border_grid: Vec2,
+    /// Linear RGBA
+    #[uniform(100)]
+    pub border_color: Vec4,
+}
+
+impl MaterialExtension for TileExtension {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/tile_material.wgsl".into()
+    }
+
+    fn deferred_fragment_shader() -> ShaderRef {
+        "shaders/tile_material.wgsl".into()
+    }
+}
+
+fn cycle_color_filter(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut filter: ResMut<ColorFilter>,
+) {
+    // With Shift it resets the camera, see `camera`, with Control it copies the board code, see `board_code`
+    if keyboard_input.just_released(KeyCode::C) && !keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight, KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        *filter = filter.next();
+        info!(filter = ?*filter, "color filter");
+    }
+}
+
+/// Sets the filter on the shared material and the baked ones, which are copies of it
+fn apply_color_filter(
+    filter: Res<ColorFilter>,
+    mut materials: ResMut<Assets<TileMaterial>>,
+) {
+    for (_, material) in materials.iter_mut() {
+        material.extension.filter = filter.shader_index();
+        material.extension.hue_shift = HUE_SHIFT;
+    }
+}
+
+/// Each tile has a mesh of its own for its UVs, its shading goes along in the vertex colors
+fn apply_tile_shading(
+    mut meshes: ResMut<Assets<Mesh>>,
+    tiles_query: Query<(&TileShading, &Handle<Mesh>), Changed<TileShading>>,
+) {
+    for (shading, mesh_handle) in tiles_query.iter() {
+        if let Some(mesh) = meshes.get_mut(mesh_handle) {
+            let vertices = mesh.count_vertices();
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, VertexAttributeValues::Float32x4(vec![shading.vertex_color(); vertices]));
+        }
+    }
+}
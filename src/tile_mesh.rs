@@ -0,0 +1,95 @@
+use bevy::{prelude::*, render::{mesh::{Indices, VertexAttributeValues}, render_resource::PrimitiveTopology}};
+
+/// Share of the smaller side of a tile the bevel of its front edges takes
+const BEVEL: f32 = 0.06;
+
+/// Depth of the tiles behind their front face, in world units, set by `TaquinPlugin`
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct TileThickness(pub f32);
+
+/// Thin box with beveled front edges, the front face on the origin facing +Z and the back `thickness` behind it,
+/// so the picture stays where a flat tile would show it. Every vertex samples `uv` at its place on the tile,
+/// the bevels and sides show the edges of the picture part
+pub fn tile_mesh(size: Vec2, thickness: f32, uv: Rect) -> Mesh {
+    let bevel = BEVEL * size.min_element();
+    let bevel_depth = bevel.min(thickness / 2.);
+    let (outer, inner) = (size / 2., size / 2. - bevel);
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut indices = Vec::new();
+    let mut quad = |corners: [Vec3; 4], normal: Vec3| {
+        let start = positions.len() as u32;
+        positions.extend(corners.map(|corner| corner.to_array()));
+        normals.extend([normal.to_array(); 4]);
+        // Counterclockwise seen from the side the face looks at
+        let order = if (corners[1] - corners[0]).cross(corners[2] - corners[0]).dot(normal) > 0. { [0, 1, 2, 0, 2, 3] } else { [0, 2, 1, 0, 3, 2] };
+        indices.extend(order.map(|index| start + index));
+    };
+    let rect = |half: Vec2, z: f32| [Vec3::new(-half.x, -half.y, z), Vec3::new(half.x, -half.y, z), Vec3::new(half.x, half.y, z), Vec3::new(-half.x, half.y, z)];
+
+    quad(rect(inner, 0.), Vec3::Z);
+    for side in [Vec2::X, Vec2::Y, Vec2::NEG_X, Vec2::NEG_Y] {
+        let along = side.perp();
+        let edge = |half: Vec2, z: f32| [(side + along) * half, (side - along) * half].map(|corner| corner.extend(z));
+        let ([inner_a, inner_b], [outer_a, outer_b]) = (edge(inner, 0.), edge(outer, -bevel_depth));
+        quad([inner_a, inner_b, outer_b, outer_a], (side * bevel_depth).extend(bevel).normalize());
+        let [back_a, back_b] = edge(outer, -thickness);
+        quad([outer_a, outer_b, back_b, back_a], side.extend(0.));
+    }
+    quad(rect(outer, -thickness), Vec3::NEG_Z);
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    set_tile_uvs(&mut mesh, uv);
+    mesh
+}
+
+/// Points the vertices of a tile at `uv`, the picture part they sample, from where they stand on the tile
+pub fn set_tile_uvs(mesh: &mut Mesh, uv: Rect) {
+    let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
+        return;
+    };
+    let size = positions.iter().fold(Vec2::ZERO, |size, position| size.max(Vec2::new(position[0].abs(), position[1].abs()) * 2.));
+    let uvs = positions.iter()
+        .map(|position| [uv.min.x + (position[0] / size.x + 0.5) * uv.width(), uv.min.y + (0.5 - position[1] / size.y) * uv.height()])
+        .collect::<Vec<[f32; 2]>>();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+
+    use crate::tile_mesh::tile_mesh;
+
+    #[test]
+    fn test_tile_mesh() {
+        let mesh = tile_mesh(Vec2::new(2., 1.), 0.4, Rect::new(0.25, 0.5, 0.5, 1.));
+        // Front, four bevels, four sides and back
+        assert_eq!(mesh.count_vertices(), 10 * 4);
+        let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else {
+            panic!("no positions");
+        };
+        let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute(Mesh::ATTRIBUTE_UV_0) else {
+            panic!("no uvs");
+        };
+        assert!(positions.iter().all(|position| position[2] <= 0. && position[2] >= -0.4));
+        // The outer corners sample the corners of the picture part, the top left one its smallest uv
+        let top_left = positions.iter().position(|position| *position == [-1., 0.5, -0.4]).unwrap();
+        assert_eq!(uvs[top_left], [0.25, 0.5]);
+        let bottom_right = positions.iter().position(|position| *position == [1., -0.5, -0.4]).unwrap();
+        assert_eq!(uvs[bottom_right], [0.5, 1.]);
+
+        // Faces wind counterclockwise around their normal
+        let Some(VertexAttributeValues::Float32x3(normals)) = mesh.attribute(Mesh::ATTRIBUTE_NORMAL) else {
+            panic!("no normals");
+        };
+        let indices = mesh.indices().unwrap().iter().collect::<Vec<usize>>();
+        for triangle in indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|corner| Vec3::from_array(positions[triangle[corner]]));
+            assert!((b - a).cross(c - a).dot(Vec3::from_array(normals[triangle[0]])) > 0.);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+
+use crate::{
+    auto_solve::AutoSolvePlayback,
+    marker::Markers,
+    playback,
+    replay::ReplayPlayback,
+    saved_game::GameRestored,
+    taquin::{ArrangeTiles, MoveCount, Taquin, TaquinShuffled, TileMoved},
+    tile::{TileCoordinates, TileIndex, TileValue},
+    AppState,
+};
+
+const BACK_KEY: KeyCode = KeyCode::F8;
+const FORWARD_KEY: KeyCode = KeyCode::F9;
+/// Back to the latest move
+const PRESENT_KEY: KeyCode = KeyCode::F10;
+/// Snapshots kept, the oldest ones go first
+const MAX_SNAPSHOTS: usize = 5000;
+
+pub struct TimeTravelPlugin;
+
+impl Plugin for TimeTravelPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<TimeTravel>()
+            .add_systems(Startup, setup_time_travel_label)
+            // The board has taken the move by then, whichever system slid the tile
+            .add_systems(PostUpdate, (
+                reset_time_travel.run_if(on_event::<TaquinShuffled>().or_else(on_event::<GameRestored>()).or_else(on_event::<ArrangeTiles>())),
+                record_snapshot.run_if(on_event::<TileMoved>()),
+            ).chain().run_if(in_state(AppState::Running)))
+            .add_systems(Update, (
+                travel_in_time,
+                update_time_travel_label.run_if(resource_changed::<TimeTravel>()),
+            ).chain().run_if(in_state(AppState::Running)
+                .and_then(not(resource_exists::<ReplayPlayback>()))
+                .and_then(not(resource_exists::<AutoSolvePlayback>()))));
+    }
+}
+
+/// The puzzle state after a move, what the tiles are rebuilt from
+#[derive(Debug, Clone, PartialEq)]
+struct BoardSnapshot {
+    tiles: Vec<Vec<TileValue>>,
+    moves: usize,
+}
+
+/// Every board of the game since the shuffle, F8 and F9 step through them and F10 comes back to the latest.
+/// Playing a move from the past drops the moves that came after it
+#[derive(Resource, Debug, Default)]
+struct TimeTravel {
+    snapshots: Vec<BoardSnapshot>,
+    /// Snapshot on the board, the last one but while travelling
+    cursor: usize,
+}
+
+impl TimeTravel {
+    fn reset(&mut self, snapshot: BoardSnapshot) {
+        self.snapshots = vec![snapshot];
+        self.cursor = 0;
+    }
+
+    fn record(&mut self, snapshot: BoardSnapshot) {
+        self.snapshots.truncate(self.cursor + 1);
+        if self.snapshots.len() == MAX_SNAPSHOTS {
+            self.snapshots.remove(0);
+        }
+        self.snapshots.push(snapshot);
+        self.cursor = self.snapshots.len() - 1;
+    }
+
+    /// Snapshot `offset` away from the cursor, which moves onto it
+    fn travel(&mut self, offset: isize) -> Option<&BoardSnapshot> {
+        let cursor = self.cursor.checked_add_signed(offset).filter(|cursor| *cursor < self.snapshots.len())?;
+        self.cursor = cursor;
+        self.snapshots.get(cursor)
+    }
+
+    fn is_travelling(&self) -> bool {
+        self.cursor + 1 < self.snapshots.len()
+    }
+}
+
+#[derive(Component)]
+struct TimeTravelLabel;
+
+fn snapshot(taquin: &Taquin, move_count: &MoveCount) -> BoardSnapshot {
+    BoardSnapshot { tiles: taquin.tiles.clone(), moves: move_count.0 }
+}
+
+fn reset_time_travel(
+    mut time_travel: ResMut<TimeTravel>,
+    taquin: Res<Taquin>,
+    move_count: Res<MoveCount>,
+) {
+    time_travel.reset(snapshot(&taquin, &move_count));
+}
+
+fn record_snapshot(
+    mut time_travel: ResMut<TimeTravel>,
+    taquin: Res<Taquin>,
+    move_count: Res<MoveCount>,
+) {
+    time_travel.record(snapshot(&taquin, &move_count));
+}
+
+/// The board model is set back first, the tiles are then put on its cells as a seek of a replay does
+#[allow(clippy::too_many_arguments)]
+fn travel_in_time(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut time_travel: ResMut<TimeTravel>,
+    mut taquin: ResMut<Taquin>,
+    mut move_count: ResMut<MoveCount>,
+    markers: Res<Markers>,
+    index: Res<TileIndex>,
+    mut tiles_query: Query<(&mut TileCoordinates, &mut Transform)>,
+) {
+    let offset = if keyboard_input.just_released(BACK_KEY) {
+        -1
+    } else if keyboard_input.just_released(FORWARD_KEY) {
+        1
+    } else if keyboard_input.just_released(PRESENT_KEY) {
+        time_travel.snapshots.len() as isize - 1 - time_travel.cursor as isize
+    } else {
+        return;
+    };
+    let Some(snapshot) = time_travel.travel(offset).cloned() else {
+        return;
+    };
+    info!(moves = snapshot.moves, "time travel");
+    taquin.set_tiles(snapshot.tiles);
+    move_count.0 = snapshot.moves;
+    playback::snap_tiles(&mut commands, &taquin, &markers, &index, &mut tiles_query);
+}
+
+fn setup_time_travel_label(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::YELLOW,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(40.),
+            top: Val::Px(10.),
+            ..default()
+        }),
+        TimeTravelLabel,
+    ));
+}
+
+fn update_time_travel_label(
+    time_travel: Res<TimeTravel>,
+    mut label_query: Query<&mut Text, With<TimeTravelLabel>>,
+) {
+    let value = match time_travel.snapshots.get(time_travel.cursor) {
+        Some(snapshot) if time_travel.is_travelling() => format!(
+            "time travel: move {} of {} (F8/F9, F10 back to the present)",
+            snapshot.moves,
+            time_travel.snapshots.last().map_or(0, |last| last.moves),
+        ),
+        _ => String::new(),
+    };
+    for mut text in label_query.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tile::TileValue,
+        time_travel::{BoardSnapshot, TimeTravel},
+    };
+
+    fn snapshot(moves: usize) -> BoardSnapshot {
+        BoardSnapshot { tiles: vec![vec![TileValue(moves as i16)]], moves }
+    }
+
+    #[test]
+    fn test_time_travel() {
+        let mut time_travel = TimeTravel::default();
+        time_travel.reset(snapshot(0));
+        time_travel.record(snapshot(1));
+        time_travel.record(snapshot(2));
+        assert!(!time_travel.is_travelling());
+        assert_eq!(time_travel.travel(1), None);
+
+        assert_eq!(time_travel.travel(-2), Some(&snapshot(0)));
+        assert_eq!(time_travel.travel(-1), None);
+        assert_eq!(time_travel.travel(1), Some(&snapshot(1)));
+        assert!(time_travel.is_travelling());
+
+        // A move from the past forgets the future it came from
+        time_travel.record(snapshot(2));
+        assert_eq!(time_travel.snapshots.len(), 3);
+        assert!(!time_travel.is_travelling());
+    }
+}
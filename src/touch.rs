@@ -0,0 +1,136 @@
+use bevy::{input::InputSystem, prelude::*, ui::UiSystem, window::PrimaryWindow};
+
+use crate::{
+    auto_solve::AutoSolvePlayback,
+    graphics::MainCamera,
+    i18n::Localized,
+    marker::Markers,
+    picking::{pick_tile, screen_ray},
+    replay::ReplayPlayback,
+    taquin::{SlideSelectedTile, Taquin, TaquinAction},
+    tile::{Direction, TileSelected},
+    tile_material::TileShading,
+    AppState,
+};
+
+/// Logical pixels a finger travels before a touch counts as a swipe rather than a tap
+const SWIPE_DISTANCE: f32 = 40.;
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Startup, setup_shuffle_button)
+            .add_systems(PreUpdate, ((read_swipes, tap_tiles).after(InputSystem), press_shuffle_button.after(UiSystem::Focus)).run_if(in_state(AppState::Running)
+                .and_then(not(resource_exists::<ReplayPlayback>()))
+                .and_then(not(resource_exists::<AutoSolvePlayback>()))));
+    }
+}
+
+/// Shuffles as R does, for the screens without a keyboard
+#[derive(Component)]
+struct ShuffleButton;
+
+/// Side of a swipe, along its main axis, none for a tap. Window coordinates go down
+fn swipe_direction(distance: Vec2) -> Option<Direction> {
+    if distance.length() < SWIPE_DISTANCE {
+        return None;
+    }
+    Some(match (distance.x.abs() > distance.y.abs(), distance.x > 0., distance.y > 0.) {
+        (true, true, _) => Direction::Right,
+        (true, false, _) => Direction::Left,
+        (false, _, true) => Direction::Down,
+        (false, _, false) => Direction::Up,
+    })
+}
+
+fn setup_shuffle_button(
+    mut commands: Commands,
+) {
+    commands.spawn((ButtonBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.),
+            bottom: Val::Percent(10.),
+            padding: UiRect::axes(Val::Px(12.), Val::Px(6.)),
+            ..default()
+        },
+        background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+        ..default()
+    }, ShuffleButton)).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "Shuffle",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), Localized::new("Shuffle")));
+    });
+}
+
+/// A swipe anywhere pushes the tile next to the empty slot that way
+fn read_swipes(
+    touches: Res<Touches>,
+    mut action_events: EventWriter<TaquinAction>,
+) {
+    for direction in touches.iter_just_released().filter_map(|touch| swipe_direction(touch.distance())) {
+        action_events.send(TaquinAction::Push(direction));
+    }
+}
+
+/// A tap selects the tile under the finger and slides it
+#[allow(clippy::too_many_arguments)]
+fn tap_tiles(
+    mut commands: Commands,
+    touches: Res<Touches>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    // The empty tile has no shading, the tiles of a mega board have no material of their own
+    tiles_query: Query<(Entity, &Transform), With<TileShading>>,
+    selected_query: Query<Entity, With<TileSelected>>,
+    markers: Res<Markers>,
+    taquin: Res<Taquin>,
+    mut slide_events: EventWriter<SlideSelectedTile>,
+) {
+    let (Ok(window), Ok((camera, camera_transform))) = (window_query.get_single(), camera_query.get_single()) else {
+        return;
+    };
+    for touch in touches.iter_just_released().filter(|touch| swipe_direction(touch.distance()).is_none()) {
+        let Some(tile) = screen_ray(window, camera, camera_transform, touch.position())
+            .and_then(|ray| pick_tile(ray, markers.rotation(), markers.tile_size(taquin.width, taquin.height), tiles_query.iter().map(|(entity, transform)| (entity, transform.translation)))) else {
+            continue;
+        };
+        for entity in selected_query.iter().filter(|entity| *entity != tile) {
+            commands.entity(entity).remove::<TileSelected>();
+        }
+        commands.entity(tile).insert(TileSelected);
+        // The selection is in place by the time the slide is read in `Update`
+        slide_events.send_default();
+    }
+}
+
+fn press_shuffle_button(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<ShuffleButton>)>,
+    mut action_events: EventWriter<TaquinAction>,
+) {
+    if button_query.iter().filter(|interaction| **interaction == Interaction::Pressed).count() > 0 {
+        action_events.send(TaquinAction::Shuffle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::{tile::Direction, touch::swipe_direction};
+
+    #[test]
+    fn test_swipe_direction() {
+        assert_eq!(swipe_direction(Vec2::new(10., -5.)), None);
+        assert_eq!(swipe_direction(Vec2::new(120., 30.)), Some(Direction::Right));
+        assert_eq!(swipe_direction(Vec2::new(-20., -90.)), Some(Direction::Up));
+        assert_eq!(swipe_direction(Vec2::new(5., 60.)), Some(Direction::Down));
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use rand::{seq::{IteratorRandom, SliceRandom}, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    solver::Board,
+    stats::GameRecorded,
+    taquin::{is_solver_board, ArrangeTiles, Taquin},
+    tile::{TileCoordinates, TileValue},
+    AppState,
+};
+
+const SCREEN_KEY: KeyCode = KeyCode::J;
+/// Moves of the empty slot scrambling the area a drill works on
+const WALK_MOVES: usize = 200;
+
+pub struct TrainerPlugin;
+
+impl Plugin for TrainerPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<TrainerProgress>()
+            .init_resource::<DrillSession>()
+            .add_systems(Update, (
+                (toggle_trainer_screen, navigate_trainer_screen.run_if(resource_exists::<TrainerScreen>())).chain().run_if(is_solver_board),
+                finish_drill.run_if(on_event::<GameRecorded>()),
+                update_trainer_screen.run_if(resource_exists::<TrainerScreen>()
+                    .and_then(resource_changed::<DrillSession>().or_else(resource_exists_and_changed::<TrainerScreen>()))),
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// A sub-skill of speedsolving, drilled on positions where only it is left to do
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Drill {
+    /// Every row but the last two is solved
+    LastTwoRows,
+    /// Three tiles swapped around, the rest solved
+    ThreeCycle,
+    /// The first row is solved but its last tile, the corner
+    CornerPlacement,
+}
+
+impl Drill {
+    const ALL: [Drill; 3] = [Drill::LastTwoRows, Drill::ThreeCycle, Drill::CornerPlacement];
+
+    fn name(self) -> &'static str {
+        match self {
+            Drill::LastTwoRows => "last two rows",
+            Drill::ThreeCycle => "3-cycle",
+            Drill::CornerPlacement => "corner placement",
+        }
+    }
+
+    /// Random position isolating the skill, never solved
+    pub fn generate(self, size: usize, rng: &mut impl Rng) -> Board {
+        loop {
+            let board = match self {
+                Drill::LastTwoRows => random_walk(size, |index| index / size + 2 >= size, rng),
+                Drill::CornerPlacement => random_walk(size, |index| index >= size - 1, rng),
+                Drill::ThreeCycle => three_cycle(size, rng),
+            };
+            if !board.is_solved() {
+                return board;
+            }
+        }
+    }
+}
+
+/// Moves the empty slot at random over the cells `allowed` keeps, the others stay solved
+fn random_walk(size: usize, allowed: impl Fn(usize) -> bool, rng: &mut impl Rng) -> Board {
+    let mut board = Board::solved(size);
+    let mut empty = size * size - 1;
+    let mut previous = empty;
+    for _ in 0..WALK_MOVES {
+        let (i, j) = (empty % size, empty / size);
+        let next = [
+            (j > 0).then(|| empty - size),
+            (i + 1 < size).then(|| empty + 1),
+            (j + 1 < size).then(|| empty + size),
+            (i > 0).then(|| empty - 1),
+        ].into_iter().flatten()
+            // Going straight back would undo the last move
+            .filter(|index| allowed(*index) && *index != previous)
+            .choose(rng);
+        let Some(next) = next else {
+            continue;
+        };
+        board.slide_tile(TileCoordinates::new((next % size) as i8, (next / size) as i8));
+        (previous, empty) = (empty, next);
+    }
+    board
+}
+
+/// A 3-cycle is an even permutation, the board stays solvable with the empty slot in place
+fn three_cycle(size: usize, rng: &mut impl Rng) -> Board {
+    let mut tiles = (1..=(size * size) as u8).collect::<Vec<u8>>();
+    let mut cells = (0..size * size - 1).collect::<Vec<usize>>();
+    cells.shuffle(rng);
+    let (a, b, c) = (cells[0], cells[1], cells[2]);
+    (tiles[a], tiles[b], tiles[c]) = (tiles[c], tiles[a], tiles[b]);
+    Board::new(size, tiles)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct DrillStats {
+    pub attempts: usize,
+    /// Seconds
+    pub best_time: Option<f32>,
+    pub total_time: f32,
+    pub best_moves: Option<usize>,
+}
+
+impl DrillStats {
+    fn record(&mut self, time: f32, moves: usize) {
+        self.attempts += 1;
+        self.total_time += time;
+        self.best_time = Some(self.best_time.map_or(time, |best| best.min(time)));
+        self.best_moves = Some(self.best_moves.map_or(moves, |best| best.min(moves)));
+    }
+
+    fn average_time(&self) -> Option<f32> {
+        (self.attempts > 0).then(|| self.total_time / self.attempts as f32)
+    }
+}
+
+/// Drills solved so far, kept between sessions
+#[derive(Resource, Serialize, Deserialize, Debug)]
+pub struct TrainerProgress {
+    pub version: u32,
+    pub drills: BTreeMap<Drill, DrillStats>,
+}
+
+impl Versioned for TrainerProgress {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl FromWorld for TrainerProgress {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().trainer();
+        let empty = TrainerProgress { version: Self::VERSION, drills: BTreeMap::new() };
+        if !persistence::exists(&path) {
+            return empty;
+        }
+        persistence::load_versioned(path, Format::Ron).unwrap_or_else(|error| {
+            error!("cannot load trainer progress: {error}");
+            empty
+        })
+    }
+}
+
+/// Lists the drills, `Tab` moves the highlight and `Return` deals a position for the highlighted one
+#[derive(Resource)]
+struct TrainerScreen {
+    entity: Entity,
+    highlighted: usize,
+}
+
+#[derive(Resource, Default)]
+struct DrillSession {
+    /// Drill on the board, timed until it is solved
+    active: Option<Drill>,
+    /// Time and moves of the last drill solved
+    last: Option<(Drill, f32, usize)>,
+}
+
+#[derive(Component)]
+struct TrainerText;
+
+fn toggle_trainer_screen(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    screen: Option<Res<TrainerScreen>>,
+) {
+    if !keyboard_input.just_released(SCREEN_KEY) {
+        return;
+    }
+    if let Some(screen) = screen {
+        commands.entity(screen.entity).despawn_recursive();
+        commands.remove_resource::<TrainerScreen>();
+        return;
+    }
+    let entity = commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.),
+            top: Val::Px(120.),
+            padding: UiRect::all(Val::Px(10.)),
+            ..default()
+        },
+        background_color: Color::rgba(0., 0., 0., 0.7).into(),
+        z_index: ZIndex::Global(5),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ), TrainerText));
+    }).id();
+    commands.insert_resource(TrainerScreen { entity, highlighted: 0 });
+}
+
+fn navigate_trainer_screen(
+    keyboard_input: Res<Input<KeyCode>>,
+    taquin: Res<Taquin>,
+    mut screen: ResMut<TrainerScreen>,
+    mut session: ResMut<DrillSession>,
+    mut arrange_events: EventWriter<ArrangeTiles>,
+) {
+    if keyboard_input.just_released(KeyCode::Tab) {
+        screen.highlighted = (screen.highlighted + 1) % Drill::ALL.len();
+    }
+    if !keyboard_input.just_released(KeyCode::Return) {
+        return;
+    }
+    let drill = Drill::ALL[screen.highlighted];
+    let board = drill.generate(taquin.width as usize, &mut rand::thread_rng());
+    info!(drill = drill.name(), "drill started");
+    arrange_events.send(ArrangeTiles(board.tiles().iter().map(|value| TileValue(*value as i16)).collect()));
+    session.active = Some(drill);
+}
+
+/// The game recorded by the stats gives the time and moves of the drill
+fn finish_drill(
+    mut recorded_events: EventReader<GameRecorded>,
+    mut session: ResMut<DrillSession>,
+    mut progress: ResMut<TrainerProgress>,
+    paths: Res<DataPaths>,
+) {
+    let Some(GameRecorded(game)) = recorded_events.read().last() else {
+        return;
+    };
+    let Some(drill) = session.active.take() else {
+        return;
+    };
+    info!(drill = drill.name(), time = game.time, moves = game.moves, "drill solved");
+    progress.drills.entry(drill).or_default().record(game.time, game.score());
+    session.last = Some((drill, game.time, game.score()));
+    if let Err(error) = persistence::save_versioned(paths.trainer(), &*progress, Format::Ron) {
+        error!("cannot save trainer progress: {error}");
+    }
+}
+
+fn update_trainer_screen(
+    screen: Res<TrainerScreen>,
+    session: Res<DrillSession>,
+    progress: Res<TrainerProgress>,
+    mut text_query: Query<&mut Text, With<TrainerText>>,
+) {
+    let mut lines = Drill::ALL.iter().enumerate().map(|(index, drill)| {
+        let cursor = if index == screen.highlighted { ">" } else { " " };
+        let stats = progress.drills.get(drill).cloned().unwrap_or_default();
+        let best = stats.best_time.map_or("-".to_string(), |time| format!("{time:.1}s"));
+        let average = stats.average_time().map_or("-".to_string(), |time| format!("{time:.1}s"));
+        let moves = stats.best_moves.map_or("-".to_string(), |moves| moves.to_string());
+        format!("{cursor} {}: {} done, best {best}, average {average}, fewest moves {moves}", drill.name(), stats.attempts)
+    }).collect::<Vec<String>>();
+    if let Some(drill) = session.active {
+        lines.push(format!("Solving {}...", drill.name()));
+    } else if let Some((drill, time, moves)) = session.last {
+        lines.push(format!("Last {}: {time:.1}s in {moves} moves", drill.name()));
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Trainer (Tab, Return)\n{}", lines.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::trainer::Drill;
+
+    #[test]
+    fn test_drill_positions() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for size in [3, 4, 5] {
+            let in_place = |tiles: &[u8], index: usize| tiles[index] as usize == index + 1;
+
+            let board = Drill::LastTwoRows.generate(size, &mut rng);
+            assert!(board.is_solvable() && !board.is_solved());
+            assert!((0..size * (size - 2)).all(|index| in_place(board.tiles(), index)));
+
+            let board = Drill::CornerPlacement.generate(size, &mut rng);
+            assert!(board.is_solvable() && !board.is_solved());
+            assert!((0..size - 1).all(|index| in_place(board.tiles(), index)));
+
+            let board = Drill::ThreeCycle.generate(size, &mut rng);
+            assert!(board.is_solvable());
+            assert_eq!((0..size * size).filter(|index| !in_place(board.tiles(), *index)).count(), 3);
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use std::f32::consts::{FRAC_PI_4, TAU};
+
+use bevy::prelude::*;
+
+use crate::{
+    i18n::Localized,
+    keybindings::{Binding, KeyBindings},
+    marker::Markers,
+    settings::Settings,
+    taquin::{ShuffleRequested, Taquin, TaquinShuffled, TextureToggled, TileMoved},
+    tile::{Direction, TileCoordinates, TileIndex, TileSelected},
+    AppState,
+};
+
+/// Directions a tile next to an empty slot is looked for in
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+/// Height in tiles the arrow bobs up and down
+const ARROW_BOB_HEIGHT: f32 = 0.1;
+/// Bobs of the arrow a second
+const ARROW_BOB_RATE: f32 = 1.5;
+const KEY_COLOR: Color = Color::rgba(1., 0.84, 0., 0.5);
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::Tutorial), setup_tutorial)
+            .add_systems(Update, (
+                advance_tutorial,
+                show_tutorial_step.run_if(resource_changed::<Tutorial>()),
+                highlight_tutorial_keys,
+                point_tutorial_arrow,
+            ).chain().run_if(in_state(AppState::Tutorial).and_then(resource_exists::<Tutorial>())))
+            .add_systems(OnExit(AppState::Tutorial), close_tutorial);
+    }
+}
+
+/// The first game after the intro is the tutorial until it was played through or skipped, automated runs never get it
+pub fn state_after_intro(settings: &Settings, automated: bool) -> AppState {
+    if settings.tutorial_done || automated { AppState::Running } else { AppState::Tutorial }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TutorialStep {
+    Select,
+    Slide,
+    Shuffle,
+    Texture,
+}
+
+impl TutorialStep {
+    const ALL: [TutorialStep; 4] = [TutorialStep::Select, TutorialStep::Slide, TutorialStep::Shuffle, TutorialStep::Texture];
+
+    fn instruction(self) -> &'static str {
+        match self {
+            TutorialStep::Select => "Move the selection to the tile the arrow points at",
+            TutorialStep::Slide => "Slide the selected tile into the empty slot",
+            TutorialStep::Shuffle => "Shuffle the board",
+            TutorialStep::Texture => "Show the next picture on the tiles",
+        }
+    }
+
+    fn bindings(self) -> &'static [Binding] {
+        match self {
+            TutorialStep::Select => &[Binding::Left, Binding::Right, Binding::Up, Binding::Down],
+            TutorialStep::Slide => &[Binding::Slide],
+            TutorialStep::Shuffle => &[Binding::Shuffle],
+            TutorialStep::Texture => &[Binding::Texture],
+        }
+    }
+
+    /// The classic controls push the tiles with the arrows, which moves the selection as well
+    fn is_done(self, progress: &Progress) -> bool {
+        match self {
+            TutorialStep::Select => progress.selection_moved || progress.tile_moved,
+            TutorialStep::Slide => progress.tile_moved,
+            TutorialStep::Shuffle => progress.shuffled,
+            TutorialStep::Texture => progress.texture_toggled,
+        }
+    }
+
+    fn next(self) -> Option<Self> {
+        Self::ALL.iter().skip_while(|step| **step != self).nth(1).copied()
+    }
+}
+
+/// What the player did on the board during the frame
+#[derive(Debug, Default)]
+struct Progress {
+    selection_moved: bool,
+    tile_moved: bool,
+    shuffled: bool,
+    texture_toggled: bool,
+}
+
+#[derive(Resource)]
+struct Tutorial {
+    step: TutorialStep,
+    /// Cell selected when the step started
+    selection: Option<TileCoordinates>,
+    panel: Entity,
+    arrow: Entity,
+}
+
+#[derive(Component)]
+struct TutorialText;
+
+/// Row of the keys of the step
+#[derive(Component)]
+struct TutorialKeys;
+
+#[derive(Component)]
+struct TutorialKey(KeyCode);
+
+#[derive(Component)]
+struct SkipTutorialButton;
+
+/// Points at the tile the step is about, above it
+#[derive(Component)]
+struct TutorialArrow;
+
+fn setup_tutorial(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    selected_query: Query<&TileCoordinates, With<TileSelected>>,
+) {
+    let text_style = |font_size| TextStyle { font_size, color: Color::WHITE, ..default() };
+    let panel = commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(22.),
+            width: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(12.),
+            ..default()
+        },
+        z_index: ZIndex::Global(4),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((TextBundle::from_section("Tutorial", text_style(30.)), Localized::new("Tutorial")));
+        parent.spawn((TextBundle::from_section("", text_style(40.)).with_text_alignment(TextAlignment::Center), TutorialText, Localized::new("")));
+        parent.spawn((NodeBundle {
+            style: Style {
+                column_gap: Val::Px(10.),
+                ..default()
+            },
+            ..default()
+        }, TutorialKeys));
+        parent.spawn((ButtonBundle {
+            style: Style {
+                padding: UiRect::axes(Val::Px(16.), Val::Px(6.)),
+                ..default()
+            },
+            background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+            ..default()
+        }, SkipTutorialButton)).with_children(|parent| {
+            parent.spawn((TextBundle::from_section("Skip the tutorial", text_style(24.)), Localized::new("Skip the tutorial")));
+        });
+    }).id();
+
+    let material = materials.add(StandardMaterial {
+        base_color: Color::GOLD,
+        unlit: true,
+        ..default()
+    });
+    // A stem over a diamond, the lower corner of the diamond being the tip, sized for a tile of 1
+    let arrow = commands.spawn((SpatialBundle { visibility: Visibility::Hidden, ..default() }, TutorialArrow)).with_children(|parent| {
+        parent.spawn(PbrBundle {
+            mesh: meshes.add(shape::Box::new(0.08, 0.35, 0.08).into()),
+            material: material.clone(),
+            transform: Transform::from_xyz(0., 0.3, 0.),
+            ..default()
+        });
+        parent.spawn(PbrBundle {
+            mesh: meshes.add(shape::Box::new(0.22, 0.22, 0.08).into()),
+            material,
+            transform: Transform::from_xyz(0., 0.155, 0.).with_rotation(Quat::from_rotation_z(FRAC_PI_4)),
+            ..default()
+        });
+    }).id();
+
+    commands.insert_resource(Tutorial { step: TutorialStep::Select, selection: selected_query.get_single().ok().copied(), panel, arrow });
+    info!("tutorial started");
+}
+
+/// One step a frame at most, the step done leads to the next one, the last one to the game
+fn advance_tutorial(
+    mut tutorial: ResMut<Tutorial>,
+    selected_query: Query<&TileCoordinates, With<TileSelected>>,
+    mut moved_events: EventReader<TileMoved>,
+    mut shuffled_events: EventReader<TaquinShuffled>,
+    mut toggled_events: EventReader<TextureToggled>,
+    skip_query: Query<&Interaction, (Changed<Interaction>, With<SkipTutorialButton>)>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if skip_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        info!(step = ?tutorial.step, "tutorial skipped");
+        next_state.set(AppState::Running);
+        return;
+    }
+    let selection = selected_query.get_single().ok().copied();
+    let progress = Progress {
+        selection_moved: selection != tutorial.selection,
+        tile_moved: moved_events.read().count() > 0,
+        shuffled: shuffled_events.read().count() > 0,
+        texture_toggled: toggled_events.read().count() > 0,
+    };
+    if !tutorial.step.is_done(&progress) {
+        return;
+    }
+    match tutorial.step.next() {
+        Some(step) => {
+            debug!(?step, "tutorial step");
+            tutorial.step = step;
+            tutorial.selection = selection;
+        },
+        None => {
+            info!("tutorial done");
+            next_state.set(AppState::Running);
+        },
+    }
+}
+
+fn show_tutorial_step(
+    mut commands: Commands,
+    tutorial: Res<Tutorial>,
+    bindings: Res<KeyBindings>,
+    mut text_query: Query<&mut Localized, With<TutorialText>>,
+    keys_query: Query<Entity, With<TutorialKeys>>,
+) {
+    for mut localized in text_query.iter_mut() {
+        *localized = Localized::new(tutorial.step.instruction());
+    }
+    for entity in keys_query.iter() {
+        commands.entity(entity).despawn_descendants().with_children(|parent| {
+            for binding in tutorial.step.bindings() {
+                let key = bindings.key(*binding);
+                parent.spawn((NodeBundle {
+                    style: Style {
+                        padding: UiRect::axes(Val::Px(14.), Val::Px(6.)),
+                        border: UiRect::all(Val::Px(2.)),
+                        ..default()
+                    },
+                    border_color: Color::WHITE.into(),
+                    background_color: KEY_COLOR.into(),
+                    ..default()
+                }, TutorialKey(key))).with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(format!("{key:?}"), TextStyle { font_size: 30., color: Color::WHITE, ..default() }));
+                });
+            }
+        });
+    }
+}
+
+/// The keys of the step pulse, and light up while held
+fn highlight_tutorial_keys(
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time<Real>>,
+    mut key_query: Query<(&TutorialKey, &mut BackgroundColor)>,
+) {
+    let pulse = 0.35 + 0.25 * (time.elapsed_seconds() * 4.).sin();
+    for (TutorialKey(key), mut background) in key_query.iter_mut() {
+        let alpha = if keyboard_input.pressed(*key) { 1. } else { pulse };
+        background.0 = KEY_COLOR.with_a(alpha);
+    }
+}
+
+/// A tile the selection can slide: one next to an empty slot, the selected one first when the step is the slide
+fn pointed_tile(step: TutorialStep, taquin: &Taquin, selected: Option<TileCoordinates>) -> Option<TileCoordinates> {
+    let mut next_to_empty = DIRECTIONS.into_iter().filter_map(|direction| taquin.get_tile_towards_empty(direction));
+    match step {
+        TutorialStep::Select => next_to_empty.find(|tile| Some(*tile) != selected),
+        TutorialStep::Slide => selected.filter(|tile| taquin.get_empty_next_to(tile).is_some()).or_else(|| next_to_empty.next()),
+        TutorialStep::Shuffle | TutorialStep::Texture => None,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn point_tutorial_arrow(
+    tutorial: Res<Tutorial>,
+    taquin: Res<Taquin>,
+    markers: Res<Markers>,
+    index: Res<TileIndex>,
+    time: Res<Time<Real>>,
+    selected_query: Query<&TileCoordinates, With<TileSelected>>,
+    tiles_query: Query<&Transform, (With<TileCoordinates>, Without<TutorialArrow>)>,
+    mut arrow_query: Query<(&mut Transform, &mut Visibility), With<TutorialArrow>>,
+) {
+    let Ok((mut arrow, mut visibility)) = arrow_query.get_mut(tutorial.arrow) else {
+        return;
+    };
+    let tile = pointed_tile(tutorial.step, &taquin, selected_query.get_single().ok().copied())
+        .and_then(|cell| index.at(cell))
+        .and_then(|entity| tiles_query.get(entity).ok());
+    let Some(tile) = tile else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    let size = markers.tile_size(taquin.width, taquin.height).y;
+    let bob = ARROW_BOB_HEIGHT * (time.elapsed_seconds() * ARROW_BOB_RATE * TAU).sin();
+    arrow.translation = tile.translation + Vec3::new(0., size * (0.55 + bob), 0.5);
+    arrow.scale = Vec3::splat(size);
+    *visibility = Visibility::Visible;
+}
+
+/// Played through or skipped, it is not shown again. The first game starts from a new shuffle, the ones of the
+/// tutorial are not counted
+fn close_tutorial(
+    mut commands: Commands,
+    tutorial: Option<Res<Tutorial>>,
+    mut settings: ResMut<Settings>,
+) {
+    if let Some(tutorial) = tutorial {
+        commands.entity(tutorial.panel).despawn_recursive();
+        commands.entity(tutorial.arrow).despawn_recursive();
+        commands.remove_resource::<Tutorial>();
+    }
+    commands.init_resource::<ShuffleRequested>();
+    if !settings.tutorial_done {
+        settings.tutorial_done = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        settings::Settings,
+        tutorial::{state_after_intro, Progress, TutorialStep},
+        AppState,
+    };
+
+    #[test]
+    fn test_tutorial_steps() {
+        let mut step = TutorialStep::Select;
+        assert!(!step.is_done(&Progress::default()));
+        // The classic controls slide the tile right away
+        assert!(step.is_done(&Progress { tile_moved: true, ..Progress::default() }));
+        step = step.next().unwrap();
+        assert!(!step.is_done(&Progress { selection_moved: true, ..Progress::default() }));
+        assert!(step.is_done(&Progress { tile_moved: true, ..Progress::default() }));
+        assert_eq!(step.next(), Some(TutorialStep::Shuffle));
+        assert_eq!(TutorialStep::Texture.next(), None);
+    }
+
+    #[test]
+    fn test_state_after_intro() {
+        let mut settings = Settings::default();
+        assert_eq!(state_after_intro(&settings, false), AppState::Tutorial);
+        assert_eq!(state_after_intro(&settings, true), AppState::Running);
+        settings.tutorial_done = true;
+        assert_eq!(state_after_intro(&settings, false), AppState::Running);
+    }
+}
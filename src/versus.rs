@@ -0,0 +1,538 @@
+use bevy::{input::InputSystem, prelude::*, tasks::{block_on, AsyncComputeTaskPool, Task}};
+
+use crate::{
+    auto_solve::AutoSolveOptions,
+    countdown::GameMode,
+    graphics::MainCamera,
+    locale::Locale,
+    frame::spawn_frame,
+    marker::Markers,
+    pause::GameClock,
+    solver::{self, SearchLimits, Solution, Strategy},
+    taquin::{Taquin, TaquinAction, TaquinShuffled, TaquinSolved, MAX_SOLVER_SIZE},
+    tile::{Direction, TileCoordinates, TileValue},
+    tile_mesh::{tile_mesh, TileThickness},
+    AppState, TaquinSprites,
+};
+
+/// Keys of the second player, WASD for the arrows of the first one and Return for Space
+const PLAYER_2_KEYS: [(KeyCode, TaquinAction); 5] = [
+    (KeyCode::A, TaquinAction::Direction(Direction::Left)),
+    (KeyCode::D, TaquinAction::Direction(Direction::Right)),
+    (KeyCode::W, TaquinAction::Direction(Direction::Up)),
+    (KeyCode::S, TaquinAction::Direction(Direction::Down)),
+    (KeyCode::Return, TaquinAction::Slide),
+];
+/// Distance between the centers of the two frames, relative to the inner width of a frame
+const FRAME_SPACING: f32 = 1.6;
+/// How much farther than usual the camera stands to see both frames
+const CAMERA_PULL_BACK: f32 = 1.6;
+/// Fraction of the way left a tile of the second board covers each 60th of a second
+const TILE_SMOOTHING: f32 = 0.3;
+/// The selected tile of the second board stands out of it by this much
+const SELECTED_LIFT: f32 = 0.3;
+/// Size of the board of the computer, relative to the one of the player
+const COMPUTER_BOARD_SCALE: f32 = 0.6;
+/// Positions the computer explores before falling back to the row by row solution
+const COMPUTER_MAX_NODES: u64 = 5_000_000;
+/// Width of the progress bars of the two boards
+const PROGRESS_BAR_WIDTH: f32 = 240.;
+
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ComputerLevel>()
+            .add_event::<Player2Action>()
+            .add_systems(Update, (pick_computer_level, update_computer_buttons).chain().run_if(in_state(AppState::MainMenu)))
+            .add_systems(OnEnter(AppState::Running), setup_versus.run_if(resource_equals(GameMode::Versus).or_else(resource_equals(GameMode::Computer)).and_then(not(resource_exists::<Versus>()))))
+            .add_systems(OnEnter(AppState::MainMenu), leave_versus.run_if(resource_exists::<Versus>()))
+            .add_systems(PreUpdate, read_player_2_keys.after(InputSystem).run_if(in_state(AppState::Running).and_then(resource_exists::<Versus>()).and_then(has_player_2)))
+            .add_systems(Update, (
+                start_versus_race.run_if(on_event::<TaquinShuffled>()),
+                play_player_2,
+                play_computer,
+                win_player_1.run_if(on_event::<TaquinSolved>()),
+                move_versus_tiles,
+                update_versus_hud.run_if(resource_changed::<Versus>()),
+                update_versus_progress.run_if(resource_changed::<Versus>().or_else(resource_changed::<Taquin>())),
+            ).chain().run_if(in_state(AppState::Running).and_then(resource_exists::<Versus>())));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Player {
+    One,
+    Two,
+}
+
+/// What the keys of the second player ask of their board
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+struct Player2Action(TaquinAction);
+
+/// How fast and how well the computer solves its board, picked in the main menu
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ComputerLevel {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+impl ComputerLevel {
+    const ALL: [ComputerLevel; 3] = [ComputerLevel::Easy, ComputerLevel::Medium, ComputerLevel::Hard];
+
+    fn label(self) -> &'static str {
+        match self {
+            ComputerLevel::Easy => "Easy",
+            ComputerLevel::Medium => "Medium",
+            ComputerLevel::Hard => "Hard",
+        }
+    }
+
+    /// The bot presets of the same names, see `--bot`
+    fn options(self) -> AutoSolveOptions {
+        AutoSolveOptions::preset(&self.label().to_lowercase()).unwrap_or_default()
+    }
+}
+
+/// Picks the level of the computer in the main menu
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputerButton(pub ComputerLevel);
+
+impl ComputerButton {
+    pub fn buttons() -> Vec<(String, ComputerButton)> {
+        ComputerLevel::ALL.iter().map(|level| (level.label().to_string(), ComputerButton(*level))).collect()
+    }
+}
+
+/// Opponent of `GameMode::Computer`, it searches the solution of the second board then plays it at the pace of its level
+struct Computer {
+    level: ComputerLevel,
+    task: Option<Task<Option<Solution>>>,
+    solution: Vec<TileCoordinates>,
+}
+
+impl Computer {
+    fn new(level: ComputerLevel) -> Self {
+        Self { level, task: None, solution: Vec::new() }
+    }
+
+    /// Moves of the solution played `elapsed` seconds after the shuffle, the first one once the start delay is over
+    fn moves_due(&self, elapsed: f32) -> usize {
+        let options = self.level.options();
+        if elapsed < options.start_delay {
+            return 0;
+        }
+        (((elapsed - options.start_delay) * options.moves_per_second) as usize + 1).min(self.solution.len())
+    }
+}
+
+/// Board of the second player, beside the first one and shuffled along it with the same tiles
+#[derive(Resource)]
+struct Versus {
+    board: Taquin,
+    selected: TileValue,
+    moves: usize,
+    /// From the shuffle, none before the first one
+    started_at: Option<f32>,
+    /// First to solve the board, and the seconds it took
+    winner: Option<(Player, f32)>,
+    /// From the first frame to the second one
+    offset: Vec3,
+    /// Of the second board, relative to the first one
+    scale: f32,
+    /// Plays the second board in place of a second player
+    computer: Option<Computer>,
+}
+
+impl Versus {
+    fn coordinates_of(&self, value: TileValue) -> TileCoordinates {
+        let index = self.board.tiles.iter().flatten().position(|tile| *tile == value).unwrap_or(0);
+        TileCoordinates::new((index % self.board.width as usize) as i8, (index / self.board.width as usize) as i8)
+    }
+
+    fn opponent_name(&self) -> String {
+        opponent_name(self.computer.as_ref())
+    }
+}
+
+/// Spawned for the versus mode, despawned when it ends
+#[derive(Component)]
+struct VersusEntity;
+
+#[derive(Component)]
+struct VersusTile(TileValue);
+
+#[derive(Component)]
+struct VersusText;
+
+/// Filled with the share of the tiles in place on the board of the player
+#[derive(Component)]
+struct VersusProgress(Player);
+
+fn opponent_name(computer: Option<&Computer>) -> String {
+    computer.map_or_else(|| "Player 2".to_string(), |computer| format!("Computer ({})", computer.level.label().to_lowercase()))
+}
+
+/// The board in the solved order, its tiles are the ones of the main menu picture
+fn solved_tiles(width: i8, height: i8) -> Vec<Vec<TileValue>> {
+    (0..height).map(|j| (0..width).map(|i| TileValue::for_cell(TileCoordinates::new(i, j), width)).collect()).collect()
+}
+
+/// Translation of a cell of the second board, scaled around the center of its frame
+fn versus_translation(markers: &Markers, board: &Taquin, (offset, scale): (Vec3, f32), coordinates: TileCoordinates) -> Vec3 {
+    let center = markers.center();
+    center + offset + (markers.tile_translation(board.width, board.height, coordinates) - center) * scale
+}
+
+/// Second frame on the right of the first one, smaller for the computer, the camera pulled back to show both
+#[allow(clippy::too_many_arguments)]
+fn setup_versus(
+    mut commands: Commands,
+    sprites: Res<TaquinSprites>,
+    taquin: Res<Taquin>,
+    markers: Res<Markers>,
+    thickness: Res<TileThickness>,
+    mode: Res<GameMode>,
+    level: Res<ComputerLevel>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    let computer = (*mode == GameMode::Computer).then(|| Computer::new(*level));
+    let scale = if computer.is_some() { COMPUTER_BOARD_SCALE } else { 1. };
+    // The frames keep the same gap whatever the size of the second one
+    let offset = Vec3::X * markers.inner_width() * (FRAME_SPACING - (1. - scale) / 2.);
+    let frame = spawn_frame(&mut commands, &mut meshes, &mut materials, &markers, offset);
+    commands.entity(frame).insert((VersusEntity, Transform::from_translation(Vec3::new(markers.center().x, markers.center().y, 0.) + offset).with_scale(Vec3::splat(scale))));
+
+    let mut board = Taquin::rect(taquin.width, taquin.height);
+    board.topology = taquin.topology;
+    board.set_tiles(solved_tiles(taquin.width, taquin.height));
+    let tile_size = markers.tile_size(taquin.width, taquin.height);
+    let (u_ratio, v_ratio) = (1. / taquin.width as f32, 1. / taquin.height as f32);
+    let material = materials.add(StandardMaterial {
+        base_color_texture: sprites.images.get(sprites.selected).or(sprites.images.first()).cloned(),
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    });
+    for value in (1..taquin.tiles_nb as i16).map(TileValue) {
+        // Cut from the picture as the tiles of the first board are
+        let goal = value.goal(taquin.width);
+        let (u, v) = (goal.i, goal.j);
+        let uv = Rect::new(u as f32 * u_ratio, v as f32 * v_ratio, (u + 1) as f32 * u_ratio, (v + 1) as f32 * v_ratio);
+        let translation = versus_translation(&markers, &board, (offset, scale), goal);
+        commands.spawn((PbrBundle {
+            mesh: meshes.add(tile_mesh(tile_size, thickness.0, uv)),
+            material: material.clone(),
+            transform: Transform::from_translation(translation).with_scale(Vec3::splat(scale)),
+            ..default()
+        }, VersusTile(value), VersusEntity));
+    }
+
+    commands.spawn((TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: 40.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    ).with_text_alignment(TextAlignment::Center).with_style(Style {
+        position_type: PositionType::Absolute,
+        width: Val::Percent(100.),
+        top: Val::Px(110.),
+        justify_content: JustifyContent::Center,
+        ..default()
+    }), VersusText, VersusEntity));
+
+    let text_style = TextStyle {
+        font_size: 24.0,
+        color: Color::WHITE,
+        ..default()
+    };
+    commands.spawn((NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            top: Val::Px(160.),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(6.),
+            ..default()
+        },
+        ..default()
+    }, VersusEntity)).with_children(|parent| {
+        for (player, name) in [(Player::One, "Player 1".to_string()), (Player::Two, opponent_name(computer.as_ref()))] {
+            parent.spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(10.),
+                    ..default()
+                },
+                ..default()
+            }).with_children(|parent| {
+                parent.spawn(TextBundle::from_section(name, text_style.clone()).with_style(Style {
+                    width: Val::Px(200.),
+                    ..default()
+                }));
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(PROGRESS_BAR_WIDTH),
+                        height: Val::Px(14.),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.65, 0.65, 0.65, 0.4).into(),
+                    ..default()
+                }).with_children(|parent| {
+                    parent.spawn((NodeBundle {
+                        style: Style {
+                            width: Val::Percent(0.),
+                            height: Val::Percent(100.),
+                            ..default()
+                        },
+                        background_color: Color::rgb(0.3, 0.8, 0.4).into(),
+                        ..default()
+                    }, VersusProgress(player)));
+                });
+            });
+        }
+    });
+
+    let center = markers.center() + offset / 2.;
+    for mut transform in camera_query.iter_mut() {
+        *transform = Transform::from_translation(center + Vec3::new(0., 29., 40.) * CAMERA_PULL_BACK).looking_at(center, Vec3::Y);
+    }
+    info!(width = taquin.width, height = taquin.height, computer = ?computer.as_ref().map(|computer| computer.level), "versus started");
+    commands.insert_resource(Versus { board, selected: TileValue(1), moves: 0, started_at: None, winner: None, offset, scale, computer });
+}
+
+fn has_player_2(
+    versus: Res<Versus>,
+) -> bool {
+    versus.computer.is_none()
+}
+
+fn pick_computer_level(
+    button_query: Query<(&Interaction, &ComputerButton), Changed<Interaction>>,
+    mut level: ResMut<ComputerLevel>,
+) {
+    if let Some((_, ComputerButton(picked))) = button_query.iter().find(|(interaction, _)| **interaction == Interaction::Pressed) {
+        *level = *picked;
+    }
+}
+
+fn update_computer_buttons(
+    level: Res<ComputerLevel>,
+    mut button_query: Query<(&ComputerButton, &mut BackgroundColor, Ref<ComputerButton>)>,
+) {
+    for (ComputerButton(button_level), mut background, button) in button_query.iter_mut() {
+        if !level.is_changed() && !button.is_added() {
+            continue;
+        }
+        background.0 = if *button_level == *level { Color::rgba(0.65, 0.65, 0.65, 0.9) } else { Color::rgba(0.65, 0.65, 0.65, 0.4) };
+    }
+}
+
+fn leave_versus(
+    mut commands: Commands,
+    versus_query: Query<Entity, With<VersusEntity>>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    for entity in versus_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for mut transform in camera_query.iter_mut() {
+        *transform = Transform::from_xyz(0.0, 30., 40.0).looking_at(Vec3::new(0., 1., 0.), Vec3::Y);
+    }
+    commands.remove_resource::<Versus>();
+}
+
+/// Consumes the keys of the second player, so that W, A, S and D do not reach their own shortcuts meanwhile
+fn read_player_2_keys(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut action_events: EventWriter<Player2Action>,
+) {
+    for (key_code, action) in PLAYER_2_KEYS {
+        if keyboard_input.clear_just_released(key_code) {
+            action_events.send(Player2Action(action));
+        }
+    }
+}
+
+/// Both boards start from the same tiles, whether they were drawn from a seed or walked to.
+/// The computer searches its solution meanwhile, its clock runs from the shuffle all the same
+fn start_versus_race(
+    mut versus: ResMut<Versus>,
+    taquin: Res<Taquin>,
+    clock: Res<GameClock>,
+) {
+    versus.board.set_tiles(taquin.tiles.clone());
+    versus.selected = TileValue(1);
+    versus.moves = 0;
+    versus.started_at = Some(clock.elapsed_seconds());
+    versus.winner = None;
+    let board = versus.board.board();
+    let solvable = taquin.is_square() && taquin.width <= MAX_SOLVER_SIZE && taquin.empty_count() == 1;
+    let Some(computer) = versus.computer.as_mut() else {
+        return;
+    };
+    computer.solution.clear();
+    computer.task = None;
+    if !solvable {
+        warn!(width = taquin.width, height = taquin.height, "the computer only solves square boards with a single empty slot");
+        return;
+    }
+    let AutoSolveOptions { strategy, extra_moves, .. } = computer.level.options();
+    computer.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+        let limits = SearchLimits::new(COMPUTER_MAX_NODES);
+        solver::solve_with_extra_moves(&board, strategy, extra_moves, limits, &mut rand::thread_rng())
+            .or_else(|| solver::solve_with_extra_moves(&board, Strategy::HumanLike, extra_moves, limits, &mut rand::thread_rng()))
+    }));
+}
+
+/// The second board plays as the first one with the selection controls, until someone wins
+fn play_player_2(
+    mut versus: ResMut<Versus>,
+    mut action_events: EventReader<Player2Action>,
+    clock: Res<GameClock>,
+) {
+    for Player2Action(action) in action_events.read() {
+        let Some(started_at) = versus.started_at.filter(|_| versus.winner.is_none()) else {
+            continue;
+        };
+        let selected = versus.coordinates_of(versus.selected);
+        match action {
+            TaquinAction::Direction(direction) => {
+                let next = versus.board.get_next_selection_coordinates(&selected, *direction);
+                versus.selected = versus.board.tiles[next.j as usize][next.i as usize];
+            },
+            TaquinAction::Slide if versus.board.slide(selected) => {
+                versus.moves += 1;
+                if versus.board.is_solved() {
+                    let seconds = clock.elapsed_seconds() - started_at;
+                    info!(seconds, moves = versus.moves, "versus won by player 2");
+                    versus.winner = Some((Player::Two, seconds));
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+/// Plays the moves of the solution due by now, the selection follows the tile moved
+fn play_computer(
+    mut versus: ResMut<Versus>,
+    clock: Res<GameClock>,
+) {
+    let Some(started_at) = versus.started_at.filter(|_| versus.winner.is_none() && versus.computer.is_some()) else {
+        return;
+    };
+    let Versus { board, selected, moves, winner, computer: Some(computer), .. } = &mut *versus else {
+        return;
+    };
+    if computer.task.as_ref().is_some_and(|task| task.is_finished()) {
+        computer.solution = computer.task.take().and_then(block_on).map_or_else(Vec::new, |solution| solution.moves);
+        info!(moves = computer.solution.len(), level = ?computer.level, "computer solution found");
+    }
+    let seconds = clock.elapsed_seconds() - started_at;
+    while *moves < computer.moves_due(seconds) {
+        let tile = computer.solution[*moves];
+        *selected = board.tiles[tile.j as usize][tile.i as usize];
+        board.slide(tile);
+        *moves += 1;
+    }
+    if *moves > 0 && board.is_solved() {
+        info!(seconds, moves = *moves, "versus won by the computer");
+        *winner = Some((Player::Two, seconds));
+    }
+}
+
+fn win_player_1(
+    mut versus: ResMut<Versus>,
+    clock: Res<GameClock>,
+) {
+    let Some(started_at) = versus.started_at.filter(|_| versus.winner.is_none()) else {
+        return;
+    };
+    let seconds = clock.elapsed_seconds() - started_at;
+    info!(seconds, "versus won by player 1");
+    versus.winner = Some((Player::One, seconds));
+}
+
+/// Tiles of the second board glide to their cell, the selected one raised
+fn move_versus_tiles(
+    versus: Res<Versus>,
+    markers: Res<Markers>,
+    time: Res<Time>,
+    mut tiles_query: Query<(&VersusTile, &mut Transform)>,
+) {
+    let smoothing = 1. - (1. - TILE_SMOOTHING).powf(time.delta_seconds() * 60.);
+    for (VersusTile(value), mut transform) in tiles_query.iter_mut() {
+        let lift = if *value == versus.selected { SELECTED_LIFT * versus.scale } else { 0. };
+        let target = versus_translation(&markers, &versus.board, (versus.offset, versus.scale), versus.coordinates_of(*value)) + Vec3::Z * lift;
+        if transform.translation != target {
+            transform.translation = transform.translation.lerp(target, smoothing);
+        }
+    }
+}
+
+fn update_versus_hud(
+    versus: Res<Versus>,
+    locale: Res<Locale>,
+    mut text_query: Query<&mut Text, With<VersusText>>,
+) {
+    let value = match versus.winner {
+        Some((Player::One, seconds)) => format!("Player 1 wins in {}!", locale.format_duration(seconds)),
+        Some((Player::Two, seconds)) => format!("{} wins in {}!", versus.opponent_name(), locale.format_duration(seconds)),
+        None if versus.started_at.is_some() => format!("{}: {} moves", versus.opponent_name(), versus.moves),
+        None if versus.computer.is_some() => "Shuffle to race the computer".to_string(),
+        None => "Player 1: arrows and Space, player 2: WASD and Return".to_string(),
+    };
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+fn update_versus_progress(
+    versus: Res<Versus>,
+    taquin: Res<Taquin>,
+    mut progress_query: Query<(&VersusProgress, &mut Style)>,
+) {
+    for (VersusProgress(player), mut style) in progress_query.iter_mut() {
+        let board = match player {
+            Player::One => &*taquin,
+            Player::Two => &versus.board,
+        };
+        let width = Val::Percent(100. * board.tiles_in_place() as f32 / board.tiles_nb.max(1) as f32);
+        if style.width != width {
+            style.width = width;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{taquin::Taquin, tile::{TileCoordinates, TileValue}, versus::{solved_tiles, Computer, ComputerLevel}};
+
+    #[test]
+    fn test_solved_tiles() {
+        let mut taquin = Taquin::rect(3, 2);
+        taquin.set_tiles(solved_tiles(3, 2));
+        assert!(taquin.is_solved());
+        assert_eq!(taquin.tiles[1], vec![TileValue(4), TileValue(5), TileValue(6)]);
+    }
+
+    #[test]
+    fn test_computer_moves_due() {
+        let mut computer = Computer::new(ComputerLevel::Medium);
+        computer.solution = vec![TileCoordinates::new(0, 0); 5];
+        let options = ComputerLevel::Medium.options();
+        assert_eq!(computer.moves_due(options.start_delay - 0.1), 0);
+        assert_eq!(computer.moves_due(options.start_delay), 1);
+        assert_eq!(computer.moves_due(options.start_delay + 1. / options.moves_per_second), 2);
+        // Never more than the solution
+        assert_eq!(computer.moves_due(options.start_delay + 100.), 5);
+    }
+}
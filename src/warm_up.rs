@@ -0,0 +1,123 @@
+use bevy::{
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages},
+        view::RenderLayers,
+    },
+};
+
+use crate::{i18n::Localized, AppState};
+
+/// Frames rendered offscreen before the game starts, pipelines are compiled during the first ones
+const WARM_UP_FRAMES: u32 = 5;
+const TARGET_SIZE: u32 = 256;
+
+pub struct WarmUpPlugin;
+
+impl Plugin for WarmUpPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::WarmUp), setup_warm_up)
+            .add_systems(Update, finish_warm_up.run_if(in_state(AppState::WarmUp)))
+            .add_systems(OnExit(AppState::WarmUp), despawn_warm_up);
+    }
+}
+
+/// Everything spawned for the warm-up, despawned once it is over
+#[derive(Component)]
+struct WarmUp;
+
+/// Render target in the swapchain format so the pipelines compiled are the ones the window uses
+fn warm_up_target() -> Image {
+    let size = Extent3d { width: TARGET_SIZE, height: TARGET_SIZE, depth_or_array_layers: 1 };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("warm_up_target"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+/// Renders the board once more offscreen, along with samples of the materials that only show up
+/// later in the game (tile labels), so their shaders are compiled before the first slide
+fn setup_warm_up(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    camera_query: Query<&Transform, With<Camera3d>>,
+) {
+    let samples = RenderLayers::layer(1);
+    let transform = camera_query.iter().next().copied().unwrap_or_default();
+    commands.spawn((Camera3dBundle {
+        camera: Camera {
+            order: -1,
+            target: RenderTarget::Image(images.add(warm_up_target())),
+            ..default()
+        },
+        transform,
+        ..default()
+    }, RenderLayers::all(), WarmUp));
+
+    let label_material = materials.add(StandardMaterial {
+        base_color: Color::rgba(1., 1., 1., 0.5),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    commands.spawn((PbrBundle {
+        mesh: meshes.add(shape::Quad::new(Vec2::ONE).into()),
+        material: label_material,
+        transform: Transform::from_xyz(0., 1., 0.),
+        ..default()
+    }, samples, WarmUp));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "compiling shaders…",
+            TextStyle {
+                font_size: 40.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.),
+            bottom: Val::Px(10.),
+            ..default()
+        }),
+        Localized::new("compiling shaders…"),
+        WarmUp,
+    ));
+}
+
+fn finish_warm_up(
+    mut frames: Local<u32>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    *frames += 1;
+    if *frames >= WARM_UP_FRAMES {
+        info!(frames = *frames, "pipelines warmed up");
+        next_state.set(AppState::Intro);
+    }
+}
+
+fn despawn_warm_up(
+    mut commands: Commands,
+    warm_up_query: Query<Entity, With<WarmUp>>,
+) {
+    for entity in warm_up_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
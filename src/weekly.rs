@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pause::GameClock,
+    persistence::{self, DataPaths, Format, PersistenceError, Versioned},
+    records::SizeRecord,
+    taquin::{seeded_tiles, ArrangeTiles, Taquin, TaquinShuffled, TileMoved},
+    tile::TileCoordinates,
+    AppState,
+};
+
+const MENU_KEY: KeyCode = KeyCode::W;
+const WEEK_SECONDS: u64 = 7 * 24 * 60 * 60;
+const SIZES: [i8; 3] = [3, 4, 5];
+/// Moves allowed for each of `SIZES`
+const MOVE_CAPS: [usize; 3] = [60, 160, 320];
+
+pub struct WeeklyPlugin;
+
+impl Plugin for WeeklyPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(WeeklyChallenge::for_week(persistence::timestamp() / WEEK_SECONDS))
+            .init_resource::<WeeklyRecords>()
+            .add_systems(Startup, setup_weekly_hud)
+            .add_systems(Update, start_weekly_challenge.run_if(in_state(AppState::MainMenu)))
+            .add_systems(OnEnter(AppState::MainMenu), leave_weekly_challenge)
+            .add_systems(OnEnter(AppState::Running), arrange_weekly_board.run_if(resource_exists::<ActiveChallenge>()))
+            .add_systems(Update, (
+                leave_weekly_challenge_on_shuffle.run_if(on_event::<TaquinShuffled>()),
+                check_weekly_goal.run_if(on_event::<TileMoved>()),
+                update_weekly_hud,
+            ).chain().run_if(in_state(AppState::Running)));
+    }
+}
+
+/// Part of the board to put in place to meet a challenge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoalLayout {
+    Solved,
+    /// The given number of rows from the top
+    TopRows(i8),
+    /// The outer ring, the corner of the empty slot left out
+    Frame,
+}
+
+impl GoalLayout {
+    pub fn cells(self, size: i8) -> Vec<TileCoordinates> {
+        (0..size).flat_map(|j| (0..size).map(move |i| TileCoordinates::new(i, j)))
+            .filter(|cell| match self {
+                GoalLayout::Solved => true,
+                GoalLayout::TopRows(rows) => cell.j < rows,
+                GoalLayout::Frame => {
+                    let border = cell.i == 0 || cell.j == 0 || cell.i == size - 1 || cell.j == size - 1;
+                    border && (cell.i, cell.j) != (size - 1, size - 1)
+                },
+            })
+            .collect()
+    }
+
+    pub fn is_met(self, taquin: &Taquin) -> bool {
+        self.cells(taquin.width).iter().all(|cell| {
+            taquin.tiles.get(cell.j as usize).and_then(|row| row.get(cell.i as usize))
+                .is_some_and(|tile| tile.goal(taquin.width) == *cell)
+        })
+    }
+
+    fn describe(self) -> String {
+        match self {
+            GoalLayout::Solved => "solve it".to_string(),
+            GoalLayout::TopRows(1) => "put the top row in place".to_string(),
+            GoalLayout::TopRows(rows) => format!("put the top {rows} rows in place"),
+            GoalLayout::Frame => "put the outer ring in place".to_string(),
+        }
+    }
+}
+
+/// Challenge of a week, the same for every player as it only depends on the week
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct WeeklyChallenge {
+    /// Weeks since the unix epoch
+    pub week: u64,
+    pub size: i8,
+    pub seed: u64,
+    pub goal: GoalLayout,
+    pub move_cap: usize,
+}
+
+impl WeeklyChallenge {
+    pub fn for_week(week: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(week);
+        let index = rng.gen_range(0..SIZES.len());
+        let size = SIZES[index];
+        let goal = match rng.gen_range(0..3) {
+            0 => GoalLayout::Solved,
+            1 => GoalLayout::TopRows(rng.gen_range(1..size - 1)),
+            _ => GoalLayout::Frame,
+        };
+        Self { week, size, seed: rng.gen(), goal, move_cap: MOVE_CAPS[index] }
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{0}x{0}, {1} within {2} moves", self.size, self.goal.describe(), self.move_cap)
+    }
+}
+
+/// Best result of each weekly challenge, apart from the records of the free games
+#[derive(Resource, Serialize, Deserialize, Debug)]
+pub struct WeeklyRecords {
+    pub version: u32,
+    pub weeks: BTreeMap<u64, SizeRecord>,
+}
+
+impl Versioned for WeeklyRecords {
+    const VERSION: u32 = 1;
+
+    fn migrate(version: u32, _contents: &str, _format: Format) -> Result<Self, PersistenceError> {
+        Err(PersistenceError::unsupported_version(version))
+    }
+}
+
+impl FromWorld for WeeklyRecords {
+    fn from_world(world: &mut World) -> Self {
+        let path = world.resource::<DataPaths>().weekly();
+        let empty = WeeklyRecords { version: Self::VERSION, weeks: BTreeMap::new() };
+        if !persistence::exists(&path) {
+            return empty;
+        }
+        persistence::load_versioned(path, Format::Ron).unwrap_or_else(|error| {
+            error!("cannot load weekly records: {error}");
+            empty
+        })
+    }
+}
+
+impl WeeklyRecords {
+    /// Best of the week for the main menu
+    pub fn describe(&self, week: u64) -> String {
+        match self.weeks.get(&week) {
+            Some(SizeRecord { best_time: Some(time), best_moves: Some(moves) }) => format!("Best: {time:.1}s, {moves} moves"),
+            _ => "Not met yet".to_string(),
+        }
+    }
+}
+
+/// Picks the weekly challenge in the main menu
+#[derive(Component)]
+pub struct WeeklyButton;
+
+/// Weekly challenge being played
+#[derive(Resource, Debug)]
+struct ActiveChallenge {
+    challenge: WeeklyChallenge,
+    /// Set once the board is arranged, a shuffle after that leaves the challenge
+    arranged: bool,
+    moves: usize,
+    /// `GameClock` time when the board was arranged
+    started_at: f32,
+    outcome: Option<String>,
+}
+
+#[derive(Component)]
+struct WeeklyHud;
+
+fn setup_weekly_hud(
+    mut commands: Commands,
+) {
+    commands.spawn((TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: 24.0,
+            color: Color::WHITE,
+            ..default()
+        },
+    ).with_style(Style {
+        position_type: PositionType::Absolute,
+        left: Val::Px(10.),
+        top: Val::Percent(27.),
+        ..default()
+    }), WeeklyHud));
+}
+
+fn start_weekly_challenge(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    button_query: Query<&Interaction, (Changed<Interaction>, With<WeeklyButton>)>,
+    challenge: Res<WeeklyChallenge>,
+    mut taquin: ResMut<Taquin>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let pressed = button_query.iter().any(|interaction| *interaction == Interaction::Pressed);
+    if !pressed && !keyboard_input.just_released(MENU_KEY) {
+        return;
+    }
+    info!(week = challenge.week, size = challenge.size, goal = ?challenge.goal, "weekly challenge started");
+    *taquin = Taquin::new(challenge.size);
+    commands.insert_resource(ActiveChallenge { challenge: *challenge, arranged: false, moves: 0, started_at: 0., outcome: None });
+    next_state.set(AppState::SetupTiles);
+}
+
+fn leave_weekly_challenge(
+    mut commands: Commands,
+) {
+    commands.remove_resource::<ActiveChallenge>();
+}
+
+fn arrange_weekly_board(
+    active: Res<ActiveChallenge>,
+    mut arrange_events: EventWriter<ArrangeTiles>,
+) {
+    if !active.arranged {
+        arrange_events.send(ArrangeTiles(seeded_tiles(active.challenge.size, active.challenge.size, active.challenge.seed)));
+    }
+}
+
+fn leave_weekly_challenge_on_shuffle(
+    mut commands: Commands,
+    active: Option<ResMut<ActiveChallenge>>,
+    clock: Res<GameClock>,
+) {
+    let Some(mut active) = active else {
+        return;
+    };
+    if active.arranged {
+        info!("weekly challenge left");
+        commands.remove_resource::<ActiveChallenge>();
+        return;
+    }
+    active.arranged = true;
+    active.started_at = clock.elapsed_seconds();
+}
+
+/// Met or failed, the outcome stays on screen and the board can still be played
+fn check_weekly_goal(
+    active: Option<ResMut<ActiveChallenge>>,
+    mut tile_moved_events: EventReader<TileMoved>,
+    taquin: Res<Taquin>,
+    clock: Res<GameClock>,
+    mut records: ResMut<WeeklyRecords>,
+    paths: Res<DataPaths>,
+) {
+    let moves = tile_moved_events.read().count();
+    let Some(mut active) = active.filter(|active| active.arranged && active.outcome.is_none()) else {
+        return;
+    };
+    active.moves += moves;
+    let challenge = active.challenge;
+    if challenge.goal.is_met(&taquin) {
+        let elapsed = clock.elapsed_seconds() - active.started_at;
+        let record = records.weeks.entry(challenge.week).or_default().update(elapsed, active.moves);
+        info!(week = challenge.week, moves = active.moves, elapsed, "weekly challenge met");
+        if let Err(error) = persistence::save_versioned(paths.weekly(), &*records, Format::Ron) {
+            error!("cannot save weekly records: {error}");
+        }
+        let new_record = if record.time || record.moves { ", new best!" } else { "" };
+        active.outcome = Some(format!("Weekly challenge met in {} moves{new_record}", active.moves));
+    } else if active.moves >= challenge.move_cap {
+        info!(week = challenge.week, "weekly challenge failed");
+        active.outcome = Some("Weekly challenge failed, out of moves".to_string());
+    }
+}
+
+fn update_weekly_hud(
+    active: Option<Res<ActiveChallenge>>,
+    mut hud_query: Query<&mut Text, With<WeeklyHud>>,
+) {
+    let Ok(mut text) = hud_query.get_single_mut() else {
+        return;
+    };
+    let value = match active.as_deref() {
+        Some(ActiveChallenge { outcome: Some(outcome), .. }) => outcome.clone(),
+        Some(active) => format!(
+            "Weekly challenge: {}\n{} of {} moves",
+            active.challenge.goal.describe(), active.moves, active.challenge.move_cap,
+        ),
+        None => String::new(),
+    };
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::weekly::{GoalLayout, WeeklyChallenge};
+
+    #[test]
+    fn test_weekly_challenge() {
+        assert_eq!(WeeklyChallenge::for_week(2800), WeeklyChallenge::for_week(2800));
+        for week in 2800..2850 {
+            let challenge = WeeklyChallenge::for_week(week);
+            if let GoalLayout::TopRows(rows) = challenge.goal {
+                assert!((1..challenge.size - 1).contains(&rows));
+            }
+        }
+
+        assert_eq!(GoalLayout::Solved.cells(4).len(), 16);
+        assert_eq!(GoalLayout::TopRows(2).cells(4).len(), 8);
+        assert_eq!(GoalLayout::Frame.cells(4).len(), 11);
+    }
+}
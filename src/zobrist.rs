@@ -0,0 +1,51 @@
+const SEED: u64 = 0x5eed_7a91_c0de_2024;
+
+/// splitmix64 finalizer, good enough to spread the small cell and value indices over 64 bits
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Key of `value` lying at `cell` on a board of `size`, the same across runs so hashes can be saved.
+/// Cells and values past a byte, only found on boards too big for the solver, get keys of their own
+pub fn key(size: usize, cell: usize, value: u16) -> u64 {
+    if cell <= u8::MAX as usize && value <= u8::MAX as u16 {
+        return mix(SEED.wrapping_add((size as u64) << 32 | (cell as u64) << 8 | value as u64));
+    }
+    mix(SEED.wrapping_add(1 << 63 | (size as u64) << 40 | (cell as u64) << 16 | value as u64))
+}
+
+/// Zobrist hash of a whole board, `tiles` listed row after row: the xor of one key per cell
+/// and the value lying on it, so a slide only xors four keys in and out
+pub fn hash(size: usize, tiles: impl IntoIterator<Item = u16>) -> u64 {
+    tiles.into_iter().enumerate().fold(0, |hash, (cell, value)| hash ^ key(size, cell, value))
+}
+
+/// Updates `hash` once the values `a` and `b` at `cell_a` and `cell_b` swapped places
+pub fn swap(hash: u64, size: usize, (cell_a, a): (usize, u16), (cell_b, b): (usize, u16)) -> u64 {
+    hash ^ key(size, cell_a, a) ^ key(size, cell_b, b) ^ key(size, cell_a, b) ^ key(size, cell_b, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::zobrist::{hash, key, swap};
+
+    #[test]
+    fn test_incremental_swap() {
+        let before = hash(2, [1, 2, 3, 4]);
+        let after = swap(before, 2, (2, 3), (3, 4));
+        assert_eq!(after, hash(2, [1, 2, 4, 3]));
+        assert_eq!(swap(after, 2, (2, 4), (3, 3)), before);
+        assert_ne!(hash(2, [1, 2, 3, 4]), hash(3, [1, 2, 3, 4]));
+
+        // A mega board, its values past a byte
+        let wide = (1..=400).collect::<Vec<u16>>();
+        let before = hash(20, wide.iter().copied());
+        let after = swap(before, 20, (380, 381), (399, 400));
+        let mut swapped = wide.clone();
+        swapped.swap(380, 399);
+        assert_eq!(after, hash(20, swapped));
+        assert_ne!(key(20, 1, 256), key(20, 2, 0));
+    }
+}